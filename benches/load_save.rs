@@ -0,0 +1,134 @@
+// Times loading and saving a large drawing as ASCII and binary DXF.
+//
+// Run with `cargo bench`; pass a number to change the entity count, e.g. `cargo bench -- 200000`,
+// or the paths of DXF files to time those as they're saved in their own version, e.g.
+// `cargo bench -- drawing.dxf`.
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use dxf::entities::*;
+use dxf::enums::AcadVersion;
+use dxf::tables::Layer;
+use dxf::{Drawing, LwPolylineVertex, Point};
+
+const DEFAULT_ENTITY_COUNT: usize = 50_000;
+const ITERATIONS: u32 = 5;
+
+fn main() {
+    let args = std::env::args().skip(1).filter(|arg| arg != "--bench");
+    let (counts, paths): (Vec<_>, Vec<_>) = args.partition(|arg| arg.parse::<usize>().is_ok());
+    if paths.is_empty() {
+        let entity_count = counts
+            .first()
+            .map_or(DEFAULT_ENTITY_COUNT, |count| count.parse().unwrap());
+        println!("{entity_count} entities");
+        time_drawing(&sample_drawing(entity_count));
+    }
+
+    for path in &paths {
+        let drawing = Drawing::load_file(path).unwrap();
+        println!(
+            "{path}: {:?}, {} entities",
+            drawing.header.version,
+            drawing.entities().count()
+        );
+        time_drawing(&drawing);
+    }
+}
+
+fn time_drawing(drawing: &Drawing) {
+    let mut ascii = vec![];
+    drawing.save(&mut ascii).unwrap();
+    let mut binary = vec![];
+    drawing.save_binary(&mut binary).unwrap();
+
+    report("save ascii", ascii.len(), || {
+        let mut buffer = Vec::with_capacity(ascii.len());
+        drawing.save(&mut buffer).unwrap();
+        black_box(buffer);
+    });
+    report("save binary", binary.len(), || {
+        let mut buffer = Vec::with_capacity(binary.len());
+        drawing.save_binary(&mut buffer).unwrap();
+        black_box(buffer);
+    });
+    report("load ascii", ascii.len(), || {
+        black_box(Drawing::load(&mut ascii.as_slice()).unwrap());
+    });
+    report("load binary", binary.len(), || {
+        black_box(Drawing::load(&mut binary.as_slice()).unwrap());
+    });
+    // a fixed thread count so the chunks are read the same way on any machine
+    report("load ascii 4 threads", ascii.len(), || {
+        let mut reader = ascii.as_slice();
+        black_box(Drawing::load_parallel_with_thread_count(&mut reader, 4).unwrap());
+    });
+    report("load binary 4 threads", binary.len(), || {
+        let mut reader = binary.as_slice();
+        black_box(Drawing::load_parallel_with_thread_count(&mut reader, 4).unwrap());
+    });
+}
+
+fn report<F: FnMut()>(name: &str, size: usize, mut f: F) {
+    f(); // warm up
+    let mut total = Duration::default();
+    for _ in 0..ITERATIONS {
+        let start = Instant::now();
+        f();
+        total += start.elapsed();
+    }
+
+    let average = total / ITERATIONS;
+    let megabytes_per_second = size as f64 / 1_000_000.0 / average.as_secs_f64();
+    println!("{name: <21} {average: >12.2?} {megabytes_per_second: >8.1} MB/s");
+}
+
+// a drawing with a mix of common entities spread over a few layers, in a current version so
+// nothing is converted when it's saved
+fn sample_drawing(entity_count: usize) -> Drawing {
+    let mut drawing = Drawing::new();
+    drawing.header.version = AcadVersion::R2018;
+    let layer_names = ["WALLS", "DOORS", "WINDOWS", "DIMENSIONS", "NOTES"];
+    for name in &layer_names {
+        drawing.add_layer(Layer {
+            name: String::from(*name),
+            ..Default::default()
+        });
+    }
+
+    for i in 0..entity_count {
+        let x = (i % 1000) as f64;
+        let y = (i / 1000) as f64;
+        let specific = match i % 4 {
+            0 => EntityType::Line(Line::new(
+                Point::new(x, y, 0.0),
+                Point::new(x + 0.5, y + 0.5, 0.0),
+            )),
+            1 => EntityType::Circle(Circle::new(Point::new(x, y, 0.0), 0.25)),
+            2 => EntityType::Text(Text {
+                location: Point::new(x, y, 0.0),
+                text_height: 0.1,
+                value: format!("label {i}"),
+                ..Default::default()
+            }),
+            _ => {
+                let mut poly = LwPolyline::default();
+                for (dx, dy) in [(0.0, 0.0), (0.4, 0.0), (0.4, 0.4), (0.0, 0.4)] {
+                    poly.vertices.push(LwPolylineVertex {
+                        x: x + dx,
+                        y: y + dy,
+                        ..Default::default()
+                    });
+                }
+                poly.set_is_closed(true);
+                EntityType::LwPolyline(poly)
+            }
+        };
+        let mut entity = Entity::new(specific);
+        entity.common.layer = String::from(layer_names[i % layer_names.len()]);
+        drawing.add_entity(entity);
+    }
+
+    drawing
+}
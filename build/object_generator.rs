@@ -161,17 +161,19 @@ fn generate_base_object(fun: &mut String, element: &Element) {
     }
 
     ////////////////////////////////////////////////////// apply_individual_pair
-    fun.push_str("    pub(crate) fn apply_individual_pair(&mut self, pair: &CodePair, iter: &mut CodePairPutBack) -> DxfResult<bool> {\n");
+    fun.push_str("    pub(crate) fn apply_individual_pair(&mut self, pair: &mut CodePair, iter: &mut CodePairPutBack) -> DxfResult<bool> {\n");
     fun.push_str("        match pair.code {\n");
     for c in &object.children {
         if c.name == "Field" {
             if name(c) == "extension_data_groups" && code(c) == 102 {
                 fun.push_str("            extension_data::EXTENSION_DATA_GROUP => {\n");
-                fun.push_str("                let group = ExtensionGroup::read_group(pair.assert_string()?, iter, pair.offset)?;\n");
+                fun.push_str("                let group = ExtensionGroup::read_group(pair.take_string()?, iter, pair.offset)?;\n");
                 fun.push_str("                self.extension_data_groups.push(group);\n");
                 fun.push_str("            },\n");
             } else if name(c) == "x_data" && code(c) == 1001 {
                 // handled below: x_data::XDATA_APPLICATIONNAME
+            } else if !generate_reader(c) {
+                // read elsewhere
             } else {
                 let read_fun = if allow_multiples(c) {
                     format!(".push({})", field_reader(c))
@@ -195,7 +197,7 @@ fn generate_base_object(fun: &mut String, element: &Element) {
     }
 
     fun.push_str("            x_data::XDATA_APPLICATIONNAME => {\n");
-    fun.push_str("                let x = XData::read_item(pair.assert_string()?, iter)?;\n");
+    fun.push_str("                let x = XData::read_item(pair.take_string()?, iter)?;\n");
     fun.push_str("                self.x_data.push(x);\n");
     fun.push_str("            },\n");
     fun.push_str("            _ => return Ok(false), // unknown code\n");
@@ -454,7 +456,7 @@ fn generate_try_apply_code_pair(fun: &mut String, element: &Element) {
     let mut unused_readers = vec![];
     fun.push_str("    #[allow(clippy::cognitive_complexity)] // long function, no good way to simplify this\n");
     fun.push_str(
-        "    pub(crate) fn try_apply_code_pair(&mut self, pair: &CodePair) -> DxfResult<bool> {\n",
+        "    pub(crate) fn try_apply_code_pair(&mut self, pair: &mut CodePair) -> DxfResult<bool> {\n",
     );
     fun.push_str("        match *self {\n");
     for c in &element.children {
@@ -825,7 +827,7 @@ fn write_converter_with_code(code: i32, field: &Element) -> String {
     let typ = code_pair_type(&expected_type);
     let mut write_converter = attr(field, "WriteConverter");
     if field.name == "Pointer" {
-        write_converter = String::from("&{}.as_string()");
+        write_converter = String::from("{}.as_string()");
     }
     if write_converter.is_empty() {
         if typ == "string" {
@@ -853,7 +855,7 @@ fn code_pair_for_field_and_code(code: i32, field: &Element, suffix: Option<&str>
     }
     let writer = write_converter.replace("{}", &field_access);
     if name(field) == "handle" && code == 5 {
-        String::from("CodePair::new_string(5, &self.handle.as_string())")
+        String::from("CodePair::new_string(5, self.handle.as_string())")
     } else {
         format!("CodePair::new_{typ}({code}, {writer})")
     }
@@ -8,8 +8,8 @@ pub fn reader_function(typ: &ExpectedType) -> &str {
         Long => "assert_i64",
         Short => "assert_i16",
         Double => "assert_f64",
-        Str => "assert_string",
-        Binary => "assert_binary",
+        Str => "take_string",
+        Binary => "take_binary",
     }
 }
 
@@ -32,7 +32,7 @@ use crate::{
     Vector,
     XData,
 };
-use crate::code_pair_put_back::CodePairPutBack;
+use crate::code_pair_put_back::{CodePairPutBack, COMMENT_CODE};
 use crate::helper_functions::*;
 use crate::extension_data;
 use crate::x_data;
@@ -64,6 +64,8 @@ fn generate_table_items(fun: &mut String, element: &Element) {
         fun.push_str("    pub __owner_handle: Handle,\n");
         fun.push_str("    pub extension_data_groups: Vec<ExtensionGroup>,\n");
         fun.push_str("    pub x_data: Vec<XData>,\n");
+        fun.push_str("    /// The 999 comments preceding the item.\n");
+        fun.push_str("    pub comments: Vec<String>,\n");
         for field in &table_item.children {
             let name = if field.name == "Pointer" {
                 format!("__{}_handle", name(field))
@@ -102,6 +104,7 @@ fn generate_table_items(fun: &mut String, element: &Element) {
         fun.push_str("            __owner_handle: Handle::empty(),\n");
         fun.push_str("            extension_data_groups: vec![],\n");
         fun.push_str("            x_data: vec![],\n");
+        fun.push_str("            comments: vec![],\n");
         for field in &table_item.children {
             let name = if field.name == "Pointer" {
                 format!("__{}_handle", name(field))
@@ -146,7 +149,7 @@ fn generate_table_reader(fun: &mut String, element: &Element) {
     fun.push_str("                return Err(DxfError::ExpectedTableType(pair.offset));\n");
     fun.push_str("            }\n");
     fun.push('\n');
-    fun.push_str("            match &*pair.assert_string()? {\n");
+    fun.push_str("            match pair.as_str()? {\n");
 
     for table in &element.children {
         fun.push_str(&format!(
@@ -185,7 +188,7 @@ fn generate_table_reader(fun: &mut String, element: &Element) {
         fun.push_str("            Some(Ok(pair)) => {\n");
         fun.push_str("                if pair.code == 0 {\n");
         fun.push_str(&format!(
-            "                    if pair.assert_string()? != \"{table_type}\" {{\n",
+            "                    if pair.as_str()? != \"{table_type}\" {{\n",
             table_type = attr(table, "TypeString")
         ));
         fun.push_str("                        iter.put_back(Ok(pair));\n");
@@ -204,22 +207,20 @@ fn generate_table_reader(fun: &mut String, element: &Element) {
         fun.push_str("                                iter.put_back(Ok(pair));\n");
         fun.push_str("                                break;\n");
         fun.push_str("                            },\n");
-        fun.push_str("                            Some(Ok(pair)) => {\n");
+        fun.push_str("                            Some(Ok(mut pair)) => {\n");
         fun.push_str("                                match pair.code {\n");
-        fun.push_str(
-            "                                    2 => item.name = pair.assert_string()?,\n",
-        );
+        fun.push_str("                                    2 => item.name = pair.take_string()?,\n");
         fun.push_str("                                    5 => item.handle = pair.as_handle()?,\n");
         fun.push_str(
             "                                    extension_data::EXTENSION_DATA_GROUP => {\n",
         );
-        fun.push_str("                                        let group = ExtensionGroup::read_group(pair.assert_string()?, iter, pair.offset)?;\n");
+        fun.push_str("                                        let group = ExtensionGroup::read_group(pair.take_string()?, iter, pair.offset)?;\n");
         fun.push_str(
             "                                        item.extension_data_groups.push(group);\n",
         );
         fun.push_str("                                    },\n");
         fun.push_str("                                    x_data::XDATA_APPLICATIONNAME => {\n");
-        fun.push_str("                                        let x = XData::read_item(pair.assert_string()?, iter)?;\n");
+        fun.push_str("                                        let x = XData::read_item(pair.take_string()?, iter)?;\n");
         fun.push_str("                                        item.x_data.push(x);\n");
         fun.push_str("                                    },\n");
         fun.push_str(
@@ -276,6 +277,7 @@ fn generate_table_reader(fun: &mut String, element: &Element) {
         fun.push_str("                        }\n");
         fun.push_str("                    }\n");
         fun.push('\n');
+        fun.push_str("                    item.comments = iter.take_comments();\n");
         fun.push_str("                    if item.handle.is_empty() {\n");
         fun.push_str(&format!(
             "                        drawing.add_{item_type}(item);\n"
@@ -366,12 +368,15 @@ fn generate_table_writer(fun: &mut String, element: &Element) {
             "    for item in drawing.{collection}() {{\n",
             collection = attr(table, "Collection")
         ));
+        fun.push_str("        for comment in &item.comments {\n");
+        fun.push_str("            pairs.push(CodePair::new_string(COMMENT_CODE, comment));\n");
+        fun.push_str("        }\n");
         fun.push_str(&format!(
             "        pairs.push(CodePair::new_str(0, \"{type_string}\"));\n",
             type_string = attr(table, "TypeString")
         ));
         fun.push_str("        if write_handles {\n");
-        fun.push_str(&format!("            pairs.push(CodePair::new_string(5, &DrawingItem::{item_type}(item).handle().as_string()));\n"));
+        fun.push_str(&format!("            pairs.push(CodePair::new_string(5, DrawingItem::{item_type}(item).handle().as_string()));\n"));
         fun.push_str("        }\n");
         fun.push('\n');
         fun.push_str("        if drawing.header.version >= AcadVersion::R14 {\n");
@@ -428,7 +433,7 @@ fn generate_table_writer(fun: &mut String, element: &Element) {
                             indent = indent,
                             field = name(field)
                         ));
-                        fun.push_str(&format!("{indent}            pairs.push(CodePair::new_string({code}, &x.as_string()));\n"));
+                        fun.push_str(&format!("{indent}            pairs.push(CodePair::new_string({code}, x.as_string()));\n"));
                     } else {
                         let expected_type = ExpectedType::new(code).unwrap();
                         let typ = code_pair_type(&expected_type);
@@ -452,7 +457,7 @@ fn generate_table_writer(fun: &mut String, element: &Element) {
                     if codes.len() == 1 {
                         let code = codes[0];
                         if field.name == "Pointer" {
-                            fun.push_str(&format!("{indent}        pairs.push(CodePair::new_string({code}, &item.__{field}_handle.as_string()));\n",
+                            fun.push_str(&format!("{indent}        pairs.push(CodePair::new_string({code}, item.__{field}_handle.as_string()));\n",
                                 indent=indent, code=code, field=name(field)));
                         } else {
                             let typ = ExpectedType::new(code).unwrap();
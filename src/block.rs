@@ -3,7 +3,8 @@ use crate::{
     ExtensionGroup, Handle, Point, XData,
 };
 
-use crate::code_pair_put_back::CodePairPutBack;
+use crate::code_pair_put_back::{CodePairPutBack, COMMENT_CODE};
+use crate::down_conversion::DownConverter;
 use crate::entities::Entity;
 use crate::entity_iter::EntityIter;
 use crate::enums::*;
@@ -39,6 +40,8 @@ pub struct Block {
     pub extension_data_groups: Vec<ExtensionGroup>,
     /// `XData`.
     pub x_data: Vec<XData>,
+    /// The 999 comments preceding the block.
+    pub comments: Vec<String>,
 }
 
 // public implementation
@@ -112,6 +115,7 @@ impl Default for Block {
             entities: vec![],
             extension_data_groups: vec![],
             x_data: vec![],
+            comments: vec![],
         }
     }
 }
@@ -133,6 +137,8 @@ impl Block {
                             value: CodePairValue::Str(ref s),
                             ..
                         } if s == "ENDBLK" => {
+                            current.comments.extend(iter.take_comments());
+
                             // swallow all non-0 code pairs
                             loop {
                                 match iter.next() {
@@ -157,6 +163,7 @@ impl Block {
                         CodePair { code: 0, .. } => {
                             // should be an entity
                             iter.put_back(Ok(pair));
+                            current.comments.extend(iter.take_comments());
                             let mut iter = EntityIter { iter };
                             iter.read_entities_into_vec(&mut current.entities)?;
                         }
@@ -204,10 +211,14 @@ impl Block {
         pairs: &mut Vec<CodePair>,
         version: AcadVersion,
         write_handles: bool,
+        converter: &mut DownConverter,
     ) {
+        for comment in &self.comments {
+            pairs.push(CodePair::new_string(COMMENT_CODE, comment));
+        }
         pairs.push(CodePair::new_str(0, "BLOCK"));
         if write_handles && version >= AcadVersion::R13 {
-            pairs.push(CodePair::new_string(5, &self.handle.as_string()));
+            pairs.push(CodePair::new_string(5, self.handle.as_string()));
         }
 
         if version >= AcadVersion::R14 {
@@ -218,7 +229,7 @@ impl Block {
 
         if version >= AcadVersion::R13 {
             if !self.__owner_handle.is_empty() {
-                pairs.push(CodePair::new_string(330, &self.__owner_handle.as_string()));
+                pairs.push(CodePair::new_string(330, self.__owner_handle.as_string()));
             }
 
             pairs.push(CodePair::new_str(100, "AcDbEntity"));
@@ -248,12 +259,12 @@ impl Block {
         }
 
         for e in &self.entities {
-            e.add_code_pairs(pairs, version, write_handles);
+            converter.add_code_pairs(e, pairs, write_handles);
         }
 
         pairs.push(CodePair::new_str(0, "ENDBLK"));
         if write_handles && !self.handle.is_empty() {
-            pairs.push(CodePair::new_string(5, &self.handle.as_string()));
+            pairs.push(CodePair::new_string(5, self.handle.as_string()));
         }
 
         if version >= AcadVersion::R14 {
@@ -263,7 +274,7 @@ impl Block {
         }
 
         if version >= AcadVersion::R2000 && !self.__owner_handle.is_empty() {
-            pairs.push(CodePair::new_string(330, &self.__owner_handle.as_string()));
+            pairs.push(CodePair::new_string(330, self.__owner_handle.as_string()));
         }
 
         if version >= AcadVersion::R13 {
@@ -301,6 +312,7 @@ impl Block {
 
 #[cfg(test)]
 mod tests {
+    use crate::down_conversion::DownConverter;
     use crate::entities::*;
     use crate::enums::*;
     use crate::helper_functions::tests::*;
@@ -336,7 +348,8 @@ mod tests {
         let mut drawing = Drawing::new();
         let block = drawing.add_block(block);
         let mut pairs = Vec::new();
-        block.add_code_pairs(&mut pairs, version, true);
+        let mut converter = DownConverter::new(version, Handle::empty());
+        block.add_code_pairs(&mut pairs, version, true, &mut converter);
         assert_vec_contains(&pairs, &expected);
     }
 
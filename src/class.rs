@@ -120,7 +120,7 @@ impl Class {
             match iter.next() {
                 Some(Ok(pair)) => {
                     if pair.code == 0 {
-                        match &*pair.assert_string()? {
+                        match pair.as_str()? {
                             "ENDSEC" => {
                                 iter.put_back(Ok(pair));
                                 break;
@@ -192,10 +192,8 @@ impl Class {
                             class.class_name = pair.assert_string()?;
                         }
                     }
-                    3 => {
-                        if drawing.header.version >= AcadVersion::R14 {
-                            class.application_name = pair.assert_string()?;
-                        }
+                    3 if drawing.header.version >= AcadVersion::R14 => {
+                        class.application_name = pair.assert_string()?;
                     }
                     90 => {
                         if drawing.header.version <= AcadVersion::R13 {
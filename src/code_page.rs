@@ -0,0 +1,75 @@
+// maps the `$DWGCODEPAGE` header value to a text encoding
+
+use encoding_rs::Encoding;
+
+/// Returns the encoding for a `$DWGCODEPAGE` value like `ANSI_1251` or `DOS866`, or `None` if the
+/// code page isn't recognized or supported.
+pub(crate) fn encoding_for_code_page(code_page: &str) -> Option<&'static Encoding> {
+    let code_page = code_page.trim().to_ascii_uppercase();
+    if code_page == "UTF8" || code_page == "UTF-8" {
+        return Some(encoding_rs::UTF_8);
+    }
+
+    let number = code_page
+        .strip_prefix("ANSI_")
+        .or_else(|| code_page.strip_prefix("DOS"))
+        .unwrap_or(&code_page);
+    match number {
+        "866" => Some(encoding_rs::IBM866),
+        "874" => Some(encoding_rs::WINDOWS_874),
+        "932" => Some(encoding_rs::SHIFT_JIS),
+        "936" => Some(encoding_rs::GBK),
+        "949" => Some(encoding_rs::EUC_KR),
+        "950" => Some(encoding_rs::BIG5),
+        "1250" => Some(encoding_rs::WINDOWS_1250),
+        "1251" => Some(encoding_rs::WINDOWS_1251),
+        "1252" => Some(encoding_rs::WINDOWS_1252),
+        "1253" => Some(encoding_rs::WINDOWS_1253),
+        "1254" => Some(encoding_rs::WINDOWS_1254),
+        "1255" => Some(encoding_rs::WINDOWS_1255),
+        "1256" => Some(encoding_rs::WINDOWS_1256),
+        "1257" => Some(encoding_rs::WINDOWS_1257),
+        "1258" => Some(encoding_rs::WINDOWS_1258),
+        _ => None,
+    }
+}
+
+/// Returns `true` if the character can be written with the encoding; characters that can't must
+/// be written as `\U+XXXX` escape sequences.
+pub(crate) fn can_encode(c: char, encoding: &'static Encoding) -> bool {
+    if c.is_ascii() || encoding == encoding_rs::UTF_8 {
+        return true;
+    }
+
+    let mut buf = [0; 4];
+    let (_, _, had_errors) = encoding.encode(c.encode_utf8(&mut buf));
+    !had_errors
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::code_page::*;
+
+    #[test]
+    fn code_page_names() {
+        assert_eq!(
+            Some(encoding_rs::SHIFT_JIS),
+            encoding_for_code_page("ANSI_932")
+        );
+        assert_eq!(
+            Some(encoding_rs::WINDOWS_1251),
+            encoding_for_code_page("ansi_1251")
+        );
+        assert_eq!(Some(encoding_rs::IBM866), encoding_for_code_page("DOS866"));
+        assert_eq!(None, encoding_for_code_page("ANSI_1361"));
+        assert_eq!(None, encoding_for_code_page(""));
+    }
+
+    #[test]
+    fn encodable_characters() {
+        assert!(can_encode('a', encoding_rs::SHIFT_JIS));
+        assert!(can_encode('\u{3042}', encoding_rs::SHIFT_JIS));
+        assert!(!can_encode('\u{0416}', encoding_rs::WINDOWS_1252));
+        assert!(can_encode('\u{0416}', encoding_rs::WINDOWS_1251));
+    }
+}
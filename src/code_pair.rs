@@ -1,12 +1,8 @@
 use std::fmt;
 use std::fmt::{Debug, Display, Formatter};
 
-use byteorder::{BigEndian, ByteOrder};
-
 use crate::{CodePairValue, DxfError, DxfResult, Handle};
 
-use crate::helper_functions::parse_hex_string;
-
 /// The basic primitive of a DXF file; a code indicating the type of the data contained, and the
 /// data itself.
 #[derive(Clone)]
@@ -28,8 +24,9 @@ impl CodePair {
     pub fn new_str(code: i32, val: &str) -> Self {
         CodePair::new(code, CodePairValue::Str(val.to_string()), 0)
     }
-    pub fn new_string(code: i32, val: &str) -> Self {
-        CodePair::new(code, CodePairValue::Str(val.to_string()), 0)
+    /// Creates a string pair, taking ownership of the value when it's already a `String`.
+    pub fn new_string(code: i32, val: impl Into<String>) -> Self {
+        CodePair::new(code, CodePairValue::Str(val.into()), 0)
     }
     pub fn new_binary(code: i32, val: Vec<u8>) -> Self {
         CodePair::new(code, CodePairValue::Binary(val), 0)
@@ -95,13 +92,42 @@ impl CodePair {
 }
 
 impl CodePair {
+    /// Borrows the string value, for the many places that only compare it.
+    pub(crate) fn as_str(&self) -> DxfResult<&str> {
+        match self.value {
+            CodePairValue::Str(ref s) => Ok(s),
+            _ => Err(DxfError::WrongValueType(self.offset)),
+        }
+    }
+    /// Moves the string value out, leaving an empty string behind.  Items are read from pairs that
+    /// are dropped once they're applied, so there's no need to copy the value.
+    pub(crate) fn take_string(&mut self) -> DxfResult<String> {
+        match self.value {
+            CodePairValue::Str(ref mut s) => Ok(std::mem::take(s)),
+            _ => Err(DxfError::WrongValueType(self.offset)),
+        }
+    }
+    /// Moves the binary value out like `take_string`.
+    pub(crate) fn take_binary(&mut self) -> DxfResult<Vec<u8>> {
+        match self.value {
+            CodePairValue::Binary(ref mut b) => Ok(std::mem::take(b)),
+            _ => Err(DxfError::WrongValueType(self.offset)),
+        }
+    }
     pub(crate) fn as_handle(&self) -> DxfResult<Handle> {
-        let mut bytes = vec![];
-        parse_hex_string(self.assert_string()?.trim(), &mut bytes, self.offset)?;
-        while bytes.len() < 8 {
-            bytes.insert(0, 0);
+        let s = match self.value {
+            CodePairValue::Str(ref s) => s.trim(),
+            _ => return Err(DxfError::WrongValueType(self.offset)),
+        };
+        if s.is_empty() {
+            return Ok(Handle::empty());
+        }
+        if !s.bytes().all(|b| b.is_ascii_hexdigit()) {
+            return Err(DxfError::ParseError(self.offset));
         }
-        Ok(Handle(BigEndian::read_u64(&bytes)))
+        u64::from_str_radix(s, 16)
+            .map(Handle)
+            .map_err(|_| DxfError::ParseError(self.offset))
     }
 }
 
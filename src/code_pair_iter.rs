@@ -1,113 +1,314 @@
 use crate::{CodePair, CodePairValue, DxfError, DxfResult, ExpectedType};
 
+use crate::code_page::encoding_for_code_page;
+use crate::code_pair_put_back::COMMENT_CODE;
 use crate::code_pair_value::un_escape_ascii_to_unicode;
 use crate::helper_functions::*;
 use encoding_rs::Encoding;
-use std::io::{Cursor, Read};
+use std::borrow::Cow;
+use std::io::Read;
 
 pub(crate) trait CodePairIter: Iterator<Item = DxfResult<CodePair>> {
     fn read_as_utf8(&mut self);
+    /// Switches to the encoding of the `$DWGCODEPAGE` value, unless a specific encoding was requested.
+    fn read_with_code_page(&mut self, code_page: &str);
+    /// Finds the undecoded pairs of the items left in the current section without reading them.
+    /// Returns `None` if the end of the section can't be found or the pairs aren't read from bytes.
+    fn scan_section(&mut self) -> Option<RawSection<'_>> {
+        None
+    }
+    /// Continues reading at `end` of the section found by `scan_section`.
+    fn skip_to(&mut self, _end: RawPosition) {}
+}
+
+/// Where a pair starts in the undecoded bytes of a section, along with the iterator's offset there.
+#[derive(Clone, Copy)]
+pub(crate) struct RawPosition {
+    pub position: usize,
+    offset: usize,
+}
+
+/// The undecoded pairs of the items left in a section, which can be decoded on other threads.
+pub(crate) struct RawSection<'a> {
+    data: &'a [u8],
+    string_encoding: &'static Encoding,
+    /// `None` for text, otherwise whether the binary codes are two bytes.
+    codes_are_two_bytes: Option<bool>,
+    pub start: RawPosition,
+    /// Where each item after the first starts, including the comments that precede it.
+    pub item_starts: Vec<RawPosition>,
+    /// The end of the last item; the comments before the end of the section and its `0/ENDSEC`
+    /// follow.
+    pub end: RawPosition,
+    pub pair_count: usize,
+}
+
+impl<'a> RawSection<'a> {
+    /// Returns an iterator that decodes the pairs from `start` up to `end`.
+    pub fn decode(&self, start: RawPosition, end: RawPosition) -> Box<dyn CodePairIter + 'a> {
+        let data: &'a [u8] = self.data;
+        let buffer = ReadBuffer::new(vec![], Box::new(&data[start.position..end.position]));
+        match self.codes_are_two_bytes {
+            None => Box::new(TextCodePairIter {
+                buffer,
+                string_encoding: self.string_encoding,
+                detect_encoding: false,
+                first_line: String::new(),
+                read_first_line: true,
+                offset: start.offset,
+            }),
+            Some(codes_are_two_bytes) => Box::new(BinaryCodePairIter {
+                buffer,
+                code_size_detection_complete: true,
+                codes_are_two_bytes,
+                string_encoding: self.string_encoding,
+                detect_encoding: false,
+                offset: start.offset,
+            }),
+        }
+    }
+}
+
+/// Tracks where items start while scanning the undecoded pairs of a section.
+struct SectionScan {
+    start: RawPosition,
+    item_starts: Vec<RawPosition>,
+    comments_start: Option<RawPosition>,
+    pair_count: usize,
+}
+
+impl SectionScan {
+    fn new(offset: usize) -> Self {
+        SectionScan {
+            start: RawPosition {
+                position: 0,
+                offset,
+            },
+            item_starts: vec![],
+            comments_start: None,
+            pair_count: 0,
+        }
+    }
+    /// Records the pair at `start`, returning where the items end if it ends the section.
+    fn add(&mut self, start: RawPosition, code: i32, value: &[u8]) -> Option<RawPosition> {
+        // comments belong to the item that follows them
+        let item_start = self.comments_start.unwrap_or(start);
+        if code == 0 {
+            if value == b"ENDSEC" || value == b"ENDBLK" {
+                return Some(item_start);
+            }
+            if item_start.position > 0 {
+                self.item_starts.push(item_start);
+            }
+        }
+
+        self.comments_start = if code == COMMENT_CODE {
+            Some(item_start)
+        } else {
+            None
+        };
+        self.pair_count += 1;
+        None
+    }
+    fn finish<'a>(
+        self,
+        data: &'a [u8],
+        string_encoding: &'static Encoding,
+        codes_are_two_bytes: Option<bool>,
+        end: RawPosition,
+    ) -> RawSection<'a> {
+        RawSection {
+            data: &data[..end.position],
+            string_encoding,
+            codes_are_two_bytes,
+            start: self.start,
+            item_starts: self.item_starts,
+            end,
+            pair_count: self.pair_count,
+        }
+    }
 }
 
 /// Directly returns code pairs; primarily used in tests.
+#[cfg_attr(not(test), allow(dead_code))]
 pub(crate) struct DirectCodePairIter {
-    pairs: Vec<CodePair>,
-    offset: usize,
+    pairs: std::vec::IntoIter<CodePair>,
 }
 
 impl CodePairIter for DirectCodePairIter {
     fn read_as_utf8(&mut self) {
         // noop
     }
+    fn read_with_code_page(&mut self, _code_page: &str) {
+        // noop
+    }
 }
 
 impl Iterator for DirectCodePairIter {
     type Item = DxfResult<CodePair>;
     fn next(&mut self) -> Option<DxfResult<CodePair>> {
-        if self.offset < self.pairs.len() {
-            let pair = self.pairs[self.offset].clone();
-            self.offset += 1;
-            return Some(Ok(pair));
-        }
-
-        None
+        self.pairs.next().map(Ok)
     }
 }
 
 #[cfg(test)]
 impl DirectCodePairIter {
     pub(crate) fn new(pairs: Vec<CodePair>) -> Self {
-        DirectCodePairIter { pairs, offset: 0 }
+        DirectCodePairIter {
+            pairs: pairs.into_iter(),
+        }
     }
 }
 
 /// Returns code pairs as read from text.  Handles the most common DXF files and when parsed from strings.
-pub(crate) struct TextCodePairIter<T: Read> {
-    reader: T,
+pub(crate) struct TextCodePairIter<'a> {
+    buffer: ReadBuffer<'a>,
     string_encoding: &'static Encoding,
+    detect_encoding: bool,
     first_line: String,
     read_first_line: bool,
     offset: usize,
 }
 
-impl<T: Read> CodePairIter for TextCodePairIter<T> {
+impl CodePairIter for TextCodePairIter<'_> {
     fn read_as_utf8(&mut self) {
         self.string_encoding = encoding_rs::UTF_8;
     }
+    fn read_with_code_page(&mut self, code_page: &str) {
+        if self.detect_encoding {
+            if let Some(encoding) = encoding_for_code_page(code_page) {
+                self.string_encoding = encoding;
+            }
+        }
+    }
+    fn scan_section(&mut self) -> Option<RawSection<'_>> {
+        if !self.read_first_line {
+            return None;
+        }
+
+        let mut scan = SectionScan::new(self.offset);
+        let mut position = 0;
+        let mut lines = 0;
+        let end = loop {
+            let start = RawPosition {
+                position,
+                offset: self.offset + lines,
+            };
+            let code_end = self.buffer.find_after(position, b'\n').ok()?? - self.buffer.position;
+            let value_end =
+                self.buffer.find_after(code_end + 1, b'\n').ok()?? - self.buffer.position;
+            let data = &self.buffer.data[self.buffer.position..];
+            let code = match parse_ascii_integer(&data[position..code_end]) {
+                Some(code) => i32::try_from(code).ok()?,
+                None => std::str::from_utf8(&data[position..code_end])
+                    .ok()?
+                    .trim()
+                    .parse::<i32>()
+                    .ok()?,
+            };
+            let value = &data[code_end + 1..value_end];
+            let value = value.strip_suffix(b"\r").unwrap_or(value);
+            position = value_end + 1;
+            lines += 2;
+            if let Some(end) = scan.add(start, code, value) {
+                break end;
+            }
+        };
+
+        let data = &self.buffer.data[self.buffer.position..];
+        Some(scan.finish(data, self.string_encoding, None, end))
+    }
+    fn skip_to(&mut self, end: RawPosition) {
+        self.buffer.position += end.position;
+        self.offset = end.offset;
+    }
 }
 
-impl<T: Read> Iterator for TextCodePairIter<T> {
+impl Iterator for TextCodePairIter<'_> {
     type Item = DxfResult<CodePair>;
     fn next(&mut self) -> Option<DxfResult<CodePair>> {
         self.read_code_pair()
     }
 }
 
-impl<T: Read> TextCodePairIter<T> {
+impl<'a> TextCodePairIter<'a> {
     pub fn new(
-        reader: T,
+        buffer: ReadBuffer<'a>,
         string_encoding: &'static Encoding,
+        detect_encoding: bool,
         first_line: String,
         offset: usize,
     ) -> Self {
         TextCodePairIter {
-            reader,
+            buffer,
             string_encoding,
+            detect_encoding,
             first_line,
             read_first_line: false,
             offset,
         }
     }
+    /// Returns the bounds of the next line, excluding the `\n`.
+    fn next_line(&mut self) -> DxfResult<(usize, usize)> {
+        let end = self.buffer.find(b'\n')?;
+        let start = self.buffer.position;
+        match end {
+            Some(end) => {
+                self.buffer.position = end + 1;
+                Ok((start, end))
+            }
+            None => {
+                self.buffer.position = self.buffer.data.len();
+                Ok((start, self.buffer.data.len()))
+            }
+        }
+    }
     fn read_code_pair(&mut self) -> Option<DxfResult<CodePair>> {
         // Read code.  If no line is available, fail gracefully.
-        let code_line = if self.read_first_line {
+        let code = if self.read_first_line {
             self.offset += 1;
-            match read_line(&mut self.reader, true, encoding_rs::WINDOWS_1252) {
-                Ok(v) => v,
-                Err(e) => return Some(Err(e)),
+            let (mut start, end) = try_into_option!(self.next_line());
+            let data = &self.buffer.data;
+            match parse_ascii_integer(&data[start..end]).and_then(|c| i32::try_from(c).ok()) {
+                Some(code) => code,
+                None => {
+                    // skip a UTF-8 BOM
+                    if start < end && data[start] == 0xEF {
+                        start += 1;
+                        if start < end && data[start] == 0xBB {
+                            start += 1;
+                        }
+                        if start < end && data[start] == 0xBF {
+                            start += 1;
+                        }
+                    }
+
+                    let code_line = decode_line(&data[start..end], encoding_rs::WINDOWS_1252);
+                    let code_line = try_into_option!(code_line);
+                    let code_line = code_line.trim();
+                    if code_line.is_empty() {
+                        // might be an empty file only containing a newline
+                        return None;
+                    }
+
+                    try_into_option!(parse_i32(code_line, self.offset))
+                }
             }
         } else {
             self.read_first_line = true;
+            let code_line = self.first_line.trim();
+            if code_line.is_empty() {
+                return None;
+            }
 
-            // .clone() is fine because it'll only ever be called once and the only valid
-            // values that might be cloned are: "0" and "999"; all others are errors.
-            self.first_line.clone()
+            try_into_option!(parse_i32(code_line, self.offset))
         };
-        let code_line = code_line.trim();
-        if code_line.is_empty() {
-            // might be an empty file only containing a newline
-            return None;
-        }
-
         let code_offset = self.offset;
-        let code = try_into_option!(parse_i32(String::from(code_line), code_offset));
 
         // Read value.  If no line is available die horribly.
         self.offset += 1;
-        let value_line = match read_line(&mut self.reader, false, self.string_encoding) {
-            Ok(v) => v,
-            Err(e) => return Some(Err(e)),
-        };
+        let (start, end) = try_into_option!(self.next_line());
+        let value_line = &self.buffer.data[start..end];
 
         // construct the value pair
         let expected_type = match ExpectedType::new(code) {
@@ -115,31 +316,37 @@ impl<T: Read> TextCodePairIter<T> {
             None => return Some(Err(DxfError::UnexpectedEnumValue(self.offset))),
         };
         let value = match expected_type {
-            ExpectedType::Boolean => {
-                CodePairValue::Boolean(try_into_option!(parse_i16(value_line, self.offset)))
-            }
-            ExpectedType::Integer => {
-                CodePairValue::Integer(try_into_option!(parse_i32(value_line, self.offset)))
-            }
-            ExpectedType::Long => {
-                CodePairValue::Long(try_into_option!(parse_i64(value_line, self.offset)))
-            }
-            ExpectedType::Short => {
-                CodePairValue::Short(try_into_option!(parse_i16(value_line, self.offset)))
-            }
-            ExpectedType::Double => {
-                CodePairValue::Double(try_into_option!(parse_f64(value_line, self.offset)))
-            }
+            ExpectedType::Boolean => CodePairValue::Boolean(try_into_option!(self.parse_value(
+                value_line,
+                parse_ascii_short,
+                parse_i16
+            ))),
+            ExpectedType::Integer => CodePairValue::Integer(try_into_option!(self.parse_value(
+                value_line,
+                |bytes| parse_ascii_integer(bytes).and_then(|i| i32::try_from(i).ok()),
+                parse_i32
+            ))),
+            ExpectedType::Long => CodePairValue::Long(try_into_option!(self.parse_value(
+                value_line,
+                parse_ascii_integer,
+                parse_i64
+            ))),
+            ExpectedType::Short => CodePairValue::Short(try_into_option!(self.parse_value(
+                value_line,
+                parse_ascii_short,
+                parse_i16
+            ))),
+            ExpectedType::Double => CodePairValue::Double(try_into_option!(self.parse_value(
+                value_line,
+                |bytes| std::str::from_utf8(bytes.trim_ascii()).ok()?.parse().ok(),
+                parse_f64
+            ))),
             ExpectedType::Str => {
-                let value_line = if self.string_encoding == encoding_rs::WINDOWS_1252 {
-                    un_escape_ascii_to_unicode(&value_line)
-                } else {
-                    value_line
-                };
-                let value_line = CodePairValue::un_escape_string(&value_line);
-                CodePairValue::Str(value_line.into_owned())
+                let value_line = try_into_option!(decode_line(value_line, self.string_encoding));
+                CodePairValue::Str(un_escape_value(value_line, self.string_encoding))
             }
             ExpectedType::Binary => {
+                let value_line = try_into_option!(decode_line(value_line, self.string_encoding));
                 let mut data = vec![];
                 match parse_hex_string(&value_line, &mut data, self.offset) {
                     Ok(()) => CodePairValue::Binary(data),
@@ -150,56 +357,154 @@ impl<T: Read> TextCodePairIter<T> {
 
         Some(Ok(CodePair::new(code, value, code_offset)))
     }
+    /// Parses a numeric value with `parse_ascii` straight from the bytes of the line, falling back to
+    /// decoding the line and parsing it with `parse` so that unusual values and errors are handled
+    /// the same way.
+    fn parse_value<T>(
+        &self,
+        bytes: &[u8],
+        parse_ascii: impl Fn(&[u8]) -> Option<T>,
+        parse: fn(&str, usize) -> DxfResult<T>,
+    ) -> DxfResult<T> {
+        match parse_ascii(bytes) {
+            Some(value) => Ok(value),
+            None => parse(&decode_line(bytes, self.string_encoding)?, self.offset),
+        }
+    }
 }
 
 /// Returns code pairs as read from a binary file.  Usually created _after_ the first line of a file has been read.
-pub(crate) struct BinaryCodePairIter<T: Read> {
-    reader: T,
+pub(crate) struct BinaryCodePairIter<'a> {
+    buffer: ReadBuffer<'a>,
     code_size_detection_complete: bool,
     codes_are_two_bytes: bool,
+    string_encoding: &'static Encoding,
+    detect_encoding: bool,
     offset: usize,
 }
 
-impl<T: Read> CodePairIter for BinaryCodePairIter<T> {
+impl CodePairIter for BinaryCodePairIter<'_> {
     fn read_as_utf8(&mut self) {
-        // noop
+        self.string_encoding = encoding_rs::UTF_8;
+    }
+    fn read_with_code_page(&mut self, code_page: &str) {
+        if self.detect_encoding {
+            if let Some(encoding) = encoding_for_code_page(code_page) {
+                self.string_encoding = encoding;
+            }
+        }
+    }
+    fn scan_section(&mut self) -> Option<RawSection<'_>> {
+        if !self.code_size_detection_complete {
+            return None;
+        }
+
+        let mut scan = SectionScan::new(self.offset);
+        let mut position = 0;
+        let end = loop {
+            let start = RawPosition {
+                position,
+                offset: self.offset + position,
+            };
+            let mut code = i32::from(self.buffer.byte_after(position)?);
+            position += 1;
+            if self.codes_are_two_bytes {
+                code += i32::from(self.buffer.byte_after(position)?) << 8;
+                position += 1;
+            } else if code == 255 {
+                let low_byte = self.buffer.byte_after(position)?;
+                let high_byte = self.buffer.byte_after(position + 1)?;
+                code = i32::from(i16::from_le_bytes([low_byte, high_byte]));
+                position += 2;
+            }
+
+            let value_start = position;
+            position += match ExpectedType::new(code)? {
+                ExpectedType::Boolean if self.codes_are_two_bytes => 1,
+                ExpectedType::Boolean | ExpectedType::Short => 2,
+                ExpectedType::Integer => 4,
+                ExpectedType::Long | ExpectedType::Double => 8,
+                ExpectedType::Str => {
+                    let end = self.buffer.find_after(position, 0).ok()??;
+                    end - self.buffer.position - position + 1
+                }
+                ExpectedType::Binary => 1 + usize::from(self.buffer.byte_after(position)?),
+            };
+            self.buffer.byte_after(position - 1)?;
+            let data = &self.buffer.data[self.buffer.position..];
+            let value = match code {
+                // without the NULL terminator
+                0 => &data[value_start..position - 1],
+                _ => &[],
+            };
+            if let Some(end) = scan.add(start, code, value) {
+                break end;
+            }
+        };
+
+        let data = &self.buffer.data[self.buffer.position..];
+        let codes_are_two_bytes = Some(self.codes_are_two_bytes);
+        Some(scan.finish(data, self.string_encoding, codes_are_two_bytes, end))
+    }
+    fn skip_to(&mut self, end: RawPosition) {
+        self.buffer.position += end.position;
+        self.offset = end.offset;
     }
 }
 
-impl<T: Read> Iterator for BinaryCodePairIter<T> {
+impl Iterator for BinaryCodePairIter<'_> {
     type Item = DxfResult<CodePair>;
     fn next(&mut self) -> Option<DxfResult<CodePair>> {
         self.read_code_pair()
     }
 }
 
-impl<T: Read> BinaryCodePairIter<T> {
-    pub fn new(reader: T, offset: usize) -> Self {
+impl<'a> BinaryCodePairIter<'a> {
+    pub fn new(
+        buffer: ReadBuffer<'a>,
+        string_encoding: &'static Encoding,
+        detect_encoding: bool,
+        offset: usize,
+    ) -> Self {
         BinaryCodePairIter {
-            reader,
+            buffer,
             code_size_detection_complete: false,
             codes_are_two_bytes: false,
+            string_encoding,
+            detect_encoding,
             offset,
         }
     }
+    fn read_bytes<const N: usize>(&mut self) -> DxfResult<[u8; N]> {
+        let start = self.buffer.ensure(N)?;
+        self.buffer.position += N;
+        let mut result = [0; N];
+        result.copy_from_slice(&self.buffer.data[start..start + N]);
+        Ok(result)
+    }
+    fn read_u8(&mut self) -> DxfResult<u8> {
+        Ok(self.read_bytes::<1>()?[0])
+    }
+    fn read_i16(&mut self) -> DxfResult<i16> {
+        Ok(i16::from_le_bytes(self.read_bytes()?))
+    }
     fn read_code_pair(&mut self) -> Option<DxfResult<CodePair>> {
         // Read code.  If no data is available, fail gracefully.
-        let mut code = match read_u8(&mut self.reader) {
-            Some(Ok(c)) => i32::from(c),
-            Some(Err(e)) => return Some(Err(DxfError::IoError(e))),
-            None => return None,
-        };
+        if !try_into_option!(self.buffer.has_more()) {
+            return None;
+        }
+        let mut code = i32::from(try_from_dxf_result!(self.read_u8()));
         self.offset += 1;
 
         // If reading a larger code and no data is available, die horribly.
         if self.codes_are_two_bytes {
             // post R13 codes are 2 bytes, read the second byte of the code
-            let high_byte = i32::from(try_from_dxf_result!(read_u8_strict(&mut self.reader)));
+            let high_byte = i32::from(try_from_dxf_result!(self.read_u8()));
             code += high_byte << 8;
             self.offset += 1;
         } else if code == 255 {
             // pre R13 codes are either 1 or 3 bytes
-            code = i32::from(try_from_dxf_result!(read_i16(&mut self.reader)));
+            code = i32::from(try_from_dxf_result!(self.read_i16()));
             self.offset += 2;
         }
 
@@ -212,34 +517,31 @@ impl<T: Read> BinaryCodePairIter<T> {
             ExpectedType::Boolean => {
                 // after R13 bools are encoded as a single byte
                 let (b_value, read_bytes) = if self.codes_are_two_bytes {
-                    (
-                        i16::from(try_from_dxf_result!(read_u8_strict(&mut self.reader))),
-                        1,
-                    )
+                    (i16::from(try_from_dxf_result!(self.read_u8())), 1)
                 } else {
-                    (try_from_dxf_result!(read_i16(&mut self.reader)), 2)
+                    (try_from_dxf_result!(self.read_i16()), 2)
                 };
                 (CodePairValue::Boolean(b_value), read_bytes)
             }
             ExpectedType::Integer => (
-                CodePairValue::Integer(try_from_dxf_result!(read_i32(&mut self.reader))),
+                CodePairValue::Integer(i32::from_le_bytes(try_from_dxf_result!(self.read_bytes()))),
                 4,
             ),
             ExpectedType::Long => (
-                CodePairValue::Long(try_from_dxf_result!(read_i64(&mut self.reader))),
+                CodePairValue::Long(i64::from_le_bytes(try_from_dxf_result!(self.read_bytes()))),
                 8,
             ),
             ExpectedType::Short => (
-                CodePairValue::Short(try_from_dxf_result!(read_i16(&mut self.reader))),
+                CodePairValue::Short(try_from_dxf_result!(self.read_i16())),
                 2,
             ),
             ExpectedType::Double => (
-                CodePairValue::Double(try_from_dxf_result!(read_f64(&mut self.reader))),
+                CodePairValue::Double(f64::from_le_bytes(try_from_dxf_result!(self.read_bytes()))),
                 8,
             ),
             ExpectedType::Str => {
-                let mut value = try_from_dxf_result!(self.read_string_binary());
-                if !self.code_size_detection_complete && code == 0 && value.is_empty() {
+                let (mut start, mut end) = try_from_dxf_result!(self.read_string_bounds());
+                if !self.code_size_detection_complete && code == 0 && start == end {
                     // If this is the first pair being read and the code is 0, the only valid string value is "SECTION".
                     // If the read value is instead empty, that means the string reader found a single 0x00 byte which
                     // indicates that this is a post R13 binary file where codes are always read as 2 bytes.  The 0x00
@@ -247,19 +549,25 @@ impl<T: Read> BinaryCodePairIter<T> {
                     // the reader up.
                     self.codes_are_two_bytes = true;
                     self.offset += 1; // account for the NULL byte that was interpreted as an empty string
-                    value = try_from_dxf_result!(self.read_string_binary()); // now read the actual value
+                    (start, end) = try_from_dxf_result!(self.read_string_bounds());
+                    // now read the actual value
                 }
+                let bytes = &self.buffer.data[start..end];
+                let value = if bytes.is_ascii() {
+                    Cow::Borrowed(std::str::from_utf8(bytes).unwrap_or_default())
+                } else {
+                    self.string_encoding.decode_without_bom_handling(bytes).0
+                };
                 (
-                    CodePairValue::Str(CodePairValue::un_escape_string(&value).into_owned()),
-                    value.len() + 1, // +1 to account for the NULL terminator
+                    CodePairValue::Str(un_escape_value(value, self.string_encoding)),
+                    end - start + 1, // +1 to account for the NULL terminator
                 )
             }
             ExpectedType::Binary => {
-                let length = try_from_dxf_result!(read_u8_strict(&mut self.reader)) as usize;
-                let mut data = vec![];
-                for _ in 0..length {
-                    data.push(try_from_dxf_result!(read_u8_strict(&mut self.reader)));
-                }
+                let length = usize::from(try_from_dxf_result!(self.read_u8()));
+                let start = try_from_dxf_result!(self.buffer.ensure(length));
+                self.buffer.position += length;
+                let data = self.buffer.data[start..start + length].to_vec();
 
                 (CodePairValue::Binary(data), length + 1) // +1 to account for initial length byte
             }
@@ -269,52 +577,236 @@ impl<T: Read> BinaryCodePairIter<T> {
 
         Some(Ok(CodePair::new(code, value, self.offset)))
     }
-    fn read_string_binary(&mut self) -> DxfResult<String> {
-        let mut s = String::new();
+    /// Reads a NULL-terminated string, returning the bounds of its bytes.
+    fn read_string_bounds(&mut self) -> DxfResult<(usize, usize)> {
+        match self.buffer.find(0)? {
+            Some(end) => {
+                let start = self.buffer.position;
+                self.buffer.position = end + 1;
+                Ok((start, end))
+            }
+            None => Err(DxfError::UnexpectedEndOfInput),
+        }
+    }
+}
+
+const READ_BLOCK_SIZE: usize = 64 * 1024;
+
+/// The bytes of a file that haven't been parsed yet.  More are read from the underlying reader in
+/// blocks as they're needed, so the whole file never has to be in memory.
+pub(crate) struct ReadBuffer<'a> {
+    reader: Box<dyn Read + 'a>,
+    data: Vec<u8>,
+    position: usize,
+    is_at_end: bool,
+}
+
+impl<'a> ReadBuffer<'a> {
+    /// Creates a buffer that starts with `data` and continues with the contents of `reader`.
+    pub fn new(data: Vec<u8>, reader: Box<dyn Read + 'a>) -> Self {
+        ReadBuffer {
+            reader,
+            data,
+            position: 0,
+            is_at_end: false,
+        }
+    }
+    /// Reads the next block, dropping the bytes that have already been parsed.  Returns `false` at
+    /// the end of the input.
+    fn fill(&mut self) -> DxfResult<bool> {
+        if self.is_at_end {
+            return Ok(false);
+        }
+
+        self.data.drain(..self.position);
+        self.position = 0;
+        let read = (&mut self.reader)
+            .take(READ_BLOCK_SIZE as u64)
+            .read_to_end(&mut self.data)?;
+        self.is_at_end = read == 0;
+        Ok(!self.is_at_end)
+    }
+    fn has_more(&mut self) -> DxfResult<bool> {
+        while self.position >= self.data.len() {
+            if !self.fill()? {
+                return Ok(false);
+            }
+        }
+
+        Ok(true)
+    }
+    /// Makes sure `count` bytes are available and returns the index of the first one.
+    fn ensure(&mut self, count: usize) -> DxfResult<usize> {
+        while self.data.len() - self.position < count {
+            if !self.fill()? {
+                return Err(DxfError::UnexpectedEndOfInput);
+            }
+        }
+
+        Ok(self.position)
+    }
+    /// Returns the index of the next `byte`, or `None` if the input ends first.
+    fn find(&mut self, byte: u8) -> DxfResult<Option<usize>> {
+        self.find_after(0, byte)
+    }
+    /// Returns the index of the next `byte` that's at least `skip` bytes past the position, or
+    /// `None` if the input ends first.
+    fn find_after(&mut self, skip: usize, byte: u8) -> DxfResult<Option<usize>> {
+        let mut searched = skip;
         loop {
-            match read_u8(&mut self.reader) {
-                Some(Ok(0)) => break,
-                Some(Ok(c)) => s.push(c as char),
-                Some(Err(e)) => return Err(DxfError::IoError(e)),
-                None => return Err(DxfError::UnexpectedEndOfInput),
+            let start = (self.position + searched).min(self.data.len());
+            if let Some(index) = find_byte(&self.data[start..], byte) {
+                return Ok(Some(start + index));
+            }
+
+            searched = searched.max(self.data.len() - self.position);
+            if !self.fill()? {
+                return Ok(None);
             }
         }
+    }
+    /// Returns the byte `skip` bytes past the position without consuming it, or `None` if the
+    /// input ends first.
+    fn byte_after(&mut self, skip: usize) -> Option<u8> {
+        let start = self.ensure(skip + 1).ok()?;
+        Some(self.data[start + skip])
+    }
+}
+
+/// Returns the index of the first `byte` in `data`, checking eight bytes at a time.
+fn find_byte(data: &[u8], byte: u8) -> Option<usize> {
+    const LOW_BITS: u64 = 0x0101_0101_0101_0101;
+    const HIGH_BITS: u64 = 0x8080_8080_8080_8080;
+    let pattern = LOW_BITS * u64::from(byte);
+    let mut chunks = data.chunks_exact(8);
+    let mut offset = 0;
+    for chunk in &mut chunks {
+        // the lowest byte that's zero after the XOR is flagged exactly; only bytes after it can be
+        // flagged by mistake
+        let word = u64::from_le_bytes(chunk.try_into().unwrap()) ^ pattern;
+        let found = word.wrapping_sub(LOW_BITS) & !word & HIGH_BITS;
+        if found != 0 {
+            return Some(offset + found.trailing_zeros() as usize / 8);
+        }
 
-        Ok(s)
+        offset += 8;
+    }
+
+    let remainder = chunks.remainder();
+    remainder
+        .iter()
+        .position(|&b| b == byte)
+        .map(|i| offset + i)
+}
+
+/// Parses a plain decimal integer like ` 10` or `-5\r` from the bytes of a line.  Returns `None`
+/// for anything else, including values that don't fit in an `i64`.
+fn parse_ascii_integer(bytes: &[u8]) -> Option<i64> {
+    let bytes = bytes.trim_ascii();
+    let (is_negative, digits) = match bytes.split_first() {
+        Some((b'-', digits)) => (true, digits),
+        Some((b'+', digits)) => (false, digits),
+        _ => (false, bytes),
+    };
+    if digits.is_empty() || digits.len() > 18 {
+        return None;
+    }
+
+    let mut value = 0;
+    for &digit in digits {
+        if !digit.is_ascii_digit() {
+            return None;
+        }
+
+        value = value * 10 + i64::from(digit - b'0');
+    }
+
+    Some(if is_negative { -value } else { value })
+}
+
+fn parse_ascii_short(bytes: &[u8]) -> Option<i16> {
+    parse_ascii_integer(bytes).and_then(|s| i16::try_from(s).ok())
+}
+
+/// Decodes a line of text, borrowing the bytes when they're plain ASCII.
+fn decode_line<'a>(bytes: &'a [u8], encoding: &'static Encoding) -> DxfResult<Cow<'a, str>> {
+    let text = if bytes.is_ascii() && encoding.is_ascii_compatible() {
+        Cow::Borrowed(std::str::from_utf8(bytes).unwrap_or_default())
+    } else {
+        match encoding.decode(bytes) {
+            (result, _, false) => result,
+            (_, _, true) => return Err(DxfError::MalformedString),
+        }
+    };
+
+    Ok(match text {
+        Cow::Borrowed(s) => Cow::Borrowed(s.strip_suffix('\r').unwrap_or(s)),
+        Cow::Owned(mut s) => {
+            if s.ends_with('\r') {
+                s.pop();
+            }
+            Cow::Owned(s)
+        }
+    })
+}
+
+/// Expands `\U+XXXX` and `^` escape sequences; the common case of a string without either is only
+/// copied once.
+fn un_escape_value(value: Cow<'_, str>, string_encoding: &'static Encoding) -> String {
+    let value = if string_encoding != encoding_rs::UTF_8 && value.contains('\\') {
+        Cow::Owned(un_escape_ascii_to_unicode(&value))
+    } else {
+        value
+    };
+    match CodePairValue::un_escape_string(&value) {
+        Cow::Borrowed(_) => value.into_owned(),
+        Cow::Owned(s) => s,
     }
 }
 
 //---------------------------
 
-pub(crate) fn new_code_pair_iter_from_reader<T>(
-    mut reader: T,
+/// Creates an iterator over everything after `first_line`, reading the rest of the file as it's
+/// needed.
+pub(crate) fn new_code_pair_iter_from_reader<'a, T>(
+    reader: T,
     string_encoding: &'static Encoding,
+    detect_encoding: bool,
     first_line: String,
-) -> DxfResult<Box<dyn CodePairIter>>
+) -> DxfResult<Box<dyn CodePairIter + 'a>>
 where
-    T: Read,
+    T: Read + 'a,
 {
-    let mut bytes = vec![];
-    reader.read_to_end(&mut bytes)?;
-    let mut cursor = Cursor::new(bytes);
-    let iter: Box<dyn CodePairIter> = match &*first_line {
+    let buffer = ReadBuffer::new(vec![], Box::new(reader));
+    new_code_pair_iter_from_buffer(buffer, string_encoding, detect_encoding, first_line)
+}
+
+/// Creates an iterator over everything after `first_line`, reading the rest of the file as it's
+/// needed.
+pub(crate) fn new_code_pair_iter_from_buffer<'a>(
+    mut buffer: ReadBuffer<'a>,
+    string_encoding: &'static Encoding,
+    detect_encoding: bool,
+    first_line: String,
+) -> DxfResult<Box<dyn CodePairIter + 'a>> {
+    let iter: Box<dyn CodePairIter + 'a> = match &*first_line {
         "AutoCAD Binary DXF" => {
             // swallow 0x1A,0x00
-            assert_or_err!(
-                try_option_io_result_into_err!(read_u8(&mut cursor)),
-                0x1A,
-                18
-            );
-            assert_or_err!(
-                try_option_io_result_into_err!(read_u8(&mut cursor)),
-                0x00,
-                19
-            );
-            Box::new(BinaryCodePairIter::new(cursor, 20))
+            let start = buffer.ensure(2)?;
+            assert_or_err!(buffer.data[start], 0x1A, 18);
+            assert_or_err!(buffer.data[start + 1], 0x00, 19);
+            buffer.position += 2;
+            Box::new(BinaryCodePairIter::new(
+                buffer,
+                string_encoding,
+                detect_encoding,
+                20,
+            ))
         }
         _ => Box::new(TextCodePairIter::new(
-            cursor,
+            buffer,
             string_encoding,
+            detect_encoding,
             first_line,
             1,
         )),
@@ -324,16 +816,18 @@ where
 
 #[cfg(test)]
 mod tests {
-    use crate::code_pair_iter::{BinaryCodePairIter, TextCodePairIter};
+    use crate::code_pair_iter::*;
     use crate::CodePair;
 
     use super::DirectCodePairIter;
 
     fn read_in_binary(codes_are_two_bytes: bool, data: Vec<u8>) -> CodePair {
         let mut reader = BinaryCodePairIter {
-            reader: data.as_slice(),
+            buffer: ReadBuffer::new(data, Box::new(std::io::empty())),
             code_size_detection_complete: true,
             codes_are_two_bytes,
+            string_encoding: encoding_rs::WINDOWS_1252,
+            detect_encoding: false,
             offset: 0,
         };
         reader.read_code_pair().unwrap().unwrap()
@@ -359,9 +853,10 @@ mod tests {
     }
 
     fn read_in_text(data: &str) -> CodePair {
-        let mut reader = TextCodePairIter::<&[u8]> {
-            reader: data.as_bytes(),
+        let mut reader = TextCodePairIter {
+            buffer: ReadBuffer::new(data.as_bytes().to_vec(), Box::new(std::io::empty())),
             string_encoding: encoding_rs::WINDOWS_1252,
+            detect_encoding: false,
             first_line: String::from("not-important"),
             read_first_line: true,
             offset: 0,
@@ -1,38 +1,94 @@
-use crate::code_pair_iter::CodePairIter;
+use crate::code_pair_iter::{CodePairIter, RawPosition, RawSection};
 use crate::dxf_result::DxfResult;
-use crate::CodePair;
+use crate::{CodePair, CodePairValue};
 
-pub(crate) struct CodePairPutBack {
-    top: Vec<DxfResult<CodePair>>,
-    iter: Box<dyn CodePairIter>,
+pub(crate) const COMMENT_CODE: i32 = 999;
+
+pub(crate) struct CodePairPutBack<'a> {
+    /// Put back pairs along with the comments that immediately preceded them.
+    top: Vec<(DxfResult<CodePair>, Vec<String>)>,
+    iter: Box<dyn CodePairIter + 'a>,
+    /// Comments that haven't been claimed by an item yet.
+    comments: Vec<String>,
+    /// The comments that immediately preceded the last returned pair.
+    last_comments: Vec<String>,
 }
 
-impl CodePairPutBack {
-    pub fn from_code_pair_iter(iter: Box<dyn CodePairIter>) -> Self {
-        CodePairPutBack { top: vec![], iter }
+impl<'a> CodePairPutBack<'a> {
+    pub fn from_code_pair_iter(iter: Box<dyn CodePairIter + 'a>) -> Self {
+        CodePairPutBack {
+            top: vec![],
+            iter,
+            comments: vec![],
+            last_comments: vec![],
+        }
     }
     pub fn put_back(&mut self, item: DxfResult<CodePair>) {
-        self.top.push(item);
+        // the comments before the put back pair belong to whatever reads it next
+        let last_comments = std::mem::take(&mut self.last_comments);
+        let remaining = self.comments.len().saturating_sub(last_comments.len());
+        self.comments.truncate(remaining);
+        self.top.push((item, last_comments));
     }
     pub fn read_as_utf8(&mut self) {
         self.iter.read_as_utf8()
     }
+    pub fn read_with_code_page(&mut self, code_page: &str) {
+        self.iter.read_with_code_page(code_page)
+    }
+    /// Finds the undecoded pairs of the items left in the current section without reading them.
+    /// Returns `None` if any pairs or comments are waiting to be returned first.
+    pub fn scan_section(&mut self) -> Option<RawSection<'_>> {
+        if !self.top.is_empty() || !self.comments.is_empty() {
+            return None;
+        }
+
+        self.iter.scan_section()
+    }
+    /// Continues reading at `end` of the section found by `scan_section`.
+    pub fn skip_to(&mut self, end: RawPosition) {
+        self.iter.skip_to(end)
+    }
+    /// Returns the unclaimed 999 comments read so far.
+    pub fn take_comments(&mut self) -> Vec<String> {
+        std::mem::take(&mut self.comments)
+    }
+    fn set_last_comments(&mut self, comments: Vec<String>) {
+        if comments.is_empty() && self.last_comments.is_empty() {
+            // the common case; nothing to track
+            return;
+        }
+
+        self.comments.extend(comments.iter().cloned());
+        self.last_comments = comments;
+    }
 }
 
-impl Iterator for CodePairPutBack {
+impl Iterator for CodePairPutBack<'_> {
     type Item = DxfResult<CodePair>;
 
     fn next(&mut self) -> Option<DxfResult<CodePair>> {
-        if self.top.is_empty() {
-            loop {
-                let pair = self.iter.next();
-                match pair {
-                    Some(Ok(CodePair { code: 999, .. })) => (), // a 999 comment code, try again
-                    _ => return pair,
+        match self.top.pop() {
+            Some((pair, comments)) => {
+                self.set_last_comments(comments);
+                Some(pair)
+            }
+            None => {
+                let mut comments = vec![];
+                loop {
+                    match self.iter.next() {
+                        Some(Ok(CodePair {
+                            code: COMMENT_CODE,
+                            value: CodePairValue::Str(comment),
+                            ..
+                        })) => comments.push(comment),
+                        pair => {
+                            self.set_last_comments(comments);
+                            return pair;
+                        }
+                    }
                 }
             }
-        } else {
-            Some(self.top.pop().unwrap())
         }
     }
 }
@@ -0,0 +1,264 @@
+use std::io::Read;
+
+use encoding_rs::Encoding;
+
+use crate::code_pair_iter::{new_code_pair_iter_from_buffer, CodePairIter, ReadBuffer};
+use crate::enums::AcadVersion;
+use crate::helper_functions::read_line;
+use crate::{CodePair, CodePairValue, DxfResult};
+
+/// Reads the code pairs of an ASCII or binary DXF file one at a time without creating a `Drawing`.
+///
+/// The file is read in blocks as pairs are requested, so arbitrarily large files can be processed.
+/// Text is decoded the same way `Drawing::load` does it: with the code page from `$DWGCODEPAGE`,
+/// or as UTF-8 when `$ACADVER` is `R2007` or later.
+///
+/// ``` rust
+/// # fn ex() -> dxf::DxfResult<()> {
+/// use dxf::CodePairReader;
+///
+/// let file = std::fs::File::open("path/to/file.dxf")?;
+/// for pair in CodePairReader::new(std::io::BufReader::new(file))? {
+///     let pair = pair?;
+///     if pair.code == 8 {
+///         println!("found layer {}", pair.assert_string()?);
+///     }
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub struct CodePairReader<'a> {
+    iter: Box<dyn CodePairIter + 'a>,
+    version: AcadVersion,
+    is_binary: bool,
+    header_variable: Option<String>,
+}
+
+impl<'a> CodePairReader<'a> {
+    /// Creates a reader over anything that implements the `Read` trait.  Text is decoded using the
+    /// code page specified by `$DWGCODEPAGE`, or as UTF-8 for `R2007` and later.
+    pub fn new<T>(reader: T) -> DxfResult<Self>
+    where
+        T: Read + 'a,
+    {
+        CodePairReader::new_internal(reader, encoding_rs::WINDOWS_1252, true)
+    }
+    /// Creates a reader over anything that implements the `Read` trait using the specified text
+    /// encoding.
+    pub fn with_encoding<T>(reader: T, encoding: &'static Encoding) -> DxfResult<Self>
+    where
+        T: Read + 'a,
+    {
+        CodePairReader::new_internal(reader, encoding, false)
+    }
+    fn new_internal<T>(
+        mut reader: T,
+        encoding: &'static Encoding,
+        detect_encoding: bool,
+    ) -> DxfResult<Self>
+    where
+        T: Read + 'a,
+    {
+        let first_line = read_line(&mut reader, true, encoding)?;
+        let is_binary = first_line == "AutoCAD Binary DXF";
+        let buffer = ReadBuffer::new(vec![], Box::new(reader));
+        let iter = new_code_pair_iter_from_buffer(buffer, encoding, detect_encoding, first_line)?;
+        Ok(CodePairReader {
+            iter,
+            version: AcadVersion::R12,
+            is_binary,
+            header_variable: None,
+        })
+    }
+    /// Returns the version given by the `$ACADVER` header variable, or `R12` until it has been
+    /// read.
+    pub fn version(&self) -> AcadVersion {
+        self.version
+    }
+    /// Returns `true` if the file is a binary DXF file.
+    pub fn is_binary(&self) -> bool {
+        self.is_binary
+    }
+    /// Switches the text encoding when the header variables that control it go by.
+    fn observe(&mut self, pair: &CodePair) {
+        let value = match pair.value {
+            CodePairValue::Str(ref s) => s,
+            _ => return,
+        };
+        match (pair.code, self.header_variable.as_deref()) {
+            (9, _) => self.header_variable = Some(value.clone()),
+            (0, _) => self.header_variable = None,
+            (1, Some("$ACADVER")) => {
+                self.version = AcadVersion::from_safe(value.clone());
+                if self.version >= AcadVersion::R2007 {
+                    self.iter.read_as_utf8();
+                }
+            }
+            (3, Some("$DWGCODEPAGE")) if self.version < AcadVersion::R2007 => {
+                self.iter.read_with_code_page(value);
+            }
+            _ => (),
+        }
+    }
+}
+
+impl Iterator for CodePairReader<'_> {
+    type Item = DxfResult<CodePair>;
+
+    fn next(&mut self) -> Option<DxfResult<CodePair>> {
+        let pair = self.iter.next();
+        if let Some(Ok(ref pair)) = pair {
+            self.observe(pair);
+        }
+
+        pair
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::entities::*;
+    use crate::enums::*;
+    use crate::*;
+
+    use std::io::Read;
+
+    /// Hands out one byte per call to make sure pairs can be split across reads.
+    struct ByteAtATime<'a>(&'a [u8]);
+
+    impl Read for ByteAtATime<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            match (self.0.split_first(), buf.first_mut()) {
+                (Some((b, rest)), Some(target)) => {
+                    *target = *b;
+                    self.0 = rest;
+                    Ok(1)
+                }
+                _ => Ok(0),
+            }
+        }
+    }
+
+    fn read_pairs(bytes: &[u8]) -> Vec<CodePair> {
+        CodePairReader::new(ByteAtATime(bytes))
+            .unwrap()
+            .map(|p| p.unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn read_ascii_pairs() {
+        let pairs =
+            read_pairs(b"0\r\nSECTION\r\n  2\r\nENTITIES\r\n 10\r\n1.5\r\n 70\r\n5\r\n0\r\nEOF");
+        assert_eq!(
+            vec![
+                CodePair::new_str(0, "SECTION"),
+                CodePair::new_str(2, "ENTITIES"),
+                CodePair::new_f64(10, 1.5),
+                CodePair::new_i16(70, 5),
+                CodePair::new_str(0, "EOF"),
+            ],
+            pairs
+                .into_iter()
+                .map(|p| CodePair::new(p.code, p.value, 0))
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn read_pairs_of_saved_drawing() {
+        let mut drawing = Drawing::new();
+        drawing.header.version = AcadVersion::R2000;
+        drawing.add_entity(Entity::new(EntityType::Line(Line::new(
+            Point::new(1.0, 2.0, 3.0),
+            Point::new(4.0, 5.0, 6.0),
+        ))));
+
+        let mut ascii = vec![];
+        drawing.save(&mut ascii).unwrap();
+        let mut binary = vec![];
+        drawing.save_binary(&mut binary).unwrap();
+
+        let ascii_pairs = read_pairs(&ascii);
+        let binary_pairs = read_pairs(&binary);
+        assert_eq!(ascii_pairs.len(), binary_pairs.len());
+        for (a, b) in ascii_pairs.iter().zip(binary_pairs.iter()) {
+            assert_eq!(a.code, b.code);
+            assert_eq!(a.value, b.value);
+        }
+        assert_eq!(Some(&CodePair::new_str(0, "EOF")), ascii_pairs.last());
+    }
+
+    #[test]
+    fn pass_pairs_through_to_writer() {
+        let mut drawing = Drawing::new();
+        drawing.header.version = AcadVersion::R2004;
+        drawing.add_entity(Entity::new(EntityType::Circle(Circle::new(
+            Point::new(1.0, 2.0, 0.0),
+            3.0,
+        ))));
+        for as_binary in [false, true] {
+            let mut input = vec![];
+            if as_binary {
+                drawing.save_binary(&mut input).unwrap();
+            } else {
+                drawing.save(&mut input).unwrap();
+            }
+
+            // the version is known once the header has been read
+            let mut reader = CodePairReader::new(input.as_slice()).unwrap();
+            assert_eq!(as_binary, reader.is_binary());
+            let mut header = vec![];
+            for pair in reader.by_ref() {
+                let pair = pair.unwrap();
+                let is_end = pair.code == 0 && pair.assert_string().unwrap() == "ENDSEC";
+                header.push(pair);
+                if is_end {
+                    break;
+                }
+            }
+            assert_eq!(AcadVersion::R2004, reader.version());
+
+            let mut output = vec![];
+            let mut writer = if reader.is_binary() {
+                CodePairWriter::new_binary(&mut output, reader.version())
+            } else {
+                CodePairWriter::new_ascii(&mut output, reader.version())
+            };
+            for pair in header.into_iter().chain(reader.map(|p| p.unwrap())) {
+                writer.write_code_pair(&pair).unwrap();
+            }
+            assert_eq!(input, output);
+        }
+    }
+
+    #[test]
+    fn read_text_with_code_page_from_header() {
+        let mut bytes = b"  0\r\nSECTION\r\n  2\r\nHEADER\r\n  9\r\n$ACADVER\r\n  1\r\nAC1015\r\n  9\r\n$DWGCODEPAGE\r\n  3\r\nANSI_1251\r\n  0\r\nENDSEC\r\n  0\r\nSECTION\r\n  2\r\nENTITIES\r\n  1\r\n".to_vec();
+        bytes.push(0xC0); // Cyrillic 'А' in code page 1251
+        bytes.extend_from_slice(b"\r\n  0\r\nENDSEC\r\n  0\r\nEOF\r\n");
+        let pairs = read_pairs(&bytes);
+        assert_eq!("\u{0410}", pairs[9].assert_string().unwrap());
+    }
+
+    #[test]
+    fn read_text_as_utf8_for_r2007() {
+        let text = "  0\r\nSECTION\r\n  2\r\nHEADER\r\n  9\r\n$ACADVER\r\n  1\r\nAC1021\r\n  0\r\nENDSEC\r\n  0\r\nSECTION\r\n  2\r\nENTITIES\r\n  1\r\n\u{00E9}\r\n  0\r\nENDSEC\r\n  0\r\nEOF\r\n";
+        let pairs = read_pairs(text.as_bytes());
+        assert_eq!("\u{00E9}", pairs[7].assert_string().unwrap());
+    }
+
+    #[test]
+    fn read_truncated_binary_pair() {
+        let mut bytes = b"AutoCAD Binary DXF\r\n".to_vec();
+        bytes.extend_from_slice(&[0x1A, 0x00, 0x00, 0x00]);
+        bytes.extend_from_slice(b"SECTION\0");
+        bytes.extend_from_slice(&[0x0A, 0x00, 0x00, 0x00]); // code 10, only 2 bytes of the double
+        let mut reader = CodePairReader::new(ByteAtATime(&bytes)).unwrap();
+        assert_eq!(
+            CodePair::new_str(0, "SECTION").value,
+            reader.next().unwrap().unwrap().value
+        );
+        assert!(reader.next().unwrap().is_err());
+    }
+}
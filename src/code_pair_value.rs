@@ -1,6 +1,7 @@
 use std::borrow::Cow;
 use std::fmt;
 use std::fmt::{Debug, Display, Formatter};
+use std::io::Write;
 
 /// Contains the data portion of a `CodePair`.
 #[derive(PartialEq)]
@@ -18,11 +19,7 @@ pub enum CodePairValue {
 // internal visibility only
 impl CodePairValue {
     pub(crate) fn un_escape_string(val: &'_ str) -> Cow<'_, str> {
-        fn needs_un_escaping(c: char) -> bool {
-            c == '^'
-        }
-
-        if let Some(first) = val.find(needs_un_escaping) {
+        if let Some(first) = val.bytes().position(|b| b == b'^') {
             let mut result = String::from(&val[0..first]);
             result.reserve(val.len() - first);
             let rest = val[first..].chars();
@@ -234,6 +231,34 @@ pub(crate) fn un_escape_ascii_to_unicode(val: &str) -> String {
 
 /// Formats an `f64` value with up to 12 digits of precision, ensuring at least one trailing digit after the decimal.
 fn format_f64(val: f64) -> String {
+    let mut buffer = vec![];
+    write_f64(&mut buffer, val);
+    String::from_utf8(buffer).expect("formatted numbers are ASCII")
+}
+
+/// Appends an `f64` value formatted like `format_f64` to `buffer`.
+pub(crate) fn write_f64(buffer: &mut Vec<u8>, val: f64) {
+    // The shortest round-trip representation is much cheaper to produce and is the same as the
+    // rounded one when it's an exact integer, or when it has at most 12 decimals and the value is
+    // small enough that its binary representation is within the rounding of that.
+    if val.is_finite() {
+        let start = buffer.len();
+        write!(buffer, "{val}").expect("writing to a Vec can't fail");
+        let shortest = &buffer[start..];
+        match shortest.iter().position(|&b| b == b'.') {
+            None if val.abs() < 9_007_199_254_740_992.0 => {
+                buffer.extend_from_slice(b".0");
+                return;
+            }
+            Some(decimal) if shortest.len() - decimal <= 13 && val.abs() < 1024.0 => return,
+            _ => buffer.truncate(start),
+        }
+    }
+
+    buffer.extend_from_slice(format_f64_rounded(val).as_bytes());
+}
+
+fn format_f64_rounded(val: f64) -> String {
     // format with 12 digits of precision
     let mut val = format!("{val:.12}");
 
@@ -331,6 +356,40 @@ mod tests {
         );
     }
 
+    #[test]
+    fn format_f64_matches_rounded_format() {
+        let mut values = vec![
+            -0.0,
+            0.1 + 0.2,
+            1.0 / 3.0,
+            1e-7,
+            1e-13,
+            123.456,
+            1_023.999_999_999_999,
+            1024.1,
+            1_234_567.1,
+            9_007_199_254_740_993.0,
+            1e23,
+            f64::MAX,
+            f64::NAN,
+            f64::INFINITY,
+        ];
+        // a spread of values with varying magnitudes and decimals
+        let mut seed = 1u64;
+        for _ in 0..10_000 {
+            seed = seed.wrapping_mul(6_364_136_223_846_793_005).wrapping_add(1);
+            let mantissa = (seed >> 11) as f64 / (1u64 << 53) as f64;
+            let exponent = ((seed >> 3) % 20) as i32 - 10;
+            let decimals = 10f64.powi(((seed >> 8) % 8) as i32);
+            values.push((mantissa * 10f64.powi(exponent) * decimals).round() / decimals);
+            values.push(-mantissa * 10f64.powi(exponent));
+        }
+
+        for val in values {
+            assert_eq!(format_f64_rounded(val), format_f64(val), "{val:e}");
+        }
+    }
+
     #[test]
     fn test_display_str() {
         assert_eq!("", format!("{}", CodePairValue::Str("".to_string())));
@@ -1,112 +1,250 @@
+use std::borrow::Cow;
 use std::io::Write;
 
 use byteorder::{LittleEndian, WriteBytesExt};
+use encoding_rs::Encoding;
 
-use crate::code_pair_value::{escape_control_characters, escape_unicode_to_ascii};
+use crate::code_page::{can_encode, encoding_for_code_page};
+use crate::code_pair_put_back::COMMENT_CODE;
+use crate::code_pair_value::{escape_control_characters, escape_unicode_to_ascii, write_f64};
 use crate::enums::AcadVersion;
 use crate::{CodePair, CodePairValue, DxfResult};
 
-pub(crate) struct CodePairWriter<'a, T>
+/// Writes code pairs to an ASCII or binary DXF file without creating a `Drawing`.
+///
+/// Values are formatted for the specified version: binary files use two-byte codes and one-byte
+/// booleans from `R13` on, and text is written as UTF-8 for `R2007` and later.  Earlier versions
+/// escape characters outside of ASCII, or outside of the code page of a written `$DWGCODEPAGE`
+/// for `R13` through `R2004`.
+///
+/// ``` rust
+/// # fn ex() -> dxf::DxfResult<()> {
+/// use dxf::{CodePair, CodePairReader, CodePairWriter};
+/// use dxf::enums::AcadVersion;
+///
+/// // move everything on layer `OLD` to layer `NEW`
+/// let input = std::fs::File::open("path/to/input.dxf")?;
+/// let mut output = std::io::BufWriter::new(std::fs::File::create("path/to/output.dxf")?);
+/// let mut writer = CodePairWriter::new_ascii(&mut output, AcadVersion::R2000);
+/// for pair in CodePairReader::new(std::io::BufReader::new(input))? {
+///     let pair = pair?;
+///     if pair.code == 8 && pair.assert_string()? == "OLD" {
+///         writer.write_code_pair(&CodePair::new_str(8, "NEW"))?;
+///     } else {
+///         writer.write_code_pair(&pair)?;
+///     }
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub struct CodePairWriter<'a, T>
 where
     T: Write + ?Sized + 'a,
 {
     writer: &'a mut T,
     as_text: bool,
-    text_as_ascii: bool,
+    /// `None` if all non-ASCII characters must be escaped.
+    string_encoding: Option<&'static Encoding>,
     version: AcadVersion,
+    /// Each pair is assembled here so it reaches the writer with a single call.
+    buffer: Vec<u8>,
+    wrote_prelude: bool,
+    /// Whether `$DWGCODEPAGE` determines the string encoding.
+    detect_encoding: bool,
+    header_variable: Option<String>,
 }
 
 impl<'a, T: Write + ?Sized> CodePairWriter<'a, T> {
-    pub fn new(
+    pub(crate) fn new(
         writer: &'a mut T,
         as_text: bool,
-        text_as_ascii: bool,
+        string_encoding: Option<&'static Encoding>,
         version: AcadVersion,
     ) -> Self {
         CodePairWriter {
             writer,
             as_text,
-            text_as_ascii,
+            string_encoding,
             version,
+            buffer: Vec::with_capacity(256),
+            wrote_prelude: false,
+            detect_encoding: false,
+            header_variable: None,
         }
     }
-    pub fn write_prelude(&mut self) -> DxfResult<()> {
-        if !self.as_text {
+    /// Creates a writer that writes pairs as ASCII text formatted for the specified version.
+    pub fn new_ascii(writer: &'a mut T, version: AcadVersion) -> Self {
+        CodePairWriter::new_detecting_encoding(writer, true, version)
+    }
+    /// Creates a writer that writes pairs as binary formatted for the specified version.  The binary
+    /// sentinel is written before the first pair.
+    pub fn new_binary(writer: &'a mut T, version: AcadVersion) -> Self {
+        CodePairWriter::new_detecting_encoding(writer, false, version)
+    }
+    fn new_detecting_encoding(writer: &'a mut T, as_text: bool, version: AcadVersion) -> Self {
+        let string_encoding = if version >= AcadVersion::R2007 {
+            Some(encoding_rs::UTF_8)
+        } else {
+            None
+        };
+        let mut writer = CodePairWriter::new(writer, as_text, string_encoding, version);
+        writer.detect_encoding = version >= AcadVersion::R13 && version < AcadVersion::R2007;
+        writer
+    }
+    pub(crate) fn write_prelude(&mut self) -> DxfResult<()> {
+        if !self.as_text && !self.wrote_prelude {
             self.writer
                 .write_fmt(format_args!("AutoCAD Binary DXF\r\n"))?;
             self.writer.write_u8(0x1A)?;
             self.writer.write_u8(0x00)?;
         }
 
+        self.wrote_prelude = true;
         Ok(())
     }
+    /// Writes a single code pair.
     pub fn write_code_pair(&mut self, pair: &CodePair) -> DxfResult<()> {
+        self.write_prelude()?;
+        if self.detect_encoding {
+            self.observe(pair);
+        }
+
         if self.as_text {
             self.write_ascii_code_pair(pair)
+        } else if pair.code == COMMENT_CODE {
+            // comments aren't allowed in binary files
+            Ok(())
         } else {
             self.write_binary_code_pair(pair)
         }
     }
+    /// Switches to the code page of a `$DWGCODEPAGE` value as it's written.
+    fn observe(&mut self, pair: &CodePair) {
+        let value = match pair.value {
+            CodePairValue::Str(ref s) => s,
+            _ => return,
+        };
+        match (pair.code, self.header_variable.as_deref()) {
+            (9, _) => self.header_variable = Some(value.clone()),
+            (0, _) => self.header_variable = None,
+            (3, Some("$DWGCODEPAGE")) => {
+                self.string_encoding = encoding_for_code_page(value)
+                    .filter(|&encoding| encoding != encoding_rs::UTF_8);
+            }
+            _ => (),
+        }
+    }
     fn write_ascii_code_pair(&mut self, pair: &CodePair) -> DxfResult<()> {
-        self.writer
-            .write_fmt(format_args!("{: >3}\r\n", pair.code))?;
+        let buffer = &mut self.buffer;
+        buffer.clear();
+        // numbers are formatted by hand since going through `fmt` dominates writing text
+        write_padded_integer(buffer, i64::from(pair.code), 3);
+        buffer.extend_from_slice(b"\r\n");
         match pair.value {
             CodePairValue::Str(ref s) => {
-                let s = escape_control_characters(s);
-                let s = if self.text_as_ascii {
-                    escape_unicode_to_ascii(&s)
-                } else {
-                    s
-                };
-                self.writer.write_fmt(format_args!("{s}\r\n"))?;
+                buffer.extend_from_slice(&encode_string(s, self.string_encoding))
             }
-            _ => self.writer.write_fmt(format_args!("{}\r\n", &pair.value))?,
+            CodePairValue::Boolean(b) => write_padded_integer(buffer, i64::from(b), 0),
+            CodePairValue::Integer(i) => write_padded_integer(buffer, i64::from(i), 9),
+            CodePairValue::Long(l) => write_padded_integer(buffer, l, 0),
+            CodePairValue::Short(s) => write_padded_integer(buffer, i64::from(s), 6),
+            CodePairValue::Double(d) => write_f64(buffer, d),
+            CodePairValue::Binary(_) => write!(buffer, "{}", &pair.value)?,
         };
+        buffer.extend_from_slice(b"\r\n");
+        self.writer.write_all(buffer)?;
         Ok(())
     }
     fn write_binary_code_pair(&mut self, pair: &CodePair) -> DxfResult<()> {
+        let buffer = &mut self.buffer;
+        buffer.clear();
+
         // write code
         if self.version >= AcadVersion::R13 {
-            self.writer.write_i16::<LittleEndian>(pair.code as i16)?;
+            buffer.write_i16::<LittleEndian>(pair.code as i16)?;
         } else if pair.code >= 255 {
-            self.writer.write_u8(255)?;
-            self.writer.write_i16::<LittleEndian>(pair.code as i16)?;
+            buffer.write_u8(255)?;
+            buffer.write_i16::<LittleEndian>(pair.code as i16)?;
         } else {
-            self.writer.write_u8(pair.code as u8)?;
+            buffer.write_u8(pair.code as u8)?;
         }
 
         // write value
         match pair.value {
             CodePairValue::Boolean(s) => {
                 if self.version >= AcadVersion::R13 {
-                    self.writer.write_u8(s as u8)?
+                    buffer.write_u8(s as u8)?
                 } else {
-                    self.writer.write_i16::<LittleEndian>(s)?
+                    buffer.write_i16::<LittleEndian>(s)?
                 }
             }
-            CodePairValue::Integer(i) => self.writer.write_i32::<LittleEndian>(i)?,
-            CodePairValue::Long(l) => self.writer.write_i64::<LittleEndian>(l)?,
-            CodePairValue::Short(s) => self.writer.write_i16::<LittleEndian>(s)?,
-            CodePairValue::Double(d) => self.writer.write_f64::<LittleEndian>(d)?,
+            CodePairValue::Integer(i) => buffer.write_i32::<LittleEndian>(i)?,
+            CodePairValue::Long(l) => buffer.write_i64::<LittleEndian>(l)?,
+            CodePairValue::Short(s) => buffer.write_i16::<LittleEndian>(s)?,
+            CodePairValue::Double(d) => buffer.write_f64::<LittleEndian>(d)?,
             CodePairValue::Str(ref s) => {
-                for &b in escape_control_characters(s).as_bytes() {
-                    self.writer.write_u8(b)?;
-                }
-
-                self.writer.write_u8(0)?;
+                buffer.extend_from_slice(&encode_string(s, self.string_encoding));
+                buffer.write_u8(0)?;
             }
             CodePairValue::Binary(ref buf) => {
-                self.writer.write_u8(buf.len() as u8)?;
-                for b in buf {
-                    self.writer.write_u8(*b)?;
-                }
+                buffer.write_u8(buf.len() as u8)?;
+                buffer.extend_from_slice(buf);
             }
         }
 
+        self.writer.write_all(buffer)?;
         Ok(())
     }
 }
 
+/// Appends `value` right-aligned to `width` characters, like `{: >width$}` does.
+fn write_padded_integer(buffer: &mut Vec<u8>, value: i64, width: usize) {
+    let mut digits = [0u8; 20];
+    let mut start = digits.len();
+    let mut remaining = value.unsigned_abs();
+    loop {
+        start -= 1;
+        digits[start] = b'0' + (remaining % 10) as u8;
+        remaining /= 10;
+        if remaining == 0 {
+            break;
+        }
+    }
+
+    let length = digits.len() - start + usize::from(value < 0);
+    buffer.resize(buffer.len() + width.saturating_sub(length), b' ');
+    if value < 0 {
+        buffer.push(b'-');
+    }
+    buffer.extend_from_slice(&digits[start..]);
+}
+
+/// Encodes the string, escaping control characters and any characters that can't be represented
+/// with the string encoding.
+fn encode_string<'a>(s: &'a str, string_encoding: Option<&'static Encoding>) -> Cow<'a, [u8]> {
+    // printable ASCII is the same in every supported encoding
+    if s.bytes().all(|b| (0x20..0x80).contains(&b) && b != b'^') {
+        return Cow::Borrowed(s.as_bytes());
+    }
+
+    let s = escape_control_characters(s);
+    let bytes = match string_encoding {
+        Some(encoding) => {
+            let mut escaped = String::new();
+            for c in s.chars() {
+                if can_encode(c, encoding) {
+                    escaped.push(c);
+                } else {
+                    escaped.push_str(&escape_unicode_to_ascii(&c.to_string()));
+                }
+            }
+            encoding.encode(&escaped).0.into_owned()
+        }
+        None => escape_unicode_to_ascii(&s).into_bytes(),
+    };
+    Cow::Owned(bytes)
+}
+
 #[cfg(test)]
 mod tests {
     use crate::code_pair_writer::CodePairWriter;
@@ -119,8 +257,12 @@ mod tests {
         let mut writer = CodePairWriter {
             writer: &mut buf,
             as_text: false,
-            text_as_ascii: true,
+            string_encoding: None,
             version: AcadVersion::R2004,
+            buffer: vec![],
+            wrote_prelude: true,
+            detect_encoding: false,
+            header_variable: None,
         };
         writer
             .write_binary_code_pair(pair)
@@ -135,8 +277,12 @@ mod tests {
         let mut writer = CodePairWriter {
             writer: &mut buf,
             as_text: true,
-            text_as_ascii: true,
+            string_encoding: None,
             version: AcadVersion::R2004,
+            buffer: vec![],
+            wrote_prelude: true,
+            detect_encoding: false,
+            header_variable: None,
         };
         writer
             .write_ascii_code_pair(pair)
@@ -210,4 +356,106 @@ mod tests {
         let expected = "100\r\nA\r\n";
         assert_eq!(expected, actual);
     }
+
+    #[test]
+    fn write_numbers_in_ascii() {
+        for (pair, expected) in [
+            (CodePair::new_i16(70, -12), " 70\r\n   -12\r\n"),
+            (CodePair::new_i16(70, i16::MIN), " 70\r\n-32768\r\n"),
+            (CodePair::new_i32(90, 0), " 90\r\n        0\r\n"),
+            (CodePair::new_i32(90, i32::MIN), " 90\r\n-2147483648\r\n"),
+            (
+                CodePair::new_i64(160, i64::MIN),
+                "160\r\n-9223372036854775808\r\n",
+            ),
+            (CodePair::new_bool(290, true), "290\r\n1\r\n"),
+            (CodePair::new_f64(10, -0.5), " 10\r\n-0.5\r\n"),
+            (CodePair::new_i16(-5, 3), " -5\r\n     3\r\n"),
+        ] {
+            assert_eq!(expected, write_in_ascii(&pair));
+        }
+    }
+
+    fn write_pairs(pairs: &[CodePair], as_text: bool, version: AcadVersion) -> Vec<u8> {
+        let mut buf = vec![];
+        let mut writer = if as_text {
+            CodePairWriter::new_ascii(&mut buf, version)
+        } else {
+            CodePairWriter::new_binary(&mut buf, version)
+        };
+        for pair in pairs {
+            writer.write_code_pair(pair).unwrap();
+        }
+        buf
+    }
+
+    #[test]
+    fn write_binary_sentinel_before_first_pair() {
+        let actual = write_pairs(&[CodePair::new_str(0, "EOF")], false, AcadVersion::R12);
+        let mut expected = b"AutoCAD Binary DXF\r\n".to_vec();
+        expected.extend_from_slice(&[0x1A, 0x00, 0x00]);
+        expected.extend_from_slice(b"EOF\0");
+        assert_eq!(expected, actual);
+    }
+
+    #[test]
+    fn write_binary_codes_and_booleans_by_version() {
+        let pairs = [CodePair::new_bool(290, true)];
+        assert_eq!(
+            vec![0xFF, 0x22, 0x01, 0x01, 0x00],
+            write_pairs(&pairs, false, AcadVersion::R12)[22..]
+        );
+        assert_eq!(
+            vec![0x22, 0x01, 0x01],
+            write_pairs(&pairs, false, AcadVersion::R2000)[22..]
+        );
+    }
+
+    #[test]
+    fn write_text_with_code_page_from_written_header() {
+        let pairs = [
+            CodePair::new_str(9, "$DWGCODEPAGE"),
+            CodePair::new_str(3, "ANSI_1251"),
+            CodePair::new_str(1, "А"),
+        ];
+        let actual = write_pairs(&pairs, true, AcadVersion::R2000);
+        assert!(actual.ends_with(b"  1\r\n\xC0\r\n"));
+
+        // code pages aren't written before R13, so everything outside of ASCII is escaped
+        let actual = write_pairs(&pairs, true, AcadVersion::R12);
+        assert!(actual.ends_with(b"  1\r\n\\U+0410\r\n"));
+
+        // and R2007 and later are always UTF-8
+        let actual = write_pairs(&pairs, true, AcadVersion::R2007);
+        assert!(actual.ends_with("  1\r\nА\r\n".as_bytes()));
+    }
+
+    #[test]
+    fn round_trip_pairs_through_reader_and_writer() {
+        use crate::CodePairReader;
+
+        let pairs = vec![
+            CodePair::new_str(0, "SECTION"),
+            CodePair::new_str(2, "ENTITIES"),
+            CodePair::new_str(0, "LINE"),
+            CodePair::new_str(8, "layer"),
+            CodePair::new_f64(10, 1.25),
+            CodePair::new_i16(70, 3),
+            CodePair::new_i32(90, 100_000),
+            CodePair::new_binary(310, vec![0x01, 0x02]),
+            CodePair::new_str(0, "ENDSEC"),
+            CodePair::new_str(0, "EOF"),
+        ];
+        for as_text in [true, false] {
+            let bytes = write_pairs(&pairs, as_text, AcadVersion::R2000);
+            let read = CodePairReader::new(bytes.as_slice())
+                .unwrap()
+                .map(|p| p.unwrap().value)
+                .collect::<Vec<_>>();
+            assert_eq!(
+                pairs.iter().map(|p| p.value.clone()).collect::<Vec<_>>(),
+                read
+            );
+        }
+    }
 }
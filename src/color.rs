@@ -76,6 +76,23 @@ impl Color {
             raw_value: i16::from(i),
         }
     }
+    /// Gets the RGB value of the color from the standard AutoCAD Color Index (ACI) palette, or
+    /// `None` if the color isn't a proper index.
+    pub fn to_rgb(&self) -> Option<(u8, u8, u8)> {
+        self.index().map(aci_to_rgb)
+    }
+    /// Creates the indexed `Color` that most closely matches the specified RGB value.
+    pub fn from_rgb_nearest(r: u8, g: u8, b: u8) -> Color {
+        let distance = |i: u8| {
+            let (ir, ig, ib) = aci_to_rgb(i);
+            let dr = i32::from(ir) - i32::from(r);
+            let dg = i32::from(ig) - i32::from(g);
+            let db = i32::from(ib) - i32::from(b);
+            dr * dr + dg * dg + db * db
+        };
+        let index = (1..=255u8).min_by_key(|&i| distance(i)).unwrap_or(7);
+        Color::from_index(index)
+    }
     pub(crate) fn writable_color_value(&self, layer: &Layer) -> i16 {
         let value = self.raw_value().abs();
         if layer.is_layer_on {
@@ -85,3 +102,73 @@ impl Color {
         }
     }
 }
+
+fn aci_to_rgb(index: u8) -> (u8, u8, u8) {
+    match index {
+        1 => (255, 0, 0),
+        2 => (255, 255, 0),
+        3 => (0, 255, 0),
+        4 => (0, 255, 255),
+        5 => (0, 0, 255),
+        6 => (255, 0, 255),
+        7 => (255, 255, 255),
+        8 => (128, 128, 128),
+        9 => (192, 192, 192),
+        10..=249 => {
+            // 24 hues in 15 degree steps, each with 5 brightness levels in full and half saturation
+            let hue = f64::from((index - 10) / 10) * 15.0;
+            let variant = (index - 10) % 10;
+            let brightness = [1.0, 0.8, 0.6, 0.5, 0.3][usize::from(variant / 2)];
+            let half_saturation = variant % 2 == 1;
+            let channel = |offset: f64| {
+                let angle = (hue - offset + 360.0) % 360.0;
+                let value = if angle <= 60.0 || angle >= 300.0 {
+                    255.0
+                } else if angle < 120.0 {
+                    255.0 * (120.0 - angle) / 60.0
+                } else if angle > 240.0 {
+                    255.0 * (angle - 240.0) / 60.0
+                } else {
+                    0.0
+                };
+                let value = if half_saturation {
+                    255.0 - (255.0 - value) / 2.0
+                } else {
+                    value
+                };
+                (value * brightness).floor() as u8
+            };
+            (channel(0.0), channel(120.0), channel(240.0))
+        }
+        250 => (51, 51, 51),
+        251 => (80, 80, 80),
+        252 => (105, 105, 105),
+        253 => (130, 130, 130),
+        254 => (190, 190, 190),
+        _ => (255, 255, 255),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Color;
+
+    #[test]
+    fn indexed_colors_to_rgb() {
+        assert_eq!(Some((255, 0, 0)), Color::from_index(1).to_rgb());
+        assert_eq!(Some((255, 63, 0)), Color::from_index(20).to_rgb());
+        assert_eq!(Some((255, 159, 127)), Color::from_index(21).to_rgb());
+        assert_eq!(Some((204, 51, 0)), Color::from_index(22).to_rgb());
+        assert_eq!(Some((76, 38, 38)), Color::from_index(19).to_rgb());
+        assert_eq!(Some((0, 0, 255)), Color::from_index(170).to_rgb());
+        assert_eq!(Some((127, 0, 255)), Color::from_index(190).to_rgb());
+        assert_eq!(None, Color::by_layer().to_rgb());
+    }
+
+    #[test]
+    fn nearest_indexed_color() {
+        assert_eq!(Some(1), Color::from_rgb_nearest(250, 2, 3).index());
+        assert_eq!(Some(7), Color::from_rgb_nearest(255, 255, 255).index());
+        assert_eq!(Some(250), Color::from_rgb_nearest(50, 50, 50).index());
+    }
+}
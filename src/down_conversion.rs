@@ -0,0 +1,590 @@
+// converts entities into equivalents that can be written to older drawing versions
+
+use std::collections::VecDeque;
+use std::f64::consts::PI;
+
+use crate::drawing::AUTO_REPLACE_HANDLE;
+use crate::entities::*;
+use crate::enums::*;
+use crate::geometry::*;
+use crate::{CodePair, Color, Handle, Point, Vector};
+
+/// The number of segments used to approximate a full ellipse.
+const ELLIPSE_SEGMENTS: usize = 64;
+
+/// The number of segments per knot span when a spline is written as a polyline.  This is coarser
+/// than the renderers use because each segment is stored in the file as a `Vertex` entity.
+const POLYLINE_SEGMENTS_PER_SPAN: usize = 8;
+
+/// Describes a change made to an entity so that it could be written to an older `AcadVersion`.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub enum DownConversion {
+    /// The entity was replaced by one or more entities of a type the target version supports.
+    Converted {
+        handle: Handle,
+        from: String,
+        to: String,
+    },
+    /// The entity's 24-bit color was replaced with the nearest indexed color.
+    ColorApproximated {
+        handle: Handle,
+        color_24_bit: i32,
+        color: Color,
+    },
+    /// The entity can't be represented in the target version and was removed.
+    Dropped { handle: Handle, type_string: String },
+}
+
+/// Reports the changes made when converting a drawing's contents to an older `AcadVersion`.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub struct DownConversionReport {
+    pub conversions: Vec<DownConversion>,
+}
+
+impl DownConversionReport {
+    /// Returns `true` if no changes were required.
+    pub fn is_empty(&self) -> bool {
+        self.conversions.is_empty()
+    }
+    /// Returns the conversions describing entities that could not be represented at all.
+    pub fn dropped(&self) -> impl Iterator<Item = &DownConversion> {
+        self.conversions
+            .iter()
+            .filter(|c| matches!(c, DownConversion::Dropped { .. }))
+    }
+}
+
+/// Converts entities one at a time, e.g., as they're written, giving new entities handles from
+/// `next_handle`.
+pub(crate) struct DownConverter {
+    version: AcadVersion,
+    pub next_handle: Handle,
+    pub report: DownConversionReport,
+    /// Entities converted by `convert_ahead` that haven't been written yet, in writing order.
+    converted_ahead: VecDeque<(*const Entity, Vec<Entity>)>,
+}
+
+impl DownConverter {
+    pub fn new(version: AcadVersion, next_handle: Handle) -> Self {
+        DownConverter {
+            version,
+            next_handle,
+            report: DownConversionReport::default(),
+            converted_ahead: VecDeque::new(),
+        }
+    }
+    /// Returns the entities that represent `entity` in the target version.
+    pub fn convert(&mut self, entity: Entity) -> Vec<Entity> {
+        if !needs_down_conversion(&entity, self.version) {
+            return vec![entity];
+        }
+
+        let mut converted = down_convert_entity(entity, self.version, &mut self.report);
+        for entity in converted.iter_mut() {
+            if entity.common.handle.is_empty() {
+                entity.common.handle = self.next_handle();
+            }
+            if let EntityType::Polyline(ref mut poly) = entity.specific {
+                poly.__seqend_handle = self.next_handle();
+                for v in poly.__vertices_and_handles.iter_mut() {
+                    if v.1 == AUTO_REPLACE_HANDLE {
+                        v.1 = self.next_handle();
+                    }
+                }
+            }
+        }
+        converted
+    }
+    /// Converts `entities` before they're written so `next_handle` already follows every handle
+    /// they need.  `add_code_pairs` reuses the results when the same entities are then written in
+    /// the same order.
+    pub fn convert_ahead<'a>(&mut self, entities: impl IntoIterator<Item = &'a Entity>) {
+        for entity in entities {
+            if needs_down_conversion(entity, self.version) {
+                let converted = self.convert(entity.clone());
+                self.converted_ahead
+                    .push_back((entity as *const Entity, converted));
+            }
+        }
+    }
+    /// Adds the code pairs of `entity`, only copying it if it has to be converted.
+    pub fn add_code_pairs(
+        &mut self,
+        entity: &Entity,
+        pairs: &mut Vec<CodePair>,
+        write_handles: bool,
+    ) {
+        if needs_down_conversion(entity, self.version) {
+            let is_converted_ahead = self
+                .converted_ahead
+                .front()
+                .is_some_and(|(ahead, _)| std::ptr::eq(*ahead, entity));
+            let converted = if is_converted_ahead {
+                self.converted_ahead.pop_front().unwrap().1
+            } else {
+                self.convert(entity.clone())
+            };
+            for converted in converted {
+                converted.add_code_pairs(pairs, self.version, write_handles);
+            }
+        } else {
+            entity.add_code_pairs(pairs, self.version, write_handles);
+        }
+    }
+    fn next_handle(&mut self) -> Handle {
+        let result = self.next_handle;
+        self.next_handle = self.next_handle.next_handle_value();
+        result
+    }
+}
+
+pub(crate) fn needs_down_conversion(entity: &Entity, version: AcadVersion) -> bool {
+    !entity.specific.is_supported_on_version(version)
+        || (version < AcadVersion::R2004 && entity.common.color_24_bit != 0)
+}
+
+/// Converts the entity into zero or more entities that can be written to `version`.  Child handles
+/// of the results are set to `AUTO_REPLACE_HANDLE` and handles of additional entities are empty.
+pub(crate) fn down_convert_entity(
+    entity: Entity,
+    version: AcadVersion,
+    report: &mut DownConversionReport,
+) -> Vec<Entity> {
+    let handle = entity.common.handle;
+    let mut converted = if entity.specific.is_supported_on_version(version) {
+        vec![entity]
+    } else {
+        let from = entity.specific.to_type_string().to_string();
+        let with_specific = |specific| Entity {
+            common: entity.common.clone(),
+            specific,
+        };
+        let mut converted = match entity.specific {
+            EntityType::Ellipse(ref ellipse) => vec![with_specific(EntityType::Polyline(
+                ellipse_to_polyline(ellipse),
+            ))],
+            EntityType::LwPolyline(_) => entity.to_polyline().into_iter().collect(),
+            EntityType::MText(_) => entity.explode_mtext().unwrap_or_default(),
+            EntityType::Spline(ref spline) => spline_to_polyline(spline)
+                .map(|poly| with_specific(EntityType::Polyline(poly)))
+                .into_iter()
+                .collect(),
+            _ => vec![],
+        };
+
+        if converted.is_empty() {
+            if matches!(entity.specific, EntityType::MText(_)) {
+                // empty text draws nothing, so nothing is lost
+                return vec![];
+            }
+
+            report.conversions.push(DownConversion::Dropped {
+                handle,
+                type_string: from,
+            });
+            return vec![];
+        }
+
+        report.conversions.push(DownConversion::Converted {
+            handle,
+            from,
+            to: converted[0].specific.to_type_string().to_string(),
+        });
+        converted[0].common.handle = handle;
+        for entity in converted.iter_mut().skip(1) {
+            entity.common.comments.clear();
+        }
+        converted
+    };
+
+    if version < AcadVersion::R2004 {
+        // exploded entities can each carry a different inline color
+        for entity in converted.iter_mut() {
+            let color_24_bit = entity.common.color_24_bit;
+            if color_24_bit != 0 {
+                let color = Color::from_rgb_nearest(
+                    (color_24_bit >> 16 & 0xFF) as u8,
+                    (color_24_bit >> 8 & 0xFF) as u8,
+                    (color_24_bit & 0xFF) as u8,
+                );
+                report.conversions.push(DownConversion::ColorApproximated {
+                    handle,
+                    color_24_bit,
+                    color: color.clone(),
+                });
+                entity.common.color = color;
+                entity.common.color_24_bit = 0;
+                entity.common.color_name = String::new();
+            }
+        }
+    }
+
+    converted
+}
+
+pub(crate) fn polyline_from_points(points: &[Point], normal: &Vector, is_closed: bool) -> Polyline {
+    let is_2d = (normal.x.abs() < EPSILON && normal.y.abs() < EPSILON && normal.z > 0.0)
+        && points.windows(2).all(|w| (w[0].z - w[1].z).abs() < EPSILON);
+    let mut poly = Polyline::default();
+    poly.set_is_closed(is_closed);
+    if is_2d {
+        poly.location = Point::new(0.0, 0.0, points.first().map_or(0.0, |p| p.z));
+    } else {
+        poly.set_is_3d_polyline(true);
+    }
+
+    for p in points {
+        let location = if is_2d {
+            Point::new(p.x, p.y, 0.0)
+        } else {
+            p.clone()
+        };
+        poly.__vertices_and_handles
+            .push((Vertex::new(location), AUTO_REPLACE_HANDLE));
+    }
+
+    poly
+}
+
+fn ellipse_to_polyline(ellipse: &Ellipse) -> Polyline {
+    let (start, end) = ellipse_parameter_range(ellipse);
+    let is_closed = ellipse.is_closed();
+    let segments = ((end - start) / (2.0 * PI) * ELLIPSE_SEGMENTS as f64).ceil() as usize;
+    let mut points = ellipse.tessellate(segments.max(2));
+    if is_closed {
+        points.pop();
+    }
+    polyline_from_points(&points, &ellipse.normal, is_closed)
+}
+
+fn spline_to_polyline(spline: &Spline) -> Option<Polyline> {
+    let points = spline.tessellate(POLYLINE_SEGMENTS_PER_SPAN);
+    if points.len() < 2 {
+        return None;
+    }
+
+    let is_closed = spline.is_closed() || points_are_close(&points[0], &points[points.len() - 1]);
+    let points = if is_closed {
+        dedupe_loop(&points)
+    } else {
+        points
+    };
+    Some(polyline_from_points(&points, &spline.normal, is_closed))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::down_conversion::*;
+    use crate::helper_functions::tests::*;
+    use crate::*;
+    use float_cmp::approx_eq;
+
+    #[test]
+    fn down_convert_ellipse_and_spline_to_polylines() {
+        let mut drawing = Drawing::new();
+        drawing.add_entity(Entity::new(EntityType::Ellipse(Ellipse {
+            center: Point::new(1.0, 2.0, 3.0),
+            major_axis: Vector::new(2.0, 0.0, 0.0),
+            minor_axis_ratio: 0.5,
+            start_parameter: 0.0,
+            end_parameter: 2.0 * PI,
+            ..Default::default()
+        })));
+        drawing.add_entity(Entity::new(EntityType::Spline(Spline {
+            degree_of_curve: 2,
+            control_points: vec![
+                Point::new(0.0, 0.0, 0.0),
+                Point::new(1.0, 2.0, 0.0),
+                Point::new(2.0, 0.0, 1.0),
+            ],
+            ..Default::default()
+        })));
+        drawing.header.version = AcadVersion::R12;
+        let report = drawing.down_convert();
+        assert_eq!(2, report.conversions.len());
+        assert_eq!(0, report.dropped().count());
+
+        let entities = drawing.entities().collect::<Vec<_>>();
+        assert_eq!(2, entities.len());
+        match entities[0].specific {
+            EntityType::Polyline(ref poly) => {
+                assert!(poly.is_closed());
+                assert!(!poly.is_3d_polyline());
+                assert_eq!(3.0, poly.location.z);
+                assert_eq!(ELLIPSE_SEGMENTS, poly.vertices().count());
+                assert_eq!(
+                    Point::new(3.0, 2.0, 0.0),
+                    poly.vertices().next().unwrap().location
+                );
+            }
+            _ => panic!("expected a polyline"),
+        }
+        match entities[1].specific {
+            EntityType::Polyline(ref poly) => {
+                assert!(!poly.is_closed());
+                assert!(poly.is_3d_polyline());
+                let vertices = poly.vertices().collect::<Vec<_>>();
+                assert_eq!(Point::new(0.0, 0.0, 0.0), vertices[0].location);
+                assert_eq!(
+                    Point::new(2.0, 0.0, 1.0),
+                    vertices[vertices.len() - 1].location
+                );
+            }
+            _ => panic!("expected a polyline"),
+        }
+    }
+
+    #[test]
+    fn down_convert_mtext_to_text_lines() {
+        let mut drawing = Drawing::new();
+        drawing.add_entity(Entity::new(EntityType::MText(MText {
+            insertion_point: Point::new(1.0, 10.0, 0.0),
+            initial_text_height: 3.0,
+            attachment_point: AttachmentPoint::TopLeft,
+            reference_rectangle_width: 0.0,
+            text: String::from("line one\\P\\fArial;line two"),
+            ..Default::default()
+        })));
+        drawing.header.version = AcadVersion::R12;
+        let report = drawing.down_convert();
+        assert_eq!(
+            vec![DownConversion::Converted {
+                handle: drawing.entities().next().unwrap().common.handle,
+                from: String::from("MTEXT"),
+                to: String::from("TEXT"),
+            }],
+            report.conversions
+        );
+
+        let texts = drawing
+            .entities()
+            .map(|e| match e.specific {
+                EntityType::Text(ref t) => t.clone(),
+                _ => panic!("expected text"),
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(2, texts.len());
+        assert_eq!("line one", texts[0].value);
+        assert_eq!("line two", texts[1].value);
+        assert_eq!(Point::new(1.0, 7.0, 0.0), texts[0].location);
+        assert!(approx_eq!(f64, 2.0, texts[1].location.y));
+
+        // every entity gets its own handle
+        let handles = drawing
+            .entities()
+            .map(|e| e.common.handle)
+            .collect::<Vec<_>>();
+        assert!(!handles[1].is_empty());
+        assert_ne!(handles[0], handles[1]);
+    }
+
+    #[test]
+    fn down_convert_true_color() {
+        let mut drawing = Drawing::new();
+        let mut line = Entity::new(EntityType::Line(Default::default()));
+        line.common.color_24_bit = 0x00_FE_01_02;
+        drawing.add_entity(line);
+
+        // nothing to do for versions that support true color
+        drawing.header.version = AcadVersion::R2004;
+        assert!(drawing.clone().down_convert().is_empty());
+
+        drawing.header.version = AcadVersion::R2000;
+        let report = drawing.down_convert();
+        match report.conversions[0] {
+            DownConversion::ColorApproximated { ref color, .. } => {
+                assert_eq!(Some(1), color.index())
+            }
+            _ => panic!("expected a color approximation"),
+        }
+        let line = drawing.entities().next().unwrap();
+        assert_eq!(Some(1), line.common.color.index());
+        assert_eq!(0, line.common.color_24_bit);
+    }
+
+    #[test]
+    fn down_convert_true_color_of_each_exploded_mtext_line() {
+        let mut drawing = Drawing::new();
+        drawing.add_entity(Entity::new(EntityType::MText(MText {
+            text: String::from("none\\P\\c16646402;red\\P\\c255;blue"),
+            reference_rectangle_width: 0.0,
+            ..Default::default()
+        })));
+        drawing.header.version = AcadVersion::R12;
+        let report = drawing.down_convert();
+        let colors = report
+            .conversions
+            .iter()
+            .filter_map(|c| match c {
+                DownConversion::ColorApproximated {
+                    color_24_bit,
+                    color,
+                    ..
+                } => Some((*color_24_bit, color.index())),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(vec![(0x00_FE_01_02, Some(1)), (0xFF, Some(5))], colors);
+        let entities = drawing.entities().collect::<Vec<_>>();
+        assert_eq!(3, entities.len());
+        assert!(entities[0].common.color.is_by_layer());
+        assert_eq!(Some(1), entities[1].common.color.index());
+        assert_eq!(Some(5), entities[2].common.color.index());
+        assert!(entities.iter().all(|e| e.common.color_24_bit == 0));
+    }
+
+    #[test]
+    fn down_convert_reports_dropped_entities() {
+        let mut drawing = Drawing::new();
+        drawing.add_entity(Entity::new(EntityType::Solid3D(Default::default())));
+        drawing.add_entity(Entity::new(EntityType::Line(Default::default())));
+        drawing.header.version = AcadVersion::R12;
+        let report = drawing.down_convert();
+        let dropped = report.dropped().collect::<Vec<_>>();
+        assert_eq!(1, dropped.len());
+        match dropped[0] {
+            DownConversion::Dropped {
+                ref type_string, ..
+            } => assert_eq!("3DSOLID", type_string),
+            _ => unreachable!(),
+        }
+        assert_eq!(1, drawing.entities().count());
+    }
+
+    #[test]
+    fn down_convert_skips_empty_mtext() {
+        let mut drawing = Drawing::new();
+        drawing.add_entity(Entity::new(EntityType::MText(MText {
+            text: String::new(),
+            ..Default::default()
+        })));
+        drawing.header.version = AcadVersion::R12;
+        let report = drawing.down_convert();
+        assert!(report.is_empty());
+        assert_eq!(0, drawing.entities().count());
+    }
+
+    #[test]
+    fn save_r12_writes_converted_entities() {
+        let mut drawing = Drawing::new();
+        drawing.header.version = AcadVersion::R12;
+        drawing.add_entity(Entity::new(EntityType::MText(MText {
+            text: String::from("some text"),
+            reference_rectangle_width: 0.0,
+            ..Default::default()
+        })));
+        assert_contains_pairs(&drawing, vec![CodePair::new_str(0, "TEXT")]);
+        assert_contains_pairs(&drawing, vec![CodePair::new_str(1, "some text")]);
+        assert_not_contains_pairs(&drawing, vec![CodePair::new_str(0, "MTEXT")]);
+
+        // the drawing itself is unchanged
+        let entities = drawing.entities().collect::<Vec<_>>();
+        match entities[0].specific {
+            EntityType::MText(_) => (),
+            _ => panic!("expected mtext"),
+        }
+    }
+
+    #[test]
+    fn save_with_options_returns_lossy_changes() {
+        let mut drawing = Drawing::new();
+        drawing.header.version = AcadVersion::R12;
+        drawing.add_entity(Entity::new(EntityType::Solid3D(Default::default())));
+        drawing.add_entity(Entity::new(EntityType::Ellipse(Default::default())));
+        let mut buf = Vec::new();
+        let report = drawing
+            .save_with_options(&mut buf, &DxfSaveOptions::default())
+            .unwrap();
+        assert_eq!(2, report.conversions.len());
+        assert_eq!(1, report.dropped().count());
+        let text = String::from_utf8(buf).unwrap();
+        assert!(!text.contains("3DSOLID"));
+        assert_eq!(2, drawing.entities().count());
+
+        // nothing to report when the entities can be written as-is
+        drawing.header.version = AcadVersion::R2018;
+        let report = drawing
+            .save_with_options(&mut Vec::new(), &DxfSaveOptions::default())
+            .unwrap();
+        assert!(report.is_empty());
+    }
+
+    #[test]
+    fn save_gives_converted_entities_handles_below_the_handle_seed() {
+        let mut drawing = Drawing::new();
+        drawing.header.version = AcadVersion::R13;
+        let mut poly = LwPolyline::default();
+        poly.vertices.push(LwPolylineVertex::default());
+        poly.vertices.push(LwPolylineVertex {
+            x: 1.0,
+            ..Default::default()
+        });
+        drawing.add_entity(Entity::new(EntityType::LwPolyline(poly)));
+        let pairs = drawing.code_pairs().unwrap();
+        let handle_seed = pairs
+            .iter()
+            .position(|p| p.code == 9 && p.assert_string().unwrap() == "$HANDSEED")
+            .map(|i| pairs[i + 1].as_handle().unwrap())
+            .unwrap();
+        let entities_start = pairs
+            .iter()
+            .position(|p| p.code == 2 && p.assert_string().unwrap() == "ENTITIES")
+            .unwrap();
+        let entities_end = entities_start
+            + pairs[entities_start..]
+                .iter()
+                .position(|p| p.code == 0 && p.assert_string().unwrap() == "ENDSEC")
+                .unwrap();
+
+        // the polyline, its two vertices and the seqend
+        let mut handles = pairs[entities_start..entities_end]
+            .iter()
+            .filter(|p| p.code == 5)
+            .map(|p| p.as_handle().unwrap().0)
+            .collect::<Vec<_>>();
+        handles.sort();
+        handles.dedup();
+        assert_eq!(4, handles.len());
+        assert!(handles[3] < handle_seed.0);
+    }
+
+    #[test]
+    fn save_converts_block_and_drawing_entities_once() {
+        let mut drawing = Drawing::new();
+        drawing.header.version = AcadVersion::R13;
+        let poly = || {
+            let mut poly = LwPolyline::default();
+            poly.vertices.push(LwPolylineVertex::default());
+            poly.vertices.push(LwPolylineVertex {
+                x: 1.0,
+                ..Default::default()
+            });
+            Entity::new(EntityType::LwPolyline(poly))
+        };
+        drawing.add_block(Block {
+            name: String::from("b"),
+            entities: vec![poly()],
+            ..Default::default()
+        });
+        drawing.add_entity(poly());
+        let report = drawing
+            .save_with_options(&mut Vec::new(), &DxfSaveOptions::default())
+            .unwrap();
+        assert_eq!(2, report.conversions.len());
+
+        // every vertex written has a handle of its own
+        let pairs = drawing.code_pairs().unwrap();
+        let vertex_handles = pairs
+            .windows(2)
+            .filter(|w| w[0].code == 0 && w[0].assert_string().unwrap() == "VERTEX")
+            .map(|w| w[1].as_handle().unwrap())
+            .collect::<Vec<_>>();
+        let mut unique = vertex_handles.clone();
+        unique.sort_by_key(|h| h.0);
+        unique.dedup();
+        assert_eq!(4, vertex_handles.len());
+        assert_eq!(vertex_handles.len(), unique.len());
+    }
+}
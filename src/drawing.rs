@@ -1,8 +1,9 @@
 use encoding_rs::Encoding;
 
-use image::DynamicImage;
+use image::codecs::png::PngEncoder;
+use image::{DynamicImage, RgbaImage};
 
-use crate::code_pair_put_back::CodePairPutBack;
+use crate::code_pair_put_back::{CodePairPutBack, COMMENT_CODE};
 use crate::drawing_item::{DrawingItem, DrawingItemMut};
 use crate::entities::*;
 use crate::enums::*;
@@ -10,17 +11,39 @@ use crate::header::*;
 use crate::objects::*;
 use crate::tables::*;
 
-use crate::{CodePair, CodePairValue, DxfError, DxfResult, Handle};
+use crate::{
+    CodePair, CodePairValue, DownConversionReport, DwgReadReport, DxbOptions, DxfError, DxfResult,
+    ExtensionGroup, GcodeOptions, GltfOptions, Handle, HpglImportOptions, HpglOptions,
+    MeshExportOptions, MeshImportOptions, RasterOptions, SvgImportOptions, SvgOptions,
+    XRecordValue,
+};
 
+use crate::down_conversion::DownConverter;
+use crate::dwg_reader::DwgReader;
 use crate::dxb_reader::DxbReader;
 use crate::dxb_writer::DxbWriter;
-use crate::entity_iter::EntityIter;
+use crate::entity_iter::{collect_entities, EntityIter};
+use crate::extension_data::{
+    add_reactor, extension_dictionary_handle, remove_reactor, set_extension_dictionary_handle,
+};
+use crate::gcode_writer::GcodeWriter;
+use crate::geo_json::{GeoJsonReader, GeoJsonWriter};
+use crate::gltf_writer::GltfWriter;
 use crate::helper_functions::*;
+use crate::hpgl_reader::HpglReader;
+use crate::hpgl_writer::HpglWriter;
+use crate::mesh_reader::MeshReader;
+use crate::mesh_writer::MeshWriter;
 use crate::object_iter::ObjectIter;
+use crate::parallel_reader::read_items_in_parallel;
+use crate::rasterizer::Rasterizer;
+use crate::svg_reader::SvgReader;
+use crate::svg_writer::SvgWriter;
 
 use crate::block::Block;
 use crate::class::Class;
 
+use crate::code_page::encoding_for_code_page;
 use crate::code_pair_iter::{new_code_pair_iter_from_reader, CodePairIter};
 use crate::code_pair_writer::CodePairWriter;
 
@@ -29,13 +52,19 @@ use crate::thumbnail;
 use std::fs::File;
 use std::io::{BufReader, BufWriter, Cursor, Read, Write};
 
-use itertools::put_back;
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::iter::Iterator;
 use std::path::Path;
 
 pub(crate) const AUTO_REPLACE_HANDLE: Handle = Handle(0xFFFF_FFFF_FFFF_FFFF);
 
+/// Options used when writing a `Drawing` as DXF.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct DxfSaveOptions {
+    /// Whether 999 comments are left out of the file.  The drawing itself keeps them.
+    pub strip_comments: bool,
+}
+
 /// Represents a DXF drawing.
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
@@ -44,6 +73,12 @@ pub struct Drawing {
     pub header: Header,
     /// The classes contained by the drawing.
     pub classes: Vec<Class>,
+    /// The 999 comments written at the start of the file.
+    pub comments: Vec<String>,
+    /// The 999 comments that weren't attached to an item when the drawing was read, keyed by the
+    /// name of the section they followed, e.g., `ENTITIES`.  They're written at the end of that
+    /// section.
+    pub section_comments: HashMap<String, Vec<String>>,
 
     //------------------------------------------------------------------- tables
     /// Internal collection of app ids.
@@ -76,6 +111,9 @@ pub struct Drawing {
     /// The thumbnail image preview of the drawing.
     #[cfg_attr(feature = "serialize", serde(skip))]
     pub thumbnail: Option<DynamicImage>,
+    /// When set, a new thumbnail is rendered with these options every time the drawing is saved.
+    #[cfg_attr(feature = "serialize", serde(skip))]
+    pub regenerate_thumbnail: Option<RasterOptions>,
 }
 
 // public implementation
@@ -85,6 +123,8 @@ impl Drawing {
         let mut drawing = Drawing {
             header: Header::default(),
             classes: vec![],
+            comments: vec![],
+            section_comments: HashMap::new(),
             __app_ids: vec![],
             __block_records: vec![],
             __dim_styles: vec![],
@@ -98,19 +138,55 @@ impl Drawing {
             __entities: vec![],
             __objects: vec![],
             thumbnail: None,
+            regenerate_thumbnail: None,
         };
         drawing.normalize();
         drawing
     }
-    /// Loads a `Drawing` from anything that implements the `Read` trait.
+    /// Loads a `Drawing` from anything that implements the `Read` trait.  Text is decoded using the
+    /// code page specified by `$DWGCODEPAGE`, or as UTF-8 for `R2007` and later.
     pub fn load<'a, T>(reader: &mut T) -> DxfResult<Drawing>
     where
         T: Read + 'a + ?Sized,
     {
-        Drawing::load_with_encoding(reader, encoding_rs::WINDOWS_1252)
+        Drawing::load_internal(reader, encoding_rs::WINDOWS_1252, true, 1)
     }
     /// Loads a `Drawing` from anything that implements the `Read` trait using the specified text encoding.
     pub fn load_with_encoding<T>(reader: &mut T, encoding: &'static Encoding) -> DxfResult<Drawing>
+    where
+        T: Read + ?Sized,
+    {
+        Drawing::load_internal(reader, encoding, false, 1)
+    }
+    /// Loads a `Drawing` from anything that implements the `Read` trait, parsing the `ENTITIES` and
+    /// `OBJECTS` sections on all available cores.  The result is identical to `load`; this only
+    /// pays off for large drawings.
+    pub fn load_parallel<T>(reader: &mut T) -> DxfResult<Drawing>
+    where
+        T: Read + ?Sized,
+    {
+        let thread_count = std::thread::available_parallelism()
+            .map(|count| count.get())
+            .unwrap_or(1);
+        Drawing::load_parallel_with_thread_count(reader, thread_count)
+    }
+    /// Loads a `Drawing` like `load_parallel`, using at most `thread_count` threads.  A
+    /// `thread_count` of 0 or 1 reads everything sequentially.
+    pub fn load_parallel_with_thread_count<T>(
+        reader: &mut T,
+        thread_count: usize,
+    ) -> DxfResult<Drawing>
+    where
+        T: Read + ?Sized,
+    {
+        Drawing::load_internal(reader, encoding_rs::WINDOWS_1252, true, thread_count)
+    }
+    fn load_internal<T>(
+        reader: &mut T,
+        encoding: &'static Encoding,
+        detect_encoding: bool,
+        thread_count: usize,
+    ) -> DxfResult<Drawing>
     where
         T: Read + ?Sized,
     {
@@ -121,17 +197,21 @@ impl Drawing {
                 reader.load()
             }
             _ => {
-                let iter = new_code_pair_iter_from_reader(reader, encoding, first_line)?;
-                Drawing::load_from_iter(iter)
+                let iter =
+                    new_code_pair_iter_from_reader(reader, encoding, detect_encoding, first_line)?;
+                Drawing::load_from_iter(iter, thread_count)
             }
         }
     }
     /// Loads a `Drawing` from the specified `CodePairIter`.
-    pub(crate) fn load_from_iter(iter: Box<dyn CodePairIter>) -> DxfResult<Drawing> {
+    pub(crate) fn load_from_iter<'a>(
+        iter: Box<dyn CodePairIter + 'a>,
+        thread_count: usize,
+    ) -> DxfResult<Drawing> {
         let mut drawing = Drawing::new();
         drawing.clear();
         let mut iter = CodePairPutBack::from_code_pair_iter(iter);
-        Drawing::read_sections(&mut drawing, &mut iter)?;
+        Drawing::read_sections(&mut drawing, &mut iter, thread_count)?;
         match iter.next() {
             Some(Ok(CodePair {
                 code: 0,
@@ -146,9 +226,124 @@ impl Drawing {
             None => Ok(drawing),
         }
     }
-    /// Loads a `Drawing` from disk, using a `BufReader`.
+    /// Loads a `Drawing` from disk, using a `BufReader`.  Text is decoded using the code page
+    /// specified by `$DWGCODEPAGE`, or as UTF-8 for `R2007` and later.
     pub fn load_file(path: impl AsRef<Path>) -> DxfResult<Drawing> {
-        Drawing::load_file_with_encoding(path, encoding_rs::WINDOWS_1252)
+        let file = File::open(&path)?;
+        let mut buf_reader = BufReader::new(file);
+        Drawing::load(&mut buf_reader)
+    }
+    /// Loads a `Drawing` from disk like `load_parallel`, using a `BufReader`.
+    pub fn load_file_parallel(path: impl AsRef<Path>) -> DxfResult<Drawing> {
+        let file = File::open(&path)?;
+        let mut buf_reader = BufReader::new(file);
+        Drawing::load_parallel(&mut buf_reader)
+    }
+    /// Loads a `Drawing` from a DWG file written by AutoCAD R2000 through R2018 (`AC1015`, `AC1018`,
+    /// `AC1021`, `AC1024`, `AC1027` and `AC1032`); other versions return
+    /// `DxfError::UnsupportedDwgVersion`.  Layers, line types, text styles, dimension styles,
+    /// application ids, blocks, dictionaries, xrecords, groups, multiline styles and the entities
+    /// other than hatches are read, along with the header variables up to `$PROJECTNAME`; other
+    /// objects, and modeler geometry stored as binary SAB, are skipped.  Use `load_dwg_with_report`
+    /// to find out which objects were skipped.
+    pub fn load_dwg<T>(reader: &mut T) -> DxfResult<Drawing>
+    where
+        T: Read + ?Sized,
+    {
+        let (drawing, _) = DwgReader::new(reader).load()?;
+        Ok(drawing)
+    }
+    /// Loads a `Drawing` from a DWG file like `load_dwg`.  The returned report lists the objects
+    /// that weren't read, either because their type isn't supported or because their data couldn't
+    /// be decoded, and any section, like the header, that couldn't be decoded.
+    pub fn load_dwg_with_report<T>(reader: &mut T) -> DxfResult<(Drawing, DwgReadReport)>
+    where
+        T: Read + ?Sized,
+    {
+        DwgReader::new(reader).load()
+    }
+    /// Loads a `Drawing` from a DWG file on disk, using a `BufReader`.
+    pub fn load_file_dwg(path: impl AsRef<Path>) -> DxfResult<Drawing> {
+        let file = File::open(&path)?;
+        let mut buf_reader = BufReader::new(file);
+        Drawing::load_dwg(&mut buf_reader)
+    }
+    /// Loads a `Drawing` from a DWG file on disk like `load_dwg_with_report`, using a `BufReader`.
+    pub fn load_file_dwg_with_report(
+        path: impl AsRef<Path>,
+    ) -> DxfResult<(Drawing, DwgReadReport)> {
+        let file = File::open(&path)?;
+        let mut buf_reader = BufReader::new(file);
+        Drawing::load_dwg_with_report(&mut buf_reader)
+    }
+    /// Imports an HP-GL or HP-GL/2 plotter file as a new `Drawing`.  Pen moves become `Line` and
+    /// `LwPolyline` entities, arcs become `Arc` entities or polyline bulges and circles become
+    /// `Circle` entities.  Each pen becomes a layer with the color index of the same number and
+    /// each `LT` line type becomes a `LineType`.
+    pub fn load_hpgl<T>(reader: &mut T, options: &HpglImportOptions) -> DxfResult<Drawing>
+    where
+        T: Read + ?Sized,
+    {
+        HpglReader::new(options).load(reader)
+    }
+    /// Imports an HP-GL plotter file from disk, using a `BufReader`.
+    pub fn load_file_hpgl(
+        path: impl AsRef<Path>,
+        options: &HpglImportOptions,
+    ) -> DxfResult<Drawing> {
+        let file = File::open(&path)?;
+        let mut buf_reader = BufReader::new(file);
+        Drawing::load_hpgl(&mut buf_reader, options)
+    }
+    /// Imports the shapes of an SVG document as a new `Drawing`.  Lines and circular arcs become
+    /// `Line` and `LwPolyline` entities, Bézier curves become `Spline` entities, circles and
+    /// ellipses become `Circle` and `Ellipse` entities and groups with an `id` become layers.
+    pub fn load_svg<T>(reader: &mut T, options: &SvgImportOptions) -> DxfResult<Drawing>
+    where
+        T: Read + ?Sized,
+    {
+        SvgReader::new(options).load(reader)
+    }
+    /// Imports the shapes of an SVG document from disk, using a `BufReader`.
+    pub fn load_file_svg(path: impl AsRef<Path>, options: &SvgImportOptions) -> DxfResult<Drawing> {
+        let file = File::open(&path)?;
+        let mut buf_reader = BufReader::new(file);
+        Drawing::load_svg(&mut buf_reader, options)
+    }
+    /// Imports an ASCII or binary STL mesh as a new `Drawing`.  Each triangle becomes a `Face3D`,
+    /// or each `solid` becomes a polyface mesh if requested, on a layer named after the solid.
+    pub fn load_stl<T>(reader: &mut T, options: &MeshImportOptions) -> DxfResult<Drawing>
+    where
+        T: Read + ?Sized,
+    {
+        MeshReader::new(options).load_stl(reader)
+    }
+    /// Imports an STL mesh from disk, using a `BufReader`.
+    pub fn load_file_stl(
+        path: impl AsRef<Path>,
+        options: &MeshImportOptions,
+    ) -> DxfResult<Drawing> {
+        let file = File::open(&path)?;
+        let mut buf_reader = BufReader::new(file);
+        Drawing::load_stl(&mut buf_reader, options)
+    }
+    /// Imports a Wavefront OBJ mesh as a new `Drawing`.  Each face becomes one or more `Face3D`
+    /// entities, or each group becomes a polyface mesh if requested, on a layer named after the
+    /// group.
+    pub fn load_obj<T>(reader: &mut T, options: &MeshImportOptions) -> DxfResult<Drawing>
+    where
+        T: Read + ?Sized,
+    {
+        MeshReader::new(options).load_obj(reader)
+    }
+    /// Imports a Wavefront OBJ mesh from disk, using a `BufReader`.
+    pub fn load_file_obj(
+        path: impl AsRef<Path>,
+        options: &MeshImportOptions,
+    ) -> DxfResult<Drawing> {
+        let file = File::open(&path)?;
+        let mut buf_reader = BufReader::new(file);
+        Drawing::load_obj(&mut buf_reader, options)
     }
     /// Loads a `Drawing` from disk, using a `BufReader` with the specified text encoding.
     pub fn load_file_with_encoding(
@@ -164,62 +359,174 @@ impl Drawing {
     where
         T: Write + ?Sized,
     {
-        self.save_internal(writer, true)
+        self.save_internal(writer, true, &DxfSaveOptions::default())
+            .map(|_| ())
+    }
+    /// Writes a `Drawing` to anything that implements the `Write` trait with the specified options
+    /// and returns the changes that were made to entities so they could be written to
+    /// `header.version`.
+    pub fn save_with_options<T>(
+        &self,
+        writer: &mut T,
+        options: &DxfSaveOptions,
+    ) -> DxfResult<DownConversionReport>
+    where
+        T: Write + ?Sized,
+    {
+        self.save_internal(writer, true, options)
     }
     /// Writes a `Drawing` as binary to anything that implements the `Write` trait.
     pub fn save_binary<T>(&self, writer: &mut T) -> DxfResult<()>
     where
         T: Write + ?Sized,
     {
-        self.save_internal(writer, false)
+        self.save_internal(writer, false, &DxfSaveOptions::default())
+            .map(|_| ())
     }
     /// Gets all code pairs that will be written.
+    #[cfg(test)]
     pub(crate) fn code_pairs(&self) -> DxfResult<Vec<CodePair>> {
-        let write_handles = self.header.version >= AcadVersion::R13 || self.header.handles_enabled;
-        let mut pairs = Vec::new();
-        self.header.add_code_pairs(&mut pairs);
-        self.add_classes_pairs(&mut pairs);
-        self.add_tables_pairs(&mut pairs, write_handles);
-        self.add_blocks_pairs(&mut pairs, write_handles);
-        self.add_entities_pairs(&mut pairs, write_handles);
-        self.add_objects_pairs(&mut pairs);
-        self.add_thumbnail_pairs(&mut pairs)?;
-        pairs.push(CodePair::new_str(0, "EOF"));
+        let mut pairs = vec![];
+        self.write_code_pairs(false, &mut |pair| {
+            pairs.push(pair);
+            Ok(())
+        })?;
         Ok(pairs)
     }
-    fn save_internal<T>(&self, writer: &mut T, as_ascii: bool) -> DxfResult<()>
+    /// Passes all code pairs that will be written to `write` and returns the changes needed to
+    /// write them.  Pairs are produced one item at a time so the drawing is never held as pairs
+    /// all at once, and entities that can't be written to `header.version` are converted as their
+    /// pairs are added.
+    fn write_code_pairs(
+        &self,
+        strip_comments: bool,
+        write: &mut dyn FnMut(CodePair) -> DxfResult<()>,
+    ) -> DxfResult<DownConversionReport> {
+        let write_handles = self.header.version >= AcadVersion::R13 || self.header.handles_enabled;
+        let mut converter =
+            DownConverter::new(self.header.version, self.header.next_available_handle);
+        let mut out = CodePairOutput::new(&self.section_comments, strip_comments, write);
+        for comment in &self.comments {
+            out.pairs.push(CodePair::new_string(COMMENT_CODE, comment));
+        }
+
+        // `$HANDSEED` has to follow the handles given to converted entities
+        converter.convert_ahead(
+            self.__blocks
+                .iter()
+                .flat_map(|b| &b.entities)
+                .chain(&self.__entities),
+        );
+        if converter.next_handle == self.header.next_available_handle {
+            self.header.add_code_pairs(&mut out.pairs);
+        } else {
+            let mut header = self.header.clone();
+            header.next_available_handle = converter.next_handle;
+            header.add_code_pairs(&mut out.pairs);
+        }
+        out.flush()?;
+        self.add_classes_pairs(&mut out.pairs);
+        self.add_tables_pairs(&mut out.pairs, write_handles);
+        out.flush()?;
+        self.add_blocks_pairs(&mut out, write_handles, &mut converter)?;
+        self.add_entities_pairs(&mut out, write_handles, &mut converter)?;
+        self.add_objects_pairs(&mut out)?;
+        self.add_thumbnail_pairs(&mut out.pairs)?;
+        out.finish()?;
+        Ok(converter.report)
+    }
+    /// Returns the encoding used when writing text, or `None` if only ASCII can be written.
+    fn string_encoding(&self) -> Option<&'static Encoding> {
+        if self.header.version >= AcadVersion::R2007 {
+            Some(encoding_rs::UTF_8)
+        } else if self.header.version >= AcadVersion::R13 {
+            // `$DWGCODEPAGE` is only written for R13 and later
+            encoding_for_code_page(&self.header.drawing_code_page)
+                .filter(|&encoding| encoding != encoding_rs::UTF_8)
+        } else {
+            None
+        }
+    }
+    fn save_internal<T>(
+        &self,
+        writer: &mut T,
+        as_ascii: bool,
+        options: &DxfSaveOptions,
+    ) -> DxfResult<DownConversionReport>
     where
         T: Write + ?Sized,
     {
-        let pairs = self.code_pairs()?;
-        let text_as_ascii = self.header.version <= AcadVersion::R2004;
-        let mut code_pair_writer =
-            CodePairWriter::new(writer, as_ascii, text_as_ascii, self.header.version);
+        let mut code_pair_writer = CodePairWriter::new(
+            writer,
+            as_ascii,
+            self.string_encoding(),
+            self.header.version,
+        );
         code_pair_writer.write_prelude()?;
-        for pair in pairs {
-            code_pair_writer.write_code_pair(&pair)?;
-        }
-        Ok(())
+        self.write_code_pairs(options.strip_comments, &mut |pair| {
+            code_pair_writer.write_code_pair(&pair)
+        })
     }
     /// Writes a `Drawing` to disk, using a `BufWriter`.
     pub fn save_file(&self, path: impl AsRef<Path>) -> DxfResult<()> {
-        self.save_file_internal(path, true)
+        self.save_file_internal(path, true, &DxfSaveOptions::default())
+            .map(|_| ())
+    }
+    /// Writes a `Drawing` to disk, using a `BufWriter`, with the specified options.
+    pub fn save_file_with_options(
+        &self,
+        path: impl AsRef<Path>,
+        options: &DxfSaveOptions,
+    ) -> DxfResult<DownConversionReport> {
+        self.save_file_internal(path, true, options)
     }
     /// Writes a `Drawing` as binary to disk, using a `BufWriter`.
     pub fn save_file_binary(&self, path: impl AsRef<Path>) -> DxfResult<()> {
-        self.save_file_internal(path, false)
+        self.save_file_internal(path, false, &DxfSaveOptions::default())
+            .map(|_| ())
     }
-    fn save_file_internal(&self, path: impl AsRef<Path>, as_ascii: bool) -> DxfResult<()> {
+    fn save_file_internal(
+        &self,
+        path: impl AsRef<Path>,
+        as_ascii: bool,
+        options: &DxfSaveOptions,
+    ) -> DxfResult<DownConversionReport> {
         let file = File::create(&path)?;
         let mut writer = BufWriter::new(file);
-        self.save_internal(&mut writer, as_ascii)
+        self.save_internal(&mut writer, as_ascii, options)
+    }
+    /// Plots the `Drawing` as HP-GL/2 to anything that implements the `Write` trait.  Each color
+    /// index is drawn with the pen of the same number.  Lines, arcs, circles and polylines are
+    /// plotted with `PD`, `AA` and `CI` commands and other curves are flattened.
+    pub fn save_hpgl<T>(&self, writer: &mut T, options: &HpglOptions) -> DxfResult<()>
+    where
+        T: Write + ?Sized,
+    {
+        HpglWriter::new(self, options).write(writer)
+    }
+    /// Plots the `Drawing` as HP-GL/2 to disk, using a `BufWriter`.
+    pub fn save_file_hpgl(&self, path: impl AsRef<Path>, options: &HpglOptions) -> DxfResult<()> {
+        let file = File::create(&path)?;
+        let mut buf_writer = BufWriter::new(file);
+        self.save_hpgl(&mut buf_writer, options)
     }
-    /// Writes a `Drawing` as DXB to anything that implements the `Write` trait.
+    /// Writes a `Drawing` as DXB to anything that implements the `Write` trait, using floating
+    /// point numbers.  Block references are expanded since DXB can't describe them, and entities
+    /// without a DXB equivalent are approximated with lines.  A drawing without entities is
+    /// written as a block with the contents of all its blocks.
     pub fn save_dxb<T>(&self, writer: &mut T) -> DxfResult<()>
     where
         T: Write + ?Sized,
     {
-        let mut writer = DxbWriter::new(writer);
+        self.save_dxb_with_options(writer, &DxbOptions::default())
+    }
+    /// Writes a `Drawing` as DXB to anything that implements the `Write` trait using the
+    /// specified number format.
+    pub fn save_dxb_with_options<T>(&self, writer: &mut T, options: &DxbOptions) -> DxfResult<()>
+    where
+        T: Write + ?Sized,
+    {
+        let mut writer = DxbWriter::new(writer, options);
         writer.write(self)
     }
     /// Writes a `Drawing` as DXB to disk, using a `BufWriter`.
@@ -228,6 +535,165 @@ impl Drawing {
         let mut buf_writer = BufWriter::new(file);
         self.save_dxb(&mut buf_writer)
     }
+    /// Renders the `Drawing` as SVG to anything that implements the `Write` trait.  Each layer
+    /// is written as a `<g>` element and each referenced block as a `<symbol>`.
+    pub fn save_svg<T>(&self, writer: &mut T, options: &SvgOptions) -> DxfResult<()>
+    where
+        T: Write + ?Sized,
+    {
+        SvgWriter::new(self, options).write(writer)
+    }
+    /// Renders the `Drawing` as SVG to disk, using a `BufWriter`.
+    pub fn save_file_svg(&self, path: impl AsRef<Path>, options: &SvgOptions) -> DxfResult<()> {
+        let file = File::create(&path)?;
+        let mut buf_writer = BufWriter::new(file);
+        self.save_svg(&mut buf_writer, options)
+    }
+    /// Renders the `Drawing` to an image.  Text is drawn as a translucent box where it would
+    /// appear.
+    pub fn rasterize(&self, options: &RasterOptions) -> DxfResult<RgbaImage> {
+        Rasterizer::new(self, options).render()
+    }
+    /// Renders the `Drawing` and uses the image as its thumbnail.
+    pub fn update_thumbnail(&mut self, options: &RasterOptions) -> DxfResult<()> {
+        let image = self.rasterize(options)?;
+        self.thumbnail = Some(DynamicImage::ImageRgba8(image));
+        Ok(())
+    }
+    /// Renders the `Drawing` as PNG to anything that implements the `Write` trait.
+    pub fn save_png<T>(&self, writer: &mut T, options: &RasterOptions) -> DxfResult<()>
+    where
+        T: Write + ?Sized,
+    {
+        let image = self.rasterize(options)?;
+        image.write_with_encoder(PngEncoder::new(writer))?;
+        Ok(())
+    }
+    /// Renders the `Drawing` as PNG to disk, using a `BufWriter`.
+    pub fn save_file_png(&self, path: impl AsRef<Path>, options: &RasterOptions) -> DxfResult<()> {
+        let file = File::create(&path)?;
+        let mut buf_writer = BufWriter::new(file);
+        self.save_png(&mut buf_writer, options)
+    }
+    /// Writes the surfaces of the drawing as an STL triangle mesh to anything that implements the
+    /// `Write` trait.  `Face3D` entities, polyface and polygon meshes, `Solid` and `Trace` entities
+    /// and `Line`, `Circle` and `Arc` entities with a thickness are exported, including those in
+    /// block references.  ASCII files have a `solid` for each layer.
+    pub fn save_stl<T>(&self, writer: &mut T, options: &MeshExportOptions) -> DxfResult<()>
+    where
+        T: Write + ?Sized,
+    {
+        MeshWriter::new(self, options)?.write_stl(writer, options.binary_stl)
+    }
+    /// Writes the surfaces of the drawing as an STL triangle mesh to disk, using a `BufWriter`.
+    pub fn save_file_stl(
+        &self,
+        path: impl AsRef<Path>,
+        options: &MeshExportOptions,
+    ) -> DxfResult<()> {
+        let file = File::create(&path)?;
+        let mut buf_writer = BufWriter::new(file);
+        self.save_stl(&mut buf_writer, options)
+    }
+    /// Writes the surfaces of the drawing as a Wavefront OBJ mesh, with a group for each layer, to
+    /// anything that implements the `Write` trait.  The same entities as `save_stl()` are
+    /// exported, but quads and other planar faces aren't split into triangles.
+    pub fn save_obj<T>(&self, writer: &mut T, options: &MeshExportOptions) -> DxfResult<()>
+    where
+        T: Write + ?Sized,
+    {
+        MeshWriter::new(self, options)?.write_obj(writer)
+    }
+    /// Writes the surfaces of the drawing as a Wavefront OBJ mesh to disk, using a `BufWriter`.
+    pub fn save_file_obj(
+        &self,
+        path: impl AsRef<Path>,
+        options: &MeshExportOptions,
+    ) -> DxfResult<()> {
+        let file = File::create(&path)?;
+        let mut buf_writer = BufWriter::new(file);
+        self.save_obj(&mut buf_writer, options)
+    }
+    /// Writes the 2D profiles of the drawing as a G-code toolpath to anything that implements the
+    /// `Write` trait.  `Line`, `Arc`, `Circle` and `LwPolyline` entities are cut with straight and
+    /// circular moves and other curves are approximated with straight moves.  Entities whose ends
+    /// meet are cut as one contour, and contours are ordered to keep rapid moves short.  Units are
+    /// taken from `$INSUNITS`.  Block references aren't expanded.
+    pub fn save_gcode<T>(&self, writer: &mut T, options: &GcodeOptions) -> DxfResult<()>
+    where
+        T: Write + ?Sized,
+    {
+        GcodeWriter::new(self, options).write(writer)
+    }
+    /// Writes the 2D profiles of the drawing as a G-code toolpath to disk, using a `BufWriter`.
+    pub fn save_file_gcode(&self, path: impl AsRef<Path>, options: &GcodeOptions) -> DxfResult<()> {
+        let file = File::create(&path)?;
+        let mut buf_writer = BufWriter::new(file);
+        self.save_gcode(&mut buf_writer, options)
+    }
+    /// Writes the drawing as a glTF 2.0 scene, with its buffer embedded as a data URI, to anything
+    /// that implements the `Write` trait.  Each layer is a node holding a mesh of its surfaces,
+    /// drawn as triangles, and its curves, drawn as lines.  Block references are nodes that
+    /// transform a mesh shared by every reference to the same block.  Materials are taken from
+    /// the `Material` objects applied to entities and layers, or from the entity colors.
+    pub fn save_gltf<T>(&self, writer: &mut T, options: &GltfOptions) -> DxfResult<()>
+    where
+        T: Write + ?Sized,
+    {
+        GltfWriter::new(self, options)?.write_gltf(writer)
+    }
+    /// Writes the drawing as a glTF 2.0 scene to disk, using a `BufWriter`.
+    pub fn save_file_gltf(&self, path: impl AsRef<Path>, options: &GltfOptions) -> DxfResult<()> {
+        let file = File::create(&path)?;
+        let mut buf_writer = BufWriter::new(file);
+        self.save_gltf(&mut buf_writer, options)
+    }
+    /// Writes the same scene as `save_gltf()` as a binary GLB file to anything that implements
+    /// the `Write` trait.
+    pub fn save_glb<T>(&self, writer: &mut T, options: &GltfOptions) -> DxfResult<()>
+    where
+        T: Write + ?Sized,
+    {
+        GltfWriter::new(self, options)?.write_glb(writer)
+    }
+    /// Writes the drawing as a binary GLB file to disk, using a `BufWriter`.
+    pub fn save_file_glb(&self, path: impl AsRef<Path>, options: &GltfOptions) -> DxfResult<()> {
+        let file = File::create(&path)?;
+        let mut buf_writer = BufWriter::new(file);
+        self.save_glb(&mut buf_writer, options)
+    }
+    /// Writes the model space entities as a `GeoJSON` `FeatureCollection` to anything that
+    /// implements the `Write` trait.  Coordinates are converted to WGS84 longitude and latitude
+    /// using the drawing's `GeoData` and each feature's properties hold the entity's layer, color
+    /// and extended data.
+    pub fn save_geojson<T>(&self, writer: &mut T) -> DxfResult<()>
+    where
+        T: Write + ?Sized,
+    {
+        GeoJsonWriter::new(self)?.write(writer)
+    }
+    /// Writes the model space entities as `GeoJSON` to disk, using a `BufWriter`.
+    pub fn save_file_geojson(&self, path: impl AsRef<Path>) -> DxfResult<()> {
+        let file = File::create(&path)?;
+        let mut buf_writer = BufWriter::new(file);
+        self.save_geojson(&mut buf_writer)
+    }
+    /// Adds the features of a `GeoJSON` document to model space, converting their WGS84
+    /// coordinates with the drawing's `GeoData`.  Points become `ModelPoint` entities, lines
+    /// become `Line` or `LwPolyline` entities and each polygon ring becomes a closed
+    /// `LwPolyline`.  The properties written by `save_geojson` are restored.
+    pub fn import_geojson<T>(&mut self, reader: &mut T) -> DxfResult<()>
+    where
+        T: Read + ?Sized,
+    {
+        GeoJsonReader::new(self)?.read(self, reader)
+    }
+    /// Adds the features of a `GeoJSON` document on disk to model space, using a `BufReader`.
+    pub fn import_file_geojson(&mut self, path: impl AsRef<Path>) -> DxfResult<()> {
+        let file = File::open(&path)?;
+        let mut buf_reader = BufReader::new(file);
+        self.import_geojson(&mut buf_reader)
+    }
     /// Returns an iterator for all app ids.
     pub fn app_ids(&self) -> impl Iterator<Item = &AppId> {
         self.__app_ids.iter()
@@ -408,6 +874,12 @@ impl Drawing {
     }
     /// Adds an entity to the `Drawing`.
     pub fn add_entity(&mut self, mut entity: Entity) -> &Entity {
+        self.set_entity_handles(&mut entity);
+
+        // ensure invariants
+        self.add_entity_no_handle_set(entity)
+    }
+    fn set_entity_handles(&mut self, entity: &mut Entity) {
         entity.common.handle = self.next_handle();
 
         // set child handles
@@ -430,13 +902,15 @@ impl Drawing {
             }
             _ => (),
         }
-
-        // ensure invariants
-        self.add_entity_no_handle_set(entity)
     }
-    /// Removes the specified `Entity` from the `Drawing`.
+    /// Removes the specified `Entity` from the `Drawing` along with its extension dictionary.
     pub fn remove_entity(&mut self, index: usize) -> Option<Entity> {
-        Drawing::remove_item(&mut self.__entities, index)
+        let entity = Drawing::remove_item(&mut self.__entities, index)?;
+        self.remove_extension_dictionary(
+            entity.common.handle,
+            &entity.common.extension_data_groups,
+        );
+        Some(entity)
     }
     /// Returns an iterator for all contained objects.
     pub fn objects(&self) -> impl Iterator<Item = &Object> {
@@ -455,13 +929,17 @@ impl Drawing {
         // ensure invariants
         self.add_object_no_handle_set(obj)
     }
-    /// Removes the specified `Object` from the `Drawing`.
+    /// Removes the specified `Object` from the `Drawing` along with its extension dictionary.
     pub fn remove_object(&mut self, index: usize) -> Option<Object> {
-        Drawing::remove_item(&mut self.__objects, index)
+        let obj = Drawing::remove_item(&mut self.__objects, index)?;
+        self.remove_extension_dictionary(obj.common.handle, &obj.common.extension_data_groups);
+        Some(obj)
     }
     /// Clears all items from the `Drawing`.
     pub fn clear(&mut self) {
         self.classes.clear();
+        self.comments.clear();
+        self.section_comments.clear();
         self.__app_ids.clear();
         self.__block_records.clear();
         self.__dim_styles.clear();
@@ -478,6 +956,33 @@ impl Drawing {
 
         self.header.next_available_handle = Handle(1);
     }
+    /// Removes the 999 comments from the drawing and all of its items so they won't be written.  To
+    /// keep them in the drawing but leave them out of a file, use `DxfSaveOptions::strip_comments`.
+    pub fn strip_comments(&mut self) {
+        fn strip<T>(items: &mut [T], comments: fn(&mut T) -> &mut Vec<String>) {
+            for item in items {
+                comments(item).clear();
+            }
+        }
+
+        self.comments.clear();
+        self.section_comments.clear();
+        strip(&mut self.__app_ids, |i| &mut i.comments);
+        strip(&mut self.__block_records, |i| &mut i.comments);
+        strip(&mut self.__dim_styles, |i| &mut i.comments);
+        strip(&mut self.__layers, |i| &mut i.comments);
+        strip(&mut self.__line_types, |i| &mut i.comments);
+        strip(&mut self.__styles, |i| &mut i.comments);
+        strip(&mut self.__ucss, |i| &mut i.comments);
+        strip(&mut self.__views, |i| &mut i.comments);
+        strip(&mut self.__view_ports, |i| &mut i.comments);
+        for block in &mut self.__blocks {
+            block.comments.clear();
+            strip(&mut block.entities, |e| &mut e.common.comments);
+        }
+        strip(&mut self.__entities, |e| &mut e.common.comments);
+        strip(&mut self.__objects, |o| &mut o.common.comments);
+    }
     /// Normalizes the `Drawing` by ensuring expected items are present.
     pub fn normalize(&mut self) {
         // TODO: check for duplicates
@@ -497,6 +1002,7 @@ impl Drawing {
         self.ensure_text_styles();
         self.ensure_view_ports();
         self.ensure_ucs();
+        self.ensure_dictionaries();
 
         self.__app_ids.sort_by(|a, b| a.name.cmp(&b.name));
         self.__block_records.sort_by(|a, b| a.name.cmp(&b.name));
@@ -508,6 +1014,153 @@ impl Drawing {
         self.__views.sort_by(|a, b| a.name.cmp(&b.name));
         self.__view_ports.sort_by(|a, b| a.name.cmp(&b.name));
     }
+    /// Converts entities that can't be represented in `header.version` into equivalents that can,
+    /// e.g., `Ellipse` and `Spline` become `Polyline`, `MText` becomes `Text` lines, `LwPolyline`
+    /// becomes `Polyline` and 24-bit colors become the nearest indexed color.  Entities with no
+    /// equivalent are removed.  Saving does this for each entity as it's written without changing
+    /// the drawing; call it directly or use `Drawing::save_with_options` to find out what was
+    /// changed.
+    pub fn down_convert(&mut self) -> DownConversionReport {
+        let mut converter =
+            DownConverter::new(self.header.version, self.header.next_available_handle);
+        let entities = std::mem::take(&mut self.__entities);
+        self.__entities = Drawing::down_convert_entities(entities, &mut converter);
+        for block in self.__blocks.iter_mut() {
+            let entities = std::mem::take(&mut block.entities);
+            block.entities = Drawing::down_convert_entities(entities, &mut converter);
+        }
+
+        self.header.next_available_handle = converter.next_handle;
+        converter.report
+    }
+    /// Replaces every `ProxyEntity` whose graphics can be decoded with the native entities that draw
+    /// it; see `Entity::explode_proxy`.  Returns the number of proxies that were replaced.
+    pub fn explode_proxy_entities(&mut self) -> usize {
+        let mut count = 0;
+        let entities = std::mem::take(&mut self.__entities);
+        self.__entities = self.explode_proxies(entities, &mut count);
+
+        let mut blocks = std::mem::take(&mut self.__blocks);
+        for block in blocks.iter_mut() {
+            let entities = std::mem::take(&mut block.entities);
+            block.entities = self.explode_proxies(entities, &mut count);
+        }
+        self.__blocks = blocks;
+
+        count
+    }
+    /// Returns the named object dictionary, the root `Dictionary` that has no owner and anchors
+    /// `ACAD_GROUP`, `ACAD_LAYOUT`, etc.
+    pub fn named_object_dictionary(&self) -> Option<&Object> {
+        self.named_object_dictionary_index()
+            .map(|index| &self.__objects[index])
+    }
+    /// Returns the object found by following `path` through nested dictionaries from the named
+    /// object dictionary, e.g., `&["ACAD_LAYOUT", "Layout1"]`.  An empty path returns the named
+    /// object dictionary.
+    pub fn dict_get(&self, path: &[&str]) -> Option<&Object> {
+        self.dict_index(path).map(|index| &self.__objects[index])
+    }
+    /// Returns the mutable object found by following `path` like `dict_get`.
+    pub fn dict_get_mut(&mut self, path: &[&str]) -> Option<&mut Object> {
+        self.dict_index(path)
+            .map(move |index| &mut self.__objects[index])
+    }
+    /// Adds `obj` to the `Drawing` as the entry `key` of the dictionary at `path` and sets its owner
+    /// to that dictionary.  An existing entry with the same key is removed first as `dict_remove`
+    /// does.
+    pub fn dict_insert(&mut self, path: &[&str], key: &str, obj: Object) -> DxfResult<&Object> {
+        let not_found = || DxfError::DictionaryNotFound(path.join("/"));
+        let dict_handle = self
+            .dict_entries_index(path)
+            .map(|index| self.__objects[index].common.handle)
+            .ok_or_else(not_found)?;
+        self.insert_dictionary_entry(dict_handle, key, obj)
+            .ok_or_else(not_found)
+    }
+    /// Removes the entry `key` from the dictionary at `path` and returns the handle it referred to.
+    /// If the dictionary is a hard owner the referenced object is removed from the `Drawing` along
+    /// with everything it hard-owns; otherwise the object is kept and its owner is cleared.
+    pub fn dict_remove(&mut self, path: &[&str], key: &str) -> Option<Handle> {
+        let dict_handle = self
+            .dict_index(path)
+            .map(|index| self.__objects[index].common.handle)?;
+        self.remove_dictionary_entry(dict_handle, key)
+    }
+    /// Returns the extension dictionary of the entity or object with the specified handle.
+    pub fn extension_dictionary(&self, owner: Handle) -> Option<&Object> {
+        let handle = extension_dictionary_handle(self.extension_data_groups(owner).ok()?)?;
+        self.object_index_by_handle(handle)
+            .map(|index| &self.__objects[index])
+    }
+    /// Returns the extension dictionary of the entity or object with the specified handle, creating
+    /// it if it doesn't exist yet.
+    pub fn get_or_create_extension_dictionary(&mut self, owner: Handle) -> DxfResult<&Object> {
+        let existing = extension_dictionary_handle(self.extension_data_groups(owner)?)
+            .and_then(|handle| self.object_index_by_handle(handle));
+        if let Some(index) = existing {
+            return Ok(&self.__objects[index]);
+        }
+
+        let mut dict = Object::new(ObjectType::Dictionary(Dictionary {
+            is_hard_owner: true,
+            ..Default::default()
+        }));
+        dict.common.handle = self.next_handle();
+        dict.common.__owner_handle = owner;
+        add_reactor(&mut dict.common.extension_data_groups, owner);
+        set_extension_dictionary_handle(self.extension_data_groups_mut(owner)?, dict.common.handle);
+        Ok(self.add_object_no_handle_set(dict))
+    }
+    /// Returns the `XRecordObject` stored as `name` in the extension dictionary of the entity or
+    /// object with the specified handle.
+    pub fn xrecord(&self, owner: Handle, name: &str) -> Option<&XRecordObject> {
+        let handle = *dictionary_entries(self.extension_dictionary(owner)?)?.get(name)?;
+        match self.__objects[self.object_index_by_handle(handle)?].specific {
+            ObjectType::XRecordObject(ref xrecord) => Some(xrecord),
+            _ => None,
+        }
+    }
+    /// Stores `values` as the `XRecordObject` named `name` in the extension dictionary of the entity
+    /// or object with the specified handle.  The extension dictionary is created if needed and an
+    /// existing `XRecordObject` of the same name is updated in place.
+    pub fn set_xrecord(
+        &mut self,
+        owner: Handle,
+        name: &str,
+        values: &[XRecordValue],
+    ) -> DxfResult<&Object> {
+        let dict_handle = self
+            .get_or_create_extension_dictionary(owner)?
+            .common
+            .handle;
+        let existing = self
+            .extension_dictionary(owner)
+            .and_then(dictionary_entries)
+            .and_then(|entries| entries.get(name))
+            .and_then(|handle| self.object_index_by_handle(*handle));
+        if let Some(index) = existing {
+            if let ObjectType::XRecordObject(ref mut xrecord) = self.__objects[index].specific {
+                xrecord.set_values(values);
+                return Ok(&self.__objects[index]);
+            }
+        }
+
+        let mut xrecord = XRecordObject::default();
+        xrecord.set_values(values);
+        self.insert_dictionary_entry(
+            dict_handle,
+            name,
+            Object::new(ObjectType::XRecordObject(xrecord)),
+        )
+        .ok_or(DxfError::WrongItemType)
+    }
+    /// Removes the entry `name` from the extension dictionary of the entity or object with the
+    /// specified handle, along with the object it referred to, and returns that object's handle.
+    pub fn remove_xrecord(&mut self, owner: Handle, name: &str) -> Option<Handle> {
+        let dict_handle = self.extension_dictionary(owner)?.common.handle;
+        self.remove_dictionary_entry(dict_handle, name)
+    }
     /// Gets a `DrawingItem` with the appropriate handle or `None`.
     pub fn item_by_handle(&'_ self, handle: Handle) -> Option<DrawingItem<'_>> {
         for item in &self.__app_ids {
@@ -651,6 +1304,39 @@ impl Drawing {
 
 // private implementation
 impl Drawing {
+    fn down_convert_entities(entities: Vec<Entity>, converter: &mut DownConverter) -> Vec<Entity> {
+        entities
+            .into_iter()
+            .flat_map(|entity| converter.convert(entity))
+            .collect()
+    }
+    fn explode_proxies(&mut self, entities: Vec<Entity>, count: &mut usize) -> Vec<Entity> {
+        let mut result = vec![];
+        for entity in entities {
+            let exploded = match entity.explode_proxy(self) {
+                Some(exploded) => exploded,
+                None => {
+                    result.push(entity);
+                    continue;
+                }
+            };
+
+            *count += 1;
+            for mut e in exploded {
+                e.common.handle = self.next_handle();
+                if let EntityType::Polyline(ref mut poly) = e.specific {
+                    poly.__seqend_handle = self.next_handle();
+                    for v in poly.__vertices_and_handles.iter_mut() {
+                        if v.1 == AUTO_REPLACE_HANDLE {
+                            v.1 = self.next_handle();
+                        }
+                    }
+                }
+                result.push(e);
+            }
+        }
+        result
+    }
     pub(crate) fn next_handle(&mut self) -> Handle {
         let result = self.header.next_available_handle;
         self.header.next_available_handle = self.header.next_available_handle.next_handle_value();
@@ -671,16 +1357,19 @@ impl Drawing {
         self.__blocks.push(block);
         self.__blocks.last().unwrap()
     }
-    fn add_entity_no_handle_set(&mut self, entity: Entity) -> &Entity {
-        self.ensure_mline_style_is_present_for_entity(&entity);
-        self.ensure_dimension_style_is_present_for_entity(&entity);
-        self.ensure_layer_is_present(&entity.common.layer);
-        self.ensure_line_type_is_present(&entity.common.line_type_name);
-        self.ensure_text_style_is_present_for_entity(&entity);
+    pub(crate) fn add_entity_no_handle_set(&mut self, entity: Entity) -> &Entity {
+        self.ensure_entity_references_are_present(&entity);
         self.__entities.push(entity);
         self.__entities.last().unwrap()
     }
-    fn add_object_no_handle_set(&mut self, obj: Object) -> &Object {
+    fn ensure_entity_references_are_present(&mut self, entity: &Entity) {
+        self.ensure_mline_style_is_present_for_entity(entity);
+        self.ensure_dimension_style_is_present_for_entity(entity);
+        self.ensure_layer_is_present(&entity.common.layer);
+        self.ensure_line_type_is_present(&entity.common.line_type_name);
+        self.ensure_text_style_is_present_for_entity(entity);
+    }
+    pub(crate) fn add_object_no_handle_set(&mut self, obj: Object) -> &Object {
         self.ensure_layer_is_present_for_object(&obj);
         self.ensure_line_type_is_present_for_object(&obj);
         self.ensure_text_style_is_present_for_object(&obj);
@@ -893,6 +1582,144 @@ impl Drawing {
             }
         }
     }
+    fn object_index_by_handle(&self, handle: Handle) -> Option<usize> {
+        self.__objects
+            .iter()
+            .position(|o| o.common.handle == handle)
+    }
+    /// Returns the index of the named object dictionary, the root dictionary that isn't owned by
+    /// anything.
+    fn named_object_dictionary_index(&self) -> Option<usize> {
+        self.__objects.iter().position(|o| {
+            matches!(o.specific, ObjectType::Dictionary(_))
+                && o.common.__owner_handle == Handle::empty()
+        })
+    }
+    fn dict_index(&self, path: &[&str]) -> Option<usize> {
+        let mut index = self.named_object_dictionary_index()?;
+        for key in path {
+            let handle = *dictionary_entries(&self.__objects[index])?.get(*key)?;
+            index = self.object_index_by_handle(handle)?;
+        }
+        Some(index)
+    }
+    /// Returns the index of the object at `path` if it's a dictionary.
+    fn dict_entries_index(&self, path: &[&str]) -> Option<usize> {
+        self.dict_index(path)
+            .filter(|index| dictionary_entries(&self.__objects[*index]).is_some())
+    }
+    fn extension_data_groups(&self, handle: Handle) -> DxfResult<&Vec<ExtensionGroup>> {
+        match self.item_by_handle(handle) {
+            Some(DrawingItem::Entity(entity)) => Ok(&entity.common.extension_data_groups),
+            Some(DrawingItem::Object(obj)) => Ok(&obj.common.extension_data_groups),
+            Some(_) => Err(DxfError::WrongItemType),
+            None => Err(DxfError::HandleNotFound(handle)),
+        }
+    }
+    fn extension_data_groups_mut(&mut self, handle: Handle) -> DxfResult<&mut Vec<ExtensionGroup>> {
+        match self.item_by_handle_mut(handle) {
+            Some(DrawingItemMut::Entity(entity)) => Ok(&mut entity.common.extension_data_groups),
+            Some(DrawingItemMut::Object(obj)) => Ok(&mut obj.common.extension_data_groups),
+            Some(_) => Err(DxfError::WrongItemType),
+            None => Err(DxfError::HandleNotFound(handle)),
+        }
+    }
+    /// Adds `obj` as the entry `key` of the dictionary with handle `dict_handle`, replacing any
+    /// existing entry, and points its owner and reactors at the dictionary.
+    fn insert_dictionary_entry(
+        &mut self,
+        dict_handle: Handle,
+        key: &str,
+        mut obj: Object,
+    ) -> Option<&Object> {
+        self.remove_dictionary_entry(dict_handle, key);
+
+        // removing the old entry may have moved the dictionary
+        let index = self.object_index_by_handle(dict_handle)?;
+        obj.common.handle = self.next_handle();
+        obj.common.__owner_handle = dict_handle;
+        add_reactor(&mut obj.common.extension_data_groups, dict_handle);
+        dictionary_entries_mut(&mut self.__objects[index])?
+            .insert(String::from(key), obj.common.handle);
+        Some(self.add_object_no_handle_set(obj))
+    }
+    fn remove_dictionary_entry(&mut self, dict_handle: Handle, key: &str) -> Option<Handle> {
+        let index = self.object_index_by_handle(dict_handle)?;
+        let (handle, is_hard_owner) = match self.__objects[index].specific {
+            ObjectType::Dictionary(ref mut dict) => {
+                (dict.value_handles.remove(key)?, dict.is_hard_owner)
+            }
+            ObjectType::DictionaryWithDefault(ref mut dict) => {
+                (dict.value_handles.remove(key)?, false)
+            }
+            _ => return None,
+        };
+
+        if is_hard_owner {
+            self.remove_hard_owned_object(handle);
+        } else if let Some(index) = self.object_index_by_handle(handle) {
+            let common = &mut self.__objects[index].common;
+            if common.__owner_handle == dict_handle {
+                common.__owner_handle = Handle::empty();
+            }
+            remove_reactor(&mut common.extension_data_groups, dict_handle);
+        }
+
+        Some(handle)
+    }
+    fn remove_hard_owned_object(&mut self, handle: Handle) {
+        let Some(index) = self.object_index_by_handle(handle) else {
+            return;
+        };
+        let obj = self.__objects.remove(index);
+        if let Some(handle) = extension_dictionary_handle(&obj.common.extension_data_groups) {
+            self.remove_hard_owned_object(handle);
+        }
+        if let ObjectType::Dictionary(ref dict) = obj.specific {
+            if dict.is_hard_owner {
+                for handle in dict.value_handles.values() {
+                    self.remove_hard_owned_object(*handle);
+                }
+            }
+        }
+    }
+    /// Removes the extension dictionary owned by `owner` and the entries the dictionary owns.  Entries
+    /// owned by something else only lose their reactor to the dictionary.
+    fn remove_extension_dictionary(&mut self, owner: Handle, groups: &[ExtensionGroup]) {
+        let Some(handle) = extension_dictionary_handle(groups) else {
+            return;
+        };
+        let Some(index) = self.object_index_by_handle(handle) else {
+            return;
+        };
+        if self.__objects[index].common.__owner_handle != owner {
+            return;
+        }
+
+        let dict = self.__objects.remove(index);
+        for entry in dictionary_entries(&dict)
+            .into_iter()
+            .flat_map(|e| e.values())
+        {
+            match self.object_index_by_handle(*entry) {
+                Some(index) if self.__objects[index].common.__owner_handle == handle => {
+                    self.remove_hard_owned_object(*entry)
+                }
+                Some(index) => remove_reactor(
+                    &mut self.__objects[index].common.extension_data_groups,
+                    handle,
+                ),
+                None => (),
+            }
+        }
+    }
+    fn ensure_dictionary_is_present(&mut self, name: &str) {
+        if self.dict_get(&[name]).is_none() {
+            let dict = Object::new(ObjectType::Dictionary(Dictionary::default()));
+            // the named object dictionary is known to exist
+            let _ = self.dict_insert(&[], name, dict);
+        }
+    }
     fn ensure_view_port_is_present(&mut self, name: &str) {
         if !self.view_ports().any(|v| v.name == name) {
             self.add_view_port(ViewPort {
@@ -920,42 +1747,70 @@ impl Drawing {
         add_table_code_pairs(self, pairs, write_handles);
         pairs.push(CodePair::new_str(0, "ENDSEC"));
     }
-    pub(crate) fn add_blocks_pairs(&self, pairs: &mut Vec<CodePair>, write_handles: bool) {
+    fn add_blocks_pairs(
+        &self,
+        out: &mut CodePairOutput,
+        write_handles: bool,
+        converter: &mut DownConverter,
+    ) -> DxfResult<()> {
         if self.__blocks.is_empty() {
-            return;
+            return Ok(());
         }
 
-        pairs.push(CodePair::new_str(0, "SECTION"));
-        pairs.push(CodePair::new_str(2, "BLOCKS"));
+        out.pairs.push(CodePair::new_str(0, "SECTION"));
+        out.pairs.push(CodePair::new_str(2, "BLOCKS"));
         for b in &self.__blocks {
-            b.add_code_pairs(pairs, self.header.version, write_handles);
+            b.add_code_pairs(
+                &mut out.pairs,
+                self.header.version,
+                write_handles,
+                converter,
+            );
+            out.flush()?;
         }
 
-        pairs.push(CodePair::new_str(0, "ENDSEC"));
+        out.pairs.push(CodePair::new_str(0, "ENDSEC"));
+        Ok(())
     }
-    pub(crate) fn add_entities_pairs(&self, pairs: &mut Vec<CodePair>, write_handles: bool) {
-        pairs.push(CodePair::new_str(0, "SECTION"));
-        pairs.push(CodePair::new_str(2, "ENTITIES"));
+    fn add_entities_pairs(
+        &self,
+        out: &mut CodePairOutput,
+        write_handles: bool,
+        converter: &mut DownConverter,
+    ) -> DxfResult<()> {
+        out.pairs.push(CodePair::new_str(0, "SECTION"));
+        out.pairs.push(CodePair::new_str(2, "ENTITIES"));
         for e in &self.__entities {
-            e.add_code_pairs(pairs, self.header.version, write_handles);
+            converter.add_code_pairs(e, &mut out.pairs, write_handles);
+            out.flush()?;
         }
 
-        pairs.push(CodePair::new_str(0, "ENDSEC"));
+        out.pairs.push(CodePair::new_str(0, "ENDSEC"));
+        Ok(())
     }
-    pub(crate) fn add_objects_pairs(&self, pairs: &mut Vec<CodePair>) {
+    fn add_objects_pairs(&self, out: &mut CodePairOutput) -> DxfResult<()> {
         if self.header.version >= AcadVersion::R13 {
-            pairs.push(CodePair::new_str(0, "SECTION"));
-            pairs.push(CodePair::new_str(2, "OBJECTS"));
+            out.pairs.push(CodePair::new_str(0, "SECTION"));
+            out.pairs.push(CodePair::new_str(2, "OBJECTS"));
             for o in &self.__objects {
-                o.add_code_pairs(pairs, self.header.version);
+                o.add_code_pairs(&mut out.pairs, self.header.version);
+                out.flush()?;
             }
 
-            pairs.push(CodePair::new_str(0, "ENDSEC"));
+            out.pairs.push(CodePair::new_str(0, "ENDSEC"));
         }
+        Ok(())
     }
     pub(crate) fn add_thumbnail_pairs(&self, pairs: &mut Vec<CodePair>) -> DxfResult<()> {
         if self.header.version >= AcadVersion::R2000 {
-            if let Some(ref img) = self.thumbnail {
+            let regenerated = match self.regenerate_thumbnail {
+                // thumbnails don't have an alpha channel
+                Some(ref options) => Some(DynamicImage::ImageRgb8(
+                    DynamicImage::ImageRgba8(self.rasterize(options)?).to_rgb8(),
+                )),
+                None => None,
+            };
+            if let Some(img) = regenerated.as_ref().or(self.thumbnail.as_ref()) {
                 pairs.push(CodePair::new_str(0, "SECTION"));
                 pairs.push(CodePair::new_str(2, "THUMBNAILIMAGE"));
                 let mut data = vec![];
@@ -971,62 +1826,99 @@ impl Drawing {
         }
         Ok(())
     }
-    fn read_sections(drawing: &mut Drawing, iter: &mut CodePairPutBack) -> DxfResult<()> {
+    fn read_sections(
+        drawing: &mut Drawing,
+        iter: &mut CodePairPutBack,
+        thread_count: usize,
+    ) -> DxfResult<()> {
+        // comments between sections stay with the section they followed
+        fn take_comments(
+            drawing: &mut Drawing,
+            iter: &mut CodePairPutBack,
+            previous_section: &Option<String>,
+        ) {
+            let comments = iter.take_comments();
+            match previous_section {
+                _ if comments.is_empty() => (),
+                Some(name) => drawing
+                    .section_comments
+                    .entry(name.clone())
+                    .or_default()
+                    .extend(comments),
+                None => drawing.comments.extend(comments),
+            }
+        }
+
+        let mut previous_section = None;
         loop {
             match iter.next() {
-                Some(Ok(pair @ CodePair { code: 0, .. })) => match &*pair.assert_string()? {
+                Some(Ok(pair @ CodePair { code: 0, .. })) => match pair.as_str()? {
                     "EOF" => {
+                        // taken first, since putting the pair back also puts back its comments
+                        take_comments(drawing, iter, &previous_section);
                         iter.put_back(Ok(pair));
                         break;
                     }
-                    "SECTION" => match iter.next() {
-                        Some(Ok(CodePair {
-                            code: 2,
-                            value: CodePairValue::Str(s),
-                            ..
-                        })) => {
-                            match &*s {
-                                "HEADER" => drawing.header = Header::read(iter)?,
-                                "CLASSES" => Class::read_classes(drawing, iter)?,
-                                "TABLES" => {
-                                    drawing.read_section_item(iter, "TABLE", read_specific_table)?
-                                }
-                                "BLOCKS" => {
-                                    drawing.read_section_item(iter, "BLOCK", Block::read_block)?
+                    "SECTION" => {
+                        // comments before the first section belong to the file
+                        take_comments(drawing, iter, &previous_section);
+                        match iter.next() {
+                            Some(Ok(CodePair {
+                                code: 2,
+                                value: CodePairValue::Str(s),
+                                ..
+                            })) => {
+                                match &*s {
+                                    "HEADER" => drawing.header = Header::read(iter)?,
+                                    "CLASSES" => Class::read_classes(drawing, iter)?,
+                                    "TABLES" => drawing.read_section_item(
+                                        iter,
+                                        "TABLE",
+                                        read_specific_table,
+                                    )?,
+                                    "BLOCKS" => drawing.read_section_item(
+                                        iter,
+                                        "BLOCK",
+                                        Block::read_block,
+                                    )?,
+                                    "ENTITIES" => drawing.read_entities(iter, thread_count)?,
+                                    "OBJECTS" => drawing.read_objects(iter, thread_count)?,
+                                    "THUMBNAILIMAGE" => {
+                                        drawing.thumbnail = thumbnail::read_thumbnail(iter)?;
+                                    }
+                                    _ => Drawing::swallow_section(iter)?,
                                 }
-                                "ENTITIES" => drawing.read_entities(iter)?,
-                                "OBJECTS" => drawing.read_objects(iter)?,
-                                "THUMBNAILIMAGE" => {
-                                    drawing.thumbnail = thumbnail::read_thumbnail(iter)?;
-                                }
-                                _ => Drawing::swallow_section(iter)?,
-                            }
 
-                            match iter.next() {
-                                Some(Ok(CodePair {
-                                    code: 0,
-                                    value: CodePairValue::Str(ref s),
-                                    ..
-                                })) if s == "ENDSEC" => (),
-                                Some(Ok(pair)) => {
-                                    return Err(DxfError::UnexpectedCodePair(
-                                        pair,
-                                        String::from("expected 0/ENDSEC"),
-                                    ))
+                                match iter.next() {
+                                    Some(Ok(CodePair {
+                                        code: 0,
+                                        value: CodePairValue::Str(ref end),
+                                        ..
+                                    })) if end == "ENDSEC" => {
+                                        // anything not claimed by an item in the section
+                                        previous_section = Some(s.to_string());
+                                        take_comments(drawing, iter, &previous_section);
+                                    }
+                                    Some(Ok(pair)) => {
+                                        return Err(DxfError::UnexpectedCodePair(
+                                            pair,
+                                            String::from("expected 0/ENDSEC"),
+                                        ))
+                                    }
+                                    Some(Err(e)) => return Err(e),
+                                    None => return Err(DxfError::UnexpectedEndOfInput),
                                 }
-                                Some(Err(e)) => return Err(e),
-                                None => return Err(DxfError::UnexpectedEndOfInput),
                             }
+                            Some(Ok(pair)) => {
+                                return Err(DxfError::UnexpectedCodePair(
+                                    pair,
+                                    String::from("expected 2/<section-name>"),
+                                ))
+                            }
+                            Some(Err(e)) => return Err(e),
+                            None => return Err(DxfError::UnexpectedEndOfInput),
                         }
-                        Some(Ok(pair)) => {
-                            return Err(DxfError::UnexpectedCodePair(
-                                pair,
-                                String::from("expected 2/<section-name>"),
-                            ))
-                        }
-                        Some(Err(e)) => return Err(e),
-                        None => return Err(DxfError::UnexpectedEndOfInput),
-                    },
+                    }
                     _ => {
                         return Err(DxfError::UnexpectedCodePair(
                             pair,
@@ -1051,7 +1943,7 @@ impl Drawing {
         loop {
             match iter.next() {
                 Some(Ok(pair)) => {
-                    if pair.code == 0 && pair.assert_string()? == "ENDSEC" {
+                    if pair.code == 0 && pair.as_str()? == "ENDSEC" {
                         iter.put_back(Ok(pair));
                         break;
                     }
@@ -1063,22 +1955,53 @@ impl Drawing {
 
         Ok(())
     }
-    fn read_entities(&mut self, iter: &mut CodePairPutBack) -> DxfResult<()> {
-        let mut iter = EntityIter { iter };
+    fn read_entities(&mut self, iter: &mut CodePairPutBack, thread_count: usize) -> DxfResult<()> {
         let mut entities = vec![];
-        iter.read_entities_into_vec(&mut entities)?;
-        for e in entities {
+        match read_items_in_parallel(iter, thread_count, Entity::read) {
+            // grouping VERTEX/ATTRIB entities happens after the chunks are stitched back together
+            Some(chunks) => {
+                entities.reserve(chunks.iter().map(Vec::len).sum());
+                collect_entities(&mut chunks.into_iter().flatten(), &mut entities)?
+            }
+            None => EntityIter { iter }.read_entities_into_vec(&mut entities)?,
+        }
+        // drawings can have hundreds of layers, so layer and line type names are only looked up
+        // once instead of searching the tables for every entity
+        let mut present_layers = HashSet::new();
+        let mut present_line_types = HashSet::new();
+        // entities are large, so they're updated in place instead of being moved one at a time
+        for e in entities.iter_mut() {
             if e.common.handle.is_empty() {
-                self.add_entity(e);
-            } else {
-                self.add_entity_no_handle_set(e);
+                self.set_entity_handles(e);
+            }
+
+            // the same order as `ensure_entity_references_are_present`
+            self.ensure_mline_style_is_present_for_entity(e);
+            self.ensure_dimension_style_is_present_for_entity(e);
+            if !present_layers.contains(&e.common.layer) {
+                self.ensure_layer_is_present(&e.common.layer);
+                present_layers.insert(e.common.layer.clone());
             }
+            if !present_line_types.contains(&e.common.line_type_name) {
+                self.ensure_line_type_is_present(&e.common.line_type_name);
+                present_line_types.insert(e.common.line_type_name.clone());
+            }
+            self.ensure_text_style_is_present_for_entity(e);
+        }
+        if self.__entities.is_empty() {
+            self.__entities = entities;
+        } else {
+            self.__entities.append(&mut entities);
         }
         Ok(())
     }
-    fn read_objects(&mut self, iter: &mut CodePairPutBack) -> DxfResult<()> {
-        let iter = put_back(ObjectIter { iter });
-        for o in iter {
+    fn read_objects(&mut self, iter: &mut CodePairPutBack, thread_count: usize) -> DxfResult<()> {
+        let objects = match read_items_in_parallel(iter, thread_count, Object::read) {
+            Some(chunks) => chunks.into_iter().flatten().collect::<Vec<_>>(),
+            None => ObjectIter { iter }.collect::<Vec<_>>(),
+        };
+        self.__objects.reserve(objects.len());
+        for o in objects {
             if o.common.handle.is_empty() {
                 self.add_object(o);
             } else {
@@ -1101,7 +2024,7 @@ impl Drawing {
             match iter.next() {
                 Some(Ok(pair)) => {
                     if pair.code == 0 {
-                        match &*pair.assert_string()? {
+                        match pair.as_str()? {
                             "ENDSEC" => {
                                 iter.put_back(Ok(pair));
                                 break;
@@ -1130,7 +2053,7 @@ impl Drawing {
             match iter.next() {
                 Some(Ok(pair)) => {
                     if pair.code == 0 {
-                        match &*pair.assert_string()? {
+                        match pair.as_str()? {
                             "TABLE" | "ENDSEC" | "ENDTAB" => {
                                 iter.put_back(Ok(pair));
                                 break;
@@ -1199,6 +2122,32 @@ impl Drawing {
         self.ensure_dimension_style_is_present("STANDARD");
         self.ensure_dimension_style_is_present("ANNOTATIVE");
     }
+    fn ensure_dictionaries(&mut self) {
+        // the named object dictionary and the standard dictionaries for the version
+        if self.header.version < AcadVersion::R13 {
+            return;
+        }
+        if self.named_object_dictionary_index().is_none() {
+            let mut nod = Object::new(ObjectType::Dictionary(Dictionary::default()));
+            nod.common.handle = self.next_handle();
+            self.__objects.insert(0, nod);
+        }
+
+        self.ensure_dictionary_is_present("ACAD_GROUP");
+        self.ensure_dictionary_is_present("ACAD_MLINESTYLE");
+        if self.header.version >= AcadVersion::R2000 {
+            self.ensure_dictionary_is_present("ACAD_LAYOUT");
+            self.ensure_dictionary_is_present("ACAD_PLOTSETTINGS");
+        }
+        if self.header.version >= AcadVersion::R2004 {
+            self.ensure_dictionary_is_present("ACAD_TABLESTYLE");
+        }
+        if self.header.version >= AcadVersion::R2007 {
+            self.ensure_dictionary_is_present("ACAD_MATERIAL");
+            self.ensure_dictionary_is_present("ACAD_MLEADERSTYLE");
+            self.ensure_dictionary_is_present("ACAD_VISUALSTYLE");
+        }
+    }
     fn ensure_layers(&mut self) {
         // ensure all layers that should exist do
         self.ensure_layer_is_present("0");
@@ -1236,6 +2185,110 @@ impl Drawing {
     }
 }
 
+fn dictionary_entries(obj: &Object) -> Option<&HashMap<String, Handle>> {
+    match obj.specific {
+        ObjectType::Dictionary(ref dict) => Some(&dict.value_handles),
+        ObjectType::DictionaryWithDefault(ref dict) => Some(&dict.value_handles),
+        _ => None,
+    }
+}
+
+fn dictionary_entries_mut(obj: &mut Object) -> Option<&mut HashMap<String, Handle>> {
+    match obj.specific {
+        ObjectType::Dictionary(ref mut dict) => Some(&mut dict.value_handles),
+        ObjectType::DictionaryWithDefault(ref mut dict) => Some(&mut dict.value_handles),
+        _ => None,
+    }
+}
+
+/// Collects the pairs of the item being written and passes them on once it's complete.  The
+/// comments that followed each section when it was read are placed before that section's
+/// `ENDSEC`, and comments of sections that weren't written are placed after all sections.
+struct CodePairOutput<'a> {
+    pairs: Vec<CodePair>,
+    write: &'a mut dyn FnMut(CodePair) -> DxfResult<()>,
+    section_comments: &'a HashMap<String, Vec<String>>,
+    strip_comments: bool,
+    section: Option<String>,
+    is_section_start: bool,
+    written_sections: HashSet<String>,
+}
+
+impl<'a> CodePairOutput<'a> {
+    fn new(
+        section_comments: &'a HashMap<String, Vec<String>>,
+        strip_comments: bool,
+        write: &'a mut dyn FnMut(CodePair) -> DxfResult<()>,
+    ) -> Self {
+        CodePairOutput {
+            pairs: vec![],
+            write,
+            section_comments,
+            strip_comments,
+            section: None,
+            is_section_start: false,
+            written_sections: HashSet::new(),
+        }
+    }
+    fn flush(&mut self) -> DxfResult<()> {
+        let mut pairs = std::mem::take(&mut self.pairs);
+        for pair in pairs.drain(..) {
+            if !self.section_comments.is_empty() {
+                self.track_section(&pair)?;
+            }
+            self.write(pair)?;
+        }
+
+        // keep the allocation for the next item
+        self.pairs = pairs;
+        Ok(())
+    }
+    fn finish(mut self) -> DxfResult<()> {
+        self.flush()?;
+        let mut unwritten = self
+            .section_comments
+            .keys()
+            .filter(|name| !self.written_sections.contains(*name))
+            .collect::<Vec<_>>();
+        unwritten.sort();
+        for name in unwritten {
+            self.write_section_comments(name)?;
+        }
+
+        self.write(CodePair::new_str(0, "EOF"))
+    }
+    fn track_section(&mut self, pair: &CodePair) -> DxfResult<()> {
+        match (pair.code, &pair.value) {
+            (0, CodePairValue::Str(s)) if s == "ENDSEC" => {
+                if let Some(name) = self.section.take() {
+                    self.write_section_comments(&name)?;
+                    self.written_sections.insert(name);
+                }
+            }
+            (2, CodePairValue::Str(s)) if self.is_section_start => {
+                self.section = Some(s.to_string())
+            }
+            _ => (),
+        }
+        self.is_section_start =
+            matches!((pair.code, &pair.value), (0, CodePairValue::Str(s)) if s == "SECTION");
+        Ok(())
+    }
+    fn write_section_comments(&mut self, name: &str) -> DxfResult<()> {
+        let section_comments = self.section_comments;
+        for comment in section_comments.get(name).into_iter().flatten() {
+            self.write(CodePair::new_string(COMMENT_CODE, comment))?;
+        }
+        Ok(())
+    }
+    fn write(&mut self, pair: CodePair) -> DxfResult<()> {
+        if self.strip_comments && pair.code == COMMENT_CODE {
+            return Ok(());
+        }
+        (self.write)(pair)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use crate::entities::*;
@@ -2096,4 +3149,380 @@ mod tests {
         let views = drawing.views().filter(|&v| v.name == "some-view");
         assert_eq!(1, views.count());
     }
+
+    fn r2000_drawing() -> Drawing {
+        let mut drawing = Drawing::new();
+        drawing.header.version = AcadVersion::R2000;
+        drawing.normalize();
+        drawing
+    }
+
+    fn new_dictionary(is_hard_owner: bool) -> Object {
+        Object::new(ObjectType::Dictionary(Dictionary {
+            is_hard_owner,
+            ..Default::default()
+        }))
+    }
+
+    #[test]
+    fn standard_dictionaries_are_created_on_normalize() {
+        assert!(Drawing::new().named_object_dictionary().is_none());
+
+        let mut drawing = r2000_drawing();
+        let nod = drawing.named_object_dictionary().unwrap();
+        let nod_handle = nod.common.handle;
+        assert_eq!(nod_handle, drawing.objects().next().unwrap().common.handle);
+        for name in [
+            "ACAD_GROUP",
+            "ACAD_LAYOUT",
+            "ACAD_MLINESTYLE",
+            "ACAD_PLOTSETTINGS",
+        ] {
+            let dict = drawing.dict_get(&[name]).unwrap();
+            assert!(matches!(dict.specific, ObjectType::Dictionary(_)));
+            assert_eq!(nod_handle, dict.common.__owner_handle);
+        }
+        assert!(drawing.dict_get(&["ACAD_MATERIAL"]).is_none());
+
+        // normalizing again doesn't add anything
+        let object_count = drawing.objects().count();
+        drawing.normalize();
+        assert_eq!(object_count, drawing.objects().count());
+    }
+
+    #[test]
+    fn extension_dictionary_is_not_taken_for_the_named_object_dictionary() {
+        let mut drawing = Drawing::new();
+        drawing.header.version = AcadVersion::R2000;
+        let line = drawing
+            .add_entity(Entity::new(EntityType::Line(Default::default())))
+            .common
+            .handle;
+        let ext_handle = drawing
+            .get_or_create_extension_dictionary(line)
+            .unwrap()
+            .common
+            .handle;
+        drawing.normalize();
+
+        let nod = drawing.named_object_dictionary().unwrap();
+        assert_ne!(ext_handle, nod.common.handle);
+        assert_eq!(Handle::empty(), nod.common.__owner_handle);
+        assert!(drawing.dict_get(&["ACAD_GROUP"]).is_some());
+        match drawing.extension_dictionary(line).unwrap().specific {
+            ObjectType::Dictionary(ref dict) => assert!(dict.value_handles.is_empty()),
+            _ => panic!("expected a dictionary"),
+        }
+    }
+
+    #[test]
+    fn get_and_insert_dictionary_entries_by_path() {
+        let mut drawing = r2000_drawing();
+        let layout = Object::new(ObjectType::Layout(Layout {
+            layout_name: String::from("Layout1"),
+            ..Default::default()
+        }));
+        let layout_handle = drawing
+            .dict_insert(&["ACAD_LAYOUT"], "Layout1", layout)
+            .unwrap()
+            .common
+            .handle;
+
+        let layouts_handle = drawing.dict_get(&["ACAD_LAYOUT"]).unwrap().common.handle;
+        let layout = drawing.dict_get(&["ACAD_LAYOUT", "Layout1"]).unwrap();
+        assert_eq!(layout_handle, layout.common.handle);
+        assert_eq!(layouts_handle, layout.common.__owner_handle);
+        match layout.specific {
+            ObjectType::Layout(ref layout) => assert_eq!("Layout1", layout.layout_name),
+            _ => panic!("expected a layout"),
+        }
+
+        if let Some(Object {
+            specific: ObjectType::Layout(ref mut layout),
+            ..
+        }) = drawing.dict_get_mut(&["ACAD_LAYOUT", "Layout1"])
+        {
+            layout.layout_name = String::from("renamed");
+        }
+        match drawing.item_by_handle(layout_handle) {
+            Some(DrawingItem::Object(Object {
+                specific: ObjectType::Layout(ref layout),
+                ..
+            })) => assert_eq!("renamed", layout.layout_name),
+            _ => panic!("expected a layout"),
+        }
+
+        assert!(drawing.dict_get(&["ACAD_LAYOUT", "missing"]).is_none());
+        assert!(drawing.dict_get(&["ACAD_LAYOUT", "Layout1", "x"]).is_none());
+        match drawing.dict_insert(&["missing", "path"], "key", new_dictionary(false)) {
+            Err(DxfError::DictionaryNotFound(path)) => assert_eq!("missing/path", path),
+            _ => panic!("expected a missing dictionary"),
+        }
+    }
+
+    #[test]
+    fn remove_soft_owned_dictionary_entry() {
+        let mut drawing = r2000_drawing();
+        drawing
+            .dict_insert(&[], "MY_DICT", new_dictionary(false))
+            .unwrap();
+        let handle = drawing
+            .dict_insert(&["MY_DICT"], "entry", new_dictionary(false))
+            .unwrap()
+            .common
+            .handle;
+
+        assert_eq!(Some(handle), drawing.dict_remove(&["MY_DICT"], "entry"));
+        assert!(drawing.dict_get(&["MY_DICT", "entry"]).is_none());
+        match drawing.item_by_handle(handle) {
+            Some(DrawingItem::Object(obj)) => {
+                assert_eq!(Handle::empty(), obj.common.__owner_handle)
+            }
+            _ => panic!("expected the object to remain"),
+        }
+        assert_eq!(None, drawing.dict_remove(&["MY_DICT"], "entry"));
+    }
+
+    #[test]
+    fn remove_hard_owned_dictionary_entry() {
+        let mut drawing = r2000_drawing();
+        drawing
+            .dict_insert(&[], "MY_DICT", new_dictionary(true))
+            .unwrap();
+        let child = drawing
+            .dict_insert(&["MY_DICT"], "child", new_dictionary(true))
+            .unwrap()
+            .common
+            .handle;
+        let grandchild = drawing
+            .dict_insert(&["MY_DICT", "child"], "grandchild", new_dictionary(false))
+            .unwrap()
+            .common
+            .handle;
+
+        // replacing an entry removes the hard-owned object it referred to
+        let replaced = drawing
+            .dict_insert(&["MY_DICT"], "child", new_dictionary(false))
+            .unwrap()
+            .common
+            .handle;
+        assert_ne!(child, replaced);
+        assert!(drawing.item_by_handle(child).is_none());
+        assert!(drawing.item_by_handle(grandchild).is_none());
+
+        assert_eq!(Some(replaced), drawing.dict_remove(&["MY_DICT"], "child"));
+        assert!(drawing.item_by_handle(replaced).is_none());
+    }
+
+    #[test]
+    fn dictionary_entries_round_trip() {
+        let mut drawing = r2000_drawing();
+        drawing
+            .dict_insert(&[], "MY_DICT", new_dictionary(true))
+            .unwrap();
+        let handle = drawing
+            .dict_insert(&["MY_DICT"], "entry", new_dictionary(false))
+            .unwrap()
+            .common
+            .handle;
+        assert_contains_pairs(
+            &drawing,
+            vec![
+                CodePair::new_str(3, "entry"),
+                CodePair::new_string(360, handle.as_string()),
+            ],
+        );
+
+        let mut buf = vec![];
+        drawing.save(&mut buf).unwrap();
+        let drawing = Drawing::load(&mut buf.as_slice()).unwrap();
+        let entry = drawing.dict_get(&["MY_DICT", "entry"]).unwrap();
+        let owner = drawing.dict_get(&["MY_DICT"]).unwrap();
+        assert_eq!(owner.common.handle, entry.common.__owner_handle);
+        assert!(drawing.dict_get(&["ACAD_GROUP"]).is_some());
+    }
+
+    fn drawing_with_line() -> (Drawing, Handle) {
+        let mut drawing = r2000_drawing();
+        let handle = drawing
+            .add_entity(Entity::new(EntityType::Line(Line::default())))
+            .common
+            .handle;
+        (drawing, handle)
+    }
+
+    fn reactors(obj: &Object) -> Vec<Handle> {
+        crate::extension_data::reactor_handles(&obj.common.extension_data_groups)
+    }
+
+    #[test]
+    fn dictionary_entries_point_back_at_their_dictionary() {
+        let mut drawing = r2000_drawing();
+        let nod_handle = drawing.named_object_dictionary().unwrap().common.handle;
+        let groups = drawing.dict_get(&["ACAD_GROUP"]).unwrap();
+        assert_eq!(vec![nod_handle], reactors(groups));
+
+        drawing
+            .dict_insert(&[], "MY_DICT", new_dictionary(false))
+            .unwrap();
+        let entry = drawing
+            .dict_insert(&["MY_DICT"], "entry", new_dictionary(false))
+            .unwrap()
+            .common
+            .handle;
+        drawing.dict_remove(&["MY_DICT"], "entry");
+        match drawing.item_by_handle(entry) {
+            Some(DrawingItem::Object(obj)) => assert!(reactors(obj).is_empty()),
+            _ => panic!("expected the object to remain"),
+        }
+    }
+
+    #[test]
+    fn create_extension_dictionary() {
+        let (mut drawing, line) = drawing_with_line();
+        assert!(drawing.extension_dictionary(line).is_none());
+
+        let dict = drawing.get_or_create_extension_dictionary(line).unwrap();
+        let dict_handle = dict.common.handle;
+        assert_eq!(line, dict.common.__owner_handle);
+        assert_eq!(vec![line], reactors(dict));
+        match dict.specific {
+            ObjectType::Dictionary(ref dict) => assert!(dict.is_hard_owner),
+            _ => panic!("expected a dictionary"),
+        }
+
+        // the same dictionary is returned from now on
+        assert_eq!(
+            dict_handle,
+            drawing
+                .get_or_create_extension_dictionary(line)
+                .unwrap()
+                .common
+                .handle
+        );
+        assert_eq!(
+            dict_handle,
+            drawing.extension_dictionary(line).unwrap().common.handle
+        );
+        assert_contains_pairs(
+            &drawing,
+            vec![
+                CodePair::new_str(102, "{ACAD_XDICTIONARY"),
+                CodePair::new_string(360, dict_handle.as_string()),
+                CodePair::new_str(102, "}"),
+            ],
+        );
+    }
+
+    #[test]
+    fn extension_dictionary_of_missing_or_wrong_item() {
+        let mut drawing = r2000_drawing();
+        match drawing.get_or_create_extension_dictionary(Handle(0xFFFF)) {
+            Err(DxfError::HandleNotFound(handle)) => assert_eq!(Handle(0xFFFF), handle),
+            _ => panic!("expected a missing handle"),
+        }
+
+        let layer = drawing.layers().next().unwrap().handle;
+        assert!(matches!(
+            drawing.set_xrecord(layer, "data", &[]),
+            Err(DxfError::WrongItemType)
+        ));
+    }
+
+    #[test]
+    fn set_and_read_xrecords() {
+        let (mut drawing, line) = drawing_with_line();
+        let values = vec![
+            XRecordValue::Str(String::from("pump")),
+            XRecordValue::Integer(3),
+            XRecordValue::Real(2.5),
+            XRecordValue::Point(Point::new(1.0, 2.0, 3.0)),
+            XRecordValue::Handle(line),
+        ];
+        let xrecord_handle = drawing
+            .set_xrecord(line, "ENGINEERING", &values)
+            .unwrap()
+            .common
+            .handle;
+        let dict_handle = drawing.extension_dictionary(line).unwrap().common.handle;
+        match drawing.item_by_handle(xrecord_handle) {
+            Some(DrawingItem::Object(xrecord)) => {
+                assert_eq!(dict_handle, xrecord.common.__owner_handle);
+                assert_eq!(vec![dict_handle], reactors(xrecord));
+            }
+            _ => panic!("expected an xrecord"),
+        }
+
+        // updating keeps the same object
+        let updated = vec![XRecordValue::Str(String::from("valve"))];
+        let xrecord = drawing.set_xrecord(line, "ENGINEERING", &updated).unwrap();
+        assert_eq!(xrecord_handle, xrecord.common.handle);
+        drawing.set_xrecord(line, "OTHER", &values).unwrap();
+
+        let mut buf = vec![];
+        drawing.save(&mut buf).unwrap();
+        let mut drawing = Drawing::load(&mut buf.as_slice()).unwrap();
+        assert_eq!(
+            updated,
+            drawing.xrecord(line, "ENGINEERING").unwrap().values()
+        );
+        assert_eq!(values, drawing.xrecord(line, "OTHER").unwrap().values());
+        assert!(drawing.xrecord(line, "missing").is_none());
+
+        // the extension dictionary hard-owns its entries
+        assert_eq!(
+            Some(xrecord_handle),
+            drawing.remove_xrecord(line, "ENGINEERING")
+        );
+        assert!(drawing.item_by_handle(xrecord_handle).is_none());
+        assert!(drawing.xrecord(line, "ENGINEERING").is_none());
+        assert!(drawing.xrecord(line, "OTHER").is_some());
+    }
+
+    #[test]
+    fn extension_dictionary_is_removed_with_its_owner() {
+        let mut drawing = r2000_drawing();
+        drawing
+            .dict_insert(&[], "MY_DICT", new_dictionary(true))
+            .unwrap();
+        let entry = drawing
+            .dict_insert(&["MY_DICT"], "entry", new_dictionary(false))
+            .unwrap()
+            .common
+            .handle;
+        let xrecord = drawing
+            .set_xrecord(entry, "data", &[XRecordValue::Integer(1)])
+            .unwrap()
+            .common
+            .handle;
+        let dict = drawing.extension_dictionary(entry).unwrap().common.handle;
+
+        drawing.dict_remove(&["MY_DICT"], "entry");
+        assert!(drawing.item_by_handle(dict).is_none());
+        assert!(drawing.item_by_handle(xrecord).is_none());
+    }
+
+    #[test]
+    fn extension_dictionary_is_removed_with_its_entity() {
+        let (mut drawing, line) = drawing_with_line();
+        let xrecord = drawing
+            .set_xrecord(line, "data", &[XRecordValue::Integer(1)])
+            .unwrap()
+            .common
+            .handle;
+        let dict = drawing.extension_dictionary(line).unwrap().common.handle;
+        let object_count = drawing.objects().count();
+
+        let index = drawing
+            .entities()
+            .position(|e| e.common.handle == line)
+            .unwrap();
+        assert_eq!(line, drawing.remove_entity(index).unwrap().common.handle);
+        assert!(drawing.item_by_handle(dict).is_none());
+        assert!(drawing.item_by_handle(xrecord).is_none());
+        assert_eq!(object_count - 2, drawing.objects().count());
+        assert!(drawing
+            .objects()
+            .all(|o| o.common.__owner_handle != line && !reactors(o).contains(&line)));
+    }
 }
@@ -0,0 +1,546 @@
+// bit-level reader for the data types used by DWG object streams
+
+use encoding_rs::Encoding;
+
+use crate::enums::AcadVersion;
+use crate::{DxfError, DxfResult, Point, Vector};
+
+/// A handle reference as stored in a DWG handle stream.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct DwgHandleRef {
+    pub code: u8,
+    pub value: u64,
+}
+
+impl DwgHandleRef {
+    /// Resolves the reference to an absolute handle value; codes 6, 8, 10 and 12 are offsets from
+    /// the handle of the object that contains the reference.
+    pub fn absolute(&self, reference: u64) -> u64 {
+        match self.code {
+            0x6 => reference.wrapping_add(1),
+            0x8 => reference.wrapping_sub(1),
+            0xA => reference.wrapping_add(self.value),
+            0xC => reference.wrapping_sub(self.value),
+            _ => self.value,
+        }
+    }
+}
+
+/// An entity color as stored with R2004 and later; `flags` is the high byte of the stored value and
+/// indicates which of the other values were present.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub(crate) struct DwgEntityColor {
+    pub index: i16,
+    pub flags: u16,
+    pub rgb: u32,
+    pub transparency: i32,
+}
+
+impl DwgEntityColor {
+    pub fn has_rgb(&self) -> bool {
+        self.flags & 0x80 != 0
+    }
+    pub fn has_color_book_handle(&self) -> bool {
+        self.flags & 0x40 != 0
+    }
+    pub fn has_transparency(&self) -> bool {
+        self.flags & 0x20 != 0
+    }
+}
+
+/// Reads values from a DWG bit stream.  Bits are consumed most significant first and multi-byte raw
+/// values are little endian.
+#[derive(Clone)]
+pub(crate) struct DwgBitReader<'a> {
+    data: &'a [u8],
+    position: usize,
+    pub version: AcadVersion,
+}
+
+impl<'a> DwgBitReader<'a> {
+    pub fn new(data: &'a [u8], version: AcadVersion) -> Self {
+        DwgBitReader {
+            data,
+            position: 0,
+            version,
+        }
+    }
+    /// The current position, in bits.
+    pub fn position(&self) -> usize {
+        self.position
+    }
+    pub fn set_position(&mut self, position: usize) {
+        self.position = position;
+    }
+    /// The length of the underlying data, in bits.
+    pub fn bit_length(&self) -> usize {
+        self.data.len() * 8
+    }
+    pub fn read_bits(&mut self, count: usize) -> DxfResult<u64> {
+        if self.position + count > self.bit_length() {
+            return Err(DxfError::UnexpectedEndOfInput);
+        }
+
+        let mut result = 0u64;
+        for _ in 0..count {
+            let byte = self.data[self.position / 8];
+            let bit = (byte >> (7 - (self.position % 8))) & 0x01;
+            result = (result << 1) | u64::from(bit);
+            self.position += 1;
+        }
+
+        Ok(result)
+    }
+    pub fn read_bytes(&mut self, count: usize) -> DxfResult<Vec<u8>> {
+        if self.position.is_multiple_of(8) {
+            let start = self.position / 8;
+            if count > self.data.len().saturating_sub(start) {
+                return Err(DxfError::UnexpectedEndOfInput);
+            }
+
+            self.position += count * 8;
+            return Ok(self.data[start..start + count].to_vec());
+        }
+
+        if count > self.bit_length().saturating_sub(self.position) / 8 {
+            return Err(DxfError::UnexpectedEndOfInput);
+        }
+
+        let mut result = Vec::with_capacity(count);
+        for _ in 0..count {
+            result.push(self.read_rc()?);
+        }
+
+        Ok(result)
+    }
+    /// B: a single bit.
+    pub fn read_b(&mut self) -> DxfResult<bool> {
+        Ok(self.read_bits(1)? == 1)
+    }
+    /// BB: two bits.
+    pub fn read_bb(&mut self) -> DxfResult<u8> {
+        Ok(self.read_bits(2)? as u8)
+    }
+    /// RC: a raw byte.
+    pub fn read_rc(&mut self) -> DxfResult<u8> {
+        Ok(self.read_bits(8)? as u8)
+    }
+    /// RS: a raw 16-bit value.
+    pub fn read_rs(&mut self) -> DxfResult<u16> {
+        let lo = u16::from(self.read_rc()?);
+        let hi = u16::from(self.read_rc()?);
+        Ok(lo | (hi << 8))
+    }
+    /// RL: a raw 32-bit value.
+    pub fn read_rl(&mut self) -> DxfResult<u32> {
+        let lo = u32::from(self.read_rs()?);
+        let hi = u32::from(self.read_rs()?);
+        Ok(lo | (hi << 16))
+    }
+    /// RD: a raw double.
+    pub fn read_rd(&mut self) -> DxfResult<f64> {
+        let mut bytes = [0u8; 8];
+        for b in bytes.iter_mut() {
+            *b = self.read_rc()?;
+        }
+
+        Ok(f64::from_le_bytes(bytes))
+    }
+    /// BS: a bit-coded short.
+    pub fn read_bs(&mut self) -> DxfResult<i16> {
+        match self.read_bb()? {
+            0 => Ok(self.read_rs()? as i16),
+            1 => Ok(i16::from(self.read_rc()?)),
+            2 => Ok(0),
+            _ => Ok(256),
+        }
+    }
+    /// BL: a bit-coded long.
+    pub fn read_bl(&mut self) -> DxfResult<i32> {
+        match self.read_bb()? {
+            0 => Ok(self.read_rl()? as i32),
+            1 => Ok(i32::from(self.read_rc()?)),
+            2 => Ok(0),
+            _ => Err(DxfError::InvalidBinaryFile),
+        }
+    }
+    /// BLL: a bit-coded long long; a 3-bit byte count followed by the bytes.
+    pub fn read_bll(&mut self) -> DxfResult<u64> {
+        let count = self.read_bits(3)?;
+        let mut result = 0u64;
+        for i in 0..count {
+            result |= u64::from(self.read_rc()?) << (i * 8);
+        }
+
+        Ok(result)
+    }
+    /// BD: a bit-coded double.
+    pub fn read_bd(&mut self) -> DxfResult<f64> {
+        match self.read_bb()? {
+            0 => self.read_rd(),
+            1 => Ok(1.0),
+            2 => Ok(0.0),
+            _ => Err(DxfError::InvalidBinaryFile),
+        }
+    }
+    /// DD: a double that patches the bytes of a default value.
+    pub fn read_dd(&mut self, default: f64) -> DxfResult<f64> {
+        let mut bytes = default.to_le_bytes();
+        match self.read_bb()? {
+            0 => (),
+            1 => {
+                for b in bytes.iter_mut().take(4) {
+                    *b = self.read_rc()?;
+                }
+            }
+            2 => {
+                bytes[4] = self.read_rc()?;
+                bytes[5] = self.read_rc()?;
+                for b in bytes.iter_mut().take(4) {
+                    *b = self.read_rc()?;
+                }
+            }
+            _ => return self.read_rd(),
+        }
+
+        Ok(f64::from_le_bytes(bytes))
+    }
+    /// 2RD: two raw doubles.
+    pub fn read_2rd(&mut self) -> DxfResult<Point> {
+        let x = self.read_rd()?;
+        let y = self.read_rd()?;
+        Ok(Point::new(x, y, 0.0))
+    }
+    /// 2DD: two doubles with default values.
+    pub fn read_2dd(&mut self, default: &Point) -> DxfResult<Point> {
+        let x = self.read_dd(default.x)?;
+        let y = self.read_dd(default.y)?;
+        Ok(Point::new(x, y, 0.0))
+    }
+    /// 3BD: three bit-coded doubles.
+    pub fn read_3bd(&mut self) -> DxfResult<Point> {
+        let x = self.read_bd()?;
+        let y = self.read_bd()?;
+        let z = self.read_bd()?;
+        Ok(Point::new(x, y, z))
+    }
+    pub fn read_3bd_vector(&mut self) -> DxfResult<Vector> {
+        let p = self.read_3bd()?;
+        Ok(Vector::new(p.x, p.y, p.z))
+    }
+    /// BE: an extrusion direction where a single set bit means the Z axis.
+    pub fn read_be(&mut self) -> DxfResult<Vector> {
+        if self.read_b()? {
+            Ok(Vector::z_axis())
+        } else {
+            self.read_3bd_vector()
+        }
+    }
+    /// BT: a thickness where a single set bit means zero.
+    pub fn read_bt(&mut self) -> DxfResult<f64> {
+        if self.read_b()? {
+            Ok(0.0)
+        } else {
+            self.read_bd()
+        }
+    }
+    /// MC: a signed modular char.
+    pub fn read_mc(&mut self) -> DxfResult<i64> {
+        let mut result = 0i64;
+        let mut shift = 0;
+        loop {
+            let b = self.read_rc()?;
+            if b & 0x80 != 0 {
+                result |= i64::from(b & 0x7F) << shift;
+                shift += 7;
+            } else {
+                result |= i64::from(b & 0x3F) << shift;
+                return Ok(if b & 0x40 != 0 { -result } else { result });
+            }
+            if shift > 56 {
+                return Err(DxfError::InvalidBinaryFile);
+            }
+        }
+    }
+    /// UMC: an unsigned modular char.
+    pub fn read_umc(&mut self) -> DxfResult<u64> {
+        let mut result = 0u64;
+        let mut shift = 0;
+        loop {
+            let b = self.read_rc()?;
+            result |= u64::from(b & 0x7F) << shift;
+            if b & 0x80 == 0 {
+                return Ok(result);
+            }
+            shift += 7;
+            if shift > 56 {
+                return Err(DxfError::InvalidBinaryFile);
+            }
+        }
+    }
+    /// MS: a modular short.
+    pub fn read_ms(&mut self) -> DxfResult<u64> {
+        let mut result = 0u64;
+        let mut shift = 0;
+        loop {
+            let word = self.read_rs()?;
+            result |= u64::from(word & 0x7FFF) << shift;
+            if word & 0x8000 == 0 {
+                return Ok(result);
+            }
+            shift += 15;
+            if shift > 45 {
+                return Err(DxfError::InvalidBinaryFile);
+            }
+        }
+    }
+    /// H: a handle reference.
+    pub fn read_h(&mut self) -> DxfResult<DwgHandleRef> {
+        let code = self.read_bits(4)? as u8;
+        let counter = self.read_bits(4)?;
+        let mut value = 0u64;
+        for _ in 0..counter {
+            value = (value << 8) | u64::from(self.read_rc()?);
+        }
+
+        Ok(DwgHandleRef { code, value })
+    }
+    /// OT: an object type.
+    pub fn read_ot(&mut self) -> DxfResult<u16> {
+        if self.version >= AcadVersion::R2010 {
+            match self.read_bb()? {
+                0 => Ok(u16::from(self.read_rc()?)),
+                1 => Ok(u16::from(self.read_rc()?) + 0x1F0),
+                _ => self.read_rs(),
+            }
+        } else {
+            Ok(self.read_bs()? as u16)
+        }
+    }
+    /// TV: a string of 8-bit characters in the drawing's code page.
+    pub fn read_tv(&mut self, encoding: &'static Encoding) -> DxfResult<String> {
+        let length = self.read_bs()? as u16 as usize;
+        let bytes = self.read_bytes(length)?;
+        let end = bytes.iter().position(|&b| b == 0).unwrap_or(bytes.len());
+        let (text, _, _) = encoding.decode(&bytes[..end]);
+        Ok(text.into_owned())
+    }
+    /// TU: a string of UTF-16 characters.
+    pub fn read_tu(&mut self) -> DxfResult<String> {
+        let length = self.read_bs()? as u16 as usize;
+        let mut chars = Vec::with_capacity(length);
+        for _ in 0..length {
+            chars.push(self.read_rs()?);
+        }
+        while chars.last() == Some(&0) {
+            chars.pop();
+        }
+
+        Ok(String::from_utf16_lossy(&chars))
+    }
+    /// ENC: an entity color as written by R2004 and later.
+    pub fn read_enc(&mut self) -> DxfResult<DwgEntityColor> {
+        let raw = self.read_bs()? as u16;
+        let mut color = DwgEntityColor {
+            index: (raw & 0x01FF) as i16,
+            flags: raw >> 8,
+            ..Default::default()
+        };
+        if color.has_rgb() {
+            color.rgb = self.read_bl()? as u32;
+        }
+        if color.has_transparency() {
+            color.transparency = self.read_bl()?;
+        }
+
+        Ok(color)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::dwg_bit_reader::*;
+
+    // packs a string of '0' and '1' characters into bytes, padding the last byte with zeros
+    fn bits(s: &str) -> Vec<u8> {
+        let digits = s
+            .chars()
+            .filter(|c| *c == '0' || *c == '1')
+            .collect::<Vec<_>>();
+        digits
+            .chunks(8)
+            .map(|chunk| {
+                let mut b = 0u8;
+                for (i, c) in chunk.iter().enumerate() {
+                    if *c == '1' {
+                        b |= 0x80 >> i;
+                    }
+                }
+                b
+            })
+            .collect()
+    }
+
+    fn reader(data: &[u8]) -> DwgBitReader<'_> {
+        DwgBitReader::new(data, AcadVersion::R2000)
+    }
+
+    #[test]
+    fn read_bit_shorts() {
+        // 00 + raw short 0x0102, 01 + byte 0x05, 10, 11
+        let data = bits("00 00000010 00000001 01 00000101 10 11");
+        let mut r = reader(&data);
+        assert_eq!(0x0102, r.read_bs().unwrap());
+        assert_eq!(5, r.read_bs().unwrap());
+        assert_eq!(0, r.read_bs().unwrap());
+        assert_eq!(256, r.read_bs().unwrap());
+    }
+
+    #[test]
+    fn read_bit_longs() {
+        let data = bits("00 01111000 01010110 00110100 00010010 01 11111111 10");
+        let mut r = reader(&data);
+        assert_eq!(0x1234_5678, r.read_bl().unwrap());
+        assert_eq!(255, r.read_bl().unwrap());
+        assert_eq!(0, r.read_bl().unwrap());
+    }
+
+    #[test]
+    fn read_bit_doubles() {
+        let mut data = vec![];
+        let raw = 2.5f64.to_le_bytes();
+        let mut s = String::from("01 10 00");
+        for b in raw.iter() {
+            s.push_str(&format!(" {b:08b}"));
+        }
+        data.extend(bits(&s));
+        let mut r = reader(&data);
+        assert_eq!(1.0, r.read_bd().unwrap());
+        assert_eq!(0.0, r.read_bd().unwrap());
+        assert_eq!(2.5, r.read_bd().unwrap());
+    }
+
+    #[test]
+    fn read_default_doubles() {
+        let default = 1.5f64;
+        let replacement = 3.25f64.to_le_bytes();
+
+        // unchanged
+        let data = bits("00");
+        assert_eq!(default, reader(&data).read_dd(default).unwrap());
+
+        // patch the first 4 bytes
+        let mut s = String::from("01");
+        let mut expected = default.to_le_bytes();
+        for (i, b) in [1u8, 2, 3, 4].iter().enumerate() {
+            s.push_str(&format!(" {b:08b}"));
+            expected[i] = *b;
+        }
+        let data = bits(&s);
+        assert_eq!(
+            f64::from_le_bytes(expected),
+            reader(&data).read_dd(default).unwrap()
+        );
+
+        // patch bytes 5 and 6, then the first 4
+        let mut s = String::from("10");
+        for b in [replacement[4], replacement[5], 0, 0, 0, 0].iter() {
+            s.push_str(&format!(" {b:08b}"));
+        }
+        let data = bits(&s);
+        let mut expected = default.to_le_bytes();
+        expected[4] = replacement[4];
+        expected[5] = replacement[5];
+        expected[0..4].copy_from_slice(&[0, 0, 0, 0]);
+        assert_eq!(
+            f64::from_le_bytes(expected),
+            reader(&data).read_dd(default).unwrap()
+        );
+
+        // full value
+        let mut s = String::from("11");
+        for b in replacement.iter() {
+            s.push_str(&format!(" {b:08b}"));
+        }
+        let data = bits(&s);
+        assert_eq!(3.25, reader(&data).read_dd(default).unwrap());
+    }
+
+    #[test]
+    fn read_modular_chars() {
+        // 0x82 0x24 => 2 | (0x24 << 7) = 4610; 0x44 => -4
+        let data = vec![0x82, 0x24, 0x44, 0xE9, 0x97, 0xE6, 0x35];
+        let mut r = reader(&data);
+        assert_eq!(4610, r.read_mc().unwrap());
+        assert_eq!(-4, r.read_mc().unwrap());
+        assert_eq!(
+            0x69 | (0x17 << 7) | (0x66 << 14) | (0x35 << 21),
+            r.read_umc().unwrap()
+        );
+    }
+
+    #[test]
+    fn read_modular_shorts() {
+        // 0x8031 0x0001 => 0x31 | (1 << 15)
+        let data = vec![0x31, 0x80, 0x01, 0x00];
+        assert_eq!(0x31 | (1 << 15), reader(&data).read_ms().unwrap());
+    }
+
+    #[test]
+    fn read_handle_references() {
+        // code 5, two bytes 0x01 0x2C; code 6 with no bytes; code 0xC with one byte 0x03
+        let data = vec![0x52, 0x01, 0x2C, 0x60, 0xC1, 0x03];
+        let mut r = reader(&data);
+        let h = r.read_h().unwrap();
+        assert_eq!(
+            DwgHandleRef {
+                code: 5,
+                value: 0x12C
+            },
+            h
+        );
+        assert_eq!(0x12C, h.absolute(0x10));
+        assert_eq!(0x11, r.read_h().unwrap().absolute(0x10));
+        assert_eq!(0x0D, r.read_h().unwrap().absolute(0x10));
+    }
+
+    #[test]
+    fn read_extrusion_and_thickness() {
+        let data = bits("1 1 0 10 10 01 0 01");
+        let mut r = reader(&data);
+        assert_eq!(Vector::z_axis(), r.read_be().unwrap());
+        assert_eq!(0.0, r.read_bt().unwrap());
+        assert_eq!(Vector::new(0.0, 0.0, 1.0), r.read_be().unwrap());
+        assert_eq!(1.0, r.read_bt().unwrap());
+    }
+
+    #[test]
+    fn read_object_types() {
+        let data = bits("01 00010011");
+        assert_eq!(19, reader(&data).read_ot().unwrap());
+
+        let data = bits("01 00000010");
+        let mut r = DwgBitReader::new(&data, AcadVersion::R2010);
+        assert_eq!(0x1F2, r.read_ot().unwrap());
+    }
+
+    #[test]
+    fn read_strings() {
+        // BS length 3 as a byte, then "abc"
+        let data = bits("01 00000011 01100001 01100010 01100011");
+        assert_eq!(
+            "abc",
+            reader(&data).read_tv(encoding_rs::WINDOWS_1252).unwrap()
+        );
+
+        let data = bits("01 00000010 01100001 00000000 00000000 00000000");
+        assert_eq!("a", reader(&data).read_tu().unwrap());
+    }
+
+    #[test]
+    fn read_past_end_is_an_error() {
+        let data = vec![0xFF];
+        let mut r = reader(&data);
+        assert!(r.read_rs().is_err());
+    }
+}
@@ -0,0 +1,6672 @@
+// reads DWG files (R2000 through R2018) into a `Drawing`
+
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::io::Read;
+
+use encoding_rs::Encoding;
+use enum_primitive::FromPrimitive;
+
+use crate::code_page::encoding_for_code_page;
+use crate::dwg_bit_reader::{DwgBitReader, DwgEntityColor};
+use crate::entities::*;
+use crate::enums::*;
+use crate::helper_functions::*;
+use crate::objects::*;
+use crate::sat::crypt_sat_line;
+use crate::tables::*;
+use crate::{
+    Block, Class, CodePair, CodePairValue, Color, Drawing, DxfError, DxfResult, ExpectedType,
+    Handle, Header, LineWeight, LwPolylineVertex, MLineStyleElement, Point, Vector,
+};
+
+// object type numbers that aren't assigned through the classes section
+const TYPE_TEXT: u16 = 0x01;
+const TYPE_ATTRIB: u16 = 0x02;
+const TYPE_ATTDEF: u16 = 0x03;
+const TYPE_BLOCK: u16 = 0x04;
+const TYPE_ENDBLK: u16 = 0x05;
+const TYPE_SEQEND: u16 = 0x06;
+const TYPE_INSERT: u16 = 0x07;
+const TYPE_MINSERT: u16 = 0x08;
+const TYPE_VERTEX_2D: u16 = 0x0A;
+const TYPE_VERTEX_3D: u16 = 0x0B;
+const TYPE_VERTEX_MESH: u16 = 0x0C;
+const TYPE_VERTEX_PFACE: u16 = 0x0D;
+const TYPE_VERTEX_PFACE_FACE: u16 = 0x0E;
+const TYPE_POLYLINE_2D: u16 = 0x0F;
+const TYPE_POLYLINE_3D: u16 = 0x10;
+const TYPE_ARC: u16 = 0x11;
+const TYPE_CIRCLE: u16 = 0x12;
+const TYPE_LINE: u16 = 0x13;
+const TYPE_DIMENSION_ORDINATE: u16 = 0x14;
+const TYPE_DIMENSION_LINEAR: u16 = 0x15;
+const TYPE_DIMENSION_ALIGNED: u16 = 0x16;
+const TYPE_DIMENSION_ANG3PT: u16 = 0x17;
+const TYPE_DIMENSION_RADIUS: u16 = 0x19;
+const TYPE_DIMENSION_DIAMETER: u16 = 0x1A;
+const TYPE_POINT: u16 = 0x1B;
+const TYPE_3DFACE: u16 = 0x1C;
+const TYPE_POLYLINE_PFACE: u16 = 0x1D;
+const TYPE_POLYLINE_MESH: u16 = 0x1E;
+const TYPE_SOLID: u16 = 0x1F;
+const TYPE_TRACE: u16 = 0x20;
+const TYPE_SHAPE: u16 = 0x21;
+const TYPE_ELLIPSE: u16 = 0x23;
+const TYPE_SPLINE: u16 = 0x24;
+const TYPE_REGION: u16 = 0x25;
+const TYPE_3DSOLID: u16 = 0x26;
+const TYPE_BODY: u16 = 0x27;
+const TYPE_RAY: u16 = 0x28;
+const TYPE_XLINE: u16 = 0x29;
+const TYPE_DICTIONARY: u16 = 0x2A;
+const TYPE_OLEFRAME: u16 = 0x2B;
+const TYPE_MTEXT: u16 = 0x2C;
+const TYPE_LEADER: u16 = 0x2D;
+const TYPE_TOLERANCE: u16 = 0x2E;
+const TYPE_MLINE: u16 = 0x2F;
+const TYPE_BLOCK_HEADER: u16 = 0x31;
+const TYPE_LAYER: u16 = 0x33;
+const TYPE_STYLE: u16 = 0x35;
+const TYPE_LTYPE: u16 = 0x39;
+const TYPE_APPID: u16 = 0x43;
+const TYPE_DIMSTYLE: u16 = 0x45;
+const TYPE_GROUP: u16 = 0x48;
+const TYPE_MLINESTYLE: u16 = 0x49;
+const TYPE_OLE2FRAME: u16 = 0x4A;
+const TYPE_LWPOLYLINE: u16 = 0x4D;
+const TYPE_HATCH: u16 = 0x4E;
+const TYPE_XRECORD: u16 = 0x4F;
+const TYPE_PLACEHOLDER: u16 = 0x50;
+const TYPE_LAYOUT: u16 = 0x52;
+const TYPE_PROXY_ENTITY: u16 = 0x1F2;
+
+// classes that have no fixed type are given one of these; class numbers never get this high
+const TYPE_IMAGE: u16 = 0xFF00;
+const TYPE_WIPEOUT: u16 = 0xFF01;
+const TYPE_HELIX: u16 = 0xFF02;
+const TYPE_LIGHT: u16 = 0xFF03;
+const TYPE_PDF_UNDERLAY: u16 = 0xFF04;
+const TYPE_DWF_UNDERLAY: u16 = 0xFF05;
+const TYPE_DGN_UNDERLAY: u16 = 0xFF06;
+
+// object types numbered from here are defined in the classes section
+const FIRST_CLASS_TYPE: u16 = 500;
+
+// R2004 and later file layout
+const R2004_HEADER_MAGIC: &[u8] = b"AcFssFcAJMB\0";
+const R2004_HEADER_OFFSET: usize = 0x80;
+const R2004_HEADER_LENGTH: usize = 0x6C;
+const R2004_PAGE_MAP_BASE: usize = 0x100;
+const PAGE_MAP_PAGE_TYPE: u32 = 0x4163_0E3B;
+const SECTION_MAP_PAGE_TYPE: u32 = 0x4163_003B;
+const DATA_PAGE_TYPE: u32 = 0x4163_043B;
+const DATA_PAGE_HEADER_MASK: u32 = 0x4164_536B;
+// each compressed byte expands to at most this many bytes
+const MAX_DECOMPRESSION_RATIO: usize = 0x100;
+
+// R2007 file layout; pages are Reed-Solomon encoded in 255 byte blocks that each hold 239 bytes of
+// system page data or 251 bytes of section data
+const R2007_HEADER_OFFSET: usize = 0x80;
+const R2007_HEADER_LENGTH: usize = 0x3D8;
+const R2007_HEADER_BLOCKS: usize = 3;
+const R2007_FILE_HEADER_LENGTH: usize = 0x110;
+const R2007_PAGES_BASE: usize = 0x480;
+const RS_BLOCK_SIZE: usize = 255;
+const RS_SYSTEM_DATA_SIZE: usize = 239;
+const RS_SECTION_DATA_SIZE: usize = 251;
+
+const SECTION_HEADER: &str = "AcDb:Header";
+const SECTION_CLASSES: &str = "AcDb:Classes";
+const SECTION_HANDLES: &str = "AcDb:Handles";
+const SECTION_OBJECTS: &str = "AcDb:AcDbObjects";
+
+// line weights in hundredths of a millimeter, indexed by the stored value
+const LINE_WEIGHTS: [i16; 24] = [
+    0, 5, 9, 13, 15, 18, 20, 25, 30, 35, 40, 50, 53, 60, 70, 80, 90, 100, 106, 120, 140, 158, 200,
+    211,
+];
+
+/// Describes a part of a DWG file that couldn't be read into the `Drawing`.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub enum DwgReadIssue {
+    /// The object's type isn't read.  `type_name` is the DXF name of the type, or its number if
+    /// the type is unknown.
+    UnsupportedObject { handle: Handle, type_name: String },
+    /// The object's data couldn't be decoded.
+    InvalidObject {
+        handle: Handle,
+        type_name: String,
+        message: String,
+    },
+    /// A section of the file couldn't be decoded and the values it holds were left at their
+    /// defaults.
+    InvalidSection { name: String, message: String },
+}
+
+/// Reports the parts of a DWG file that were skipped by `Drawing::load_dwg_with_report`.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub struct DwgReadReport {
+    pub issues: Vec<DwgReadIssue>,
+}
+
+impl DwgReadReport {
+    /// Returns `true` if everything in the file was read.
+    pub fn is_empty(&self) -> bool {
+        self.issues.is_empty()
+    }
+    /// Returns the issues describing objects whose data couldn't be decoded.
+    pub fn invalid_objects(&self) -> impl Iterator<Item = &DwgReadIssue> {
+        self.issues
+            .iter()
+            .filter(|i| matches!(i, DwgReadIssue::InvalidObject { .. }))
+    }
+}
+
+pub(crate) struct DwgReader<T: Read> {
+    reader: T,
+}
+
+impl<T: Read> DwgReader<T> {
+    pub fn new(reader: T) -> Self {
+        DwgReader { reader }
+    }
+    pub fn load(&mut self) -> DxfResult<(Drawing, DwgReadReport)> {
+        let mut data = vec![];
+        self.reader.read_to_end(&mut data)?;
+        let file = DwgFile::parse(&data)?;
+        Ok(file.into_drawing())
+    }
+}
+
+struct DwgSections<'a> {
+    header: Option<Cow<'a, [u8]>>,
+    classes: Option<Cow<'a, [u8]>>,
+    handles: Cow<'a, [u8]>,
+    objects: Cow<'a, [u8]>,
+}
+
+struct DwgFile {
+    version: AcadVersion,
+    code_page: &'static str,
+    header: Header,
+    header_handles: DwgHeaderHandles,
+    classes: Vec<Class>,
+    objects: Vec<DwgObject>,
+    report: DwgReadReport,
+}
+
+struct DwgObject {
+    handle: u64,
+    owner: u64,
+    data: DwgObjectData,
+}
+
+enum DwgObjectData {
+    Entity(Box<DwgEntity>),
+    BlockHeader(Box<DwgBlockHeader>),
+    Layer(Layer, u64),
+    LineType(LineType),
+    Style(Style),
+    AppId(AppId),
+    DimStyle(Box<DwgDimStyle>),
+    Dictionary(Dictionary),
+    XRecord(XRecordObject),
+    Group(Group),
+    MLineStyle(Box<DwgMLineStyle>),
+}
+
+// a dimension style and the handles of the table entries it refers to
+struct DwgDimStyle {
+    dim_style: DimStyle,
+    text_style: u64,
+    leader_block: u64,
+    arrow_block: u64,
+    first_arrow_block: u64,
+    second_arrow_block: u64,
+}
+
+// a multiline style and the line types of its elements
+struct DwgMLineStyle {
+    mline_style: MLineStyle,
+    element_line_types: Vec<DwgLineTypeRef>,
+}
+
+struct DwgEntity {
+    object_type: u16,
+    entity: Entity,
+    mode: u8,
+    layer: u64,
+    line_type: DwgLineTypeRef,
+    style: Option<u64>,
+    block: Option<u64>,
+}
+
+enum DwgLineTypeRef {
+    ByLayer,
+    ByBlock,
+    Continuous,
+    Handle(u64),
+}
+
+struct DwgBlockHeader {
+    record: BlockRecord,
+    flags: i32,
+    base_point: Point,
+    xref_path_name: String,
+    description: String,
+    block_entity: u64,
+}
+
+impl DwgFile {
+    fn parse(data: &[u8]) -> DxfResult<DwgFile> {
+        if data.len() < 0x19 {
+            return Err(DxfError::InvalidBinaryFile);
+        }
+
+        let version = acad_version(&data[0..6])?;
+        let maintenance_version = data[0x0B];
+        let code_page = code_page_name(u16::from(data[0x13]) | (u16::from(data[0x14]) << 8));
+        let encoding = encoding_for_code_page(code_page).unwrap_or(encoding_rs::WINDOWS_1252);
+        let sections = match version {
+            AcadVersion::R2000 => read_r2000_sections(data)?,
+            AcadVersion::R2007 => read_r2007_sections(data)?,
+            _ => read_r2004_sections(data)?,
+        };
+
+        // the header and classes are informational; a malformed section shouldn't prevent reading
+        // the geometry
+        let mut report = DwgReadReport::default();
+        let (header, header_handles) = match sections.header {
+            Some(ref header) => match read_header(header, version, maintenance_version, encoding) {
+                Ok(header) => header,
+                Err(e) => {
+                    report.issues.push(DwgReadIssue::InvalidSection {
+                        name: String::from(SECTION_HEADER),
+                        message: e.to_string(),
+                    });
+                    (Header::default(), DwgHeaderHandles::default())
+                }
+            },
+            None => (Header::default(), DwgHeaderHandles::default()),
+        };
+        let numbered_classes = match sections.classes {
+            Some(ref classes) => {
+                match read_classes(classes, version, maintenance_version, encoding) {
+                    Ok(classes) => classes,
+                    Err(e) => {
+                        report.issues.push(DwgReadIssue::InvalidSection {
+                            name: String::from(SECTION_CLASSES),
+                            message: e.to_string(),
+                        });
+                        vec![]
+                    }
+                }
+            }
+            None => vec![],
+        };
+        let class_names = numbered_classes
+            .iter()
+            .map(|(number, class)| (*number, class.record_name.clone()))
+            .collect::<HashMap<_, _>>();
+        let mut objects = vec![];
+        for (handle, location) in read_object_map(&sections.handles)? {
+            let (object_type, mut reader) =
+                match open_object(&sections.objects, handle, location, version, encoding) {
+                    Ok(opened) => opened,
+                    Err(e) => {
+                        report.issues.push(DwgReadIssue::InvalidObject {
+                            handle: Handle(handle),
+                            type_name: String::new(),
+                            message: e.to_string(),
+                        });
+                        continue;
+                    }
+                };
+            match read_object(&mut reader, class_type(object_type, &class_names)) {
+                Ok(Some(object)) => objects.push(object),
+                Ok(None) if has_dxf_equivalent(object_type) => {
+                    report.issues.push(DwgReadIssue::UnsupportedObject {
+                        handle: Handle(handle),
+                        type_name: object_type_name(object_type, &class_names),
+                    });
+                }
+                Ok(None) => (),
+                Err(e) => report.issues.push(DwgReadIssue::InvalidObject {
+                    handle: Handle(handle),
+                    type_name: object_type_name(object_type, &class_names),
+                    message: e.to_string(),
+                }),
+            }
+        }
+
+        Ok(DwgFile {
+            version,
+            code_page,
+            header,
+            header_handles,
+            classes: numbered_classes.into_iter().map(|(_, c)| c).collect(),
+            objects,
+            report,
+        })
+    }
+    fn into_drawing(self) -> (Drawing, DwgReadReport) {
+        let mut drawing = Drawing::new();
+        drawing.clear();
+        drawing.header = self.header.clone();
+        drawing.header.version = self.version;
+        drawing.header.drawing_code_page = String::from(self.code_page);
+        drawing.classes = self.classes;
+
+        let mut line_type_names = HashMap::new();
+        let mut layer_names = HashMap::new();
+        let mut style_names = HashMap::new();
+        let mut block_names = HashMap::new();
+        let mut dim_style_names = HashMap::new();
+        let mut mline_style_names = HashMap::new();
+        for object in &self.objects {
+            match object.data {
+                DwgObjectData::LineType(ref lt) => {
+                    line_type_names.insert(object.handle, lt.name.clone());
+                }
+                DwgObjectData::Layer(ref layer, _) => {
+                    layer_names.insert(object.handle, layer.name.clone());
+                }
+                DwgObjectData::Style(ref style) => {
+                    style_names.insert(object.handle, style.name.clone());
+                }
+                DwgObjectData::BlockHeader(ref header) => {
+                    block_names.insert(object.handle, header.record.name.clone());
+                }
+                DwgObjectData::DimStyle(ref dim_style) => {
+                    dim_style_names.insert(object.handle, dim_style.dim_style.name.clone());
+                }
+                DwgObjectData::MLineStyle(ref style) => {
+                    mline_style_names.insert(object.handle, style.mline_style.style_name.clone());
+                }
+                _ => (),
+            }
+        }
+
+        let builder = DwgEntityBuilder {
+            objects: &self.objects,
+            line_type_names: &line_type_names,
+            layer_names: &layer_names,
+            style_names: &style_names,
+            block_names: &block_names,
+            dim_style_names: &dim_style_names,
+            mline_style_names: &mline_style_names,
+            children: owned_entities(&self.objects),
+        };
+
+        // tables
+        for object in &self.objects {
+            match object.data {
+                DwgObjectData::LineType(ref lt) => {
+                    drawing.add_line_type_no_handle_set(lt.clone());
+                }
+                DwgObjectData::Style(ref style) => {
+                    drawing.add_style_no_handle_set(style.clone());
+                }
+                DwgObjectData::AppId(ref app_id) => {
+                    drawing.add_app_id_no_handle_set(app_id.clone());
+                }
+                _ => (),
+            }
+        }
+        for object in &self.objects {
+            if let DwgObjectData::Layer(ref layer, line_type) = object.data {
+                let mut layer = layer.clone();
+                if let Some(name) = line_type_names.get(&line_type) {
+                    layer.line_type_name = name.clone();
+                }
+                drawing.add_layer_no_handle_set(layer);
+            }
+        }
+        for object in &self.objects {
+            if let DwgObjectData::DimStyle(ref dim_style) = object.data {
+                let name_of = |names: &HashMap<u64, String>, handle: u64| {
+                    names.get(&handle).cloned().unwrap_or_default()
+                };
+                drawing.add_dim_style_no_handle_set(DimStyle {
+                    dimension_text_style: name_of(&style_names, dim_style.text_style),
+                    dimension_leader_block_name: name_of(&block_names, dim_style.leader_block),
+                    arrow_block_name: name_of(&block_names, dim_style.arrow_block),
+                    first_arrow_block_name: name_of(&block_names, dim_style.first_arrow_block),
+                    second_arrow_block_name: name_of(&block_names, dim_style.second_arrow_block),
+                    ..dim_style.dim_style.clone()
+                });
+            }
+        }
+
+        // new items get handles past the highest one in the file
+        let max_handle = self.objects.iter().map(|o| o.handle).max().unwrap_or(0);
+        drawing.header.next_available_handle =
+            Handle(self.header.next_available_handle.0.max(max_handle + 1));
+
+        // header values that refer to table entries
+        let name_of = |names: &HashMap<u64, String>, handle: u64, default: &String| {
+            names
+                .get(&handle)
+                .cloned()
+                .unwrap_or_else(|| default.clone())
+        };
+        let handles = &self.header_handles;
+        let header = &mut drawing.header;
+        header.current_layer = name_of(&layer_names, handles.current_layer, &header.current_layer);
+        header.text_style = name_of(&style_names, handles.text_style, &header.text_style);
+        header.dimension_style_name = name_of(
+            &dim_style_names,
+            handles.dimension_style,
+            &header.dimension_style_name,
+        );
+        header.current_entity_line_type = name_of(
+            &line_type_names,
+            handles.current_entity_line_type,
+            &header.current_entity_line_type,
+        );
+        header.dimension_text_style = name_of(
+            &style_names,
+            handles.dimension_text_style,
+            &header.dimension_text_style,
+        );
+        header.dimension_leader_block_name = name_of(
+            &block_names,
+            handles.dimension_leader_block,
+            &header.dimension_leader_block_name,
+        );
+        header.arrow_block_name =
+            name_of(&block_names, handles.arrow_block, &header.arrow_block_name);
+        header.first_arrow_block_name = name_of(
+            &block_names,
+            handles.first_arrow_block,
+            &header.first_arrow_block_name,
+        );
+        header.second_arrow_block_name = name_of(
+            &block_names,
+            handles.second_arrow_block,
+            &header.second_arrow_block_name,
+        );
+        header.dimension_line_type = name_of(
+            &line_type_names,
+            handles.dimension_line_type,
+            &header.dimension_line_type,
+        );
+        header.dimension_first_extension_line_type = name_of(
+            &line_type_names,
+            handles.dimension_first_extension_line_type,
+            &header.dimension_first_extension_line_type,
+        );
+        header.dimension_second_extension_line_type = name_of(
+            &line_type_names,
+            handles.dimension_second_extension_line_type,
+            &header.dimension_second_extension_line_type,
+        );
+
+        // multiline styles come before the entities so the ones they use aren't added again
+        for object in &self.objects {
+            if let DwgObjectData::MLineStyle(ref style) = object.data {
+                let mut mline_style = style.mline_style.clone();
+                for (element, line_type) in mline_style
+                    .elements
+                    .iter_mut()
+                    .zip(&style.element_line_types)
+                {
+                    element.line_type = match line_type {
+                        DwgLineTypeRef::ByLayer => String::from("BYLAYER"),
+                        DwgLineTypeRef::ByBlock => String::from("BYBLOCK"),
+                        DwgLineTypeRef::Continuous => String::from("CONTINUOUS"),
+                        DwgLineTypeRef::Handle(h) => line_type_names
+                            .get(h)
+                            .cloned()
+                            .unwrap_or_else(|| String::from("BYLAYER")),
+                    };
+                }
+                let mut obj = Object::new(ObjectType::MLineStyle(mline_style));
+                obj.common.handle = Handle(object.handle);
+                obj.common.__owner_handle = Handle(object.owner);
+                drawing.add_object_no_handle_set(obj);
+            }
+        }
+
+        // blocks
+        for object in &self.objects {
+            if let DwgObjectData::BlockHeader(ref header) = object.data {
+                if is_layout_block(&header.record.name) {
+                    drawing.add_block_record_no_handle_set(header.record.clone());
+                    continue;
+                }
+
+                let mut block = Block {
+                    handle: Handle(header.block_entity),
+                    name: header.record.name.clone(),
+                    flags: header.flags,
+                    base_point: header.base_point.clone(),
+                    xref_path_name: header.xref_path_name.clone(),
+                    description: header.description.clone(),
+                    ..Default::default()
+                };
+                block.__owner_handle = Handle(object.handle);
+                if let Some(block_entity) = builder.entity(header.block_entity) {
+                    block.layer = builder.layer_name(block_entity);
+                }
+                block.entities = builder.owned_entities(object.handle);
+                drawing.add_block_no_handle_set(block);
+                if let Some(record) = drawing.block_records_mut().last() {
+                    *record = BlockRecord {
+                        name: record.name.clone(),
+                        ..header.record.clone()
+                    };
+                }
+            }
+        }
+
+        // model and paper space entities
+        for object in &self.objects {
+            if let DwgObjectData::Entity(ref entity) = object.data {
+                if (entity.mode == 1 || entity.mode == 2) && !is_owned_type(entity.object_type) {
+                    drawing.add_entity_no_handle_set(builder.build(object.handle, entity));
+                }
+            }
+        }
+
+        // objects
+        for object in &self.objects {
+            let specific = match object.data {
+                DwgObjectData::Dictionary(ref dictionary) => {
+                    ObjectType::Dictionary(dictionary.clone())
+                }
+                DwgObjectData::XRecord(ref xrecord) => ObjectType::XRecordObject(xrecord.clone()),
+                DwgObjectData::Group(ref group) => ObjectType::Group(group.clone()),
+                _ => continue,
+            };
+            let mut obj = Object::new(specific);
+            obj.common.handle = Handle(object.handle);
+            obj.common.__owner_handle = Handle(object.owner);
+            drawing.add_object_no_handle_set(obj);
+        }
+
+        (drawing, self.report)
+    }
+}
+
+// maps the classes that stand in for a fixed object type to that type
+fn class_type(object_type: u16, class_names: &HashMap<u16, String>) -> u16 {
+    if object_type < FIRST_CLASS_TYPE {
+        return object_type;
+    }
+
+    match class_names.get(&object_type).map(|n| n.as_str()) {
+        Some("LWPOLYLINE") => TYPE_LWPOLYLINE,
+        Some("HATCH") => TYPE_HATCH,
+        Some("XRECORD") => TYPE_XRECORD,
+        Some("ACDBPLACEHOLDER") => TYPE_PLACEHOLDER,
+        Some("LAYOUT") => TYPE_LAYOUT,
+        Some("IMAGE") => TYPE_IMAGE,
+        Some("WIPEOUT") => TYPE_WIPEOUT,
+        Some("HELIX") => TYPE_HELIX,
+        Some("LIGHT") => TYPE_LIGHT,
+        Some("PDFUNDERLAY") => TYPE_PDF_UNDERLAY,
+        Some("DWFUNDERLAY") => TYPE_DWF_UNDERLAY,
+        Some("DGNUNDERLAY") => TYPE_DGN_UNDERLAY,
+        _ => object_type,
+    }
+}
+
+fn is_entity_type(object_type: u16) -> bool {
+    matches!(
+        object_type,
+        0x01..=0x29
+            | 0x2B..=0x2F
+            | TYPE_OLE2FRAME
+            | TYPE_LWPOLYLINE
+            | TYPE_HATCH
+            | TYPE_PROXY_ENTITY
+            | TYPE_IMAGE..=TYPE_DGN_UNDERLAY
+    )
+}
+
+// table control objects, viewport entity headers and the like only exist in DWG files
+fn has_dxf_equivalent(object_type: u16) -> bool {
+    !matches!(
+        object_type,
+        0x00 | 0x30 | 0x32 | 0x34 | 0x38 | 0x3C | 0x3E | 0x40 | 0x42 | 0x44 | 0x46 | 0x47 | 0x4B
+    )
+}
+
+fn object_type_name(object_type: u16, class_names: &HashMap<u16, String>) -> String {
+    let name = match object_type {
+        0x01 => "TEXT",
+        0x02 => "ATTRIB",
+        0x03 => "ATTDEF",
+        0x04 => "BLOCK",
+        0x05 => "ENDBLK",
+        0x06 => "SEQEND",
+        0x07 | 0x08 => "INSERT",
+        0x0A..=0x0E => "VERTEX",
+        0x0F | 0x10 | 0x1D | 0x1E => "POLYLINE",
+        0x11 => "ARC",
+        0x12 => "CIRCLE",
+        0x13 => "LINE",
+        0x14..=0x1A => "DIMENSION",
+        0x1B => "POINT",
+        0x1C => "3DFACE",
+        0x1F => "SOLID",
+        0x20 => "TRACE",
+        0x21 => "SHAPE",
+        0x22 => "VIEWPORT",
+        0x23 => "ELLIPSE",
+        0x24 => "SPLINE",
+        0x25 => "REGION",
+        0x26 => "3DSOLID",
+        0x27 => "BODY",
+        0x28 => "RAY",
+        0x29 => "XLINE",
+        0x2A => "DICTIONARY",
+        0x2B => "OLEFRAME",
+        0x2C => "MTEXT",
+        0x2D => "LEADER",
+        0x2E => "TOLERANCE",
+        0x2F => "MLINE",
+        0x31 => "BLOCK_RECORD",
+        0x33 => "LAYER",
+        0x35 => "STYLE",
+        0x39 => "LTYPE",
+        0x3D => "VIEW",
+        0x3F => "UCS",
+        0x41 => "VPORT",
+        0x43 => "APPID",
+        0x45 => "DIMSTYLE",
+        0x48 => "GROUP",
+        TYPE_MLINESTYLE => "MLINESTYLE",
+        0x4A => "OLE2FRAME",
+        0x4C => "LONG_TRANSACTION",
+        TYPE_LWPOLYLINE => "LWPOLYLINE",
+        TYPE_HATCH => "HATCH",
+        TYPE_XRECORD => "XRECORD",
+        TYPE_PLACEHOLDER => "ACDBPLACEHOLDER",
+        0x51 => "VBA_PROJECT",
+        TYPE_LAYOUT => "LAYOUT",
+        TYPE_PROXY_ENTITY => "ACAD_PROXY_ENTITY",
+        _ => match class_names.get(&object_type) {
+            Some(name) => return name.clone(),
+            None => return object_type.to_string(),
+        },
+    };
+
+    String::from(name)
+}
+
+fn is_layout_block(name: &str) -> bool {
+    name.eq_ignore_ascii_case("*Model_Space") || name.eq_ignore_ascii_case("*Paper_Space")
+}
+
+// entities that only appear as part of another entity or a block definition
+fn is_owned_type(object_type: u16) -> bool {
+    matches!(
+        object_type,
+        TYPE_ATTRIB
+            | TYPE_BLOCK
+            | TYPE_ENDBLK
+            | TYPE_SEQEND
+            | TYPE_VERTEX_2D
+            | TYPE_VERTEX_3D
+            | TYPE_VERTEX_MESH
+            | TYPE_VERTEX_PFACE
+            | TYPE_VERTEX_PFACE_FACE
+    )
+}
+
+// maps owner handles to the entities they own, in handle order
+fn owned_entities(objects: &[DwgObject]) -> HashMap<u64, Vec<usize>> {
+    let mut children: HashMap<u64, Vec<usize>> = HashMap::new();
+    for (i, object) in objects.iter().enumerate() {
+        if let DwgObjectData::Entity(ref entity) = object.data {
+            if entity.mode == 0 {
+                children.entry(object.owner).or_default().push(i);
+            }
+        }
+    }
+
+    children
+}
+
+struct DwgEntityBuilder<'a> {
+    objects: &'a [DwgObject],
+    line_type_names: &'a HashMap<u64, String>,
+    layer_names: &'a HashMap<u64, String>,
+    style_names: &'a HashMap<u64, String>,
+    block_names: &'a HashMap<u64, String>,
+    dim_style_names: &'a HashMap<u64, String>,
+    mline_style_names: &'a HashMap<u64, String>,
+    children: HashMap<u64, Vec<usize>>,
+}
+
+impl DwgEntityBuilder<'_> {
+    fn entity(&self, handle: u64) -> Option<&DwgEntity> {
+        let index = self
+            .objects
+            .binary_search_by_key(&handle, |o| o.handle)
+            .ok()?;
+        match self.objects[index].data {
+            DwgObjectData::Entity(ref entity) => Some(entity),
+            _ => None,
+        }
+    }
+    fn layer_name(&self, entity: &DwgEntity) -> String {
+        self.layer_names
+            .get(&entity.layer)
+            .cloned()
+            .unwrap_or_else(|| String::from("0"))
+    }
+    fn owned_entities(&self, owner: u64) -> Vec<Entity> {
+        let mut result = vec![];
+        for &i in self.children.get(&owner).map(|c| &c[..]).unwrap_or(&[]) {
+            let object = &self.objects[i];
+            if let DwgObjectData::Entity(ref entity) = object.data {
+                if !is_owned_type(entity.object_type) {
+                    result.push(self.build(object.handle, entity));
+                }
+            }
+        }
+
+        result
+    }
+    fn build(&self, handle: u64, dwg_entity: &DwgEntity) -> Entity {
+        let mut entity = dwg_entity.entity.clone();
+        entity.common.layer = self.layer_name(dwg_entity);
+        entity.common.line_type_name = match dwg_entity.line_type {
+            DwgLineTypeRef::ByLayer => String::from("BYLAYER"),
+            DwgLineTypeRef::ByBlock => String::from("BYBLOCK"),
+            DwgLineTypeRef::Continuous => String::from("CONTINUOUS"),
+            DwgLineTypeRef::Handle(h) => self
+                .line_type_names
+                .get(&h)
+                .cloned()
+                .unwrap_or_else(|| String::from("BYLAYER")),
+        };
+        let style_name = dwg_entity.style.and_then(|h| self.style_names.get(&h));
+        let dim_style_name = dwg_entity.style.and_then(|h| self.dim_style_names.get(&h));
+        let block_name = dwg_entity.block.and_then(|h| self.block_names.get(&h));
+        let dimension_base = match entity.specific {
+            EntityType::RotatedDimension(ref mut d) => Some(&mut d.dimension_base),
+            EntityType::RadialDimension(ref mut d) => Some(&mut d.dimension_base),
+            EntityType::DiameterDimension(ref mut d) => Some(&mut d.dimension_base),
+            EntityType::AngularThreePointDimension(ref mut d) => Some(&mut d.dimension_base),
+            EntityType::OrdinateDimension(ref mut d) => Some(&mut d.dimension_base),
+            _ => None,
+        };
+        if let Some(dimension_base) = dimension_base {
+            if let Some(name) = dim_style_name {
+                dimension_base.dimension_style_name = name.clone();
+            }
+            if let Some(name) = block_name {
+                dimension_base.block_name = name.clone();
+            }
+        }
+        match entity.specific {
+            EntityType::Text(ref mut text) => {
+                if let Some(name) = style_name {
+                    text.text_style_name = name.clone();
+                }
+            }
+            EntityType::Attribute(ref mut att) => {
+                if let Some(name) = style_name {
+                    att.text_style_name = name.clone();
+                }
+            }
+            EntityType::AttributeDefinition(ref mut att) => {
+                if let Some(name) = style_name {
+                    att.text_style_name = name.clone();
+                }
+            }
+            EntityType::MText(ref mut mtext) => {
+                if let Some(name) = style_name {
+                    mtext.text_style_name = name.clone();
+                }
+            }
+            EntityType::Tolerance(ref mut tolerance) => {
+                if let Some(name) = dim_style_name {
+                    tolerance.dimension_style_name = name.clone();
+                }
+            }
+            EntityType::Leader(ref mut leader) => {
+                if let Some(name) = dim_style_name {
+                    leader.dimension_style_name = name.clone();
+                }
+            }
+            EntityType::MLine(ref mut mline) => {
+                if let Some(name) = self.mline_style_names.get(&mline.__style_handle.0) {
+                    mline.style_name = name.clone();
+                }
+            }
+            EntityType::Insert(ref mut insert) => {
+                if let Some(name) = block_name {
+                    insert.name = name.clone();
+                }
+                for (child, child_handle) in self.children_of(handle) {
+                    if let EntityType::Attribute(att) = child.specific {
+                        insert.__attributes_and_handles.push((att, child_handle));
+                    }
+                }
+                insert.__has_attributes = !insert.__attributes_and_handles.is_empty();
+            }
+            EntityType::Polyline(ref mut poly) => {
+                for (child, child_handle) in self.children_of(handle) {
+                    if let EntityType::Vertex(vertex) = child.specific {
+                        poly.__vertices_and_handles.push((vertex, child_handle));
+                    }
+                }
+            }
+            _ => (),
+        }
+
+        entity
+    }
+    fn children_of(&self, owner: u64) -> Vec<(Entity, Handle)> {
+        let mut result = vec![];
+        for &i in self.children.get(&owner).map(|c| &c[..]).unwrap_or(&[]) {
+            let object = &self.objects[i];
+            if let DwgObjectData::Entity(ref entity) = object.data {
+                result.push((self.build(object.handle, entity), Handle(object.handle)));
+            }
+        }
+
+        result
+    }
+}
+
+fn acad_version(magic: &[u8]) -> DxfResult<AcadVersion> {
+    match magic {
+        b"AC1015" => Ok(AcadVersion::R2000),
+        b"AC1018" => Ok(AcadVersion::R2004),
+        b"AC1021" => Ok(AcadVersion::R2007),
+        b"AC1024" => Ok(AcadVersion::R2010),
+        b"AC1027" => Ok(AcadVersion::R2013),
+        b"AC1032" => Ok(AcadVersion::R2018),
+        _ => Err(DxfError::UnsupportedDwgVersion(
+            String::from_utf8_lossy(magic).into_owned(),
+        )),
+    }
+}
+
+// maps the code page number stored in the file header to a `$DWGCODEPAGE` value
+fn code_page_name(code_page: u16) -> &'static str {
+    match code_page {
+        27 => "DOS866",
+        28 => "ANSI_1250",
+        29 => "ANSI_1251",
+        32 => "ANSI_1253",
+        33 => "ANSI_1254",
+        34 => "ANSI_1255",
+        35 => "ANSI_1256",
+        36 => "ANSI_1257",
+        37 => "ANSI_874",
+        38 => "ANSI_932",
+        39 => "ANSI_936",
+        40 => "ANSI_949",
+        41 => "ANSI_950",
+        42 => "ANSI_1361",
+        44 => "ANSI_1258",
+        _ => "ANSI_1252",
+    }
+}
+
+fn slice(data: &[u8], offset: usize, length: usize) -> DxfResult<&[u8]> {
+    match offset.checked_add(length) {
+        Some(end) if end <= data.len() => Ok(&data[offset..end]),
+        _ => Err(DxfError::UnexpectedEndOfInput),
+    }
+}
+
+fn read_r2000_sections(data: &[u8]) -> DxfResult<DwgSections<'_>> {
+    let mut reader = DwgBitReader::new(data, AcadVersion::R2000);
+    reader.set_position(0x15 * 8);
+    let count = reader.read_rl()?;
+    let mut header = None;
+    let mut classes = None;
+    let mut handles = None;
+    for _ in 0..count {
+        let number = reader.read_rc()?;
+        let seeker = reader.read_rl()? as usize;
+        let size = reader.read_rl()? as usize;
+        match number {
+            0 if size > 0 => header = Some(Cow::Borrowed(slice(data, seeker, size)?)),
+            1 => classes = Some(Cow::Borrowed(slice(data, seeker, size)?)),
+            2 => handles = Some(Cow::Borrowed(slice(data, seeker, size)?)),
+            _ => (),
+        }
+    }
+
+    match handles {
+        // object locations are absolute file offsets
+        Some(handles) => Ok(DwgSections {
+            header,
+            classes,
+            handles,
+            objects: Cow::Borrowed(data),
+        }),
+        None => Err(DxfError::InvalidBinaryFile),
+    }
+}
+
+// the header data at 0x80 is XOR'd with a pseudo-random sequence
+fn decrypt_r2004_header(data: &[u8]) -> Vec<u8> {
+    let mut seed = 1u32;
+    data.iter()
+        .map(|b| {
+            seed = seed.wrapping_mul(0x0003_43FD).wrapping_add(0x0026_9EC3);
+            b ^ (seed >> 16) as u8
+        })
+        .collect()
+}
+
+fn read_r2004_sections(data: &[u8]) -> DxfResult<DwgSections<'_>> {
+    let header = decrypt_r2004_header(slice(data, R2004_HEADER_OFFSET, R2004_HEADER_LENGTH)?);
+    if &header[0..R2004_HEADER_MAGIC.len()] != R2004_HEADER_MAGIC {
+        return Err(DxfError::InvalidBinaryFile);
+    }
+
+    let mut reader = DwgBitReader::new(&header, AcadVersion::R2004);
+    reader.set_position(0x54 * 8);
+    let page_map_address = read_rll(&mut reader)? as usize + R2004_PAGE_MAP_BASE;
+    let section_map_id = reader.read_rl()? as i32;
+
+    // page numbers to file offsets
+    let page_map = read_system_page(data, page_map_address, PAGE_MAP_PAGE_TYPE)?;
+    let mut pages = HashMap::new();
+    let mut reader = DwgBitReader::new(&page_map, AcadVersion::R2004);
+    let mut address = R2004_PAGE_MAP_BASE;
+    while reader.position() + 64 <= reader.bit_length() {
+        let number = reader.read_rl()? as i32;
+        let size = reader.read_rl()? as usize;
+        if number >= 0 {
+            pages.insert(number, address);
+        } else {
+            // gaps have a parent, left, right and unused value
+            reader.read_bytes(16)?;
+        }
+        address += size;
+    }
+
+    let section_map_address = match pages.get(&section_map_id) {
+        Some(address) => *address,
+        None => return Err(DxfError::InvalidBinaryFile),
+    };
+    let section_map = read_system_page(data, section_map_address, SECTION_MAP_PAGE_TYPE)?;
+    let mut reader = DwgBitReader::new(&section_map, AcadVersion::R2004);
+    let section_count = reader.read_rl()?;
+    reader.read_bytes(16)?;
+    let mut sections = HashMap::new();
+    for _ in 0..section_count {
+        let size = read_rll(&mut reader)? as usize;
+        let page_count = reader.read_rl()?;
+        let _max_page_size = reader.read_rl()?;
+        let _unknown = reader.read_rl()?;
+        let compressed = reader.read_rl()? == 2;
+        let _section_id = reader.read_rl()?;
+        let encrypted = reader.read_rl()? == 1;
+        let name_bytes = reader.read_bytes(64)?;
+        let name_end = name_bytes.iter().position(|&b| b == 0).unwrap_or(64);
+        let name = String::from_utf8_lossy(&name_bytes[..name_end]).into_owned();
+        let mut section_pages = vec![];
+        for _ in 0..page_count {
+            let number = reader.read_rl()? as i32;
+            let data_size = reader.read_rl()? as usize;
+            let start_offset = read_rll(&mut reader)? as usize;
+            section_pages.push((number, data_size, start_offset));
+        }
+
+        let wanted = name == SECTION_HEADER
+            || name == SECTION_CLASSES
+            || name == SECTION_HANDLES
+            || name == SECTION_OBJECTS;
+        if !wanted || encrypted {
+            continue;
+        }
+
+        let mut section_data = vec![];
+        for (number, _, start_offset) in section_pages {
+            let address = match pages.get(&number) {
+                Some(address) => *address,
+                None => return Err(DxfError::InvalidBinaryFile),
+            };
+            section_data.push((start_offset, read_data_page(data, address, compressed)?));
+        }
+
+        // the size comes straight from the file, so don't allocate more than the pages hold
+        let extent = section_data
+            .iter()
+            .map(|(start_offset, page)| start_offset.saturating_add(page.len()))
+            .max()
+            .unwrap_or(0);
+        if size > extent {
+            return Err(DxfError::InvalidBinaryFile);
+        }
+        let mut section = vec![0u8; size];
+        for (start_offset, page) in section_data {
+            if start_offset < section.len() {
+                let length = page.len().min(section.len() - start_offset);
+                section[start_offset..start_offset + length].copy_from_slice(&page[..length]);
+            }
+        }
+
+        sections.insert(name, section);
+    }
+
+    let handles = sections
+        .remove(SECTION_HANDLES)
+        .ok_or(DxfError::InvalidBinaryFile)?;
+    let objects = sections
+        .remove(SECTION_OBJECTS)
+        .ok_or(DxfError::InvalidBinaryFile)?;
+    Ok(DwgSections {
+        header: sections.remove(SECTION_HEADER).map(Cow::Owned),
+        classes: sections.remove(SECTION_CLASSES).map(Cow::Owned),
+        handles: Cow::Owned(handles),
+        objects: Cow::Owned(objects),
+    })
+}
+
+fn read_rll(reader: &mut DwgBitReader) -> DxfResult<u64> {
+    let lo = u64::from(reader.read_rl()?);
+    let hi = u64::from(reader.read_rl()?);
+    Ok(lo | (hi << 32))
+}
+
+fn read_system_page(data: &[u8], address: usize, expected_type: u32) -> DxfResult<Vec<u8>> {
+    let mut reader = DwgBitReader::new(slice(data, address, 20)?, AcadVersion::R2004);
+    let page_type = reader.read_rl()?;
+    let decompressed_size = reader.read_rl()? as usize;
+    let compressed_size = reader.read_rl()? as usize;
+    let compression_type = reader.read_rl()?;
+    if page_type != expected_type {
+        return Err(DxfError::InvalidBinaryFile);
+    }
+
+    let page = slice(data, address + 20, compressed_size)?;
+    if compression_type == 2 {
+        decompress(page, decompressed_size)
+    } else {
+        Ok(page.to_vec())
+    }
+}
+
+fn read_data_page(data: &[u8], address: usize, compressed: bool) -> DxfResult<Vec<u8>> {
+    // each value of the page header is XOR'd with a mask based on the page's location
+    let mut header = slice(data, address, 32)?.to_vec();
+    let mask = DATA_PAGE_HEADER_MASK ^ address as u32;
+    for chunk in header.chunks_mut(4) {
+        let value = u32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]) ^ mask;
+        chunk.copy_from_slice(&value.to_le_bytes());
+    }
+
+    let mut reader = DwgBitReader::new(&header, AcadVersion::R2004);
+    let page_type = reader.read_rl()?;
+    let _section_number = reader.read_rl()?;
+    let data_size = reader.read_rl()? as usize;
+    let page_size = reader.read_rl()? as usize;
+    if page_type != DATA_PAGE_TYPE {
+        return Err(DxfError::InvalidBinaryFile);
+    }
+
+    let page = slice(data, address + 32, data_size)?;
+    if compressed {
+        decompress(page, page_size)
+    } else {
+        Ok(page.to_vec())
+    }
+}
+
+fn read_u64(data: &[u8], offset: usize) -> DxfResult<u64> {
+    let bytes = slice(data, offset, 8)?;
+    let mut value = [0u8; 8];
+    value.copy_from_slice(bytes);
+    Ok(u64::from_le_bytes(value))
+}
+
+// converts a size read from the file, rejecting sizes that can't be a part of it
+fn file_size(value: u64, data: &[u8]) -> DxfResult<usize> {
+    match usize::try_from(value) {
+        Ok(size) if size <= data.len().saturating_mul(MAX_DECOMPRESSION_RATIO) => Ok(size),
+        _ => Err(DxfError::InvalidBinaryFile),
+    }
+}
+
+/// Returns the data bytes of `block_count` Reed-Solomon blocks whose bytes are interleaved, i.e.,
+/// byte `j` of block `i` is at `i + j * block_count`.  The parity bytes are ignored.
+fn decode_reed_solomon(data: &[u8], block_count: usize, data_size: usize) -> DxfResult<Vec<u8>> {
+    if block_count.saturating_mul(RS_BLOCK_SIZE) > data.len() {
+        return Err(DxfError::UnexpectedEndOfInput);
+    }
+
+    let mut result = Vec::with_capacity(block_count * data_size);
+    for i in 0..block_count {
+        for j in 0..data_size {
+            result.push(data[i + j * block_count]);
+        }
+    }
+
+    Ok(result)
+}
+
+fn read_r2007_sections(data: &[u8]) -> DxfResult<DwgSections<'_>> {
+    // the file header is compressed after a CRC, a key and the compressed CRC and size
+    let header_data = decode_reed_solomon(
+        slice(data, R2007_HEADER_OFFSET, R2007_HEADER_LENGTH)?,
+        R2007_HEADER_BLOCKS,
+        RS_SYSTEM_DATA_SIZE,
+    )?;
+    let compressed_size = u32::from_le_bytes([
+        header_data[24],
+        header_data[25],
+        header_data[26],
+        header_data[27],
+    ]) as i32;
+    let header = if compressed_size > 0 {
+        decompress_r2007(
+            slice(&header_data, 32, compressed_size as usize)?,
+            R2007_FILE_HEADER_LENGTH,
+        )?
+    } else {
+        slice(&header_data, 32, R2007_FILE_HEADER_LENGTH)?.to_vec()
+    };
+
+    // page ids to file offsets and sizes
+    let page_map = read_r2007_system_page(
+        data,
+        R2007_PAGES_BASE.saturating_add(file_size(read_u64(&header, 0x38)?, data)?),
+        file_size(read_u64(&header, 0x50)?, data)?,
+        file_size(read_u64(&header, 0x58)?, data)?,
+        file_size(read_u64(&header, 0x18)?, data)?,
+    )?;
+    let mut pages = HashMap::new();
+    let mut address = R2007_PAGES_BASE;
+    for entry in page_map.chunks_exact(16) {
+        let size = file_size(read_u64(entry, 0)?, data)?;
+        let id = read_u64(entry, 8)? as i64;
+        pages.insert(id, (address, size));
+        address = address.saturating_add(size);
+    }
+
+    let section_map_id = read_u64(&header, 0xC0)? as i64;
+    let section_map_address = match pages.get(&section_map_id) {
+        Some((address, _)) => *address,
+        None => return Err(DxfError::InvalidBinaryFile),
+    };
+    let section_map = read_r2007_system_page(
+        data,
+        section_map_address,
+        file_size(read_u64(&header, 0xB0)?, data)?,
+        file_size(read_u64(&header, 0xC8)?, data)?,
+        file_size(read_u64(&header, 0xD8)?, data)?,
+    )?;
+    let mut sections = HashMap::new();
+    let mut position = 0;
+    while position + 64 <= section_map.len() {
+        let values = (0..8)
+            .map(|i| read_u64(&section_map, position + i * 8))
+            .collect::<DxfResult<Vec<_>>>()?;
+        position += 64;
+        let size = file_size(values[0], data)?;
+        let encrypted = values[2] == 1;
+        let name_length = file_size(values[4], data)?;
+        let page_count = file_size(values[7], data)?;
+        let name_bytes = slice(&section_map, position, name_length)?;
+        position += name_length;
+        let name = String::from_utf16_lossy(
+            &name_bytes
+                .chunks_exact(2)
+                .map(|c| u16::from_le_bytes([c[0], c[1]]))
+                .take_while(|c| *c != 0)
+                .collect::<Vec<_>>(),
+        );
+        let mut section_pages = vec![];
+        for _ in 0..page_count {
+            let offset = file_size(read_u64(&section_map, position)?, data)?;
+            let id = read_u64(&section_map, position + 16)? as i64;
+            let uncompressed_size = file_size(read_u64(&section_map, position + 24)?, data)?;
+            let compressed_size = file_size(read_u64(&section_map, position + 32)?, data)?;
+            section_pages.push((offset, id, uncompressed_size, compressed_size));
+            position += 56;
+        }
+
+        let wanted = name == SECTION_HEADER
+            || name == SECTION_CLASSES
+            || name == SECTION_HANDLES
+            || name == SECTION_OBJECTS;
+        if !wanted || encrypted {
+            continue;
+        }
+
+        let mut section = vec![0u8; size];
+        for (offset, id, uncompressed_size, compressed_size) in section_pages {
+            let (address, page_size) = match pages.get(&id) {
+                Some(page) => *page,
+                None => return Err(DxfError::InvalidBinaryFile),
+            };
+            let page = read_r2007_data_page(
+                slice(data, address, page_size)?,
+                compressed_size,
+                uncompressed_size,
+            )?;
+            let end = offset
+                .checked_add(page.len())
+                .filter(|end| *end <= section.len())
+                .ok_or(DxfError::InvalidBinaryFile)?;
+            section[offset..end].copy_from_slice(&page);
+        }
+
+        sections.insert(name, section);
+    }
+
+    let handles = sections
+        .remove(SECTION_HANDLES)
+        .ok_or(DxfError::InvalidBinaryFile)?;
+    let objects = sections
+        .remove(SECTION_OBJECTS)
+        .ok_or(DxfError::InvalidBinaryFile)?;
+    Ok(DwgSections {
+        header: sections.remove(SECTION_HEADER).map(Cow::Owned),
+        classes: sections.remove(SECTION_CLASSES).map(Cow::Owned),
+        handles: Cow::Owned(handles),
+        objects: Cow::Owned(objects),
+    })
+}
+
+// system pages hold `repeat_count` copies of their data; only the first is read
+fn read_r2007_system_page(
+    data: &[u8],
+    address: usize,
+    compressed_size: usize,
+    decompressed_size: usize,
+    repeat_count: usize,
+) -> DxfResult<Vec<u8>> {
+    let encoded_size = ((compressed_size + 7) & !7).saturating_mul(repeat_count.max(1));
+    let block_count = encoded_size.div_ceil(RS_SYSTEM_DATA_SIZE);
+    let page_size = (block_count * RS_BLOCK_SIZE + 7) & !7;
+    let page = decode_reed_solomon(
+        slice(data, address, page_size)?,
+        block_count,
+        RS_SYSTEM_DATA_SIZE,
+    )?;
+    if compressed_size < decompressed_size {
+        decompress_r2007(slice(&page, 0, compressed_size)?, decompressed_size)
+    } else {
+        Ok(slice(&page, 0, decompressed_size)?.to_vec())
+    }
+}
+
+fn read_r2007_data_page(
+    page: &[u8],
+    compressed_size: usize,
+    decompressed_size: usize,
+) -> DxfResult<Vec<u8>> {
+    let block_count = ((compressed_size + 7) & !7).div_ceil(RS_SECTION_DATA_SIZE);
+    let page = decode_reed_solomon(page, block_count, RS_SECTION_DATA_SIZE)?;
+    if compressed_size < decompressed_size {
+        decompress_r2007(slice(&page, 0, compressed_size)?, decompressed_size)
+    } else {
+        Ok(slice(&page, 0, decompressed_size)?.to_vec())
+    }
+}
+
+struct CompressedInput<'a> {
+    data: &'a [u8],
+    position: usize,
+}
+
+impl CompressedInput<'_> {
+    fn is_at_end(&self) -> bool {
+        self.position >= self.data.len()
+    }
+    fn read(&mut self) -> DxfResult<u8> {
+        match self.data.get(self.position) {
+            Some(b) => {
+                self.position += 1;
+                Ok(*b)
+            }
+            None => Err(DxfError::UnexpectedEndOfInput),
+        }
+    }
+    // returns the literal length and, if the byte wasn't a length, the next opcode
+    fn literal_length(&mut self) -> DxfResult<(usize, u8)> {
+        let b = self.read()?;
+        match b {
+            0x00 => {
+                let mut total = 0x0F;
+                loop {
+                    let b = self.read()?;
+                    if b != 0x00 {
+                        return Ok((total + usize::from(b) + 3, 0x00));
+                    }
+                    total += 0xFF;
+                }
+            }
+            0x01..=0x0F => Ok((usize::from(b) + 3, 0x00)),
+            _ => Ok((0, b)),
+        }
+    }
+    fn long_length(&mut self) -> DxfResult<usize> {
+        let mut total = 0;
+        let mut b = self.read()?;
+        if b == 0x00 {
+            total = 0xFF;
+            loop {
+                b = self.read()?;
+                if b != 0x00 {
+                    break;
+                }
+                total += 0xFF;
+            }
+        }
+
+        Ok(total + usize::from(b))
+    }
+    // returns the offset and the literal length stored in the low bits
+    fn two_byte_offset(&mut self) -> DxfResult<(usize, usize)> {
+        let first = self.read()?;
+        let second = self.read()?;
+        let offset = usize::from(first >> 2) | (usize::from(second) << 6);
+        Ok((offset, usize::from(first & 0x03)))
+    }
+}
+
+/// Decompresses the LZ77 variant used by R2004 and later pages.
+fn decompress(data: &[u8], decompressed_size: usize) -> DxfResult<Vec<u8>> {
+    // the size comes from the page header; a size the data can't expand to means the page is
+    // corrupt
+    if decompressed_size > data.len().saturating_mul(MAX_DECOMPRESSION_RATIO) {
+        return Err(DxfError::InvalidBinaryFile);
+    }
+
+    let mut input = CompressedInput { data, position: 0 };
+    let mut output = Vec::with_capacity(decompressed_size);
+    let (literal, mut opcode) = input.literal_length()?;
+    for _ in 0..literal {
+        output.push(input.read()?);
+    }
+
+    loop {
+        if opcode == 0x00 {
+            if input.is_at_end() {
+                break;
+            }
+            opcode = input.read()?;
+        }
+
+        let (count, offset, mut literal) = match opcode {
+            0x11 => break,
+            0x40..=0xFF => {
+                let count = usize::from(opcode >> 4) - 1;
+                let second = input.read()?;
+                let offset = (usize::from(second) << 2) | usize::from((opcode & 0x0C) >> 2);
+                (count, offset, usize::from(opcode & 0x03))
+            }
+            0x21..=0x3F => {
+                let count = usize::from(opcode) - 0x1E;
+                let (offset, literal) = input.two_byte_offset()?;
+                (count, offset, literal)
+            }
+            0x20 => {
+                let count = input.long_length()? + 0x21;
+                let (offset, literal) = input.two_byte_offset()?;
+                (count, offset, literal)
+            }
+            0x12..=0x1F => {
+                let count = usize::from(opcode & 0x0F) + 2;
+                let (offset, literal) = input.two_byte_offset()?;
+                (count, offset + 0x3FFF, literal)
+            }
+            0x10 => {
+                let count = input.long_length()? + 9;
+                let (offset, literal) = input.two_byte_offset()?;
+                (count, offset + 0x3FFF, literal)
+            }
+            _ => return Err(DxfError::UnexpectedByte(opcode, input.position - 1)),
+        };
+        opcode = 0x00;
+        if literal == 0 {
+            let (length, next) = input.literal_length()?;
+            literal = length;
+            opcode = next;
+        }
+
+        if offset >= output.len() {
+            return Err(DxfError::InvalidBinaryFile);
+        }
+        if output.len() + count > decompressed_size {
+            return Err(DxfError::InvalidBinaryFile);
+        }
+        let start = output.len() - offset - 1;
+        for i in 0..count {
+            let b = output[start + i];
+            output.push(b);
+        }
+        for _ in 0..literal {
+            output.push(input.read()?);
+        }
+    }
+
+    output.resize(decompressed_size, 0);
+    Ok(output)
+}
+
+// the order R2007 literal runs are stored in: each step copies `length` bytes from `offset`, with
+// runs of 2 and 3 bytes reversed and runs of 16 bytes stored as two swapped halves
+const R2007_LITERAL_STEPS: [&[(usize, usize)]; 32] = [
+    &[],
+    &[(0, 1)],
+    &[(0, 2)],
+    &[(0, 3)],
+    &[(0, 4)],
+    &[(4, 1), (0, 4)],
+    &[(5, 1), (1, 4), (0, 1)],
+    &[(5, 2), (1, 4), (0, 1)],
+    &[(0, 8)],
+    &[(8, 1), (0, 8)],
+    &[(9, 1), (1, 8), (0, 1)],
+    &[(9, 2), (1, 8), (0, 1)],
+    &[(8, 4), (0, 8)],
+    &[(12, 1), (8, 4), (0, 8)],
+    &[(13, 1), (9, 4), (1, 8), (0, 1)],
+    &[(13, 2), (9, 4), (1, 8), (0, 1)],
+    &[(0, 16)],
+    &[(9, 8), (8, 1), (0, 8)],
+    &[(17, 1), (1, 16), (0, 1)],
+    &[(16, 3), (0, 16)],
+    &[(16, 4), (0, 16)],
+    &[(20, 1), (16, 4), (0, 16)],
+    &[(20, 2), (16, 4), (0, 16)],
+    &[(20, 3), (16, 4), (0, 16)],
+    &[(16, 8), (0, 16)],
+    &[(17, 8), (16, 1), (0, 16)],
+    &[(25, 1), (17, 8), (16, 1), (0, 16)],
+    &[(25, 2), (17, 8), (16, 1), (0, 16)],
+    &[(24, 4), (16, 8), (0, 16)],
+    &[(28, 1), (24, 4), (16, 8), (0, 16)],
+    &[(28, 2), (24, 4), (16, 8), (0, 16)],
+    &[(30, 1), (26, 4), (18, 8), (2, 16), (0, 2)],
+];
+
+fn push_r2007_literal(output: &mut Vec<u8>, literal: &[u8]) {
+    let push_step = |output: &mut Vec<u8>, bytes: &[u8]| match bytes.len() {
+        2 | 3 => output.extend(bytes.iter().rev()),
+        16 => {
+            output.extend(&bytes[8..]);
+            output.extend(&bytes[..8]);
+        }
+        _ => output.extend(bytes),
+    };
+    let mut chunks = literal.chunks_exact(32);
+    for chunk in &mut chunks {
+        push_step(output, &chunk[16..]);
+        push_step(output, &chunk[..16]);
+    }
+    let rest = chunks.remainder();
+    for (offset, length) in R2007_LITERAL_STEPS[rest.len()] {
+        push_step(output, &rest[*offset..*offset + *length]);
+    }
+}
+
+impl CompressedInput<'_> {
+    fn r2007_literal_length(&mut self, opcode: u8) -> DxfResult<usize> {
+        let mut length = usize::from(opcode) + 8;
+        if length == 0x17 {
+            let mut n = usize::from(self.read()?);
+            length += n;
+            if n == 0xFF {
+                loop {
+                    n = usize::from(self.read()?) | (usize::from(self.read()?) << 8);
+                    length += n;
+                    if n != 0xFFFF {
+                        break;
+                    }
+                }
+            }
+        }
+
+        Ok(length)
+    }
+    // returns the offset and length of a back reference and the opcode whose low bits hold the
+    // length of the literal run that follows
+    fn r2007_instruction(&mut self, opcode: u8) -> DxfResult<(usize, usize, u8)> {
+        let (offset, length, opcode) = match opcode >> 4 {
+            0 => {
+                let offset = usize::from(self.read()?);
+                let next = self.read()?;
+                let length = usize::from(opcode & 0x0F) + 0x13 + usize::from((next >> 3) & 0x10);
+                (((usize::from(next) & 0x78) << 5) + 1 + offset, length, next)
+            }
+            1 => {
+                let length = usize::from(opcode & 0x0F) + 3;
+                let offset = usize::from(self.read()?);
+                let next = self.read()?;
+                (((usize::from(next) & 0xF8) << 5) + 1 + offset, length, next)
+            }
+            2 => {
+                let mut offset = usize::from(self.read()?) | (usize::from(self.read()?) << 8);
+                let mut length = usize::from(opcode & 0x07);
+                let next = if opcode & 0x08 == 0 {
+                    let next = self.read()?;
+                    length += usize::from(next & 0xF8);
+                    next
+                } else {
+                    offset += 1;
+                    length += usize::from(self.read()?) << 3;
+                    let next = self.read()?;
+                    length += ((usize::from(next) & 0xF8) << 8) + 0x100;
+                    next
+                };
+                (offset, length, next)
+            }
+            _ => {
+                let length = usize::from(opcode >> 4);
+                let next = self.read()?;
+                let offset = ((usize::from(next) & 0xF8) << 1) + usize::from(opcode & 0x0F) + 1;
+                (offset, length, next)
+            }
+        };
+
+        Ok((offset, length, opcode))
+    }
+}
+
+/// Decompresses the LZ77 variant used by R2007 pages, whose literal runs are stored out of order.
+fn decompress_r2007(data: &[u8], decompressed_size: usize) -> DxfResult<Vec<u8>> {
+    if decompressed_size > data.len().saturating_mul(MAX_DECOMPRESSION_RATIO) {
+        return Err(DxfError::InvalidBinaryFile);
+    }
+
+    let mut input = CompressedInput { data, position: 0 };
+    let mut output = Vec::with_capacity(decompressed_size);
+    let mut opcode = input.read()?;
+    let mut literal = 0;
+    if opcode & 0xF0 == 0x20 {
+        input.position += 2;
+        literal = usize::from(input.read()? & 0x07);
+        if literal == 0 {
+            return Err(DxfError::InvalidBinaryFile);
+        }
+    }
+
+    while !input.is_at_end() {
+        if literal == 0 {
+            literal = input.r2007_literal_length(opcode)?;
+        }
+        let bytes = slice(data, input.position, literal)?;
+        if output.len() + literal > decompressed_size {
+            return Err(DxfError::InvalidBinaryFile);
+        }
+        push_r2007_literal(&mut output, bytes);
+        input.position += literal;
+        if input.is_at_end() {
+            break;
+        }
+
+        let instruction = input.read()?;
+        let (mut offset, mut length, next) = input.r2007_instruction(instruction)?;
+        opcode = next;
+        loop {
+            if offset > output.len() || output.len() + length > decompressed_size {
+                return Err(DxfError::InvalidBinaryFile);
+            }
+            let start = output.len() - offset;
+            for i in 0..length {
+                let b = output[start + i];
+                output.push(b);
+            }
+
+            literal = usize::from(opcode & 0x07);
+            if literal != 0 || input.is_at_end() {
+                break;
+            }
+            opcode = input.read()?;
+            if opcode >> 4 == 0 {
+                break;
+            }
+            if opcode >> 4 == 0x0F {
+                opcode &= 0x0F;
+            }
+            (offset, length, opcode) = input.r2007_instruction(opcode)?;
+        }
+    }
+
+    output.resize(decompressed_size, 0);
+    Ok(output)
+}
+
+fn read_object_map(data: &[u8]) -> DxfResult<Vec<(u64, usize)>> {
+    let mut reader = DwgBitReader::new(data, AcadVersion::R2000);
+    let mut result = vec![];
+    loop {
+        let start = reader.position() / 8;
+        if start + 2 > data.len() {
+            break;
+        }
+
+        // the section size is big endian and includes itself but not the trailing CRC
+        let size = (usize::from(reader.read_rc()?) << 8) | usize::from(reader.read_rc()?);
+        if size <= 2 {
+            break;
+        }
+
+        let mut handle = 0u64;
+        let mut location = 0i64;
+        while reader.position() / 8 < start + size {
+            handle = handle.wrapping_add(reader.read_umc()?);
+            location += reader.read_mc()?;
+            if location >= 0 {
+                result.push((handle, location as usize));
+            }
+        }
+
+        reader.read_rs()?;
+    }
+
+    result.sort_by_key(|(handle, _)| *handle);
+    result.dedup_by_key(|(handle, _)| *handle);
+    Ok(result)
+}
+
+// strings for R2007 and later are stored at the end of the data, before the size that ends at
+// `end_bit`
+fn string_stream<'a>(
+    reader: &DwgBitReader<'a>,
+    end_bit: usize,
+) -> DxfResult<Option<DwgBitReader<'a>>> {
+    if end_bit == 0 || end_bit > reader.bit_length() {
+        return Ok(None);
+    }
+
+    let mut strings = reader.clone();
+    let mut position = end_bit - 1;
+    strings.set_position(position);
+    if !strings.read_b()? {
+        return Ok(None);
+    }
+
+    position = position
+        .checked_sub(16)
+        .ok_or(DxfError::InvalidBinaryFile)?;
+    strings.set_position(position);
+    let mut size = usize::from(strings.read_rs()?);
+    if size & 0x8000 != 0 {
+        position = position
+            .checked_sub(16)
+            .ok_or(DxfError::InvalidBinaryFile)?;
+        strings.set_position(position);
+        let hi = usize::from(strings.read_rs()?);
+        size = (size & 0x7FFF) | (hi << 15);
+    }
+
+    let start = position
+        .checked_sub(size)
+        .ok_or(DxfError::InvalidBinaryFile)?;
+    strings.set_position(start);
+    Ok(Some(strings))
+}
+
+fn read_text(
+    main: &mut DwgBitReader,
+    strings: &mut Option<DwgBitReader>,
+    encoding: &'static Encoding,
+) -> DxfResult<String> {
+    if main.version >= AcadVersion::R2007 {
+        match strings {
+            Some(ref mut strings) => strings.read_tu(),
+            None => Ok(String::new()),
+        }
+    } else {
+        main.read_tv(encoding)
+    }
+}
+
+fn read_classes(
+    data: &[u8],
+    version: AcadVersion,
+    maintenance_version: u8,
+    encoding: &'static Encoding,
+) -> DxfResult<Vec<(u16, Class)>> {
+    let mut reader = DwgBitReader::new(data, version);
+    reader.set_position(16 * 8);
+    let size = reader.read_rl()? as usize;
+    let mut header_bits = 20 * 8;
+    if version >= AcadVersion::R2018 || (version >= AcadVersion::R2010 && maintenance_version > 3) {
+        reader.read_rl()?;
+        header_bits += 32;
+    }
+
+    let end = header_bits + size * 8;
+    let mut strings = None;
+    let mut class_count = None;
+    if version >= AcadVersion::R2004 {
+        if version >= AcadVersion::R2007 {
+            let bit_size = reader.read_rl()? as usize;
+            strings = string_stream(&reader, header_bits + bit_size)?;
+        }
+
+        let max_class_number = reader.read_bs()?;
+        reader.read_rc()?;
+        reader.read_rc()?;
+        reader.read_b()?;
+        class_count = Some((i32::from(max_class_number) - 499).max(0) as usize);
+    }
+
+    let mut classes = vec![];
+    loop {
+        match class_count {
+            Some(count) if classes.len() >= count => break,
+            None if reader.position() + 8 > end => break,
+            _ => (),
+        }
+
+        let number = reader.read_bs()? as u16;
+        let proxy_flags = reader.read_bs()?;
+        let application_name = read_text(&mut reader, &mut strings, encoding)?;
+        let class_name = read_text(&mut reader, &mut strings, encoding)?;
+        let record_name = read_text(&mut reader, &mut strings, encoding)?;
+        let was_zombie = reader.read_b()?;
+        let item_class_id = reader.read_bs()?;
+        let mut instance_count = 0;
+        if version >= AcadVersion::R2004 {
+            instance_count = reader.read_bl()?.max(0) as usize;
+            reader.read_bl()?; // dwg version
+            reader.read_bl()?; // maintenance version
+            reader.read_bl()?;
+            reader.read_bl()?;
+        }
+
+        classes.push((
+            number,
+            Class {
+                record_name,
+                class_name,
+                application_name,
+                version_number: 0,
+                proxy_capability_flags: i32::from(proxy_flags),
+                instance_count,
+                was_class_loaded_with_file: !was_zombie,
+                is_entity: item_class_id == 0x1F2,
+            },
+        ));
+    }
+
+    Ok(classes)
+}
+
+// handles in the header section that refer to table entries, resolved once the tables are read
+#[derive(Default)]
+struct DwgHeaderHandles {
+    current_layer: u64,
+    text_style: u64,
+    current_entity_line_type: u64,
+    dimension_style: u64,
+    dimension_text_style: u64,
+    dimension_leader_block: u64,
+    arrow_block: u64,
+    first_arrow_block: u64,
+    second_arrow_block: u64,
+    dimension_line_type: u64,
+    dimension_first_extension_line_type: u64,
+    dimension_second_extension_line_type: u64,
+}
+
+// handles in the header are part of the main data before R2007
+fn read_header_handle(reader: &mut DwgObjectReader) -> DxfResult<u64> {
+    if reader.version() >= AcadVersion::R2007 {
+        reader.handle_ref()
+    } else {
+        Ok(reader.main.read_h()?.absolute(0))
+    }
+}
+
+// dates are stored as a Julian day and the milliseconds into that day
+fn read_julian_date(reader: &mut DwgBitReader) -> DxfResult<f64> {
+    let days = f64::from(reader.read_bl()?);
+    let milliseconds = f64::from(reader.read_bl()?);
+    Ok(days + milliseconds / 86_400_000.0)
+}
+
+fn read_header(
+    data: &[u8],
+    version: AcadVersion,
+    maintenance_version: u8,
+    encoding: &'static Encoding,
+) -> DxfResult<(Header, DwgHeaderHandles)> {
+    let mut main = DwgBitReader::new(data, version);
+    main.set_position(16 * 8);
+    main.read_rl()?; // size
+    let mut header_bits = 20 * 8;
+    if version >= AcadVersion::R2018 || (version >= AcadVersion::R2010 && maintenance_version > 3) {
+        main.read_rl()?;
+        header_bits += 32;
+    }
+
+    let mut handles = main.clone();
+    let mut strings = None;
+    if version >= AcadVersion::R2007 {
+        let bit_size = main.read_rl()? as usize;
+        strings = string_stream(&main, header_bits + bit_size)?;
+        handles.set_position(header_bits + bit_size);
+    }
+    let mut reader = DwgObjectReader {
+        main,
+        strings,
+        handles,
+        handle: 0,
+        encoding,
+    };
+    let r = &mut reader;
+    let mut header = Header::default();
+    let mut names = DwgHeaderHandles::default();
+    let h = &mut header;
+
+    if version >= AcadVersion::R2013 {
+        h.required_versions = r.main.read_bll()? as i64;
+    }
+    for _ in 0..4 {
+        r.main.read_bd()?;
+    }
+    for _ in 0..4 {
+        r.text()?;
+    }
+    r.main.read_bl()?;
+    r.main.read_bl()?;
+    if version < AcadVersion::R2004 {
+        read_header_handle(r)?; // current viewport entity header
+    }
+
+    h.create_associative_dimensioning = r.main.read_b()?;
+    h.recompute_dimensions_while_dragging = r.main.read_b()?;
+    h.is_polyline_continuous_around_verticies = r.main.read_b()?;
+    h.draw_orthogonal_lines = r.main.read_b()?;
+    h.use_regen_mode = r.main.read_b()?;
+    h.fill_mode_on = r.main.read_b()?;
+    h.use_quick_text_mode = r.main.read_b()?;
+    h.scale_line_types_in_paperspace = r.main.read_b()?;
+    h.use_limits_checking = r.main.read_b()?;
+    if version >= AcadVersion::R2004 {
+        r.main.read_b()?;
+    }
+    h.user_timer_on = r.main.read_b()?;
+    h.polyline_sketch_mode = if r.main.read_b()? {
+        PolySketchMode::SketchPolylines
+    } else {
+        PolySketchMode::SketchLines
+    };
+    h.angle_direction = if r.main.read_b()? {
+        AngleDirection::Clockwise
+    } else {
+        AngleDirection::CounterClockwise
+    };
+    h.display_spline_polygon_control = r.main.read_b()?;
+    h.mirror_text = r.main.read_b()?;
+    h.set_ucs_to_wcs_in_d_view_or_v_point = r.main.read_b()?;
+    h.previous_release_tile_compatability = r.main.read_b()?;
+    h.limit_checking_in_paperspace = r.main.read_b()?;
+    h.retain_x_ref_dependent_visibility_settings = r.main.read_b()?;
+    h.display_silhouette_curves_in_wireframe_mode = r.main.read_b()?;
+    r.main.read_b()?; // PELLIPSE
+    h.save_proxy_graphics = r.main.read_bs()? != 0;
+    h.spacial_index_max_depth = r.main.read_bs()?;
+    h.unit_format = enum_from_number!(UnitFormat, Decimal, from_i16, r.main.read_bs()?);
+    h.unit_precision = r.main.read_bs()?;
+    h.angle_unit_format =
+        enum_from_number!(AngleFormat, DecimalDegrees, from_i16, r.main.read_bs()?);
+    h.angle_unit_precision = r.main.read_bs()?;
+    h.attribute_visibility =
+        enum_from_number!(AttributeVisibility, Normal, from_i16, r.main.read_bs()?);
+    h.point_display_mode = i32::from(r.main.read_bs()?);
+    if version >= AcadVersion::R2004 {
+        for _ in 0..3 {
+            r.main.read_bl()?;
+        }
+    }
+    h.user_int1 = r.main.read_bs()?;
+    h.user_int2 = r.main.read_bs()?;
+    h.user_int3 = r.main.read_bs()?;
+    h.user_int4 = r.main.read_bs()?;
+    h.user_int5 = r.main.read_bs()?;
+    h.line_segments_per_spline_patch = r.main.read_bs()?;
+    h.pedit_smooth_m_densith = r.main.read_bs()?;
+    h.pedit_smooth_n_densith = r.main.read_bs()?;
+    h.pedit_smooth_surface_type = enum_from_number!(
+        PolylineCurvedAndSmoothSurfaceType,
+        CubicBSpline,
+        from_i16,
+        r.main.read_bs()?
+    );
+    h.mesh_tabulations_in_first_direction = r.main.read_bs()?;
+    h.mesh_tabulations_in_second_direction = r.main.read_bs()?;
+    h.pedit_spline_curve_type = enum_from_number!(
+        PolylineCurvedAndSmoothSurfaceType,
+        CubicBSpline,
+        from_i16,
+        r.main.read_bs()?
+    );
+    h.edge_shading = enum_from_number!(
+        ShadeEdgeMode,
+        FacesInEntityColorEdgesInBlack,
+        from_i16,
+        r.main.read_bs()?
+    );
+    h.percent_ambient_to_diffuse = r.main.read_bs()?;
+    h.display_fractions_in_input = r.main.read_bs()? != 0;
+    h.maximum_active_viewports = r.main.read_bs()?;
+    r.main.read_bs()?; // ISOLINES
+    h.current_multiline_justification =
+        enum_from_number!(Justification, Top, from_i16, r.main.read_bs()?);
+    r.main.read_bs()?; // TEXTQLTY
+    h.line_type_scale = r.main.read_bd()?;
+    h.default_text_height = r.main.read_bd()?;
+    h.trace_width = r.main.read_bd()?;
+    h.sketch_record_increment = r.main.read_bd()?;
+    h.fillet_radius = r.main.read_bd()?;
+    h.thickness = r.main.read_bd()?;
+    h.angle_zero_direction = r.main.read_bd()?.to_degrees();
+    h.point_display_size = r.main.read_bd()?;
+    h.default_polyline_width = r.main.read_bd()?;
+    h.user_real1 = r.main.read_bd()?;
+    h.user_real2 = r.main.read_bd()?;
+    h.user_real3 = r.main.read_bd()?;
+    h.user_real4 = r.main.read_bd()?;
+    h.user_real5 = r.main.read_bd()?;
+    h.first_chamfer_distance = r.main.read_bd()?;
+    h.second_chamfer_distance = r.main.read_bd()?;
+    h.chamfer_length = r.main.read_bd()?;
+    h.chamfer_angle = r.main.read_bd()?;
+    r.main.read_bd()?; // FACETRES
+    h.current_multiline_scale = r.main.read_bd()?;
+    h.current_entity_line_type_scale = r.main.read_bd()?;
+    if version < AcadVersion::R2007 {
+        h.file_name = r.text()?;
+    }
+    h.creation_date = as_datetime_local(read_julian_date(&mut r.main)?);
+    h.update_date = as_datetime_local(read_julian_date(&mut r.main)?);
+    if version >= AcadVersion::R2004 {
+        for _ in 0..3 {
+            r.main.read_bl()?;
+        }
+    }
+    h.time_in_drawing = as_duration(read_julian_date(&mut r.main)?);
+    h.user_elapsed_timer = as_duration(read_julian_date(&mut r.main)?);
+    h.current_entity_color = color_values(&r.read_cmc()?).0;
+
+    // the next handle is always part of the main data
+    h.next_available_handle = Handle(r.main.read_h()?.absolute(0));
+    names.current_layer = read_header_handle(r)?;
+    names.text_style = read_header_handle(r)?;
+    names.current_entity_line_type = read_header_handle(r)?;
+    if version >= AcadVersion::R2007 {
+        h.current_material_handle = Handle(read_header_handle(r)?);
+    }
+    names.dimension_style = read_header_handle(r)?;
+    read_header_handle(r)?; // CMLSTYLE
+    h.viewport_view_scale_factor = r.main.read_bd()?;
+
+    // paper space
+    h.paperspace_insertion_base = r.main.read_3bd()?;
+    h.paperspace_minimum_drawing_extents = r.main.read_3bd()?;
+    h.paperspace_maximum_drawing_extents = r.main.read_3bd()?;
+    h.paperspace_minimum_drawing_limits = r.main.read_2rd()?;
+    h.paperspace_maximum_drawing_limits = r.main.read_2rd()?;
+    h.paperspace_elevation = r.main.read_bd()?;
+    h.paperspace_ucs_origin = r.main.read_3bd()?;
+    h.paperspace_x_axis = r.main.read_3bd_vector()?;
+    h.paperspace_y_axis = r.main.read_3bd_vector()?;
+    read_header_handle(r)?; // PUCSNAME
+    read_header_handle(r)?; // PUCSORTHOREF
+    h.paperspace_orthographic_view_type =
+        enum_from_number!(OrthographicViewType, None, from_i16, r.main.read_bs()?);
+    read_header_handle(r)?; // PUCSBASE
+    h.paperspace_ucs_origin_top = r.main.read_3bd()?;
+    h.paperspace_ucs_origin_bottom = r.main.read_3bd()?;
+    h.paperspace_ucs_origin_left = r.main.read_3bd()?;
+    h.paperspace_ucs_origin_right = r.main.read_3bd()?;
+    h.paperspace_ucs_origin_front = r.main.read_3bd()?;
+    h.paperspace_ucs_origin_back = r.main.read_3bd()?;
+
+    // model space
+    h.insertion_base = r.main.read_3bd()?;
+    h.minimum_drawing_extents = r.main.read_3bd()?;
+    h.maximum_drawing_extents = r.main.read_3bd()?;
+    h.minimum_drawing_limits = r.main.read_2rd()?;
+    h.maximum_drawing_limits = r.main.read_2rd()?;
+    h.elevation = r.main.read_bd()?;
+    h.ucs_origin = r.main.read_3bd()?;
+    h.ucs_x_axis = r.main.read_3bd_vector()?;
+    h.ucs_y_axis = r.main.read_3bd_vector()?;
+    read_header_handle(r)?; // UCSNAME
+    read_header_handle(r)?; // UCSORTHOREF
+    h.orthgraphic_view_type =
+        enum_from_number!(OrthographicViewType, None, from_i16, r.main.read_bs()?);
+    read_header_handle(r)?; // UCSBASE
+    h.ucs_origin_top = r.main.read_3bd()?;
+    h.ucs_origin_bottom = r.main.read_3bd()?;
+    h.ucs_origin_left = r.main.read_3bd()?;
+    h.ucs_origin_right = r.main.read_3bd()?;
+    h.ucs_origin_front = r.main.read_3bd()?;
+    h.ucs_origin_back = r.main.read_3bd()?;
+
+    // dimensions
+    h.dimensioning_suffix = r.text()?;
+    h.alternate_dimensioning_suffix = r.text()?;
+    h.dimensioning_scale_factor = r.main.read_bd()?;
+    h.dimensioning_arrow_size = r.main.read_bd()?;
+    h.dimension_extension_line_offset = r.main.read_bd()?;
+    h.dimension_line_increment = r.main.read_bd()?;
+    h.dimension_extension_line_extension = r.main.read_bd()?;
+    h.dimension_distance_rounding_value = r.main.read_bd()?;
+    h.dimension_line_extension = r.main.read_bd()?;
+    h.dimension_plus_tolerance = r.main.read_bd()?;
+    h.dimension_minus_tolerance = r.main.read_bd()?;
+    if version >= AcadVersion::R2007 {
+        h.dimension_line_fixed_length = r.main.read_bd()?;
+        h.dimension_transverse_segment_angle_in_jogged_radius = r.main.read_bd()?;
+        h.dimension_text_background_color_mode = enum_from_number!(
+            DimensionTextBackgroundColorMode,
+            None,
+            from_i16,
+            r.main.read_bs()?
+        );
+        h.dxf_dimension_text_background_custom_color = color_values(&r.read_cmc()?).0;
+    }
+    h.generate_dimension_tolerances = r.main.read_b()?;
+    h.generate_dimension_limits = r.main.read_b()?;
+    h.dimension_text_inside_horizontal = r.main.read_b()?;
+    h.dimension_text_outside_horizontal = r.main.read_b()?;
+    h.suppress_first_dimension_extension_line = r.main.read_b()?;
+    h.suppress_second_dimension_extension_line = r.main.read_b()?;
+    h.text_above_dimension_line = r.main.read_bs()? != 0;
+    h.dimension_unit_zero_suppression = enum_from_number!(
+        UnitZeroSuppression,
+        SuppressZeroFeetAndZeroInches,
+        from_i16,
+        r.main.read_bs()?
+    );
+    h.dimension_angle_zero_suppression = enum_from_number!(
+        UnitZeroSuppression,
+        SuppressZeroFeetAndZeroInches,
+        from_i16,
+        r.main.read_bs()?
+    );
+    if version >= AcadVersion::R2007 {
+        h.dimension_arc_symbol_display_mode = enum_from_number!(
+            DimensionArcSymbolDisplayMode,
+            SymbolBeforeText,
+            from_i16,
+            r.main.read_bs()?
+        );
+    }
+    h.dimensioning_text_height = r.main.read_bd()?;
+    h.center_mark_size = r.main.read_bd()?;
+    h.dimensioning_tick_size = r.main.read_bd()?;
+    h.alternate_dimensioning_scale_factor = r.main.read_bd()?;
+    h.dimension_linear_measurements_scale_factor = r.main.read_bd()?;
+    h.dimension_vertical_text_position = r.main.read_bd()?;
+    h.dimension_tolerance_display_scale_factor = r.main.read_bd()?;
+    h.dimension_line_gap = r.main.read_bd()?;
+    h.alternate_dimensioning_unit_rounding = r.main.read_bd()?;
+    h.use_alternate_dimensioning = r.main.read_b()?;
+    h.alternate_dimensioning_decimal_places = r.main.read_bs()?;
+    h.force_dimension_line_extensions_outside_if_text_is = r.main.read_b()?;
+    h.use_separate_arrow_blocks_for_dimensions = r.main.read_b()?;
+    h.force_dimension_text_inside_extensions = r.main.read_b()?;
+    h.suppress_outside_extension_dimension_lines = r.main.read_b()?;
+    h.dimension_line_color = color_values(&r.read_cmc()?).0;
+    h.dimension_extension_line_color = color_values(&r.read_cmc()?).0;
+    h.dimension_text_color = color_values(&r.read_cmc()?).0;
+    h.angular_dimension_precision = r.main.read_bs()?;
+    h.dimension_unit_tolerance_decimal_places = r.main.read_bs()?;
+    h.dimension_tolerance_decimal_places = r.main.read_bs()?;
+    h.alternate_dimensioning_units =
+        enum_from_number!(UnitFormat, Decimal, from_i16, r.main.read_bs()?);
+    h.alternate_dimensioning_tolerance_decimal_places = r.main.read_bs()?;
+    h.dimensioning_angle_format =
+        enum_from_number!(AngleFormat, DecimalDegrees, from_i16, r.main.read_bs()?);
+    h.dimension_text_height_scale_factor = enum_from_number!(
+        DimensionFractionFormat,
+        HorizontalStacking,
+        from_i16,
+        r.main.read_bs()?
+    );
+    h.dimension_non_angular_units =
+        enum_from_number!(NonAngularUnits, Decimal, from_i16, r.main.read_bs()?);
+    h.dimension_decimal_separator_char = r.main.read_bs()? as u8 as char;
+    h.dimension_text_movement_rule = enum_from_number!(
+        DimensionTextMovementRule,
+        MoveLineWithText,
+        from_i16,
+        r.main.read_bs()?
+    );
+    h.dimension_text_justification = enum_from_number!(
+        DimensionTextJustification,
+        AboveLineCenter,
+        from_i16,
+        r.main.read_bs()?
+    );
+    r.main.read_b()?; // DIMSD1
+    r.main.read_b()?; // DIMSD2
+    h.dimension_tolerance_vertical_justification =
+        enum_from_number!(Justification, Middle, from_i16, r.main.read_bs()?);
+    h.dimension_tolerance_zero_suppression = enum_from_number!(
+        UnitZeroSuppression,
+        SuppressZeroFeetAndZeroInches,
+        from_i16,
+        r.main.read_bs()?
+    );
+    h.alternate_dimensioning_zero_supression = enum_from_number!(
+        UnitZeroSuppression,
+        SuppressZeroFeetAndZeroInches,
+        from_i16,
+        r.main.read_bs()?
+    );
+    h.alternate_dimensioning_tolerance_zero_supression = enum_from_number!(
+        UnitZeroSuppression,
+        SuppressZeroFeetAndZeroInches,
+        from_i16,
+        r.main.read_bs()?
+    );
+    h.dimension_cursor_controls_text_position = r.main.read_b()?;
+    h.dimension_text_and_arrow_placement = enum_from_number!(
+        DimensionFit,
+        MoveEitherForBestFit,
+        from_i16,
+        r.main.read_bs()?
+    );
+    if version >= AcadVersion::R2007 {
+        h.dimension_line_fixed_length_on = r.main.read_b()?;
+    }
+    if version >= AcadVersion::R2010 {
+        h.dimension_text_direction = if r.main.read_b()? {
+            TextDirection::RightToLeft
+        } else {
+            TextDirection::LeftToRight
+        };
+        r.main.read_bd()?; // DIMALTMZF
+        r.text()?; // DIMALTMZS
+        r.main.read_bd()?; // DIMMZF
+        r.text()?; // DIMMZS
+    }
+    names.dimension_text_style = read_header_handle(r)?;
+    names.dimension_leader_block = read_header_handle(r)?;
+    names.arrow_block = read_header_handle(r)?;
+    names.first_arrow_block = read_header_handle(r)?;
+    names.second_arrow_block = read_header_handle(r)?;
+    if version >= AcadVersion::R2007 {
+        names.dimension_line_type = read_header_handle(r)?;
+        names.dimension_first_extension_line_type = read_header_handle(r)?;
+        names.dimension_second_extension_line_type = read_header_handle(r)?;
+    }
+    h.dimension_line_weight = LineWeight::from_raw_value(r.main.read_bs()?);
+    h.dimension_extension_line_weight = LineWeight::from_raw_value(r.main.read_bs()?);
+
+    // table control objects and the root dictionaries
+    let mut object_handles = 12;
+    if version <= AcadVersion::R2000 {
+        object_handles += 1;
+    }
+    for _ in 0..object_handles {
+        read_header_handle(r)?;
+    }
+    r.main.read_bs()?; // TSTACKALIGN
+    r.main.read_bs()?; // TSTACKSIZE
+    h.hyperlink_base = r.text()?;
+    h.stylesheet = r.text()?;
+    let mut dictionary_handles = 3;
+    if version >= AcadVersion::R2004 {
+        dictionary_handles += 2;
+    }
+    if version >= AcadVersion::R2007 {
+        dictionary_handles += 1;
+    }
+    if version >= AcadVersion::R2013 {
+        dictionary_handles += 1;
+    }
+    for _ in 0..dictionary_handles {
+        read_header_handle(r)?;
+    }
+
+    let flags = r.main.read_bl()?;
+    h.new_object_line_weight = LineWeight::from_raw_value(line_weight_value((flags & 0x1F) as u8));
+    h.end_cap_setting =
+        enum_from_number!(EndCapSetting, None, from_i16, ((flags & 0x60) >> 5) as i16);
+    h.lineweight_joint_setting =
+        enum_from_number!(JoinStyle, None, from_i16, ((flags & 0x180) >> 7) as i16);
+    h.display_linewieght_in_model_and_layout_tab = flags & 0x200 == 0;
+    h.can_use_in_place_reference_editing = flags & 0x400 == 0;
+    h.use_acad2000_symbol_table_naming = flags & 0x800 != 0;
+    h.uses_color_dependent_plot_style_tables = flags & 0x2000 != 0;
+    h.ole_startup = flags & 0x4000 != 0;
+    h.default_drawing_units = enum_from_number!(Units, Unitless, from_i16, r.main.read_bs()?);
+    h.new_object_plot_style = enum_from_number!(PlotStyle, ByLayer, from_i16, r.main.read_bs()?);
+    if h.new_object_plot_style == PlotStyle::ByObjectId {
+        h.new_object_plot_style_handle = Handle(read_header_handle(r)?);
+    }
+    h.fingerprint_guid = as_uuid(r.text()?);
+    h.version_guid = as_uuid(r.text()?);
+    if version >= AcadVersion::R2004 {
+        h.object_sorting_methods_flags = i32::from(r.main.read_rc()?);
+        h.layer_and_spatial_index_save_mode = enum_from_number!(
+            LayerAndSpatialIndexSaveMode,
+            None,
+            from_i16,
+            i16::from(r.main.read_rc()?)
+        );
+        h.hide_text_objects_when_producint_hidden_view = r.main.read_rc()? != 0;
+        h.is_x_ref_clipping_boundary_visible = enum_from_number!(
+            XrefClippingBoundaryVisibility,
+            DisplayedNotPlotted,
+            from_i16,
+            i16::from(r.main.read_rc()?)
+        );
+        h.dimension_object_associativity = enum_from_number!(
+            DimensionAssociativity,
+            NonAssociativeObjects,
+            from_i16,
+            i16::from(r.main.read_rc()?)
+        );
+        h.halo_gap_percent = f64::from(r.main.read_rc()?);
+        h.obscured_line_color = Color::from_raw_value(r.main.read_bs()?);
+        h.intersection_polyline_color = Color::from_raw_value(r.main.read_bs()?);
+        h.obscured_line_type_style =
+            enum_from_number!(LineTypeStyle, Off, from_i16, i16::from(r.main.read_rc()?));
+        h.display_intersection_polylines = r.main.read_rc()? != 0;
+        h.project_name = r.text()?;
+    }
+
+    // the layout and line type handles and the R2007 camera, lofting and location values that
+    // follow aren't read
+    Ok((header, names))
+}
+
+/// Reads the main data, strings and handles of a single object.
+struct DwgObjectReader<'a> {
+    main: DwgBitReader<'a>,
+    strings: Option<DwgBitReader<'a>>,
+    handles: DwgBitReader<'a>,
+    handle: u64,
+    encoding: &'static Encoding,
+}
+
+impl DwgObjectReader<'_> {
+    fn version(&self) -> AcadVersion {
+        self.main.version
+    }
+    fn text(&mut self) -> DxfResult<String> {
+        read_text(&mut self.main, &mut self.strings, self.encoding)
+    }
+    fn handle_ref(&mut self) -> DxfResult<u64> {
+        Ok(self.handles.read_h()?.absolute(self.handle))
+    }
+    fn skip_handles(&mut self, count: i32) -> DxfResult<()> {
+        for _ in 0..count.max(0) {
+            self.handles.read_h()?;
+        }
+
+        Ok(())
+    }
+    // CMC: the color of a non-entity object
+    fn read_cmc(&mut self) -> DxfResult<DwgEntityColor> {
+        let mut color = DwgEntityColor {
+            index: self.main.read_bs()?,
+            ..Default::default()
+        };
+        if self.version() >= AcadVersion::R2004 {
+            color.rgb = self.main.read_bl()? as u32;
+            color.flags = 0x80;
+            let name_flags = self.main.read_rc()?;
+            if name_flags & 0x01 != 0 {
+                self.text()?;
+            }
+            if name_flags & 0x02 != 0 {
+                self.text()?;
+            }
+        }
+
+        Ok(color)
+    }
+    // reads the data common to all non-entity objects and returns the owner handle
+    fn read_object_common(&mut self) -> DxfResult<u64> {
+        let reactor_count = self.main.read_bl()?;
+        let xdictionary_missing = if self.version() >= AcadVersion::R2004 {
+            self.main.read_b()?
+        } else {
+            false
+        };
+        if self.version() >= AcadVersion::R2013 {
+            self.main.read_b()?; // has binary data
+        }
+
+        let owner = self.handle_ref()?;
+        self.skip_handles(reactor_count)?;
+        if !xdictionary_missing {
+            self.handle_ref()?;
+        }
+
+        Ok(owner)
+    }
+    // reads the name and flags common to all table entries, then the external reference handle
+    fn read_table_entry_common(&mut self) -> DxfResult<(u64, String)> {
+        let owner = self.read_object_common()?;
+        let name = self.text()?;
+        self.main.read_b()?; // 64-flag
+        self.main.read_bs()?; // xref index + 1
+        self.main.read_b()?; // xref dependent
+        self.handle_ref()?;
+        Ok((owner, name))
+    }
+}
+
+// reads the type of the object at `location` and positions a reader after its common data
+fn open_object<'a>(
+    data: &'a [u8],
+    handle: u64,
+    location: usize,
+    version: AcadVersion,
+    encoding: &'static Encoding,
+) -> DxfResult<(u16, DwgObjectReader<'a>)> {
+    let mut reader = DwgBitReader::new(data, version);
+    reader.set_position(location * 8);
+    let size = reader.read_ms()? as usize;
+    let handle_bits = if version >= AcadVersion::R2010 {
+        reader.read_umc()? as usize
+    } else {
+        0
+    };
+    let object_data = slice(data, reader.position() / 8, size)?;
+
+    let mut main = DwgBitReader::new(object_data, version);
+    let object_type = main.read_ot()?;
+    let bit_size = if version >= AcadVersion::R2010 {
+        (size * 8)
+            .checked_sub(handle_bits)
+            .ok_or(DxfError::InvalidBinaryFile)?
+    } else {
+        main.read_rl()? as usize
+    };
+    main.read_h()?;
+
+    // extended data is skipped
+    loop {
+        let length = main.read_bs()? as u16 as usize;
+        if length == 0 {
+            break;
+        }
+        main.read_h()?;
+        main.read_bytes(length)?;
+    }
+
+    let mut handles = main.clone();
+    handles.set_position(bit_size);
+    let strings = if version >= AcadVersion::R2007 {
+        string_stream(&main, bit_size)?
+    } else {
+        None
+    };
+    let reader = DwgObjectReader {
+        main,
+        strings,
+        handles,
+        handle,
+        encoding,
+    };
+    Ok((object_type, reader))
+}
+
+fn read_object(reader: &mut DwgObjectReader, object_type: u16) -> DxfResult<Option<DwgObject>> {
+    let handle = reader.handle;
+    let (owner, data) = match object_type {
+        TYPE_BLOCK_HEADER => {
+            let (owner, header) = read_block_header(reader)?;
+            (owner, DwgObjectData::BlockHeader(Box::new(header)))
+        }
+        TYPE_LAYER => {
+            let (owner, layer, line_type) = read_layer(reader)?;
+            (owner, DwgObjectData::Layer(layer, line_type))
+        }
+        TYPE_LTYPE => {
+            let (owner, line_type) = read_line_type(reader)?;
+            (owner, DwgObjectData::LineType(line_type))
+        }
+        TYPE_STYLE => {
+            let (owner, style) = read_style(reader)?;
+            (owner, DwgObjectData::Style(style))
+        }
+        TYPE_APPID => {
+            let (owner, name) = reader.read_table_entry_common()?;
+            let mut app_id = AppId {
+                name,
+                handle: Handle(handle),
+                ..Default::default()
+            };
+            app_id.__owner_handle = Handle(owner);
+            (owner, DwgObjectData::AppId(app_id))
+        }
+        TYPE_DIMSTYLE => {
+            let (owner, dim_style) = read_dim_style(reader)?;
+            (owner, DwgObjectData::DimStyle(Box::new(dim_style)))
+        }
+        TYPE_DICTIONARY => {
+            let (owner, dictionary) = read_dictionary(reader)?;
+            (owner, DwgObjectData::Dictionary(dictionary))
+        }
+        TYPE_XRECORD => {
+            let (owner, xrecord) = read_xrecord(reader)?;
+            (owner, DwgObjectData::XRecord(xrecord))
+        }
+        TYPE_GROUP => {
+            let (owner, group) = read_group(reader)?;
+            (owner, DwgObjectData::Group(group))
+        }
+        TYPE_MLINESTYLE => {
+            let (owner, style) = read_mline_style(reader)?;
+            (owner, DwgObjectData::MLineStyle(Box::new(style)))
+        }
+        _ if !is_entity_type(object_type) => return Ok(None),
+        _ => match read_entity(reader, object_type)? {
+            Some(entity) => (
+                entity.entity.common.__owner_handle.0,
+                DwgObjectData::Entity(Box::new(entity)),
+            ),
+            None => return Ok(None),
+        },
+    };
+
+    Ok(Some(DwgObject {
+        handle,
+        owner,
+        data,
+    }))
+}
+
+fn line_weight_value(value: u8) -> i16 {
+    match value {
+        29 => LineWeight::by_layer().raw_value(),
+        30 => LineWeight::by_block().raw_value(),
+        31 => -3,
+        _ => LINE_WEIGHTS.get(usize::from(value)).cloned().unwrap_or(0),
+    }
+}
+
+// returns the indexed color and the 24-bit color, if any
+fn color_values(color: &DwgEntityColor) -> (Color, i32) {
+    if color.has_rgb() {
+        let rgb = color.rgb & 0x00FF_FFFF;
+        match color.rgb >> 24 {
+            0xC0 => return (Color::by_layer(), 0),
+            0xC1 => return (Color::by_block(), 0),
+            0xC2 => {
+                let nearest =
+                    Color::from_rgb_nearest((rgb >> 16) as u8, (rgb >> 8) as u8, rgb as u8);
+                return (nearest, rgb as i32);
+            }
+            0xC3 => return (Color::from_raw_value((rgb & 0xFF) as i16), 0),
+            _ => (),
+        }
+    }
+
+    (Color::from_raw_value(color.index), 0)
+}
+
+fn read_entity(reader: &mut DwgObjectReader, object_type: u16) -> DxfResult<Option<DwgEntity>> {
+    let version = reader.version();
+
+    // preview graphics are only kept as the graphics of proxy entities
+    let mut preview = vec![];
+    if reader.main.read_b()? {
+        let size = if version >= AcadVersion::R2010 {
+            reader.main.read_bll()? as usize
+        } else {
+            reader.main.read_rl()? as usize
+        };
+        if object_type == TYPE_PROXY_ENTITY {
+            preview = reader.main.read_bytes(size)?;
+        } else {
+            let position = reader.main.position() + size * 8;
+            reader.main.set_position(position);
+        }
+    }
+
+    let mode = reader.main.read_bb()?;
+    let reactor_count = reader.main.read_bl()?;
+    let xdictionary_missing = if version >= AcadVersion::R2004 {
+        reader.main.read_b()?
+    } else {
+        false
+    };
+    if version >= AcadVersion::R2013 {
+        reader.main.read_b()?; // has binary data
+    }
+    let no_links = if version <= AcadVersion::R2000 {
+        reader.main.read_b()?
+    } else {
+        true
+    };
+    let color = if version >= AcadVersion::R2004 {
+        reader.main.read_enc()?
+    } else {
+        DwgEntityColor {
+            index: reader.main.read_bs()?,
+            ..Default::default()
+        }
+    };
+    let line_type_scale = reader.main.read_bd()?;
+    let line_type_flags = reader.main.read_bb()?;
+    let plot_style_flags = reader.main.read_bb()?;
+    let mut material_flags = 0;
+    if version >= AcadVersion::R2007 {
+        material_flags = reader.main.read_bb()?;
+        reader.main.read_rc()?; // shadow flags
+    }
+    let mut visual_style_count = 0;
+    if version >= AcadVersion::R2010 {
+        for _ in 0..3 {
+            if reader.main.read_b()? {
+                visual_style_count += 1;
+            }
+        }
+    }
+    let invisibility = reader.main.read_bs()?;
+    let line_weight = reader.main.read_rc()?;
+
+    let owner = if mode == 0 { reader.handle_ref()? } else { 0 };
+    reader.skip_handles(reactor_count)?;
+    if !xdictionary_missing {
+        reader.handle_ref()?;
+    }
+    if !no_links {
+        reader.skip_handles(2)?;
+    }
+    if version >= AcadVersion::R2004 && color.has_color_book_handle() {
+        reader.handle_ref()?;
+    }
+    let layer = reader.handle_ref()?;
+    let line_type = match line_type_flags {
+        0 => DwgLineTypeRef::ByLayer,
+        1 => DwgLineTypeRef::ByBlock,
+        2 => DwgLineTypeRef::Continuous,
+        _ => DwgLineTypeRef::Handle(reader.handle_ref()?),
+    };
+    let material = if material_flags == 3 {
+        reader.handle_ref()?
+    } else {
+        0
+    };
+    let plot_style = if plot_style_flags == 3 {
+        reader.handle_ref()?
+    } else {
+        0
+    };
+    reader.skip_handles(visual_style_count)?;
+
+    let mut style = None;
+    let mut block = None;
+    let mut elevation = 0.0;
+    let specific = match object_type {
+        TYPE_TEXT => {
+            let text = read_text_entity(reader)?;
+            style = Some(reader.handle_ref()?);
+            EntityType::Text(text)
+        }
+        TYPE_ATTRIB => {
+            let att = read_attribute(reader)?;
+            style = Some(reader.handle_ref()?);
+            EntityType::Attribute(att)
+        }
+        TYPE_ATTDEF => {
+            let att = read_attribute_definition(reader)?;
+            style = Some(reader.handle_ref()?);
+            EntityType::AttributeDefinition(att)
+        }
+        TYPE_BLOCK | TYPE_ENDBLK | TYPE_SEQEND => EntityType::Seqend(Default::default()),
+        TYPE_INSERT | TYPE_MINSERT => {
+            let (insert, block_header) = read_insert(reader, object_type == TYPE_MINSERT)?;
+            block = Some(block_header);
+            EntityType::Insert(insert)
+        }
+        TYPE_VERTEX_2D => EntityType::Vertex(read_vertex_2d(reader)?),
+        TYPE_VERTEX_3D | TYPE_VERTEX_MESH | TYPE_VERTEX_PFACE => {
+            let flags = i32::from(reader.main.read_rc()?);
+            let location = reader.main.read_3bd()?;
+            EntityType::Vertex(Vertex {
+                location,
+                flags,
+                ..Default::default()
+            })
+        }
+        TYPE_VERTEX_PFACE_FACE => {
+            let mut vertex = Vertex {
+                flags: 128,
+                ..Default::default()
+            };
+            vertex.polyface_mesh_vertex_index1 = i32::from(reader.main.read_bs()?);
+            vertex.polyface_mesh_vertex_index2 = i32::from(reader.main.read_bs()?);
+            vertex.polyface_mesh_vertex_index3 = i32::from(reader.main.read_bs()?);
+            vertex.polyface_mesh_vertex_index4 = i32::from(reader.main.read_bs()?);
+            EntityType::Vertex(vertex)
+        }
+        TYPE_POLYLINE_2D | TYPE_POLYLINE_3D | TYPE_POLYLINE_PFACE | TYPE_POLYLINE_MESH => {
+            EntityType::Polyline(read_polyline(reader, object_type)?)
+        }
+        TYPE_ARC => {
+            let center = reader.main.read_3bd()?;
+            let radius = reader.main.read_bd()?;
+            let thickness = reader.main.read_bt()?;
+            let normal = reader.main.read_be()?;
+            let start_angle = reader.main.read_bd()?.to_degrees();
+            let end_angle = reader.main.read_bd()?.to_degrees();
+            EntityType::Arc(Arc {
+                thickness,
+                center,
+                radius,
+                normal,
+                start_angle,
+                end_angle,
+            })
+        }
+        TYPE_CIRCLE => {
+            let center = reader.main.read_3bd()?;
+            let radius = reader.main.read_bd()?;
+            let thickness = reader.main.read_bt()?;
+            let normal = reader.main.read_be()?;
+            EntityType::Circle(Circle {
+                thickness,
+                center,
+                radius,
+                normal,
+            })
+        }
+        TYPE_LINE => EntityType::Line(read_line(reader)?),
+        TYPE_DIMENSION_ORDINATE
+        | TYPE_DIMENSION_LINEAR
+        | TYPE_DIMENSION_ALIGNED
+        | TYPE_DIMENSION_ANG3PT
+        | TYPE_DIMENSION_RADIUS
+        | TYPE_DIMENSION_DIAMETER => {
+            let dimension = read_dimension(reader, object_type)?;
+            style = Some(reader.handle_ref()?);
+            block = Some(reader.handle_ref()?);
+            dimension
+        }
+        TYPE_POINT => {
+            let location = reader.main.read_3bd()?;
+            let thickness = reader.main.read_bt()?;
+            let extrusion_direction = reader.main.read_be()?;
+            let angle = reader.main.read_bd()?.to_degrees();
+            EntityType::ModelPoint(ModelPoint {
+                location,
+                thickness,
+                extrusion_direction,
+                angle,
+            })
+        }
+        TYPE_3DFACE => EntityType::Face3D(read_face_3d(reader)?),
+        TYPE_SOLID | TYPE_TRACE => {
+            let thickness = reader.main.read_bt()?;
+            let elevation = reader.main.read_bd()?;
+            let mut corners = vec![];
+            for _ in 0..4 {
+                let p = reader.main.read_2rd()?;
+                corners.push(Point::new(p.x, p.y, elevation));
+            }
+            let extrusion_direction = reader.main.read_be()?;
+            if object_type == TYPE_SOLID {
+                EntityType::Solid(Solid {
+                    first_corner: corners[0].clone(),
+                    second_corner: corners[1].clone(),
+                    third_corner: corners[2].clone(),
+                    fourth_corner: corners[3].clone(),
+                    thickness,
+                    extrusion_direction,
+                })
+            } else {
+                EntityType::Trace(Trace {
+                    first_corner: corners[0].clone(),
+                    second_corner: corners[1].clone(),
+                    third_corner: corners[2].clone(),
+                    fourth_corner: corners[3].clone(),
+                    thickness,
+                    extrusion_direction,
+                })
+            }
+        }
+        TYPE_ELLIPSE => {
+            let center = reader.main.read_3bd()?;
+            let major_axis = reader.main.read_3bd_vector()?;
+            let normal = reader.main.read_3bd_vector()?;
+            let minor_axis_ratio = reader.main.read_bd()?;
+            let start_parameter = reader.main.read_bd()?;
+            let end_parameter = reader.main.read_bd()?;
+            EntityType::Ellipse(Ellipse {
+                center,
+                major_axis,
+                normal,
+                minor_axis_ratio,
+                start_parameter,
+                end_parameter,
+            })
+        }
+        TYPE_SPLINE => EntityType::Spline(read_spline(reader)?),
+        TYPE_RAY => {
+            let start_point = reader.main.read_3bd()?;
+            let unit_direction_vector = reader.main.read_3bd_vector()?;
+            EntityType::Ray(Ray {
+                start_point,
+                unit_direction_vector,
+            })
+        }
+        TYPE_XLINE => {
+            let first_point = reader.main.read_3bd()?;
+            let unit_direction_vector = reader.main.read_3bd_vector()?;
+            EntityType::XLine(XLine {
+                first_point,
+                unit_direction_vector,
+            })
+        }
+        TYPE_MTEXT => {
+            let mtext = read_mtext(reader)?;
+            style = Some(reader.handle_ref()?);
+            EntityType::MText(mtext)
+        }
+        TYPE_TOLERANCE => {
+            let insertion_point = reader.main.read_3bd()?;
+            let direction_vector = reader.main.read_3bd_vector()?;
+            let extrusion_direction = reader.main.read_3bd_vector()?;
+            let display_text = reader.text()?;
+            style = Some(reader.handle_ref()?);
+            EntityType::Tolerance(Tolerance {
+                insertion_point,
+                display_text,
+                extrusion_direction,
+                direction_vector,
+                ..Default::default()
+            })
+        }
+        TYPE_LWPOLYLINE => {
+            let (poly, poly_elevation) = read_lw_polyline(reader)?;
+            elevation = poly_elevation;
+            EntityType::LwPolyline(poly)
+        }
+        TYPE_SHAPE => {
+            let shape = read_shape(reader)?;
+            reader.handle_ref()?; // shape file
+            EntityType::Shape(shape)
+        }
+        TYPE_REGION | TYPE_3DSOLID | TYPE_BODY => {
+            // SAT text that's stored as binary SAB isn't decoded
+            let Some(sat_text) = read_sat_text(reader)? else {
+                return Ok(None);
+            };
+            match object_type {
+                TYPE_REGION => {
+                    let mut region = Region::default();
+                    region.set_sat_text(&sat_text, version);
+                    EntityType::Region(region)
+                }
+                TYPE_3DSOLID => {
+                    let mut solid = Solid3D::default();
+                    solid.set_sat_text(&sat_text, version);
+                    EntityType::Solid3D(solid)
+                }
+                _ => {
+                    let mut body = Body::default();
+                    body.set_sat_text(&sat_text, version);
+                    EntityType::Body(body)
+                }
+            }
+        }
+        TYPE_OLEFRAME => {
+            let version_number = i32::from(reader.main.read_bs()?);
+            reader.main.read_bs()?; // mode
+            let data = read_ole_data(reader)?;
+            EntityType::OleFrame(OleFrame {
+                version_number,
+                binary_data_length: data.len() as i32,
+                binary_data_strings: data.chunks(127).map(|c| c.to_vec()).collect(),
+            })
+        }
+        TYPE_OLE2FRAME => {
+            let version_number = i32::from(reader.main.read_bs()?);
+            let tile_mode = reader.main.read_bs()?;
+            let data = read_ole_data(reader)?;
+            EntityType::Ole2Frame(Ole2Frame {
+                version_number,
+                tile_mode: enum_from_number!(
+                    TileModeDescriptor,
+                    InTiledViewport,
+                    from_i16,
+                    tile_mode
+                ),
+                binary_data_length: data.len() as i32,
+                binary_data_strings: data.chunks(127).map(|c| c.to_vec()).collect(),
+                ..Default::default()
+            })
+        }
+        TYPE_LEADER => {
+            let leader = read_leader(reader)?;
+            reader.handle_ref()?; // associated annotation
+            style = Some(reader.handle_ref()?);
+            EntityType::Leader(leader)
+        }
+        TYPE_MLINE => {
+            let mut mline = read_mline(reader)?;
+            mline.__style_handle = Handle(reader.handle_ref()?);
+            EntityType::MLine(mline)
+        }
+        TYPE_IMAGE => {
+            let image = read_image(reader)?;
+            EntityType::Image(Image {
+                class_version: image.class_version,
+                location: image.location,
+                u_vector: image.u_vector,
+                v_vector: image.v_vector,
+                image_size: image.image_size,
+                image_def_reference: Handle(reader.handle_ref()?).as_string(),
+                display_options_flags: image.display_options_flags,
+                use_clipping: image.use_clipping,
+                brightness: image.brightness,
+                contrast: image.contrast,
+                fade: image.fade,
+                image_def_reactor_reference: Handle(reader.handle_ref()?).as_string(),
+                clipping_type: image.clipping_type,
+                clipping_vertex_count: image.clipping_vertices.len() as i32,
+                clipping_vertices: image.clipping_vertices,
+                is_inside_clipping: image.is_inside_clipping,
+                ..Default::default()
+            })
+        }
+        TYPE_WIPEOUT => {
+            let image = read_image(reader)?;
+            EntityType::Wipeout(Wipeout {
+                class_version: image.class_version,
+                location: image.location,
+                u_vector: image.u_vector,
+                v_vector: image.v_vector,
+                image_size: image.image_size,
+                image_def_reference: Handle(reader.handle_ref()?).as_string(),
+                display_options_flags: image.display_options_flags,
+                use_clipping: image.use_clipping,
+                brightness: image.brightness,
+                contrast: image.contrast,
+                fade: image.fade,
+                image_def_reactor_reference: Handle(reader.handle_ref()?).as_string(),
+                clipping_type: image.clipping_type,
+                clipping_vertex_count: image.clipping_vertices.len() as i32,
+                clipping_vertices: image.clipping_vertices,
+                is_inside_clipping: image.is_inside_clipping,
+                ..Default::default()
+            })
+        }
+        TYPE_HELIX => EntityType::Helix(read_helix(reader)?),
+        TYPE_LIGHT => EntityType::Light(read_light(reader)?),
+        TYPE_PDF_UNDERLAY => {
+            let u = read_underlay(reader)?;
+            EntityType::PdfUnderlay(PdfUnderlay {
+                __object_handle: Handle(reader.handle_ref()?),
+                insertion_point: u.insertion_point,
+                x_scale: u.x_scale,
+                y_scale: u.y_scale,
+                z_scale: u.z_scale,
+                rotation_angle: u.rotation_angle,
+                normal: u.normal,
+                flags: u.flags,
+                contrast: u.contrast,
+                fade: u.fade,
+                points: u.points,
+                ..Default::default()
+            })
+        }
+        TYPE_DWF_UNDERLAY => {
+            let u = read_underlay(reader)?;
+            EntityType::DwfUnderlay(DwfUnderlay {
+                __object_handle: Handle(reader.handle_ref()?),
+                insertion_point: u.insertion_point,
+                x_scale: u.x_scale,
+                y_scale: u.y_scale,
+                z_scale: u.z_scale,
+                rotation_angle: u.rotation_angle,
+                normal: u.normal,
+                flags: u.flags,
+                contrast: u.contrast,
+                fade: u.fade,
+                points: u.points,
+                ..Default::default()
+            })
+        }
+        TYPE_DGN_UNDERLAY => {
+            let u = read_underlay(reader)?;
+            EntityType::DgnUnderlay(DgnUnderlay {
+                __object_handle: Handle(reader.handle_ref()?),
+                insertion_point: u.insertion_point,
+                x_scale: u.x_scale,
+                y_scale: u.y_scale,
+                z_scale: u.z_scale,
+                rotation_angle: u.rotation_angle,
+                normal: u.normal,
+                flags: u.flags,
+                contrast: u.contrast,
+                fade: u.fade,
+                points: u.points,
+                ..Default::default()
+            })
+        }
+        TYPE_PROXY_ENTITY => {
+            let mut proxy = read_proxy_entity(reader)?;
+            proxy.graphics_data_size = preview.len() as i32;
+            proxy.graphics_data_string = preview.chunks(127).map(|c| c.to_vec()).collect();
+            EntityType::ProxyEntity(proxy)
+        }
+        // there's no `HATCH` entity to read them into
+        TYPE_HATCH => return Ok(None),
+        _ => return Ok(None),
+    };
+
+    let mut entity = Entity::new(specific);
+    let common = &mut entity.common;
+    common.handle = Handle(reader.handle);
+    common.__owner_handle = Handle(owner);
+    common.is_in_paper_space = mode == 1;
+    let (indexed_color, color_24_bit) = color_values(&color);
+    common.color = indexed_color;
+    common.color_24_bit = color_24_bit;
+    if color.has_transparency() {
+        common.transparency = color.transparency;
+    }
+    common.line_type_scale = line_type_scale;
+    common.is_visible = invisibility & 0x01 == 0;
+    common.lineweight_enum_value = line_weight_value(line_weight);
+    common.__material_handle = Handle(material);
+    common.__plot_style_handle = Handle(plot_style);
+    common.elevation = elevation;
+    Ok(Some(DwgEntity {
+        object_type,
+        entity,
+        mode,
+        layer,
+        line_type,
+        style,
+        block,
+    }))
+}
+
+fn read_line(reader: &mut DwgObjectReader) -> DxfResult<Line> {
+    let main = &mut reader.main;
+    let z_is_zero = main.read_b()?;
+    let x1 = main.read_rd()?;
+    let x2 = main.read_dd(x1)?;
+    let y1 = main.read_rd()?;
+    let y2 = main.read_dd(y1)?;
+    let (z1, z2) = if z_is_zero {
+        (0.0, 0.0)
+    } else {
+        let z1 = main.read_rd()?;
+        (z1, main.read_dd(z1)?)
+    };
+    let thickness = main.read_bt()?;
+    let extrusion_direction = main.read_be()?;
+    Ok(Line {
+        thickness,
+        p1: Point::new(x1, y1, z1),
+        p2: Point::new(x2, y2, z2),
+        extrusion_direction,
+    })
+}
+
+// the values shared by `TEXT`, `ATTRIB` and `ATTDEF`
+struct DwgTextData {
+    location: Point,
+    second_alignment_point: Point,
+    normal: Vector,
+    thickness: f64,
+    oblique_angle: f64,
+    rotation: f64,
+    text_height: f64,
+    relative_x_scale_factor: f64,
+    value: String,
+    text_generation_flags: i32,
+    horizontal_text_justification: HorizontalTextJustification,
+    vertical_text_justification: VerticalTextJustification,
+}
+
+fn read_text_data(reader: &mut DwgObjectReader) -> DxfResult<DwgTextData> {
+    // set bits mean the value has its default and isn't stored
+    let flags = reader.main.read_rc()?;
+    let elevation = if flags & 0x01 == 0 {
+        reader.main.read_rd()?
+    } else {
+        0.0
+    };
+    let insertion = reader.main.read_2rd()?;
+    let alignment = if flags & 0x02 == 0 {
+        reader.main.read_2dd(&insertion)?
+    } else {
+        insertion.clone()
+    };
+    let normal = reader.main.read_be()?;
+    let thickness = reader.main.read_bt()?;
+    let oblique_angle = if flags & 0x04 == 0 {
+        reader.main.read_rd()?
+    } else {
+        0.0
+    };
+    let rotation = if flags & 0x08 == 0 {
+        reader.main.read_rd()?
+    } else {
+        0.0
+    };
+    let text_height = reader.main.read_rd()?;
+    let relative_x_scale_factor = if flags & 0x10 == 0 {
+        reader.main.read_rd()?
+    } else {
+        1.0
+    };
+    let value = reader.text()?;
+    let generation = if flags & 0x20 == 0 {
+        reader.main.read_bs()?
+    } else {
+        0
+    };
+    let horizontal = if flags & 0x40 == 0 {
+        reader.main.read_bs()?
+    } else {
+        0
+    };
+    let vertical = if flags & 0x80 == 0 {
+        reader.main.read_bs()?
+    } else {
+        0
+    };
+    Ok(DwgTextData {
+        location: Point::new(insertion.x, insertion.y, elevation),
+        second_alignment_point: Point::new(alignment.x, alignment.y, elevation),
+        normal,
+        thickness,
+        oblique_angle: oblique_angle.to_degrees(),
+        rotation: rotation.to_degrees(),
+        text_height,
+        relative_x_scale_factor,
+        value,
+        text_generation_flags: i32::from(generation),
+        horizontal_text_justification: enum_from_number!(
+            HorizontalTextJustification,
+            Left,
+            from_i16,
+            horizontal
+        ),
+        vertical_text_justification: enum_from_number!(
+            VerticalTextJustification,
+            Baseline,
+            from_i16,
+            vertical
+        ),
+    })
+}
+
+fn read_text_entity(reader: &mut DwgObjectReader) -> DxfResult<Text> {
+    let data = read_text_data(reader)?;
+    Ok(Text {
+        thickness: data.thickness,
+        location: data.location,
+        text_height: data.text_height,
+        value: data.value,
+        rotation: data.rotation,
+        relative_x_scale_factor: data.relative_x_scale_factor,
+        oblique_angle: data.oblique_angle,
+        text_generation_flags: data.text_generation_flags,
+        horizontal_text_justification: data.horizontal_text_justification,
+        second_alignment_point: data.second_alignment_point,
+        normal: data.normal,
+        vertical_text_justification: data.vertical_text_justification,
+        ..Default::default()
+    })
+}
+
+fn read_attribute(reader: &mut DwgObjectReader) -> DxfResult<Attribute> {
+    let data = read_text_data(reader)?;
+    let mut att = Attribute {
+        thickness: data.thickness,
+        location: data.location,
+        text_height: data.text_height,
+        value: data.value,
+        rotation: data.rotation,
+        relative_x_scale_factor: data.relative_x_scale_factor,
+        oblique_angle: data.oblique_angle,
+        text_generation_flags: data.text_generation_flags,
+        horizontal_text_justification: data.horizontal_text_justification,
+        vertical_text_justification: data.vertical_text_justification,
+        second_alignment_point: data.second_alignment_point,
+        normal: data.normal,
+        ..Default::default()
+    };
+    if read_attribute_version(reader)? {
+        att.attribute_tag = reader.text()?;
+        att.field_length = reader.main.read_bs()?;
+        att.flags = i32::from(reader.main.read_rc()?);
+        if reader.version() >= AcadVersion::R2007 {
+            att.is_locked_in_block = reader.main.read_b()?;
+        }
+    }
+
+    Ok(att)
+}
+
+fn read_attribute_definition(reader: &mut DwgObjectReader) -> DxfResult<AttributeDefinition> {
+    let data = read_text_data(reader)?;
+    let mut att = AttributeDefinition {
+        thickness: data.thickness,
+        location: data.location,
+        text_height: data.text_height,
+        value: data.value,
+        rotation: data.rotation,
+        relative_x_scale_factor: data.relative_x_scale_factor,
+        oblique_angle: data.oblique_angle,
+        text_generation_flags: data.text_generation_flags,
+        horizontal_text_justification: data.horizontal_text_justification,
+        second_alignment_point: data.second_alignment_point,
+        normal: data.normal,
+        vertical_text_justification: data.vertical_text_justification,
+        ..Default::default()
+    };
+    if read_attribute_version(reader)? {
+        att.text_tag = reader.text()?;
+        att.field_length = reader.main.read_bs()?;
+        att.flags = i32::from(reader.main.read_rc()?);
+        if reader.version() >= AcadVersion::R2007 {
+            att.is_locked_in_block = reader.main.read_b()?;
+        }
+        if reader.version() >= AcadVersion::R2010 {
+            reader.main.read_rc()?; // version
+        }
+        att.prompt = reader.text()?;
+    }
+
+    Ok(att)
+}
+
+// returns `false` for multiline attributes, whose embedded `MTEXT` data isn't read
+fn read_attribute_version(reader: &mut DwgObjectReader) -> DxfResult<bool> {
+    if reader.version() >= AcadVersion::R2010 {
+        reader.main.read_rc()?; // version
+    }
+    if reader.version() >= AcadVersion::R2018 {
+        let attribute_type = reader.main.read_rc()?;
+        if attribute_type > 1 {
+            return Ok(false);
+        }
+    }
+
+    Ok(true)
+}
+
+// returns the insert and the handle of its block header
+fn read_insert(reader: &mut DwgObjectReader, is_minsert: bool) -> DxfResult<(Insert, u64)> {
+    let location = reader.main.read_3bd()?;
+    let (x_scale_factor, y_scale_factor, z_scale_factor) = match reader.main.read_bb()? {
+        3 => (1.0, 1.0, 1.0),
+        1 => {
+            let y = reader.main.read_dd(1.0)?;
+            let z = reader.main.read_dd(1.0)?;
+            (1.0, y, z)
+        }
+        2 => {
+            let x = reader.main.read_rd()?;
+            (x, x, x)
+        }
+        _ => {
+            let x = reader.main.read_rd()?;
+            let y = reader.main.read_dd(x)?;
+            let z = reader.main.read_dd(x)?;
+            (x, y, z)
+        }
+    };
+    let rotation = reader.main.read_bd()?.to_degrees();
+    let extrusion_direction = reader.main.read_3bd_vector()?;
+    let has_attributes = reader.main.read_b()?;
+    let owned_count = if has_attributes && reader.version() >= AcadVersion::R2004 {
+        reader.main.read_bl()?
+    } else {
+        0
+    };
+    let mut insert = Insert {
+        location,
+        x_scale_factor,
+        y_scale_factor,
+        z_scale_factor,
+        rotation,
+        extrusion_direction,
+        ..Default::default()
+    };
+    if is_minsert {
+        insert.column_count = reader.main.read_bs()?;
+        insert.row_count = reader.main.read_bs()?;
+        insert.column_spacing = reader.main.read_bd()?;
+        insert.row_spacing = reader.main.read_bd()?;
+    }
+
+    let block = reader.handle_ref()?;
+    if has_attributes {
+        // the attributes themselves are found through their owner handles
+        if reader.version() >= AcadVersion::R2004 {
+            reader.skip_handles(owned_count)?;
+        } else {
+            reader.skip_handles(2)?;
+        }
+        insert.__seqend_handle = Handle(reader.handle_ref()?);
+    }
+
+    Ok((insert, block))
+}
+
+fn read_vertex_2d(reader: &mut DwgObjectReader) -> DxfResult<Vertex> {
+    let flags = i32::from(reader.main.read_rc()?);
+    let location = reader.main.read_3bd()?;
+
+    // a negative starting width means both widths are the same
+    let mut starting_width = reader.main.read_bd()?;
+    let ending_width = if starting_width < 0.0 {
+        starting_width = -starting_width;
+        starting_width
+    } else {
+        reader.main.read_bd()?
+    };
+    let bulge = reader.main.read_bd()?;
+    let identifier = if reader.version() >= AcadVersion::R2010 {
+        reader.main.read_bl()?
+    } else {
+        0
+    };
+    let curve_fit_tangent_direction = reader.main.read_bd()?.to_degrees();
+    Ok(Vertex {
+        location,
+        starting_width,
+        ending_width,
+        bulge,
+        flags,
+        curve_fit_tangent_direction,
+        identifier,
+        ..Default::default()
+    })
+}
+
+fn read_polyline(reader: &mut DwgObjectReader, object_type: u16) -> DxfResult<Polyline> {
+    let mut poly = Polyline::default();
+    match object_type {
+        TYPE_POLYLINE_2D => {
+            poly.flags = i32::from(reader.main.read_bs()?);
+            let curve_type = reader.main.read_bs()?;
+            poly.surface_type = enum_from_number!(
+                PolylineCurvedAndSmoothSurfaceType,
+                None,
+                from_i16,
+                curve_type
+            );
+            poly.default_starting_width = reader.main.read_bd()?;
+            poly.default_ending_width = reader.main.read_bd()?;
+            poly.thickness = reader.main.read_bt()?;
+            let elevation = reader.main.read_bd()?;
+            poly.location = Point::new(0.0, 0.0, elevation);
+            poly.normal = reader.main.read_be()?;
+        }
+        TYPE_POLYLINE_3D => {
+            let curve_type = reader.main.read_rc()?;
+            let closed = reader.main.read_rc()?;
+            poly.surface_type = enum_from_number!(
+                PolylineCurvedAndSmoothSurfaceType,
+                None,
+                from_i16,
+                i16::from(curve_type)
+            );
+            poly.flags = 8 | i32::from(closed & 0x01);
+            if curve_type != 0 {
+                poly.flags |= 4;
+            }
+        }
+        TYPE_POLYLINE_PFACE => {
+            poly.flags = 64;
+            poly.polygon_mesh_m_vertex_count = i32::from(reader.main.read_bs()?);
+            poly.polygon_mesh_n_vertex_count = i32::from(reader.main.read_bs()?);
+        }
+        _ => {
+            poly.flags = i32::from(reader.main.read_bs()?) | 16;
+            let curve_type = reader.main.read_bs()?;
+            poly.surface_type = enum_from_number!(
+                PolylineCurvedAndSmoothSurfaceType,
+                None,
+                from_i16,
+                curve_type
+            );
+            poly.polygon_mesh_m_vertex_count = i32::from(reader.main.read_bs()?);
+            poly.polygon_mesh_n_vertex_count = i32::from(reader.main.read_bs()?);
+            poly.smooth_surface_m_density = i32::from(reader.main.read_bs()?);
+            poly.smooth_surface_n_density = i32::from(reader.main.read_bs()?);
+        }
+    }
+
+    // the vertices themselves are found through their owner handles
+    if reader.version() >= AcadVersion::R2004 {
+        let owned_count = reader.main.read_bl()?;
+        reader.skip_handles(owned_count)?;
+    } else {
+        reader.skip_handles(2)?;
+    }
+    poly.__seqend_handle = Handle(reader.handle_ref()?);
+    Ok(poly)
+}
+
+fn read_dimension(reader: &mut DwgObjectReader, object_type: u16) -> DxfResult<EntityType> {
+    let version = reader.version();
+    let main = &mut reader.main;
+    let mut base = DimensionBase::default();
+    if version >= AcadVersion::R2010 {
+        base.version = enum_from_number!(Version, R2010, from_i16, i16::from(main.read_rc()?));
+    }
+    base.normal = main.read_3bd_vector()?;
+    let text_mid_point = main.read_2rd()?;
+    let elevation = main.read_bd()?;
+    base.text_mid_point = Point::new(text_mid_point.x, text_mid_point.y, elevation);
+    let flags = main.read_rc()?;
+    base.is_at_user_defined_location = flags & 0x01 == 0;
+    base.is_block_reference_referenced_by_this_block_only = flags & 0x02 != 0;
+    base.text = reader.text()?;
+    let main = &mut reader.main;
+    base.text_rotation_angle = main.read_bd()?.to_degrees();
+    base.horizontal_direction_angle = main.read_bd()?.to_degrees();
+    main.read_3bd()?; // insertion scale
+    main.read_bd()?; // insertion rotation
+    base.attachment_point = enum_from_number!(AttachmentPoint, TopLeft, from_i16, main.read_bs()?);
+    base.text_line_spacing_style =
+        enum_from_number!(TextLineSpacingStyle, AtLeast, from_i16, main.read_bs()?);
+    base.text_line_spacing_factor = main.read_bd()?;
+    base.actual_measurement = main.read_bd()?;
+    if version >= AcadVersion::R2007 {
+        main.read_b()?; // unknown
+        main.read_b()?; // flip first arrow
+        main.read_b()?; // flip second arrow
+    }
+    let clone_insertion_point = main.read_2rd()?;
+
+    let dimension = match object_type {
+        TYPE_DIMENSION_ORDINATE => {
+            base.dimension_type = DimensionType::Ordinate;
+            base.definition_point_1 = main.read_3bd()?;
+            let definition_point_2 = main.read_3bd()?;
+            let definition_point_3 = main.read_3bd()?;
+            base.is_ordinate_x_type = main.read_rc()? & 0x01 != 0;
+            EntityType::OrdinateDimension(OrdinateDimension {
+                dimension_base: base,
+                definition_point_2,
+                definition_point_3,
+            })
+        }
+        TYPE_DIMENSION_LINEAR | TYPE_DIMENSION_ALIGNED => {
+            let definition_point_2 = main.read_3bd()?;
+            let definition_point_3 = main.read_3bd()?;
+            base.definition_point_1 = main.read_3bd()?;
+            let extension_line_angle = main.read_bd()?.to_degrees();
+            let rotation_angle = if object_type == TYPE_DIMENSION_LINEAR {
+                base.dimension_type = DimensionType::RotatedHorizontalOrVertical;
+                main.read_bd()?.to_degrees()
+            } else {
+                base.dimension_type = DimensionType::Aligned;
+                0.0
+            };
+            EntityType::RotatedDimension(RotatedDimension {
+                dimension_base: base,
+                insertion_point: Point::new(
+                    clone_insertion_point.x,
+                    clone_insertion_point.y,
+                    elevation,
+                ),
+                definition_point_2,
+                definition_point_3,
+                rotation_angle,
+                extension_line_angle,
+            })
+        }
+        TYPE_DIMENSION_ANG3PT => {
+            base.dimension_type = DimensionType::AngularThreePoint;
+            base.definition_point_1 = main.read_3bd()?;
+            EntityType::AngularThreePointDimension(AngularThreePointDimension {
+                dimension_base: base,
+                definition_point_2: main.read_3bd()?,
+                definition_point_3: main.read_3bd()?,
+                definition_point_4: main.read_3bd()?,
+                ..Default::default()
+            })
+        }
+        TYPE_DIMENSION_RADIUS => {
+            base.dimension_type = DimensionType::Radius;
+            base.definition_point_1 = main.read_3bd()?;
+            EntityType::RadialDimension(RadialDimension {
+                dimension_base: base,
+                definition_point_2: main.read_3bd()?,
+                leader_length: main.read_bd()?,
+            })
+        }
+        _ => {
+            base.dimension_type = DimensionType::Diameter;
+            base.definition_point_1 = main.read_3bd()?;
+            EntityType::DiameterDimension(DiameterDimension {
+                dimension_base: base,
+                definition_point_2: main.read_3bd()?,
+                leader_length: main.read_bd()?,
+            })
+        }
+    };
+
+    Ok(dimension)
+}
+
+fn read_face_3d(reader: &mut DwgObjectReader) -> DxfResult<Face3D> {
+    let main = &mut reader.main;
+    let has_no_flags = main.read_b()?;
+    let z_is_zero = main.read_b()?;
+    let x = main.read_rd()?;
+    let y = main.read_rd()?;
+    let z = if z_is_zero { 0.0 } else { main.read_rd()? };
+    let first_corner = Point::new(x, y, z);
+    let mut corners = vec![first_corner];
+    for _ in 0..3 {
+        let previous = corners.last().unwrap().clone();
+        let x = main.read_dd(previous.x)?;
+        let y = main.read_dd(previous.y)?;
+        let z = main.read_dd(previous.z)?;
+        corners.push(Point::new(x, y, z));
+    }
+    let edge_flags = if has_no_flags {
+        0
+    } else {
+        i32::from(main.read_bs()?)
+    };
+    Ok(Face3D {
+        first_corner: corners[0].clone(),
+        second_corner: corners[1].clone(),
+        third_corner: corners[2].clone(),
+        fourth_corner: corners[3].clone(),
+        edge_flags,
+    })
+}
+
+fn read_count(reader: &mut DwgBitReader) -> DxfResult<usize> {
+    let count = reader.read_bl()?;
+    if count < 0 {
+        return Err(DxfError::InvalidBinaryFile);
+    }
+
+    Ok(count as usize)
+}
+
+fn read_spline(reader: &mut DwgObjectReader) -> DxfResult<Spline> {
+    let main = &mut reader.main;
+    let mut scenario = main.read_bl()?;
+    if main.version >= AcadVersion::R2013 {
+        let spline_flags = main.read_bl()?;
+        main.read_bl()?; // knot parameterization
+        scenario = if spline_flags & 0x01 != 0 { 2 } else { 1 };
+    }
+
+    let mut spline = Spline {
+        degree_of_curve: main.read_bl()?,
+        ..Default::default()
+    };
+    let mut knot_count = 0;
+    let mut control_point_count = 0;
+    let mut fit_point_count = 0;
+    let mut is_weighted = false;
+    if scenario == 2 {
+        spline.fit_tolerance = main.read_bd()?;
+        spline.start_tangent = main.read_3bd()?;
+        spline.end_tangent = main.read_3bd()?;
+        fit_point_count = read_count(main)?;
+    } else {
+        let is_rational = main.read_b()?;
+        let is_closed = main.read_b()?;
+        let is_periodic = main.read_b()?;
+        spline.knot_tolerance = main.read_bd()?;
+        spline.control_point_tolerance = main.read_bd()?;
+        knot_count = read_count(main)?;
+        control_point_count = read_count(main)?;
+        is_weighted = main.read_b()?;
+        spline.flags = if is_closed { 1 } else { 0 }
+            | if is_periodic { 2 } else { 0 }
+            | if is_rational { 4 } else { 0 };
+    }
+
+    for _ in 0..knot_count {
+        spline.knot_values.push(main.read_bd()?);
+    }
+    for _ in 0..control_point_count {
+        spline.control_points.push(main.read_3bd()?);
+        if is_weighted {
+            spline.weight_values.push(main.read_bd()?);
+        }
+    }
+    for _ in 0..fit_point_count {
+        spline.fit_points.push(main.read_3bd()?);
+    }
+
+    Ok(spline)
+}
+
+fn read_mtext(reader: &mut DwgObjectReader) -> DxfResult<MText> {
+    let mut mtext = MText {
+        insertion_point: reader.main.read_3bd()?,
+        extrusion_direction: reader.main.read_3bd_vector()?,
+        x_axis_direction: reader.main.read_3bd_vector()?,
+        reference_rectangle_width: reader.main.read_bd()?,
+        ..Default::default()
+    };
+    if reader.version() >= AcadVersion::R2007 {
+        reader.main.read_bd()?; // reference rectangle height
+    }
+    mtext.initial_text_height = reader.main.read_bd()?;
+    let attachment_point = reader.main.read_bs()?;
+    mtext.attachment_point =
+        enum_from_number!(AttachmentPoint, TopLeft, from_i16, attachment_point);
+    let drawing_direction = reader.main.read_bs()?;
+    mtext.drawing_direction =
+        enum_from_number!(DrawingDirection, LeftToRight, from_i16, drawing_direction);
+    mtext.vertical_height = reader.main.read_bd()?;
+    mtext.horizontal_width = reader.main.read_bd()?;
+    mtext.text = reader.text()?;
+    let line_spacing_style = reader.main.read_bs()?;
+    mtext.line_spacing_style =
+        enum_from_number!(MTextLineSpacingStyle, AtLeast, from_i16, line_spacing_style);
+    mtext.line_spacing_factor = reader.main.read_bd()?;
+    reader.main.read_b()?;
+    if reader.version() >= AcadVersion::R2004 {
+        let background_flags = reader.main.read_bl()?;
+        mtext.background_fill_setting = enum_from_number!(
+            BackgroundFillSetting,
+            Off,
+            from_i32,
+            background_flags & 0x03
+        );
+        if background_flags & 0x01 != 0 {
+            mtext.fill_box_scale = reader.main.read_bd()?;
+            let color = reader.read_cmc()?;
+            mtext.background_fill_color = color_values(&color).0;
+            mtext.background_fill_color_transparency = reader.main.read_bl()?;
+        }
+    }
+
+    Ok(mtext)
+}
+
+// returns the polyline and its elevation
+fn read_lw_polyline(reader: &mut DwgObjectReader) -> DxfResult<(LwPolyline, f64)> {
+    let main = &mut reader.main;
+    let flags = main.read_bs()? as u16;
+    let mut poly = LwPolyline::default();
+    if flags & 0x04 != 0 {
+        poly.constant_width = main.read_bd()?;
+    }
+    let elevation = if flags & 0x08 != 0 {
+        main.read_bd()?
+    } else {
+        0.0
+    };
+    if flags & 0x02 != 0 {
+        poly.thickness = main.read_bd()?;
+    }
+    if flags & 0x01 != 0 {
+        poly.extrusion_direction = main.read_3bd_vector()?;
+    }
+
+    let point_count = read_count(main)?;
+    let bulge_count = if flags & 0x10 != 0 {
+        read_count(main)?
+    } else {
+        0
+    };
+    let id_count = if flags & 0x0400 != 0 && main.version >= AcadVersion::R2010 {
+        read_count(main)?
+    } else {
+        0
+    };
+    let width_count = if flags & 0x20 != 0 {
+        read_count(main)?
+    } else {
+        0
+    };
+
+    // points after the first default to the previous one
+    let mut previous = Point::origin();
+    for i in 0..point_count {
+        let p = if i == 0 {
+            main.read_2rd()?
+        } else {
+            main.read_2dd(&previous)?
+        };
+        poly.vertices.push(LwPolylineVertex {
+            x: p.x,
+            y: p.y,
+            ..Default::default()
+        });
+        previous = p;
+    }
+    for i in 0..bulge_count {
+        let bulge = main.read_bd()?;
+        if let Some(v) = poly.vertices.get_mut(i) {
+            v.bulge = bulge;
+        }
+    }
+    for i in 0..id_count {
+        let id = main.read_bl()?;
+        if let Some(v) = poly.vertices.get_mut(i) {
+            v.id = id;
+        }
+    }
+    for i in 0..width_count {
+        let starting_width = main.read_bd()?;
+        let ending_width = main.read_bd()?;
+        if let Some(v) = poly.vertices.get_mut(i) {
+            v.starting_width = starting_width;
+            v.ending_width = ending_width;
+        }
+    }
+
+    poly.set_is_closed(flags & 0x0200 != 0);
+    poly.set_is_pline_gen(flags & 0x0100 != 0);
+    Ok((poly, elevation))
+}
+
+fn read_shape(reader: &mut DwgObjectReader) -> DxfResult<Shape> {
+    let main = &mut reader.main;
+    let location = main.read_3bd()?;
+    let size = main.read_bd()?;
+    let rotation_angle = main.read_bd()?.to_degrees();
+    let relative_x_scale_factor = main.read_bd()?;
+    let oblique_angle = main.read_bd()?.to_degrees();
+    let thickness = main.read_bd()?;
+    main.read_bs()?; // shape number; its name is only in the shape file
+    let extrusion_direction = main.read_3bd_vector()?;
+    Ok(Shape {
+        thickness,
+        location,
+        size,
+        rotation_angle,
+        relative_x_scale_factor,
+        oblique_angle,
+        extrusion_direction,
+        ..Default::default()
+    })
+}
+
+// returns the SAT text of a modeler geometry entity, or `None` if it's stored as binary SAB
+fn read_sat_text(reader: &mut DwgObjectReader) -> DxfResult<Option<String>> {
+    let main = &mut reader.main;
+    if main.read_b()? {
+        return Ok(Some(String::new())); // no ACIS data
+    }
+    main.read_b()?;
+    if main.read_bs()? != 1 {
+        return Ok(None);
+    }
+
+    let mut data = vec![];
+    loop {
+        let size = read_count(main)?;
+        if size == 0 {
+            break;
+        }
+        data.extend(main.read_bytes(size)?);
+    }
+
+    Ok(Some(crypt_sat_line(&String::from_utf8_lossy(&data))))
+}
+
+fn read_ole_data(reader: &mut DwgObjectReader) -> DxfResult<Vec<u8>> {
+    let size = read_count(&mut reader.main)?;
+    reader.main.read_bytes(size)
+}
+
+fn read_leader(reader: &mut DwgObjectReader) -> DxfResult<Leader> {
+    let main = &mut reader.main;
+    main.read_b()?;
+    let annotation_type = main.read_bs()?;
+    let path_type = main.read_bs()?;
+    let vertex_count = read_count(main)?;
+    let mut vertices = vec![];
+    for _ in 0..vertex_count {
+        vertices.push(main.read_3bd()?);
+    }
+    main.read_3bd()?; // origin
+    let normal = main.read_3bd_vector()?;
+    let right = main.read_3bd_vector()?;
+    let block_offset = main.read_3bd_vector()?;
+    main.read_3bd()?; // end point projection
+    let text_annotation_height = main.read_bd()?;
+    let text_annotation_width = main.read_bd()?;
+    let hookline_is_on_x_direction = main.read_b()?;
+    let use_arrowheads = main.read_b()?;
+    Ok(Leader {
+        use_arrowheads,
+        path_type: enum_from_number!(LeaderPathType, StraightLineSegments, from_i16, path_type),
+        annotation_type: enum_from_number!(
+            LeaderCreationAnnotationType,
+            NoAnnotation,
+            from_i16,
+            annotation_type
+        ),
+        hookline_direction: if hookline_is_on_x_direction {
+            LeaderHooklineDirection::SameAsHorizontalVector
+        } else {
+            LeaderHooklineDirection::OppositeFromHorizontalVector
+        },
+        text_annotation_height,
+        text_annotation_width,
+        vertex_count: vertices.len() as i32,
+        vertices,
+        normal,
+        right,
+        block_offset,
+        ..Default::default()
+    })
+}
+
+fn read_mline(reader: &mut DwgObjectReader) -> DxfResult<MLine> {
+    let main = &mut reader.main;
+    let scale_factor = main.read_bd()?;
+    let justification = main.read_rc()?;
+    let start_point = main.read_3bd()?;
+    let normal = main.read_3bd_vector()?;
+    let flags = i32::from(main.read_bs()?); // 1 for open and 3 for closed, as in DXF
+    let style_element_count = main.read_rc()?;
+    let vertex_count = main.read_bs()?;
+    let mut mline = MLine {
+        scale_factor,
+        justification: enum_from_number!(Justification, Top, from_i16, i16::from(justification)),
+        flags,
+        __vertex_count: i32::from(vertex_count),
+        style_element_count: i32::from(style_element_count),
+        start_point,
+        normal,
+        ..Default::default()
+    };
+    for _ in 0..vertex_count {
+        mline.vertices.push(main.read_3bd()?);
+        mline.segment_directions.push(main.read_3bd_vector()?);
+        mline.miter_directions.push(main.read_3bd_vector()?);
+        for _ in 0..style_element_count {
+            for _ in 0..main.read_bs()? {
+                mline.parameters.push(main.read_bd()?);
+            }
+            for _ in 0..main.read_bs()? {
+                mline.area_fill_parameters.push(main.read_bd()?);
+            }
+        }
+    }
+
+    mline.__parameter_count = mline.parameters.len() as i32;
+    mline.__area_fill_parameter_count = mline.area_fill_parameters.len() as i32;
+    Ok(mline)
+}
+
+// the values shared by `IMAGE` and `WIPEOUT`
+struct DwgImageData {
+    class_version: i32,
+    location: Point,
+    u_vector: Vector,
+    v_vector: Vector,
+    image_size: Vector,
+    display_options_flags: i32,
+    use_clipping: bool,
+    brightness: i16,
+    contrast: i16,
+    fade: i16,
+    is_inside_clipping: bool,
+    clipping_type: ImageClippingBoundaryType,
+    clipping_vertices: Vec<Point>,
+}
+
+fn read_image(reader: &mut DwgObjectReader) -> DxfResult<DwgImageData> {
+    let main = &mut reader.main;
+    let class_version = main.read_bl()?;
+    let location = main.read_3bd()?;
+    let u_vector = main.read_3bd_vector()?;
+    let v_vector = main.read_3bd_vector()?;
+    let size = main.read_2rd()?;
+    let display_options_flags = i32::from(main.read_bs()?);
+    let use_clipping = main.read_b()?;
+    let brightness = i16::from(main.read_rc()?);
+    let contrast = i16::from(main.read_rc()?);
+    let fade = i16::from(main.read_rc()?);
+    let is_inside_clipping = if main.version >= AcadVersion::R2010 {
+        main.read_b()?
+    } else {
+        false
+    };
+    let clipping_type = main.read_bs()?;
+
+    // rectangular boundaries are given by two opposite corners
+    let vertex_count = if clipping_type == 1 {
+        2
+    } else {
+        read_count(main)?
+    };
+    let mut clipping_vertices = vec![];
+    for _ in 0..vertex_count {
+        clipping_vertices.push(main.read_2rd()?);
+    }
+
+    Ok(DwgImageData {
+        class_version,
+        location,
+        u_vector,
+        v_vector,
+        image_size: Vector::new(size.x, size.y, 0.0),
+        display_options_flags,
+        use_clipping,
+        brightness,
+        contrast,
+        fade,
+        is_inside_clipping,
+        clipping_type: enum_from_number!(
+            ImageClippingBoundaryType,
+            Rectangular,
+            from_i16,
+            clipping_type
+        ),
+        clipping_vertices,
+    })
+}
+
+fn read_helix(reader: &mut DwgObjectReader) -> DxfResult<Helix> {
+    read_spline(reader)?; // the curve itself is generated from the values that follow
+    let main = &mut reader.main;
+    let major_release_number = i32::from(main.read_bs()?);
+    let maintainence_release_number = i32::from(main.read_bs()?);
+    let axis_base_point = main.read_3bd()?;
+    let start_point = main.read_3bd()?;
+    let axis_vector = main.read_3bd_vector()?;
+    let radius = main.read_bd()?;
+    let number_of_turns = main.read_bd()?;
+    let turn_height = main.read_bd()?;
+    let is_right_handed = main.read_b()?;
+    let constraint = i16::from(main.read_rc()?);
+    Ok(Helix {
+        major_release_number,
+        maintainence_release_number,
+        axis_base_point,
+        start_point,
+        axis_vector,
+        radius,
+        number_of_turns,
+        turn_height,
+        is_right_handed,
+        constraint: enum_from_number!(HelixConstraint, ConstrainTurnHeight, from_i16, constraint),
+    })
+}
+
+fn read_light(reader: &mut DwgObjectReader) -> DxfResult<Light> {
+    let version_number = reader.main.read_bl()?;
+    let name = reader.text()?;
+    let light_type = reader.main.read_bl()?;
+    let is_active = reader.main.read_b()?;
+    reader.read_cmc()?; // color
+    let main = &mut reader.main;
+    let plot_glyph = main.read_b()?;
+    let intensity = main.read_bd()?;
+    let position = main.read_3bd()?;
+    let target_location = main.read_3bd()?;
+    let attentuation_type = main.read_bl()?;
+    let use_attenuation_limits = main.read_b()?;
+    let attenuation_start_limit = main.read_bd()?;
+    let attenuation_end_limit = main.read_bd()?;
+    let hotspot_angle = main.read_bd()?.to_degrees();
+    let falloff_angle = main.read_bd()?.to_degrees();
+    let cast_shadows = main.read_b()?;
+    let shadow_type = main.read_bl()?;
+    let shadow_map_size = i32::from(main.read_bs()?);
+    let shadow_map_softness = i16::from(main.read_rc()?);
+    Ok(Light {
+        version_number,
+        name,
+        light_type: enum_from_number!(LightType, Distant, from_i32, light_type),
+        is_active,
+        plot_glyph,
+        intensity,
+        position,
+        target_location,
+        attentuation_type: enum_from_number!(
+            LightAttenuationType,
+            None,
+            from_i32,
+            attentuation_type
+        ),
+        use_attenuation_limits,
+        attenuation_start_limit,
+        attenuation_end_limit,
+        hotspot_angle,
+        falloff_angle,
+        cast_shadows,
+        shadow_type: enum_from_number!(ShadowType, RayTraced, from_i32, shadow_type),
+        shadow_map_size,
+        shadow_map_softness,
+    })
+}
+
+// the values shared by the `PDFUNDERLAY`, `DWFUNDERLAY` and `DGNUNDERLAY` entities
+struct DwgUnderlayData {
+    insertion_point: Point,
+    x_scale: f64,
+    y_scale: f64,
+    z_scale: f64,
+    rotation_angle: f64,
+    normal: Vector,
+    flags: i32,
+    contrast: i16,
+    fade: i16,
+    points: Vec<Point>,
+}
+
+fn read_underlay(reader: &mut DwgObjectReader) -> DxfResult<DwgUnderlayData> {
+    let main = &mut reader.main;
+    let normal = main.read_3bd_vector()?;
+    let insertion_point = main.read_3bd()?;
+    let scale = main.read_3bd()?;
+    let rotation_angle = main.read_bd()?.to_degrees();
+    let flags = i32::from(main.read_rc()?);
+    let contrast = i16::from(main.read_rc()?);
+    let fade = i16::from(main.read_rc()?);
+    let point_count = read_count(main)?;
+    let mut points = vec![];
+    for _ in 0..point_count {
+        points.push(main.read_2rd()?);
+    }
+
+    // the inverted clip boundary that follows has no DXF equivalent
+    Ok(DwgUnderlayData {
+        insertion_point,
+        x_scale: scale.x,
+        y_scale: scale.y,
+        z_scale: scale.z,
+        rotation_angle,
+        normal,
+        flags,
+        contrast,
+        fade,
+        points,
+    })
+}
+
+fn read_proxy_entity(reader: &mut DwgObjectReader) -> DxfResult<ProxyEntity> {
+    let main = &mut reader.main;
+    let application_entity_class_id = main.read_bl()?;
+
+    // the drawing format holds the version in its low word and the maintenance version above it
+    let object_drawing_format = if main.version >= AcadVersion::R2018 {
+        let version = main.read_bl()? as u32;
+        let maintenance_version = main.read_bl()? as u32;
+        version | (maintenance_version << 16)
+    } else {
+        main.read_bl()? as u32
+    };
+    let original_data_format_is_dxf = main.read_b()?;
+
+    // the entity data and the object ids it refers to are left out
+    Ok(ProxyEntity {
+        application_entity_class_id,
+        __object_drawing_format: object_drawing_format,
+        original_data_format_is_dxf,
+        ..Default::default()
+    })
+}
+
+fn read_block_header(reader: &mut DwgObjectReader) -> DxfResult<(u64, DwgBlockHeader)> {
+    let (owner, name) = reader.read_table_entry_common()?;
+    let is_anonymous = reader.main.read_b()?;
+    let has_attributes = reader.main.read_b()?;
+    let is_xref = reader.main.read_b()?;
+    let is_overlaid = reader.main.read_b()?;
+    reader.main.read_b()?; // loaded
+    let owned_count = if reader.version() >= AcadVersion::R2004 && !is_xref && !is_overlaid {
+        reader.main.read_bl()?
+    } else {
+        0
+    };
+    let base_point = reader.main.read_3bd()?;
+    let xref_path_name = reader.text()?;
+
+    // a run of non-zero bytes, one per insert
+    let mut insert_count = 0;
+    while reader.main.read_rc()? != 0 {
+        insert_count += 1;
+    }
+    let description = reader.text()?;
+    let preview_size = read_count(&mut reader.main)?;
+    reader.main.read_bytes(preview_size)?;
+
+    let mut record = BlockRecord {
+        name,
+        handle: Handle(reader.handle),
+        ..Default::default()
+    };
+    record.__owner_handle = Handle(owner);
+    if reader.version() >= AcadVersion::R2007 {
+        let units = reader.main.read_bs()?;
+        record.insertion_units = enum_from_number!(Units, Unitless, from_i16, units);
+        record.explodability = reader.main.read_b()?;
+        record.scalability = reader.main.read_rc()? != 0;
+    }
+
+    let block_entity = reader.handle_ref()?;
+    if reader.version() >= AcadVersion::R2004 {
+        reader.skip_handles(owned_count)?;
+    } else if !is_xref && !is_overlaid {
+        reader.skip_handles(2)?;
+    }
+    reader.skip_handles(1 + insert_count)?; // end block and inserts
+    record.__layout_handle = Handle(reader.handle_ref()?);
+
+    let mut flags = 0;
+    if is_anonymous {
+        flags |= 1;
+    }
+    if has_attributes {
+        flags |= 2;
+    }
+    if is_xref {
+        flags |= 4;
+    }
+    if is_overlaid {
+        flags |= 8;
+    }
+
+    Ok((
+        owner,
+        DwgBlockHeader {
+            record,
+            flags,
+            base_point,
+            xref_path_name,
+            description,
+            block_entity,
+        },
+    ))
+}
+
+// returns the owner, the layer and the handle of its line type
+fn read_layer(reader: &mut DwgObjectReader) -> DxfResult<(u64, Layer, u64)> {
+    let (owner, name) = reader.read_table_entry_common()?;
+    let flags = reader.main.read_bs()?;
+    let color = reader.read_cmc()?;
+    let mut layer = Layer {
+        name,
+        handle: Handle(reader.handle),
+        is_layer_plotted: flags & 0x10 != 0,
+        line_weight: LineWeight::from_raw_value(line_weight_value(((flags & 0x03E0) >> 5) as u8)),
+        ..Default::default()
+    };
+    layer.__owner_handle = Handle(owner);
+    layer.color = if reader.version() >= AcadVersion::R2004 {
+        color_values(&color).0
+    } else {
+        read_color_value(&mut layer, color.index)
+    };
+    if flags & 0x02 != 0 {
+        layer.is_layer_on = false;
+    }
+
+    layer.__plot_style_handle = Handle(reader.handle_ref()?);
+    if reader.version() >= AcadVersion::R2007 {
+        layer.__material_handle = Handle(reader.handle_ref()?);
+    }
+    let line_type = reader.handle_ref()?;
+    Ok((owner, layer, line_type))
+}
+
+fn read_line_type(reader: &mut DwgObjectReader) -> DxfResult<(u64, LineType)> {
+    let (owner, name) = reader.read_table_entry_common()?;
+    let mut line_type = LineType {
+        name,
+        handle: Handle(reader.handle),
+        description: reader.text()?,
+        total_pattern_length: reader.main.read_bd()?,
+        alignment_code: i32::from(reader.main.read_rc()?),
+        ..Default::default()
+    };
+    line_type.__owner_handle = Handle(owner);
+    let dash_count = reader.main.read_rc()?;
+    line_type.element_count = i32::from(dash_count);
+    let mut text_dashes = 0;
+    for _ in 0..dash_count {
+        line_type
+            .dash_dot_space_lengths
+            .push(reader.main.read_bd()?);
+        let shape_number = reader.main.read_bs()?;
+        let x_offset = reader.main.read_rd()?;
+        let y_offset = reader.main.read_rd()?;
+        let scale = reader.main.read_bd()?;
+        let rotation = reader.main.read_bd()?.to_degrees();
+        let element_type = reader.main.read_bs()?;
+        line_type.complex_line_type_element_types.push(element_type);
+        if element_type != 0 {
+            line_type.shape_numbers.push(shape_number);
+            line_type.x_offsets.push(x_offset);
+            line_type.y_offsets.push(y_offset);
+            line_type.scale_values.push(scale);
+            line_type.rotation_angles.push(rotation);
+        }
+        if element_type & 0x02 != 0 {
+            text_dashes += 1;
+        }
+    }
+
+    // the text of each text dash is stored in order in a fixed-size area
+    if reader.version() >= AcadVersion::R2007 {
+        if text_dashes > 0 {
+            let area = reader.main.read_bytes(512)?;
+            let chars = area
+                .chunks(2)
+                .map(|c| u16::from(c[0]) | (u16::from(c[1]) << 8))
+                .collect::<Vec<_>>();
+            for text in chars.split(|c| *c == 0).take(text_dashes) {
+                line_type.text_strings.push(String::from_utf16_lossy(text));
+            }
+        }
+    } else {
+        let area = reader.main.read_bytes(256)?;
+        for text in area.split(|b| *b == 0).take(text_dashes) {
+            let (text, _, _) = reader.encoding.decode(text);
+            line_type.text_strings.push(text.into_owned());
+        }
+    }
+
+    for _ in 0..dash_count {
+        let style = reader.handle_ref()?;
+        line_type.__styles_handle.push(Handle(style));
+    }
+
+    Ok((owner, line_type))
+}
+
+fn read_style(reader: &mut DwgObjectReader) -> DxfResult<(u64, Style)> {
+    let (owner, name) = reader.read_table_entry_common()?;
+    reader.main.read_b()?; // vertical
+    reader.main.read_b()?; // shape file
+    let mut style = Style {
+        name,
+        handle: Handle(reader.handle),
+        text_height: reader.main.read_bd()?,
+        width_factor: reader.main.read_bd()?,
+        oblique_angle: reader.main.read_bd()?.to_degrees(),
+        text_generation_flags: i32::from(reader.main.read_rc()?),
+        last_height_used: reader.main.read_bd()?,
+        primary_font_file_name: reader.text()?,
+        big_font_file_name: reader.text()?,
+        ..Default::default()
+    };
+    style.__owner_handle = Handle(owner);
+    Ok((owner, style))
+}
+
+// the values are stored in the same order as the dimension variables in the header
+fn read_dim_style(reader: &mut DwgObjectReader) -> DxfResult<(u64, DwgDimStyle)> {
+    let version = reader.version();
+    let (owner, name) = reader.read_table_entry_common()?;
+    let mut d = DimStyle {
+        name,
+        handle: Handle(reader.handle),
+        ..Default::default()
+    };
+    d.__owner_handle = Handle(owner);
+    d.dimensioning_suffix = reader.text()?;
+    d.alternate_dimensioning_suffix = reader.text()?;
+    d.dimensioning_scale_factor = reader.main.read_bd()?;
+    d.dimensioning_arrow_size = reader.main.read_bd()?;
+    d.dimension_extension_line_offset = reader.main.read_bd()?;
+    d.dimension_line_increment = reader.main.read_bd()?;
+    d.dimension_extension_line_extension = reader.main.read_bd()?;
+    d.dimension_distance_rounding_value = reader.main.read_bd()?;
+    d.dimension_line_extension = reader.main.read_bd()?;
+    d.dimension_plus_tolerance = reader.main.read_bd()?;
+    d.dimension_minus_tolerance = reader.main.read_bd()?;
+    if version >= AcadVersion::R2007 {
+        reader.main.read_bd()?; // DIMFXL
+        reader.main.read_bd()?; // DIMJOGANG
+        reader.main.read_bs()?; // DIMTFILL
+        reader.read_cmc()?; // DIMTFILLCLR
+    }
+    d.generate_dimension_tolerances = reader.main.read_b()?;
+    d.generate_dimension_limits = reader.main.read_b()?;
+    d.dimension_text_inside_horizontal = reader.main.read_b()?;
+    d.dimension_text_outside_horizontal = reader.main.read_b()?;
+    d.suppress_first_dimension_extension_line = reader.main.read_b()?;
+    d.suppress_second_dimension_extension_line = reader.main.read_b()?;
+    d.text_above_dimension_line = reader.main.read_bs()? != 0;
+    d.dimension_unit_zero_suppression = enum_from_number!(
+        UnitZeroSuppression,
+        SuppressZeroFeetAndZeroInches,
+        from_i16,
+        reader.main.read_bs()?
+    );
+    d.dimension_angle_zero_suppression = enum_from_number!(
+        UnitZeroSuppression,
+        SuppressZeroFeetAndZeroInches,
+        from_i16,
+        reader.main.read_bs()?
+    );
+    if version >= AcadVersion::R2007 {
+        reader.main.read_bs()?; // DIMARCSYM
+    }
+    d.dimensioning_text_height = reader.main.read_bd()?;
+    d.center_mark_size = reader.main.read_bd()?;
+    d.dimensioning_tick_size = reader.main.read_bd()?;
+    d.alternate_dimensioning_scale_factor = reader.main.read_bd()?;
+    d.dimension_linear_measurement_scale_factor = reader.main.read_bd()?;
+    d.dimension_vertical_text_position = reader.main.read_bd()?;
+    d.dimension_tolerance_displace_scale_factor = reader.main.read_bd()?;
+    d.dimension_line_gap = reader.main.read_bd()?;
+    d.alternate_dimensioning_unit_rounding = reader.main.read_bd()?;
+    d.use_alternate_dimensioning = reader.main.read_b()?;
+    d.alternate_dimensioning_decimal_places = reader.main.read_bs()?;
+    d.force_dimension_line_extensions_outside_if_text_exists = reader.main.read_b()?;
+    d.use_separate_arrow_blocks_for_dimensions = reader.main.read_b()?;
+    d.force_dimension_text_inside_extensions = reader.main.read_b()?;
+    d.suppress_outside_extension_dimension_lines = reader.main.read_b()?;
+    d.dimension_line_color = color_values(&reader.read_cmc()?).0;
+    d.dimension_extension_line_color = color_values(&reader.read_cmc()?).0;
+    d.dimension_text_color = color_values(&reader.read_cmc()?).0;
+    d.angular_dimension_precision = reader.main.read_bs()?;
+    d.dimension_unit_tolerance_decimal_places = reader.main.read_bs()?;
+    d.dimension_tolerace_decimal_places = reader.main.read_bs()?;
+    d.alternate_dimensioning_units =
+        enum_from_number!(UnitFormat, Decimal, from_i16, reader.main.read_bs()?);
+    d.alternate_dimensioning_tolerance_decimal_places = reader.main.read_bs()?;
+    d.dimensioning_angle_format = enum_from_number!(
+        AngleFormat,
+        DecimalDegrees,
+        from_i16,
+        reader.main.read_bs()?
+    );
+    d.dimension_precision = reader.main.read_bs()?;
+    d.dimension_non_angular_units =
+        enum_from_number!(NonAngularUnits, Decimal, from_i16, reader.main.read_bs()?);
+    d.dimension_decilam_separator_char = reader.main.read_bs()? as u8 as char;
+    d.dimension_text_movement_rule = enum_from_number!(
+        DimensionTextMovementRule,
+        MoveLineWithText,
+        from_i16,
+        reader.main.read_bs()?
+    );
+    d.dimension_text_justification = enum_from_number!(
+        DimensionTextJustification,
+        AboveLineCenter,
+        from_i16,
+        reader.main.read_bs()?
+    );
+    reader.main.read_b()?; // DIMSD1
+    reader.main.read_b()?; // DIMSD2
+    d.dimension_tolerance_vertical_justification =
+        enum_from_number!(Justification, Middle, from_i16, reader.main.read_bs()?);
+    d.dimension_tolerance_zero_suppression = enum_from_number!(
+        UnitZeroSuppression,
+        SuppressZeroFeetAndZeroInches,
+        from_i16,
+        reader.main.read_bs()?
+    );
+    d.alternate_dimensioning_zero_suppression = enum_from_number!(
+        UnitZeroSuppression,
+        SuppressZeroFeetAndZeroInches,
+        from_i16,
+        reader.main.read_bs()?
+    );
+    d.alternate_dimensioning_tolerance_zero_suppression = enum_from_number!(
+        UnitZeroSuppression,
+        SuppressZeroFeetAndZeroInches,
+        from_i16,
+        reader.main.read_bs()?
+    );
+    d.dimension_cursor_controls_text_position = reader.main.read_b()?;
+    d.dimension_text_and_arrow_placement = enum_from_number!(
+        DimensionFit,
+        MoveEitherForBestFit,
+        from_i16,
+        reader.main.read_bs()?
+    );
+    if version >= AcadVersion::R2007 {
+        reader.main.read_b()?; // DIMFXLON
+    }
+    if version >= AcadVersion::R2010 {
+        reader.main.read_b()?; // DIMTXTDIRECTION
+        reader.main.read_bd()?; // DIMALTMZF
+        reader.text()?; // DIMALTMZS
+        reader.main.read_bd()?; // DIMMZF
+        reader.text()?; // DIMMZS
+    }
+    d.dimension_line_weight = LineWeight::from_raw_value(reader.main.read_bs()?);
+    d.dimension_extension_line_weight = LineWeight::from_raw_value(reader.main.read_bs()?);
+    reader.main.read_b()?; // unknown
+
+    let text_style = reader.handle_ref()?;
+    let leader_block = reader.handle_ref()?;
+    let arrow_block = reader.handle_ref()?;
+    let first_arrow_block = reader.handle_ref()?;
+    let second_arrow_block = reader.handle_ref()?;
+    Ok((
+        owner,
+        DwgDimStyle {
+            dim_style: d,
+            text_style,
+            leader_block,
+            arrow_block,
+            first_arrow_block,
+            second_arrow_block,
+        },
+    ))
+}
+
+fn read_dictionary(reader: &mut DwgObjectReader) -> DxfResult<(u64, Dictionary)> {
+    let owner = reader.read_object_common()?;
+    let item_count = read_count(&mut reader.main)?;
+    let cloning = reader.main.read_bs()?;
+    let is_hard_owner = reader.main.read_rc()? != 0;
+    let mut names = vec![];
+    for _ in 0..item_count {
+        names.push(reader.text()?);
+    }
+
+    let mut dictionary = Dictionary {
+        is_hard_owner,
+        duplicate_record_handling: enum_from_number!(
+            DictionaryDuplicateRecordHandling,
+            NotApplicable,
+            from_i16,
+            cloning
+        ),
+        ..Default::default()
+    };
+    for name in names {
+        let item = reader.handle_ref()?;
+        dictionary.value_handles.insert(name, Handle(item));
+    }
+
+    Ok((owner, dictionary))
+}
+
+fn read_xrecord(reader: &mut DwgObjectReader) -> DxfResult<(u64, XRecordObject)> {
+    let owner = reader.read_object_common()?;
+    let size = reader.main.read_bl()? as usize;
+    let data = reader.main.read_bytes(size)?;
+    let data_pairs = read_xrecord_pairs(&data, reader.version(), reader.encoding)?;
+    let cloning = reader.main.read_bs()?;
+    let xrecord = XRecordObject {
+        duplicate_record_handling: enum_from_number!(
+            DictionaryDuplicateRecordHandling,
+            NotApplicable,
+            from_i16,
+            cloning
+        ),
+        data_pairs,
+    };
+
+    // the handles of the objects referenced by the data follow, but they're already in the pairs
+    Ok((owner, xrecord))
+}
+
+// each value is a raw group code followed by raw data whose layout depends on the code
+fn read_xrecord_pairs(
+    data: &[u8],
+    version: AcadVersion,
+    encoding: &'static Encoding,
+) -> DxfResult<Vec<CodePair>> {
+    let mut reader = DwgBitReader::new(data, version);
+    let mut pairs = vec![];
+    while reader.position() < reader.bit_length() {
+        let code = i32::from(reader.read_rs()? as i16);
+        match code {
+            // points are stored as a single value
+            10..=18 | 110..=112 | 210 | 1010..=1013 => {
+                pairs.push(CodePair::new_f64(code, reader.read_rd()?));
+                pairs.push(CodePair::new_f64(code + 10, reader.read_rd()?));
+                pairs.push(CodePair::new_f64(code + 20, reader.read_rd()?));
+            }
+            5 | 105 | 320..=369 | 390..=399 | 480..=481 | 1005 => {
+                let mut bytes = [0u8; 8];
+                for b in bytes.iter_mut() {
+                    *b = reader.read_rc()?;
+                }
+                let handle = Handle(u64::from_le_bytes(bytes));
+                pairs.push(CodePair::new_string(code, handle.as_string()));
+            }
+            // these shorts are stored as a single byte
+            280..=289 => pairs.push(CodePair::new_i16(code, i16::from(reader.read_rc()?))),
+            _ => {
+                let value = match ExpectedType::new(code) {
+                    Some(ExpectedType::Boolean) => {
+                        CodePairValue::Boolean(i16::from(reader.read_rc()?))
+                    }
+                    Some(ExpectedType::Integer) => CodePairValue::Integer(reader.read_rl()? as i32),
+                    Some(ExpectedType::Long) => {
+                        let lo = u64::from(reader.read_rl()?);
+                        let hi = u64::from(reader.read_rl()?);
+                        CodePairValue::Long((lo | (hi << 32)) as i64)
+                    }
+                    Some(ExpectedType::Short) => CodePairValue::Short(reader.read_rs()? as i16),
+                    Some(ExpectedType::Double) => CodePairValue::Double(reader.read_rd()?),
+                    Some(ExpectedType::Str) => {
+                        let length = usize::from(reader.read_rs()?);
+                        if version >= AcadVersion::R2007 {
+                            let mut chars = Vec::with_capacity(length);
+                            for _ in 0..length {
+                                chars.push(reader.read_rs()?);
+                            }
+                            CodePairValue::Str(String::from_utf16_lossy(&chars))
+                        } else {
+                            reader.read_rc()?; // code page
+                            let bytes = reader.read_bytes(length)?;
+                            let (text, _, _) = encoding.decode(&bytes);
+                            CodePairValue::Str(text.into_owned())
+                        }
+                    }
+                    Some(ExpectedType::Binary) => {
+                        let length = usize::from(reader.read_rc()?);
+                        CodePairValue::Binary(reader.read_bytes(length)?)
+                    }
+                    None => return Err(DxfError::UnexpectedCode(code, 0)),
+                };
+                pairs.push(CodePair::new(code, value, 0));
+            }
+        }
+    }
+
+    Ok(pairs)
+}
+
+fn read_group(reader: &mut DwgObjectReader) -> DxfResult<(u64, Group)> {
+    let owner = reader.read_object_common()?;
+    let mut group = Group {
+        description: reader.text()?,
+        is_named: reader.main.read_bs()? == 0,
+        is_selectable: reader.main.read_bs()? != 0,
+        ..Default::default()
+    };
+    let entity_count = read_count(&mut reader.main)?;
+    for _ in 0..entity_count {
+        group.__entities_handle.push(Handle(reader.handle_ref()?));
+    }
+
+    Ok((owner, group))
+}
+
+fn read_mline_style(reader: &mut DwgObjectReader) -> DxfResult<(u64, DwgMLineStyle)> {
+    let owner = reader.read_object_common()?;
+    let style_name = reader.text()?;
+    let description = reader.text()?;
+    let flags = i32::from(reader.main.read_bs()?);
+    let (fill_color, _) = color_values(&reader.read_cmc()?);
+    let start_angle = reader.main.read_bd()?.to_degrees();
+    let end_angle = reader.main.read_bd()?.to_degrees();
+    let element_count = reader.main.read_rc()?;
+    let mut elements = vec![];
+    let mut element_line_types = vec![];
+    for _ in 0..element_count {
+        let offset = reader.main.read_bd()?;
+        let (color, _) = color_values(&reader.read_cmc()?);
+        elements.push(MLineStyleElement::new(offset, color, String::new()));
+        if reader.version() < AcadVersion::R2018 {
+            // 32767 is BYLAYER and 32766 BYBLOCK; the table entries other values count through
+            // aren't read in order, so they're taken as BYLAYER too
+            element_line_types.push(match reader.main.read_bs()? {
+                32766 => DwgLineTypeRef::ByBlock,
+                _ => DwgLineTypeRef::ByLayer,
+            });
+        }
+    }
+    if reader.version() >= AcadVersion::R2018 {
+        for _ in 0..element_count {
+            element_line_types.push(DwgLineTypeRef::Handle(reader.handle_ref()?));
+        }
+    }
+
+    let mline_style = MLineStyle {
+        style_name,
+        __flags: flags,
+        description,
+        fill_color,
+        start_angle,
+        end_angle,
+        elements,
+        ..Default::default()
+    };
+    Ok((
+        owner,
+        DwgMLineStyle {
+            mline_style,
+            element_line_types,
+        },
+    ))
+}
+
+// No files saved by AutoCAD are checked in, so these tests read files built from the layouts in
+// the Open Design Alliance's DWG specification.  They show the reader agrees with that document,
+// not with real drawings; a saved file for each supported version belongs in `src/misc_tests`
+// next to `diamond-bin.dxf` once one can be added.
+#[cfg(test)]
+mod tests {
+    use crate::dwg_reader::*;
+    use crate::helper_functions::tests::*;
+    use crate::XRecordValue;
+    use chrono::Datelike;
+
+    // writes values in the layout read by `DwgBitReader`
+    #[derive(Default)]
+    struct BitWriter {
+        bytes: Vec<u8>,
+        length: usize,
+    }
+
+    impl BitWriter {
+        fn bits(&mut self, value: u64, count: usize) {
+            for i in (0..count).rev() {
+                if self.length.is_multiple_of(8) {
+                    self.bytes.push(0);
+                }
+                if (value >> i) & 0x01 != 0 {
+                    self.bytes[self.length / 8] |= 0x80 >> (self.length % 8);
+                }
+                self.length += 1;
+            }
+        }
+        fn set_bits(&mut self, position: usize, value: u64, count: usize) {
+            for i in 0..count {
+                let bit = position + i;
+                let mask = 0x80 >> (bit % 8);
+                if (value >> (count - i - 1)) & 0x01 != 0 {
+                    self.bytes[bit / 8] |= mask;
+                } else {
+                    self.bytes[bit / 8] &= !mask;
+                }
+            }
+        }
+        fn append(&mut self, other: &BitWriter) {
+            for i in 0..other.length {
+                let bit = (other.bytes[i / 8] >> (7 - i % 8)) & 0x01;
+                self.bits(u64::from(bit), 1);
+            }
+        }
+        fn b(&mut self, value: bool) {
+            self.bits(u64::from(value), 1);
+        }
+        fn bb(&mut self, value: u8) {
+            self.bits(u64::from(value), 2);
+        }
+        fn rc(&mut self, value: u8) {
+            self.bits(u64::from(value), 8);
+        }
+        fn rs(&mut self, value: u16) {
+            for b in value.to_le_bytes().iter() {
+                self.rc(*b);
+            }
+        }
+        fn rl(&mut self, value: u32) {
+            for b in value.to_le_bytes().iter() {
+                self.rc(*b);
+            }
+        }
+        fn rd(&mut self, value: f64) {
+            for b in value.to_le_bytes().iter() {
+                self.rc(*b);
+            }
+        }
+        fn bs(&mut self, value: i16) {
+            self.bb(0);
+            self.rs(value as u16);
+        }
+        fn bl(&mut self, value: i32) {
+            self.bb(0);
+            self.rl(value as u32);
+        }
+        fn bd(&mut self, value: f64) {
+            if value == 0.0 {
+                self.bb(2);
+            } else if value == 1.0 {
+                self.bb(1);
+            } else {
+                self.bb(0);
+                self.rd(value);
+            }
+        }
+        fn dd(&mut self, value: f64) {
+            self.bb(3);
+            self.rd(value);
+        }
+        fn point(&mut self, p: &Point) {
+            self.bd(p.x);
+            self.bd(p.y);
+            self.bd(p.z);
+        }
+        fn h(&mut self, code: u8, value: u64) {
+            let bytes = value.to_be_bytes();
+            let start = bytes.iter().position(|b| *b != 0).unwrap_or(8);
+            self.bits(u64::from(code), 4);
+            self.bits((8 - start) as u64, 4);
+            for b in &bytes[start..] {
+                self.rc(*b);
+            }
+        }
+        fn tv(&mut self, value: &str) {
+            self.bs(value.len() as i16);
+            for b in value.bytes() {
+                self.rc(b);
+            }
+        }
+        fn tu(&mut self, value: &str) {
+            let chars = value.encode_utf16().collect::<Vec<_>>();
+            self.bs(chars.len() as i16);
+            for c in chars {
+                self.rs(c);
+            }
+        }
+    }
+
+    fn umc(mut value: u64) -> Vec<u8> {
+        let mut result = vec![];
+        loop {
+            if value < 0x80 {
+                result.push(value as u8);
+                return result;
+            }
+            result.push((value & 0x7F) as u8 | 0x80);
+            value >>= 7;
+        }
+    }
+
+    fn mc(value: i64) -> Vec<u8> {
+        let mut result = vec![];
+        let mut remaining = value.unsigned_abs();
+        loop {
+            if remaining < 0x40 {
+                result.push(remaining as u8 | if value < 0 { 0x40 } else { 0x00 });
+                return result;
+            }
+            result.push((remaining & 0x7F) as u8 | 0x80);
+            remaining >>= 7;
+        }
+    }
+
+    // builds the main data, string and handle streams of a single object
+    struct TestObject {
+        version: AcadVersion,
+        main: BitWriter,
+        strings: BitWriter,
+        handles: BitWriter,
+        bit_size_position: usize,
+    }
+
+    impl TestObject {
+        fn new(version: AcadVersion, object_type: u16, handle: u64) -> Self {
+            let mut main = BitWriter::default();
+            if version >= AcadVersion::R2010 {
+                main.bb(2);
+                main.rs(object_type);
+            } else {
+                main.bs(object_type as i16);
+            }
+            let bit_size_position = main.length;
+            if version < AcadVersion::R2010 {
+                main.rl(0);
+            }
+            main.h(0, handle);
+            main.bs(0); // no extended data
+            TestObject {
+                version,
+                main,
+                strings: BitWriter::default(),
+                handles: BitWriter::default(),
+                bit_size_position,
+            }
+        }
+        fn text(&mut self, value: &str) {
+            if self.version >= AcadVersion::R2007 {
+                self.strings.tu(value);
+            } else {
+                self.main.tv(value);
+            }
+        }
+        fn cmc(&mut self, index: i16) {
+            self.main.bs(index);
+            if self.version >= AcadVersion::R2004 {
+                self.main.bl(0);
+                self.main.rc(0);
+            }
+        }
+        fn entity(
+            version: AcadVersion,
+            object_type: u16,
+            handle: u64,
+            owner: u64,
+            layer: u64,
+        ) -> Self {
+            TestObject::entity_with_preview(version, object_type, handle, owner, layer, &[])
+        }
+        fn entity_with_preview(
+            version: AcadVersion,
+            object_type: u16,
+            handle: u64,
+            owner: u64,
+            layer: u64,
+            preview: &[u8],
+        ) -> Self {
+            let mut o = TestObject::new(version, object_type, handle);
+            o.main.b(!preview.is_empty());
+            if !preview.is_empty() {
+                assert!(version < AcadVersion::R2010);
+                o.main.rl(preview.len() as u32);
+                for b in preview {
+                    o.main.rc(*b);
+                }
+            }
+            o.main.bb(if owner == 0 { 2 } else { 0 });
+            o.main.bl(0); // reactors
+            if version >= AcadVersion::R2004 {
+                o.main.b(true); // no extension dictionary
+            }
+            if version >= AcadVersion::R2013 {
+                o.main.b(false);
+            }
+            if version <= AcadVersion::R2000 {
+                o.main.b(true); // no links
+            }
+            o.main.bs(256);
+            o.main.bd(1.0);
+            o.main.bb(0); // line type by layer
+            o.main.bb(0); // plot style by layer
+            if version >= AcadVersion::R2007 {
+                o.main.bb(0);
+                o.main.rc(0);
+            }
+            if version >= AcadVersion::R2010 {
+                o.main.b(false);
+                o.main.b(false);
+                o.main.b(false);
+            }
+            o.main.bs(0);
+            o.main.rc(29);
+            if owner != 0 {
+                o.handles.h(4, owner);
+            }
+            if version < AcadVersion::R2004 {
+                o.handles.h(3, 0);
+            }
+            o.handles.h(5, layer);
+            o
+        }
+        fn object(version: AcadVersion, object_type: u16, handle: u64, owner: u64) -> Self {
+            let mut o = TestObject::new(version, object_type, handle);
+            o.main.bl(0); // reactors
+            if version >= AcadVersion::R2004 {
+                o.main.b(true); // no extension dictionary
+            }
+            if version >= AcadVersion::R2013 {
+                o.main.b(false);
+            }
+            o.handles.h(4, owner);
+            if version < AcadVersion::R2004 {
+                o.handles.h(3, 0);
+            }
+            o
+        }
+        fn table_entry(version: AcadVersion, object_type: u16, handle: u64, name: &str) -> Self {
+            let mut o = TestObject::object(version, object_type, handle, 1);
+            o.text(name);
+            o.main.b(false);
+            o.main.bs(0);
+            o.main.b(false);
+            o.handles.h(5, 0); // external reference
+            o
+        }
+        fn finish(mut self) -> Vec<u8> {
+            let mut data = BitWriter::default();
+            let mut result = BitWriter::default();
+            if self.version >= AcadVersion::R2010 {
+                data.append(&self.main);
+                if self.strings.length > 0 {
+                    data.append(&self.strings);
+                    data.rs(self.strings.length as u16);
+                    data.b(true);
+                } else {
+                    data.b(false);
+                }
+                let bit_size = data.length;
+                data.append(&self.handles);
+                result.rs(data.bytes.len() as u16);
+                for b in umc((data.bytes.len() * 8 - bit_size) as u64) {
+                    result.rc(b);
+                }
+            } else {
+                if self.version >= AcadVersion::R2007 {
+                    if self.strings.length > 0 {
+                        self.main.append(&self.strings);
+                        self.main.rs(self.strings.length as u16);
+                        self.main.b(true);
+                    } else {
+                        self.main.b(false);
+                    }
+                }
+                let bit_size = self.main.length as u32;
+                let position = self.bit_size_position;
+                self.main
+                    .set_bits(position, u64::from(bit_size.swap_bytes()), 32);
+                data.append(&self.main);
+                data.append(&self.handles);
+                result.rs(data.bytes.len() as u16);
+            }
+
+            let mut bytes = result.bytes;
+            bytes.extend(data.bytes);
+            bytes.extend([0, 0]); // CRC
+            bytes
+        }
+    }
+
+    fn layer(version: AcadVersion, handle: u64, name: &str, color: i16) -> Vec<u8> {
+        let mut o = TestObject::table_entry(version, TYPE_LAYER, handle, name);
+        o.main.bs(0x10);
+        o.main.bs(color);
+        if version >= AcadVersion::R2004 {
+            o.main.bl(0xC300_0000u32 as i32 | i32::from(color));
+            o.main.rc(0);
+        }
+        o.handles.h(5, 0); // plot style
+        if version >= AcadVersion::R2007 {
+            o.handles.h(5, 0); // material
+        }
+        o.handles.h(5, 0); // line type
+        o.finish()
+    }
+
+    fn style(version: AcadVersion, handle: u64, name: &str) -> Vec<u8> {
+        let mut o = TestObject::table_entry(version, TYPE_STYLE, handle, name);
+        o.main.b(false);
+        o.main.b(false);
+        o.main.bd(0.0);
+        o.main.bd(1.0);
+        o.main.bd(0.0);
+        o.main.rc(0);
+        o.main.bd(2.5);
+        o.text("arial.ttf");
+        o.text("");
+        o.finish()
+    }
+
+    fn dim_style(version: AcadVersion, handle: u64, name: &str, text_style: u64) -> Vec<u8> {
+        let v = version;
+        let mut o = TestObject::table_entry(v, TYPE_DIMSTYLE, handle, name);
+        o.text("mm"); // DIMPOST
+        o.text("");
+        o.main.bd(2.0); // DIMSCALE
+        for _ in 0..8 {
+            o.main.bd(0.0);
+        }
+        if v >= AcadVersion::R2007 {
+            o.main.bd(0.0);
+            o.main.bd(0.0);
+            o.main.bs(0);
+            o.cmc(0);
+        }
+        for _ in 0..6 {
+            o.main.b(false);
+        }
+        for _ in 0..3 {
+            o.main.bs(0);
+        }
+        if v >= AcadVersion::R2007 {
+            o.main.bs(0);
+        }
+        o.main.bd(0.25); // DIMTXT
+        for _ in 0..8 {
+            o.main.bd(0.0);
+        }
+        o.main.b(false);
+        o.main.bs(2);
+        for _ in 0..4 {
+            o.main.b(false);
+        }
+        for _ in 0..3 {
+            o.cmc(0);
+        }
+        for value in [0, 4, 4, 2, 4, 0, 0, 2, 46, 0, 0] {
+            o.main.bs(value); // DIMADEC through DIMJUST
+        }
+        o.main.b(false);
+        o.main.b(false);
+        for _ in 0..4 {
+            o.main.bs(0);
+        }
+        o.main.b(false);
+        o.main.bs(3);
+        if v >= AcadVersion::R2007 {
+            o.main.b(false);
+        }
+        if v >= AcadVersion::R2010 {
+            o.main.b(false);
+            o.main.bd(0.0);
+            o.text("");
+            o.main.bd(0.0);
+            o.text("");
+        }
+        o.main.bs(-2);
+        o.main.bs(-2);
+        o.main.b(false);
+        o.handles.h(5, text_style);
+        for _ in 0..4 {
+            o.handles.h(5, 0);
+        }
+        if v >= AcadVersion::R2007 {
+            for _ in 0..3 {
+                o.handles.h(5, 0);
+            }
+        }
+        o.finish()
+    }
+
+    fn block_header(
+        version: AcadVersion,
+        handle: u64,
+        name: &str,
+        block_entity: u64,
+        entities: &[u64],
+        end_block: u64,
+    ) -> Vec<u8> {
+        let mut o = TestObject::table_entry(version, TYPE_BLOCK_HEADER, handle, name);
+        for _ in 0..5 {
+            o.main.b(false);
+        }
+        if version >= AcadVersion::R2004 {
+            o.main.bl(entities.len() as i32);
+        }
+        o.main.point(&Point::new(1.0, 2.0, 0.0));
+        o.text("");
+        o.main.rc(0); // no inserts
+        o.text("description");
+        o.main.bl(0); // no preview
+        if version >= AcadVersion::R2007 {
+            o.main.bs(0);
+            o.main.b(true);
+            o.main.rc(1);
+        }
+        o.handles.h(3, block_entity);
+        if version >= AcadVersion::R2004 {
+            for entity in entities {
+                o.handles.h(4, *entity);
+            }
+        } else {
+            o.handles.h(4, entities.first().cloned().unwrap_or(0));
+            o.handles.h(4, entities.last().cloned().unwrap_or(0));
+        }
+        o.handles.h(3, end_block);
+        o.handles.h(5, 0); // layout
+        o.finish()
+    }
+
+    fn line(version: AcadVersion, handle: u64, layer: u64) -> Vec<u8> {
+        let mut o = TestObject::entity(version, TYPE_LINE, handle, 0, layer);
+        o.main.b(false);
+        o.main.rd(1.0);
+        o.main.dd(4.0);
+        o.main.rd(2.0);
+        o.main.dd(5.0);
+        o.main.rd(3.0);
+        o.main.bb(0); // same as the start point
+        o.main.b(true);
+        o.main.b(true);
+        o.finish()
+    }
+
+    fn text(version: AcadVersion, handle: u64, layer: u64, style: u64, value: &str) -> Vec<u8> {
+        let mut o = TestObject::entity(version, TYPE_TEXT, handle, 0, layer);
+        o.main.rc(0xF7); // only the rotation is stored
+        o.main.rd(1.0);
+        o.main.rd(2.0);
+        o.main.b(true);
+        o.main.b(true);
+        o.main.rd(std::f64::consts::FRAC_PI_2);
+        o.main.rd(2.5);
+        o.text(value);
+        o.handles.h(5, style);
+        o.finish()
+    }
+
+    fn seqend(version: AcadVersion, object_type: u16, handle: u64, owner: u64) -> Vec<u8> {
+        let mut o = TestObject::entity(version, object_type, handle, owner, 0x10);
+        if object_type == TYPE_BLOCK {
+            o.text("B1");
+        }
+        o.finish()
+    }
+
+    fn circle(version: AcadVersion, handle: u64, owner: u64, layer: u64) -> Vec<u8> {
+        let mut o = TestObject::entity(version, TYPE_CIRCLE, handle, owner, layer);
+        o.main.point(&Point::new(1.0, 1.0, 0.0));
+        o.main.bd(3.0);
+        o.main.b(true);
+        o.main.b(true);
+        o.finish()
+    }
+
+    fn insert(version: AcadVersion, handle: u64, block: u64) -> Vec<u8> {
+        let mut o = TestObject::entity(version, TYPE_INSERT, handle, 0, 0x10);
+        o.main.point(&Point::new(10.0, 20.0, 0.0));
+        o.main.bb(3);
+        o.main.bd(0.0);
+        o.main.point(&Point::new(0.0, 0.0, 1.0));
+        o.main.b(false);
+        o.handles.h(5, block);
+        o.finish()
+    }
+
+    fn linear_dimension(version: AcadVersion, handle: u64, dim_style: u64, block: u64) -> Vec<u8> {
+        let mut o = TestObject::entity(version, TYPE_DIMENSION_LINEAR, handle, 0, 0x10);
+        if version >= AcadVersion::R2010 {
+            o.main.rc(0);
+        }
+        o.main.point(&Point::new(0.0, 0.0, 1.0));
+        o.main.rd(5.0);
+        o.main.rd(6.0);
+        o.main.bd(0.0);
+        o.main.rc(0x01); // text isn't at a user-defined location
+        o.text("<>");
+        o.main.bd(0.0);
+        o.main.bd(0.0);
+        o.main.point(&Point::new(1.0, 1.0, 1.0));
+        o.main.bd(0.0);
+        o.main.bs(5);
+        o.main.bs(1);
+        o.main.bd(1.0);
+        o.main.bd(10.0);
+        if version >= AcadVersion::R2007 {
+            o.main.b(false);
+            o.main.b(false);
+            o.main.b(false);
+        }
+        o.main.rd(0.0);
+        o.main.rd(0.0);
+        o.main.point(&Point::new(0.0, 0.0, 0.0));
+        o.main.point(&Point::new(10.0, 0.0, 0.0));
+        o.main.point(&Point::new(10.0, 5.0, 0.0));
+        o.main.bd(0.0);
+        o.main.bd(std::f64::consts::FRAC_PI_2);
+        o.handles.h(5, dim_style);
+        o.handles.h(5, block);
+        o.finish()
+    }
+
+    fn tolerance(version: AcadVersion, handle: u64, dim_style: u64) -> Vec<u8> {
+        let mut o = TestObject::entity(version, TYPE_TOLERANCE, handle, 0, 0x10);
+        o.main.point(&Point::new(1.0, 2.0, 0.0));
+        o.main.point(&Point::new(1.0, 0.0, 0.0));
+        o.main.point(&Point::new(0.0, 0.0, 1.0));
+        o.text("{\\Fgdt;j}%%v0.1");
+        o.handles.h(5, dim_style);
+        o.finish()
+    }
+
+    fn leader(version: AcadVersion, handle: u64, dim_style: u64) -> Vec<u8> {
+        let mut o = TestObject::entity(version, TYPE_LEADER, handle, 0, 0x10);
+        o.main.b(false);
+        o.main.bs(3); // no annotation
+        o.main.bs(1); // spline
+        o.main.bl(2);
+        o.main.point(&Point::new(0.0, 0.0, 0.0));
+        o.main.point(&Point::new(3.0, 4.0, 0.0));
+        o.main.point(&Point::new(0.0, 0.0, 0.0));
+        o.main.point(&Point::new(0.0, 0.0, 1.0));
+        o.main.point(&Point::new(1.0, 0.0, 0.0));
+        o.main.point(&Point::new(0.0, 0.0, 0.0));
+        o.main.point(&Point::new(0.0, 0.0, 0.0));
+        o.main.bd(0.5);
+        o.main.bd(2.0);
+        o.main.b(true); // hookline on the x direction
+        o.main.b(false); // no arrowhead
+        o.main.bs(0);
+        o.main.b(false);
+        o.main.b(false);
+        o.handles.h(5, 0); // associated annotation
+        o.handles.h(5, dim_style);
+        o.finish()
+    }
+
+    fn mline_style(version: AcadVersion, handle: u64, name: &str) -> Vec<u8> {
+        let mut o = TestObject::object(version, TYPE_MLINESTYLE, handle, 0x0C);
+        o.text(name);
+        o.text("two lines");
+        o.main.bs(0);
+        o.cmc(256);
+        o.main.bd(std::f64::consts::FRAC_PI_2);
+        o.main.bd(std::f64::consts::FRAC_PI_2);
+        o.main.rc(2);
+        for (offset, line_type) in [(0.5, 32767), (-0.5, 32766)] {
+            o.main.bd(offset);
+            o.cmc(1);
+            o.main.bs(line_type);
+        }
+        o.finish()
+    }
+
+    fn mline(version: AcadVersion, handle: u64, style: u64) -> Vec<u8> {
+        let mut o = TestObject::entity(version, TYPE_MLINE, handle, 0, 0x10);
+        o.main.bd(2.0);
+        o.main.rc(1); // middle
+        o.main.point(&Point::new(1.0, 2.0, 0.0));
+        o.main.point(&Point::new(0.0, 0.0, 1.0));
+        o.main.bs(3); // closed
+        o.main.rc(2);
+        o.main.bs(2);
+        for x in [1.0, 5.0] {
+            o.main.point(&Point::new(x, 2.0, 0.0));
+            o.main.point(&Point::new(1.0, 0.0, 0.0));
+            o.main.point(&Point::new(0.0, 1.0, 0.0));
+            for _ in 0..2 {
+                o.main.bs(2);
+                o.main.bd(0.0);
+                o.main.bd(4.0);
+                o.main.bs(0);
+            }
+        }
+        o.handles.h(5, style);
+        o.finish()
+    }
+
+    // writes the SAT text encrypted in a single block, or with a version whose data is binary SAB
+    fn solid_3d(version: AcadVersion, handle: u64, sat_text: &str, acis_version: i16) -> Vec<u8> {
+        let mut o = TestObject::entity(version, TYPE_3DSOLID, handle, 0, 0x10);
+        o.main.b(false);
+        o.main.b(false);
+        o.main.bs(acis_version);
+        o.main.bl(sat_text.len() as i32);
+        for b in sat_text.bytes() {
+            o.main.rc(if b > 32 { 159 - b } else { b });
+        }
+        o.main.bl(0);
+        o.finish()
+    }
+
+    fn image(version: AcadVersion, object_type: u16, handle: u64) -> Vec<u8> {
+        let mut o = TestObject::entity(version, object_type, handle, 0, 0x10);
+        o.main.bl(0);
+        o.main.point(&Point::new(1.0, 2.0, 0.0));
+        o.main.point(&Point::new(0.1, 0.0, 0.0));
+        o.main.point(&Point::new(0.0, 0.1, 0.0));
+        o.main.rd(640.0);
+        o.main.rd(480.0);
+        o.main.bs(7);
+        o.main.b(true);
+        o.main.rc(50);
+        o.main.rc(60);
+        o.main.rc(10);
+        if version >= AcadVersion::R2010 {
+            o.main.b(false);
+        }
+        o.main.bs(1); // rectangular
+        for value in [-0.5, -0.5, 639.5, 479.5] {
+            o.main.rd(value);
+        }
+        o.handles.h(5, 0x50);
+        o.handles.h(3, 0x51);
+        o.finish()
+    }
+
+    fn pdf_underlay(version: AcadVersion, object_type: u16, handle: u64) -> Vec<u8> {
+        let mut o = TestObject::entity(version, object_type, handle, 0, 0x10);
+        o.main.point(&Point::new(0.0, 0.0, 1.0));
+        o.main.point(&Point::new(1.0, 1.0, 0.0));
+        o.main.point(&Point::new(2.0, 3.0, 1.0));
+        o.main.bd(std::f64::consts::FRAC_PI_2);
+        o.main.rc(2);
+        o.main.rc(60);
+        o.main.rc(25);
+        o.main.bl(2);
+        for value in [0.0, 0.0, 8.0, 6.0] {
+            o.main.rd(value);
+        }
+        o.handles.h(5, 0x52);
+        o.finish()
+    }
+
+    fn light(version: AcadVersion, object_type: u16, handle: u64) -> Vec<u8> {
+        let mut o = TestObject::entity(version, object_type, handle, 0, 0x10);
+        o.main.bl(1);
+        o.text("spot");
+        o.main.bl(3); // spot
+        o.main.b(true);
+        o.cmc(7);
+        o.main.b(false);
+        o.main.bd(2.0);
+        o.main.point(&Point::new(1.0, 2.0, 3.0));
+        o.main.point(&Point::new(1.0, 2.0, 0.0));
+        o.main.bl(2); // inverse square
+        o.main.b(true);
+        o.main.bd(1.0);
+        o.main.bd(10.0);
+        o.main.bd(std::f64::consts::FRAC_PI_4);
+        o.main.bd(std::f64::consts::FRAC_PI_2);
+        o.main.b(true);
+        o.main.bl(1); // shadow maps
+        o.main.bs(256);
+        o.main.rc(3);
+        o.finish()
+    }
+
+    fn xrecord(version: AcadVersion, object_type: u16, handle: u64, owner: u64) -> Vec<u8> {
+        let mut o = TestObject::object(version, object_type, handle, owner);
+        let mut data = BitWriter::default();
+        data.rs(1);
+        if version >= AcadVersion::R2007 {
+            data.rs(2);
+            data.rs(u16::from(b'h'));
+            data.rs(u16::from(b'i'));
+        } else {
+            data.rs(2);
+            data.rc(30); // code page
+            data.rc(b'h');
+            data.rc(b'i');
+        }
+        data.rs(10);
+        data.rd(1.0);
+        data.rd(2.0);
+        data.rd(3.0);
+        data.rs(70);
+        data.rs(7);
+        data.rs(290);
+        data.rc(1);
+        data.rs(340);
+        for b in 0x40u64.to_le_bytes() {
+            data.rc(b);
+        }
+        o.main.bl(data.bytes.len() as i32);
+        for b in &data.bytes {
+            o.main.rc(*b);
+        }
+        o.main.bs(1); // keep existing
+        o.handles.h(5, 0x40);
+        o.finish()
+    }
+
+    fn group(version: AcadVersion, handle: u64, owner: u64, entities: &[u64]) -> Vec<u8> {
+        let mut o = TestObject::object(version, TYPE_GROUP, handle, owner);
+        o.text("doors");
+        o.main.bs(0);
+        o.main.bs(1);
+        o.main.bl(entities.len() as i32);
+        for entity in entities {
+            o.handles.h(5, *entity);
+        }
+        o.finish()
+    }
+
+    fn lw_polyline(version: AcadVersion, object_type: u16, handle: u64) -> Vec<u8> {
+        let mut o = TestObject::entity(version, object_type, handle, 0, 0x10);
+        o.main.bs(0x0210);
+        o.main.bl(2);
+        o.main.bl(2);
+        o.main.rd(1.0);
+        o.main.rd(2.0);
+        o.main.dd(3.0);
+        o.main.bb(0); // same y as the previous point
+        o.main.bd(1.0);
+        o.main.bd(0.0);
+        o.finish()
+    }
+
+    fn polyline(version: AcadVersion, handle: u64, vertices: &[u64], seqend: u64) -> Vec<u8> {
+        let mut o = TestObject::entity(version, TYPE_POLYLINE_2D, handle, 0, 0x10);
+        o.main.bs(1);
+        o.main.bs(0);
+        o.main.bd(0.0);
+        o.main.bd(0.0);
+        o.main.b(true);
+        o.main.bd(0.0);
+        o.main.b(true);
+        if version >= AcadVersion::R2004 {
+            o.main.bl(vertices.len() as i32);
+            for vertex in vertices {
+                o.handles.h(4, *vertex);
+            }
+        } else {
+            o.handles.h(4, vertices[0]);
+            o.handles.h(4, vertices[vertices.len() - 1]);
+        }
+        o.handles.h(3, seqend);
+        o.finish()
+    }
+
+    fn vertex(version: AcadVersion, handle: u64, owner: u64, location: Point) -> Vec<u8> {
+        let mut o = TestObject::entity(version, TYPE_VERTEX_2D, handle, owner, 0x10);
+        o.main.rc(0);
+        o.main.point(&location);
+        o.main.bd(-0.5); // both widths
+        o.main.bd(0.0);
+        if version >= AcadVersion::R2010 {
+            o.main.bl(0);
+        }
+        o.main.bd(0.0);
+        o.finish()
+    }
+
+    // an object map with a single section
+    fn object_map(locations: &[(u64, usize)]) -> Vec<u8> {
+        let mut body = vec![];
+        let mut last_handle = 0;
+        let mut last_location = 0;
+        for (handle, location) in locations {
+            body.extend(umc(handle - last_handle));
+            body.extend(mc(*location as i64 - last_location as i64));
+            last_handle = *handle;
+            last_location = *location;
+        }
+
+        let size = body.len() + 2;
+        let mut result = vec![(size >> 8) as u8, size as u8];
+        result.extend(body);
+        result.extend([0, 0, 0, 2, 0, 0]);
+        result
+    }
+
+    // writes a header section with mostly default values; handles are written to the handle
+    // stream for R2007 and later
+    fn header_section(version: AcadVersion) -> Vec<u8> {
+        let mut o = TestObject {
+            version,
+            main: BitWriter::default(),
+            strings: BitWriter::default(),
+            handles: BitWriter::default(),
+            bit_size_position: 0,
+        };
+        fn h(o: &mut TestObject, value: u64) {
+            if o.version >= AcadVersion::R2007 {
+                o.handles.h(5, value);
+            } else {
+                o.main.h(5, value);
+            }
+        }
+        let v = version;
+        for _ in 0..4 {
+            o.main.bd(0.0);
+        }
+        for _ in 0..4 {
+            o.text("");
+        }
+        o.main.bl(24);
+        o.main.bl(0);
+        if v < AcadVersion::R2004 {
+            h(&mut o, 0);
+        }
+        for i in 0..9 {
+            o.main.b(i == 3); // ORTHOMODE
+        }
+        if v >= AcadVersion::R2004 {
+            o.main.b(false);
+        }
+        for _ in 0..11 {
+            o.main.b(false);
+        }
+        for value in [1, 3, 2, 4, 0, 0, 1, 0] {
+            o.main.bs(value); // PROXYGRAPHICS through PDMODE
+        }
+        if v >= AcadVersion::R2004 {
+            for _ in 0..3 {
+                o.main.bl(0);
+            }
+        }
+        for _ in 0..19 {
+            o.main.bs(0);
+        }
+        o.main.bd(2.5); // LTSCALE
+        for _ in 0..20 {
+            o.main.bd(0.0);
+        }
+        if v < AcadVersion::R2007 {
+            o.text("acad");
+        }
+        o.main.bl(2_451_545); // TDCREATE
+        o.main.bl(0);
+        o.main.bl(2_451_545);
+        o.main.bl(0);
+        if v >= AcadVersion::R2004 {
+            for _ in 0..3 {
+                o.main.bl(0);
+            }
+        }
+        for _ in 0..4 {
+            o.main.bl(0);
+        }
+        o.cmc(3); // CECOLOR
+        o.main.h(0, 0x200); // HANDSEED
+        h(&mut o, 0x11); // CLAYER
+        h(&mut o, 0x12); // TEXTSTYLE
+        h(&mut o, 0);
+        if v >= AcadVersion::R2007 {
+            h(&mut o, 0);
+        }
+        h(&mut o, 0x13); // DIMSTYLE
+        h(&mut o, 0);
+        o.main.bd(1.0);
+        for extents_max in [Point::origin(), Point::new(10.0, 20.0, 0.0)] {
+            for _ in 0..2 {
+                o.main.point(&Point::origin());
+            }
+            o.main.point(&extents_max); // EXTMAX
+            o.main.rd(0.0);
+            o.main.rd(0.0);
+            o.main.rd(12.0);
+            o.main.rd(9.0);
+            o.main.bd(0.0);
+            o.main.point(&Point::origin());
+            o.main.point(&Point::new(1.0, 0.0, 0.0));
+            o.main.point(&Point::new(0.0, 1.0, 0.0));
+            h(&mut o, 0);
+            h(&mut o, 0);
+            o.main.bs(0);
+            h(&mut o, 0);
+            for _ in 0..6 {
+                o.main.point(&Point::origin());
+            }
+        }
+        o.text("mm"); // DIMPOST
+        o.text("");
+        o.main.bd(2.0); // DIMSCALE
+        for _ in 0..8 {
+            o.main.bd(0.0);
+        }
+        if v >= AcadVersion::R2007 {
+            o.main.bd(0.0);
+            o.main.bd(0.0);
+            o.main.bs(0);
+            o.cmc(0);
+        }
+        for _ in 0..6 {
+            o.main.b(false);
+        }
+        for _ in 0..3 {
+            o.main.bs(0);
+        }
+        if v >= AcadVersion::R2007 {
+            o.main.bs(0);
+        }
+        for _ in 0..9 {
+            o.main.bd(0.0);
+        }
+        o.main.b(false);
+        o.main.bs(2);
+        for _ in 0..4 {
+            o.main.b(false);
+        }
+        for _ in 0..3 {
+            o.cmc(0);
+        }
+        for value in [0, 4, 4, 2, 4, 0, 0, 2, 46, 0, 0] {
+            o.main.bs(value); // DIMADEC through DIMJUST
+        }
+        o.main.b(false);
+        o.main.b(false);
+        for _ in 0..4 {
+            o.main.bs(0);
+        }
+        o.main.b(false);
+        o.main.bs(3);
+        if v >= AcadVersion::R2007 {
+            o.main.b(false);
+        }
+        if v >= AcadVersion::R2010 {
+            o.main.b(false);
+            o.main.bd(0.0);
+            o.text("");
+            o.main.bd(0.0);
+            o.text("");
+        }
+        h(&mut o, 0x12); // DIMTXSTY
+        for _ in 0..4 {
+            h(&mut o, 0);
+        }
+        if v >= AcadVersion::R2007 {
+            for _ in 0..3 {
+                h(&mut o, 0);
+            }
+        }
+        o.main.bs(-2);
+        o.main.bs(-2);
+        let mut object_handles = 12;
+        if v <= AcadVersion::R2000 {
+            object_handles += 1;
+        }
+        for _ in 0..object_handles {
+            h(&mut o, 0);
+        }
+        o.main.bs(1);
+        o.main.bs(70);
+        o.text("");
+        o.text("");
+        let mut dictionary_handles = 3;
+        if v >= AcadVersion::R2004 {
+            dictionary_handles += 2;
+        }
+        if v >= AcadVersion::R2007 {
+            dictionary_handles += 1;
+        }
+        for _ in 0..dictionary_handles {
+            h(&mut o, 0);
+        }
+        o.main.bl(0x05); // CELWEIGHT
+        o.main.bs(4); // INSUNITS
+        o.main.bs(0);
+        o.text("{12345678-1234-1234-1234-123456789ABC}");
+        o.text("{00000000-0000-0000-0000-000000000000}");
+        if v >= AcadVersion::R2004 {
+            for _ in 0..6 {
+                o.main.rc(0);
+            }
+            o.main.bs(257);
+            o.main.bs(257);
+            o.main.rc(0);
+            o.main.rc(0);
+            o.text("");
+        }
+
+        let mut data = BitWriter::default();
+        if v >= AcadVersion::R2007 {
+            data.rl(0);
+            data.append(&o.main);
+            if o.strings.length > 0 {
+                data.append(&o.strings);
+                data.rs(o.strings.length as u16);
+                data.b(true);
+            } else {
+                data.b(false);
+            }
+            let bit_size = data.length as u32;
+            data.set_bits(0, u64::from(bit_size.swap_bytes()), 32);
+            data.append(&o.handles);
+        } else {
+            data.append(&o.main);
+        }
+        let mut section = vec![0u8; 16];
+        section.extend((data.bytes.len() as u32).to_le_bytes());
+        section.extend(data.bytes);
+        section.extend([0, 0]); // CRC
+        section
+    }
+
+    fn r2000_file(objects: &[(u64, Vec<u8>)]) -> Vec<u8> {
+        r2000_file_with_sections(&[], &[], objects)
+    }
+
+    fn r2000_file_with_sections(
+        header: &[u8],
+        classes: &[(u16, &str)],
+        objects: &[(u64, Vec<u8>)],
+    ) -> Vec<u8> {
+        let mut file = vec![0u8; 0x80];
+        file[0..6].copy_from_slice(b"AC1015");
+        file[0x13] = 30;
+
+        let mut locations = vec![];
+        for (handle, data) in objects {
+            locations.push((*handle, file.len()));
+            file.extend(data);
+        }
+
+        let mut class_data = BitWriter::default();
+        for (number, name) in classes {
+            class_data.bs(*number as i16);
+            class_data.bs(0);
+            class_data.tv("ObjectDBX Classes");
+            class_data.tv(name);
+            class_data.tv(name);
+            class_data.b(false);
+            class_data.bs(0x1F2);
+        }
+        let header_location = file.len();
+        file.extend(header);
+
+        let classes_location = file.len();
+        file.extend([0u8; 16]);
+        file.extend((class_data.bytes.len() as u32).to_le_bytes());
+        file.extend(class_data.bytes);
+        file.extend([0, 0]); // CRC
+        file.extend([0u8; 16]);
+        let classes_size = file.len() - classes_location;
+
+        let handles_location = file.len();
+        file.extend(object_map(&locations));
+        let handles_size = file.len() - handles_location;
+
+        let mut locator = BitWriter::default();
+        locator.rl(3);
+        for (number, location, size) in [
+            (0, header_location, header.len()),
+            (1, classes_location, classes_size),
+            (2, handles_location, handles_size),
+        ] {
+            locator.rc(number);
+            locator.rl(location as u32);
+            locator.rl(size as u32);
+        }
+        file[0x15..0x15 + locator.bytes.len()].copy_from_slice(&locator.bytes);
+        file
+    }
+
+    // compresses data as a single run of literal bytes
+    fn compress_literal(data: &[u8]) -> Vec<u8> {
+        assert!(data.len() >= 4);
+        let mut result = vec![];
+        if data.len() - 3 <= 0x0F {
+            result.push((data.len() - 3) as u8);
+        } else {
+            result.push(0x00);
+            let mut remaining = data.len() - 3 - 0x0F;
+            while remaining > 0xFF {
+                result.push(0x00);
+                remaining -= 0xFF;
+            }
+            result.push(remaining as u8);
+        }
+        result.extend(data);
+        result.push(0x11);
+        result
+    }
+
+    fn system_page(page_type: u32, data: &[u8]) -> Vec<u8> {
+        let compressed = compress_literal(data);
+        let mut page = BitWriter::default();
+        page.rl(page_type);
+        page.rl(data.len() as u32);
+        page.rl(compressed.len() as u32);
+        page.rl(2);
+        page.rl(0);
+        let mut result = page.bytes;
+        result.extend(compressed);
+        result
+    }
+
+    fn r2004_file(magic: &[u8], objects: &[(u64, Vec<u8>)]) -> Vec<u8> {
+        r2004_file_with_header(magic, &[], objects)
+    }
+
+    fn r2004_file_with_header(magic: &[u8], header: &[u8], objects: &[(u64, Vec<u8>)]) -> Vec<u8> {
+        let mut object_data = vec![];
+        let mut locations = vec![];
+        for (handle, data) in objects {
+            locations.push((*handle, object_data.len()));
+            object_data.extend(data);
+        }
+        let mut sections = vec![];
+        if !header.is_empty() {
+            sections.push((SECTION_HEADER, header.to_vec()));
+        }
+        sections.push((SECTION_HANDLES, object_map(&locations)));
+        sections.push((SECTION_OBJECTS, object_data));
+
+        // page 1 is the page map, page 2 the section map and the rest hold the section data
+        let data_pages = sections
+            .iter()
+            .map(|(_, data)| compress_literal(data))
+            .collect::<Vec<_>>();
+        let mut section_map = BitWriter::default();
+        section_map.rl(sections.len() as u32);
+        section_map.rl(2);
+        section_map.rl(0x7400);
+        section_map.rl(0);
+        section_map.rl(sections.len() as u32);
+        for (i, (name, data)) in sections.iter().enumerate() {
+            section_map.rl(data.len() as u32);
+            section_map.rl(0);
+            section_map.rl(1);
+            section_map.rl(0x7400);
+            section_map.rl(1);
+            section_map.rl(2);
+            section_map.rl(i as u32 + 1);
+            section_map.rl(0);
+            let mut name_bytes = [0u8; 64];
+            name_bytes[..name.len()].copy_from_slice(name.as_bytes());
+            for b in name_bytes.iter() {
+                section_map.rc(*b);
+            }
+            section_map.rl(i as u32 + 3);
+            section_map.rl(data.len() as u32);
+            section_map.rl(0);
+            section_map.rl(0);
+        }
+        let section_map = system_page(SECTION_MAP_PAGE_TYPE, &section_map.bytes);
+
+        let page_count = 2 + data_pages.len();
+        let page_map_size = system_page(PAGE_MAP_PAGE_TYPE, &vec![0u8; page_count * 8]).len();
+        let mut page_sizes = vec![page_map_size, section_map.len()];
+        page_sizes.extend(data_pages.iter().map(|p| p.len() + 32));
+        let mut page_map = BitWriter::default();
+        for (i, size) in page_sizes.iter().enumerate() {
+            page_map.rl(i as u32 + 1);
+            page_map.rl(*size as u32);
+        }
+
+        let mut header = BitWriter::default();
+        for b in R2004_HEADER_MAGIC {
+            header.rc(*b);
+        }
+        while header.bytes.len() < 0x54 {
+            header.rc(0);
+        }
+        header.rl(0); // page map address
+        header.rl(0);
+        header.rl(2); // section map id
+        while header.bytes.len() < R2004_HEADER_LENGTH {
+            header.rc(0);
+        }
+
+        let mut file = vec![0u8; R2004_PAGE_MAP_BASE];
+        file[0..6].copy_from_slice(magic);
+        file[0x13] = 30;
+        file[R2004_HEADER_OFFSET..R2004_HEADER_OFFSET + R2004_HEADER_LENGTH]
+            .copy_from_slice(&decrypt_r2004_header(&header.bytes));
+        file.extend(system_page(PAGE_MAP_PAGE_TYPE, &page_map.bytes));
+        file.extend(section_map);
+        for (i, page) in data_pages.iter().enumerate() {
+            let address = file.len() as u32;
+            let mut header = BitWriter::default();
+            for value in [
+                DATA_PAGE_TYPE,
+                i as u32 + 1,
+                page.len() as u32,
+                sections[i].1.len() as u32,
+                0,
+                0,
+                0,
+                0,
+            ] {
+                header.rl(value ^ DATA_PAGE_HEADER_MASK ^ address);
+            }
+            file.extend(header.bytes);
+            file.extend(page);
+        }
+
+        file
+    }
+
+    // interleaves `data` into Reed-Solomon blocks of `data_size` bytes with zeroed parity bytes
+    fn encode_reed_solomon(data: &[u8], data_size: usize) -> Vec<u8> {
+        let block_count = data.len().div_ceil(data_size).max(1);
+        let mut result = vec![0u8; block_count * RS_BLOCK_SIZE];
+        for (index, b) in data.iter().enumerate() {
+            result[index / data_size + (index % data_size) * block_count] = *b;
+        }
+        result
+    }
+
+    // writes a system page that isn't compressed
+    fn r2007_system_page(data: &[u8]) -> Vec<u8> {
+        let mut padded = data.to_vec();
+        padded.resize((data.len() + 7) & !7, 0);
+        let mut page = encode_reed_solomon(&padded, RS_SYSTEM_DATA_SIZE);
+        page.resize((page.len() + 7) & !7, 0);
+        page
+    }
+
+    fn r2007_file(objects: &[(u64, Vec<u8>)]) -> Vec<u8> {
+        r2007_file_with_header(&[], objects)
+    }
+
+    fn r2007_file_with_header(header: &[u8], objects: &[(u64, Vec<u8>)]) -> Vec<u8> {
+        let mut object_data = vec![];
+        let mut locations = vec![];
+        for (handle, data) in objects {
+            locations.push((*handle, object_data.len()));
+            object_data.extend(data);
+        }
+        let mut sections = vec![];
+        if !header.is_empty() {
+            sections.push((SECTION_HEADER, header.to_vec()));
+        }
+        sections.push((SECTION_HANDLES, object_map(&locations)));
+        sections.push((SECTION_OBJECTS, object_data));
+
+        // pages 1 through n hold the section data, the next one the section map; the page map
+        // follows them
+        let mut pages = vec![];
+        let mut section_map = vec![];
+        for (i, (name, data)) in sections.iter().enumerate() {
+            let mut padded = data.clone();
+            padded.resize((data.len() + 7) & !7, 0);
+            pages.push(encode_reed_solomon(&padded, RS_SECTION_DATA_SIZE));
+            let name = name
+                .encode_utf16()
+                .chain([0])
+                .flat_map(|c| c.to_le_bytes())
+                .collect::<Vec<_>>();
+            for value in [data.len(), data.len(), 0, 0, name.len(), 0, 0, 1] {
+                section_map.extend((value as u64).to_le_bytes());
+            }
+            section_map.extend(name);
+            let page = pages.last().unwrap().len();
+            for value in [0, page, i + 1, data.len(), data.len(), 0, 0] {
+                section_map.extend((value as u64).to_le_bytes());
+            }
+        }
+        let section_map_id = pages.len() + 1;
+        pages.push(r2007_system_page(&section_map));
+        let mut page_map = vec![];
+        for (i, page) in pages.iter().enumerate() {
+            page_map.extend((page.len() as u64).to_le_bytes());
+            page_map.extend((i as u64 + 1).to_le_bytes());
+        }
+        let page_map_offset = pages.iter().map(|p| p.len()).sum::<usize>();
+
+        let mut file_header = vec![0u8; R2007_FILE_HEADER_LENGTH];
+        for (offset, value) in [
+            (0x18, 1),
+            (0x38, page_map_offset),
+            (0x50, page_map.len()),
+            (0x58, page_map.len()),
+            (0xB0, section_map.len()),
+            (0xC0, section_map_id),
+            (0xC8, section_map.len()),
+            (0xD8, 1),
+        ] {
+            file_header[offset..offset + 8].copy_from_slice(&(value as u64).to_le_bytes());
+        }
+        let mut header_data = vec![0u8; 32];
+        header_data.extend(file_header);
+        header_data.resize(R2007_HEADER_BLOCKS * RS_SYSTEM_DATA_SIZE, 0);
+        let mut header_data = encode_reed_solomon(&header_data, RS_SYSTEM_DATA_SIZE);
+        header_data.resize(R2007_HEADER_LENGTH, 0);
+
+        let mut file = vec![0u8; R2007_PAGES_BASE];
+        file[0..6].copy_from_slice(b"AC1021");
+        file[0x13] = 30;
+        file[R2007_HEADER_OFFSET..R2007_HEADER_OFFSET + R2007_HEADER_LENGTH]
+            .copy_from_slice(&header_data);
+        for page in pages {
+            file.extend(page);
+        }
+        file.extend(r2007_system_page(&page_map));
+        file
+    }
+
+    #[test]
+    fn decompress_literal_and_back_reference() {
+        let data = [0x01, b'a', b'b', b'c', b'd', 0x5C, 0x00, 0x11];
+        assert_eq!(b"abcdabcd".to_vec(), decompress(&data, 8).unwrap());
+    }
+
+    #[test]
+    fn decompress_two_byte_offset_with_trailing_literals() {
+        let data = [
+            0x01, b'a', b'b', b'c', b'd', 0x22, 0x0E, 0x00, b'x', b'y', 0x11,
+        ];
+        assert_eq!(b"abcdabcdxy".to_vec(), decompress(&data, 10).unwrap());
+    }
+
+    #[test]
+    fn decompress_long_literal_run() {
+        let expected = (0..40u8).collect::<Vec<_>>();
+        assert_eq!(
+            expected,
+            decompress(&compress_literal(&expected), 40).unwrap()
+        );
+    }
+
+    #[test]
+    fn decompress_invalid_back_reference() {
+        let data = [0x01, b'a', b'b', b'c', b'd', 0x5C, 0x10, 0x11];
+        assert!(decompress(&data, 8).is_err());
+    }
+
+    #[test]
+    fn decompress_size_larger_than_the_data_allows() {
+        let data = [0x01, b'a', b'b', b'c', b'd', 0x11];
+        assert!(matches!(
+            decompress(&data, 176_093_705_011),
+            Err(DxfError::InvalidBinaryFile)
+        ));
+    }
+
+    #[test]
+    fn decompress_output_larger_than_the_declared_size() {
+        let data = [0x01, b'a', b'b', b'c', b'd', 0x5C, 0x00, 0x11];
+        assert!(decompress(&data, 6).is_err());
+    }
+
+    #[test]
+    fn decompress_r2007_literal() {
+        let data = [0x00, b'a', b'b', b'c', b'd', b'e', b'f', b'g', b'h'];
+        assert_eq!(b"abcdefgh".to_vec(), decompress_r2007(&data, 8).unwrap());
+    }
+
+    #[test]
+    fn decompress_r2007_literal_and_back_reference() {
+        let data = [
+            0x00, b'a', b'b', b'c', b'd', b'e', b'f', b'g', b'h', 0x47, 0x00,
+        ];
+        assert_eq!(
+            b"abcdefghabcd".to_vec(),
+            decompress_r2007(&data, 12).unwrap()
+        );
+    }
+
+    #[test]
+    fn decompress_r2007_literal_halves_are_swapped() {
+        let mut data = vec![0x08];
+        data.extend(0..16u8);
+        let mut expected = (8..16u8).collect::<Vec<_>>();
+        expected.extend(0..8u8);
+        assert_eq!(expected, decompress_r2007(&data, 16).unwrap());
+    }
+
+    #[test]
+    fn decompress_r2007_invalid_back_reference() {
+        let data = [
+            0x00, b'a', b'b', b'c', b'd', b'e', b'f', b'g', b'h', 0x47, 0x08,
+        ];
+        assert!(decompress_r2007(&data, 12).is_err());
+    }
+
+    #[test]
+    fn decode_interleaved_reed_solomon_blocks() {
+        let data = (0..=255u8).cycle().take(300).collect::<Vec<_>>();
+        let encoded = encode_reed_solomon(&data, RS_SECTION_DATA_SIZE);
+        assert_eq!(2 * RS_BLOCK_SIZE, encoded.len());
+        let decoded = decode_reed_solomon(&encoded, 2, RS_SECTION_DATA_SIZE).unwrap();
+        assert_eq!(data, decoded[..300]);
+        assert!(decode_reed_solomon(&encoded[1..], 2, RS_SECTION_DATA_SIZE).is_err());
+    }
+
+    #[test]
+    fn decrypt_r2004_header_magic() {
+        let encrypted = [
+            0x68, 0x40, 0xF8, 0xF7, 0x92, 0x2A, 0xB5, 0xEF, 0x18, 0xDD, 0x0B, 0xF1,
+        ];
+        assert_eq!(R2004_HEADER_MAGIC, &decrypt_r2004_header(&encrypted)[..]);
+    }
+
+    #[test]
+    fn read_object_map_deltas() {
+        let map = object_map(&[(0x01, 0x100), (0x10, 0x180), (0x11, 0x120)]);
+        assert_eq!(
+            vec![(0x01, 0x100), (0x10, 0x180), (0x11, 0x120)],
+            read_object_map(&map).unwrap()
+        );
+    }
+
+    #[test]
+    fn unsupported_dwg_version() {
+        let mut data = vec![0u8; 0x100];
+        data[0..6].copy_from_slice(b"AC1014");
+        match Drawing::load_dwg(&mut data.as_slice()) {
+            Err(DxfError::UnsupportedDwgVersion(version)) => assert_eq!("AC1014", version),
+            _ => panic!("expected an unsupported version"),
+        }
+    }
+
+    #[test]
+    fn invalid_dwg_file() {
+        assert!(Drawing::load_dwg(&mut b"not a drawing".as_ref()).is_err());
+        let mut data = vec![0u8; 0x100];
+        data[0..6].copy_from_slice(b"AC1018");
+        assert!(Drawing::load_dwg(&mut data.as_slice()).is_err());
+    }
+
+    #[test]
+    fn corrupted_r2004_section_size() {
+        let mut data = r2004_file(
+            b"AC1018",
+            &[(0x10, layer(AcadVersion::R2004, 0x10, "0", 7))],
+        );
+        // the high word of the section size precedes the name by 28 bytes
+        let name = data
+            .windows(SECTION_OBJECTS.len())
+            .position(|w| w == SECTION_OBJECTS.as_bytes())
+            .unwrap();
+        data[name - 28] = 0x28;
+        assert!(matches!(
+            Drawing::load_dwg(&mut data.as_slice()),
+            Err(DxfError::InvalidBinaryFile)
+        ));
+    }
+
+    #[test]
+    fn read_r2000_file() {
+        let v = AcadVersion::R2000;
+        let data = r2000_file(&[
+            (0x10, layer(v, 0x10, "0", 7)),
+            (0x11, layer(v, 0x11, "WALLS", 1)),
+            (0x12, style(v, 0x12, "MYSTYLE")),
+            (0x1F, block_header(v, 0x1F, "*Model_Space", 0, &[], 0)),
+            (0x30, block_header(v, 0x30, "B1", 0x31, &[0x32], 0x33)),
+            (0x31, seqend(v, TYPE_BLOCK, 0x31, 0x30)),
+            (0x32, circle(v, 0x32, 0x30, 0x11)),
+            (0x33, seqend(v, TYPE_ENDBLK, 0x33, 0x30)),
+            (0x40, line(v, 0x40, 0x11)),
+            (0x41, insert(v, 0x41, 0x30)),
+            (0x42, lw_polyline(v, TYPE_LWPOLYLINE, 0x42)),
+            (0x43, text(v, 0x43, 0x11, 0x12, "hello")),
+        ]);
+        let drawing = Drawing::load_dwg(&mut data.as_slice()).unwrap();
+        assert_eq!(AcadVersion::R2000, drawing.header.version);
+        assert_eq!("ANSI_1252", drawing.header.drawing_code_page);
+        assert!(drawing.header.next_available_handle.0 > 0x43);
+
+        let layers = drawing.layers().collect::<Vec<_>>();
+        assert_eq!(2, layers.len());
+        assert_eq!("WALLS", layers[1].name);
+        assert_eq!(Some(1), layers[1].color.index());
+        assert_eq!(Handle(0x11), layers[1].handle);
+        assert_eq!(
+            "MYSTYLE",
+            drawing.styles().collect::<Vec<_>>()[0].name.as_str()
+        );
+
+        let blocks = drawing.blocks().collect::<Vec<_>>();
+        assert_eq!(1, blocks.len());
+        assert_eq!("B1", blocks[0].name);
+        assert_eq!("description", blocks[0].description);
+        assert_eq!(Point::new(1.0, 2.0, 0.0), blocks[0].base_point);
+        assert_eq!(1, blocks[0].entities.len());
+        match blocks[0].entities[0].specific {
+            EntityType::Circle(ref circle) => assert_eq!(3.0, circle.radius),
+            _ => panic!("expected a circle"),
+        }
+        assert_eq!("WALLS", blocks[0].entities[0].common.layer);
+
+        let entities = drawing.entities().collect::<Vec<_>>();
+        assert_eq!(4, entities.len());
+        match entities[0].specific {
+            EntityType::Line(ref line) => {
+                assert_eq!(Point::new(1.0, 2.0, 3.0), line.p1);
+                assert_eq!(Point::new(4.0, 5.0, 3.0), line.p2);
+            }
+            _ => panic!("expected a line"),
+        }
+        assert_eq!("WALLS", entities[0].common.layer);
+        assert_eq!(Handle(0x40), entities[0].common.handle);
+        match entities[1].specific {
+            EntityType::Insert(ref insert) => {
+                assert_eq!("B1", insert.name);
+                assert_eq!(Point::new(10.0, 20.0, 0.0), insert.location);
+            }
+            _ => panic!("expected an insert"),
+        }
+        match entities[2].specific {
+            EntityType::LwPolyline(ref poly) => {
+                assert!(poly.is_closed());
+                assert_eq!(2, poly.vertices.len());
+                assert_eq!(1.0, poly.vertices[0].bulge);
+                assert_eq!(3.0, poly.vertices[1].x);
+                assert_eq!(2.0, poly.vertices[1].y);
+            }
+            _ => panic!("expected a polyline"),
+        }
+        match entities[3].specific {
+            EntityType::Text(ref text) => {
+                assert_eq!("hello", text.value);
+                assert_eq!("MYSTYLE", text.text_style_name);
+                assert_eq!(2.5, text.text_height);
+                assert!((text.rotation - 90.0).abs() < 1e-9);
+            }
+            _ => panic!("expected text"),
+        }
+    }
+
+    #[test]
+    fn read_r2004_file() {
+        let v = AcadVersion::R2004;
+        let data = r2004_file(
+            b"AC1018",
+            &[
+                (0x10, layer(v, 0x10, "0", 7)),
+                (0x20, polyline(v, 0x20, &[0x21, 0x22], 0x23)),
+                (0x21, vertex(v, 0x21, 0x20, Point::new(1.0, 2.0, 0.0))),
+                (0x22, vertex(v, 0x22, 0x20, Point::new(3.0, 4.0, 0.0))),
+                (0x23, seqend(v, TYPE_SEQEND, 0x23, 0x20)),
+                (0x24, line(v, 0x24, 0x10)),
+            ],
+        );
+        let drawing = Drawing::load_dwg(&mut data.as_slice()).unwrap();
+        assert_eq!(AcadVersion::R2004, drawing.header.version);
+        assert_eq!(
+            Some(7),
+            drawing.layers().collect::<Vec<_>>()[0].color.index()
+        );
+
+        let entities = drawing.entities().collect::<Vec<_>>();
+        assert_eq!(2, entities.len());
+        match entities[0].specific {
+            EntityType::Polyline(ref poly) => {
+                let vertices = poly.vertices().collect::<Vec<_>>();
+                assert_eq!(2, vertices.len());
+                assert_eq!(Point::new(3.0, 4.0, 0.0), vertices[1].location);
+                assert_eq!(0.5, vertices[1].starting_width);
+                assert_eq!(0.5, vertices[1].ending_width);
+                assert!(poly.is_closed());
+            }
+            _ => panic!("expected a polyline"),
+        }
+        match entities[1].specific {
+            EntityType::Line(ref line) => assert_eq!(Point::new(4.0, 5.0, 3.0), line.p2),
+            _ => panic!("expected a line"),
+        }
+    }
+
+    #[test]
+    fn read_r2010_file_with_string_stream() {
+        let v = AcadVersion::R2010;
+        let data = r2004_file(
+            b"AC1024",
+            &[
+                (0x10, layer(v, 0x10, "0", 7)),
+                (0x11, layer(v, 0x11, "NOTES", 3)),
+                (0x12, style(v, 0x12, "MYSTYLE")),
+                (0x20, text(v, 0x20, 0x11, 0x12, "h\u{e9}llo")),
+                (0x21, line(v, 0x21, 0x10)),
+            ],
+        );
+        let drawing = Drawing::load_dwg(&mut data.as_slice()).unwrap();
+        assert_eq!(AcadVersion::R2010, drawing.header.version);
+        let layers = drawing.layers().collect::<Vec<_>>();
+        assert_eq!("NOTES", layers[1].name);
+        assert_eq!(Some(3), layers[1].color.index());
+
+        let entities = drawing.entities().collect::<Vec<_>>();
+        assert_eq!(2, entities.len());
+        assert_eq!("NOTES", entities[0].common.layer);
+        match entities[0].specific {
+            EntityType::Text(ref text) => {
+                assert_eq!("h\u{e9}llo", text.value);
+                assert_eq!("MYSTYLE", text.text_style_name);
+            }
+            _ => panic!("expected text"),
+        }
+        match entities[1].specific {
+            EntityType::Line(ref line) => assert_eq!(Point::new(1.0, 2.0, 3.0), line.p1),
+            _ => panic!("expected a line"),
+        }
+    }
+
+    #[test]
+    fn read_r2007_file() {
+        let v = AcadVersion::R2007;
+        let data = r2007_file(&[
+            (0x10, layer(v, 0x10, "0", 7)),
+            (0x11, layer(v, 0x11, "NOTES", 3)),
+            (0x12, style(v, 0x12, "MYSTYLE")),
+            (0x20, text(v, 0x20, 0x11, 0x12, "h\u{e9}llo")),
+            (0x21, line(v, 0x21, 0x10)),
+        ]);
+        let drawing = Drawing::load_dwg(&mut data.as_slice()).unwrap();
+        assert_eq!(AcadVersion::R2007, drawing.header.version);
+        assert_eq!(
+            Some(3),
+            drawing.layers().collect::<Vec<_>>()[1].color.index()
+        );
+
+        let entities = drawing.entities().collect::<Vec<_>>();
+        assert_eq!(2, entities.len());
+        match entities[0].specific {
+            EntityType::Text(ref text) => {
+                assert_eq!("h\u{e9}llo", text.value);
+                assert_eq!("MYSTYLE", text.text_style_name);
+            }
+            _ => panic!("expected text"),
+        }
+        match entities[1].specific {
+            EntityType::Line(ref line) => assert_eq!(Point::new(4.0, 5.0, 3.0), line.p2),
+            _ => panic!("expected a line"),
+        }
+    }
+
+    #[test]
+    fn corrupted_r2007_page_map() {
+        let v = AcadVersion::R2007;
+        let mut data = r2007_file(&[(0x10, layer(v, 0x10, "0", 7))]);
+        // the page map is the last page and starts with the size of the first page
+        let page_map = data.len() - r2007_system_page(&[0u8; 48]).len();
+        data[page_map + 6] = 0x01;
+        assert!(matches!(
+            Drawing::load_dwg(&mut data.as_slice()),
+            Err(DxfError::InvalidBinaryFile)
+        ));
+    }
+
+    fn assert_dwg_header(version: AcadVersion, data: &[u8]) {
+        let (drawing, report) = Drawing::load_dwg_with_report(&mut &data[..]).unwrap();
+        assert!(report.is_empty());
+        let header = &drawing.header;
+        assert_eq!(version, header.version);
+        assert!(header.draw_orthogonal_lines);
+        assert!(!header.fill_mode_on);
+        assert_eq!(UnitFormat::Decimal, header.unit_format);
+        assert_eq!(4, header.unit_precision);
+        assert_eq!(2.5, header.line_type_scale);
+        assert_eq!(2000, header.creation_date.year());
+        assert_eq!(Some(3), header.current_entity_color.index());
+        assert_eq!(Handle(0x200), header.next_available_handle);
+        assert_eq!("WALLS", header.current_layer);
+        assert_eq!("MYSTYLE", header.text_style);
+        assert_eq!("MYDIM", header.dimension_style_name);
+        assert_eq!(Point::new(10.0, 20.0, 0.0), header.maximum_drawing_extents);
+        assert_eq!(Point::new(12.0, 9.0, 0.0), header.maximum_drawing_limits);
+        assert_eq!("mm", header.dimensioning_suffix);
+        assert_eq!(2.0, header.dimensioning_scale_factor);
+        assert_eq!('.', header.dimension_decimal_separator_char);
+        assert_eq!(
+            DimensionFit::MoveEitherForBestFit,
+            header.dimension_text_and_arrow_placement
+        );
+        assert_eq!("MYSTYLE", header.dimension_text_style);
+        assert_eq!(18, header.new_object_line_weight.raw_value());
+        assert!(header.display_linewieght_in_model_and_layout_tab);
+        assert_eq!(Units::Millimeters, header.default_drawing_units);
+        assert_eq!(
+            "12345678-1234-1234-1234-123456789abc",
+            header.fingerprint_guid.to_string()
+        );
+    }
+
+    #[test]
+    fn read_r2000_header() {
+        let v = AcadVersion::R2000;
+        let data = r2000_file_with_sections(
+            &header_section(v),
+            &[],
+            &[
+                (0x10, layer(v, 0x10, "0", 7)),
+                (0x11, layer(v, 0x11, "WALLS", 1)),
+                (0x12, style(v, 0x12, "MYSTYLE")),
+                (0x13, dim_style(v, 0x13, "MYDIM", 0x12)),
+            ],
+        );
+        assert_dwg_header(v, &data);
+        let drawing = Drawing::load_dwg(&mut data.as_slice()).unwrap();
+        assert_eq!("acad", drawing.header.file_name);
+    }
+
+    #[test]
+    fn read_r2010_header() {
+        let v = AcadVersion::R2010;
+        let data = r2004_file_with_header(
+            b"AC1024",
+            &header_section(v),
+            &[
+                (0x10, layer(v, 0x10, "0", 7)),
+                (0x11, layer(v, 0x11, "WALLS", 1)),
+                (0x12, style(v, 0x12, "MYSTYLE")),
+                (0x13, dim_style(v, 0x13, "MYDIM", 0x12)),
+            ],
+        );
+        assert_dwg_header(v, &data);
+    }
+
+    #[test]
+    fn read_r2007_header() {
+        let v = AcadVersion::R2007;
+        let data = r2007_file_with_header(
+            &header_section(v),
+            &[
+                (0x10, layer(v, 0x10, "0", 7)),
+                (0x11, layer(v, 0x11, "WALLS", 1)),
+                (0x12, style(v, 0x12, "MYSTYLE")),
+                (0x13, dim_style(v, 0x13, "MYDIM", 0x12)),
+            ],
+        );
+        assert_dwg_header(v, &data);
+    }
+
+    #[test]
+    fn read_dimensions_and_dimension_styles() {
+        for v in [AcadVersion::R2000, AcadVersion::R2010] {
+            let objects = [
+                (0x10, layer(v, 0x10, "0", 7)),
+                (0x12, style(v, 0x12, "MYSTYLE")),
+                (0x13, dim_style(v, 0x13, "MYDIM", 0x12)),
+                (0x30, block_header(v, 0x30, "*D1", 0, &[], 0)),
+                (0x40, linear_dimension(v, 0x40, 0x13, 0x30)),
+                (0x41, tolerance(v, 0x41, 0x13)),
+            ];
+            let data = if v == AcadVersion::R2000 {
+                r2000_file(&objects)
+            } else {
+                r2004_file(b"AC1024", &objects)
+            };
+            let (drawing, report) = Drawing::load_dwg_with_report(&mut data.as_slice()).unwrap();
+            assert!(report.is_empty());
+
+            let dim_styles = drawing.dim_styles().collect::<Vec<_>>();
+            assert_eq!(1, dim_styles.len());
+            let dim_style = dim_styles[0];
+            assert_eq!("MYDIM", dim_style.name);
+            assert_eq!("mm", dim_style.dimensioning_suffix);
+            assert_eq!(2.0, dim_style.dimensioning_scale_factor);
+            assert_eq!(0.25, dim_style.dimensioning_text_height);
+            assert_eq!('.', dim_style.dimension_decilam_separator_char);
+            assert_eq!(
+                DimensionFit::MoveEitherForBestFit,
+                dim_style.dimension_text_and_arrow_placement
+            );
+            assert_eq!("MYSTYLE", dim_style.dimension_text_style);
+
+            let entities = drawing.entities().collect::<Vec<_>>();
+            assert_eq!(2, entities.len());
+            match entities[0].specific {
+                EntityType::RotatedDimension(ref dim) => {
+                    let base = &dim.dimension_base;
+                    assert_eq!(
+                        DimensionType::RotatedHorizontalOrVertical,
+                        base.dimension_type
+                    );
+                    assert_eq!("MYDIM", base.dimension_style_name);
+                    assert_eq!("*D1", base.block_name);
+                    assert_eq!("<>", base.text);
+                    assert_eq!(Point::new(10.0, 5.0, 0.0), base.definition_point_1);
+                    assert_eq!(Point::new(5.0, 6.0, 0.0), base.text_mid_point);
+                    assert_eq!(10.0, base.actual_measurement);
+                    assert!(!base.is_at_user_defined_location);
+                    assert_eq!(Point::new(10.0, 0.0, 0.0), dim.definition_point_3);
+                    assert_eq!(90.0, dim.rotation_angle);
+                }
+                _ => panic!("expected a rotated dimension"),
+            }
+            match entities[1].specific {
+                EntityType::Tolerance(ref tolerance) => {
+                    assert_eq!("MYDIM", tolerance.dimension_style_name);
+                    assert_eq!(Point::new(1.0, 2.0, 0.0), tolerance.insertion_point);
+                    assert_eq!("{\\Fgdt;j}%%v0.1", tolerance.display_text);
+                }
+                _ => panic!("expected a tolerance"),
+            }
+        }
+    }
+
+    #[test]
+    fn read_xrecords_and_groups() {
+        for v in [AcadVersion::R2000, AcadVersion::R2010] {
+            let data = if v == AcadVersion::R2000 {
+                r2000_file_with_sections(
+                    &[],
+                    &[(500, "XRECORD")],
+                    &[
+                        (0x10, layer(v, 0x10, "0", 7)),
+                        (0x40, line(v, 0x40, 0x10)),
+                        (0x50, xrecord(v, 500, 0x50, 0x0C)),
+                        (0x51, group(v, 0x51, 0x0D, &[0x40])),
+                    ],
+                )
+            } else {
+                // the classes section isn't written, so the fixed XRECORD type is used
+                r2004_file(
+                    b"AC1024",
+                    &[
+                        (0x10, layer(v, 0x10, "0", 7)),
+                        (0x40, line(v, 0x40, 0x10)),
+                        (0x50, xrecord(v, TYPE_XRECORD, 0x50, 0x0C)),
+                        (0x51, group(v, 0x51, 0x0D, &[0x40])),
+                    ],
+                )
+            };
+            let (drawing, report) = Drawing::load_dwg_with_report(&mut data.as_slice()).unwrap();
+            assert!(report.is_empty());
+            let objects = drawing.objects().collect::<Vec<_>>();
+            assert_eq!(2, objects.len());
+            match objects[0].specific {
+                ObjectType::XRecordObject(ref xr) => {
+                    assert_eq!(Handle(0x0C), objects[0].common.__owner_handle);
+                    assert_eq!(
+                        DictionaryDuplicateRecordHandling::KeepExisting,
+                        xr.duplicate_record_handling
+                    );
+                    assert_eq!(
+                        vec![
+                            XRecordValue::Str(String::from("hi")),
+                            XRecordValue::Point(Point::new(1.0, 2.0, 3.0)),
+                            XRecordValue::Other(CodePair::new_i16(70, 7)),
+                            XRecordValue::Other(CodePair::new_bool(290, true)),
+                            XRecordValue::Handle(Handle(0x40)),
+                        ],
+                        xr.values()
+                    );
+                }
+                _ => panic!("expected an xrecord"),
+            }
+            match objects[1].specific {
+                ObjectType::Group(ref group) => {
+                    assert_eq!("doors", group.description);
+                    assert!(group.is_named);
+                    assert!(group.is_selectable);
+                    let entities = group.entities(&drawing);
+                    assert_eq!(1, entities.len());
+                    assert_eq!(Handle(0x40), entities[0].common.handle);
+                }
+                _ => panic!("expected a group"),
+            }
+        }
+    }
+
+    #[test]
+    fn report_invalid_dwg_header() {
+        let v = AcadVersion::R2000;
+        let mut header = header_section(v);
+        header.truncate(40);
+        let data = r2000_file_with_sections(&header, &[], &[(0x10, layer(v, 0x10, "0", 7))]);
+        let (drawing, report) = Drawing::load_dwg_with_report(&mut data.as_slice()).unwrap();
+        assert_eq!(1, drawing.layers().count());
+        match report.issues[..] {
+            [DwgReadIssue::InvalidSection { ref name, .. }] => assert_eq!("AcDb:Header", name),
+            _ => panic!("expected an invalid header section"),
+        }
+    }
+
+    #[test]
+    fn report_skipped_dwg_objects() {
+        let v = AcadVersion::R2000;
+        let mut truncated_line = TestObject::entity(v, TYPE_LINE, 0x41, 0, 0x10);
+        truncated_line.main.b(false);
+        let data = r2000_file_with_sections(
+            &[],
+            &[(500, "LWPOLYLINE"), (501, "HATCH")],
+            &[
+                (0x02, TestObject::new(v, 0x32, 0x02).finish()), // layer table control
+                (0x10, layer(v, 0x10, "0", 7)),
+                (0x40, line(v, 0x40, 0x10)),
+                (0x41, truncated_line.finish()),
+                (0x42, TestObject::entity(v, 501, 0x42, 0, 0x10).finish()),
+                (0x43, lw_polyline(v, 500, 0x43)),
+            ],
+        );
+        let (drawing, report) = Drawing::load_dwg_with_report(&mut data.as_slice()).unwrap();
+        let entities = drawing.entities().collect::<Vec<_>>();
+        assert_eq!(2, entities.len());
+        match entities[1].specific {
+            EntityType::LwPolyline(ref poly) => assert_eq!(2, poly.vertices.len()),
+            _ => panic!("expected a polyline"),
+        }
+
+        assert_eq!(2, report.issues.len());
+        match report.issues[0] {
+            DwgReadIssue::InvalidObject {
+                handle,
+                ref type_name,
+                ..
+            } => {
+                assert_eq!(Handle(0x41), handle);
+                assert_eq!("LINE", type_name);
+            }
+            _ => panic!("expected an invalid object"),
+        }
+        assert_eq!(
+            DwgReadIssue::UnsupportedObject {
+                handle: Handle(0x42),
+                type_name: String::from("HATCH"),
+            },
+            report.issues[1]
+        );
+        assert_eq!(1, report.invalid_objects().count());
+    }
+
+    #[test]
+    fn read_leaders_multilines_and_solids() {
+        for v in [AcadVersion::R2000, AcadVersion::R2010] {
+            let sat_text = "700 0 1 0\n@33 Open Design Alliance ACIS Builder\n";
+            let objects = [
+                (0x10, layer(v, 0x10, "0", 7)),
+                (0x12, style(v, 0x12, "MYSTYLE")),
+                (0x13, dim_style(v, 0x13, "MYDIM", 0x12)),
+                (0x14, mline_style(v, 0x14, "WALL")),
+                (0x40, leader(v, 0x40, 0x13)),
+                (0x41, mline(v, 0x41, 0x14)),
+                (0x42, solid_3d(v, 0x42, sat_text, 1)),
+                (0x43, solid_3d(v, 0x43, "", 2)),
+            ];
+            let data = if v == AcadVersion::R2000 {
+                r2000_file(&objects)
+            } else {
+                r2004_file(b"AC1024", &objects)
+            };
+            let (drawing, report) = Drawing::load_dwg_with_report(&mut data.as_slice()).unwrap();
+            assert_eq!(
+                vec![DwgReadIssue::UnsupportedObject {
+                    handle: Handle(0x43),
+                    type_name: String::from("3DSOLID"),
+                }],
+                report.issues
+            );
+
+            let styles = drawing
+                .objects()
+                .filter_map(|o| match o.specific {
+                    ObjectType::MLineStyle(ref style) => Some(style),
+                    _ => None,
+                })
+                .collect::<Vec<_>>();
+            assert_eq!(1, styles.len());
+            assert_eq!("WALL", styles[0].style_name);
+            assert_eq!(90.0, styles[0].start_angle);
+            assert_eq!(-0.5, styles[0].elements[1].offset);
+            assert_eq!(Some(1), styles[0].elements[1].color.index());
+            assert_eq!("BYBLOCK", styles[0].elements[1].line_type);
+
+            let entities = drawing.entities().collect::<Vec<_>>();
+            assert_eq!(3, entities.len());
+            match entities[0].specific {
+                EntityType::Leader(ref leader) => {
+                    assert_eq!("MYDIM", leader.dimension_style_name);
+                    assert_eq!(LeaderPathType::Spline, leader.path_type);
+                    assert_eq!(
+                        LeaderCreationAnnotationType::NoAnnotation,
+                        leader.annotation_type
+                    );
+                    assert_eq!(
+                        LeaderHooklineDirection::SameAsHorizontalVector,
+                        leader.hookline_direction
+                    );
+                    assert!(!leader.use_arrowheads);
+                    assert_eq!(2.0, leader.text_annotation_width);
+                    assert_eq!(Point::new(3.0, 4.0, 0.0), leader.vertices[1]);
+                }
+                _ => panic!("expected a leader"),
+            }
+            match entities[1].specific {
+                EntityType::MLine(ref mline) => {
+                    assert_eq!("WALL", mline.style_name);
+                    assert_eq!(Handle(0x14), mline.__style_handle);
+                    assert_eq!(Justification::Middle, mline.justification);
+                    assert_eq!(3, mline.flags);
+                    assert_eq!(2.0, mline.scale_factor);
+                    assert_eq!(Point::new(5.0, 2.0, 0.0), mline.vertices[1]);
+                    assert_eq!(Vector::new(0.0, 1.0, 0.0), mline.miter_directions[1]);
+                    assert_eq!(8, mline.parameters.len());
+                    assert_eq!(4.0, mline.parameters[7]);
+                }
+                _ => panic!("expected a multiline"),
+            }
+            match entities[2].specific {
+                EntityType::Solid3D(ref solid) => assert_eq!(sat_text, solid.sat_text()),
+                _ => panic!("expected a 3D solid"),
+            }
+        }
+    }
+
+    #[test]
+    fn read_class_and_proxy_entities() {
+        let v = AcadVersion::R2000;
+        let mut proxy =
+            TestObject::entity_with_preview(v, TYPE_PROXY_ENTITY, 0x44, 0, 0x10, &[1, 2, 3, 4]);
+        proxy.main.bl(501);
+        proxy.main.bl(0x0003_0019);
+        proxy.main.b(false);
+        let data = r2000_file_with_sections(
+            &[],
+            &[(500, "IMAGE"), (501, "PDFUNDERLAY"), (502, "LIGHT")],
+            &[
+                (0x10, layer(v, 0x10, "0", 7)),
+                (0x41, image(v, 500, 0x41)),
+                (0x42, pdf_underlay(v, 501, 0x42)),
+                (0x43, light(v, 502, 0x43)),
+                (0x44, proxy.finish()),
+            ],
+        );
+        let (drawing, report) = Drawing::load_dwg_with_report(&mut data.as_slice()).unwrap();
+        assert!(report.is_empty());
+
+        let entities = drawing.entities().collect::<Vec<_>>();
+        assert_eq!(4, entities.len());
+        match entities[0].specific {
+            EntityType::Image(ref image) => {
+                assert_eq!(Point::new(1.0, 2.0, 0.0), image.location);
+                assert_eq!(Vector::new(640.0, 480.0, 0.0), image.image_size);
+                assert_eq!(7, image.display_options_flags);
+                assert!(image.use_clipping);
+                assert_eq!(60, image.contrast);
+                assert_eq!("50", image.image_def_reference);
+                assert_eq!("51", image.image_def_reactor_reference);
+                assert_eq!(ImageClippingBoundaryType::Rectangular, image.clipping_type);
+                assert_eq!(2, image.clipping_vertex_count);
+                assert_eq!(Point::new(639.5, 479.5, 0.0), image.clipping_vertices[1]);
+            }
+            _ => panic!("expected an image"),
+        }
+        match entities[1].specific {
+            EntityType::PdfUnderlay(ref underlay) => {
+                assert_eq!(Handle(0x52), underlay.__object_handle);
+                assert_eq!(Point::new(1.0, 1.0, 0.0), underlay.insertion_point);
+                assert_eq!(3.0, underlay.y_scale);
+                assert_eq!(90.0, underlay.rotation_angle);
+                assert_eq!(25, underlay.fade);
+                assert_eq!(Point::new(8.0, 6.0, 0.0), underlay.points[1]);
+            }
+            _ => panic!("expected a PDF underlay"),
+        }
+        match entities[2].specific {
+            EntityType::Light(ref light) => {
+                assert_eq!("spot", light.name);
+                assert_eq!(LightType::Spot, light.light_type);
+                assert!(light.is_active);
+                assert_eq!(Point::new(1.0, 2.0, 3.0), light.position);
+                assert_eq!(LightAttenuationType::InverseSquare, light.attentuation_type);
+                assert_eq!(45.0, light.hotspot_angle);
+                assert_eq!(ShadowType::ShadowMaps, light.shadow_type);
+                assert_eq!(3, light.shadow_map_softness);
+            }
+            _ => panic!("expected a light"),
+        }
+        match entities[3].specific {
+            EntityType::ProxyEntity(ref proxy) => {
+                assert_eq!(501, proxy.application_entity_class_id);
+                assert_eq!(0x0003_0019, proxy.__object_drawing_format);
+                assert_eq!(4, proxy.graphics_data_size);
+                assert_eq!(vec![vec![1, 2, 3, 4]], proxy.graphics_data_string);
+            }
+            _ => panic!("expected a proxy entity"),
+        }
+    }
+
+    #[test]
+    fn read_dwg_and_write_dxf() {
+        let v = AcadVersion::R2000;
+        let data = r2000_file(&[(0x10, layer(v, 0x10, "0", 7)), (0x40, line(v, 0x40, 0x10))]);
+        let drawing = Drawing::load_dwg(&mut data.as_slice()).unwrap();
+        assert_contains(&drawing, ["  0", "LINE", "  5", "40"].join("\r\n"));
+    }
+}
@@ -103,7 +103,7 @@ impl<T: Read> DxbReader<T> {
                 }
                 // global values
                 DxbItemType::NewColor => {
-                    self.current_color = Color::from_raw_value(self.read_w()? as i16);
+                    self.current_color = Color::from_raw_value(self.read_w()?);
                 }
                 DxbItemType::NewLayer => {
                     self.layer_name = self.read_null_terminated_string()?;
@@ -147,6 +147,14 @@ impl<T: Read> DxbReader<T> {
                             v.starting_width = starting_width;
                             v.ending_width = ending_width;
                         }
+                        // widths before the first vertex apply to the whole polyline
+                        Entity {
+                            specific: EntityType::Polyline(ref mut p),
+                            ..
+                        } => {
+                            p.default_starting_width = starting_width;
+                            p.default_ending_width = ending_width;
+                        }
                         _ => return Err(DxfError::UnexpectedEnumValue(self.offset)),
                     }
                 }
@@ -286,42 +294,45 @@ impl<T: Read> DxbReader<T> {
             }
         }
     }
+    /// Reads an angle in degrees.
     fn read_a(&mut self) -> DxfResult<f64> {
-        let value = if self.is_integer_mode {
-            f64::from(read_i32(&mut self.reader)?) * self.scale_factor / 1_000_000.0
+        if self.is_integer_mode {
+            // integer angles are in millionths of a degree
+            let value = f64::from(read_i32(&mut self.reader)?) / 1_000_000.0;
+            self.advance_offset(4);
+            Ok(value)
         } else {
-            f64::from(read_f32(&mut self.reader)?)
-        };
-        self.advance_offset(4);
-        Ok(value)
+            self.read_f()
+        }
     }
     fn read_f(&mut self) -> DxfResult<f64> {
-        let value = read_f64(&mut self.reader);
+        let value = read_f64(&mut self.reader)?;
         self.advance_offset(8);
-        Ok(value.unwrap_or(0.0))
+        Ok(value)
     }
+    /// Reads a coordinate or distance.
     fn read_n(&mut self) -> DxfResult<f64> {
         if self.is_integer_mode {
             let value = f64::from(read_i16(&mut self.reader)?) * self.scale_factor;
             self.advance_offset(2);
             Ok(value)
         } else {
-            let value = f64::from(read_f32(&mut self.reader)?);
-            self.advance_offset(4);
-            Ok(value)
+            self.read_f()
         }
     }
+    /// Reads a bulge.
     fn read_u(&mut self) -> DxfResult<f64> {
-        let value = if self.is_integer_mode {
-            f64::from(read_i32(&mut self.reader)?) * 65536.0 * self.scale_factor
+        if self.is_integer_mode {
+            // integer bulges are in units of 1/65536
+            let value = f64::from(read_i32(&mut self.reader)?) / 65536.0;
+            self.advance_offset(4);
+            Ok(value)
         } else {
-            f64::from(read_f32(&mut self.reader)?)
-        };
-        self.advance_offset(4);
-        Ok(value)
+            self.read_f()
+        }
     }
-    fn read_w(&mut self) -> DxfResult<i32> {
-        let value = (f64::from(read_i16(&mut self.reader)?) * self.scale_factor) as i32;
+    fn read_w(&mut self) -> DxfResult<i16> {
+        let value = read_i16(&mut self.reader)?;
         self.advance_offset(2);
         Ok(value)
     }
@@ -2,20 +2,54 @@ use std::io::Write;
 
 use byteorder::{LittleEndian, WriteBytesExt};
 
-use crate::{Drawing, DxfResult};
+use crate::{Block, Color, Drawing, DxfResult, Point, Vector};
 
 use crate::dxb_item_type::DxbItemType;
 use crate::entities::*;
+use crate::geometry::*;
+use crate::render::{
+    curve_paths, is_planar, surface_faces, Placement, MAX_BLOCK_DEPTH, SPLINE_SEGMENTS_PER_SPAN,
+};
 
-use itertools::Itertools;
+/// Options used when writing a `Drawing` as DXB.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DxbOptions {
+    /// Whether numbers are written as 16-bit integers instead of 64-bit floats.  Integer files are
+    /// smaller but coordinates are rounded to multiples of `scale_factor`.
+    pub use_integers: bool,
+    /// The size of one integer unit, in drawing units, when `use_integers` is set.
+    pub scale_factor: f64,
+}
+
+impl Default for DxbOptions {
+    fn default() -> Self {
+        DxbOptions {
+            use_integers: false,
+            scale_factor: 1.0,
+        }
+    }
+}
 
-pub(crate) struct DxbWriter<T: Write> {
+pub(crate) struct DxbWriter<'a, T: Write> {
     writer: T,
+    options: &'a DxbOptions,
+    layer_name: String,
+    color: i16,
+    last_line_point: Option<Point>,
+    last_trace_corners: Option<(Point, Point)>,
 }
 
-impl<T: Write> DxbWriter<T> {
-    pub fn new(writer: T) -> Self {
-        DxbWriter { writer }
+impl<'a, T: Write> DxbWriter<'a, T> {
+    pub fn new(writer: T, options: &'a DxbOptions) -> Self {
+        DxbWriter {
+            writer,
+            options,
+            // the initial values assumed by readers
+            layer_name: String::from("0"),
+            color: Color::by_layer().raw_value(),
+            last_line_point: None,
+            last_trace_corners: None,
+        }
     }
     pub fn write(&mut self, drawing: &Drawing) -> DxfResult<()> {
         // write sentinel
@@ -23,77 +57,165 @@ impl<T: Write> DxbWriter<T> {
         self.writer.write_u8(0x1A)?;
         self.writer.write_u8(0x00)?;
 
-        let writing_block = drawing.entities().any(|_| true) && drawing.blocks().any(|_| true);
-        if writing_block {
-            // write block header
-            self.write_item_type(DxbItemType::BlockBase)?;
-            let block = drawing.blocks().next().unwrap();
-            self.write_n(block.base_point.x)?;
-            self.write_n(block.base_point.y)?;
-        }
-
-        // force all numbers to be floats
         self.write_item_type(DxbItemType::NumberMode)?;
-        self.write_w(1)?;
-
-        // write color
-        let mut last_color = 0i16;
-        self.write_item_type(DxbItemType::NewColor)?;
-        self.write_w(last_color)?;
+        self.writer
+            .write_i16::<LittleEndian>(if self.options.use_integers { 0 } else { 1 })?;
+        if self.options.use_integers {
+            self.write_item_type(DxbItemType::ScaleFactor)?;
+            self.writer
+                .write_f64::<LittleEndian>(self.options.scale_factor)?;
+        }
 
-        if writing_block {
-            self.write_entities(&drawing.blocks().next().unwrap().entities)?;
-        } else {
-            let groups = drawing.entities().chunk_by(|&e| e.common.layer.clone());
-            for (layer, entities) in &groups {
-                self.write_item_type(DxbItemType::NewLayer)?;
-                self.write_null_terminated_string(&layer)?;
-                for entity in entities {
-                    match entity.common.color.raw_value() {
-                        c if c == last_color => (), // same color, do nothing
-                        c => {
-                            last_color = c;
-                            self.write_item_type(DxbItemType::NewColor)?;
-                            self.write_w(last_color)?;
-                        }
-                    }
-                    self.write_entity(entity)?;
+        // a file without entities describes a block
+        let blocks = drawing
+            .blocks()
+            .filter(|b| !is_layout_block(b))
+            .collect::<Vec<_>>();
+        if drawing.entities().next().is_none() && !blocks.is_empty() {
+            let base_point = blocks[0].base_point.clone();
+            self.write_item_type(DxbItemType::BlockBase)?;
+            self.write_n(base_point.x)?;
+            self.write_n(base_point.y)?;
+            for block in blocks {
+                // every block shares the base point of the first
+                let offset = Point::new(
+                    base_point.x - block.base_point.x,
+                    base_point.y - block.base_point.y,
+                    base_point.z - block.base_point.z,
+                );
+                let placement = Placement::from_columns(
+                    &Vector::x_axis(),
+                    &Vector::y_axis(),
+                    &Vector::z_axis(),
+                    &offset,
+                );
+                for entity in &block.entities {
+                    self.write_entity(drawing, entity, Some(&placement), None, 0)?;
                 }
             }
+        } else {
+            for entity in drawing.entities() {
+                self.write_entity(drawing, entity, None, None, 0)?;
+            }
         }
 
         // write null terminator
         self.writer.write_u8(0)?;
         Ok(())
     }
-    fn write_entities(&mut self, entities: &[Entity]) -> DxfResult<()> {
-        for entity in entities.iter() {
-            self.write_entity(entity)?;
+    /// Writes an entity, placed by `placement` if it's in a block.  Block references are expanded
+    /// since DXB can't describe them.
+    fn write_entity(
+        &mut self,
+        drawing: &Drawing,
+        entity: &Entity,
+        placement: Option<&Placement>,
+        inherited: Option<(&str, &Color)>,
+        depth: usize,
+    ) -> DxfResult<()> {
+        // entities in blocks take their layer and color from the block reference
+        let (layer, color) = match inherited {
+            Some((layer, color)) => (
+                if entity.common.layer == "0" {
+                    layer
+                } else {
+                    entity.common.layer.as_str()
+                },
+                if entity.common.color.is_by_block() {
+                    color
+                } else {
+                    &entity.common.color
+                },
+            ),
+            None => (entity.common.layer.as_str(), &entity.common.color),
+        };
+
+        if let EntityType::Insert(ref insert) = entity.specific {
+            let block = match drawing
+                .blocks()
+                .find(|b| b.name.eq_ignore_ascii_case(&insert.name))
+            {
+                Some(block) if depth < MAX_BLOCK_DEPTH => block,
+                _ => return Ok(()),
+            };
+            for row in 0..insert.row_count.max(1) {
+                for column in 0..insert.column_count.max(1) {
+                    let mut cell = Placement::for_insert(insert, &block.base_point, row, column);
+                    if let Some(outer) = placement {
+                        cell = cell.then(outer);
+                    }
+                    for block_entity in &block.entities {
+                        self.write_entity(
+                            drawing,
+                            block_entity,
+                            Some(&cell),
+                            Some((layer, color)),
+                            depth + 1,
+                        )?;
+                    }
+                }
+            }
+            return Ok(());
         }
-        Ok(())
-    }
-    fn write_entity(&mut self, entity: &Entity) -> DxfResult<()> {
+
+        self.write_layer_and_color(layer, color)?;
+        let placed;
+        let entity = match placement {
+            None => entity,
+            Some(placement) => match place(entity, placement) {
+                Some(entity) => {
+                    placed = entity;
+                    &placed
+                }
+                None => {
+                    // curves that can't be placed exactly are approximated with lines
+                    for (points, is_closed) in curve_paths(entity, SPLINE_SEGMENTS_PER_SPAN) {
+                        let points = points.iter().map(|p| placement.apply(p)).collect();
+                        self.write_path(points, is_closed)?;
+                    }
+                    return Ok(());
+                }
+            },
+        };
         match &entity.specific {
-            EntityType::Arc(ref arc) => {
+            EntityType::Arc(ref arc) if is_upright(&arc.normal) => {
                 self.write_arc(arc)?;
             }
-            EntityType::Circle(ref circle) => {
+            EntityType::Circle(ref circle) if is_upright(&circle.normal) => {
                 self.write_circle(circle)?;
             }
             EntityType::Face3D(ref face) => {
-                self.write_face(face)?;
+                self.write_face([
+                    &face.first_corner,
+                    &face.second_corner,
+                    &face.third_corner,
+                    &face.fourth_corner,
+                ])?;
             }
             EntityType::Line(ref line) => {
-                self.write_line(line)?;
+                self.write_line(&line.p1, &line.p2)?;
+            }
+            EntityType::LwPolyline(ref poly) if is_upright(&poly.extrusion_direction) => {
+                self.write_lw_polyline(poly)?;
             }
             EntityType::ModelPoint(ref point) => {
                 self.write_point(point)?;
             }
-            EntityType::Polyline(ref poly) => {
-                self.write_polyline(poly)?;
+            EntityType::Polyline(ref poly)
+                if poly.is_3d_polygon_mesh() || poly.is_polyface_mesh() =>
+            {
+                for face in surface_faces(entity) {
+                    match face.len() {
+                        3 => self.write_face([&face[0], &face[1], &face[2], &face[2]])?,
+                        4 => self.write_face([&face[0], &face[1], &face[2], &face[3]])?,
+                        _ => self.write_path(face, true)?,
+                    }
+                }
             }
-            EntityType::Seqend(_) => {
-                self.write_seqend()?;
+            EntityType::Polyline(ref poly)
+                if !poly.is_3d_polyline() && is_upright(&poly.normal) =>
+            {
+                self.write_polyline(poly)?;
             }
             EntityType::Solid(ref solid) => {
                 self.write_solid(solid)?;
@@ -101,10 +223,24 @@ impl<T: Write> DxbWriter<T> {
             EntityType::Trace(ref trace) => {
                 self.write_trace(trace)?;
             }
-            EntityType::Vertex(ref vertex) => {
-                self.write_vertex(vertex)?;
+            _ => {
+                for (points, is_closed) in curve_paths(entity, SPLINE_SEGMENTS_PER_SPAN) {
+                    self.write_path(points, is_closed)?;
+                }
             }
-            _ => (),
+        }
+        Ok(())
+    }
+    fn write_layer_and_color(&mut self, layer: &str, color: &Color) -> DxfResult<()> {
+        if layer != self.layer_name {
+            self.layer_name = String::from(layer);
+            self.write_item_type(DxbItemType::NewLayer)?;
+            self.write_null_terminated_string(layer)?;
+        }
+        if color.raw_value() != self.color {
+            self.color = color.raw_value();
+            self.write_item_type(DxbItemType::NewColor)?;
+            self.write_w(self.color)?;
         }
         Ok(())
     }
@@ -113,8 +249,8 @@ impl<T: Write> DxbWriter<T> {
         self.write_n(arc.center.x)?;
         self.write_n(arc.center.y)?;
         self.write_n(arc.radius)?;
-        self.write_n(arc.start_angle)?;
-        self.write_n(arc.end_angle)?;
+        self.write_a(arc.start_angle)?;
+        self.write_a(arc.end_angle)?;
         Ok(())
     }
     fn write_circle(&mut self, circle: &Circle) -> DxfResult<()> {
@@ -124,30 +260,82 @@ impl<T: Write> DxbWriter<T> {
         self.write_n(circle.radius)?;
         Ok(())
     }
-    fn write_face(&mut self, face: &Face3D) -> DxfResult<()> {
+    fn write_face(&mut self, corners: [&Point; 4]) -> DxfResult<()> {
         self.write_item_type(DxbItemType::Face)?;
-        self.write_n(face.first_corner.x)?;
-        self.write_n(face.first_corner.y)?;
-        self.write_n(face.first_corner.z)?;
-        self.write_n(face.second_corner.x)?;
-        self.write_n(face.second_corner.y)?;
-        self.write_n(face.second_corner.z)?;
-        self.write_n(face.third_corner.x)?;
-        self.write_n(face.third_corner.y)?;
-        self.write_n(face.third_corner.z)?;
-        self.write_n(face.fourth_corner.x)?;
-        self.write_n(face.fourth_corner.y)?;
-        self.write_n(face.fourth_corner.z)?;
-        Ok(())
-    }
-    fn write_line(&mut self, line: &Line) -> DxfResult<()> {
-        self.write_item_type(DxbItemType::Line)?;
-        self.write_n(line.p1.x)?;
-        self.write_n(line.p1.y)?;
-        self.write_n(line.p1.z)?;
-        self.write_n(line.p2.x)?;
-        self.write_n(line.p2.y)?;
-        self.write_n(line.p2.z)?;
+        for corner in corners {
+            self.write_n(corner.x)?;
+            self.write_n(corner.y)?;
+            self.write_n(corner.z)?;
+        }
+        Ok(())
+    }
+    /// Writes a line, as an extension of the previous line if it starts where that one ended.
+    fn write_line(&mut self, from: &Point, to: &Point) -> DxfResult<()> {
+        match self.last_line_point {
+            Some(ref last) if last == from && to.z == 0.0 => {
+                self.write_item_type(DxbItemType::LineExtension)?;
+                self.write_n(to.x)?;
+                self.write_n(to.y)?;
+            }
+            Some(ref last) if last == from => {
+                self.write_item_type(DxbItemType::LineExtension3D)?;
+                self.write_n(to.x)?;
+                self.write_n(to.y)?;
+                self.write_n(to.z)?;
+            }
+            _ => {
+                self.write_item_type(if from.z == 0.0 && to.z == 0.0 {
+                    DxbItemType::Line
+                } else {
+                    DxbItemType::Line3D
+                })?;
+                self.write_n(from.x)?;
+                self.write_n(from.y)?;
+                self.write_n(from.z)?;
+                self.write_n(to.x)?;
+                self.write_n(to.y)?;
+                self.write_n(to.z)?;
+            }
+        }
+        // the reader only tracks the end of lines as they're read
+        self.last_line_point = Some(if self.options.use_integers {
+            Point::new(self.round(to.x), self.round(to.y), self.round(to.z))
+        } else {
+            to.clone()
+        });
+        Ok(())
+    }
+    fn write_path(&mut self, mut points: Vec<Point>, is_closed: bool) -> DxfResult<()> {
+        if is_closed && !points.is_empty() {
+            points.push(points[0].clone());
+        }
+        for pair in points.windows(2) {
+            self.write_line(&pair[0], &pair[1])?;
+        }
+        Ok(())
+    }
+    fn write_lw_polyline(&mut self, poly: &LwPolyline) -> DxfResult<()> {
+        self.write_item_type(DxbItemType::Polyline)?;
+        self.write_w(i16::from(poly.is_closed()))?;
+        // the constant width only applies when no vertex has a width of its own
+        let has_vertex_widths = poly
+            .vertices
+            .iter()
+            .any(|v| v.starting_width != 0.0 || v.ending_width != 0.0);
+        for vertex in &poly.vertices {
+            let (starting_width, ending_width) = if has_vertex_widths {
+                (vertex.starting_width, vertex.ending_width)
+            } else {
+                (poly.constant_width, poly.constant_width)
+            };
+            self.write_vertex(
+                &Point::new(vertex.x, vertex.y, 0.0),
+                vertex.bulge,
+                starting_width,
+                ending_width,
+            )?;
+        }
+        self.write_seqend()?;
         Ok(())
     }
     fn write_point(&mut self, point: &ModelPoint) -> DxfResult<()> {
@@ -158,9 +346,17 @@ impl<T: Write> DxbWriter<T> {
     }
     fn write_polyline(&mut self, poly: &Polyline) -> DxfResult<()> {
         self.write_item_type(DxbItemType::Polyline)?;
-        self.write_w(if poly.is_closed() { 1 } else { 0 })?;
+        self.write_w(i16::from(poly.is_closed()))?;
+        if poly.default_starting_width != 0.0 || poly.default_ending_width != 0.0 {
+            self.write_width(poly.default_starting_width, poly.default_ending_width)?;
+        }
         for vertex in poly.vertices() {
-            self.write_vertex(vertex)?;
+            self.write_vertex(
+                &vertex.location,
+                vertex.bulge,
+                vertex.starting_width,
+                vertex.ending_width,
+            )?;
         }
         self.write_seqend()?;
         Ok(())
@@ -181,22 +377,58 @@ impl<T: Write> DxbWriter<T> {
         self.write_n(solid.fourth_corner.y)?;
         Ok(())
     }
+    /// Writes a trace, as an extension of the previous trace if it starts where that one ended.
     fn write_trace(&mut self, trace: &Trace) -> DxfResult<()> {
-        self.write_item_type(DxbItemType::Trace)?;
-        self.write_n(trace.first_corner.x)?;
-        self.write_n(trace.first_corner.y)?;
-        self.write_n(trace.second_corner.x)?;
-        self.write_n(trace.second_corner.y)?;
+        let corner = |p: &Point| Point::new(p.x, p.y, 0.0);
+        let (first, second) = (corner(&trace.first_corner), corner(&trace.second_corner));
+        match self.last_trace_corners {
+            Some((ref p3, ref p4)) if *p3 == first && *p4 == second => {
+                self.write_item_type(DxbItemType::TraceExtension)?;
+            }
+            _ => {
+                self.write_item_type(DxbItemType::Trace)?;
+                self.write_n(first.x)?;
+                self.write_n(first.y)?;
+                self.write_n(second.x)?;
+                self.write_n(second.y)?;
+            }
+        }
         self.write_n(trace.third_corner.x)?;
         self.write_n(trace.third_corner.y)?;
         self.write_n(trace.fourth_corner.x)?;
         self.write_n(trace.fourth_corner.y)?;
+        let (third, fourth) = (corner(&trace.third_corner), corner(&trace.fourth_corner));
+        self.last_trace_corners = Some(if self.options.use_integers {
+            let round = |p: &Point| Point::new(self.round(p.x), self.round(p.y), 0.0);
+            (round(&third), round(&fourth))
+        } else {
+            (third, fourth)
+        });
         Ok(())
     }
-    fn write_vertex(&mut self, vertex: &Vertex) -> DxfResult<()> {
+    fn write_vertex(
+        &mut self,
+        location: &Point,
+        bulge: f64,
+        starting_width: f64,
+        ending_width: f64,
+    ) -> DxfResult<()> {
         self.write_item_type(DxbItemType::Vertex)?;
-        self.write_n(vertex.location.x)?;
-        self.write_n(vertex.location.y)?;
+        self.write_n(location.x)?;
+        self.write_n(location.y)?;
+        if bulge != 0.0 {
+            self.write_item_type(DxbItemType::Bulge)?;
+            self.write_u(bulge)?;
+        }
+        if starting_width != 0.0 || ending_width != 0.0 {
+            self.write_width(starting_width, ending_width)?;
+        }
+        Ok(())
+    }
+    fn write_width(&mut self, starting_width: f64, ending_width: f64) -> DxfResult<()> {
+        self.write_item_type(DxbItemType::Width)?;
+        self.write_n(starting_width)?;
+        self.write_n(ending_width)?;
         Ok(())
     }
     fn write_string(&mut self, value: &str) -> DxfResult<()> {
@@ -210,8 +442,44 @@ impl<T: Write> DxbWriter<T> {
         self.writer.write_u8(0)?;
         Ok(())
     }
+    /// Returns the value that's read back after writing `d` as an integer.
+    fn round(&self, d: f64) -> f64 {
+        let scale_factor = self.options.scale_factor;
+        (d / scale_factor)
+            .round()
+            .clamp(f64::from(i16::MIN), f64::from(i16::MAX))
+            * scale_factor
+    }
+    /// Writes an angle in degrees.
+    fn write_a(&mut self, degrees: f64) -> DxfResult<()> {
+        if self.options.use_integers {
+            // integer angles are in millionths of a degree
+            self.writer
+                .write_i32::<LittleEndian>((degrees * 1_000_000.0).round() as i32)?;
+        } else {
+            self.writer.write_f64::<LittleEndian>(degrees)?;
+        }
+        Ok(())
+    }
+    /// Writes a coordinate or distance.
     fn write_n(&mut self, d: f64) -> DxfResult<()> {
-        self.writer.write_f32::<LittleEndian>(d as f32)?;
+        if self.options.use_integers {
+            let value = self.round(d) / self.options.scale_factor;
+            self.writer.write_i16::<LittleEndian>(value as i16)?;
+        } else {
+            self.writer.write_f64::<LittleEndian>(d)?;
+        }
+        Ok(())
+    }
+    /// Writes a bulge.
+    fn write_u(&mut self, bulge: f64) -> DxfResult<()> {
+        if self.options.use_integers {
+            // integer bulges are in units of 1/65536
+            self.writer
+                .write_i32::<LittleEndian>((bulge * 65536.0).round() as i32)?;
+        } else {
+            self.writer.write_f64::<LittleEndian>(bulge)?;
+        }
         Ok(())
     }
     fn write_w(&mut self, s: i16) -> DxfResult<()> {
@@ -223,3 +491,112 @@ impl<T: Write> DxbWriter<T> {
         Ok(())
     }
 }
+
+/// Returns `true` for the blocks that hold the entities of model and paper space.
+fn is_layout_block(block: &Block) -> bool {
+    let name = block.name.to_uppercase();
+    name.starts_with("*MODEL_SPACE") || name.starts_with("*PAPER_SPACE")
+}
+
+/// Returns `true` if an object coordinate system matches the world, which is the only one DXB
+/// can describe.
+fn is_upright(normal: &Vector) -> bool {
+    is_planar(normal) && normal.z > 0.0
+}
+
+/// Returns a copy of an entity from a block placed by a block reference, or `None` if the
+/// placement distorts its curves.
+fn place(entity: &Entity, placement: &Placement) -> Option<Entity> {
+    // the uniform scale and rotation, in degrees, of placements that don't distort arcs
+    let origin = placement.apply(&Point::origin());
+    let x_axis = placement.apply(&Point::new(1.0, 0.0, 0.0));
+    let y_axis = placement.apply(&Point::new(0.0, 1.0, 0.0));
+    let (x, y) = (
+        Vector::new(
+            x_axis.x - origin.x,
+            x_axis.y - origin.y,
+            x_axis.z - origin.z,
+        ),
+        Vector::new(
+            y_axis.x - origin.x,
+            y_axis.y - origin.y,
+            y_axis.z - origin.z,
+        ),
+    );
+    let scale = vector_length(&x);
+    let is_similar = !placement.is_mirroring()
+        && x.z.abs() < EPSILON
+        && y.z.abs() < EPSILON
+        && (vector_length(&y) - scale).abs() < EPSILON * scale.max(1.0)
+        && dot(&x, &y).abs() < EPSILON * scale.max(1.0);
+    let rotation = x.y.atan2(x.x).to_degrees();
+
+    let mut placed = entity.clone();
+    match placed.specific {
+        EntityType::Arc(ref mut arc) if is_similar && is_upright(&arc.normal) => {
+            arc.center = placement.apply(&arc.center);
+            arc.radius *= scale;
+            arc.start_angle = normalize_degrees(arc.start_angle + rotation);
+            arc.end_angle = normalize_degrees(arc.end_angle + rotation);
+        }
+        EntityType::Circle(ref mut circle) if is_similar && is_upright(&circle.normal) => {
+            circle.center = placement.apply(&circle.center);
+            circle.radius *= scale;
+        }
+        EntityType::Face3D(ref mut face) => {
+            face.first_corner = placement.apply(&face.first_corner);
+            face.second_corner = placement.apply(&face.second_corner);
+            face.third_corner = placement.apply(&face.third_corner);
+            face.fourth_corner = placement.apply(&face.fourth_corner);
+        }
+        EntityType::Line(ref mut line) => {
+            line.p1 = placement.apply(&line.p1);
+            line.p2 = placement.apply(&line.p2);
+        }
+        EntityType::LwPolyline(ref mut poly)
+            if is_similar && is_upright(&poly.extrusion_direction) =>
+        {
+            let elevation = entity.common.elevation;
+            poly.constant_width *= scale;
+            for vertex in &mut poly.vertices {
+                let location = placement.apply(&Point::new(vertex.x, vertex.y, elevation));
+                vertex.x = location.x;
+                vertex.y = location.y;
+                vertex.starting_width *= scale;
+                vertex.ending_width *= scale;
+            }
+        }
+        EntityType::ModelPoint(ref mut point) => {
+            point.location = placement.apply(&point.location);
+        }
+        EntityType::Polyline(ref mut poly)
+            if poly.is_3d_polyline()
+                || poly.is_3d_polygon_mesh()
+                || poly.is_polyface_mesh()
+                || (is_similar && is_upright(&poly.normal)) =>
+        {
+            poly.default_starting_width *= scale;
+            poly.default_ending_width *= scale;
+            // polyface meshes reuse their locations so those are placed as well
+            for vertex in poly.vertices_mut() {
+                vertex.location = placement.apply(&vertex.location);
+                vertex.starting_width *= scale;
+                vertex.ending_width *= scale;
+            }
+        }
+        EntityType::Solid(ref mut solid) => {
+            solid.first_corner = placement.apply(&solid.first_corner);
+            solid.second_corner = placement.apply(&solid.second_corner);
+            solid.third_corner = placement.apply(&solid.third_corner);
+            solid.fourth_corner = placement.apply(&solid.fourth_corner);
+        }
+        EntityType::Trace(ref mut trace) => {
+            trace.first_corner = placement.apply(&trace.first_corner);
+            trace.second_corner = placement.apply(&trace.second_corner);
+            trace.third_corner = placement.apply(&trace.third_corner);
+            trace.fourth_corner = placement.apply(&trace.fourth_corner);
+        }
+        _ => return None,
+    }
+    Some(placed)
+}
@@ -3,12 +3,13 @@ use std::fmt;
 use std::io;
 use std::num;
 
-use crate::CodePair;
+use crate::{CodePair, Handle};
 
 #[derive(Debug)]
 pub enum DxfError {
     IoError(io::Error),
     ImageError(image::ImageError),
+    XmlError(xmltree::ParseError),
     ParseFloatError(num::ParseFloatError, usize),
     ParseIntError(num::ParseIntError, usize),
     ParseError(usize),
@@ -23,6 +24,11 @@ pub enum DxfError {
     InvalidBinaryFile,
     MalformedString,
     WrongItemType,
+    LayoutNotFound(String),
+    DictionaryNotFound(String),
+    HandleNotFound(Handle),
+    MissingGeoReference,
+    UnsupportedDwgVersion(String),
 }
 
 impl From<io::Error> for DxfError {
@@ -37,11 +43,18 @@ impl From<::image::ImageError> for DxfError {
     }
 }
 
+impl From<::xmltree::ParseError> for DxfError {
+    fn from(xe: ::xmltree::ParseError) -> DxfError {
+        DxfError::XmlError(xe)
+    }
+}
+
 impl fmt::Display for DxfError {
     fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
         match *self {
             DxfError::IoError(ref e) => write!(formatter, "{e}"),
             DxfError::ImageError(ref e) => write!(formatter, "{e}"),
+            DxfError::XmlError(ref e) => write!(formatter, "{e}"),
             DxfError::ParseFloatError(ref e, o) => write!(formatter, "{e} at line/offset {o}"),
             DxfError::ParseIntError(ref e, o) => write!(formatter, "{e} at line/offset {o}"),
             DxfError::ParseError(o) => write!(
@@ -83,6 +96,26 @@ impl fmt::Display for DxfError {
             DxfError::InvalidBinaryFile => write!(formatter, "the binary file is invalid"),
             DxfError::MalformedString => write!(formatter, "the string is malformed"),
             DxfError::WrongItemType => write!(formatter, "the specified item type is not correct"),
+            DxfError::LayoutNotFound(ref name) => {
+                write!(formatter, "the layout '{name}' was not found")
+            }
+            DxfError::DictionaryNotFound(ref path) => {
+                write!(formatter, "the dictionary '{path}' was not found")
+            }
+            DxfError::HandleNotFound(handle) => {
+                write!(
+                    formatter,
+                    "no item with the handle '{}' was found",
+                    handle.as_string()
+                )
+            }
+            DxfError::MissingGeoReference => write!(
+                formatter,
+                "the drawing has no GeoData that relates it to WGS84 coordinates"
+            ),
+            DxfError::UnsupportedDwgVersion(ref version) => {
+                write!(formatter, "the DWG version '{version}' is not supported")
+            }
         }
     }
 }
@@ -92,6 +125,7 @@ impl error::Error for DxfError {
         match *self {
             DxfError::IoError(ref e) => Some(e),
             DxfError::ImageError(ref e) => Some(e),
+            DxfError::XmlError(ref e) => Some(e),
             DxfError::ParseFloatError(ref e, _) => Some(e),
             DxfError::ParseIntError(ref e, _) => Some(e),
             _ => None,
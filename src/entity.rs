@@ -1,14 +1,25 @@
 // other implementation is in `generated/entities.rs`
 
+use std::f64::consts::PI;
+
 use enum_primitive::FromPrimitive;
 
-use crate::{CodePair, Color, DxfError, DxfResult, Handle, Point, Vector};
+use crate::{
+    ApproximateTextMetrics, CodePair, Color, DecodedText, DxfError, DxfResult, Handle, MTextNode,
+    MTextRun, Point, TextMetrics, Vector,
+};
 
-use crate::code_pair_put_back::CodePairPutBack;
+use crate::code_pair_put_back::{CodePairPutBack, COMMENT_CODE};
+use crate::drawing::AUTO_REPLACE_HANDLE;
 use crate::entities::*;
 use crate::enums::*;
+use crate::geometry::*;
 use crate::helper_functions::*;
-use crate::Drawing;
+use crate::mtext_format;
+use crate::mtext_layout;
+use crate::proxy_graphics::{decode_proxy_graphics, explode_proxy};
+use crate::sat::{sat_lines_from_text, sat_text_from_lines};
+use crate::{Drawing, ProxyGraphicsPrimitive, SatDocument};
 
 //------------------------------------------------------------------------------
 //                                                                           Arc
@@ -23,6 +34,44 @@ impl Arc {
             ..Default::default()
         }
     }
+    /// Returns the angle in degrees swept counter-clockwise from `start_angle` to `end_angle`.
+    pub fn sweep_angle(&self) -> f64 {
+        sweep_degrees(self.start_angle, self.end_angle)
+    }
+    /// Returns the length of the arc.
+    pub fn length(&self) -> f64 {
+        self.radius * self.sweep_angle().to_radians()
+    }
+}
+
+//------------------------------------------------------------------------------
+//                                                                     Attribute
+//------------------------------------------------------------------------------
+impl Attribute {
+    /// Returns the attribute value with its `%%` control codes and unicode escapes decoded.
+    pub fn decoded_value(&self) -> DecodedText {
+        DecodedText::decode(&self.value)
+    }
+    /// Sets the attribute value from decoded text, encoding symbols and underline/overline spans
+    /// as `%%` control codes.
+    pub fn set_decoded_value(&mut self, value: &DecodedText) {
+        self.value = value.encode();
+    }
+}
+
+//------------------------------------------------------------------------------
+//                                                           AttributeDefinition
+//------------------------------------------------------------------------------
+impl AttributeDefinition {
+    /// Returns the default attribute value with its `%%` control codes and unicode escapes decoded.
+    pub fn decoded_value(&self) -> DecodedText {
+        DecodedText::decode(&self.value)
+    }
+    /// Sets the default attribute value from decoded text, encoding symbols and underline/overline
+    /// spans as `%%` control codes.
+    pub fn set_decoded_value(&mut self, value: &DecodedText) {
+        self.value = value.encode();
+    }
 }
 
 //------------------------------------------------------------------------------
@@ -36,6 +85,14 @@ impl Circle {
             ..Default::default()
         }
     }
+    /// Returns the circumference of the circle.
+    pub fn length(&self) -> f64 {
+        2.0 * PI * self.radius
+    }
+    /// Returns the area enclosed by the circle.
+    pub fn area(&self) -> f64 {
+        PI * self.radius * self.radius
+    }
 }
 
 //------------------------------------------------------------------------------
@@ -64,6 +121,60 @@ impl DimensionBase {
     }
 }
 
+//------------------------------------------------------------------------------
+//                                                                       Ellipse
+//------------------------------------------------------------------------------
+impl Ellipse {
+    /// Returns `true` if the ellipse sweeps a full revolution.
+    pub fn is_closed(&self) -> bool {
+        let (start, end) = ellipse_parameter_range(self);
+        (end - start - 2.0 * PI).abs() < 1e-9
+    }
+    /// Returns the point on the ellipse at the specified parameter, in radians.
+    pub fn point_at_parameter(&self, parameter: f64) -> Point {
+        let minor_axis = ellipse_minor_axis(self);
+        let (sin, cos) = parameter.sin_cos();
+        Point::new(
+            self.center.x + self.major_axis.x * cos + minor_axis.x * sin,
+            self.center.y + self.major_axis.y * cos + minor_axis.y * sin,
+            self.center.z + self.major_axis.z * cos + minor_axis.z * sin,
+        )
+    }
+    /// Approximates the ellipse with `segments` straight segments, returning `segments + 1` points.
+    pub fn tessellate(&self, segments: usize) -> Vec<Point> {
+        let segments = segments.max(1);
+        let (start, end) = ellipse_parameter_range(self);
+        (0..=segments)
+            .map(|i| self.point_at_parameter(start + (end - start) * i as f64 / segments as f64))
+            .collect()
+    }
+    /// Returns the length of the ellipse, computed by numeric integration.
+    pub fn length(&self) -> f64 {
+        // composite Simpson's rule over the speed of the parametric curve
+        const INTERVALS: usize = 1024;
+        let a = vector_length(&self.major_axis);
+        let b = a * self.minor_axis_ratio;
+        let (start, end) = ellipse_parameter_range(self);
+        let h = (end - start) / INTERVALS as f64;
+        let speed = |t: f64| (a * a * t.sin().powi(2) + b * b * t.cos().powi(2)).sqrt();
+        let mut sum = speed(start) + speed(end);
+        for i in 1..INTERVALS {
+            let weight = if i % 2 == 0 { 2.0 } else { 4.0 };
+            sum += weight * speed(start + h * i as f64);
+        }
+        sum * h / 3.0
+    }
+    /// Returns the area enclosed by the ellipse, or `None` if the ellipse is not closed.
+    pub fn area(&self) -> Option<f64> {
+        if self.is_closed() {
+            let a = vector_length(&self.major_axis);
+            Some(PI * a * a * self.minor_axis_ratio)
+        } else {
+            None
+        }
+    }
+}
+
 //------------------------------------------------------------------------------
 //                                                                        Face3D
 //------------------------------------------------------------------------------
@@ -82,6 +193,31 @@ impl Face3D {
             ..Default::default()
         }
     }
+    /// Returns the distinct corners of the face in order; triangular faces return three points.
+    pub fn corners(&self) -> Vec<Point> {
+        dedupe_loop(&[
+            self.first_corner.clone(),
+            self.second_corner.clone(),
+            self.third_corner.clone(),
+            self.fourth_corner.clone(),
+        ])
+    }
+    /// Returns the perimeter of the face.
+    pub fn length(&self) -> f64 {
+        let mut corners = self.corners();
+        if let Some(first) = corners.first().cloned() {
+            corners.push(first);
+        }
+        path_length(&corners)
+    }
+    /// Returns the area of the face.
+    pub fn area(&self) -> f64 {
+        polygon_area_and_centroid_3d(&self.corners()).0
+    }
+    /// Returns the area centroid of the face.
+    pub fn centroid(&self) -> Point {
+        polygon_area_and_centroid_3d(&self.corners()).1
+    }
 }
 
 //------------------------------------------------------------------------------
@@ -111,6 +247,104 @@ impl Line {
             ..Default::default()
         }
     }
+    /// Returns the length of the line.
+    pub fn length(&self) -> f64 {
+        distance(&self.p1, &self.p2)
+    }
+}
+
+//------------------------------------------------------------------------------
+//                                                                    LwPolyline
+//------------------------------------------------------------------------------
+const LW_POLYLINE_PLINEGEN_FLAG: i32 = 128;
+
+impl LwPolyline {
+    /// Converts this to an equivalent 2D `Polyline` at the specified elevation.  The per-vertex
+    /// widths, bulges and identifiers, the constant width, thickness, extrusion direction and flags
+    /// are all preserved.
+    pub fn to_polyline(&self, elevation: f64) -> Polyline {
+        let mut poly = Polyline {
+            location: Point::new(0.0, 0.0, elevation),
+            thickness: self.thickness,
+            default_starting_width: self.constant_width,
+            default_ending_width: self.constant_width,
+            normal: self.extrusion_direction.clone(),
+            ..Default::default()
+        };
+        poly.set_is_closed(self.is_closed());
+        poly.set_is_line_type_pattern_generated_continuously(
+            self.flags & LW_POLYLINE_PLINEGEN_FLAG != 0,
+        );
+        // the constant width only applies when no vertex has a width of its own
+        let has_vertex_widths = self
+            .vertices
+            .iter()
+            .any(|v| v.starting_width != 0.0 || v.ending_width != 0.0);
+        for v in &self.vertices {
+            let (starting_width, ending_width) = if has_vertex_widths {
+                (v.starting_width, v.ending_width)
+            } else {
+                (self.constant_width, self.constant_width)
+            };
+            let vertex = Vertex {
+                location: Point::new(v.x, v.y, 0.0),
+                starting_width,
+                ending_width,
+                bulge: v.bulge,
+                identifier: v.id,
+                ..Default::default()
+            };
+            poly.__vertices_and_handles
+                .push((vertex, AUTO_REPLACE_HANDLE));
+        }
+        poly
+    }
+    /// Returns the length of the polyline, including the closing segment if the polyline is closed.
+    /// Bulged segments are measured along their arcs.
+    pub fn length(&self) -> f64 {
+        let count = self.vertices.len();
+        let segment_count = if self.is_closed() {
+            count
+        } else {
+            count.saturating_sub(1)
+        };
+        (0..segment_count)
+            .map(|i| {
+                let v1 = &self.vertices[i];
+                let v2 = &self.vertices[(i + 1) % count];
+                bulge_segment_length(v1.x, v1.y, v2.x, v2.y, v1.bulge)
+            })
+            .sum()
+    }
+    /// Returns the area enclosed by the polyline, or `None` if it is not closed.  Bulged segments
+    /// are treated as exact circular arcs.
+    pub fn area(&self) -> Option<f64> {
+        self.signed_area_and_centroid()
+            .map(|(area, _, _)| area.abs())
+    }
+    /// Returns the area centroid of the polyline in its object coordinate system, or `None` if it
+    /// is not closed.  The z value is always 0; the elevation is stored on the entity.
+    pub fn centroid(&self) -> Option<Point> {
+        self.signed_area_and_centroid()
+            .map(|(_, x, y)| Point::new(x, y, 0.0))
+    }
+    /// Returns the winding direction of the polyline when viewed from the tip of the extrusion
+    /// direction, or `None` if it is not closed or encloses no area.
+    pub fn winding(&self) -> Option<WindingDirection> {
+        self.signed_area_and_centroid()
+            .and_then(|(area, _, _)| winding_from_signed_area(area))
+    }
+    fn signed_area_and_centroid(&self) -> Option<(f64, f64, f64)> {
+        let mut vertices = self
+            .vertices
+            .iter()
+            .map(|v| (v.x, v.y, v.bulge))
+            .collect::<Vec<_>>();
+        if !close_bulged_loop(&mut vertices, self.is_closed()) {
+            return None;
+        }
+        Some(bulged_loop_area_and_centroid(&vertices))
+    }
 }
 
 //------------------------------------------------------------------------------
@@ -128,6 +362,51 @@ pub struct LwPolylineVertex {
     pub bulge: f64,
 }
 
+//------------------------------------------------------------------------------
+//                                                                         MText
+//------------------------------------------------------------------------------
+impl MText {
+    /// Returns the complete text content, joining `extended_text` and `text`.
+    pub fn full_text(&self) -> String {
+        let mut content = self.extended_text.concat();
+        content.push_str(&self.text);
+        content
+    }
+    /// Sets the complete text content, splitting it into 250 character chunks between
+    /// `extended_text` and `text` without breaking any formatting codes.
+    pub fn set_full_text(&mut self, content: &str) {
+        let mut chunks = mtext_format::split_mtext_chunks(content);
+        self.text = chunks.pop().unwrap_or_default();
+        self.extended_text = chunks;
+    }
+    /// Parses the inline formatting codes of the text content.
+    pub fn formatting(&self) -> Vec<MTextNode> {
+        MTextNode::parse(&self.full_text())
+    }
+    /// Sets the text content from formatted nodes.
+    pub fn set_formatting(&mut self, nodes: &[MTextNode]) {
+        self.set_full_text(&MTextNode::format(nodes));
+    }
+    /// Returns the text content with all formatting codes removed and paragraph breaks replaced
+    /// with newlines.
+    pub fn plain_text(&self) -> String {
+        MTextNode::plain_text(&self.formatting())
+    }
+    /// Lays out the text, applying the inline formatting codes and wrapping to
+    /// `reference_rectangle_width`, and returns the positioned runs of text.
+    pub fn layout(&self, metrics: &dyn TextMetrics) -> Vec<MTextRun> {
+        mtext_layout::layout(self, metrics)
+    }
+    /// Converts the text into equivalent `Text` values using approximate font metrics.  Use
+    /// `Entity::explode_mtext()` to also keep inline color changes.
+    pub fn explode(&self) -> Vec<Text> {
+        self.layout(&ApproximateTextMetrics)
+            .into_iter()
+            .map(|run| run.text)
+            .collect()
+    }
+}
+
 //------------------------------------------------------------------------------
 //                                                                    ModelPoint
 //------------------------------------------------------------------------------
@@ -140,6 +419,35 @@ impl ModelPoint {
     }
 }
 
+//------------------------------------------------------------------------------
+//                                                    Body, Region, and Solid3D
+//------------------------------------------------------------------------------
+macro_rules! impl_modeler_geometry {
+    ($type:ident) => {
+        impl $type {
+            /// Returns the ACIS SAT text of the entity, decrypted if needed.
+            pub fn sat_text(&self) -> String {
+                sat_text_from_lines(&self.custom_data, &self.custom_data2)
+            }
+            /// Sets the ACIS SAT text of the entity, encrypted as `version` expects it.
+            pub fn set_sat_text(&mut self, text: &str, version: AcadVersion) {
+                let (lines, continuations) =
+                    sat_lines_from_text(text, version < AcadVersion::R2013);
+                self.custom_data = lines;
+                self.custom_data2 = continuations;
+            }
+            /// Parses the ACIS SAT text of the entity.
+            pub fn sat_document(&self) -> DxfResult<SatDocument> {
+                SatDocument::parse(&self.sat_text())
+            }
+        }
+    };
+}
+
+impl_modeler_geometry!(Body);
+impl_modeler_geometry!(Region);
+impl_modeler_geometry!(Solid3D);
+
 //------------------------------------------------------------------------------
 //                                                                      Polyline
 //------------------------------------------------------------------------------
@@ -154,6 +462,199 @@ impl Polyline {
         let vertex_handle = drawing.next_handle();
         self.__vertices_and_handles.push((vertex, vertex_handle));
     }
+    fn is_mesh(&self) -> bool {
+        self.is_polyface_mesh() || self.is_3d_polygon_mesh()
+    }
+    /// Converts this to an equivalent `LwPolyline`, or returns `None` if the polyline can't be
+    /// represented without loss, e.g., polyface and polygon meshes, curve- or spline-fit polylines
+    /// and non-planar 3D polylines.  The elevation of the result is `self.location.z` for 2D
+    /// polylines or the shared z value of a 3D polyline's vertices; use `Entity::to_lw_polyline()`
+    /// to have it applied automatically.
+    pub fn to_lw_polyline(&self) -> Option<LwPolyline> {
+        if self.is_mesh() || self.curve_fit_vertices_added() || self.spline_fit_vertices_added() {
+            return None;
+        }
+
+        if self.is_3d_polyline() {
+            let mut vertices = self.vertices();
+            if let Some(first) = vertices.next() {
+                let z = first.location.z;
+                if vertices.any(|v| (v.location.z - z).abs() > EPSILON) {
+                    return None;
+                }
+            }
+        }
+
+        // the default widths only apply when no vertex has a width of its own
+        let has_vertex_widths = self
+            .vertices()
+            .any(|v| v.starting_width != 0.0 || v.ending_width != 0.0);
+        let widths = self
+            .vertices()
+            .map(|v| {
+                if has_vertex_widths {
+                    (v.starting_width, v.ending_width)
+                } else {
+                    (self.default_starting_width, self.default_ending_width)
+                }
+            })
+            .collect::<Vec<_>>();
+        let default_width = if self.default_starting_width == self.default_ending_width {
+            self.default_starting_width
+        } else {
+            0.0
+        };
+        // a width shared by every vertex becomes the constant width; otherwise the vertices keep
+        // their own widths and the constant width, which is then ignored, keeps the default
+        let shared_width = match widths.first() {
+            Some(&(width, _)) if widths.iter().all(|&(s, e)| s == width && e == width) => {
+                Some(width)
+            }
+            None => Some(default_width),
+            _ => None,
+        };
+        let (constant_width, widths) = match shared_width {
+            Some(width) => (width, vec![(0.0, 0.0); widths.len()]),
+            None => (default_width, widths),
+        };
+        let mut lw = LwPolyline {
+            constant_width,
+            thickness: self.thickness,
+            extrusion_direction: if self.is_3d_polyline() {
+                Vector::z_axis()
+            } else {
+                self.normal.clone()
+            },
+            vertices: self
+                .vertices()
+                .zip(&widths)
+                .map(|(v, &(starting_width, ending_width))| LwPolylineVertex {
+                    x: v.location.x,
+                    y: v.location.y,
+                    id: v.identifier,
+                    starting_width,
+                    ending_width,
+                    bulge: v.bulge,
+                })
+                .collect(),
+            ..Default::default()
+        };
+        lw.set_is_closed(self.is_closed());
+        if self.is_line_type_pattern_generated_continuously() {
+            lw.flags |= LW_POLYLINE_PLINEGEN_FLAG;
+        }
+        Some(lw)
+    }
+    fn lw_polyline_elevation(&self) -> f64 {
+        if self.is_3d_polyline() {
+            self.vertices().next().map_or(0.0, |v| v.location.z)
+        } else {
+            self.location.z
+        }
+    }
+    /// Returns the length of the polyline, including the closing segment if the polyline is closed,
+    /// or `None` for polyface and polygon meshes.  Bulged segments of 2D polylines are measured
+    /// along their arcs and the control points of spline-fit polylines are skipped.
+    pub fn length(&self) -> Option<f64> {
+        if self.is_mesh() {
+            return None;
+        }
+
+        let vertices = self.drawn_vertices().collect::<Vec<_>>();
+        let count = vertices.len();
+        let segment_count = if self.is_closed() {
+            count
+        } else {
+            count.saturating_sub(1)
+        };
+        let is_3d = self.is_3d_polyline();
+        let length = (0..segment_count)
+            .map(|i| {
+                let v1 = vertices[i];
+                let v2 = vertices[(i + 1) % count];
+                if is_3d {
+                    distance(&v1.location, &v2.location)
+                } else {
+                    bulge_segment_length(
+                        v1.location.x,
+                        v1.location.y,
+                        v2.location.x,
+                        v2.location.y,
+                        v1.bulge,
+                    )
+                }
+            })
+            .sum();
+        Some(length)
+    }
+    /// Returns the area enclosed by the polyline, or `None` if it is not closed or is a mesh.
+    /// Bulged segments of 2D polylines are treated as exact circular arcs.
+    pub fn area(&self) -> Option<f64> {
+        if self.is_3d_polyline() {
+            self.closed_points()
+                .map(|points| polygon_area_and_centroid_3d(&points).0)
+        } else {
+            self.signed_area_and_centroid()
+                .map(|(area, _, _)| area.abs())
+        }
+    }
+    /// Returns the area centroid of the polyline, or `None` if it is not closed or is a mesh.  2D
+    /// polylines report the centroid in their object coordinate system at the polyline's elevation.
+    pub fn centroid(&self) -> Option<Point> {
+        if self.is_3d_polyline() {
+            self.closed_points()
+                .map(|points| polygon_area_and_centroid_3d(&points).1)
+        } else {
+            self.signed_area_and_centroid()
+                .map(|(_, x, y)| Point::new(x, y, self.location.z))
+        }
+    }
+    /// Returns the winding direction of the polyline when viewed from the tip of the normal, or
+    /// `None` if it is not closed, is a mesh, or encloses no area.
+    pub fn winding(&self) -> Option<WindingDirection> {
+        if self.is_3d_polyline() {
+            self.closed_points()
+                .and_then(|points| winding_about(&points, &self.normal))
+        } else {
+            self.signed_area_and_centroid()
+                .and_then(|(area, _, _)| winding_from_signed_area(area))
+        }
+    }
+    /// Returns the vertices that lie on the drawn polyline, skipping the frame of a spline-fit
+    /// polyline.
+    fn drawn_vertices(&self) -> impl Iterator<Item = &Vertex> {
+        self.vertices()
+            .filter(|v| !v.is_spline_frame_control_point())
+    }
+    fn closed_points(&self) -> Option<Vec<Point>> {
+        if self.is_mesh() {
+            return None;
+        }
+        let points = self
+            .drawn_vertices()
+            .map(|v| v.location.clone())
+            .collect::<Vec<_>>();
+        let is_closed = self.is_closed()
+            || (points.len() > 2 && points_are_close(&points[0], &points[points.len() - 1]));
+        if is_closed {
+            Some(dedupe_loop(&points))
+        } else {
+            None
+        }
+    }
+    fn signed_area_and_centroid(&self) -> Option<(f64, f64, f64)> {
+        if self.is_mesh() {
+            return None;
+        }
+        let mut vertices = self
+            .drawn_vertices()
+            .map(|v| (v.location.x, v.location.y, v.bulge))
+            .collect::<Vec<_>>();
+        if !close_bulged_loop(&mut vertices, self.is_closed()) {
+            return None;
+        }
+        Some(bulged_loop_area_and_centroid(&vertices))
+    }
 }
 
 //------------------------------------------------------------------------------
@@ -175,6 +676,18 @@ impl ProxyEntity {
         self.__object_drawing_format =
             (version << 4) as u32 + (self.__object_drawing_format & 0xFFFF);
     }
+    /// Returns the graphics data with its 310 chunks joined and limited to `graphics_data_size`.
+    pub fn graphics_data(&self) -> Vec<u8> {
+        let mut data = self.graphics_data_string.concat();
+        if self.graphics_data_size > 0 {
+            data.truncate(self.graphics_data_size as usize);
+        }
+        data
+    }
+    /// Decodes the graphics data into the primitives that draw the proxy.
+    pub fn graphics(&self) -> DxfResult<Vec<ProxyGraphicsPrimitive>> {
+        decode_proxy_graphics(&self.graphics_data())
+    }
 }
 
 //------------------------------------------------------------------------------
@@ -195,6 +708,104 @@ impl Solid {
             ..Default::default()
         }
     }
+    /// Returns the distinct corners of the solid in drawing order.  Note that the third and fourth
+    /// corners are swapped compared to the storage order.
+    pub fn corners(&self) -> Vec<Point> {
+        dedupe_loop(&[
+            self.first_corner.clone(),
+            self.second_corner.clone(),
+            self.fourth_corner.clone(),
+            self.third_corner.clone(),
+        ])
+    }
+    /// Returns the perimeter of the solid.
+    pub fn length(&self) -> f64 {
+        let mut corners = self.corners();
+        if let Some(first) = corners.first().cloned() {
+            corners.push(first);
+        }
+        path_length(&corners)
+    }
+    /// Returns the area of the solid.
+    pub fn area(&self) -> f64 {
+        polygon_area_and_centroid_3d(&self.corners()).0
+    }
+    /// Returns the area centroid of the solid in its object coordinate system.
+    pub fn centroid(&self) -> Point {
+        polygon_area_and_centroid_3d(&self.corners()).1
+    }
+    /// Returns the winding direction of the corners when viewed from the tip of the extrusion
+    /// direction.
+    pub fn winding(&self) -> Option<WindingDirection> {
+        winding_about(&self.corners(), &Vector::z_axis())
+    }
+}
+
+//------------------------------------------------------------------------------
+//                                                                        Spline
+//------------------------------------------------------------------------------
+impl Spline {
+    /// Returns the point on the spline at the parameter `t`, which ranges over the knot values.
+    pub fn point_at(&self, t: f64) -> Point {
+        spline_point_at(self, &spline_knots(self), t)
+    }
+    /// Approximates the spline with a series of points, evaluating each knot span
+    /// `segments_per_span` times.  Splines with no control points return their fit points.
+    pub fn tessellate(&self, segments_per_span: usize) -> Vec<Point> {
+        tessellate_spline(self, segments_per_span)
+    }
+    /// Returns the approximate length of the spline.
+    pub fn length(&self) -> f64 {
+        let mut points = self.tessellate(SPLINE_MEASUREMENT_SEGMENTS);
+        if self.is_closed()
+            && points.len() > 1
+            && !points_are_close(&points[0], &points[points.len() - 1])
+        {
+            points.push(points[0].clone());
+        }
+        path_length(&points)
+    }
+    /// Returns the approximate area enclosed by the spline, or `None` if it is not closed.
+    pub fn area(&self) -> Option<f64> {
+        self.closed_points()
+            .map(|points| polygon_area_and_centroid_3d(&points).0)
+    }
+    /// Returns the approximate area centroid of the spline, or `None` if it is not closed.
+    pub fn centroid(&self) -> Option<Point> {
+        self.closed_points()
+            .map(|points| polygon_area_and_centroid_3d(&points).1)
+    }
+    /// Returns the winding direction of the spline when viewed from the tip of the normal, or
+    /// `None` if it is not closed.
+    pub fn winding(&self) -> Option<WindingDirection> {
+        self.closed_points()
+            .and_then(|points| winding_about(&points, &self.normal))
+    }
+    fn closed_points(&self) -> Option<Vec<Point>> {
+        let points = self.tessellate(SPLINE_MEASUREMENT_SEGMENTS);
+        let is_closed = self.is_closed()
+            || (points.len() > 2 && points_are_close(&points[0], &points[points.len() - 1]));
+        if is_closed && points.len() > 2 {
+            Some(dedupe_loop(&points))
+        } else {
+            None
+        }
+    }
+}
+
+//------------------------------------------------------------------------------
+//                                                                          Text
+//------------------------------------------------------------------------------
+impl Text {
+    /// Returns the text value with its `%%` control codes and unicode escapes decoded.
+    pub fn decoded_value(&self) -> DecodedText {
+        DecodedText::decode(&self.value)
+    }
+    /// Sets the text value from decoded text, encoding symbols and underline/overline spans as `%%`
+    /// control codes.
+    pub fn set_decoded_value(&mut self, value: &DecodedText) {
+        self.value = value.encode();
+    }
 }
 
 //------------------------------------------------------------------------------
@@ -405,18 +1016,160 @@ impl Entity {
         self.common.normalize();
         // no entity-specific values to set
     }
+    /// Converts a `LwPolyline` entity into an equivalent `Polyline` entity, moving the elevation from
+    /// the common values onto the polyline.  Returns `None` for all other entity types.
+    pub fn to_polyline(&self) -> Option<Entity> {
+        match self.specific {
+            EntityType::LwPolyline(ref lw) => Some(Entity {
+                common: EntityCommon {
+                    elevation: 0.0,
+                    ..self.common.clone()
+                },
+                specific: EntityType::Polyline(lw.to_polyline(self.common.elevation)),
+            }),
+            _ => None,
+        }
+    }
+    /// Converts a `Polyline` entity into an equivalent `LwPolyline` entity with the polyline's
+    /// elevation applied to the common values.  Returns `None` for all other entity types or if the
+    /// polyline can't be represented without loss.
+    pub fn to_lw_polyline(&self) -> Option<Entity> {
+        match self.specific {
+            EntityType::Polyline(ref poly) => poly.to_lw_polyline().map(|lw| Entity {
+                common: EntityCommon {
+                    elevation: poly.lw_polyline_elevation(),
+                    ..self.common.clone()
+                },
+                specific: EntityType::LwPolyline(lw),
+            }),
+            _ => None,
+        }
+    }
+    /// Converts an `MText` entity into `Text` entities that share its common values, applying any
+    /// inline color changes.  Returns `None` for all other entity types.
+    pub fn explode_mtext(&self) -> Option<Vec<Entity>> {
+        match self.specific {
+            EntityType::MText(ref mtext) => Some(
+                mtext
+                    .layout(&ApproximateTextMetrics)
+                    .into_iter()
+                    .map(|run| {
+                        let mut common = EntityCommon {
+                            handle: Handle::empty(),
+                            ..self.common.clone()
+                        };
+                        if let Some(color) = run.color {
+                            common.color = color;
+                        }
+                        if let Some(color_24_bit) = run.color_24_bit {
+                            common.color_24_bit = color_24_bit;
+                        }
+                        Entity {
+                            common,
+                            specific: EntityType::Text(run.text),
+                        }
+                    })
+                    .collect(),
+            ),
+            _ => None,
+        }
+    }
+    /// Converts the graphics of a `ProxyEntity` into native entities that share its layer, color,
+    /// line type and other display properties, but not its handle, reactors or XDATA, applying
+    /// the color, layer, line type, line weight and matrix changes in the graphics.  Layers and
+    /// line types are looked up by index in `drawing`.  Returns `None` for all other entity
+    /// types or if the graphics can't be decoded.
+    pub fn explode_proxy(&self, drawing: &Drawing) -> Option<Vec<Entity>> {
+        match self.specific {
+            EntityType::ProxyEntity(ref proxy) => explode_proxy(self, proxy, drawing),
+            _ => None,
+        }
+    }
+    /// Returns the length of the entity's path, or the perimeter of closed shapes.  Returns `None`
+    /// for entities that have no meaningful length.
+    pub fn length(&self) -> Option<f64> {
+        match self.specific {
+            EntityType::Arc(ref arc) => Some(arc.length()),
+            EntityType::Circle(ref circle) => Some(circle.length()),
+            EntityType::Ellipse(ref ellipse) => Some(ellipse.length()),
+            EntityType::Face3D(ref face) => Some(face.length()),
+            EntityType::Line(ref line) => Some(line.length()),
+            EntityType::LwPolyline(ref poly) => Some(poly.length()),
+            EntityType::Polyline(ref poly) => poly.length(),
+            EntityType::Solid(ref solid) => Some(solid.length()),
+            EntityType::Spline(ref spline) => Some(spline.length()),
+            _ => None,
+        }
+    }
+    /// Returns the area enclosed by the entity, or `None` if the entity is not a closed shape.
+    pub fn area(&self) -> Option<f64> {
+        match self.specific {
+            EntityType::Circle(ref circle) => Some(circle.area()),
+            EntityType::Ellipse(ref ellipse) => ellipse.area(),
+            EntityType::Face3D(ref face) => Some(face.area()),
+            EntityType::LwPolyline(ref poly) => poly.area(),
+            EntityType::Polyline(ref poly) => poly.area(),
+            EntityType::Solid(ref solid) => Some(solid.area()),
+            EntityType::Spline(ref spline) => spline.area(),
+            _ => None,
+        }
+    }
+    /// Returns the area centroid of the entity, or `None` if the entity is not a closed shape.  The
+    /// centroid is reported in the same coordinate system as the entity's own points.
+    pub fn centroid(&self) -> Option<Point> {
+        match self.specific {
+            EntityType::Circle(ref circle) => Some(circle.center.clone()),
+            EntityType::Ellipse(ref ellipse) if ellipse.is_closed() => Some(ellipse.center.clone()),
+            EntityType::Face3D(ref face) => Some(face.centroid()),
+            EntityType::LwPolyline(ref poly) => poly.centroid().map(|p| Point {
+                z: self.common.elevation,
+                ..p
+            }),
+            EntityType::Polyline(ref poly) => poly.centroid(),
+            EntityType::Solid(ref solid) => Some(solid.centroid()),
+            EntityType::Spline(ref spline) => spline.centroid(),
+            _ => None,
+        }
+    }
+    /// Returns the winding direction of a closed shape when viewed from the tip of its normal, or
+    /// `None` if the entity is not a closed shape.
+    pub fn winding(&self) -> Option<WindingDirection> {
+        match self.specific {
+            EntityType::Circle(_) => Some(WindingDirection::CounterClockwise),
+            EntityType::Ellipse(ref ellipse) if ellipse.is_closed() => {
+                Some(WindingDirection::CounterClockwise)
+            }
+            EntityType::Face3D(ref face) => winding_about(&face.corners(), &Vector::z_axis()),
+            EntityType::LwPolyline(ref poly) => poly.winding(),
+            EntityType::Polyline(ref poly) => poly.winding(),
+            EntityType::Solid(ref solid) => solid.winding(),
+            EntityType::Spline(ref spline) => spline.winding(),
+            _ => None,
+        }
+    }
+    /// Returns `true` if the entity describes a closed shape.
+    pub fn is_closed(&self) -> bool {
+        self.area().is_some()
+    }
     pub(crate) fn read(iter: &mut CodePairPutBack) -> DxfResult<Option<Entity>> {
+        let mut entity = Entity::read_without_comments(iter)?;
+        if let Some(ref mut entity) = entity {
+            entity.common.comments = iter.take_comments();
+        }
+        Ok(entity)
+    }
+    fn read_without_comments(iter: &mut CodePairPutBack) -> DxfResult<Option<Entity>> {
         'new_entity: loop {
             match iter.next() {
                 // first code pair must be 0/entity-type
                 Some(Ok(pair @ CodePair { code: 0, .. })) => {
-                    let type_string = pair.assert_string()?;
+                    let type_string = pair.as_str()?;
                     if type_string == "ENDSEC" || type_string == "ENDBLK" {
                         iter.put_back(Ok(pair));
                         return Ok(None);
                     }
 
-                    match &*type_string {
+                    match type_string {
                         "DIMENSION" => {
                             // dimensions require special handling
                             let mut common = EntityCommon::default();
@@ -429,11 +1182,12 @@ impl Entity {
                                         iter.put_back(Ok(pair));
                                         break;
                                     }
-                                    Some(Ok(pair)) => {
+                                    Some(Ok(mut pair)) => {
                                         match dimension_entity {
                                             Some(ref mut dim) => {
                                                 if !dim.apply_dimension_code_pair(&pair)? {
-                                                    common.apply_individual_pair(&pair, iter)?;
+                                                    common
+                                                        .apply_individual_pair(&mut pair, iter)?;
                                                 }
                                             }
                                             None => {
@@ -532,7 +1286,7 @@ impl Entity {
                                                         );
                                                     }
                                                     100 => {
-                                                        match &*pair.assert_string()? {
+                                                        match pair.as_str()? {
                                                             "AcDbAlignedDimension" => {
                                                                 dimension_entity = Some(
                                                                     EntityType::RotatedDimension(
@@ -588,8 +1342,9 @@ impl Entity {
                                                         }
                                                     }
                                                     _ => {
-                                                        common
-                                                            .apply_individual_pair(&pair, iter)?;
+                                                        common.apply_individual_pair(
+                                                            &mut pair, iter,
+                                                        )?;
                                                     }
                                                 }
                                             }
@@ -613,7 +1368,7 @@ impl Entity {
                             }
                         }
                         _ => {
-                            match EntityType::from_type_string(&type_string) {
+                            match EntityType::from_type_string(type_string) {
                                 Some(e) => {
                                     let mut entity = Entity::new(e);
                                     if !entity.apply_custom_reader(iter)? {
@@ -625,8 +1380,8 @@ impl Entity {
                                                     iter.put_back(Ok(pair));
                                                     break;
                                                 }
-                                                Some(Ok(pair)) => {
-                                                    entity.apply_code_pair(&pair, iter)?
+                                                Some(Ok(mut pair)) => {
+                                                    entity.apply_code_pair(&mut pair, iter)?
                                                 }
                                                 Some(Err(e)) => return Err(e),
                                                 None => return Err(DxfError::UnexpectedEndOfInput),
@@ -668,7 +1423,11 @@ impl Entity {
             }
         }
     }
-    fn apply_code_pair(&mut self, pair: &CodePair, iter: &mut CodePairPutBack) -> DxfResult<()> {
+    fn apply_code_pair(
+        &mut self,
+        pair: &mut CodePair,
+        iter: &mut CodePairPutBack,
+    ) -> DxfResult<()> {
         if !self.specific.try_apply_code_pair(pair)? {
             self.common.apply_individual_pair(pair, iter)?;
         }
@@ -812,7 +1571,7 @@ impl Entity {
         let mut is_version_set = false;
         let mut xrec_code_70_count = 0;
         loop {
-            let pair = next_pair!(iter);
+            let mut pair = next_pair!(iter);
             match pair.code {
                 100 => {
                     last_subclass_marker = pair.assert_string()?;
@@ -945,7 +1704,7 @@ impl Entity {
                     att.__secondary_attributes_handle.push(pair.as_handle()?);
                 }
                 _ => {
-                    common.apply_individual_pair(&pair, iter)?;
+                    common.apply_individual_pair(&mut pair, iter)?;
                 }
             }
         }
@@ -960,7 +1719,7 @@ impl Entity {
         let mut is_version_set = false;
         let mut xrec_code_70_count = 0;
         loop {
-            let pair = next_pair!(iter);
+            let mut pair = next_pair!(iter);
             match pair.code {
                 100 => {
                     last_subclass_marker = pair.assert_string()?;
@@ -1096,7 +1855,7 @@ impl Entity {
                     att.__secondary_attributes_handle.push(pair.as_handle()?);
                 }
                 _ => {
-                    common.apply_individual_pair(&pair, iter)?;
+                    common.apply_individual_pair(&mut pair, iter)?;
                 }
             }
         }
@@ -1107,7 +1866,7 @@ impl Entity {
         iter: &mut CodePairPutBack,
     ) -> DxfResult<bool> {
         loop {
-            let pair = next_pair!(iter);
+            let mut pair = next_pair!(iter);
             match pair.code {
                 // vertex-specific pairs
                 10 => {
@@ -1150,7 +1909,7 @@ impl Entity {
                     poly.extrusion_direction.z = pair.assert_f64()?;
                 }
                 _ => {
-                    common.apply_individual_pair(&pair, iter)?;
+                    common.apply_individual_pair(&mut pair, iter)?;
                 }
             }
         }
@@ -1163,7 +1922,7 @@ impl Entity {
         let mut reading_column_data = false;
         let mut read_column_count = false;
         loop {
-            let pair = next_pair!(iter);
+            let mut pair = next_pair!(iter);
             match pair.code {
                 10 => {
                     mtext.insertion_point.x = pair.assert_f64()?;
@@ -1287,7 +2046,7 @@ impl Entity {
                     mtext.column_gutter = pair.assert_f64()?;
                 }
                 _ => {
-                    common.apply_individual_pair(&pair, iter)?;
+                    common.apply_individual_pair(&mut pair, iter)?;
                 }
             }
         }
@@ -1299,6 +2058,9 @@ impl Entity {
         write_handles: bool,
     ) {
         if self.specific.is_supported_on_version(version) {
+            for comment in &self.common.comments {
+                pairs.push(CodePair::new_string(COMMENT_CODE, comment));
+            }
             pairs.push(CodePair::new_str(0, self.specific.to_type_string()));
             self.common.add_code_pairs(pairs, version, write_handles);
             if !self.add_custom_code_pairs(pairs, version) {
@@ -1577,7 +2339,13 @@ impl Entity {
                         && v.polyface_mesh_vertex_index3 == 0
                         && v.polyface_mesh_vertex_index4 == 0
                     {
-                        v.set_is_3d_polygon_mesh(poly.is_3d_polygon_mesh());
+                        // polyface mesh locations are flagged as polygon mesh vertices, too
+                        v.set_is_3d_polygon_mesh(
+                            poly.is_3d_polygon_mesh() || poly.is_polyface_mesh(),
+                        );
+                    }
+                    if poly.is_polyface_mesh() {
+                        v.set_is_polyface_mesh_vertex(true);
                     }
                     let v = Entity {
                         common: EntityCommon {
@@ -1616,7 +2384,7 @@ impl Entity {
     fn add_code_pairs_seqend(pairs: &mut Vec<CodePair>, handle: &Handle, write_handles: bool) {
         pairs.push(CodePair::new_str(0, "SEQEND"));
         if write_handles {
-            pairs.push(CodePair::new_string(5, &handle.as_string()));
+            pairs.push(CodePair::new_string(5, handle.as_string()));
         }
     }
 }
@@ -1976,6 +2744,50 @@ mod tests {
         );
     }
 
+    #[test]
+    fn read_mtext_extended_text_chunks() {
+        let ent = read_entity(
+            "MTEXT",
+            vec![
+                CodePair::new_str(3, "{\\H2x;first "),
+                CodePair::new_str(3, "second} "),
+                CodePair::new_str(1, "third\\Pfourth"),
+            ],
+        );
+        match ent.specific {
+            EntityType::MText(ref mtext) => {
+                assert_eq!("{\\H2x;first second} third\\Pfourth", mtext.full_text());
+                assert_eq!("first second third\nfourth", mtext.plain_text());
+            }
+            _ => panic!("expected an MTEXT"),
+        }
+    }
+
+    #[test]
+    fn mtext_full_text_round_trip() {
+        let content = MTextBuilder::new()
+            .text(&"word ".repeat(100))
+            .group(|b| b.format(MTextFormat::Height(2.5)).text("big"))
+            .build();
+        let mut mtext = MText::default();
+        mtext.set_full_text(&content);
+        assert_eq!(2, mtext.extended_text.len());
+        assert!(mtext.extended_text.iter().all(|c| c.chars().count() == 250));
+
+        let mut drawing = Drawing::new();
+        drawing.header.version = AcadVersion::R2000;
+        drawing.add_entity(Entity::new(EntityType::MText(mtext)));
+        let drawing = parse_drawing(&to_test_string(&drawing));
+        let entities = drawing.entities().collect::<Vec<_>>();
+        match entities[0].specific {
+            EntityType::MText(ref mtext) => {
+                assert_eq!(content, mtext.full_text());
+                assert!(mtext.plain_text().ends_with("word big"));
+            }
+            _ => panic!("expected an MTEXT"),
+        }
+    }
+
     #[test]
     fn read_entity_with_custom_reader_mtext() {
         let ent = read_entity(
@@ -2526,6 +3338,44 @@ mod tests {
         );
     }
 
+    #[test]
+    fn write_polyface_mesh_vertex_flags() {
+        let mut drawing = Drawing::new();
+        drawing.header.version = AcadVersion::R13;
+        let mut poly = Polyline::default();
+        poly.set_is_polyface_mesh(true);
+        poly.add_vertex(&mut drawing, Vertex::new(Point::new(1.1, 2.1, 3.1)));
+        poly.add_vertex(
+            &mut drawing,
+            Vertex {
+                polyface_mesh_vertex_index1: 1,
+                polyface_mesh_vertex_index2: 1,
+                polyface_mesh_vertex_index3: 1,
+                ..Default::default()
+            },
+        );
+        drawing.add_entity(Entity::new(EntityType::Polyline(poly)));
+        assert_contains_pairs(
+            &drawing,
+            vec![
+                CodePair::new_f64(10, 1.1),
+                CodePair::new_f64(20, 2.1),
+                CodePair::new_f64(30, 3.1),
+                CodePair::new_i16(70, 192), // polyface mesh vertex + 3d polygon mesh
+            ],
+        );
+        assert_contains_pairs(
+            &drawing,
+            vec![
+                CodePair::new_i16(70, 128), // polyface mesh vertex
+                CodePair::new_f64(50, 0.0),
+                CodePair::new_i16(71, 1),
+                CodePair::new_i16(72, 1),
+                CodePair::new_i16(73, 1),
+            ],
+        );
+    }
+
     #[test]
     fn polyline_seqend_handle_is_assigned_when_added_to_drawing() {
         let mut drawing = Drawing::new();
@@ -2697,6 +3547,217 @@ mod tests {
         );
     }
 
+    #[test]
+    fn lw_polyline_to_polyline_round_trip() {
+        let mut lw = LwPolyline {
+            constant_width: 0.5,
+            thickness: 2.0,
+            extrusion_direction: Vector::new(0.0, 0.0, -1.0),
+            vertices: vec![
+                LwPolylineVertex {
+                    x: 1.0,
+                    y: 2.0,
+                    ..Default::default()
+                },
+                LwPolylineVertex {
+                    x: 3.0,
+                    y: 4.0,
+                    starting_width: 1.5,
+                    ending_width: 2.5,
+                    bulge: 0.25,
+                    id: 7,
+                },
+            ],
+            ..Default::default()
+        };
+        lw.set_is_closed(true);
+        let mut entity = Entity::new(EntityType::LwPolyline(lw.clone()));
+        entity.common.elevation = 3.5;
+        entity.common.layer = String::from("layer");
+
+        let poly_entity = entity.to_polyline().unwrap();
+        assert_eq!(0.0, poly_entity.common.elevation);
+        assert_eq!("layer", poly_entity.common.layer);
+        match poly_entity.specific {
+            EntityType::Polyline(ref poly) => {
+                assert!(poly.is_closed());
+                assert!(!poly.is_3d_polyline());
+                assert_eq!(3.5, poly.location.z);
+                assert_eq!(Vector::new(0.0, 0.0, -1.0), poly.normal);
+                assert_eq!(2.0, poly.thickness);
+                assert_eq!(0.5, poly.default_starting_width);
+                let vertices = poly.vertices().collect::<Vec<_>>();
+                assert_eq!(2, vertices.len());
+                // the second vertex has its own widths, so the constant width doesn't apply
+                assert_eq!(0.0, vertices[0].starting_width);
+                assert_eq!(Point::new(3.0, 4.0, 0.0), vertices[1].location);
+                assert_eq!(0.25, vertices[1].bulge);
+                assert_eq!(7, vertices[1].identifier);
+            }
+            _ => panic!("expected a polyline"),
+        }
+
+        let round_tripped = poly_entity.to_lw_polyline().unwrap();
+        assert_eq!(3.5, round_tripped.common.elevation);
+        match round_tripped.specific {
+            EntityType::LwPolyline(ref round_tripped) => assert_eq!(&lw, round_tripped),
+            _ => panic!("expected a lw polyline"),
+        }
+    }
+
+    #[test]
+    fn polyline_to_lw_polyline_round_trip_with_mixed_widths() {
+        let mut drawing = Drawing::new();
+        let mut poly = Polyline {
+            default_starting_width: 0.5,
+            default_ending_width: 0.5,
+            ..Default::default()
+        };
+        poly.add_vertex(&mut drawing, Vertex::new(Point::new(0.0, 0.0, 0.0)));
+        poly.add_vertex(
+            &mut drawing,
+            Vertex {
+                starting_width: 3.0,
+                ending_width: 1.0,
+                ..Vertex::new(Point::new(1.0, 0.0, 0.0))
+            },
+        );
+        poly.add_vertex(&mut drawing, Vertex::new(Point::new(2.0, 0.0, 0.0)));
+        let widths = |poly: &Polyline| {
+            poly.vertices()
+                .map(|v| (v.starting_width, v.ending_width))
+                .collect::<Vec<_>>()
+        };
+
+        // the vertices keep their own widths; the default is ignored
+        let lw = poly.to_lw_polyline().unwrap();
+        assert_eq!(0.5, lw.constant_width);
+        let lw_widths = lw
+            .vertices
+            .iter()
+            .map(|v| (v.starting_width, v.ending_width))
+            .collect::<Vec<_>>();
+        assert_eq!(widths(&poly), lw_widths);
+
+        let round_tripped = lw.to_polyline(0.0);
+        assert_eq!(0.5, round_tripped.default_starting_width);
+        assert_eq!(widths(&poly), widths(&round_tripped));
+    }
+
+    #[test]
+    fn polyline_default_widths_apply_without_vertex_widths() {
+        let mut drawing = Drawing::new();
+        let mut poly = Polyline {
+            default_starting_width: 1.5,
+            default_ending_width: 1.5,
+            ..Default::default()
+        };
+        poly.add_vertex(&mut drawing, Vertex::new(Point::new(0.0, 0.0, 0.0)));
+        poly.add_vertex(&mut drawing, Vertex::new(Point::new(1.0, 0.0, 0.0)));
+        let lw = poly.to_lw_polyline().unwrap();
+        assert_eq!(1.5, lw.constant_width);
+        assert!(lw
+            .vertices
+            .iter()
+            .all(|v| v.starting_width == 0.0 && v.ending_width == 0.0));
+
+        // tapering defaults can only be kept on each vertex
+        poly.default_ending_width = 2.5;
+        let lw = poly.to_lw_polyline().unwrap();
+        assert_eq!(0.0, lw.constant_width);
+        assert!(lw
+            .vertices
+            .iter()
+            .all(|v| v.starting_width == 1.5 && v.ending_width == 2.5));
+    }
+
+    #[test]
+    fn polyline_to_lw_polyline_limitations() {
+        let mut drawing = Drawing::new();
+
+        // planar 3D polylines can be downgraded
+        let mut poly = Polyline::default();
+        poly.set_is_3d_polyline(true);
+        poly.add_vertex(&mut drawing, Vertex::new(Point::new(1.0, 2.0, 5.0)));
+        poly.add_vertex(&mut drawing, Vertex::new(Point::new(3.0, 4.0, 5.0)));
+        let entity = Entity::new(EntityType::Polyline(poly.clone()));
+        let lw = entity.to_lw_polyline().unwrap();
+        assert_eq!(5.0, lw.common.elevation);
+
+        // but not when they leave the plane
+        poly.add_vertex(&mut drawing, Vertex::new(Point::new(3.0, 4.0, 6.0)));
+        assert_eq!(None, poly.to_lw_polyline());
+
+        let mut mesh = Polyline::default();
+        mesh.set_is_polyface_mesh(true);
+        assert_eq!(None, mesh.to_lw_polyline());
+
+        assert!(Entity::new(EntityType::Line(Default::default()))
+            .to_lw_polyline()
+            .is_none());
+    }
+
+    #[test]
+    fn write_lw_polyline_as_polyline_on_r12() {
+        let mut drawing = Drawing::new();
+        drawing.header.version = AcadVersion::R12;
+        let mut poly = LwPolyline::default();
+        poly.set_is_closed(true);
+        poly.vertices.push(LwPolylineVertex {
+            x: 1.1,
+            y: 2.1,
+            bulge: 0.5,
+            ..Default::default()
+        });
+        poly.vertices.push(LwPolylineVertex {
+            x: 1.2,
+            y: 2.2,
+            ..Default::default()
+        });
+        let mut entity = Entity::new(EntityType::LwPolyline(poly));
+        entity.common.elevation = 3.0;
+        drawing.add_entity(entity);
+        assert_contains_pairs(
+            &drawing,
+            vec![
+                CodePair::new_str(8, "0"),
+                CodePair::new_i16(66, 1),
+                CodePair::new_f64(10, 0.0),
+                CodePair::new_f64(20, 0.0),
+                CodePair::new_f64(30, 3.0), // elevation
+                CodePair::new_i16(70, 1),   // closed
+                CodePair::new_str(0, "VERTEX"),
+            ],
+        );
+        assert_contains_pairs(
+            &drawing,
+            vec![
+                CodePair::new_f64(10, 1.1),
+                CodePair::new_f64(20, 2.1),
+                CodePair::new_f64(30, 0.0),
+                CodePair::new_f64(42, 0.5),
+            ],
+        );
+        assert_contains_pairs(
+            &drawing,
+            vec![
+                CodePair::new_f64(10, 1.2),
+                CodePair::new_f64(20, 2.2),
+                CodePair::new_f64(30, 0.0),
+            ],
+        );
+        assert_contains_pairs(&drawing, vec![CodePair::new_str(0, "SEQEND")]);
+        assert_not_contains_pairs(&drawing, vec![CodePair::new_str(0, "LWPOLYLINE")]);
+
+        // the drawing itself is left untouched
+        assert_eq!(1, drawing.entities().count());
+        let entities = drawing.entities().collect::<Vec<_>>();
+        match entities[0].specific {
+            EntityType::LwPolyline(_) => (),
+            _ => panic!("expected a lw polyline"),
+        }
+    }
+
     #[test]
     fn read_dimension() {
         let ent = read_entity(
@@ -3316,4 +4377,317 @@ mod tests {
         assert_eq!("STANDARD", dim_styles[1].name);
         assert_eq!("style name", dim_styles[2].name);
     }
+
+    fn lw_vertex(x: f64, y: f64, bulge: f64) -> LwPolylineVertex {
+        LwPolylineVertex {
+            x,
+            y,
+            bulge,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn line_and_arc_length() {
+        let line = Line::new(Point::new(1.0, 1.0, 0.0), Point::new(4.0, 5.0, 0.0));
+        assert!(approx_eq!(f64, 5.0, line.length()));
+
+        // sweep wraps through zero degrees
+        let arc = Arc::new(Point::origin(), 2.0, 270.0, 90.0);
+        assert!(approx_eq!(f64, 180.0, arc.sweep_angle()));
+        assert!(approx_eq!(
+            f64,
+            2.0 * std::f64::consts::PI,
+            arc.length(),
+            epsilon = 1e-12
+        ));
+        assert!(!Entity::new(EntityType::Arc(arc)).is_closed());
+    }
+
+    #[test]
+    fn ellipses_with_huge_or_non_finite_parameters_are_bounded() {
+        for (start_parameter, end_parameter) in [
+            (1e20, 0.0),
+            (-1e12, 1e12),
+            (0.0, 1e300),
+            (f64::NAN, f64::INFINITY),
+        ] {
+            let ellipse = Ellipse {
+                major_axis: Vector::new(2.0, 0.0, 0.0),
+                minor_axis_ratio: 0.5,
+                start_parameter,
+                end_parameter,
+                ..Default::default()
+            };
+            // no longer than a full turn of the major axis' circle
+            assert!(ellipse.length() <= 2.0 * std::f64::consts::PI * 2.0);
+            let mut drawing = Drawing::new();
+            drawing.add_entity(Entity::new(EntityType::Ellipse(ellipse)));
+
+            // R12 has no ellipses, so they're written as polylines
+            drawing.header.version = AcadVersion::R12;
+            drawing.save(&mut vec![]).unwrap();
+            drawing.save_svg(&mut vec![], &Default::default()).unwrap();
+            let options = crate::RasterOptions {
+                width: 16,
+                height: 16,
+                ..Default::default()
+            };
+            drawing.save_png(&mut vec![], &options).unwrap();
+        }
+    }
+
+    #[test]
+    fn circle_and_ellipse_measurements() {
+        let circle = Entity::new(EntityType::Circle(Circle::new(
+            Point::new(1.0, 2.0, 0.0),
+            3.0,
+        )));
+        assert!(approx_eq!(
+            f64,
+            9.0 * std::f64::consts::PI,
+            circle.area().unwrap()
+        ));
+        assert_eq!(Some(Point::new(1.0, 2.0, 0.0)), circle.centroid());
+        assert_eq!(Some(WindingDirection::CounterClockwise), circle.winding());
+
+        let ellipse = Ellipse {
+            major_axis: Vector::new(2.0, 0.0, 0.0),
+            minor_axis_ratio: 0.5,
+            start_parameter: 0.0,
+            end_parameter: 2.0 * std::f64::consts::PI,
+            ..Default::default()
+        };
+        assert!(ellipse.is_closed());
+        assert!(approx_eq!(
+            f64,
+            2.0 * std::f64::consts::PI,
+            ellipse.area().unwrap(),
+            epsilon = 1e-12
+        ));
+        // Ramanujan's approximation is exact to many digits for this ratio
+        let (a, b) = (2.0f64, 1.0f64);
+        let h = ((a - b) / (a + b)).powi(2);
+        let expected =
+            std::f64::consts::PI * (a + b) * (1.0 + 3.0 * h / (10.0 + (4.0 - 3.0 * h).sqrt()));
+        assert!(approx_eq!(f64, expected, ellipse.length(), epsilon = 1e-6));
+
+        let half = Ellipse {
+            end_parameter: std::f64::consts::PI,
+            ..ellipse
+        };
+        assert!(!half.is_closed());
+        assert_eq!(None, half.area());
+        assert!(approx_eq!(
+            f64,
+            expected / 2.0,
+            half.length(),
+            epsilon = 1e-6
+        ));
+    }
+
+    #[test]
+    fn lw_polyline_square_measurements() {
+        let mut poly = LwPolyline {
+            vertices: vec![
+                lw_vertex(0.0, 0.0, 0.0),
+                lw_vertex(2.0, 0.0, 0.0),
+                lw_vertex(2.0, 2.0, 0.0),
+                lw_vertex(0.0, 2.0, 0.0),
+            ],
+            ..Default::default()
+        };
+        assert!(approx_eq!(f64, 6.0, poly.length()));
+        assert_eq!(None, poly.area());
+        assert_eq!(None, poly.winding());
+
+        poly.set_is_closed(true);
+        assert!(approx_eq!(f64, 8.0, poly.length()));
+        assert!(approx_eq!(f64, 4.0, poly.area().unwrap()));
+        assert_eq!(Some(Point::new(1.0, 1.0, 0.0)), poly.centroid());
+        assert_eq!(Some(WindingDirection::CounterClockwise), poly.winding());
+
+        poly.vertices.reverse();
+        assert_eq!(Some(WindingDirection::Clockwise), poly.winding());
+        assert!(approx_eq!(f64, 4.0, poly.area().unwrap()));
+    }
+
+    #[test]
+    fn lw_polyline_bulge_area_is_exact() {
+        // two semicircles make a full circle of radius 1
+        let poly = LwPolyline {
+            flags: 1,
+            vertices: vec![lw_vertex(1.0, 0.0, 1.0), lw_vertex(-1.0, 0.0, 1.0)],
+            ..Default::default()
+        };
+        assert!(approx_eq!(
+            f64,
+            std::f64::consts::PI,
+            poly.area().unwrap(),
+            epsilon = 1e-12
+        ));
+        assert!(approx_eq!(
+            f64,
+            2.0 * std::f64::consts::PI,
+            poly.length(),
+            epsilon = 1e-12
+        ));
+        let centroid = poly.centroid().unwrap();
+        assert!(approx_eq!(f64, 0.0, centroid.x, epsilon = 1e-12));
+        assert!(approx_eq!(f64, 0.0, centroid.y, epsilon = 1e-12));
+
+        // a square with one edge bulged inward; the semicircle is removed from the area
+        let poly = LwPolyline {
+            flags: 1,
+            vertices: vec![
+                lw_vertex(0.0, 0.0, -1.0),
+                lw_vertex(2.0, 0.0, 0.0),
+                lw_vertex(2.0, 2.0, 0.0),
+                lw_vertex(0.0, 2.0, 0.0),
+            ],
+            ..Default::default()
+        };
+        let expected_area = 4.0 - std::f64::consts::PI / 2.0;
+        assert!(approx_eq!(
+            f64,
+            expected_area,
+            poly.area().unwrap(),
+            epsilon = 1e-12
+        ));
+        // the removed half-disk has its centroid at (1, 4/(3pi))
+        let removed_moment = std::f64::consts::PI / 2.0 * (4.0 / (3.0 * std::f64::consts::PI));
+        let expected_y = (4.0 * 1.0 - removed_moment) / expected_area;
+        let centroid = poly.centroid().unwrap();
+        assert!(approx_eq!(f64, 1.0, centroid.x, epsilon = 1e-12));
+        assert!(approx_eq!(f64, expected_y, centroid.y, epsilon = 1e-12));
+    }
+
+    #[test]
+    fn lw_polyline_centroid_uses_elevation() {
+        let mut ent = Entity::new(EntityType::LwPolyline(LwPolyline {
+            flags: 1,
+            vertices: vec![
+                lw_vertex(0.0, 0.0, 0.0),
+                lw_vertex(1.0, 0.0, 0.0),
+                lw_vertex(1.0, 1.0, 0.0),
+            ],
+            ..Default::default()
+        }));
+        ent.common.elevation = 3.0;
+        assert_eq!(3.0, ent.centroid().unwrap().z);
+        assert!(ent.is_closed());
+    }
+
+    #[test]
+    fn polyline_measurements() {
+        let mut drawing = Drawing::new();
+        let mut poly = Polyline::default();
+        poly.add_vertex(&mut drawing, Vertex::new(Point::new(0.0, 0.0, 0.0)));
+        poly.add_vertex(&mut drawing, Vertex::new(Point::new(3.0, 0.0, 0.0)));
+        poly.add_vertex(&mut drawing, Vertex::new(Point::new(3.0, 3.0, 0.0)));
+        poly.add_vertex(&mut drawing, Vertex::new(Point::new(0.0, 0.0, 0.0)));
+        assert!(approx_eq!(
+            f64,
+            6.0 + 18.0f64.sqrt(),
+            poly.length().unwrap()
+        ));
+        // explicitly repeating the first vertex closes the shape
+        assert!(approx_eq!(f64, 4.5, poly.area().unwrap()));
+        assert_eq!(Some(WindingDirection::CounterClockwise), poly.winding());
+
+        let mut poly3d = Polyline::default();
+        poly3d.set_is_3d_polyline(true);
+        poly3d.set_is_closed(true);
+        poly3d.add_vertex(&mut drawing, Vertex::new(Point::new(0.0, 0.0, 0.0)));
+        poly3d.add_vertex(&mut drawing, Vertex::new(Point::new(0.0, 2.0, 0.0)));
+        poly3d.add_vertex(&mut drawing, Vertex::new(Point::new(0.0, 2.0, 2.0)));
+        poly3d.add_vertex(&mut drawing, Vertex::new(Point::new(0.0, 0.0, 2.0)));
+        assert!(approx_eq!(f64, 8.0, poly3d.length().unwrap()));
+        assert!(approx_eq!(f64, 4.0, poly3d.area().unwrap()));
+        assert_eq!(Some(Point::new(0.0, 1.0, 1.0)), poly3d.centroid());
+
+        let mut mesh = Polyline::default();
+        mesh.set_is_polyface_mesh(true);
+        assert_eq!(None, mesh.length());
+        assert_eq!(None, mesh.area());
+    }
+
+    #[test]
+    fn spline_fit_polyline_measurements_skip_the_frame() {
+        let mut drawing = Drawing::new();
+        let mut poly = Polyline::default();
+        poly.set_is_closed(true);
+        poly.set_spline_fit_vertices_added(true);
+        let mut frame = Vertex::new(Point::new(-5.0, -5.0, 0.0));
+        frame.set_is_spline_frame_control_point(true);
+        poly.add_vertex(&mut drawing, frame);
+        for (x, y) in [(0.0, 0.0), (2.0, 0.0), (2.0, 2.0), (0.0, 2.0)] {
+            let mut vertex = Vertex::new(Point::new(x, y, 0.0));
+            vertex.set_is_spline_vertex_created_by_spline_fitting(true);
+            poly.add_vertex(&mut drawing, vertex);
+        }
+        assert!(approx_eq!(f64, 8.0, poly.length().unwrap()));
+        assert!(approx_eq!(f64, 4.0, poly.area().unwrap()));
+    }
+
+    #[test]
+    fn solid_and_face_measurements() {
+        // solids store their corners in 'Z' order
+        let solid = Solid::new(
+            Point::new(0.0, 0.0, 0.0),
+            Point::new(2.0, 0.0, 0.0),
+            Point::new(0.0, 1.0, 0.0),
+            Point::new(2.0, 1.0, 0.0),
+        );
+        assert!(approx_eq!(f64, 2.0, solid.area()));
+        assert!(approx_eq!(f64, 6.0, solid.length()));
+        assert_eq!(Point::new(1.0, 0.5, 0.0), solid.centroid());
+        assert_eq!(Some(WindingDirection::CounterClockwise), solid.winding());
+
+        // triangular face
+        let face = Face3D::new(
+            Point::new(0.0, 0.0, 1.0),
+            Point::new(3.0, 0.0, 1.0),
+            Point::new(0.0, 3.0, 1.0),
+            Point::new(0.0, 3.0, 1.0),
+        );
+        assert_eq!(3, face.corners().len());
+        assert!(approx_eq!(f64, 4.5, face.area()));
+        assert_eq!(Point::new(1.0, 1.0, 1.0), face.centroid());
+    }
+
+    #[test]
+    fn spline_measurements() {
+        // a degree 1 spline is just its control polygon
+        let spline = Spline {
+            degree_of_curve: 1,
+            control_points: vec![
+                Point::new(0.0, 0.0, 0.0),
+                Point::new(1.0, 0.0, 0.0),
+                Point::new(1.0, 1.0, 0.0),
+                Point::new(0.0, 1.0, 0.0),
+                Point::new(0.0, 0.0, 0.0),
+            ],
+            ..Default::default()
+        };
+        assert!(approx_eq!(f64, 4.0, spline.length(), epsilon = 1e-9));
+        assert!(approx_eq!(f64, 1.0, spline.area().unwrap(), epsilon = 1e-9));
+        assert_eq!(Some(WindingDirection::CounterClockwise), spline.winding());
+
+        // quadratic bezier from (0,0) through control (1,2) to (2,0) peaks at (1,1)
+        let spline = Spline {
+            degree_of_curve: 2,
+            knot_values: vec![0.0, 0.0, 0.0, 1.0, 1.0, 1.0],
+            control_points: vec![
+                Point::new(0.0, 0.0, 0.0),
+                Point::new(1.0, 2.0, 0.0),
+                Point::new(2.0, 0.0, 0.0),
+            ],
+            ..Default::default()
+        };
+        let mid = spline.point_at(0.5);
+        assert!(approx_eq!(f64, 1.0, mid.x, epsilon = 1e-12));
+        assert!(approx_eq!(f64, 1.0, mid.y, epsilon = 1e-12));
+        assert_eq!(None, spline.area());
+    }
 }
@@ -5,11 +5,11 @@ use crate::DxfResult;
 
 use itertools::{put_back, PutBack};
 
-pub(crate) struct EntityIter<'a> {
-    pub iter: &'a mut CodePairPutBack,
+pub(crate) struct EntityIter<'a, 'b> {
+    pub iter: &'a mut CodePairPutBack<'b>,
 }
 
-impl Iterator for EntityIter<'_> {
+impl Iterator for EntityIter<'_, '_> {
     type Item = Entity;
 
     fn next(&mut self) -> Option<Entity> {
@@ -20,7 +20,7 @@ impl Iterator for EntityIter<'_> {
     }
 }
 
-impl EntityIter<'_> {
+impl EntityIter<'_, '_> {
     pub(crate) fn read_entities_into_vec(&mut self, entities: &mut Vec<Entity>) -> DxfResult<()> {
         collect_entities(self, entities)
     }
@@ -71,10 +71,9 @@ where
     loop {
         match iter.next() {
             Some(Entity {
-                ref common,
-                specific: EntityType::Attribute(ref att),
+                common,
+                specific: EntityType::Attribute(mut att),
             }) => {
-                let mut att = att.clone(); // 27 fields
                 match mtext(&mut iter) {
                     Ok(Some(m_text)) => att.m_text = m_text,
                     Ok(None) => (),
@@ -82,15 +81,14 @@ where
                 }
 
                 entities.push(Entity {
-                    common: common.clone(), // 18 fields
+                    common,
                     specific: EntityType::Attribute(att),
                 });
             }
             Some(Entity {
-                ref common,
-                specific: EntityType::AttributeDefinition(ref att),
+                common,
+                specific: EntityType::AttributeDefinition(mut att),
             }) => {
-                let mut att = att.clone(); // 27 fields
                 match mtext(&mut iter) {
                     Ok(Some(m_text)) => att.m_text = m_text,
                     Ok(None) => (),
@@ -98,15 +96,14 @@ where
                 }
 
                 entities.push(Entity {
-                    common: common.clone(), // 18 fields
+                    common,
                     specific: EntityType::AttributeDefinition(att),
                 });
             }
             Some(Entity {
-                ref common,
-                specific: EntityType::Insert(ref ins),
+                common,
+                specific: EntityType::Insert(mut ins),
             }) if ins.__has_attributes => {
-                let mut ins = ins.clone(); // 12 fields
                 loop {
                     match iter.next() {
                         Some(Entity {
@@ -128,15 +125,14 @@ where
 
                 // and finally keep the INSERT
                 entities.push(Entity {
-                    common: common.clone(), // 18 fields
+                    common,
                     specific: EntityType::Insert(ins),
                 });
             }
             Some(Entity {
                 common,
-                specific: EntityType::Polyline(poly),
+                specific: EntityType::Polyline(mut poly),
             }) => {
-                let mut poly = poly.clone(); // 13 fields
                 loop {
                     match iter.next() {
                         Some(Entity {
@@ -158,7 +154,7 @@ where
 
                 // and finally keep the POLYLINE
                 entities.push(Entity {
-                    common: common.clone(), // 18 fields
+                    common,
                     specific: EntityType::Polyline(poly),
                 });
             }
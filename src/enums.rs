@@ -1393,6 +1393,14 @@ pub enum VerticalTextJustification {
 }
 }
 
+/// The direction in which the vertices of a closed shape are traversed.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub enum WindingDirection {
+    Clockwise,
+    CounterClockwise,
+}
+
 enum_from_primitive! {
 #[derive(Clone, Copy, Debug, PartialEq)]
 #[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
@@ -1,8 +1,12 @@
-use crate::{CodePair, DxfError, DxfResult};
+use crate::{CodePair, DxfError, DxfResult, Handle};
 
 use crate::code_pair_put_back::CodePairPutBack;
 
 pub(crate) const EXTENSION_DATA_GROUP: i32 = 102;
+const EXTENSION_DICTIONARY_GROUP: &str = "ACAD_XDICTIONARY";
+const REACTORS_GROUP: &str = "ACAD_REACTORS";
+const EXTENSION_DICTIONARY_CODE: i32 = 360;
+const REACTOR_CODE: i32 = 330;
 
 /// Represents an application name and a collection of extension group data in the form of `CodePair`s.
 #[derive(Clone, Debug, PartialEq)]
@@ -79,3 +83,81 @@ impl ExtensionGroup {
         }
     }
 }
+
+/// Returns the handle of the extension dictionary from an `{ACAD_XDICTIONARY` group.
+pub(crate) fn extension_dictionary_handle(groups: &[ExtensionGroup]) -> Option<Handle> {
+    group_handles(
+        groups,
+        EXTENSION_DICTIONARY_GROUP,
+        EXTENSION_DICTIONARY_CODE,
+    )
+    .into_iter()
+    .next()
+}
+
+/// Replaces the `{ACAD_XDICTIONARY` group with one that refers to `handle`.
+pub(crate) fn set_extension_dictionary_handle(groups: &mut Vec<ExtensionGroup>, handle: Handle) {
+    groups.retain(|g| g.application_name != EXTENSION_DICTIONARY_GROUP);
+    groups.push(ExtensionGroup {
+        application_name: String::from(EXTENSION_DICTIONARY_GROUP),
+        items: vec![ExtensionGroupItem::CodePair(CodePair::new_string(
+            EXTENSION_DICTIONARY_CODE,
+            handle.as_string(),
+        ))],
+    });
+}
+
+/// Returns the handles of the `{ACAD_REACTORS` group, i.e., the items that are notified of changes.
+pub(crate) fn reactor_handles(groups: &[ExtensionGroup]) -> Vec<Handle> {
+    group_handles(groups, REACTORS_GROUP, REACTOR_CODE)
+}
+
+pub(crate) fn add_reactor(groups: &mut Vec<ExtensionGroup>, handle: Handle) {
+    if reactor_handles(groups).contains(&handle) {
+        return;
+    }
+
+    let pair = ExtensionGroupItem::CodePair(CodePair::new_string(REACTOR_CODE, handle.as_string()));
+    match groups
+        .iter_mut()
+        .find(|g| g.application_name == REACTORS_GROUP)
+    {
+        Some(group) => group.items.push(pair),
+        None => groups.insert(
+            0,
+            ExtensionGroup {
+                application_name: String::from(REACTORS_GROUP),
+                items: vec![pair],
+            },
+        ),
+    }
+}
+
+pub(crate) fn remove_reactor(groups: &mut Vec<ExtensionGroup>, handle: Handle) {
+    for group in groups
+        .iter_mut()
+        .filter(|g| g.application_name == REACTORS_GROUP)
+    {
+        group.items.retain(|item| match item {
+            ExtensionGroupItem::CodePair(pair) if pair.code == REACTOR_CODE => {
+                pair.as_handle().ok() != Some(handle)
+            }
+            _ => true,
+        });
+    }
+
+    // an empty group isn't written anyway
+    groups.retain(|g| g.application_name != REACTORS_GROUP || !g.items.is_empty());
+}
+
+fn group_handles(groups: &[ExtensionGroup], application_name: &str, code: i32) -> Vec<Handle> {
+    groups
+        .iter()
+        .filter(|g| g.application_name == application_name)
+        .flat_map(|g| g.items.iter())
+        .filter_map(|item| match item {
+            ExtensionGroupItem::CodePair(pair) if pair.code == code => pair.as_handle().ok(),
+            _ => None,
+        })
+        .collect()
+}
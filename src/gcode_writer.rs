@@ -0,0 +1,563 @@
+// writes 2D profiles of a drawing as G-code toolpaths
+
+use std::f64::consts::PI;
+use std::io::Write;
+
+use crate::entities::*;
+use crate::enums::Units;
+use crate::geometry::*;
+use crate::render::*;
+use crate::{Drawing, DxfResult, Point};
+
+/// The number of decimal places written for coordinates, feeds and speeds.
+const DECIMAL_PLACES: i32 = 4;
+
+/// Options used when exporting a `Drawing` as G-code.
+#[derive(Clone, Debug, PartialEq)]
+pub struct GcodeOptions {
+    /// The layers to cut, or all layers if empty.
+    pub layers: Vec<String>,
+    /// The feed rate used while cutting, in machine units per minute.
+    pub feed_rate: f64,
+    /// The feed rate used while plunging into the material, in machine units per minute.
+    pub plunge_rate: f64,
+    /// The spindle speed, or `None` to leave the spindle alone.
+    pub spindle_speed: Option<f64>,
+    /// The height above the work the tool moves at between contours, in machine units.
+    pub safe_height: f64,
+    /// The total depth of each cut, in machine units.
+    pub cut_depth: f64,
+    /// The deepest a single pass cuts, in machine units, or zero to cut the full depth at once.
+    pub depth_per_pass: f64,
+    /// The distance, in drawing units, within which the ends of entities are joined into one
+    /// contour.
+    pub tolerance: f64,
+}
+
+impl Default for GcodeOptions {
+    fn default() -> Self {
+        GcodeOptions {
+            layers: vec![],
+            feed_rate: 600.0,
+            plunge_rate: 200.0,
+            spindle_speed: None,
+            safe_height: 5.0,
+            cut_depth: 1.0,
+            depth_per_pass: 0.0,
+            tolerance: 1e-6,
+        }
+    }
+}
+
+/// A straight or circular move in the XY plane.
+#[derive(Clone, Debug)]
+enum Segment {
+    Line(Point, Point),
+    Arc {
+        start: Point,
+        end: Point,
+        center: Point,
+        is_clockwise: bool,
+    },
+}
+
+impl Segment {
+    fn start(&self) -> &Point {
+        match self {
+            Segment::Line(start, _) | Segment::Arc { start, .. } => start,
+        }
+    }
+    fn end(&self) -> &Point {
+        match self {
+            Segment::Line(_, end) | Segment::Arc { end, .. } => end,
+        }
+    }
+    fn reversed(&self) -> Segment {
+        match self {
+            Segment::Line(start, end) => Segment::Line(end.clone(), start.clone()),
+            Segment::Arc {
+                start,
+                end,
+                center,
+                is_clockwise,
+            } => Segment::Arc {
+                start: end.clone(),
+                end: start.clone(),
+                center: center.clone(),
+                is_clockwise: !is_clockwise,
+            },
+        }
+    }
+}
+
+/// A continuous run of segments.
+struct Contour {
+    segments: Vec<Segment>,
+}
+
+impl Contour {
+    fn start(&self) -> &Point {
+        self.segments[0].start()
+    }
+    fn end(&self) -> &Point {
+        self.segments[self.segments.len() - 1].end()
+    }
+    fn reverse(&mut self) {
+        self.segments.reverse();
+        for segment in &mut self.segments {
+            *segment = segment.reversed();
+        }
+    }
+}
+
+pub(crate) struct GcodeWriter<'a> {
+    options: &'a GcodeOptions,
+    /// `G20` or `G21`.
+    units_code: &'static str,
+    /// The factor that converts drawing units to machine units.
+    scale: f64,
+    contours: Vec<Contour>,
+}
+
+impl<'a> GcodeWriter<'a> {
+    pub fn new(drawing: &Drawing, options: &'a GcodeOptions) -> Self {
+        // imperial drawings are cut in inches and everything else in millimeters
+        let units = drawing.header.default_drawing_units;
+        let (units_code, scale) = match units {
+            Units::Inches
+            | Units::Feet
+            | Units::Miles
+            | Units::Microinches
+            | Units::Mils
+            | Units::Yards
+            | Units::USSurveyFeet
+            | Units::USSurveyInch
+            | Units::USSurveyYard
+            | Units::USSurveyMile => ("G20", millimeters_per_unit(units) / 25.4),
+            _ => ("G21", millimeters_per_unit(units)),
+        };
+
+        let mut pieces = vec![];
+        for entity in drawing.entities() {
+            let is_selected = options.layers.is_empty()
+                || options
+                    .layers
+                    .iter()
+                    .any(|l| l.eq_ignore_ascii_case(&entity.common.layer));
+            if is_selected && !entity.common.is_in_paper_space && entity.common.is_visible {
+                pieces.extend(entity_contours(entity));
+            }
+        }
+        let mut contours = chain(pieces, options.tolerance);
+        order(&mut contours, options.tolerance);
+        GcodeWriter {
+            options,
+            units_code,
+            scale,
+            contours,
+        }
+    }
+    pub fn write<T>(&self, writer: &mut T) -> DxfResult<()>
+    where
+        T: Write + ?Sized,
+    {
+        let options = self.options;
+        let xy = |p: &Point| {
+            format!(
+                "X{} Y{}",
+                num(p.x * self.scale, DECIMAL_PLACES),
+                num(p.y * self.scale, DECIMAL_PLACES)
+            )
+        };
+        writeln!(writer, "(toolpath exported from a DXF drawing)")?;
+        writeln!(writer, "{}", self.units_code)?;
+        writeln!(writer, "G90")?;
+        writeln!(writer, "G17")?;
+        if let Some(speed) = options.spindle_speed {
+            writeln!(writer, "M3 S{}", num(speed, DECIMAL_PLACES))?;
+        }
+        writeln!(writer, "G0 Z{}", num(options.safe_height, DECIMAL_PLACES))?;
+
+        let depths = pass_depths(options.cut_depth, options.depth_per_pass);
+        for contour in &self.contours {
+            let is_closed = points_within(contour.start(), contour.end(), options.tolerance);
+            writeln!(writer, "G0 {}", xy(contour.start()))?;
+            for (pass, depth) in depths.iter().enumerate() {
+                writeln!(
+                    writer,
+                    "G1 Z{} F{}",
+                    num(-depth, DECIMAL_PLACES),
+                    num(options.plunge_rate, DECIMAL_PLACES)
+                )?;
+                // open contours are cut back and forth to avoid returning to the start
+                let is_reversed = !is_closed && pass % 2 == 1;
+                let segments: Box<dyn Iterator<Item = Segment>> = if is_reversed {
+                    Box::new(contour.segments.iter().rev().map(|s| s.reversed()))
+                } else {
+                    Box::new(contour.segments.iter().cloned())
+                };
+                for (i, segment) in segments.enumerate() {
+                    let feed = if i == 0 {
+                        format!(" F{}", num(options.feed_rate, DECIMAL_PLACES))
+                    } else {
+                        String::new()
+                    };
+                    match segment {
+                        Segment::Line(_, ref end) => writeln!(writer, "G1 {}{}", xy(end), feed)?,
+                        Segment::Arc {
+                            ref start,
+                            ref end,
+                            ref center,
+                            is_clockwise,
+                        } => writeln!(
+                            writer,
+                            "{} {} I{} J{}{}",
+                            if is_clockwise { "G2" } else { "G3" },
+                            xy(end),
+                            num((center.x - start.x) * self.scale, DECIMAL_PLACES),
+                            num((center.y - start.y) * self.scale, DECIMAL_PLACES),
+                            feed
+                        )?,
+                    }
+                }
+            }
+            writeln!(writer, "G0 Z{}", num(options.safe_height, DECIMAL_PLACES))?;
+        }
+
+        if options.spindle_speed.is_some() {
+            writeln!(writer, "M5")?;
+        }
+        writeln!(writer, "M30")?;
+        Ok(())
+    }
+}
+
+/// Returns the depth of each pass needed to reach `cut_depth`.
+fn pass_depths(cut_depth: f64, depth_per_pass: f64) -> Vec<f64> {
+    if depth_per_pass <= 0.0 || depth_per_pass >= cut_depth {
+        return vec![cut_depth];
+    }
+    let count = (cut_depth / depth_per_pass - EPSILON).ceil() as usize;
+    (1..=count)
+        .map(|i| (depth_per_pass * i as f64).min(cut_depth))
+        .collect()
+}
+
+/// Returns the moves that trace an entity, flattened onto the XY plane.  Lines, arcs, circles and
+/// polyline bulges in the XY plane keep their arcs; everything else is approximated by lines.
+fn entity_contours(entity: &Entity) -> Vec<Contour> {
+    let arc = |center: &Point, radius: f64, start: f64, end: f64, normal| {
+        let (start_point, end_point) = (
+            circle_point(center, radius, start, normal),
+            circle_point(center, radius, end, normal),
+        );
+        Segment::Arc {
+            start: flatten(&start_point),
+            end: flatten(&end_point),
+            center: flatten(&ocs_to_wcs(center, normal)),
+            // arcs are counter-clockwise about their normal, which is clockwise when seen from above
+            // if the normal points down
+            is_clockwise: normal.z < 0.0,
+        }
+    };
+    let segments = match entity.specific {
+        EntityType::Arc(ref a) if is_planar(&a.normal) => {
+            let start = a.start_angle.to_radians();
+            let end = start + sweep_degrees(a.start_angle, a.end_angle).to_radians();
+            vec![arc(&a.center, a.radius, start, end, &a.normal)]
+        }
+        EntityType::Circle(ref c) if is_planar(&c.normal) => {
+            // full circles are split in half since many controllers can't cut them in one move
+            vec![
+                arc(&c.center, c.radius, 0.0, PI, &c.normal),
+                arc(&c.center, c.radius, PI, 2.0 * PI, &c.normal),
+            ]
+        }
+        EntityType::LwPolyline(ref poly) if is_planar(&poly.extrusion_direction) => {
+            let normal = &poly.extrusion_direction;
+            let elevation = entity.common.elevation;
+            let count = poly.vertices.len();
+            let segment_count = match count {
+                0 | 1 => 0,
+                _ if poly.is_closed() => count,
+                _ => count - 1,
+            };
+            let world = |x: f64, y: f64| flatten(&ocs_to_wcs(&Point::new(x, y, elevation), normal));
+            (0..segment_count)
+                .map(|i| {
+                    let v1 = &poly.vertices[i];
+                    let v2 = &poly.vertices[(i + 1) % count];
+                    let start = world(v1.x, v1.y);
+                    let end = world(v2.x, v2.y);
+                    match bulge_to_arc(v1.x, v1.y, v2.x, v2.y, v1.bulge) {
+                        Some(a) => Segment::Arc {
+                            start,
+                            end,
+                            center: world(a.center_x, a.center_y),
+                            is_clockwise: (a.included_angle < 0.0) != (normal.z < 0.0),
+                        },
+                        None => Segment::Line(start, end),
+                    }
+                })
+                .collect()
+        }
+        _ => {
+            return curve_paths(entity, SPLINE_SEGMENTS_PER_SPAN)
+                .into_iter()
+                .map(|(mut points, is_closed)| {
+                    if is_closed {
+                        points.push(points[0].clone());
+                    }
+                    let points = points.iter().map(flatten).collect::<Vec<_>>();
+                    Contour {
+                        segments: points
+                            .windows(2)
+                            .map(|w| Segment::Line(w[0].clone(), w[1].clone()))
+                            .collect(),
+                    }
+                })
+                .filter(|c| !c.segments.is_empty())
+                .collect();
+        }
+    };
+    if segments.is_empty() {
+        vec![]
+    } else {
+        vec![Contour { segments }]
+    }
+}
+
+fn flatten(p: &Point) -> Point {
+    Point::new(p.x, p.y, 0.0)
+}
+
+fn points_within(a: &Point, b: &Point, tolerance: f64) -> bool {
+    distance(a, b) <= tolerance
+}
+
+/// Joins pieces whose ends meet into continuous contours, reversing pieces as needed.
+fn chain(mut pieces: Vec<Contour>, tolerance: f64) -> Vec<Contour> {
+    let mut contours = vec![];
+    while !pieces.is_empty() {
+        let mut contour = pieces.remove(0);
+        loop {
+            if points_within(contour.start(), contour.end(), tolerance) {
+                break;
+            }
+            let next = pieces.iter().position(|p| {
+                points_within(contour.end(), p.start(), tolerance)
+                    || points_within(contour.end(), p.end(), tolerance)
+            });
+            if let Some(index) = next {
+                let mut piece = pieces.remove(index);
+                if !points_within(contour.end(), piece.start(), tolerance) {
+                    piece.reverse();
+                }
+                contour.segments.extend(piece.segments);
+                continue;
+            }
+            let previous = pieces.iter().position(|p| {
+                points_within(contour.start(), p.end(), tolerance)
+                    || points_within(contour.start(), p.start(), tolerance)
+            });
+            match previous {
+                Some(index) => {
+                    let mut piece = pieces.remove(index);
+                    if !points_within(contour.start(), piece.end(), tolerance) {
+                        piece.reverse();
+                    }
+                    piece.segments.extend(contour.segments);
+                    contour = piece;
+                }
+                None => break,
+            }
+        }
+        contours.push(contour);
+    }
+    contours
+}
+
+/// Orders contours so each starts as close as possible to where the previous one ended, starting
+/// from the origin.  Open contours may be reversed and closed contours may start at any vertex.
+fn order(contours: &mut Vec<Contour>, tolerance: f64) {
+    let mut remaining = std::mem::take(contours);
+    let mut position = Point::origin();
+    while !remaining.is_empty() {
+        // the contour, segment to start at, and whether to reverse it
+        let mut best = (0, 0, false);
+        let mut best_distance = f64::INFINITY;
+        for (i, contour) in remaining.iter().enumerate() {
+            if points_within(contour.start(), contour.end(), tolerance) {
+                for (j, segment) in contour.segments.iter().enumerate() {
+                    let d = distance(&position, segment.start());
+                    if d < best_distance {
+                        best = (i, j, false);
+                        best_distance = d;
+                    }
+                }
+            } else {
+                for (d, is_reversed) in [
+                    (distance(&position, contour.start()), false),
+                    (distance(&position, contour.end()), true),
+                ] {
+                    if d < best_distance {
+                        best = (i, 0, is_reversed);
+                        best_distance = d;
+                    }
+                }
+            }
+        }
+        let (index, start, is_reversed) = best;
+        let mut contour = remaining.remove(index);
+        contour.segments.rotate_left(start);
+        if is_reversed {
+            contour.reverse();
+        }
+        position = contour.end().clone();
+        contours.push(contour);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::enums::Units;
+    use crate::gcode_writer::*;
+    use crate::*;
+
+    fn to_gcode(drawing: &Drawing, options: &GcodeOptions) -> Vec<String> {
+        let mut buf = vec![];
+        drawing.save_gcode(&mut buf, options).unwrap();
+        String::from_utf8(buf)
+            .unwrap()
+            .lines()
+            .map(String::from)
+            .collect()
+    }
+
+    fn line(x1: f64, y1: f64, x2: f64, y2: f64) -> Entity {
+        Entity::new(EntityType::Line(Line::new(
+            Point::new(x1, y1, 0.0),
+            Point::new(x2, y2, 0.0),
+        )))
+    }
+
+    fn moves<'a>(gcode: &'a [String], prefix: &str) -> Vec<&'a str> {
+        gcode
+            .iter()
+            .filter(|l| l.starts_with(prefix))
+            .map(|l| l.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn units_header() {
+        let mut drawing = Drawing::new();
+        drawing.header.default_drawing_units = Units::Millimeters;
+        assert!(to_gcode(&drawing, &GcodeOptions::default()).contains(&String::from("G21")));
+        drawing.header.default_drawing_units = Units::Inches;
+        assert!(to_gcode(&drawing, &GcodeOptions::default()).contains(&String::from("G20")));
+
+        // centimeters are converted to millimeters
+        drawing.header.default_drawing_units = Units::Centimeters;
+        drawing.add_entity(line(1.0, 0.0, 2.0, 0.0));
+        let gcode = to_gcode(&drawing, &GcodeOptions::default());
+        assert!(gcode.contains(&String::from("G21")));
+        assert_eq!(vec!["G0 X10 Y0"], moves(&gcode, "G0 X"));
+        assert_eq!(vec!["G1 X20 Y0 F600"], moves(&gcode, "G1 X"));
+    }
+
+    #[test]
+    fn entities_are_chained_into_contours() {
+        let mut drawing = Drawing::new();
+        // a square drawn as lines in no particular order or direction
+        drawing.add_entity(line(10.0, 0.0, 10.0, 10.0));
+        drawing.add_entity(line(0.0, 0.0, 10.0, 0.0));
+        drawing.add_entity(line(0.0, 10.0, 10.0, 10.0));
+        drawing.add_entity(line(0.0, 10.0, 0.0, 0.0));
+        let gcode = to_gcode(&drawing, &GcodeOptions::default());
+        assert_eq!(vec!["G0 X0 Y0"], moves(&gcode, "G0 X"));
+        assert_eq!(4, moves(&gcode, "G1 X").len());
+        assert_eq!("G1 X0 Y0", *moves(&gcode, "G1 X").last().unwrap());
+    }
+
+    #[test]
+    fn contours_are_ordered_by_distance() {
+        let mut drawing = Drawing::new();
+        drawing.add_entity(line(100.0, 0.0, 110.0, 0.0));
+        drawing.add_entity(line(20.0, 0.0, 10.0, 0.0));
+        drawing.add_entity(line(50.0, 0.0, 40.0, 0.0));
+        let gcode = to_gcode(&drawing, &GcodeOptions::default());
+        assert_eq!(
+            vec!["G0 X10 Y0", "G0 X40 Y0", "G0 X100 Y0"],
+            moves(&gcode, "G0 X")
+        );
+    }
+
+    #[test]
+    fn arcs_and_bulges() {
+        let mut drawing = Drawing::new();
+        drawing.add_entity(Entity::new(EntityType::Circle(Circle::new(
+            Point::new(10.0, 0.0, 0.0),
+            5.0,
+        ))));
+        let mut poly = LwPolyline::default();
+        poly.vertices.push(LwPolylineVertex {
+            x: 30.0,
+            y: 0.0,
+            bulge: -1.0,
+            ..Default::default()
+        });
+        poly.vertices.push(LwPolylineVertex {
+            x: 40.0,
+            y: 0.0,
+            ..Default::default()
+        });
+        drawing.add_entity(Entity::new(EntityType::LwPolyline(poly)));
+        let gcode = to_gcode(&drawing, &GcodeOptions::default());
+        assert_eq!(
+            vec!["G3 X15 Y0 I5 J0 F600", "G3 X5 Y0 I-5 J0"],
+            moves(&gcode, "G3 ")
+        );
+        assert_eq!(vec!["G2 X40 Y0 I5 J0 F600"], moves(&gcode, "G2 "));
+    }
+
+    #[test]
+    fn depth_passes() {
+        assert_eq!(vec![1.0], pass_depths(1.0, 0.0));
+        assert_eq!(vec![1.0, 2.0, 2.5], pass_depths(2.5, 1.0));
+        assert_eq!(vec![1.0, 2.0], pass_depths(2.0, 1.0));
+
+        let mut drawing = Drawing::new();
+        drawing.add_entity(line(0.0, 0.0, 10.0, 0.0));
+        let options = GcodeOptions {
+            cut_depth: 2.0,
+            depth_per_pass: 1.0,
+            spindle_speed: Some(12000.0),
+            ..Default::default()
+        };
+        let gcode = to_gcode(&drawing, &options);
+        assert_eq!(vec!["G1 Z-1 F200", "G1 Z-2 F200"], moves(&gcode, "G1 Z"));
+        // the second pass of an open contour runs back to the start
+        assert_eq!(
+            vec!["G1 X10 Y0 F600", "G1 X0 Y0 F600"],
+            moves(&gcode, "G1 X")
+        );
+        assert_eq!("M3 S12000", moves(&gcode, "M3")[0]);
+        assert_eq!(vec!["M5", "M30"], gcode[gcode.len() - 2..].to_vec());
+    }
+
+    #[test]
+    fn selected_layers() {
+        let mut drawing = Drawing::new();
+        let mut cut = line(0.0, 0.0, 1.0, 0.0);
+        cut.common.layer = String::from("Cut");
+        drawing.add_entity(cut);
+        drawing.add_entity(line(5.0, 0.0, 6.0, 0.0));
+        let options = GcodeOptions {
+            layers: vec![String::from("CUT")],
+            ..Default::default()
+        };
+        let gcode = to_gcode(&drawing, &options);
+        assert_eq!(vec!["G1 X1 Y0 F600"], moves(&gcode, "G1 X"));
+    }
+}
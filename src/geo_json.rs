@@ -0,0 +1,1065 @@
+// converts drawings to and from GeoJSON, located with the drawing's GeoData
+
+use std::f64::consts::PI;
+use std::io::{Read, Write};
+
+use crate::entities::*;
+use crate::enums::*;
+use crate::geometry::*;
+use crate::json::JsonValue;
+use crate::objects::*;
+use crate::render::*;
+use crate::{
+    Color, Drawing, DxfError, DxfResult, GeoMeshPoint, Handle, LwPolylineVertex, Point, Vector,
+    XData, XDataItem,
+};
+
+/// The semi-major axis of the WGS84 ellipsoid, in meters.
+const SEMI_MAJOR_AXIS: f64 = 6_378_137.0;
+
+/// The flattening of the WGS84 ellipsoid.
+const FLATTENING: f64 = 1.0 / 298.257_223_563;
+
+/// Converts between drawing coordinates and WGS84 longitude, latitude and altitude.
+pub(crate) enum GeoReference {
+    Local(LocalFrame),
+    Mesh(MeshFrame),
+}
+
+impl GeoReference {
+    /// Creates the conversion from the drawing's `GeoData`.  Geographic coordinate systems are
+    /// mapped through a plane tangent to the ellipsoid at the reference point; other coordinate
+    /// systems need a mesh relating design points to geographic points.
+    pub fn from_drawing(drawing: &Drawing) -> DxfResult<GeoReference> {
+        let geo = drawing
+            .objects()
+            .find_map(|o| match o.specific {
+                ObjectType::GeoData(ref geo) => Some(geo),
+                _ => None,
+            })
+            .ok_or(DxfError::MissingGeoReference)?;
+        if let Some(mesh) = MeshFrame::new(&geo.geo_mesh_points, &geo.face_indices) {
+            Ok(GeoReference::Mesh(mesh))
+        } else if geo.coordinate_type == DesignCoordinateType::Geographic {
+            Ok(GeoReference::Local(LocalFrame::new(geo)))
+        } else {
+            Err(DxfError::MissingGeoReference)
+        }
+    }
+    /// Returns the longitude and latitude in degrees, and the altitude in meters, of a point.
+    pub fn to_wgs84(&self, p: &Point) -> Point {
+        match self {
+            GeoReference::Local(frame) => frame.to_wgs84(p),
+            GeoReference::Mesh(mesh) => mesh.map(p, true),
+        }
+    }
+    pub fn to_design(&self, p: &Point) -> Point {
+        match self {
+            GeoReference::Local(frame) => frame.to_design(p),
+            GeoReference::Mesh(mesh) => mesh.map(p, false),
+        }
+    }
+    /// The altitude used for positions that don't specify one.
+    fn default_altitude(&self) -> f64 {
+        match self {
+            GeoReference::Local(frame) => frame.reference.z,
+            GeoReference::Mesh(_) => 0.0,
+        }
+    }
+}
+
+pub(crate) struct LocalFrame {
+    design_point: Point,
+    /// The longitude, latitude and elevation of the design point.
+    reference: Point,
+    meters_per_unit: f64,
+    vertical_meters_per_unit: f64,
+    /// The unit direction of north in the drawing.
+    north: (f64, f64),
+    meters_per_degree_longitude: f64,
+    meters_per_degree_latitude: f64,
+}
+
+impl LocalFrame {
+    fn new(geo: &GeoData) -> Self {
+        let north_length = geo.north_vector.x.hypot(geo.north_vector.y);
+        let north = if north_length > EPSILON {
+            (
+                geo.north_vector.x / north_length,
+                geo.north_vector.y / north_length,
+            )
+        } else {
+            (0.0, 1.0)
+        };
+        let positive_or_one = |value: f64| if value > 0.0 { value } else { 1.0 };
+
+        // the radii of curvature of the ellipsoid at the reference latitude
+        let latitude = geo.reference_point.y.to_radians();
+        let eccentricity_squared = FLATTENING * (2.0 - FLATTENING);
+        let w = (1.0 - eccentricity_squared * latitude.sin().powi(2)).sqrt();
+        let meridian_radius = SEMI_MAJOR_AXIS * (1.0 - eccentricity_squared) / w.powi(3);
+        let normal_radius = SEMI_MAJOR_AXIS / w;
+        LocalFrame {
+            design_point: geo.design_point.clone(),
+            reference: geo.reference_point.clone(),
+            meters_per_unit: positive_or_one(geo.horizontal_unit_scale),
+            vertical_meters_per_unit: positive_or_one(geo.vertical_unit_scale),
+            north,
+            meters_per_degree_longitude: normal_radius * latitude.cos() * PI / 180.0,
+            meters_per_degree_latitude: meridian_radius * PI / 180.0,
+        }
+    }
+    fn to_wgs84(&self, p: &Point) -> Point {
+        let (nx, ny) = self.north;
+        let dx = p.x - self.design_point.x;
+        let dy = p.y - self.design_point.y;
+        let north = (dx * nx + dy * ny) * self.meters_per_unit;
+        let east = (dx * ny - dy * nx) * self.meters_per_unit;
+        Point::new(
+            self.reference.x + east / self.meters_per_degree_longitude,
+            self.reference.y + north / self.meters_per_degree_latitude,
+            self.reference.z + (p.z - self.design_point.z) * self.vertical_meters_per_unit,
+        )
+    }
+    fn to_design(&self, p: &Point) -> Point {
+        let (nx, ny) = self.north;
+        let east =
+            (p.x - self.reference.x) * self.meters_per_degree_longitude / self.meters_per_unit;
+        let north =
+            (p.y - self.reference.y) * self.meters_per_degree_latitude / self.meters_per_unit;
+        Point::new(
+            self.design_point.x + north * nx + east * ny,
+            self.design_point.y + north * ny - east * nx,
+            self.design_point.z + (p.z - self.reference.z) / self.vertical_meters_per_unit,
+        )
+    }
+}
+
+/// An affine transform mapping `(x, y)` to `(a x + b y + c, d x + e y + f)`.
+#[derive(Clone, Copy)]
+struct Affine([f64; 6]);
+
+impl Affine {
+    /// Fits a transform to the point pairs by least squares, or returns `None` if the points are
+    /// collinear.
+    fn fit(pairs: &[(&Point, &Point)]) -> Option<Affine> {
+        // normal equations for each output coordinate share the same matrix
+        let mut m = [[0.0; 3]; 3];
+        let mut rx = [0.0; 3];
+        let mut ry = [0.0; 3];
+        for (from, to) in pairs {
+            let row = [from.x, from.y, 1.0];
+            for i in 0..3 {
+                for j in 0..3 {
+                    m[i][j] += row[i] * row[j];
+                }
+                rx[i] += row[i] * to.x;
+                ry[i] += row[i] * to.y;
+            }
+        }
+        let determinant = determinant_3(&m);
+        if determinant.abs() < EPSILON {
+            return None;
+        }
+        // Cramer's rule
+        let solve = |r: &[f64; 3]| {
+            let mut result = [0.0; 3];
+            for (column, value) in result.iter_mut().enumerate() {
+                let mut replaced = m;
+                for row in 0..3 {
+                    replaced[row][column] = r[row];
+                }
+                *value = determinant_3(&replaced) / determinant;
+            }
+            result
+        };
+        let [a, b, c] = solve(&rx);
+        let [d, e, f] = solve(&ry);
+        Some(Affine([a, b, c, d, e, f]))
+    }
+    fn apply(&self, p: &Point) -> Point {
+        let [a, b, c, d, e, f] = self.0;
+        Point::new(a * p.x + b * p.y + c, d * p.x + e * p.y + f, p.z)
+    }
+}
+
+fn determinant_3(m: &[[f64; 3]; 3]) -> f64 {
+    m[0][0] * (m[1][1] * m[2][2] - m[1][2] * m[2][1])
+        - m[0][1] * (m[1][0] * m[2][2] - m[1][2] * m[2][0])
+        + m[0][2] * (m[1][0] * m[2][1] - m[1][1] * m[2][0])
+}
+
+/// Maps points through the triangles of a geo mesh, falling back to the best fitting affine
+/// transform outside of them.
+pub(crate) struct MeshFrame {
+    points: Vec<GeoMeshPoint>,
+    faces: Vec<[usize; 3]>,
+    forward: Affine,
+    inverse: Affine,
+}
+
+impl MeshFrame {
+    fn new(points: &[GeoMeshPoint], face_indices: &[Point]) -> Option<Self> {
+        let forward_pairs = points
+            .iter()
+            .map(|p| (&p.source, &p.destination))
+            .collect::<Vec<_>>();
+        let inverse_pairs = points
+            .iter()
+            .map(|p| (&p.destination, &p.source))
+            .collect::<Vec<_>>();
+        let faces = face_indices
+            .iter()
+            .map(|f| [f.x as usize, f.y as usize, f.z as usize])
+            .filter(|f| f.iter().all(|i| *i < points.len()))
+            .collect();
+        Some(MeshFrame {
+            points: points.to_vec(),
+            faces,
+            forward: Affine::fit(&forward_pairs)?,
+            inverse: Affine::fit(&inverse_pairs)?,
+        })
+    }
+    fn map(&self, p: &Point, is_forward: bool) -> Point {
+        let from = |m: &'_ GeoMeshPoint| -> Point {
+            if is_forward {
+                m.source.clone()
+            } else {
+                m.destination.clone()
+            }
+        };
+        let to = |m: &'_ GeoMeshPoint| -> Point {
+            if is_forward {
+                m.destination.clone()
+            } else {
+                m.source.clone()
+            }
+        };
+        for face in &self.faces {
+            let [a, b, c] = face.map(|i| &self.points[i]);
+            let (pa, pb, pc) = (from(a), from(b), from(c));
+            let denominator = (pb.y - pc.y) * (pa.x - pc.x) + (pc.x - pb.x) * (pa.y - pc.y);
+            if denominator.abs() < EPSILON {
+                continue;
+            }
+            let wa = ((pb.y - pc.y) * (p.x - pc.x) + (pc.x - pb.x) * (p.y - pc.y)) / denominator;
+            let wb = ((pc.y - pa.y) * (p.x - pc.x) + (pa.x - pc.x) * (p.y - pc.y)) / denominator;
+            let wc = 1.0 - wa - wb;
+            if wa >= -EPSILON && wb >= -EPSILON && wc >= -EPSILON {
+                let (ta, tb, tc) = (to(a), to(b), to(c));
+                return Point::new(
+                    wa * ta.x + wb * tb.x + wc * tc.x,
+                    wa * ta.y + wb * tb.y + wc * tc.y,
+                    p.z,
+                );
+            }
+        }
+        if is_forward {
+            self.forward.apply(p)
+        } else {
+            self.inverse.apply(p)
+        }
+    }
+}
+
+/// A simple feature geometry in drawing coordinates.
+enum Shape {
+    Point(Point),
+    LineString(Vec<Point>),
+    /// A closed ring; the first point isn't repeated.
+    Polygon(Vec<Point>),
+}
+
+pub(crate) struct GeoJsonWriter<'a> {
+    drawing: &'a Drawing,
+    geo: GeoReference,
+    styles: StyleResolver<'a>,
+}
+
+impl<'a> GeoJsonWriter<'a> {
+    pub fn new(drawing: &'a Drawing) -> DxfResult<Self> {
+        Ok(GeoJsonWriter {
+            drawing,
+            geo: GeoReference::from_drawing(drawing)?,
+            styles: StyleResolver {
+                drawing,
+                default_line_weight: 0.25,
+                foreground_color: (0, 0, 0),
+            },
+        })
+    }
+    pub fn write<T>(&self, writer: &mut T) -> DxfResult<()>
+    where
+        T: Write + ?Sized,
+    {
+        let mut out = String::from("{\"type\":\"FeatureCollection\",\"features\":[");
+        let mut is_first = true;
+        for entity in layout_entities(self.drawing, None)? {
+            if !entity.common.is_visible {
+                continue;
+            }
+            for shape in shapes(entity) {
+                let feature = JsonValue::Object(vec![
+                    (String::from("type"), string("Feature")),
+                    (String::from("geometry"), self.geometry(&shape)),
+                    (String::from("properties"), self.properties(entity)),
+                ]);
+                out.push_str(if is_first { "\n" } else { ",\n" });
+                feature.write(&mut out);
+                is_first = false;
+            }
+        }
+        out.push_str("\n]}\n");
+        writer.write_all(out.as_bytes())?;
+        Ok(())
+    }
+    fn geometry(&self, shape: &Shape) -> JsonValue {
+        let positions = |points: &[Point]| {
+            let points = points
+                .iter()
+                .map(|p| self.geo.to_wgs84(p))
+                .collect::<Vec<_>>();
+            // altitudes are only written when they're meaningful
+            let has_altitude = points.iter().any(|p| p.z != 0.0);
+            JsonValue::Array(
+                points
+                    .iter()
+                    .map(|p| {
+                        let mut position = vec![JsonValue::Number(p.x), JsonValue::Number(p.y)];
+                        if has_altitude {
+                            position.push(JsonValue::Number(p.z));
+                        }
+                        JsonValue::Array(position)
+                    })
+                    .collect(),
+            )
+        };
+        let (kind, coordinates) = match shape {
+            Shape::Point(p) => match positions(std::slice::from_ref(p)) {
+                JsonValue::Array(mut values) => ("Point", values.remove(0)),
+                _ => unreachable!(),
+            },
+            Shape::LineString(points) => ("LineString", positions(points)),
+            Shape::Polygon(points) => {
+                let mut ring = points.clone();
+                ring.push(points[0].clone());
+                ("Polygon", JsonValue::Array(vec![positions(&ring)]))
+            }
+        };
+        JsonValue::Object(vec![
+            (String::from("type"), string(kind)),
+            (String::from("coordinates"), coordinates),
+        ])
+    }
+    fn properties(&self, entity: &Entity) -> JsonValue {
+        let common = &entity.common;
+        let mut properties = vec![];
+        if !common.handle.is_empty() {
+            properties.push((String::from("handle"), string(&common.handle.as_string())));
+        }
+        properties.push((String::from("layer"), string(&common.layer)));
+        let color = match self.styles.color(entity, false) {
+            Some((r, g, b)) => string(&format!("#{r:02x}{g:02x}{b:02x}")),
+            None => JsonValue::Null,
+        };
+        properties.push((String::from("color"), color));
+        properties.push((
+            String::from("colorIndex"),
+            JsonValue::Number(f64::from(common.color.raw_value())),
+        ));
+        match entity.specific {
+            EntityType::Text(ref text) => {
+                properties.push((String::from("text"), string(&text.decoded_value().text)));
+                properties.push((
+                    String::from("textHeight"),
+                    JsonValue::Number(text.text_height),
+                ));
+            }
+            EntityType::MText(ref mtext) => {
+                properties.push((String::from("text"), string(&mtext.plain_text())));
+                properties.push((
+                    String::from("textHeight"),
+                    JsonValue::Number(mtext.initial_text_height),
+                ));
+            }
+            EntityType::Insert(ref insert) => {
+                properties.push((String::from("block"), string(&insert.name)));
+                let attributes = insert
+                    .attributes()
+                    .map(|a| (a.attribute_tag.clone(), string(&a.value)))
+                    .collect::<Vec<_>>();
+                if !attributes.is_empty() {
+                    properties.push((String::from("attributes"), JsonValue::Object(attributes)));
+                }
+            }
+            _ => (),
+        }
+        if !entity.common.x_data.is_empty() {
+            let x_data = entity
+                .common
+                .x_data
+                .iter()
+                .map(|x| {
+                    let items = x.items.iter().map(x_data_item_to_json).collect();
+                    (x.application_name.clone(), JsonValue::Array(items))
+                })
+                .collect();
+            properties.push((String::from("xdata"), JsonValue::Object(x_data)));
+        }
+        JsonValue::Object(properties)
+    }
+}
+
+/// Returns the shapes, in world coordinates, that represent an entity.
+fn shapes(entity: &Entity) -> Vec<Shape> {
+    let path = |points: Vec<Point>, is_closed: bool| {
+        let ring = dedupe_loop(&points);
+        if is_closed && ring.len() >= 3 {
+            Shape::Polygon(ring)
+        } else {
+            Shape::LineString(points)
+        }
+    };
+    let shape = match entity.specific {
+        EntityType::ModelPoint(ref point) => Shape::Point(point.location.clone()),
+        EntityType::Line(..)
+        | EntityType::Circle(..)
+        | EntityType::Arc(..)
+        | EntityType::Ellipse(..)
+        | EntityType::LwPolyline(..)
+        | EntityType::Polyline(..)
+        | EntityType::Spline(..) => {
+            return curve_paths(entity, SPLINE_SEGMENTS_PER_SPAN)
+                .into_iter()
+                .map(|(points, is_closed)| path(points, is_closed))
+                .collect()
+        }
+        EntityType::Solid(ref solid) => path(
+            [
+                &solid.first_corner,
+                &solid.second_corner,
+                &solid.fourth_corner,
+                &solid.third_corner,
+            ]
+            .iter()
+            .map(|p| ocs_to_wcs(p, &solid.extrusion_direction))
+            .collect(),
+            true,
+        ),
+        EntityType::Trace(ref trace) => path(
+            [
+                &trace.first_corner,
+                &trace.second_corner,
+                &trace.fourth_corner,
+                &trace.third_corner,
+            ]
+            .iter()
+            .map(|p| ocs_to_wcs(p, &trace.extrusion_direction))
+            .collect(),
+            true,
+        ),
+        EntityType::Face3D(ref face) => path(
+            vec![
+                face.first_corner.clone(),
+                face.second_corner.clone(),
+                face.third_corner.clone(),
+                face.fourth_corner.clone(),
+            ],
+            true,
+        ),
+        EntityType::Text(ref text) => Shape::Point(ocs_to_wcs(&text.location, &text.normal)),
+        EntityType::MText(ref mtext) => Shape::Point(mtext.insertion_point.clone()),
+        EntityType::Insert(ref insert) => {
+            Shape::Point(ocs_to_wcs(&insert.location, &insert.extrusion_direction))
+        }
+        _ => return vec![],
+    };
+    match shape {
+        Shape::LineString(ref points) if points.len() < 2 => vec![],
+        shape => vec![shape],
+    }
+}
+
+fn string(value: &str) -> JsonValue {
+    JsonValue::String(String::from(value))
+}
+
+fn point_to_json(p: &Point) -> JsonValue {
+    JsonValue::Array(vec![
+        JsonValue::Number(p.x),
+        JsonValue::Number(p.y),
+        JsonValue::Number(p.z),
+    ])
+}
+
+/// Writes an extended data item as an object whose single member names its type.
+fn x_data_item_to_json(item: &XDataItem) -> JsonValue {
+    let (kind, value) = match item {
+        XDataItem::Str(s) => ("string", string(s)),
+        XDataItem::ControlGroup(items) => (
+            "controlGroup",
+            JsonValue::Array(items.iter().map(x_data_item_to_json).collect()),
+        ),
+        XDataItem::LayerName(s) => ("layerName", string(s)),
+        XDataItem::BinaryData(data) => (
+            "binaryData",
+            string(&data.iter().map(|b| format!("{b:02X}")).collect::<String>()),
+        ),
+        XDataItem::Handle(h) => ("handle", string(&h.as_string())),
+        XDataItem::ThreeReals(x, y, z) => ("threeReals", point_to_json(&Point::new(*x, *y, *z))),
+        XDataItem::WorldSpacePosition(p) => ("worldSpacePosition", point_to_json(p)),
+        XDataItem::WorldSpaceDisplacement(p) => ("worldSpaceDisplacement", point_to_json(p)),
+        XDataItem::WorldDirection(v) => {
+            ("worldDirection", point_to_json(&Point::new(v.x, v.y, v.z)))
+        }
+        XDataItem::Real(r) => ("real", JsonValue::Number(*r)),
+        XDataItem::Distance(d) => ("distance", JsonValue::Number(*d)),
+        XDataItem::ScaleFactor(s) => ("scaleFactor", JsonValue::Number(*s)),
+        XDataItem::Integer(i) => ("integer", JsonValue::Number(f64::from(*i))),
+        XDataItem::Long(l) => ("long", JsonValue::Number(f64::from(*l))),
+    };
+    JsonValue::Object(vec![(String::from(kind), value)])
+}
+
+fn x_data_item_from_json(value: &JsonValue) -> Option<XDataItem> {
+    let (kind, value) = match value.as_object()? {
+        [(kind, value)] => (kind.as_str(), value),
+        _ => return None,
+    };
+    let point = || {
+        let values = value.as_array()?;
+        Some(Point::new(
+            values.first()?.as_f64()?,
+            values.get(1)?.as_f64()?,
+            values.get(2)?.as_f64()?,
+        ))
+    };
+    let item = match kind {
+        "string" => XDataItem::Str(String::from(value.as_str()?)),
+        "controlGroup" => XDataItem::ControlGroup(
+            value
+                .as_array()?
+                .iter()
+                .filter_map(x_data_item_from_json)
+                .collect(),
+        ),
+        "layerName" => XDataItem::LayerName(String::from(value.as_str()?)),
+        "binaryData" => {
+            let hex = value.as_str()?;
+            let bytes = (0..hex.len() / 2)
+                .map(|i| u8::from_str_radix(hex.get(i * 2..i * 2 + 2)?, 16).ok())
+                .collect::<Option<Vec<_>>>()?;
+            XDataItem::BinaryData(bytes)
+        }
+        "handle" => XDataItem::Handle(Handle(u64::from_str_radix(value.as_str()?, 16).ok()?)),
+        "threeReals" => {
+            let p = point()?;
+            XDataItem::ThreeReals(p.x, p.y, p.z)
+        }
+        "worldSpacePosition" => XDataItem::WorldSpacePosition(point()?),
+        "worldSpaceDisplacement" => XDataItem::WorldSpaceDisplacement(point()?),
+        "worldDirection" => {
+            let p = point()?;
+            XDataItem::WorldDirection(Vector::new(p.x, p.y, p.z))
+        }
+        "real" => XDataItem::Real(value.as_f64()?),
+        "distance" => XDataItem::Distance(value.as_f64()?),
+        "scaleFactor" => XDataItem::ScaleFactor(value.as_f64()?),
+        "integer" => XDataItem::Integer(value.as_f64()? as i16),
+        "long" => XDataItem::Long(value.as_f64()? as i32),
+        _ => return None,
+    };
+    Some(item)
+}
+
+pub(crate) struct GeoJsonReader {
+    geo: GeoReference,
+}
+
+impl GeoJsonReader {
+    pub fn new(drawing: &Drawing) -> DxfResult<Self> {
+        Ok(GeoJsonReader {
+            geo: GeoReference::from_drawing(drawing)?,
+        })
+    }
+    /// Adds the features of the document to the drawing.  Features with missing or malformed
+    /// geometry are skipped.
+    pub fn read<T>(&self, drawing: &mut Drawing, reader: &mut T) -> DxfResult<()>
+    where
+        T: Read + ?Sized,
+    {
+        let mut text = String::new();
+        reader.read_to_string(&mut text)?;
+        let document = JsonValue::parse(&text)?;
+        let no_properties = JsonValue::Object(vec![]);
+        let features = match document.get("type").and_then(|t| t.as_str()) {
+            Some("FeatureCollection") => document
+                .get("features")
+                .and_then(|f| f.as_array())
+                .unwrap_or_default()
+                .iter()
+                .filter_map(|f| Some((f.get("geometry")?, f.get("properties"))))
+                .collect(),
+            Some("Feature") => match document.get("geometry") {
+                Some(geometry) => vec![(geometry, document.get("properties"))],
+                None => vec![],
+            },
+            _ => vec![(&document, None)],
+        };
+
+        for (geometry, properties) in features {
+            let properties = match properties {
+                Some(p @ JsonValue::Object(_)) => p,
+                _ => &no_properties,
+            };
+            let mut entities = vec![];
+            self.read_geometry(drawing, geometry, properties, &mut entities);
+            for specific in entities {
+                let mut entity = Entity::new(specific);
+                apply_properties(&mut entity, properties);
+                drawing.add_entity(entity);
+            }
+        }
+        Ok(())
+    }
+    fn read_geometry(
+        &self,
+        drawing: &Drawing,
+        geometry: &JsonValue,
+        properties: &JsonValue,
+        entities: &mut Vec<EntityType>,
+    ) {
+        let coordinates = geometry.get("coordinates");
+        fn list(value: Option<&JsonValue>) -> &[JsonValue] {
+            value.and_then(|v| v.as_array()).unwrap_or_default()
+        }
+        match geometry.get("type").and_then(|t| t.as_str()) {
+            Some("Point") => {
+                if let Some(p) = coordinates.and_then(|c| self.position(c)) {
+                    entities.push(point_entity(drawing, p, properties));
+                }
+            }
+            Some("MultiPoint") => {
+                for p in list(coordinates).iter().filter_map(|c| self.position(c)) {
+                    entities.push(point_entity(drawing, p, properties));
+                }
+            }
+            Some("LineString") => {
+                entities.extend(coordinates.and_then(|c| self.line(c, false)));
+            }
+            Some("MultiLineString") => {
+                entities.extend(list(coordinates).iter().filter_map(|c| self.line(c, false)));
+            }
+            Some("Polygon") => {
+                entities.extend(list(coordinates).iter().filter_map(|c| self.line(c, true)));
+            }
+            Some("MultiPolygon") => {
+                for polygon in list(coordinates) {
+                    entities.extend(
+                        list(Some(polygon))
+                            .iter()
+                            .filter_map(|c| self.line(c, true)),
+                    );
+                }
+            }
+            Some("GeometryCollection") => {
+                for geometry in list(geometry.get("geometries")) {
+                    self.read_geometry(drawing, geometry, properties, entities);
+                }
+            }
+            _ => (),
+        }
+    }
+    fn position(&self, value: &JsonValue) -> Option<Point> {
+        let values = value.as_array()?;
+        let longitude = values.first()?.as_f64()?;
+        let latitude = values.get(1)?.as_f64()?;
+        let altitude = values
+            .get(2)
+            .and_then(|a| a.as_f64())
+            .unwrap_or_else(|| self.geo.default_altitude());
+        Some(
+            self.geo
+                .to_design(&Point::new(longitude, latitude, altitude)),
+        )
+    }
+    /// Converts a line string or polygon ring to a `Line` or `LwPolyline`.
+    fn line(&self, value: &JsonValue, is_ring: bool) -> Option<EntityType> {
+        let mut points = value
+            .as_array()?
+            .iter()
+            .map(|p| self.position(p))
+            .collect::<Option<Vec<_>>>()?;
+        if is_ring && points.len() > 1 && points_are_close(&points[0], &points[points.len() - 1]) {
+            points.pop();
+        }
+        match points.len() {
+            0 | 1 => None,
+            2 if !is_ring => Some(EntityType::Line(Line::new(
+                points[0].clone(),
+                points[1].clone(),
+            ))),
+            _ => {
+                let mut poly = LwPolyline {
+                    vertices: points
+                        .iter()
+                        .map(|p| LwPolylineVertex {
+                            x: p.x,
+                            y: p.y,
+                            ..Default::default()
+                        })
+                        .collect(),
+                    ..Default::default()
+                };
+                poly.set_is_closed(is_ring);
+                Some(EntityType::LwPolyline(poly))
+            }
+        }
+    }
+}
+
+/// Creates the entity for a point feature; features with text become `Text` and those naming a
+/// block in the drawing become `Insert` entities.
+fn point_entity(drawing: &Drawing, location: Point, properties: &JsonValue) -> EntityType {
+    if let Some(value) = properties.get("text").and_then(|t| t.as_str()) {
+        let mut text = Text {
+            location,
+            text_height: properties
+                .get("textHeight")
+                .and_then(|h| h.as_f64())
+                .unwrap_or(1.0),
+            ..Default::default()
+        };
+        text.set_decoded_value(&crate::DecodedText {
+            text: String::from(value),
+            ..Default::default()
+        });
+        return EntityType::Text(text);
+    }
+    if let Some(name) = properties.get("block").and_then(|b| b.as_str()) {
+        if drawing.blocks().any(|b| b.name == name) {
+            return EntityType::Insert(Insert {
+                name: String::from(name),
+                location,
+                ..Default::default()
+            });
+        }
+    }
+    EntityType::ModelPoint(ModelPoint::new(location))
+}
+
+fn apply_properties(entity: &mut Entity, properties: &JsonValue) {
+    let common = &mut entity.common;
+    if let Some(layer) = properties.get("layer").and_then(|l| l.as_str()) {
+        common.layer = String::from(layer);
+    }
+    if let Some(index) = properties.get("colorIndex").and_then(|c| c.as_f64()) {
+        common.color = Color::from_raw_value(index as i16);
+    } else if let Some(hex) = properties
+        .get("color")
+        .and_then(|c| c.as_str())
+        .and_then(|c| c.strip_prefix('#'))
+    {
+        if let Ok(rgb) = i32::from_str_radix(hex, 16) {
+            common.color_24_bit = rgb;
+        }
+    }
+    if let Some(x_data) = properties.get("xdata").and_then(|x| x.as_object()) {
+        common.x_data = x_data
+            .iter()
+            .map(|(application_name, items)| XData {
+                application_name: application_name.clone(),
+                items: items
+                    .as_array()
+                    .unwrap_or_default()
+                    .iter()
+                    .filter_map(x_data_item_from_json)
+                    .collect(),
+            })
+            .collect();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::geo_json::*;
+    use crate::tables::Layer;
+    use float_cmp::approx_eq;
+
+    /// A drawing in meters whose origin is at the given longitude and latitude with north along
+    /// the drawing's `north` direction.
+    fn georeferenced(longitude: f64, latitude: f64, north: Vector) -> Drawing {
+        let mut drawing = Drawing::new();
+        drawing.add_object(Object::new(ObjectType::GeoData(GeoData {
+            coordinate_type: DesignCoordinateType::Geographic,
+            design_point: Point::origin(),
+            reference_point: Point::new(longitude, latitude, 0.0),
+            north_vector: north,
+            horizontal_unit_scale: 1.0,
+            ..Default::default()
+        })));
+        drawing
+    }
+
+    fn to_geojson(drawing: &Drawing) -> JsonValue {
+        let mut buf = vec![];
+        drawing.save_geojson(&mut buf).unwrap();
+        JsonValue::parse(std::str::from_utf8(&buf).unwrap()).unwrap()
+    }
+
+    fn features(document: &JsonValue) -> &[JsonValue] {
+        document.get("features").unwrap().as_array().unwrap()
+    }
+
+    fn position(value: &JsonValue) -> (f64, f64) {
+        let values = value.as_array().unwrap();
+        (values[0].as_f64().unwrap(), values[1].as_f64().unwrap())
+    }
+
+    #[test]
+    fn local_frame_round_trips_points() {
+        let drawing = georeferenced(-122.0, 47.0, Vector::new(1.0, 1.0, 0.0));
+        let geo = GeoReference::from_drawing(&drawing).unwrap();
+        let p = Point::new(123.0, -456.0, 7.0);
+        let wgs84 = geo.to_wgs84(&p);
+        let back = geo.to_design(&wgs84);
+        assert!(approx_eq!(f64, p.x, back.x, epsilon = 1e-6));
+        assert!(approx_eq!(f64, p.y, back.y, epsilon = 1e-6));
+        assert!(approx_eq!(f64, p.z, back.z, epsilon = 1e-6));
+    }
+
+    #[test]
+    fn north_and_scale_are_applied() {
+        let drawing = georeferenced(0.0, 0.0, Vector::new(0.0, 1.0, 0.0));
+        let geo = GeoReference::from_drawing(&drawing).unwrap();
+        // a degree of latitude at the equator is about 110.574km
+        let north = geo.to_wgs84(&Point::new(0.0, 110_574.0, 0.0));
+        assert!(approx_eq!(f64, 0.0, north.x, epsilon = 1e-9));
+        assert!(approx_eq!(f64, 1.0, north.y, epsilon = 1e-4));
+        // and a degree of longitude is about 111.319km
+        let east = geo.to_wgs84(&Point::new(111_319.5, 0.0, 0.0));
+        assert!(approx_eq!(f64, 1.0, east.x, epsilon = 1e-4));
+
+        // with north along the drawing's x axis, the drawing's y axis points west
+        let drawing = georeferenced(0.0, 0.0, Vector::new(1.0, 0.0, 0.0));
+        let geo = GeoReference::from_drawing(&drawing).unwrap();
+        let west = geo.to_wgs84(&Point::new(0.0, 111_319.5, 0.0));
+        assert!(approx_eq!(f64, -1.0, west.x, epsilon = 1e-4));
+        assert!(approx_eq!(f64, 0.0, west.y, epsilon = 1e-9));
+    }
+
+    #[test]
+    fn mesh_maps_through_faces() {
+        let mut drawing = Drawing::new();
+        drawing.add_object(Object::new(ObjectType::GeoData(GeoData {
+            coordinate_type: DesignCoordinateType::ProjectedGrid,
+            geo_mesh_points: vec![
+                GeoMeshPoint::new(Point::new(0.0, 0.0, 0.0), Point::new(10.0, 50.0, 0.0)),
+                GeoMeshPoint::new(Point::new(100.0, 0.0, 0.0), Point::new(10.001, 50.0, 0.0)),
+                GeoMeshPoint::new(Point::new(0.0, 100.0, 0.0), Point::new(10.0, 50.001, 0.0)),
+            ],
+            face_indices: vec![Point::new(0.0, 1.0, 2.0)],
+            ..Default::default()
+        })));
+        let geo = GeoReference::from_drawing(&drawing).unwrap();
+        let p = geo.to_wgs84(&Point::new(50.0, 25.0, 0.0));
+        assert!(approx_eq!(f64, 10.0005, p.x, epsilon = 1e-9));
+        assert!(approx_eq!(f64, 50.00025, p.y, epsilon = 1e-9));
+        let back = geo.to_design(&p);
+        assert!(approx_eq!(f64, 50.0, back.x, epsilon = 1e-6));
+        assert!(approx_eq!(f64, 25.0, back.y, epsilon = 1e-6));
+    }
+
+    #[test]
+    fn missing_geo_data_is_an_error() {
+        let drawing = Drawing::new();
+        assert!(matches!(
+            drawing.save_geojson(&mut vec![]),
+            Err(DxfError::MissingGeoReference)
+        ));
+
+        // projected coordinates need a mesh
+        let mut drawing = Drawing::new();
+        drawing.add_object(Object::new(ObjectType::GeoData(GeoData {
+            coordinate_type: DesignCoordinateType::ProjectedGrid,
+            ..Default::default()
+        })));
+        assert!(matches!(
+            drawing.save_geojson(&mut vec![]),
+            Err(DxfError::MissingGeoReference)
+        ));
+    }
+
+    #[test]
+    fn export_features() {
+        let mut drawing = georeferenced(10.0, 50.0, Vector::new(0.0, 1.0, 0.0));
+        drawing.add_layer(Layer {
+            name: String::from("roads"),
+            color: Color::from_index(1),
+            ..Default::default()
+        });
+        let mut line = Entity::new(EntityType::Line(Line::new(
+            Point::origin(),
+            Point::new(100.0, 0.0, 0.0),
+        )));
+        line.common.layer = String::from("roads");
+        line.common.x_data.push(XData {
+            application_name: String::from("GIS"),
+            items: vec![XDataItem::Str(String::from("A1")), XDataItem::Integer(3)],
+        });
+        drawing.add_entity(line);
+        let mut poly = LwPolyline {
+            vertices: vec![
+                LwPolylineVertex {
+                    x: 0.0,
+                    y: 0.0,
+                    ..Default::default()
+                },
+                LwPolylineVertex {
+                    x: 10.0,
+                    y: 0.0,
+                    ..Default::default()
+                },
+                LwPolylineVertex {
+                    x: 10.0,
+                    y: 10.0,
+                    ..Default::default()
+                },
+            ],
+            ..Default::default()
+        };
+        poly.set_is_closed(true);
+        drawing.add_entity(Entity::new(EntityType::LwPolyline(poly)));
+        drawing.add_entity(Entity::new(EntityType::ModelPoint(ModelPoint::new(
+            Point::origin(),
+        ))));
+
+        let document = to_geojson(&drawing);
+        let features = features(&document);
+        assert_eq!(3, features.len());
+
+        let geometry = features[0].get("geometry").unwrap();
+        assert_eq!(Some("LineString"), geometry.get("type").unwrap().as_str());
+        let coordinates = geometry.get("coordinates").unwrap().as_array().unwrap();
+        assert_eq!((10.0, 50.0), position(&coordinates[0]));
+        let (longitude, latitude) = position(&coordinates[1]);
+        assert!(longitude > 10.0 && approx_eq!(f64, 50.0, latitude));
+        let properties = features[0].get("properties").unwrap();
+        assert_eq!(Some("roads"), properties.get("layer").unwrap().as_str());
+        assert_eq!(Some("#ff0000"), properties.get("color").unwrap().as_str());
+        let mut x_data = String::new();
+        properties.get("xdata").unwrap().write(&mut x_data);
+        assert_eq!(r#"{"GIS":[{"string":"A1"},{"integer":3}]}"#, x_data);
+
+        let geometry = features[1].get("geometry").unwrap();
+        assert_eq!(Some("Polygon"), geometry.get("type").unwrap().as_str());
+        let rings = geometry.get("coordinates").unwrap().as_array().unwrap();
+        let ring = rings[0].as_array().unwrap();
+        assert_eq!(4, ring.len());
+        assert_eq!(position(&ring[0]), position(&ring[3]));
+
+        let geometry = features[2].get("geometry").unwrap();
+        assert_eq!(Some("Point"), geometry.get("type").unwrap().as_str());
+        assert_eq!((10.0, 50.0), position(geometry.get("coordinates").unwrap()));
+    }
+
+    #[test]
+    fn import_features() {
+        let mut drawing = georeferenced(10.0, 50.0, Vector::new(0.0, 1.0, 0.0));
+        let document = r##"{
+            "type": "FeatureCollection",
+            "features": [
+                {
+                    "type": "Feature",
+                    "geometry": { "type": "LineString", "coordinates": [[10, 50], [10.001, 50]] },
+                    "properties": { "layer": "roads", "color": "#00ff00" }
+                },
+                {
+                    "type": "Feature",
+                    "geometry": {
+                        "type": "Polygon",
+                        "coordinates": [[[10, 50], [10.001, 50], [10.001, 50.001], [10, 50]]]
+                    },
+                    "properties": {
+                        "colorIndex": 5,
+                        "xdata": { "GIS": [{ "real": 1.5 }, { "controlGroup": [{ "long": 7 }] }] }
+                    }
+                },
+                {
+                    "type": "Feature",
+                    "geometry": { "type": "Point", "coordinates": [10, 50] },
+                    "properties": { "text": "site", "textHeight": 2.5 }
+                },
+                { "type": "Feature", "geometry": null, "properties": null }
+            ]
+        }"##;
+        drawing.import_geojson(&mut document.as_bytes()).unwrap();
+        let entities = drawing.entities().collect::<Vec<_>>();
+        assert_eq!(3, entities.len());
+
+        assert_eq!("roads", entities[0].common.layer);
+        assert_eq!(0x00FF00, entities[0].common.color_24_bit);
+        match entities[0].specific {
+            EntityType::Line(ref line) => {
+                assert!(approx_eq!(f64, 0.0, line.p1.x, epsilon = 1e-9));
+                // a thousandth of a degree of longitude at 50 degrees north is about 71.7m
+                assert!(approx_eq!(f64, 71.7, line.p2.x, epsilon = 0.1));
+                assert!(approx_eq!(f64, 0.0, line.p2.y, epsilon = 1e-9));
+            }
+            _ => panic!("expected a line"),
+        }
+
+        assert_eq!(Some(5), entities[1].common.color.index());
+        assert_eq!(
+            vec![
+                XDataItem::Real(1.5),
+                XDataItem::ControlGroup(vec![XDataItem::Long(7)])
+            ],
+            entities[1].common.x_data[0].items
+        );
+        match entities[1].specific {
+            EntityType::LwPolyline(ref poly) => {
+                assert!(poly.is_closed());
+                assert_eq!(3, poly.vertices.len());
+            }
+            _ => panic!("expected a polyline"),
+        }
+
+        match entities[2].specific {
+            EntityType::Text(ref text) => {
+                assert_eq!("site", text.value);
+                assert_eq!(2.5, text.text_height);
+            }
+            _ => panic!("expected text"),
+        }
+    }
+
+    #[test]
+    fn export_and_import_round_trip() {
+        let mut drawing = georeferenced(-71.06, 42.36, Vector::new(0.5, 1.0, 0.0));
+        let mut circle = Entity::new(EntityType::Circle(Circle::new(
+            Point::new(20.0, 30.0, 0.0),
+            5.0,
+        )));
+        circle.common.color = Color::from_index(3);
+        drawing.add_entity(circle);
+
+        let mut buf = vec![];
+        drawing.save_geojson(&mut buf).unwrap();
+        let mut imported = georeferenced(-71.06, 42.36, Vector::new(0.5, 1.0, 0.0));
+        imported.import_geojson(&mut buf.as_slice()).unwrap();
+        let entity = imported.entities().next().unwrap();
+        assert_eq!(Some(3), entity.common.color.index());
+        match entity.specific {
+            EntityType::LwPolyline(ref poly) => {
+                assert!(poly.is_closed());
+                assert_eq!(CIRCLE_SEGMENTS, poly.vertices.len());
+                for v in &poly.vertices {
+                    let distance = (v.x - 20.0).hypot(v.y - 30.0);
+                    assert!(approx_eq!(f64, 5.0, distance, epsilon = 1e-6));
+                }
+            }
+            _ => panic!("expected a polyline"),
+        }
+    }
+}
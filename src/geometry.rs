@@ -0,0 +1,515 @@
+// shared geometric helpers used by the measurement and conversion code
+
+use std::f64::consts::PI;
+
+use crate::entities::*;
+use crate::enums::WindingDirection;
+use crate::{Point, Vector};
+
+/// The tolerance used when comparing coordinates.
+pub(crate) const EPSILON: f64 = 1e-10;
+
+pub(crate) fn points_are_close(a: &Point, b: &Point) -> bool {
+    (a.x - b.x).abs() < EPSILON && (a.y - b.y).abs() < EPSILON && (a.z - b.z).abs() < EPSILON
+}
+
+pub(crate) fn distance(a: &Point, b: &Point) -> f64 {
+    let dx = b.x - a.x;
+    let dy = b.y - a.y;
+    let dz = b.z - a.z;
+    (dx * dx + dy * dy + dz * dz).sqrt()
+}
+
+pub(crate) fn cross(a: &Vector, b: &Vector) -> Vector {
+    Vector::new(
+        a.y * b.z - a.z * b.y,
+        a.z * b.x - a.x * b.z,
+        a.x * b.y - a.y * b.x,
+    )
+}
+
+pub(crate) fn vector_length(v: &Vector) -> f64 {
+    (v.x * v.x + v.y * v.y + v.z * v.z).sqrt()
+}
+
+pub(crate) fn normalize(v: &Vector) -> Vector {
+    let length = vector_length(v);
+    if length < EPSILON {
+        v.clone()
+    } else {
+        Vector::new(v.x / length, v.y / length, v.z / length)
+    }
+}
+
+pub(crate) fn dot(a: &Vector, b: &Vector) -> f64 {
+    a.x * b.x + a.y * b.y + a.z * b.z
+}
+
+/// Returns the x and y axes of the object coordinate system (OCS) for the given normal using the
+/// arbitrary axis algorithm.
+pub(crate) fn ocs_axes(normal: &Vector) -> (Vector, Vector) {
+    let normal = normalize(normal);
+    let x_axis = if normal.x.abs() < 1.0 / 64.0 && normal.y.abs() < 1.0 / 64.0 {
+        normalize(&cross(&Vector::y_axis(), &normal))
+    } else {
+        normalize(&cross(&Vector::z_axis(), &normal))
+    };
+    let y_axis = normalize(&cross(&normal, &x_axis));
+    (x_axis, y_axis)
+}
+
+/// Converts a point from world coordinates to the object coordinate system for the given normal.
+pub(crate) fn wcs_to_ocs(point: &Point, normal: &Vector) -> Point {
+    let (x_axis, y_axis) = ocs_axes(normal);
+    let normal = normalize(normal);
+    let v = Vector::new(point.x, point.y, point.z);
+    Point::new(dot(&v, &x_axis), dot(&v, &y_axis), dot(&v, &normal))
+}
+
+/// Converts a point from the object coordinate system for the given normal to world coordinates.
+pub(crate) fn ocs_to_wcs(point: &Point, normal: &Vector) -> Point {
+    let (x_axis, y_axis) = ocs_axes(normal);
+    let normal = normalize(normal);
+    Point::new(
+        x_axis.x * point.x + y_axis.x * point.y + normal.x * point.z,
+        x_axis.y * point.x + y_axis.y * point.y + normal.y * point.z,
+        x_axis.z * point.x + y_axis.z * point.y + normal.z * point.z,
+    )
+}
+
+/// Normalizes an angle in degrees into the range [0, 360).
+pub(crate) fn normalize_degrees(angle: f64) -> f64 {
+    let angle = angle % 360.0;
+    if angle < 0.0 {
+        angle + 360.0
+    } else {
+        angle
+    }
+}
+
+/// Returns the included angle in degrees when sweeping counter-clockwise from `start` to `end`.
+pub(crate) fn sweep_degrees(start: f64, end: f64) -> f64 {
+    let sweep = normalize_degrees(end) - normalize_degrees(start);
+    if sweep <= 0.0 {
+        sweep + 360.0
+    } else {
+        sweep
+    }
+}
+
+/// Returns the arc length of a polyline segment from `p1` to `p2` with the given bulge.
+pub(crate) fn bulge_segment_length(x1: f64, y1: f64, x2: f64, y2: f64, bulge: f64) -> f64 {
+    let chord = ((x2 - x1).powi(2) + (y2 - y1).powi(2)).sqrt();
+    if bulge.abs() < EPSILON || chord < EPSILON {
+        return chord;
+    }
+
+    let included_angle = 4.0 * bulge.atan().abs();
+    let radius = chord / (2.0 * (included_angle / 2.0).sin());
+    radius * included_angle
+}
+
+/// Describes the circle that a bulged polyline segment lies on.
+pub(crate) struct BulgeArc {
+    pub center_x: f64,
+    pub center_y: f64,
+    pub radius: f64,
+    /// Start angle in radians.
+    pub start_angle: f64,
+    /// Signed included angle in radians; positive values are counter-clockwise.
+    pub included_angle: f64,
+}
+
+/// Returns the arc described by a bulged segment, or `None` if the segment is straight.
+pub(crate) fn bulge_to_arc(x1: f64, y1: f64, x2: f64, y2: f64, bulge: f64) -> Option<BulgeArc> {
+    let dx = x2 - x1;
+    let dy = y2 - y1;
+    let chord = (dx * dx + dy * dy).sqrt();
+    if bulge.abs() < EPSILON || chord < EPSILON {
+        return None;
+    }
+
+    let included_angle = 4.0 * bulge.atan();
+    let radius = chord / (2.0 * (included_angle / 2.0).sin().abs());
+    // the arc midpoint is offset to the right of the chord by the signed sagitta
+    let sagitta = bulge * chord / 2.0;
+    let (right_x, right_y) = (dy / chord, -dx / chord);
+    let mid_x = (x1 + x2) / 2.0 + right_x * sagitta;
+    let mid_y = (y1 + y2) / 2.0 + right_y * sagitta;
+    let sign = bulge.signum();
+    let center_x = mid_x - right_x * sign * radius;
+    let center_y = mid_y - right_y * sign * radius;
+    Some(BulgeArc {
+        center_x,
+        center_y,
+        radius,
+        start_angle: (y1 - center_y).atan2(x1 - center_x),
+        included_angle,
+    })
+}
+
+/// Computes the signed area and area centroid of a planar loop whose segments may be bulged.  Each
+/// vertex is `(x, y, bulge)` where the bulge applies to the segment leaving that vertex.  Positive
+/// areas indicate counter-clockwise loops.
+pub(crate) fn bulged_loop_area_and_centroid(vertices: &[(f64, f64, f64)]) -> (f64, f64, f64) {
+    let mut area = 0.0;
+    let mut moment_x = 0.0;
+    let mut moment_y = 0.0;
+    let count = vertices.len();
+    for i in 0..count {
+        let (x1, y1, bulge) = vertices[i];
+        let (x2, y2, _) = vertices[(i + 1) % count];
+
+        // straight edge contribution (shoelace)
+        let c = x1 * y2 - x2 * y1;
+        area += c / 2.0;
+        moment_x += (x1 + x2) * c / 6.0;
+        moment_y += (y1 + y2) * c / 6.0;
+
+        // circular segment contribution
+        if let Some(arc) = bulge_to_arc(x1, y1, x2, y2, bulge) {
+            let half_angle = arc.included_angle.abs() / 2.0;
+            let theta = 2.0 * half_angle;
+            let segment_area =
+                bulge.signum() * arc.radius * arc.radius * (theta - theta.sin()) / 2.0;
+            let centroid_distance =
+                4.0 * arc.radius * half_angle.sin().powi(3) / (3.0 * (theta - theta.sin()));
+            let mid_angle = arc.start_angle + arc.included_angle / 2.0;
+            let segment_x = arc.center_x + centroid_distance * mid_angle.cos();
+            let segment_y = arc.center_y + centroid_distance * mid_angle.sin();
+            area += segment_area;
+            moment_x += segment_area * segment_x;
+            moment_y += segment_area * segment_y;
+        }
+    }
+
+    if area.abs() < EPSILON {
+        let (sx, sy) = vertices
+            .iter()
+            .fold((0.0, 0.0), |(sx, sy), v| (sx + v.0, sy + v.1));
+        let n = count.max(1) as f64;
+        (0.0, sx / n, sy / n)
+    } else {
+        (area, moment_x / area, moment_y / area)
+    }
+}
+
+/// Determines whether a sequence of `(x, y, bulge)` vertices forms a closed loop, either because it
+/// is flagged as closed or because the last vertex repeats the first.  A repeated final vertex is
+/// removed.
+pub(crate) fn close_bulged_loop(vertices: &mut Vec<(f64, f64, f64)>, is_closed: bool) -> bool {
+    if is_closed {
+        return vertices.len() > 1;
+    }
+
+    match (vertices.first(), vertices.last()) {
+        (Some(first), Some(last))
+            if vertices.len() > 2
+                && (first.0 - last.0).abs() < EPSILON
+                && (first.1 - last.1).abs() < EPSILON =>
+        {
+            vertices.pop();
+            true
+        }
+        _ => false,
+    }
+}
+
+/// Computes the area vector (normal scaled by twice the area) of a planar polygon using Newell's
+/// method.
+pub(crate) fn newell_normal(points: &[Point]) -> Vector {
+    let mut normal = Vector::zero();
+    let count = points.len();
+    for i in 0..count {
+        let a = &points[i];
+        let b = &points[(i + 1) % count];
+        normal.x += (a.y - b.y) * (a.z + b.z);
+        normal.y += (a.z - b.z) * (a.x + b.x);
+        normal.z += (a.x - b.x) * (a.y + b.y);
+    }
+    normal
+}
+
+/// Computes the unsigned area and area centroid of a planar (possibly non-axis-aligned) polygon.
+pub(crate) fn polygon_area_and_centroid_3d(points: &[Point]) -> (f64, Point) {
+    if points.is_empty() {
+        return (0.0, Point::origin());
+    }
+
+    let normal = normalize(&newell_normal(points));
+    let origin = &points[0];
+    let mut area = 0.0;
+    let mut cx = 0.0;
+    let mut cy = 0.0;
+    let mut cz = 0.0;
+    for i in 1..points.len().saturating_sub(1) {
+        let b = &points[i];
+        let c = &points[i + 1];
+        let ab = Vector::new(b.x - origin.x, b.y - origin.y, b.z - origin.z);
+        let ac = Vector::new(c.x - origin.x, c.y - origin.y, c.z - origin.z);
+        let n = cross(&ab, &ac);
+        // signed relative to the polygon normal so that concave polygons work
+        let triangle_area = (n.x * normal.x + n.y * normal.y + n.z * normal.z) / 2.0;
+        area += triangle_area;
+        cx += triangle_area * (origin.x + b.x + c.x) / 3.0;
+        cy += triangle_area * (origin.y + b.y + c.y) / 3.0;
+        cz += triangle_area * (origin.z + b.z + c.z) / 3.0;
+    }
+
+    if area.abs() < EPSILON {
+        let n = points.len() as f64;
+        let (sx, sy, sz) = points
+            .iter()
+            .fold((0.0, 0.0, 0.0), |(x, y, z), p| (x + p.x, y + p.y, z + p.z));
+        (0.0, Point::new(sx / n, sy / n, sz / n))
+    } else {
+        (area.abs(), Point::new(cx / area, cy / area, cz / area))
+    }
+}
+
+/// Returns the winding direction of a signed area; positive areas are counter-clockwise.
+pub(crate) fn winding_from_signed_area(area: f64) -> Option<WindingDirection> {
+    if area > EPSILON {
+        Some(WindingDirection::CounterClockwise)
+    } else if area < -EPSILON {
+        Some(WindingDirection::Clockwise)
+    } else {
+        None
+    }
+}
+
+/// Returns the winding direction of a planar polygon when viewed from the tip of `normal`.
+pub(crate) fn winding_about(points: &[Point], normal: &Vector) -> Option<WindingDirection> {
+    let n = newell_normal(points);
+    winding_from_signed_area(n.x * normal.x + n.y * normal.y + n.z * normal.z)
+}
+
+/// Returns the total length of the path through the given points.
+pub(crate) fn path_length(points: &[Point]) -> f64 {
+    points.windows(2).map(|w| distance(&w[0], &w[1])).sum()
+}
+
+/// Removes consecutive duplicate points and a trailing point that duplicates the first.
+pub(crate) fn dedupe_loop(points: &[Point]) -> Vec<Point> {
+    let mut result: Vec<Point> = vec![];
+    for p in points {
+        match result.last() {
+            Some(last) if points_are_close(last, p) => (),
+            _ => result.push(p.clone()),
+        }
+    }
+    if result.len() > 1 && points_are_close(&result[0], &result[result.len() - 1]) {
+        result.pop();
+    }
+    result
+}
+
+//------------------------------------------------------------------------------
+//                                                                        Ellipse
+//------------------------------------------------------------------------------
+pub(crate) fn ellipse_minor_axis(ellipse: &Ellipse) -> Vector {
+    let minor = cross(&normalize(&ellipse.normal), &ellipse.major_axis);
+    Vector::new(
+        minor.x * ellipse.minor_axis_ratio,
+        minor.y * ellipse.minor_axis_ratio,
+        minor.z * ellipse.minor_axis_ratio,
+    )
+}
+
+/// Returns the start and end parameters of the ellipse with `start` in `[0, 2π)` and the span
+/// `end - start` in `(0, 2π]`.  Parameters that aren't finite give the full ellipse.
+pub(crate) fn ellipse_parameter_range(ellipse: &Ellipse) -> (f64, f64) {
+    let (start, end) = (ellipse.start_parameter, ellipse.end_parameter);
+    if !start.is_finite() || !end.is_finite() {
+        return (0.0, 2.0 * PI);
+    }
+    let start = start.rem_euclid(2.0 * PI);
+    let span = (end.rem_euclid(2.0 * PI) - start).rem_euclid(2.0 * PI);
+    // equal parameters and rounding right below a full turn both mean the full ellipse
+    let span = if span <= EPSILON || span > 2.0 * PI {
+        2.0 * PI
+    } else {
+        span
+    };
+    (start, start + span)
+}
+
+//------------------------------------------------------------------------------
+//                                                                        Spline
+//------------------------------------------------------------------------------
+/// The number of samples per knot span used when measuring splines.
+pub(crate) const SPLINE_MEASUREMENT_SEGMENTS: usize = 32;
+
+/// Returns the knot vector to use for evaluation, generating a clamped uniform vector when the
+/// stored one is unusable.
+pub(crate) fn spline_knots(spline: &Spline) -> Vec<f64> {
+    let degree = spline.degree_of_curve.max(1) as usize;
+    let count = spline.control_points.len();
+    if spline.knot_values.len() == count + degree + 1 {
+        return spline.knot_values.clone();
+    }
+
+    let interior = count.saturating_sub(degree + 1);
+    let mut knots = vec![0.0; degree + 1];
+    knots.extend((1..=interior).map(|i| i as f64 / (interior + 1) as f64));
+    knots.extend(vec![1.0; degree + 1]);
+    knots
+}
+
+/// Evaluates the (possibly rational) B-spline at parameter `t` using de Boor's algorithm.
+pub(crate) fn spline_point_at(spline: &Spline, knots: &[f64], t: f64) -> Point {
+    let degree = spline.degree_of_curve.max(1) as usize;
+    let count = spline.control_points.len();
+    if count == 0 {
+        return Point::origin();
+    }
+    if count <= degree {
+        return spline.control_points[0].clone();
+    }
+
+    // find the knot span
+    let mut span = degree;
+    while span < count - 1 && t >= knots[span + 1] {
+        span += 1;
+    }
+
+    let weight = |i: usize| -> f64 {
+        if spline.weight_values.len() == count {
+            spline.weight_values[i]
+        } else {
+            1.0
+        }
+    };
+    let mut d = (0..=degree)
+        .map(|j| {
+            let index = j + span - degree;
+            let p = &spline.control_points[index];
+            let w = weight(index);
+            [p.x * w, p.y * w, p.z * w, w]
+        })
+        .collect::<Vec<_>>();
+    for r in 1..=degree {
+        for j in (r..=degree).rev() {
+            let i = j + span - degree;
+            let denominator = knots[i + degree + 1 - r] - knots[i];
+            let alpha = if denominator.abs() < EPSILON {
+                0.0
+            } else {
+                (t - knots[i]) / denominator
+            };
+            let previous = d[j - 1];
+            for (value, previous) in d[j].iter_mut().zip(previous.iter()) {
+                *value = (1.0 - alpha) * previous + alpha * *value;
+            }
+        }
+    }
+
+    let [x, y, z, w] = d[degree];
+    if w.abs() < EPSILON {
+        Point::new(x, y, z)
+    } else {
+        Point::new(x / w, y / w, z / w)
+    }
+}
+
+/// Approximates the spline with a series of points, sampling each knot span `segments_per_span`
+/// times.  Splines defined only by fit points return those fit points.
+pub(crate) fn tessellate_spline(spline: &Spline, segments_per_span: usize) -> Vec<Point> {
+    if spline.control_points.is_empty() {
+        return spline.fit_points.clone();
+    }
+
+    let degree = spline.degree_of_curve.max(1) as usize;
+    let count = spline.control_points.len();
+    if count <= degree {
+        return spline.control_points.clone();
+    }
+
+    let knots = spline_knots(spline);
+    let segments_per_span = segments_per_span.max(1);
+    let mut points = vec![];
+    for span in degree..count {
+        let t0 = knots[span];
+        let t1 = knots[span + 1];
+        if t1 - t0 < EPSILON {
+            continue;
+        }
+        for i in 0..segments_per_span {
+            let t = t0 + (t1 - t0) * i as f64 / segments_per_span as f64;
+            points.push(spline_point_at(spline, &knots, t));
+        }
+    }
+    points.push(spline_point_at(spline, &knots, knots[count]));
+    points
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::entities::Ellipse;
+    use crate::geometry::*;
+    use float_cmp::approx_eq;
+
+    #[test]
+    fn bulged_square_area() {
+        // unit square with the top edge bulged outward into a semicircle
+        let vertices = vec![
+            (0.0, 0.0, 0.0),
+            (1.0, 0.0, 0.0),
+            (1.0, 1.0, 1.0),
+            (0.0, 1.0, 0.0),
+        ];
+        let (area, _, cy) = bulged_loop_area_and_centroid(&vertices);
+        assert!(approx_eq!(f64, 1.0 + PI / 8.0, area, epsilon = 1e-9));
+        assert!(cy > 0.5);
+    }
+
+    #[test]
+    fn bulge_arc_is_centered_between_points() {
+        let arc = bulge_to_arc(1.0, 0.0, -1.0, 0.0, 1.0).unwrap();
+        assert!(approx_eq!(f64, 0.0, arc.center_x, epsilon = 1e-9));
+        assert!(approx_eq!(f64, 0.0, arc.center_y, epsilon = 1e-9));
+        assert!(approx_eq!(f64, 1.0, arc.radius, epsilon = 1e-9));
+        assert!(approx_eq!(f64, PI, arc.included_angle, epsilon = 1e-9));
+    }
+
+    #[test]
+    fn ellipse_parameter_range_is_limited_to_one_turn() {
+        let range = |start_parameter: f64, end_parameter: f64| {
+            ellipse_parameter_range(&Ellipse {
+                start_parameter,
+                end_parameter,
+                ..Default::default()
+            })
+        };
+        let (start, end) = range(-PI / 2.0, 5.0 * PI);
+        assert!(approx_eq!(f64, 1.5 * PI, start, epsilon = 1e-9));
+        assert!(approx_eq!(f64, 3.0 * PI, end, epsilon = 1e-9));
+        // equal parameters sweep the whole ellipse
+        let (start, end) = range(1.0, 1.0);
+        assert!(approx_eq!(f64, 2.0 * PI, end - start, epsilon = 1e-9));
+
+        for (start_parameter, end_parameter) in [
+            (1e20, 0.0),
+            (0.0, 1e300),
+            (-1e12, 1e12),
+            (f64::MAX, f64::MIN),
+        ] {
+            let (start, end) = range(start_parameter, end_parameter);
+            assert!((0.0..2.0 * PI).contains(&start));
+            assert!(end > start && end - start <= 2.0 * PI);
+        }
+        for (start_parameter, end_parameter) in [
+            (f64::NAN, 1.0),
+            (0.0, f64::INFINITY),
+            (f64::NEG_INFINITY, f64::NAN),
+        ] {
+            assert_eq!((0.0, 2.0 * PI), range(start_parameter, end_parameter));
+        }
+    }
+
+    #[test]
+    fn sweep_wraps_around_zero() {
+        assert!(approx_eq!(f64, 90.0, sweep_degrees(315.0, 45.0)));
+        assert!(approx_eq!(f64, 360.0, sweep_degrees(0.0, 360.0)));
+    }
+}
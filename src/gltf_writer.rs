@@ -0,0 +1,945 @@
+// writes the 3D content of a drawing as a glTF 2.0 scene
+
+use std::collections::HashMap;
+use std::io::Write;
+
+use byteorder::{LittleEndian, WriteBytesExt};
+
+use crate::entities::*;
+use crate::geometry::*;
+use crate::json::JsonValue;
+use crate::objects::*;
+use crate::render::*;
+use crate::{Drawing, DrawingItem, DxfResult, Handle, Point};
+
+const MODE_LINES: u32 = 1;
+const MODE_TRIANGLES: u32 = 4;
+
+const COMPONENT_FLOAT: u32 = 5126;
+const COMPONENT_UNSIGNED_INT: u32 = 5125;
+
+const TARGET_ARRAY_BUFFER: u32 = 34962;
+const TARGET_ELEMENT_ARRAY_BUFFER: u32 = 34963;
+
+const GLB_MAGIC: u32 = 0x4654_6C67;
+const GLB_CHUNK_JSON: u32 = 0x4E4F_534A;
+const GLB_CHUNK_BIN: u32 = 0x004E_4942;
+
+/// Options used when exporting a `Drawing` as glTF.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct GltfOptions {
+    /// The name of the `Layout` to export, or `None` to export model space.
+    pub layout_name: Option<String>,
+    /// The color used for color index 7, which is drawn as either black or white depending on
+    /// the background.
+    pub foreground_color: (u8, u8, u8),
+}
+
+/// The properties an entity in a block takes from the block reference.
+#[derive(Clone)]
+struct Inherited {
+    color: (u8, u8, u8),
+    layer: String,
+    material: Option<Handle>,
+}
+
+/// Geometry drawn with one material, as triangles or as line segments.
+struct Primitive {
+    material: usize,
+    mode: u32,
+    positions: Vec<Point>,
+    indices: Vec<u32>,
+}
+
+#[derive(Default)]
+struct MeshBuilder {
+    primitives: Vec<Primitive>,
+}
+
+impl MeshBuilder {
+    fn primitive(&mut self, material: usize, mode: u32) -> &mut Primitive {
+        let index = match self
+            .primitives
+            .iter()
+            .position(|p| p.material == material && p.mode == mode)
+        {
+            Some(index) => index,
+            None => {
+                self.primitives.push(Primitive {
+                    material,
+                    mode,
+                    positions: vec![],
+                    indices: vec![],
+                });
+                self.primitives.len() - 1
+            }
+        };
+        &mut self.primitives[index]
+    }
+    fn add_face(&mut self, material: usize, face: &[Point]) {
+        let primitive = self.primitive(material, MODE_TRIANGLES);
+        for (_, corners) in triangles(face) {
+            for corner in corners {
+                primitive.indices.push(primitive.positions.len() as u32);
+                primitive.positions.push(corner);
+            }
+        }
+    }
+    fn add_path(&mut self, material: usize, points: &[Point], is_closed: bool) {
+        let primitive = self.primitive(material, MODE_LINES);
+        let first = primitive.positions.len() as u32;
+        primitive.positions.extend_from_slice(points);
+        let count = points.len() as u32;
+        let segment_count = if is_closed { count } else { count - 1 };
+        for i in 0..segment_count {
+            primitive.indices.push(first + i);
+            primitive.indices.push(first + (i + 1) % count);
+        }
+    }
+}
+
+/// A mesh whose positions are relative to `center`, which keeps them precise as 32-bit floats.
+struct Mesh {
+    name: String,
+    primitives: Vec<Primitive>,
+    center: Point,
+}
+
+/// A block name and the color, layer and material handle its contents inherit.
+type BlockMeshKey = (String, (u8, u8, u8), String, Option<u64>);
+
+#[derive(Clone, Default)]
+struct Node {
+    name: String,
+    matrix: Option<[f64; 16]>,
+    translation: Option<Point>,
+    mesh: Option<usize>,
+    children: Vec<Node>,
+}
+
+impl Node {
+    /// Returns a node that draws a mesh at its original position.
+    fn for_mesh(name: &str, mesh: &Mesh, index: usize) -> Node {
+        Node {
+            name: String::from(name),
+            translation: Some(mesh.center.clone()),
+            mesh: Some(index),
+            ..Default::default()
+        }
+    }
+}
+
+pub(crate) struct GltfWriter<'a> {
+    drawing: &'a Drawing,
+    styles: StyleResolver<'a>,
+    /// The `Material` object, if any, and color of each material.
+    materials: Vec<(Option<Handle>, (u8, u8, u8))>,
+    meshes: Vec<Mesh>,
+    /// The mesh drawn for each block and the properties its contents inherit.
+    block_meshes: HashMap<BlockMeshKey, Option<usize>>,
+    root: Node,
+}
+
+impl<'a> GltfWriter<'a> {
+    pub fn new(drawing: &'a Drawing, options: &GltfOptions) -> DxfResult<Self> {
+        let mut writer = GltfWriter {
+            drawing,
+            styles: StyleResolver {
+                drawing,
+                default_line_weight: 0.25,
+                foreground_color: options.foreground_color,
+            },
+            materials: vec![],
+            meshes: vec![],
+            block_meshes: HashMap::new(),
+            root: Node {
+                name: options
+                    .layout_name
+                    .clone()
+                    .unwrap_or_else(|| String::from("Model")),
+                // drawings are z-up and glTF is y-up
+                matrix: Some([
+                    1.0, 0.0, 0.0, 0.0, 0.0, 0.0, -1.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0, 0.0, 1.0,
+                ]),
+                ..Default::default()
+            },
+        };
+
+        // each layer gets a node holding its geometry and block references
+        let top_level = Inherited {
+            color: options.foreground_color,
+            layer: String::from("0"),
+            material: None,
+        };
+        let mut layers: Vec<(String, MeshBuilder, Vec<Node>)> = vec![];
+        for entity in layout_entities(drawing, options.layout_name.as_deref())? {
+            let layer = &entity.common.layer;
+            if !entity.common.is_visible || !writer.styles.is_layer_on(layer) {
+                continue;
+            }
+            let index = match layers.iter().position(|(name, _, _)| name == layer) {
+                Some(index) => index,
+                None => {
+                    layers.push((layer.clone(), MeshBuilder::default(), vec![]));
+                    layers.len() - 1
+                }
+            };
+            let (_, builder, children) = &mut layers[index];
+            match entity.specific {
+                EntityType::Insert(ref insert) => {
+                    children.extend(writer.insert_nodes(entity, insert, false, &top_level, 0))
+                }
+                _ => writer.add_geometry(builder, entity, false, &top_level),
+            }
+        }
+        for (name, builder, children) in layers {
+            let mut node = Node {
+                name: name.clone(),
+                ..Default::default()
+            };
+            if let Some(index) = writer.finish_mesh(&name, builder) {
+                node.children
+                    .push(Node::for_mesh(&name, &writer.meshes[index], index));
+            }
+            node.children.extend(children);
+            if !node.children.is_empty() {
+                writer.root.children.push(node);
+            }
+        }
+        Ok(writer)
+    }
+    /// Returns the nodes that place a block for each cell of a block reference.
+    fn insert_nodes(
+        &mut self,
+        entity: &Entity,
+        insert: &Insert,
+        in_block: bool,
+        inherited: &Inherited,
+        depth: usize,
+    ) -> Vec<Node> {
+        let drawing = self.drawing;
+        let block = match drawing
+            .blocks()
+            .find(|b| b.name.eq_ignore_ascii_case(&insert.name))
+        {
+            Some(block) => block,
+            None => return vec![],
+        };
+        if depth >= MAX_BLOCK_DEPTH {
+            return vec![];
+        }
+
+        let layer = self.layer_name(entity, in_block, inherited);
+        let contents = Inherited {
+            color: self
+                .styles
+                .color(entity, in_block)
+                .unwrap_or(inherited.color),
+            material: self.material_handle(entity, &layer, inherited),
+            layer,
+        };
+        let key = (
+            block.name.to_uppercase(),
+            contents.color,
+            contents.layer.to_uppercase(),
+            contents.material.map(|h| h.0),
+        );
+        let cached_mesh = self.block_meshes.get(&key).copied();
+        let mut builder = MeshBuilder::default();
+        let mut children = vec![];
+        for block_entity in &block.entities {
+            let layer = self.layer_name(block_entity, true, &contents);
+            if !block_entity.common.is_visible || !self.styles.is_layer_on(&layer) {
+                continue;
+            }
+            match block_entity.specific {
+                EntityType::Insert(ref nested) => children.extend(self.insert_nodes(
+                    block_entity,
+                    nested,
+                    true,
+                    &contents,
+                    depth + 1,
+                )),
+                _ if cached_mesh.is_none() => {
+                    self.add_geometry(&mut builder, block_entity, true, &contents)
+                }
+                _ => (),
+            }
+        }
+        let mesh = match cached_mesh {
+            Some(mesh) => mesh,
+            None => {
+                let mesh = self.finish_mesh(&block.name, builder);
+                self.block_meshes.insert(key, mesh);
+                mesh
+            }
+        };
+        if let Some(index) = mesh {
+            children.insert(0, Node::for_mesh(&block.name, &self.meshes[index], index));
+        }
+
+        let mut nodes = vec![];
+        for row in 0..insert.row_count.max(1) {
+            for column in 0..insert.column_count.max(1) {
+                let placement = Placement::for_insert(insert, &block.base_point, row, column);
+                nodes.push(Node {
+                    name: block.name.clone(),
+                    matrix: Some(placement.column_major()),
+                    children: children.clone(),
+                    ..Default::default()
+                });
+            }
+        }
+        nodes
+    }
+    /// Adds an entity's surfaces as triangles or, if it has none, its curves as lines.
+    fn add_geometry(
+        &mut self,
+        builder: &mut MeshBuilder,
+        entity: &Entity,
+        in_block: bool,
+        inherited: &Inherited,
+    ) {
+        let faces = surface_faces(entity);
+        let paths = if faces.is_empty() {
+            curve_paths(entity, SPLINE_SEGMENTS_PER_SPAN)
+        } else {
+            vec![]
+        };
+        if faces.is_empty() && paths.is_empty() {
+            return;
+        }
+
+        let layer = self.layer_name(entity, in_block, inherited);
+        let color = self
+            .styles
+            .color(entity, in_block)
+            .unwrap_or(inherited.color);
+        let key = (self.material_handle(entity, &layer, inherited), color);
+        let material = match self.materials.iter().position(|m| *m == key) {
+            Some(material) => material,
+            None => {
+                self.materials.push(key);
+                self.materials.len() - 1
+            }
+        };
+        for face in faces {
+            builder.add_face(material, &dedupe_loop(&face));
+        }
+        for (points, is_closed) in paths {
+            builder.add_path(material, &points, is_closed);
+        }
+    }
+    fn finish_mesh(&mut self, name: &str, builder: MeshBuilder) -> Option<usize> {
+        let mut primitives = builder.primitives;
+        primitives.retain(|p| !p.indices.is_empty());
+        let mut points = primitives.iter().flat_map(|p| &p.positions);
+        let first = points.next()?.clone();
+        let (min, max) = points.fold((first.clone(), first), |(min, max), p| {
+            (
+                Point::new(min.x.min(p.x), min.y.min(p.y), min.z.min(p.z)),
+                Point::new(max.x.max(p.x), max.y.max(p.y), max.z.max(p.z)),
+            )
+        });
+        let center = Point::new(
+            (min.x + max.x) / 2.0,
+            (min.y + max.y) / 2.0,
+            (min.z + max.z) / 2.0,
+        );
+        for primitive in &mut primitives {
+            for p in &mut primitive.positions {
+                *p = Point::new(p.x - center.x, p.y - center.y, p.z - center.z);
+            }
+        }
+        self.meshes.push(Mesh {
+            name: String::from(name),
+            primitives,
+            center,
+        });
+        Some(self.meshes.len() - 1)
+    }
+    /// Returns the layer of an entity; entities on layer `0` in a block take the layer of the
+    /// block reference.
+    fn layer_name(&self, entity: &Entity, in_block: bool, inherited: &Inherited) -> String {
+        if in_block && entity.common.layer == "0" {
+            inherited.layer.clone()
+        } else {
+            entity.common.layer.clone()
+        }
+    }
+    fn material(&self, handle: Handle) -> Option<&'a Material> {
+        if handle.is_empty() {
+            return None;
+        }
+        match self.drawing.item_by_handle(handle) {
+            Some(DrawingItem::Object(Object {
+                specific: ObjectType::Material(ref material),
+                ..
+            })) => Some(material),
+            _ => None,
+        }
+    }
+    /// Returns the handle of the `Material` that's applied to an entity, following the `ByLayer`
+    /// and `ByBlock` materials.
+    fn material_handle(
+        &self,
+        entity: &Entity,
+        layer: &str,
+        inherited: &Inherited,
+    ) -> Option<Handle> {
+        let is_named = |material: &Material, name: &str| material.name.eq_ignore_ascii_case(name);
+        let handle = entity.common.__material_handle;
+        match self.material(handle) {
+            Some(m) if is_named(m, "ByBlock") => inherited.material,
+            Some(m) if !is_named(m, "ByLayer") => Some(handle),
+            // entities without a material use their layer's
+            _ => {
+                let handle = self.styles.layer(layer)?.__material_handle;
+                match self.material(handle) {
+                    Some(m) if !is_named(m, "ByLayer") && !is_named(m, "ByBlock") => Some(handle),
+                    _ => None,
+                }
+            }
+        }
+    }
+    fn material_json(&self, handle: Option<Handle>, color: (u8, u8, u8)) -> JsonValue {
+        let material = handle.and_then(|h| self.material(h));
+        let (r, g, b) = match material {
+            Some(m) if m.override_diffuse_color => rgb_components(m.diffuse_color_value),
+            _ => color,
+        };
+        let alpha = material.map_or(1.0, |m| m.opacity_factor.clamp(0.0, 1.0));
+        let roughness = material.map_or(1.0, |m| (1.0 - m.specular_gloss_factor).clamp(0.0, 1.0));
+        let name = match material {
+            Some(m) => m.name.clone(),
+            None => format!("#{r:02x}{g:02x}{b:02x}"),
+        };
+        let mut members = vec![
+            (String::from("name"), JsonValue::String(name)),
+            (
+                String::from("pbrMetallicRoughness"),
+                JsonValue::Object(vec![
+                    (
+                        String::from("baseColorFactor"),
+                        numbers(&[
+                            srgb_to_linear(r),
+                            srgb_to_linear(g),
+                            srgb_to_linear(b),
+                            alpha,
+                        ]),
+                    ),
+                    (String::from("metallicFactor"), JsonValue::Number(0.0)),
+                    (
+                        String::from("roughnessFactor"),
+                        JsonValue::Number(roughness),
+                    ),
+                ]),
+            ),
+            // the winding of CAD faces isn't reliable, so both sides are drawn
+            (String::from("doubleSided"), JsonValue::Bool(true)),
+        ];
+        if alpha < 1.0 {
+            members.push((
+                String::from("alphaMode"),
+                JsonValue::String(String::from("BLEND")),
+            ));
+        }
+        JsonValue::Object(members)
+    }
+    /// Returns the glTF document and the contents of its buffer.
+    fn document(&self) -> (JsonValue, Vec<u8>) {
+        let mut buffer = vec![];
+        let mut buffer_views = vec![];
+        let mut accessors = vec![];
+        let mut meshes = vec![];
+        for mesh in &self.meshes {
+            let mut primitives = vec![];
+            for primitive in &mesh.primitives {
+                let positions = primitive
+                    .positions
+                    .iter()
+                    .map(|p| [p.x as f32, p.y as f32, p.z as f32])
+                    .collect::<Vec<_>>();
+                let mut min = positions[0];
+                let mut max = positions[0];
+                for p in &positions {
+                    for i in 0..3 {
+                        min[i] = min[i].min(p[i]);
+                        max[i] = max[i].max(p[i]);
+                    }
+                }
+                let offset = buffer.len();
+                for value in positions.iter().flatten() {
+                    buffer.write_f32::<LittleEndian>(*value).unwrap();
+                }
+                let position_accessor = accessors.len();
+                buffer_views.push(buffer_view(offset, buffer.len(), TARGET_ARRAY_BUFFER));
+                accessors.push(JsonValue::Object(vec![
+                    (
+                        String::from("bufferView"),
+                        JsonValue::Number((buffer_views.len() - 1) as f64),
+                    ),
+                    (
+                        String::from("componentType"),
+                        JsonValue::Number(f64::from(COMPONENT_FLOAT)),
+                    ),
+                    (
+                        String::from("count"),
+                        JsonValue::Number(positions.len() as f64),
+                    ),
+                    (
+                        String::from("type"),
+                        JsonValue::String(String::from("VEC3")),
+                    ),
+                    (String::from("min"), numbers(&min.map(f64::from))),
+                    (String::from("max"), numbers(&max.map(f64::from))),
+                ]));
+
+                let offset = buffer.len();
+                for index in &primitive.indices {
+                    buffer.write_u32::<LittleEndian>(*index).unwrap();
+                }
+                buffer_views.push(buffer_view(
+                    offset,
+                    buffer.len(),
+                    TARGET_ELEMENT_ARRAY_BUFFER,
+                ));
+                accessors.push(JsonValue::Object(vec![
+                    (
+                        String::from("bufferView"),
+                        JsonValue::Number((buffer_views.len() - 1) as f64),
+                    ),
+                    (
+                        String::from("componentType"),
+                        JsonValue::Number(f64::from(COMPONENT_UNSIGNED_INT)),
+                    ),
+                    (
+                        String::from("count"),
+                        JsonValue::Number(primitive.indices.len() as f64),
+                    ),
+                    (
+                        String::from("type"),
+                        JsonValue::String(String::from("SCALAR")),
+                    ),
+                ]));
+
+                primitives.push(JsonValue::Object(vec![
+                    (
+                        String::from("attributes"),
+                        JsonValue::Object(vec![(
+                            String::from("POSITION"),
+                            JsonValue::Number(position_accessor as f64),
+                        )]),
+                    ),
+                    (
+                        String::from("indices"),
+                        JsonValue::Number((position_accessor + 1) as f64),
+                    ),
+                    (
+                        String::from("material"),
+                        JsonValue::Number(primitive.material as f64),
+                    ),
+                    (
+                        String::from("mode"),
+                        JsonValue::Number(f64::from(primitive.mode)),
+                    ),
+                ]));
+            }
+            meshes.push(JsonValue::Object(vec![
+                (String::from("name"), JsonValue::String(mesh.name.clone())),
+                (String::from("primitives"), JsonValue::Array(primitives)),
+            ]));
+        }
+
+        let mut nodes = vec![];
+        add_node(&self.root, &mut nodes);
+        let materials = self
+            .materials
+            .iter()
+            .map(|(handle, color)| self.material_json(*handle, *color))
+            .collect::<Vec<_>>();
+
+        let mut document = vec![
+            (
+                String::from("asset"),
+                JsonValue::Object(vec![
+                    (
+                        String::from("version"),
+                        JsonValue::String(String::from("2.0")),
+                    ),
+                    (
+                        String::from("generator"),
+                        JsonValue::String(String::from("dxf-rs")),
+                    ),
+                ]),
+            ),
+            (String::from("scene"), JsonValue::Number(0.0)),
+            (
+                String::from("scenes"),
+                JsonValue::Array(vec![JsonValue::Object(vec![(
+                    String::from("nodes"),
+                    JsonValue::Array(vec![JsonValue::Number(0.0)]),
+                )])]),
+            ),
+            (String::from("nodes"), JsonValue::Array(nodes)),
+        ];
+        // glTF doesn't allow empty arrays
+        for (name, values) in [
+            ("meshes", meshes),
+            ("materials", materials),
+            ("accessors", accessors),
+            ("bufferViews", buffer_views),
+        ] {
+            if !values.is_empty() {
+                document.push((String::from(name), JsonValue::Array(values)));
+            }
+        }
+        (JsonValue::Object(document), buffer)
+    }
+    /// Writes a `.gltf` document with its buffer embedded as a data URI.
+    pub fn write_gltf<T>(&self, writer: &mut T) -> DxfResult<()>
+    where
+        T: Write + ?Sized,
+    {
+        let (mut document, buffer) = self.document();
+        if !buffer.is_empty() {
+            let uri = format!(
+                "data:application/octet-stream;base64,{}",
+                base64_encode(&buffer)
+            );
+            add_buffer(&mut document, buffer.len(), Some(uri));
+        }
+        let mut json = String::new();
+        document.write(&mut json);
+        writer.write_all(json.as_bytes())?;
+        Ok(())
+    }
+    /// Writes a binary `.glb` file holding the document and its buffer.
+    pub fn write_glb<T>(&self, writer: &mut T) -> DxfResult<()>
+    where
+        T: Write + ?Sized,
+    {
+        let (mut document, mut buffer) = self.document();
+        if !buffer.is_empty() {
+            add_buffer(&mut document, buffer.len(), None);
+        }
+        let mut json = String::new();
+        document.write(&mut json);
+        // chunks are padded to 4 bytes, JSON with spaces and binary data with zeros
+        let mut json = json.into_bytes();
+        while !json.len().is_multiple_of(4) {
+            json.push(b' ');
+        }
+        while !buffer.len().is_multiple_of(4) {
+            buffer.push(0);
+        }
+        let mut length = 12 + 8 + json.len();
+        if !buffer.is_empty() {
+            length += 8 + buffer.len();
+        }
+        writer.write_u32::<LittleEndian>(GLB_MAGIC)?;
+        writer.write_u32::<LittleEndian>(2)?;
+        writer.write_u32::<LittleEndian>(length as u32)?;
+        writer.write_u32::<LittleEndian>(json.len() as u32)?;
+        writer.write_u32::<LittleEndian>(GLB_CHUNK_JSON)?;
+        writer.write_all(&json)?;
+        if !buffer.is_empty() {
+            writer.write_u32::<LittleEndian>(buffer.len() as u32)?;
+            writer.write_u32::<LittleEndian>(GLB_CHUNK_BIN)?;
+            writer.write_all(&buffer)?;
+        }
+        Ok(())
+    }
+}
+
+/// Adds a node and its descendants to the flattened list, returning the node's index.
+fn add_node(node: &Node, nodes: &mut Vec<JsonValue>) -> usize {
+    let index = nodes.len();
+    nodes.push(JsonValue::Null);
+    let children = node
+        .children
+        .iter()
+        .map(|child| JsonValue::Number(add_node(child, nodes) as f64))
+        .collect::<Vec<_>>();
+    let mut members = vec![(String::from("name"), JsonValue::String(node.name.clone()))];
+    if let Some(matrix) = node.matrix {
+        members.push((String::from("matrix"), numbers(&matrix)));
+    }
+    if let Some(ref t) = node.translation {
+        members.push((String::from("translation"), numbers(&[t.x, t.y, t.z])));
+    }
+    if let Some(mesh) = node.mesh {
+        members.push((String::from("mesh"), JsonValue::Number(mesh as f64)));
+    }
+    if !children.is_empty() {
+        members.push((String::from("children"), JsonValue::Array(children)));
+    }
+    nodes[index] = JsonValue::Object(members);
+    index
+}
+
+fn add_buffer(document: &mut JsonValue, length: usize, uri: Option<String>) {
+    let mut buffer = vec![(String::from("byteLength"), JsonValue::Number(length as f64))];
+    if let Some(uri) = uri {
+        buffer.push((String::from("uri"), JsonValue::String(uri)));
+    }
+    if let JsonValue::Object(ref mut members) = document {
+        members.push((
+            String::from("buffers"),
+            JsonValue::Array(vec![JsonValue::Object(buffer)]),
+        ));
+    }
+}
+
+fn buffer_view(start: usize, end: usize, target: u32) -> JsonValue {
+    JsonValue::Object(vec![
+        (String::from("buffer"), JsonValue::Number(0.0)),
+        (String::from("byteOffset"), JsonValue::Number(start as f64)),
+        (
+            String::from("byteLength"),
+            JsonValue::Number((end - start) as f64),
+        ),
+        (String::from("target"), JsonValue::Number(f64::from(target))),
+    ])
+}
+
+fn numbers(values: &[f64]) -> JsonValue {
+    JsonValue::Array(values.iter().map(|v| JsonValue::Number(*v)).collect())
+}
+
+/// Converts an sRGB color component to the linear value glTF expects.
+fn srgb_to_linear(component: u8) -> f64 {
+    let c = f64::from(component) / 255.0;
+    if c <= 0.04045 {
+        c / 12.92
+    } else {
+        ((c + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+fn base64_encode(data: &[u8]) -> String {
+    const ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut result = String::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3) {
+        let bytes = [
+            chunk[0],
+            chunk.get(1).copied().unwrap_or(0),
+            chunk.get(2).copied().unwrap_or(0),
+        ];
+        let n = u32::from(bytes[0]) << 16 | u32::from(bytes[1]) << 8 | u32::from(bytes[2]);
+        for i in 0..4 {
+            if i <= chunk.len() {
+                result.push(ALPHABET[(n >> (18 - 6 * i) & 0x3F) as usize] as char);
+            } else {
+                result.push('=');
+            }
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::gltf_writer::*;
+    use crate::objects::{Material, Object, ObjectType};
+    use crate::tables::Layer;
+    use crate::{Block, Color};
+
+    fn to_gltf(drawing: &Drawing) -> JsonValue {
+        let mut buf = vec![];
+        drawing
+            .save_gltf(&mut buf, &GltfOptions::default())
+            .unwrap();
+        JsonValue::parse(std::str::from_utf8(&buf).unwrap()).unwrap()
+    }
+
+    fn array<'v>(value: &'v JsonValue, key: &str) -> &'v [JsonValue] {
+        value.get(key).unwrap().as_array().unwrap()
+    }
+
+    fn number(value: &JsonValue, key: &str) -> f64 {
+        value.get(key).unwrap().as_f64().unwrap()
+    }
+
+    fn triangle() -> Entity {
+        Entity::new(EntityType::Face3D(Face3D::new(
+            Point::new(0.0, 0.0, 0.0),
+            Point::new(1.0, 0.0, 0.0),
+            Point::new(0.0, 1.0, 0.0),
+            Point::new(0.0, 1.0, 0.0),
+        )))
+    }
+
+    #[test]
+    fn base64() {
+        assert_eq!("", base64_encode(b""));
+        assert_eq!("Zg==", base64_encode(b"f"));
+        assert_eq!("Zm8=", base64_encode(b"fo"));
+        assert_eq!("Zm9v", base64_encode(b"foo"));
+        assert_eq!("Zm9vYmFy", base64_encode(b"foobar"));
+    }
+
+    #[test]
+    fn faces_and_lines_by_layer() {
+        let mut drawing = Drawing::new();
+        drawing.add_layer(Layer {
+            name: String::from("site"),
+            color: Color::from_index(1),
+            ..Default::default()
+        });
+        let mut face = triangle();
+        face.common.layer = String::from("site");
+        drawing.add_entity(face);
+        let mut line = Entity::new(EntityType::Line(Line::new(
+            Point::new(10.0, 0.0, 0.0),
+            Point::new(12.0, 0.0, 0.0),
+        )));
+        line.common.layer = String::from("site");
+        line.common.color = Color::from_index(5);
+        drawing.add_entity(line);
+
+        let gltf = to_gltf(&drawing);
+        let nodes = array(&gltf, "nodes");
+        // the root, the layer and the layer's geometry
+        assert_eq!(3, nodes.len());
+        assert_eq!(Some("site"), nodes[1].get("name").unwrap().as_str());
+        let geometry = &nodes[2];
+        assert_eq!(0.0, number(geometry, "mesh"));
+        // positions are stored relative to the center of the mesh
+        let mut translation = String::new();
+        geometry.get("translation").unwrap().write(&mut translation);
+        assert_eq!("[6,0.5,0]", translation);
+
+        let primitives = array(&array(&gltf, "meshes")[0], "primitives");
+        assert_eq!(2, primitives.len());
+        assert_eq!(f64::from(MODE_TRIANGLES), number(&primitives[0], "mode"));
+        assert_eq!(f64::from(MODE_LINES), number(&primitives[1], "mode"));
+        let accessors = array(&gltf, "accessors");
+        assert_eq!(3.0, number(&accessors[0], "count"));
+        assert_eq!(2.0, number(&accessors[3], "count"));
+
+        let materials = array(&gltf, "materials");
+        let base_color = |material: &JsonValue| {
+            let mut color = String::new();
+            material
+                .get("pbrMetallicRoughness")
+                .unwrap()
+                .get("baseColorFactor")
+                .unwrap()
+                .write(&mut color);
+            color
+        };
+        assert_eq!("[1,0,0,1]", base_color(&materials[0]));
+        assert_eq!("[0,0,1,1]", base_color(&materials[1]));
+
+        let buffer = &array(&gltf, "buffers")[0];
+        // 3 positions and 3 indices, then 2 positions and 2 indices
+        assert_eq!(36.0 + 12.0 + 24.0 + 8.0, number(buffer, "byteLength"));
+        assert!(buffer
+            .get("uri")
+            .unwrap()
+            .as_str()
+            .unwrap()
+            .starts_with("data:application/octet-stream;base64,"));
+    }
+
+    #[test]
+    fn blocks_are_shared_meshes() {
+        let mut drawing = Drawing::new();
+        let mut block = Block {
+            name: String::from("tree"),
+            ..Default::default()
+        };
+        block.entities.push(triangle());
+        drawing.add_block(block);
+        for x in [0.0, 10.0] {
+            drawing.add_entity(Entity::new(EntityType::Insert(Insert {
+                name: String::from("tree"),
+                location: Point::new(x, 0.0, 0.0),
+                ..Default::default()
+            })));
+        }
+
+        let gltf = to_gltf(&drawing);
+        assert_eq!(1, array(&gltf, "meshes").len());
+        let nodes = array(&gltf, "nodes");
+        // the root, the layer, and each insert with its geometry
+        assert_eq!(6, nodes.len());
+        let inserts = nodes
+            .iter()
+            .filter(|n| {
+                n.get("matrix").is_some() && n.get("name").unwrap().as_str() == Some("tree")
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(2, inserts.len());
+        assert_eq!(10.0, array(inserts[1], "matrix")[12].as_f64().unwrap());
+        let meshes = nodes
+            .iter()
+            .filter_map(|n| n.get("mesh").and_then(|m| m.as_f64()))
+            .collect::<Vec<_>>();
+        assert_eq!(vec![0.0, 0.0], meshes);
+    }
+
+    #[test]
+    fn materials_override_colors() {
+        let mut drawing = Drawing::new();
+        let material = drawing
+            .add_object(Object::new(ObjectType::Material(Material {
+                name: String::from("Glass"),
+                override_diffuse_color: true,
+                diffuse_color_value: 0x00FF_FFFF,
+                opacity_factor: 0.25,
+                specular_gloss_factor: 0.75,
+                ..Default::default()
+            })))
+            .clone();
+        let mut layer = Layer {
+            name: String::from("windows"),
+            ..Default::default()
+        };
+        layer.__material_handle = material.common.handle;
+        drawing.add_layer(layer);
+        let mut face = triangle();
+        face.common.layer = String::from("windows");
+        drawing.add_entity(face);
+
+        let gltf = to_gltf(&drawing);
+        let material = &array(&gltf, "materials")[0];
+        assert_eq!(Some("Glass"), material.get("name").unwrap().as_str());
+        assert_eq!(Some("BLEND"), material.get("alphaMode").unwrap().as_str());
+        let pbr = material.get("pbrMetallicRoughness").unwrap();
+        assert_eq!(0.25, number(pbr, "roughnessFactor"));
+        assert_eq!(0.25, array(pbr, "baseColorFactor")[3].as_f64().unwrap());
+    }
+
+    #[test]
+    fn glb_container() {
+        let mut drawing = Drawing::new();
+        drawing.add_entity(triangle());
+        let mut buf = vec![];
+        drawing.save_glb(&mut buf, &GltfOptions::default()).unwrap();
+        assert_eq!(b"glTF", &buf[0..4]);
+        assert_eq!(2, u32::from_le_bytes([buf[4], buf[5], buf[6], buf[7]]));
+        let length = u32::from_le_bytes([buf[8], buf[9], buf[10], buf[11]]) as usize;
+        assert_eq!(buf.len(), length);
+        let json_length = u32::from_le_bytes([buf[12], buf[13], buf[14], buf[15]]) as usize;
+        assert_eq!(0, json_length % 4);
+        assert_eq!(b"JSON", &buf[16..20]);
+        let json = std::str::from_utf8(&buf[20..20 + json_length]).unwrap();
+        let gltf = JsonValue::parse(json).unwrap();
+        let buffer = &array(&gltf, "buffers")[0];
+        assert!(buffer.get("uri").is_none());
+        assert_eq!(48.0, number(buffer, "byteLength"));
+        let bin = &buf[20 + json_length..];
+        assert_eq!(b"BIN\0", &bin[4..8]);
+        assert_eq!(48 + 8, bin.len());
+    }
+
+    #[test]
+    fn empty_drawing() {
+        let gltf = to_gltf(&Drawing::new());
+        assert_eq!(1, array(&gltf, "nodes").len());
+        assert!(gltf.get("meshes").is_none());
+        assert!(gltf.get("buffers").is_none());
+    }
+}
@@ -32,19 +32,26 @@ impl Header {
                             let last_header_variable = pair.assert_string()?;
                             loop {
                                 match iter.next() {
-                                    Some(Ok(pair)) => {
+                                    Some(Ok(mut pair)) => {
                                         if pair.code == 0 || pair.code == 9 {
                                             // ENDSEC or a new header variable
                                             iter.put_back(Ok(pair));
                                             break;
                                         } else {
-                                            header
-                                                .set_header_value(&last_header_variable, &pair)?;
+                                            header.set_header_value(
+                                                &last_header_variable,
+                                                &mut pair,
+                                            )?;
                                             if last_header_variable == "$ACADVER"
                                                 && header.version >= AcadVersion::R2007
                                             {
                                                 iter.read_as_utf8();
                                             }
+                                            if last_header_variable == "$DWGCODEPAGE"
+                                                && header.version < AcadVersion::R2007
+                                            {
+                                                iter.read_with_code_page(&header.drawing_code_page);
+                                            }
                                         }
                                     }
                                     Some(Err(e)) => return Err(e),
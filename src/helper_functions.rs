@@ -234,7 +234,7 @@ pub(crate) fn bool_from_clipping(c: XrefClippingBoundaryVisibility) -> bool {
     c != XrefClippingBoundaryVisibility::NotDisplayedNotPlotted
 }
 
-pub(crate) fn parse_f64(s: String, offset: usize) -> DxfResult<f64> {
+pub(crate) fn parse_f64(s: &str, offset: usize) -> DxfResult<f64> {
     match s.trim().parse::<f64>() {
         Ok(d) => Ok(d),
         Err(e) => Err(DxfError::ParseFloatError(e, offset)),
@@ -244,10 +244,10 @@ pub(crate) fn parse_f64(s: String, offset: usize) -> DxfResult<f64> {
 #[test]
 #[allow(clippy::float_cmp)]
 fn parse_f64_test() {
-    assert_eq!(2.5, parse_f64("  2.5 ".to_string(), 0).unwrap());
+    assert_eq!(2.5, parse_f64("  2.5 ", 0).unwrap());
 }
 
-pub(crate) fn parse_i32(s: String, offset: usize) -> DxfResult<i32> {
+pub(crate) fn parse_i32(s: &str, offset: usize) -> DxfResult<i32> {
     match s.trim().parse::<i32>() {
         Ok(i) => Ok(i),
         Err(e) => Err(DxfError::ParseIntError(e, offset)),
@@ -256,10 +256,10 @@ pub(crate) fn parse_i32(s: String, offset: usize) -> DxfResult<i32> {
 
 #[test]
 fn parse_i32_test() {
-    assert_eq!(2, parse_i32("  2 ".to_string(), 0).unwrap());
+    assert_eq!(2, parse_i32("  2 ", 0).unwrap());
 }
 
-pub(crate) fn parse_i64(s: String, offset: usize) -> DxfResult<i64> {
+pub(crate) fn parse_i64(s: &str, offset: usize) -> DxfResult<i64> {
     match s.trim().parse::<i64>() {
         Ok(l) => Ok(l),
         Err(e) => Err(DxfError::ParseIntError(e, offset)),
@@ -268,11 +268,16 @@ pub(crate) fn parse_i64(s: String, offset: usize) -> DxfResult<i64> {
 
 #[test]
 fn parse_i64_test() {
-    assert_eq!(2, parse_i64("  2 ".to_string(), 0).unwrap());
+    assert_eq!(2, parse_i64("  2 ", 0).unwrap());
 }
 
-pub(crate) fn parse_i16(s: String, offset: usize) -> DxfResult<i16> {
-    match s.trim().parse::<f64>() {
+pub(crate) fn parse_i16(s: &str, offset: usize) -> DxfResult<i16> {
+    let s = s.trim();
+    if let Ok(s) = s.parse::<i16>() {
+        return Ok(s);
+    }
+
+    match s.parse::<f64>() {
         Ok(s) => Ok(s as i16),
         Err(e) => Err(DxfError::ParseFloatError(e, offset)),
     }
@@ -280,10 +285,10 @@ pub(crate) fn parse_i16(s: String, offset: usize) -> DxfResult<i16> {
 
 #[test]
 fn parse_i16_test() {
-    assert_eq!(2, parse_i16("  2 ".to_string(), 0).unwrap());
+    assert_eq!(2, parse_i16("  2 ", 0).unwrap());
 
     // some files write shorts as a double
-    assert_eq!(2, parse_i16(" 2.0 ".to_string(), 0).unwrap());
+    assert_eq!(2, parse_i16(" 2.0 ", 0).unwrap());
 }
 
 pub(crate) fn read_color_value(layer: &mut Layer, color: i16) -> Color {
@@ -301,6 +306,7 @@ where
 {
     let mut bytes = vec![];
     let mut skipping_bom = false;
+    #[allow(clippy::unbuffered_bytes)] // callers are expected to pass a buffered reader
     let reader_bytes = reader.bytes();
     for (i, b) in reader_bytes.enumerate() {
         let b = match b {
@@ -432,11 +438,6 @@ macro_rules! vec_last {
     };
 }
 
-pub(crate) fn read_u8_strict<T: Read>(reader: &mut T) -> DxfResult<u8> {
-    let u = try_from_option_io_result!(read_u8(reader));
-    Ok(u)
-}
-
 pub(crate) fn read_i16<T: Read>(reader: &mut T) -> DxfResult<i16> {
     let a = try_from_option_io_result!(read_u8(reader));
     let b = try_from_option_io_result!(read_u8(reader));
@@ -451,27 +452,6 @@ pub(crate) fn read_i32<T: Read>(reader: &mut T) -> DxfResult<i32> {
     Ok(LittleEndian::read_i32(&[a, b, c, d]))
 }
 
-#[allow(clippy::many_single_char_names)]
-pub(crate) fn read_i64<T: Read>(reader: &mut T) -> DxfResult<i64> {
-    let a = try_from_option_io_result!(read_u8(reader));
-    let b = try_from_option_io_result!(read_u8(reader));
-    let c = try_from_option_io_result!(read_u8(reader));
-    let d = try_from_option_io_result!(read_u8(reader));
-    let e = try_from_option_io_result!(read_u8(reader));
-    let f = try_from_option_io_result!(read_u8(reader));
-    let g = try_from_option_io_result!(read_u8(reader));
-    let h = try_from_option_io_result!(read_u8(reader));
-    Ok(LittleEndian::read_i64(&[a, b, c, d, e, f, g, h]))
-}
-
-pub(crate) fn read_f32<T: Read>(reader: &mut T) -> DxfResult<f32> {
-    let a = try_from_option_io_result!(read_u8(reader));
-    let b = try_from_option_io_result!(read_u8(reader));
-    let c = try_from_option_io_result!(read_u8(reader));
-    let d = try_from_option_io_result!(read_u8(reader));
-    Ok(LittleEndian::read_f32(&[a, b, c, d]))
-}
-
 #[allow(clippy::many_single_char_names)]
 pub(crate) fn read_f64<T: Read>(reader: &mut T) -> DxfResult<f64> {
     let a = try_from_option_io_result!(read_u8(reader));
@@ -509,7 +489,7 @@ pub(crate) fn parse_hex_string(data: &str, bytes: &mut Vec<u8>, offset: usize) -
         Ok(value)
     }
 
-    let mut complete_byte = data.len() % 2 != 0; // handles strings with an odd number of bytes
+    let mut complete_byte = !data.len().is_multiple_of(2); // handles strings with an odd number of bytes
     let mut current_byte = 0u8;
     for c in data.chars() {
         let value = char_to_value(c, offset)?;
@@ -550,7 +530,7 @@ pub mod tests {
         println!("reading from pairs: {pairs:?}");
         let iter = DirectCodePairIter::new(pairs);
         let iter = Box::new(iter);
-        unwrap_drawing(Drawing::load_from_iter(iter))
+        unwrap_drawing(Drawing::load_from_iter(iter, 1))
     }
 
     pub fn parse_drawing(s: &str) -> Drawing {
@@ -0,0 +1,563 @@
+// imports HP-GL and HP-GL/2 plotter files as drawing entities
+
+use std::io::Read;
+
+use crate::entities::*;
+use crate::enums::*;
+use crate::geometry::{bulge_to_arc, normalize_degrees};
+use crate::tables::{Layer, LineType};
+use crate::{Color, Drawing, DxfResult, LwPolylineVertex, Point};
+
+/// The size of a plotter unit in millimeters.
+pub(crate) const MILLIMETERS_PER_PLOTTER_UNIT: f64 = 0.025;
+
+/// The pattern length, in millimeters, of line types that don't specify one.
+const DEFAULT_PATTERN_LENGTH: f64 = 4.0;
+
+/// The prefix of the names of the line types created for the `LT` command.
+pub(crate) const LINE_TYPE_PREFIX: &str = "HPGL_";
+
+/// The character that ends a label unless changed by the `DT` command.
+const DEFAULT_LABEL_TERMINATOR: u8 = 0x03;
+
+/// Options used when importing an HP-GL file into a `Drawing`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct HpglImportOptions {
+    /// The number of drawing units per plotter unit.  When `None` the drawing is created in
+    /// millimeters, with 40 plotter units per millimeter.
+    pub scale: Option<f64>,
+}
+
+/// A vertex of the pen-down path being traced, with the bulge of the segment leaving it.
+struct PathVertex {
+    x: f64,
+    y: f64,
+    bulge: f64,
+}
+
+pub(crate) struct HpglReader<'a> {
+    options: &'a HpglImportOptions,
+    entities: Vec<Entity>,
+    pens: Vec<i32>,
+    line_types: Vec<(i32, f64)>,
+    position: (f64, f64),
+    is_pen_down: bool,
+    is_relative: bool,
+    pen: i32,
+    line_type: Option<i32>,
+    path: Vec<PathVertex>,
+}
+
+impl<'a> HpglReader<'a> {
+    pub fn new(options: &'a HpglImportOptions) -> Self {
+        HpglReader {
+            options,
+            entities: vec![],
+            pens: vec![],
+            line_types: vec![],
+            position: (0.0, 0.0),
+            is_pen_down: false,
+            is_relative: false,
+            pen: 1,
+            line_type: None,
+            path: vec![],
+        }
+    }
+    pub fn load<T>(&mut self, reader: &mut T) -> DxfResult<Drawing>
+    where
+        T: Read + ?Sized,
+    {
+        let mut data = vec![];
+        reader.read_to_end(&mut data)?;
+        let mut parser = Parser {
+            data: &data,
+            offset: 0,
+            label_terminator: DEFAULT_LABEL_TERMINATOR,
+        };
+        while let Some((mnemonic, parameters)) = parser.next_command() {
+            self.execute(&mnemonic, &parameters);
+        }
+        self.finish_path();
+
+        let mut drawing = Drawing::new();
+        if self.options.scale.is_none() {
+            drawing.header.default_drawing_units = Units::Millimeters;
+        }
+        for &pen in &self.pens {
+            drawing.add_layer(Layer {
+                name: pen_layer_name(pen),
+                color: pen_color(pen),
+                ..Default::default()
+            });
+        }
+        let units_per_millimeter = self.scale() / MILLIMETERS_PER_PLOTTER_UNIT;
+        for &(number, pattern_length) in &self.line_types {
+            drawing.add_line_type(line_type(number, pattern_length * units_per_millimeter));
+        }
+        for entity in self.entities.drain(..) {
+            drawing.add_entity(entity);
+        }
+        Ok(drawing)
+    }
+    fn scale(&self) -> f64 {
+        self.options.scale.unwrap_or(MILLIMETERS_PER_PLOTTER_UNIT)
+    }
+    fn execute(&mut self, mnemonic: &str, parameters: &[f64]) {
+        match mnemonic {
+            "IN" | "DF" => {
+                self.finish_path();
+                self.is_pen_down = false;
+                self.is_relative = false;
+                self.line_type = None;
+                if mnemonic == "IN" {
+                    self.position = (0.0, 0.0);
+                }
+            }
+            "SP" => {
+                self.finish_path();
+                self.pen = parameters.first().map_or(0, |&p| p as i32);
+            }
+            "LT" => {
+                self.finish_path();
+                self.line_type = match parameters.first() {
+                    Some(&number) => {
+                        let number = (number as i32).abs();
+                        if !self.line_types.iter().any(|&(n, _)| n == number) {
+                            let length =
+                                parameters.get(1).copied().unwrap_or(DEFAULT_PATTERN_LENGTH);
+                            self.line_types.push((number, length));
+                        }
+                        Some(number)
+                    }
+                    None => None,
+                };
+            }
+            "PA" | "PR" => {
+                self.is_relative = mnemonic == "PR";
+                self.move_through(parameters);
+            }
+            "PU" | "PD" => {
+                self.is_pen_down = mnemonic == "PD";
+                if !self.is_pen_down {
+                    self.finish_path();
+                }
+                self.move_through(parameters);
+            }
+            "CI" => {
+                if let Some(&radius) = parameters.first() {
+                    if self.pen > 0 {
+                        let (x, y) = self.position;
+                        let circle = Circle::new(self.point(x, y), radius.abs() * self.scale());
+                        self.add_entity(EntityType::Circle(circle));
+                    }
+                }
+            }
+            "AA" | "AR" => {
+                if let [x, y, sweep, ..] = *parameters {
+                    let center = if mnemonic == "AR" {
+                        (self.position.0 + x, self.position.1 + y)
+                    } else {
+                        (x, y)
+                    };
+                    self.arc_to(center, sweep);
+                }
+            }
+            _ => (),
+        }
+    }
+    /// Moves the pen through the coordinate pairs, tracing them if the pen is down.
+    fn move_through(&mut self, parameters: &[f64]) {
+        for pair in parameters.chunks_exact(2) {
+            let (x, y) = if self.is_relative {
+                (self.position.0 + pair[0], self.position.1 + pair[1])
+            } else {
+                (pair[0], pair[1])
+            };
+            if self.is_pen_down && self.pen > 0 && (x, y) != self.position {
+                self.start_path();
+                self.path.push(PathVertex { x, y, bulge: 0.0 });
+            }
+            self.position = (x, y);
+        }
+    }
+    /// Traces an arc about `center`, from the current position, by `sweep` degrees.
+    fn arc_to(&mut self, center: (f64, f64), sweep: f64) {
+        let (x, y) = self.position;
+        let radius = (x - center.0).hypot(y - center.1);
+        let start = (y - center.1).atan2(x - center.0);
+        let end = start + sweep.to_radians();
+        let end_point = (center.0 + radius * end.cos(), center.1 + radius * end.sin());
+        if self.is_pen_down && self.pen > 0 && radius > 0.0 && sweep != 0.0 {
+            if sweep.abs() >= 360.0 {
+                // full circles can't be described by a bulge
+                self.finish_path();
+                let circle = Circle::new(self.point(center.0, center.1), radius * self.scale());
+                self.add_entity(EntityType::Circle(circle));
+            } else {
+                self.start_path();
+                let last = self.path.len() - 1;
+                self.path[last].bulge = (sweep.to_radians() / 4.0).tan();
+                self.path.push(PathVertex {
+                    x: end_point.0,
+                    y: end_point.1,
+                    bulge: 0.0,
+                });
+            }
+        }
+        self.position = end_point;
+    }
+    fn start_path(&mut self) {
+        if self.path.is_empty() {
+            let (x, y) = self.position;
+            self.path.push(PathVertex { x, y, bulge: 0.0 });
+        }
+    }
+    /// Adds the traced path as a `Line`, `Arc` or `LwPolyline`.
+    fn finish_path(&mut self) {
+        let mut path = std::mem::take(&mut self.path);
+        if path.len() < 2 {
+            return;
+        }
+        let scale = self.scale();
+        if path.len() == 2 {
+            let (start, end) = (&path[0], &path[1]);
+            let specific = if start.bulge == 0.0 {
+                EntityType::Line(Line::new(
+                    self.point(start.x, start.y),
+                    self.point(end.x, end.y),
+                ))
+            } else {
+                match bulge_to_arc(start.x, start.y, end.x, end.y, start.bulge) {
+                    Some(arc) => {
+                        let (mut from, mut to) = (
+                            arc.start_angle.to_degrees(),
+                            (arc.start_angle + arc.included_angle).to_degrees(),
+                        );
+                        // clockwise arcs are stored from their end
+                        if arc.included_angle < 0.0 {
+                            std::mem::swap(&mut from, &mut to);
+                        }
+                        EntityType::Arc(Arc::new(
+                            self.point(arc.center_x, arc.center_y),
+                            arc.radius * scale,
+                            normalize_degrees(from),
+                            normalize_degrees(to),
+                        ))
+                    }
+                    None => return,
+                }
+            };
+            self.add_entity(specific);
+            return;
+        }
+
+        let mut poly = LwPolyline::default();
+        let (first, last) = (&path[0], &path[path.len() - 1]);
+        if first.x == last.x && first.y == last.y {
+            path.pop();
+            poly.set_is_closed(true);
+        }
+        poly.vertices = path
+            .iter()
+            .map(|v| LwPolylineVertex {
+                x: v.x * scale,
+                y: v.y * scale,
+                bulge: v.bulge,
+                ..Default::default()
+            })
+            .collect();
+        self.add_entity(EntityType::LwPolyline(poly));
+    }
+    fn point(&self, x: f64, y: f64) -> Point {
+        let scale = self.scale();
+        Point::new(x * scale, y * scale, 0.0)
+    }
+    fn add_entity(&mut self, specific: EntityType) {
+        if !self.pens.contains(&self.pen) {
+            self.pens.push(self.pen);
+        }
+        let mut entity = Entity::new(specific);
+        entity.common.layer = pen_layer_name(self.pen);
+        if let Some(number) = self.line_type {
+            entity.common.line_type_name = format!("{LINE_TYPE_PREFIX}{number}");
+        }
+        self.entities.push(entity);
+    }
+}
+
+/// Splits HP-GL data into commands and their numeric parameters.
+struct Parser<'a> {
+    data: &'a [u8],
+    offset: usize,
+    label_terminator: u8,
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<u8> {
+        self.data.get(self.offset).copied()
+    }
+    fn next_command(&mut self) -> Option<(String, Vec<f64>)> {
+        loop {
+            let c = self.peek()?;
+            self.offset += 1;
+            if c == 0x1B {
+                self.skip_escape_sequence();
+            } else if c.is_ascii_alphabetic() {
+                let second = self.peek().filter(|c| c.is_ascii_alphabetic())?;
+                self.offset += 1;
+                let mnemonic = String::from_utf8_lossy(&[c, second]).to_uppercase();
+                return Some(match mnemonic.as_str() {
+                    "LB" => {
+                        self.skip_past(self.label_terminator);
+                        (mnemonic, vec![])
+                    }
+                    "DT" => {
+                        if let Some(terminator) = self.peek().filter(|&c| c != b';') {
+                            self.label_terminator = terminator;
+                        }
+                        self.skip_past(b';');
+                        (mnemonic, vec![])
+                    }
+                    // encoded polylines aren't supported
+                    "PE" => {
+                        self.skip_past(b';');
+                        (mnemonic, vec![])
+                    }
+                    _ => {
+                        let parameters = self.parameters();
+                        (mnemonic, parameters)
+                    }
+                });
+            }
+        }
+    }
+    /// Skips a PCL escape sequence, such as the one that switches a printer into HP-GL/2 mode.
+    fn skip_escape_sequence(&mut self) {
+        match self.peek() {
+            // parameterized sequences end with an upper case letter
+            Some(c) if (b'!'..=b'/').contains(&c) => {
+                while let Some(c) = self.peek() {
+                    self.offset += 1;
+                    if c.is_ascii_uppercase() || c == b'@' {
+                        break;
+                    }
+                }
+            }
+            Some(_) => self.offset += 1,
+            None => (),
+        }
+    }
+    fn skip_past(&mut self, terminator: u8) {
+        while let Some(c) = self.peek() {
+            self.offset += 1;
+            if c == terminator {
+                break;
+            }
+        }
+    }
+    /// Reads numbers up to the end of the command, which is a `;` or the next mnemonic.
+    fn parameters(&mut self) -> Vec<f64> {
+        let mut parameters = vec![];
+        while let Some(c) = self.peek() {
+            match c {
+                b';' => {
+                    self.offset += 1;
+                    break;
+                }
+                b'+' | b'-' | b'.' | b'0'..=b'9' => {
+                    let start = self.offset;
+                    self.offset += 1;
+                    while matches!(self.peek(), Some(b'.' | b'0'..=b'9')) {
+                        self.offset += 1;
+                    }
+                    let text = String::from_utf8_lossy(&self.data[start..self.offset]);
+                    if let Ok(value) = text.parse() {
+                        parameters.push(value);
+                    }
+                }
+                c if c.is_ascii_alphabetic() || c == 0x1B => break,
+                _ => self.offset += 1,
+            }
+        }
+        parameters
+    }
+}
+
+pub(crate) fn pen_layer_name(pen: i32) -> String {
+    format!("PEN{pen}")
+}
+
+fn pen_color(pen: i32) -> Color {
+    match u8::try_from(pen) {
+        Ok(index @ 1..=255) => Color::from_index(index),
+        _ => Color::from_index(7),
+    }
+}
+
+/// Returns the `LineType` that approximates one of the fixed HP-GL/2 line types.
+fn line_type(number: i32, pattern_length: f64) -> LineType {
+    // dashes, gaps and dots as percentages of the pattern length
+    let percentages: &[f64] = match number {
+        0 | 1 => &[0.0, -100.0],
+        2 => &[50.0, -50.0],
+        3 => &[70.0, -30.0],
+        4 => &[80.0, -10.0, 0.0, -10.0],
+        5 => &[70.0, -10.0, 10.0, -10.0],
+        6 => &[50.0, -10.0, 10.0, -10.0, 10.0, -10.0],
+        7 => &[70.0, -10.0, 0.0, -10.0, 0.0, -10.0],
+        8 => &[50.0, -10.0, 0.0, -10.0, 10.0, -10.0, 0.0, -10.0],
+        _ => &[],
+    };
+    let lengths = percentages
+        .iter()
+        .map(|p| p / 100.0 * pattern_length)
+        .collect::<Vec<_>>();
+    LineType {
+        name: format!("{LINE_TYPE_PREFIX}{number}"),
+        description: format!("HP-GL line type {number}"),
+        element_count: lengths.len() as i32,
+        total_pattern_length: if lengths.is_empty() {
+            0.0
+        } else {
+            pattern_length
+        },
+        dash_dot_space_lengths: lengths,
+        ..Default::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::hpgl_reader::*;
+
+    fn load(hpgl: &str) -> Drawing {
+        let options = HpglImportOptions { scale: Some(1.0) };
+        Drawing::load_hpgl(&mut hpgl.as_bytes(), &options).unwrap()
+    }
+
+    #[test]
+    fn pen_moves() {
+        let drawing = load("IN;SP1;PU0,0;PD100,0;PU;PA200,0;PD300,0,300,100,200,100,200,0;PU;");
+        let entities = drawing.entities().collect::<Vec<_>>();
+        assert_eq!(2, entities.len());
+        match entities[0].specific {
+            EntityType::Line(ref line) => {
+                assert_eq!(Point::new(0.0, 0.0, 0.0), line.p1);
+                assert_eq!(Point::new(100.0, 0.0, 0.0), line.p2);
+            }
+            _ => panic!("expected a line"),
+        }
+        match entities[1].specific {
+            EntityType::LwPolyline(ref poly) => {
+                assert!(poly.is_closed());
+                assert_eq!(4, poly.vertices.len());
+                assert_eq!((300.0, 100.0), (poly.vertices[2].x, poly.vertices[2].y));
+            }
+            _ => panic!("expected a polyline"),
+        }
+    }
+
+    #[test]
+    fn relative_moves_without_separators() {
+        let drawing = load("IN;PU10 10;PR;PD10 0 0 10;PA;PU");
+        let entity = drawing.entities().next().unwrap();
+        match entity.specific {
+            EntityType::LwPolyline(ref poly) => {
+                let points = poly.vertices.iter().map(|v| (v.x, v.y)).collect::<Vec<_>>();
+                assert_eq!(vec![(10.0, 10.0), (20.0, 10.0), (20.0, 20.0)], points);
+            }
+            _ => panic!("expected a polyline"),
+        }
+    }
+
+    #[test]
+    fn circles_and_arcs() {
+        let drawing = load("IN;SP1;PU50,0;CI25;PD;AA0,0,90;PU;PA100,0;PD;AA100,100,360;PU;");
+        let entities = drawing.entities().collect::<Vec<_>>();
+        assert_eq!(3, entities.len());
+        match entities[0].specific {
+            EntityType::Circle(ref circle) => {
+                assert_eq!(Point::new(50.0, 0.0, 0.0), circle.center);
+                assert_eq!(25.0, circle.radius);
+            }
+            _ => panic!("expected a circle"),
+        }
+        match entities[1].specific {
+            EntityType::Arc(ref arc) => {
+                assert!(arc.center.x.abs() < 1e-9 && arc.center.y.abs() < 1e-9);
+                assert!((arc.radius - 50.0).abs() < 1e-9);
+                assert!(arc.start_angle.abs() < 1e-9);
+                assert!((arc.end_angle - 90.0).abs() < 1e-9);
+            }
+            _ => panic!("expected an arc"),
+        }
+        match entities[2].specific {
+            EntityType::Circle(ref circle) => {
+                assert_eq!(Point::new(100.0, 100.0, 0.0), circle.center);
+                assert_eq!(100.0, circle.radius);
+            }
+            _ => panic!("expected a circle"),
+        }
+    }
+
+    #[test]
+    fn clockwise_arcs_in_polylines() {
+        let drawing = load("IN;PU0,0;PD10,0;AR0,10,-90;PU;");
+        let entity = drawing.entities().next().unwrap();
+        match entity.specific {
+            EntityType::LwPolyline(ref poly) => {
+                assert_eq!(3, poly.vertices.len());
+                assert!((poly.vertices[1].bulge + (22.5f64).to_radians().tan()).abs() < 1e-9);
+                assert!(poly.vertices[2].x.abs() < 1e-9);
+                assert!((poly.vertices[2].y - 10.0).abs() < 1e-9);
+            }
+            _ => panic!("expected a polyline"),
+        }
+    }
+
+    #[test]
+    fn pens_become_layers() {
+        let drawing = load("IN;SP3;PU0,0;PD1,0;SP5;PD2,0;SP0;PD3,0;");
+        let layers = drawing
+            .entities()
+            .map(|e| e.common.layer.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(vec!["PEN3", "PEN5"], layers);
+        let layer = drawing.layers().find(|l| l.name == "PEN5").unwrap();
+        assert_eq!(Some(5), layer.color.index());
+    }
+
+    #[test]
+    fn line_types() {
+        let drawing = load("IN;LT2,5;PU0,0;PD10,0;LT;PD20,0;");
+        let names = drawing
+            .entities()
+            .map(|e| e.common.line_type_name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(vec!["HPGL_2", "BYLAYER"], names);
+        let line_type = drawing.line_types().find(|l| l.name == "HPGL_2").unwrap();
+        // 5 mm in plotter units
+        assert_eq!(vec![100.0, -100.0], line_type.dash_dot_space_lengths);
+        assert_eq!(200.0, line_type.total_pattern_length);
+    }
+
+    #[test]
+    fn labels_and_escape_sequences_are_skipped() {
+        let drawing = load("\x1bE\x1b%0BIN;PU0,0;LBPD;CI;\x03DT*;LBAA*PD0,10;PU;\x1b%0A\x1bE");
+        let entities = drawing.entities().collect::<Vec<_>>();
+        assert_eq!(1, entities.len());
+        assert!(matches!(entities[0].specific, EntityType::Line(_)));
+    }
+
+    #[test]
+    fn default_scale_is_millimeters() {
+        let mut hpgl = "IN;PU0,0;PD400,0;".as_bytes();
+        let drawing = Drawing::load_hpgl(&mut hpgl, &HpglImportOptions::default()).unwrap();
+        assert_eq!(Units::Millimeters, drawing.header.default_drawing_units);
+        let entity = drawing.entities().next().unwrap();
+        match entity.specific {
+            EntityType::Line(ref line) => assert_eq!(10.0, line.p2.x),
+            _ => panic!("expected a line"),
+        }
+    }
+}
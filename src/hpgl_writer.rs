@@ -0,0 +1,397 @@
+// plots a drawing as HP-GL/2 commands
+
+use std::io::Write;
+
+use crate::entities::*;
+use crate::geometry::*;
+use crate::hpgl_reader::{LINE_TYPE_PREFIX, MILLIMETERS_PER_PLOTTER_UNIT};
+use crate::render::*;
+use crate::{Drawing, DxfResult, Point};
+
+/// The pen used for entities whose color doesn't have an index.
+const DEFAULT_PEN: i32 = 7;
+
+/// The number of decimal places written for coordinates and arc angles.
+const DECIMAL_PLACES: i32 = 3;
+
+/// Options used when plotting a `Drawing` as HP-GL.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct HpglOptions {
+    /// The name of the `Layout` to plot, or `None` to plot model space.
+    pub layout_name: Option<String>,
+    /// The number of drawing units per millimeter.  When `None` this is derived from `$INSUNITS`,
+    /// treating unitless drawings as millimeters.
+    pub units_per_millimeter: Option<f64>,
+}
+
+pub(crate) struct HpglWriter<'a> {
+    drawing: &'a Drawing,
+    options: &'a HpglOptions,
+    /// The number of plotter units per drawing unit.
+    scale: f64,
+    commands: Vec<String>,
+    pen: i32,
+    line_type: Option<i32>,
+}
+
+impl<'a> HpglWriter<'a> {
+    pub fn new(drawing: &'a Drawing, options: &'a HpglOptions) -> Self {
+        let units_per_millimeter = units_per_millimeter(drawing, options.units_per_millimeter);
+        HpglWriter {
+            drawing,
+            options,
+            scale: 1.0 / (units_per_millimeter * MILLIMETERS_PER_PLOTTER_UNIT),
+            commands: vec![],
+            pen: 0,
+            line_type: None,
+        }
+    }
+    pub fn write<T>(&mut self, writer: &mut T) -> DxfResult<()>
+    where
+        T: Write + ?Sized,
+    {
+        self.commands.push(String::from("IN;"));
+        let drawing = self.drawing;
+        for entity in layout_entities(drawing, self.options.layout_name.as_deref())? {
+            self.plot_entity(entity, None, DEFAULT_PEN, 0);
+        }
+        self.commands.push(String::from("PU;SP0;"));
+        for command in &self.commands {
+            writeln!(writer, "{command}")?;
+        }
+        Ok(())
+    }
+    /// Plots an entity, placed by `placement` if it's in a block.  Entities in blocks are
+    /// flattened to straight lines since a block reference may distort their arcs.
+    fn plot_entity(
+        &mut self,
+        entity: &Entity,
+        placement: Option<&Placement>,
+        inherited_pen: i32,
+        depth: usize,
+    ) {
+        let layer = self
+            .drawing
+            .layers()
+            .find(|l| l.name.eq_ignore_ascii_case(&entity.common.layer));
+        if !entity.common.is_visible || layer.is_some_and(|l| !l.is_layer_on) {
+            return;
+        }
+        // entities on layer `0` in a block take their layer's color from the block reference
+        let inherits_layer = placement.is_some() && entity.common.layer == "0";
+        let color = &entity.common.color;
+        let pen = if color.is_by_block() || (color.is_by_layer() && inherits_layer) {
+            inherited_pen
+        } else if color.is_by_layer() {
+            layer
+                .and_then(|l| l.color.index())
+                .map_or(DEFAULT_PEN, i32::from)
+        } else {
+            color.index().map_or(DEFAULT_PEN, i32::from)
+        };
+
+        if let EntityType::Insert(ref insert) = entity.specific {
+            let block = match self
+                .drawing
+                .blocks()
+                .find(|b| b.name.eq_ignore_ascii_case(&insert.name))
+            {
+                Some(block) if depth < MAX_BLOCK_DEPTH => block,
+                _ => return,
+            };
+            for row in 0..insert.row_count.max(1) {
+                for column in 0..insert.column_count.max(1) {
+                    let mut cell = Placement::for_insert(insert, &block.base_point, row, column);
+                    if let Some(outer) = placement {
+                        cell = cell.then(outer);
+                    }
+                    for block_entity in &block.entities {
+                        self.plot_entity(block_entity, Some(&cell), pen, depth + 1);
+                    }
+                }
+            }
+            return;
+        }
+
+        let line_type = line_type_number(&entity.common.line_type_name);
+        let commands = match placement {
+            None => self.entity_commands(entity),
+            Some(placement) => curve_paths(entity, SPLINE_SEGMENTS_PER_SPAN)
+                .into_iter()
+                .map(|(points, is_closed)| {
+                    let points = points
+                        .iter()
+                        .map(|p| placement.apply(p))
+                        .collect::<Vec<_>>();
+                    self.path_commands(&points, is_closed)
+                })
+                .collect(),
+        };
+        if commands.is_empty() {
+            return;
+        }
+        if pen != self.pen {
+            self.pen = pen;
+            self.commands.push(format!("SP{pen};"));
+        }
+        if line_type != self.line_type {
+            self.line_type = line_type;
+            self.commands.push(match line_type {
+                Some(number) => format!("LT{number};"),
+                None => String::from("LT;"),
+            });
+        }
+        self.commands.extend(commands);
+    }
+    /// Returns the commands that plot an entity in model or paper space, keeping arcs in the XY
+    /// plane as arcs.
+    fn entity_commands(&self, entity: &Entity) -> Vec<String> {
+        match entity.specific {
+            EntityType::Circle(ref circle) if is_planar(&circle.normal) => {
+                let center = ocs_to_wcs(&circle.center, &circle.normal);
+                vec![format!(
+                    "PU{};CI{};",
+                    self.coordinates(&center),
+                    self.number(circle.radius)
+                )]
+            }
+            EntityType::Arc(ref arc) if is_planar(&arc.normal) => {
+                let start = circle_point(
+                    &arc.center,
+                    arc.radius,
+                    arc.start_angle.to_radians(),
+                    &arc.normal,
+                );
+                let center = ocs_to_wcs(&arc.center, &arc.normal);
+                // arcs are counter-clockwise about their normal
+                let sweep = sweep_degrees(arc.start_angle, arc.end_angle) * arc.normal.z.signum();
+                vec![format!(
+                    "PU{};PD;AA{},{};",
+                    self.coordinates(&start),
+                    self.coordinates(&center),
+                    num(sweep, DECIMAL_PLACES)
+                )]
+            }
+            EntityType::LwPolyline(ref poly)
+                if is_planar(&poly.extrusion_direction) && poly.vertices.len() >= 2 =>
+            {
+                let normal = &poly.extrusion_direction;
+                let world =
+                    |x: f64, y: f64| ocs_to_wcs(&Point::new(x, y, entity.common.elevation), normal);
+                let count = poly.vertices.len();
+                let segment_count = if poly.is_closed() { count } else { count - 1 };
+                let first = &poly.vertices[0];
+                let mut command = format!("PU{};PD", self.coordinates(&world(first.x, first.y)));
+                let mut has_points = false;
+                for i in 0..segment_count {
+                    let v1 = &poly.vertices[i];
+                    let v2 = &poly.vertices[(i + 1) % count];
+                    match bulge_to_arc(v1.x, v1.y, v2.x, v2.y, v1.bulge) {
+                        Some(arc) => {
+                            let center = world(arc.center_x, arc.center_y);
+                            let sweep = arc.included_angle.to_degrees() * normal.z.signum();
+                            command.push_str(&format!(
+                                ";AA{},{};PD",
+                                self.coordinates(&center),
+                                num(sweep, DECIMAL_PLACES)
+                            ));
+                            has_points = false;
+                        }
+                        None => {
+                            command.push_str(if has_points { "," } else { "" });
+                            command.push_str(&self.coordinates(&world(v2.x, v2.y)));
+                            has_points = true;
+                        }
+                    }
+                }
+                command.push(';');
+                vec![command]
+            }
+            _ => curve_paths(entity, SPLINE_SEGMENTS_PER_SPAN)
+                .into_iter()
+                .map(|(points, is_closed)| self.path_commands(&points, is_closed))
+                .collect(),
+        }
+    }
+    fn path_commands(&self, points: &[Point], is_closed: bool) -> String {
+        let mut rest = points[1..]
+            .iter()
+            .map(|p| self.coordinates(p))
+            .collect::<Vec<_>>();
+        if is_closed {
+            rest.push(self.coordinates(&points[0]));
+        }
+        format!("PU{};PD{};", self.coordinates(&points[0]), rest.join(","))
+    }
+    fn number(&self, value: f64) -> String {
+        num(value * self.scale, DECIMAL_PLACES)
+    }
+    fn coordinates(&self, p: &Point) -> String {
+        format!("{},{}", self.number(p.x), self.number(p.y))
+    }
+}
+
+/// Returns the HP-GL line type of the line types created when importing HP-GL.
+fn line_type_number(line_type_name: &str) -> Option<i32> {
+    let prefix = line_type_name.get(..LINE_TYPE_PREFIX.len())?;
+    if prefix.eq_ignore_ascii_case(LINE_TYPE_PREFIX) {
+        line_type_name[LINE_TYPE_PREFIX.len()..].parse().ok()
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::hpgl_writer::*;
+    use crate::tables::Layer;
+    use crate::{Block, Color, HpglImportOptions, LwPolylineVertex};
+
+    fn to_hpgl(drawing: &Drawing) -> Vec<String> {
+        let options = HpglOptions {
+            units_per_millimeter: Some(1.0 / MILLIMETERS_PER_PLOTTER_UNIT),
+            ..Default::default()
+        };
+        let mut buf = vec![];
+        drawing.save_hpgl(&mut buf, &options).unwrap();
+        String::from_utf8(buf)
+            .unwrap()
+            .lines()
+            .map(String::from)
+            .collect()
+    }
+
+    #[test]
+    fn lines_circles_and_arcs() {
+        let mut drawing = Drawing::new();
+        drawing.add_entity(Entity::new(EntityType::Line(Line::new(
+            Point::new(0.0, 0.0, 0.0),
+            Point::new(100.0, 50.0, 0.0),
+        ))));
+        drawing.add_entity(Entity::new(EntityType::Circle(Circle::new(
+            Point::new(10.0, 20.0, 0.0),
+            5.0,
+        ))));
+        drawing.add_entity(Entity::new(EntityType::Arc(Arc::new(
+            Point::new(0.0, 0.0, 0.0),
+            10.0,
+            0.0,
+            90.0,
+        ))));
+        assert_eq!(
+            vec![
+                "IN;",
+                "SP7;",
+                "PU0,0;PD100,50;",
+                "PU10,20;CI5;",
+                "PU10,0;PD;AA0,0,90;",
+                "PU;SP0;"
+            ],
+            to_hpgl(&drawing)
+        );
+    }
+
+    #[test]
+    fn bulged_polylines() {
+        let mut drawing = Drawing::new();
+        let mut poly = LwPolyline::default();
+        for (x, bulge) in [(0.0, 0.0), (10.0, -1.0), (20.0, 0.0)] {
+            poly.vertices.push(LwPolylineVertex {
+                x,
+                bulge,
+                ..Default::default()
+            });
+        }
+        poly.vertices.push(LwPolylineVertex {
+            x: 20.0,
+            y: 10.0,
+            ..Default::default()
+        });
+        drawing.add_entity(Entity::new(EntityType::LwPolyline(poly)));
+        assert_eq!("PU0,0;PD10,0;AA15,0,-180;PD20,10;", to_hpgl(&drawing)[2]);
+    }
+
+    #[test]
+    fn pens_from_colors() {
+        let mut drawing = Drawing::new();
+        drawing.add_layer(Layer {
+            name: String::from("red"),
+            color: Color::from_index(1),
+            ..Default::default()
+        });
+        let mut line = Entity::new(EntityType::Line(Line::default()));
+        line.common.layer = String::from("red");
+        drawing.add_entity(line.clone());
+        line.common.color = Color::from_index(3);
+        line.common.line_type_name = String::from("HPGL_2");
+        drawing.add_entity(line);
+        let hpgl = to_hpgl(&drawing);
+        assert!(hpgl.contains(&String::from("SP1;")));
+        assert!(hpgl.contains(&String::from("SP3;")));
+        assert!(hpgl.contains(&String::from("LT2;")));
+    }
+
+    #[test]
+    fn block_references_are_expanded() {
+        let mut drawing = Drawing::new();
+        let mut block = Block {
+            name: String::from("b"),
+            ..Default::default()
+        };
+        let mut line = Entity::new(EntityType::Line(Line::new(
+            Point::new(0.0, 0.0, 0.0),
+            Point::new(1.0, 0.0, 0.0),
+        )));
+        line.common.color = Color::by_block();
+        block.entities.push(line);
+        drawing.add_block(block);
+        let mut insert = Entity::new(EntityType::Insert(Insert {
+            name: String::from("b"),
+            location: Point::new(5.0, 5.0, 0.0),
+            x_scale_factor: 2.0,
+            ..Default::default()
+        }));
+        insert.common.color = Color::from_index(4);
+        drawing.add_entity(insert);
+        assert_eq!(
+            vec!["IN;", "SP4;", "PU5,5;PD7,5;", "PU;SP0;"],
+            to_hpgl(&drawing)
+        );
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut drawing = Drawing::new();
+        let mut poly = LwPolyline::default();
+        for (x, y, bulge) in [(0.0, 0.0, 0.5), (40.0, 0.0, 0.0), (40.0, 40.0, 0.0)] {
+            poly.vertices.push(LwPolylineVertex {
+                x,
+                y,
+                bulge,
+                ..Default::default()
+            });
+        }
+        poly.set_is_closed(true);
+        let mut entity = Entity::new(EntityType::LwPolyline(poly));
+        entity.common.color = Color::from_index(2);
+        drawing.add_entity(entity);
+
+        let mut buf = vec![];
+        drawing
+            .save_hpgl(&mut buf, &HpglOptions::default())
+            .unwrap();
+        let options = HpglImportOptions { scale: None };
+        let drawing = Drawing::load_hpgl(&mut buf.as_slice(), &options).unwrap();
+        let entity = drawing.entities().next().unwrap();
+        assert_eq!("PEN2", entity.common.layer);
+        match entity.specific {
+            EntityType::LwPolyline(ref poly) => {
+                assert!(poly.is_closed());
+                assert_eq!(3, poly.vertices.len());
+                assert!((poly.vertices[0].bulge - 0.5).abs() < 1e-4);
+                assert!((poly.vertices[2].y - 40.0).abs() < 1e-6);
+            }
+            _ => panic!("expected a polyline"),
+        }
+    }
+}
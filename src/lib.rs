@@ -130,6 +130,34 @@ mod helper_functions;
 mod dxb_item_type;
 mod dxb_reader;
 mod dxb_writer;
+pub use crate::dxb_writer::DxbOptions;
+mod dwg_bit_reader;
+mod dwg_reader;
+pub use crate::dwg_reader::{DwgReadIssue, DwgReadReport};
+mod hpgl_reader;
+mod hpgl_writer;
+pub use crate::hpgl_reader::HpglImportOptions;
+pub use crate::hpgl_writer::HpglOptions;
+
+mod gcode_writer;
+mod geo_json;
+mod gltf_writer;
+mod json;
+pub use crate::gcode_writer::GcodeOptions;
+pub use crate::gltf_writer::GltfOptions;
+mod mesh_reader;
+mod mesh_writer;
+pub use crate::mesh_reader::MeshImportOptions;
+pub use crate::mesh_writer::MeshExportOptions;
+
+mod rasterizer;
+pub use crate::rasterizer::RasterOptions;
+
+mod render;
+mod svg_reader;
+mod svg_writer;
+pub use crate::svg_reader::SvgImportOptions;
+pub use crate::svg_writer::SvgOptions;
 
 mod extension_data;
 pub use crate::extension_data::*;
@@ -141,7 +169,10 @@ mod table;
 mod thumbnail;
 
 mod drawing;
-pub use crate::drawing::Drawing;
+pub use crate::drawing::{Drawing, DxfSaveOptions};
+
+mod down_conversion;
+pub use crate::down_conversion::{DownConversion, DownConversionReport};
 
 mod drawing_item;
 pub use crate::drawing_item::{DrawingItem, DrawingItemMut};
@@ -163,6 +194,8 @@ pub mod enums;
 mod color;
 pub use crate::color::Color;
 
+mod geometry;
+
 mod point;
 pub use crate::point::Point;
 
@@ -182,9 +215,13 @@ pub mod objects {
 
 include!("expected_type.rs");
 
+mod code_page;
 mod code_pair_iter;
+mod code_pair_reader;
+pub use crate::code_pair_reader::CodePairReader;
 mod code_pair_put_back;
 mod code_pair_writer;
+pub use crate::code_pair_writer::CodePairWriter;
 
 mod block;
 pub use crate::block::Block;
@@ -201,8 +238,20 @@ pub use crate::line_weight::LineWeight;
 mod entity;
 pub use crate::entity::LwPolylineVertex;
 
+mod mtext_format;
+pub use crate::mtext_format::{
+    MTextBuilder, MTextFont, MTextFormat, MTextNode, MTextParagraphProperties, MTextStackKind,
+    MTextVerticalAlignment,
+};
+
+mod mtext_layout;
+pub use crate::mtext_layout::{ApproximateTextMetrics, MTextRun, TextMetrics};
+
+mod text_codes;
+pub use crate::text_codes::DecodedText;
+
 mod object;
-pub use crate::object::{GeoMeshPoint, MLineStyleElement};
+pub use crate::object::{GeoMeshPoint, MLineStyleElement, XRecordValue};
 
 mod dxf_error;
 pub use crate::dxf_error::DxfError;
@@ -212,6 +261,16 @@ pub use crate::dxf_result::DxfResult;
 
 mod entity_iter;
 mod object_iter;
+mod parallel_reader;
+mod proxy_graphics;
+pub use crate::proxy_graphics::{
+    ProxyArcType, ProxyGraphicsPrimitive, ProxyLineType, ProxyShellFace, ProxyText,
+};
+mod sat;
+pub use crate::sat::{
+    SatBody, SatCurve, SatDocument, SatEdge, SatFace, SatLoop, SatLump, SatRecord, SatSurface,
+    SatToken,
+};
 
 //------------------------------- test imports ---------------------------------
 
@@ -0,0 +1,451 @@
+// reads STL and Wavefront OBJ triangle meshes as 3D faces or polyface meshes
+
+use std::collections::HashMap;
+use std::io::Read;
+
+use crate::entities::*;
+use crate::{Drawing, DxfError, DxfResult, Point};
+
+/// The most vertices a polyface mesh can index; larger groups are split across several meshes.
+const MAX_POLYFACE_VERTICES: usize = i16::MAX as usize;
+
+/// Options used when importing an STL or OBJ mesh as a `Drawing`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct MeshImportOptions {
+    /// Whether each group of faces becomes a polyface mesh `Polyline` instead of a `Face3D` per
+    /// face.
+    pub create_polyface_meshes: bool,
+}
+
+/// A named group of faces, each a list of indices into the shared vertices.
+struct FaceGroup {
+    name: String,
+    faces: Vec<Vec<usize>>,
+}
+
+pub(crate) struct MeshReader<'a> {
+    options: &'a MeshImportOptions,
+    vertices: Vec<Point>,
+    groups: Vec<FaceGroup>,
+}
+
+impl<'a> MeshReader<'a> {
+    pub fn new(options: &'a MeshImportOptions) -> Self {
+        MeshReader {
+            options,
+            vertices: vec![],
+            groups: vec![],
+        }
+    }
+    /// Reads an ASCII or binary STL file.  Each `solid` of an ASCII file becomes a layer.
+    pub fn load_stl<T>(mut self, reader: &mut T) -> DxfResult<Drawing>
+    where
+        T: Read + ?Sized,
+    {
+        let mut bytes = vec![];
+        reader.read_to_end(&mut bytes)?;
+
+        // binary files may also start with `solid`, but their size always matches their
+        // triangle count
+        let triangle_count = bytes
+            .get(80..84)
+            .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]) as usize);
+        let is_sized_like_binary = triangle_count.map(|count| 84 + count * 50) == Some(bytes.len());
+        let is_ascii = !is_sized_like_binary
+            && bytes
+                .iter()
+                .skip_while(|b| b.is_ascii_whitespace())
+                .take(5)
+                .eq(b"solid".iter());
+        if is_ascii {
+            let text = String::from_utf8_lossy(&bytes);
+            self.read_ascii_stl(&text)?;
+        } else {
+            match triangle_count {
+                Some(count) if is_sized_like_binary => self.read_binary_stl(&bytes[84..], count),
+                _ => return Err(DxfError::UnexpectedEndOfInput),
+            }
+        }
+        Ok(self.into_drawing())
+    }
+    fn read_binary_stl(&mut self, data: &[u8], triangle_count: usize) {
+        let read_f32 = |offset: usize| {
+            f64::from(f32::from_le_bytes([
+                data[offset],
+                data[offset + 1],
+                data[offset + 2],
+                data[offset + 3],
+            ]))
+        };
+        let mut faces = vec![];
+        for triangle in 0..triangle_count {
+            // skip the normal; the winding of the corners is used instead
+            let start = triangle * 50 + 12;
+            let corners = (0..3)
+                .map(|corner| {
+                    let offset = start + corner * 12;
+                    self.vertices.push(Point::new(
+                        read_f32(offset),
+                        read_f32(offset + 4),
+                        read_f32(offset + 8),
+                    ));
+                    self.vertices.len() - 1
+                })
+                .collect();
+            faces.push(corners);
+        }
+        self.groups.push(FaceGroup {
+            name: String::from("0"),
+            faces,
+        });
+    }
+    fn read_ascii_stl(&mut self, text: &str) -> DxfResult<()> {
+        let mut corners = vec![];
+        for (line_number, line) in text.lines().enumerate() {
+            let line_number = line_number + 1;
+            let mut tokens = line.split_whitespace();
+            match tokens.next() {
+                Some("solid") => {
+                    let name = tokens.collect::<Vec<_>>().join(" ");
+                    self.start_group(&name);
+                }
+                Some("vertex") => {
+                    let p = parse_point(&mut tokens, line_number)?;
+                    self.vertices.push(p);
+                    corners.push(self.vertices.len() - 1);
+                }
+                Some("endloop") => {
+                    if self.groups.is_empty() {
+                        self.start_group("");
+                    }
+                    let face = std::mem::take(&mut corners);
+                    if face.len() >= 3 {
+                        self.groups.last_mut().unwrap().faces.push(face);
+                    }
+                }
+                Some("facet" | "outer" | "endfacet" | "endsolid") | None => (),
+                Some(_) => return Err(DxfError::ParseError(line_number)),
+            }
+        }
+        Ok(())
+    }
+    /// Reads an OBJ file.  Each `g` or `o` group becomes a layer; texture coordinates, normals,
+    /// materials and free-form geometry are ignored.
+    pub fn load_obj<T>(mut self, reader: &mut T) -> DxfResult<Drawing>
+    where
+        T: Read + ?Sized,
+    {
+        let mut bytes = vec![];
+        reader.read_to_end(&mut bytes)?;
+        let text = String::from_utf8_lossy(&bytes);
+        for (line_number, line) in text.lines().enumerate() {
+            let line_number = line_number + 1;
+            let line = match line.find('#') {
+                Some(comment) => &line[..comment],
+                None => line,
+            };
+            let mut tokens = line.split_whitespace();
+            match tokens.next() {
+                Some("v") => {
+                    let p = parse_point(&mut tokens, line_number)?;
+                    self.vertices.push(p);
+                }
+                Some("f") => {
+                    let mut face = vec![];
+                    for token in tokens {
+                        // faces may also reference texture coordinates and normals as `v/vt/vn`
+                        let index = token.split('/').next().unwrap_or_default();
+                        let index = index
+                            .parse::<i64>()
+                            .map_err(|e| DxfError::ParseIntError(e, line_number))?;
+                        // negative indices count back from the latest vertex
+                        let index = if index < 0 {
+                            self.vertices.len() as i64 + index
+                        } else {
+                            index - 1
+                        };
+                        if index < 0 || index as usize >= self.vertices.len() {
+                            return Err(DxfError::ParseError(line_number));
+                        }
+                        face.push(index as usize);
+                    }
+                    if face.len() >= 3 {
+                        if self.groups.is_empty() {
+                            self.start_group("");
+                        }
+                        self.groups.last_mut().unwrap().faces.push(face);
+                    }
+                }
+                Some("g" | "o") => {
+                    let name = tokens.next().unwrap_or_default();
+                    self.start_group(name);
+                }
+                _ => (),
+            }
+        }
+        Ok(self.into_drawing())
+    }
+    fn start_group(&mut self, name: &str) {
+        let name = if name.is_empty() { "0" } else { name };
+        match self.groups.last() {
+            // repeated group statements with the same name continue the group
+            Some(group) if group.name == name => (),
+            _ => self.groups.push(FaceGroup {
+                name: String::from(name),
+                faces: vec![],
+            }),
+        }
+    }
+    fn into_drawing(self) -> Drawing {
+        let mut drawing = Drawing::new();
+        for group in &self.groups {
+            if self.options.create_polyface_meshes {
+                self.add_polyface_meshes(&mut drawing, group);
+            } else {
+                for face in &group.faces {
+                    let corners = face
+                        .iter()
+                        .map(|i| self.vertices[*i].clone())
+                        .collect::<Vec<_>>();
+                    // 3D faces have at most four corners, so larger faces are split into a fan
+                    for start in (1..corners.len() - 1).step_by(2) {
+                        let third = &corners[start + 1];
+                        let fourth = corners.get(start + 2).unwrap_or(third);
+                        let mut entity = Entity::new(EntityType::Face3D(Face3D::new(
+                            corners[0].clone(),
+                            corners[start].clone(),
+                            third.clone(),
+                            fourth.clone(),
+                        )));
+                        entity.common.layer = group.name.clone();
+                        drawing.add_entity(entity);
+                    }
+                }
+            }
+        }
+        drawing
+    }
+    fn add_polyface_meshes(&self, drawing: &mut Drawing, group: &FaceGroup) {
+        let mut locations = vec![];
+        let mut location_indices = HashMap::new();
+        let mut face_records = vec![];
+        let mut faces = group.faces.iter().peekable();
+        while let Some(face) = faces.next() {
+            // face records hold at most four indices, so larger faces are split into a fan
+            for start in (1..face.len() - 1).step_by(2) {
+                let end = (start + 3).min(face.len());
+                let mut record = vec![];
+                for vertex in std::iter::once(&face[0]).chain(&face[start..end]) {
+                    // STL repeats the corners of each triangle, so vertices are shared by value
+                    let p = &self.vertices[*vertex];
+                    let key = (p.x.to_bits(), p.y.to_bits(), p.z.to_bits());
+                    let next = locations.len() + 1;
+                    let index = *location_indices.entry(key).or_insert_with(|| {
+                        locations.push(p.clone());
+                        next
+                    });
+                    record.push(index as i32);
+                }
+                face_records.push(record);
+            }
+
+            // close the mesh when it can't take another face
+            let is_full = match faces.peek() {
+                Some(next) => locations.len() + next.len() > MAX_POLYFACE_VERTICES,
+                None => true,
+            };
+            if is_full {
+                let mut poly = Polyline::default();
+                poly.set_is_polyface_mesh(true);
+                poly.polygon_mesh_m_vertex_count = locations.len() as i32;
+                poly.polygon_mesh_n_vertex_count = face_records.len() as i32;
+                for location in locations.drain(..) {
+                    let mut vertex = Vertex::new(location);
+                    vertex.set_is_3d_polygon_mesh(true);
+                    vertex.set_is_polyface_mesh_vertex(true);
+                    poly.add_vertex(drawing, vertex);
+                }
+                for record in face_records.drain(..) {
+                    let mut vertex = Vertex::default();
+                    vertex.set_is_polyface_mesh_vertex(true);
+                    vertex.polyface_mesh_vertex_index1 = record[0];
+                    vertex.polyface_mesh_vertex_index2 = record[1];
+                    vertex.polyface_mesh_vertex_index3 = record[2];
+                    vertex.polyface_mesh_vertex_index4 = record.get(3).copied().unwrap_or(0);
+                    poly.add_vertex(drawing, vertex);
+                }
+                location_indices.clear();
+                let mut entity = Entity::new(EntityType::Polyline(poly));
+                entity.common.layer = group.name.clone();
+                drawing.add_entity(entity);
+            }
+        }
+    }
+}
+
+fn parse_point<'t>(
+    tokens: &mut impl Iterator<Item = &'t str>,
+    line_number: usize,
+) -> DxfResult<Point> {
+    let mut next = || match tokens.next() {
+        Some(token) => token
+            .parse::<f64>()
+            .map_err(|e| DxfError::ParseFloatError(e, line_number)),
+        None => Err(DxfError::ParseError(line_number)),
+    };
+    Ok(Point::new(next()?, next()?, next()?))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::mesh_reader::*;
+    use crate::MeshExportOptions;
+
+    fn load_obj(text: &str, create_polyface_meshes: bool) -> Drawing {
+        let options = MeshImportOptions {
+            create_polyface_meshes,
+        };
+        Drawing::load_obj(&mut text.as_bytes(), &options).unwrap()
+    }
+
+    #[test]
+    fn ascii_stl_solids_become_layers() {
+        let stl = "solid part one
+  facet normal 0 0 1
+    outer loop
+      vertex 0 0 0
+      vertex 1 0 0
+      vertex 1e0 1 0
+    endloop
+  endfacet
+endsolid part one
+";
+        let drawing = Drawing::load_stl(&mut stl.as_bytes(), &Default::default()).unwrap();
+        let entities = drawing.entities().collect::<Vec<_>>();
+        assert_eq!(1, entities.len());
+        assert_eq!("part one", entities[0].common.layer);
+        match entities[0].specific {
+            EntityType::Face3D(ref face) => {
+                assert_eq!(Point::new(1.0, 1.0, 0.0), face.third_corner);
+                assert_eq!(face.third_corner, face.fourth_corner);
+            }
+            _ => panic!("expected a 3D face"),
+        }
+        assert!(drawing.layers().any(|l| l.name == "part one"));
+    }
+
+    #[test]
+    fn malformed_stl() {
+        let stl = "solid\nfacet normal 0 0 1\nouter loop\nvertex 0 zero 0\n";
+        match Drawing::load_stl(&mut stl.as_bytes(), &Default::default()) {
+            Err(DxfError::ParseFloatError(_, 4)) => (),
+            result => panic!("expected a parse error, got {result:?}"),
+        }
+        assert!(Drawing::load_stl(&mut &b"not an stl file"[..], &Default::default()).is_err());
+    }
+
+    #[test]
+    fn binary_stl_round_trip() {
+        let mut drawing = Drawing::new();
+        drawing.add_entity(Entity::new(EntityType::Face3D(Face3D::new(
+            Point::new(0.0, 0.0, 0.0),
+            Point::new(1.0, 0.0, 0.0),
+            Point::new(1.0, 1.0, 0.5),
+            Point::new(0.0, 1.0, 0.5),
+        ))));
+        let mut buf = vec![];
+        let options = MeshExportOptions {
+            binary_stl: true,
+            ..Default::default()
+        };
+        drawing.save_stl(&mut buf, &options).unwrap();
+        let drawing = Drawing::load_stl(&mut buf.as_slice(), &Default::default()).unwrap();
+        let faces = drawing
+            .entities()
+            .map(|e| match e.specific {
+                EntityType::Face3D(ref face) => face.clone(),
+                _ => panic!("expected a 3D face"),
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(2, faces.len());
+        assert_eq!(Point::new(1.0, 1.0, 0.5), faces[0].third_corner);
+        assert_eq!(Point::new(0.0, 1.0, 0.5), faces[1].third_corner);
+    }
+
+    #[test]
+    fn obj_faces() {
+        let obj = "# a square and a pentagon
+v 0 0 0
+v 1 0 0
+v 1 1 0
+v 0 1 0
+v 2 0 0
+v 3 0 0
+v 3 1 0
+v 2.5 2 0
+v 2 1 0
+g square
+f 1/1/1 2/2/1 3/3/1 4/4/1
+g
+f -5 -4 -3 -2 -1
+";
+        let drawing = load_obj(obj, false);
+        let entities = drawing.entities().collect::<Vec<_>>();
+        // the pentagon is split into a quad and a triangle
+        assert_eq!(3, entities.len());
+        assert_eq!("square", entities[0].common.layer);
+        assert_eq!("0", entities[1].common.layer);
+        match (&entities[1].specific, &entities[2].specific) {
+            (EntityType::Face3D(ref quad), EntityType::Face3D(ref triangle)) => {
+                assert_eq!(Point::new(2.5, 2.0, 0.0), quad.fourth_corner);
+                assert_eq!(Point::new(2.0, 0.0, 0.0), triangle.first_corner);
+                assert_eq!(Point::new(2.0, 1.0, 0.0), triangle.third_corner);
+                assert_eq!(triangle.third_corner, triangle.fourth_corner);
+            }
+            _ => panic!("expected 3D faces"),
+        }
+
+        assert!(matches!(
+            Drawing::load_obj(&mut &b"v 0 0 0\nf 1 2 3\n"[..], &Default::default()),
+            Err(DxfError::ParseError(2))
+        ));
+    }
+
+    #[test]
+    fn obj_as_polyface_mesh() {
+        let obj =
+            "v 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\nv 0 0 1\no part\nf 1 2 3\nf 1 3 4\nf 1 2 5\n";
+        let drawing = load_obj(obj, true);
+        let entities = drawing.entities().collect::<Vec<_>>();
+        assert_eq!(1, entities.len());
+        assert_eq!("part", entities[0].common.layer);
+        let poly = match entities[0].specific {
+            EntityType::Polyline(ref poly) => poly,
+            _ => panic!("expected a polyline"),
+        };
+        assert!(poly.is_polyface_mesh());
+        assert_eq!(5, poly.polygon_mesh_m_vertex_count);
+        assert_eq!(3, poly.polygon_mesh_n_vertex_count);
+        let faces = poly
+            .vertices()
+            .filter(|v| !v.is_3d_polygon_mesh())
+            .map(|v| {
+                (
+                    v.polyface_mesh_vertex_index1,
+                    v.polyface_mesh_vertex_index2,
+                    v.polyface_mesh_vertex_index3,
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(vec![(1, 2, 3), (1, 3, 4), (1, 2, 5)], faces);
+
+        // the mesh survives being saved and exported again
+        let mut buf = vec![];
+        drawing.save(&mut buf).unwrap();
+        let drawing = Drawing::load(&mut buf.as_slice()).unwrap();
+        let mut obj = vec![];
+        drawing.save_obj(&mut obj, &Default::default()).unwrap();
+        let obj = String::from_utf8(obj).unwrap();
+        assert!(obj.contains("g part\nf 1 2 3\nf 1 3 4\nf 1 2 5\n"));
+    }
+}
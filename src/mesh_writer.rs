@@ -0,0 +1,453 @@
+// writes the surfaces of a drawing as STL or Wavefront OBJ triangle meshes
+
+use std::collections::HashMap;
+use std::io::Write;
+
+use byteorder::{LittleEndian, WriteBytesExt};
+
+use crate::entities::*;
+use crate::geometry::*;
+use crate::render::*;
+use crate::{Drawing, DxfResult, Point};
+
+/// The text at the start of binary STL files; it must not begin with `solid`, which marks ASCII
+/// files.
+const BINARY_STL_HEADER: &[u8] = b"binary STL exported from a DXF drawing";
+
+/// Options used when exporting the surfaces of a `Drawing` as STL or OBJ.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct MeshExportOptions {
+    /// The name of the `Layout` to export, or `None` to export model space.
+    pub layout_name: Option<String>,
+    /// Whether STL is written in its compact binary form instead of as text.  Binary STL has no
+    /// room for names, so faces aren't grouped by layer.  This is ignored when writing OBJ.
+    pub binary_stl: bool,
+}
+
+pub(crate) struct MeshWriter<'a> {
+    drawing: &'a Drawing,
+    styles: StyleResolver<'a>,
+    /// Planar faces grouped by layer, in the order the layers were first seen.
+    groups: Vec<(String, Vec<Vec<Point>>)>,
+}
+
+impl<'a> MeshWriter<'a> {
+    pub fn new(drawing: &'a Drawing, options: &MeshExportOptions) -> DxfResult<Self> {
+        let mut writer = MeshWriter {
+            drawing,
+            styles: StyleResolver {
+                drawing,
+                default_line_weight: 0.25,
+                foreground_color: (0, 0, 0),
+            },
+            groups: vec![],
+        };
+        for entity in layout_entities(drawing, options.layout_name.as_deref())? {
+            writer.add_entity(entity, &Placement::identity(), None, 0);
+        }
+        Ok(writer)
+    }
+    fn add_entity(
+        &mut self,
+        entity: &Entity,
+        placement: &Placement,
+        block_layer: Option<&str>,
+        depth: usize,
+    ) {
+        // entities on layer 0 in a block take the layer of the insert
+        let layer = match block_layer {
+            Some(layer) if entity.common.layer == "0" => layer,
+            _ => entity.common.layer.as_str(),
+        };
+        if !entity.common.is_visible || !self.styles.is_layer_on(layer) {
+            return;
+        }
+        if let EntityType::Insert(ref insert) = entity.specific {
+            let block = match self
+                .drawing
+                .blocks()
+                .find(|b| b.name.eq_ignore_ascii_case(&insert.name))
+            {
+                Some(block) => block,
+                None => return,
+            };
+            if depth >= MAX_BLOCK_DEPTH {
+                return;
+            }
+            for row in 0..insert.row_count.max(1) {
+                for column in 0..insert.column_count.max(1) {
+                    let block_placement =
+                        Placement::for_insert(insert, &block.base_point, row, column)
+                            .then(placement);
+                    for block_entity in &block.entities {
+                        self.add_entity(block_entity, &block_placement, Some(layer), depth + 1);
+                    }
+                }
+            }
+            return;
+        }
+
+        let is_mirroring = placement.is_mirroring();
+        let faces = surface_faces(entity)
+            .into_iter()
+            .map(|face| {
+                let mut face =
+                    dedupe_loop(&face.iter().map(|p| placement.apply(p)).collect::<Vec<_>>());
+                if is_mirroring {
+                    face.reverse();
+                }
+                face
+            })
+            .filter(|face| face.len() >= 3)
+            .collect::<Vec<_>>();
+        if faces.is_empty() {
+            return;
+        }
+        match self.groups.iter_mut().find(|(name, _)| name == layer) {
+            Some((_, group)) => group.extend(faces),
+            None => self.groups.push((String::from(layer), faces)),
+        }
+    }
+    pub fn write_stl<T>(&self, writer: &mut T, is_binary: bool) -> DxfResult<()>
+    where
+        T: Write + ?Sized,
+    {
+        if is_binary {
+            let triangles = self
+                .groups
+                .iter()
+                .flat_map(|(_, faces)| faces.iter().flat_map(|f| triangles(f)))
+                .collect::<Vec<_>>();
+            let mut header = [b' '; 80];
+            header[..BINARY_STL_HEADER.len()].copy_from_slice(BINARY_STL_HEADER);
+            writer.write_all(&header)?;
+            writer.write_u32::<LittleEndian>(triangles.len() as u32)?;
+            for (normal, corners) in &triangles {
+                for value in [normal.x, normal.y, normal.z] {
+                    writer.write_f32::<LittleEndian>(value as f32)?;
+                }
+                for p in corners {
+                    for value in [p.x, p.y, p.z] {
+                        writer.write_f32::<LittleEndian>(value as f32)?;
+                    }
+                }
+                // attribute byte count
+                writer.write_u16::<LittleEndian>(0)?;
+            }
+        } else {
+            for (layer, faces) in &self.groups {
+                let name = group_name(layer);
+                writeln!(writer, "solid {name}")?;
+                for (normal, corners) in faces.iter().flat_map(|f| triangles(f)) {
+                    writeln!(
+                        writer,
+                        "  facet normal {:e} {:e} {:e}",
+                        normal.x, normal.y, normal.z
+                    )?;
+                    writeln!(writer, "    outer loop")?;
+                    for p in &corners {
+                        writeln!(writer, "      vertex {:e} {:e} {:e}", p.x, p.y, p.z)?;
+                    }
+                    writeln!(writer, "    endloop")?;
+                    writeln!(writer, "  endfacet")?;
+                }
+                writeln!(writer, "endsolid {name}")?;
+            }
+        }
+        Ok(())
+    }
+    /// Writes the faces as an OBJ file with a group for each layer.  Vertices shared by faces are
+    /// written once so the mesh stays connected.
+    pub fn write_obj<T>(&self, writer: &mut T) -> DxfResult<()>
+    where
+        T: Write + ?Sized,
+    {
+        let mut indices = HashMap::new();
+        let mut groups = vec![];
+        for (layer, faces) in &self.groups {
+            let mut group = vec![];
+            for face in faces {
+                let mut face_indices = vec![];
+                for p in face {
+                    let key = (p.x.to_bits(), p.y.to_bits(), p.z.to_bits());
+                    let index = match indices.get(&key) {
+                        Some(index) => *index,
+                        None => {
+                            writeln!(writer, "v {} {} {}", p.x, p.y, p.z)?;
+                            let index = indices.len() + 1;
+                            indices.insert(key, index);
+                            index
+                        }
+                    };
+                    face_indices.push(index);
+                }
+                group.push(face_indices);
+            }
+            groups.push((layer, group));
+        }
+        for (layer, faces) in groups {
+            writeln!(writer, "g {}", group_name(layer))?;
+            for face in faces {
+                write!(writer, "f")?;
+                for index in face {
+                    write!(writer, " {index}")?;
+                }
+                writeln!(writer)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Returns a layer name usable as an STL solid or OBJ group name, neither of which may contain
+/// whitespace.
+fn group_name(layer: &str) -> String {
+    layer
+        .chars()
+        .map(|c| if c.is_whitespace() { '_' } else { c })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::mesh_writer::*;
+    use crate::Block;
+    use std::f64::consts::PI;
+
+    fn to_stl(drawing: &Drawing) -> String {
+        let mut buf = vec![];
+        drawing
+            .save_stl(&mut buf, &MeshExportOptions::default())
+            .unwrap();
+        String::from_utf8(buf).unwrap()
+    }
+
+    fn to_obj(drawing: &Drawing) -> String {
+        let mut buf = vec![];
+        drawing
+            .save_obj(&mut buf, &MeshExportOptions::default())
+            .unwrap();
+        String::from_utf8(buf).unwrap()
+    }
+
+    fn face_on_layer(layer: &str, corners: [(f64, f64, f64); 4]) -> Entity {
+        let [a, b, c, d] = corners.map(|(x, y, z)| Point::new(x, y, z));
+        let mut entity = Entity::new(EntityType::Face3D(Face3D::new(a, b, c, d)));
+        entity.common.layer = String::from(layer);
+        entity
+    }
+
+    #[test]
+    fn ascii_stl_groups_faces_by_layer() {
+        let mut drawing = Drawing::new();
+        // a quad and a triangle
+        drawing.add_entity(face_on_layer(
+            "walls",
+            [
+                (0.0, 0.0, 0.0),
+                (1.0, 0.0, 0.0),
+                (1.0, 1.0, 0.0),
+                (0.0, 1.0, 0.0),
+            ],
+        ));
+        drawing.add_entity(face_on_layer(
+            "roof top",
+            [
+                (0.0, 0.0, 1.0),
+                (1.0, 0.0, 1.0),
+                (0.0, 1.0, 1.0),
+                (0.0, 1.0, 1.0),
+            ],
+        ));
+        let stl = to_stl(&drawing);
+        assert!(stl.starts_with("solid walls\n"));
+        assert!(stl.contains("endsolid walls\nsolid roof_top\n"));
+        assert_eq!(3, stl.matches("facet normal").count());
+        assert!(stl.contains("facet normal 0e0 0e0 1e0"));
+        assert!(stl.contains("vertex 1e0 1e0 0e0"));
+    }
+
+    #[test]
+    fn binary_stl() {
+        let mut drawing = Drawing::new();
+        drawing.add_entity(face_on_layer(
+            "0",
+            [
+                (0.0, 0.0, 0.0),
+                (1.0, 0.0, 0.0),
+                (1.0, 1.0, 0.0),
+                (0.0, 1.0, 0.0),
+            ],
+        ));
+        let mut buf = vec![];
+        let options = MeshExportOptions {
+            binary_stl: true,
+            ..Default::default()
+        };
+        drawing.save_stl(&mut buf, &options).unwrap();
+        assert_eq!(84 + 2 * 50, buf.len());
+        assert!(!buf.starts_with(b"solid"));
+        assert_eq!([2, 0, 0, 0], buf[80..84]);
+        // the first triangle's normal is +z
+        assert_eq!(1.0f32.to_le_bytes(), buf[92..96]);
+    }
+
+    #[test]
+    fn obj_shares_vertices() {
+        let mut drawing = Drawing::new();
+        drawing.add_entity(face_on_layer(
+            "a",
+            [
+                (0.0, 0.0, 0.0),
+                (1.0, 0.0, 0.0),
+                (1.0, 1.0, 0.0),
+                (0.0, 1.0, 0.0),
+            ],
+        ));
+        drawing.add_entity(face_on_layer(
+            "b",
+            [
+                (1.0, 0.0, 0.0),
+                (2.0, 0.0, 0.0),
+                (2.0, 1.0, 0.0),
+                (1.0, 1.0, 0.0),
+            ],
+        ));
+        let obj = to_obj(&drawing);
+        assert_eq!(6, obj.matches("v ").count());
+        assert!(obj.contains("g a\nf 1 2 3 4\ng b\nf 2 5 6 3\n"));
+    }
+
+    #[test]
+    fn polyface_and_polygon_meshes() {
+        let mut drawing = Drawing::new();
+        let mut polyface = Polyline::default();
+        polyface.set_is_polyface_mesh(true);
+        for (x, y) in [(0.0, 0.0), (1.0, 0.0), (1.0, 1.0), (0.0, 1.0)] {
+            let mut vertex = Vertex::new(Point::new(x, y, 0.0));
+            vertex.set_is_3d_polygon_mesh(true);
+            vertex.set_is_polyface_mesh_vertex(true);
+            polyface.add_vertex(&mut drawing, vertex);
+        }
+        for indices in [(1, 2, 3), (1, 3, -4)] {
+            let mut face = Vertex::default();
+            face.set_is_polyface_mesh_vertex(true);
+            face.polyface_mesh_vertex_index1 = indices.0;
+            face.polyface_mesh_vertex_index2 = indices.1;
+            face.polyface_mesh_vertex_index3 = indices.2;
+            polyface.add_vertex(&mut drawing, face);
+        }
+        drawing.add_entity(Entity::new(EntityType::Polyline(polyface)));
+        assert_eq!(2, to_stl(&drawing).matches("facet normal").count());
+
+        // a 3x3 grid closed in the n direction has 2 rows of 3 quads
+        let mut drawing = Drawing::new();
+        let mut mesh = Polyline::default();
+        mesh.set_is_3d_polygon_mesh(true);
+        mesh.set_is_polygon_mesh_closed_in_n_direction(true);
+        mesh.polygon_mesh_m_vertex_count = 3;
+        mesh.polygon_mesh_n_vertex_count = 3;
+        for m in 0..3 {
+            for n in 0..3 {
+                let angle = f64::from(n) * 2.0 * PI / 3.0;
+                mesh.add_vertex(
+                    &mut drawing,
+                    Vertex::new(Point::new(angle.cos(), angle.sin(), f64::from(m))),
+                );
+            }
+        }
+        drawing.add_entity(Entity::new(EntityType::Polyline(mesh)));
+        assert_eq!(6, to_obj(&drawing).matches("f ").count());
+    }
+
+    #[test]
+    fn extruded_entities() {
+        let mut drawing = Drawing::new();
+        let mut line = Line::new(Point::origin(), Point::new(1.0, 0.0, 0.0));
+        line.thickness = 2.0;
+        drawing.add_entity(Entity::new(EntityType::Line(line)));
+        // flat lines have no surface
+        drawing.add_entity(Entity::new(EntityType::Line(Line::new(
+            Point::origin(),
+            Point::new(0.0, 1.0, 0.0),
+        ))));
+        let mut solid = Solid::new(
+            Point::new(0.0, 0.0, 0.0),
+            Point::new(1.0, 0.0, 0.0),
+            Point::new(0.0, 1.0, 0.0),
+            Point::new(1.0, 1.0, 0.0),
+        );
+        solid.thickness = 1.0;
+        drawing.add_entity(Entity::new(EntityType::Solid(solid)));
+        let mut circle = Circle::new(Point::origin(), 1.0);
+        circle.thickness = 1.0;
+        drawing.add_entity(Entity::new(EntityType::Circle(circle)));
+
+        let obj = to_obj(&drawing);
+        // the line's wall, the solid's box and the circle's walls and caps
+        assert_eq!(1 + 6 + CIRCLE_SEGMENTS + 2, obj.matches("f ").count());
+
+        // the box is closed, so every edge is shared by exactly two faces with opposite
+        // directions
+        let mut drawing = Drawing::new();
+        let mut solid = Solid::new(
+            Point::new(0.0, 0.0, 0.0),
+            Point::new(1.0, 0.0, 0.0),
+            Point::new(0.0, 1.0, 0.0),
+            Point::new(1.0, 1.0, 0.0),
+        );
+        solid.thickness = -1.0;
+        drawing.add_entity(Entity::new(EntityType::Solid(solid)));
+        let writer = MeshWriter::new(&drawing, &MeshExportOptions::default()).unwrap();
+        let mut edges = HashMap::new();
+        for face in &writer.groups[0].1 {
+            for i in 0..face.len() {
+                let a = &face[i];
+                let b = &face[(i + 1) % face.len()];
+                *edges.entry(format!("{a:?}->{b:?}")).or_insert(0) += 1;
+            }
+        }
+        assert_eq!(24, edges.len());
+        for (key, count) in &edges {
+            assert_eq!(1, *count);
+            let (a, b) = key.split_once("->").unwrap();
+            assert!(edges.contains_key(&format!("{b}->{a}")));
+        }
+    }
+
+    #[test]
+    fn inserts_are_expanded() {
+        let mut drawing = Drawing::new();
+        let mut block = Block {
+            name: String::from("tile"),
+            base_point: Point::new(1.0, 0.0, 0.0),
+            ..Default::default()
+        };
+        block.entities.push(face_on_layer(
+            "0",
+            [
+                (1.0, 0.0, 0.0),
+                (2.0, 0.0, 0.0),
+                (2.0, 1.0, 0.0),
+                (1.0, 1.0, 0.0),
+            ],
+        ));
+        drawing.add_block(block);
+        let mut insert = Entity::new(EntityType::Insert(Insert {
+            name: String::from("tile"),
+            location: Point::new(10.0, 0.0, 5.0),
+            rotation: 90.0,
+            x_scale_factor: 2.0,
+            ..Default::default()
+        }));
+        insert.common.layer = String::from("floor");
+        drawing.add_entity(insert);
+
+        let obj = to_obj(&drawing);
+        assert!(obj.contains("g floor\n"));
+        assert!(obj.contains("v 10 0 5\n"));
+        // x is scaled by 2, then rotated onto the y axis
+        assert!(obj.contains("v 10 2 5\n"));
+        assert!(obj.contains("v 9 2 5\n"));
+    }
+}
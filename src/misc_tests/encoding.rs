@@ -1,9 +1,10 @@
 use crate::entities::*;
 use crate::enums::*;
 use crate::helper_functions::tests::*;
+use crate::objects::*;
 use crate::*;
 
-use std::io::{BufReader, Cursor, Seek, SeekFrom};
+use std::io::{BufReader, Cursor, Read, Seek, SeekFrom};
 use std::str::from_utf8;
 
 use image::{DynamicImage, GenericImageView};
@@ -105,6 +106,161 @@ fn read_file_with_comments() {
     }
 }
 
+#[test]
+fn read_comments_attached_to_items() {
+    let drawing = drawing_from_pairs(vec![
+        CodePair::new_str(999, "generated by a tool"),
+        CodePair::new_str(0, "SECTION"),
+        CodePair::new_str(2, "TABLES"),
+        CodePair::new_str(0, "TABLE"),
+        CodePair::new_str(2, "LAYER"),
+        CodePair::new_str(999, "layer comment"),
+        CodePair::new_str(0, "LAYER"),
+        CodePair::new_str(2, "some-layer"),
+        CodePair::new_str(0, "ENDTAB"),
+        CodePair::new_str(0, "ENDSEC"),
+        CodePair::new_str(0, "SECTION"),
+        CodePair::new_str(2, "BLOCKS"),
+        CodePair::new_str(999, "block comment"),
+        CodePair::new_str(0, "BLOCK"),
+        CodePair::new_str(2, "some-block"),
+        CodePair::new_str(999, "block entity comment"),
+        CodePair::new_str(0, "LINE"),
+        CodePair::new_str(0, "ENDBLK"),
+        CodePair::new_str(0, "ENDSEC"),
+        CodePair::new_str(0, "SECTION"),
+        CodePair::new_str(2, "ENTITIES"),
+        CodePair::new_str(999, "first"),
+        CodePair::new_str(0, "LINE"),
+        CodePair::new_str(999, "inside first"),
+        CodePair::new_f64(10, 1.0),
+        CodePair::new_str(999, "second"),
+        CodePair::new_str(0, "CIRCLE"),
+        CodePair::new_str(999, "end of entities"),
+        CodePair::new_str(0, "ENDSEC"),
+        CodePair::new_str(0, "EOF"),
+    ]);
+    assert_eq!(vec!["generated by a tool"], drawing.comments);
+    assert_eq!(
+        vec!["end of entities"],
+        drawing.section_comments["ENTITIES"]
+    );
+    let layer = drawing.layers().find(|l| l.name == "some-layer").unwrap();
+    assert_eq!(vec!["layer comment"], layer.comments);
+    let block = drawing.blocks().next().unwrap();
+    assert_eq!(vec!["block comment"], block.comments);
+    assert_eq!(
+        vec!["block entity comment"],
+        block.entities[0].common.comments
+    );
+    let entities = drawing.entities().collect::<Vec<_>>();
+    assert_eq!(vec!["first", "inside first"], entities[0].common.comments);
+    assert_eq!(vec!["second"], entities[1].common.comments);
+}
+
+#[test]
+fn write_unclaimed_comments_in_their_section() {
+    let mut drawing = drawing_from_pairs(vec![
+        CodePair::new_str(999, "file"),
+        CodePair::new_str(0, "SECTION"),
+        CodePair::new_str(2, "ENTITIES"),
+        CodePair::new_str(0, "LINE"),
+        CodePair::new_str(999, "end of entities"),
+        CodePair::new_str(0, "ENDSEC"),
+        CodePair::new_str(999, "after entities"),
+        CodePair::new_str(0, "SECTION"),
+        CodePair::new_str(2, "UNSUPPORTED"),
+        CodePair::new_str(0, "ENDSEC"),
+        CodePair::new_str(999, "last"),
+        CodePair::new_str(0, "EOF"),
+    ]);
+    assert_eq!(vec!["file"], drawing.comments);
+    assert_eq!(
+        vec!["end of entities", "after entities"],
+        drawing.section_comments["ENTITIES"]
+    );
+    assert_eq!(vec!["last"], drawing.section_comments["UNSUPPORTED"]);
+
+    drawing.header.version = AcadVersion::R12;
+    let pairs = drawing.code_pairs().unwrap();
+    assert_eq!(CodePair::new_str(999, "file"), pairs[0]);
+    assert_vec_contains(
+        &pairs,
+        &[
+            CodePair::new_str(999, "end of entities"),
+            CodePair::new_str(999, "after entities"),
+            CodePair::new_str(0, "ENDSEC"),
+        ],
+    );
+    // the section isn't written, so its comments go last
+    assert_eq!(
+        &[CodePair::new_str(999, "last"), CodePair::new_str(0, "EOF"),],
+        &pairs[pairs.len() - 2..]
+    );
+}
+
+#[test]
+fn write_comments() {
+    let mut drawing = Drawing::new();
+    drawing.header.version = AcadVersion::R2000;
+    drawing.comments.push(String::from("generator 1.0"));
+    let mut line = Entity::new(EntityType::Line(Default::default()));
+    line.common.comments.push(String::from("line comment"));
+    drawing.add_entity(line);
+    let mut object = Object::new(ObjectType::PlaceHolder(Default::default()));
+    object.common.comments.push(String::from("object comment"));
+    drawing.add_object(object);
+
+    let pairs = drawing.code_pairs().unwrap();
+    assert_eq!(CodePair::new_str(999, "generator 1.0"), pairs[0]);
+    assert_vec_contains(
+        &pairs,
+        &[
+            CodePair::new_str(999, "line comment"),
+            CodePair::new_str(0, "LINE"),
+        ],
+    );
+    assert_vec_contains(
+        &pairs,
+        &[
+            CodePair::new_str(999, "object comment"),
+            CodePair::new_str(0, "ACDBPLACEHOLDER"),
+        ],
+    );
+
+    // comments survive a round trip
+    let round_tripped = parse_drawing(&to_test_string(&drawing));
+    assert_eq!(vec!["generator 1.0"], round_tripped.comments);
+    let entities = round_tripped.entities().collect::<Vec<_>>();
+    assert_eq!(vec!["line comment"], entities[0].common.comments);
+
+    // but aren't written to binary files
+    let binary = to_binary(&drawing);
+    assert!(!binary.windows("generator".len()).any(|w| w == b"generator"));
+    let round_tripped = unwrap_drawing(Drawing::load(&mut binary.as_slice()));
+    assert!(round_tripped.comments.is_empty());
+
+    // or left out when saving
+    let mut buf = Vec::new();
+    drawing
+        .save_with_options(
+            &mut buf,
+            &DxfSaveOptions {
+                strip_comments: true,
+            },
+        )
+        .unwrap();
+    let text = String::from_utf8(buf).unwrap();
+    assert!(!text.contains("generator"));
+    assert!(!text.contains("line comment"));
+    assert_eq!(vec!["generator 1.0"], drawing.comments);
+
+    // and can be removed
+    drawing.strip_comments();
+    assert_not_contains_pairs(&drawing, vec![CodePair::new_str(999, "line comment")]);
+    assert!(!drawing.code_pairs().unwrap().iter().any(|p| p.code == 999));
+}
+
 #[test]
 fn enum_out_of_bounds() {
     let file = from_section(
@@ -135,6 +291,15 @@ fn round_trip() {
     assert_eq!(1, drawing.layers().count());
 }
 
+#[test]
+fn load_reads_only_as_much_as_needed() {
+    // anything after `EOF` is never read, so an endless stream can follow it
+    let text = "0\nSECTION\n2\nENTITIES\n0\nLINE\n0\nENDSEC\n0\nEOF\n";
+    let mut reader = text.as_bytes().chain(std::io::repeat(b' '));
+    let drawing = unwrap_drawing(Drawing::load(&mut reader));
+    assert_eq!(1, drawing.entities().count());
+}
+
 #[test]
 fn parse_with_leading_bom() {
     let buf = vec![
@@ -418,6 +583,346 @@ fn read_dxb_after_writing() {
     }
 }
 
+fn dxb_round_trip(drawing: &Drawing, options: &DxbOptions) -> Drawing {
+    let mut buf = vec![];
+    drawing.save_dxb_with_options(&mut buf, options).unwrap();
+    unwrap_drawing(Drawing::load(&mut buf.as_slice()))
+}
+
+#[test]
+fn read_dxb_file_with_scale_factor() {
+    let mut data = b"AutoCAD DXB 1.0\r\n\x1A\x00".to_vec();
+    data.push(128); // scale factor
+    data.extend_from_slice(&0.5f64.to_le_bytes());
+    data.push(136); // new color, which isn't scaled
+    data.extend_from_slice(&3i16.to_le_bytes());
+    data.push(8); // arc
+    for n in [2i16, 4, 10] {
+        data.extend_from_slice(&n.to_le_bytes());
+    }
+    // integer angles are in millionths of a degree
+    data.extend_from_slice(&45_000_000i32.to_le_bytes());
+    data.extend_from_slice(&90_500_000i32.to_le_bytes());
+    data.push(19); // polyline
+    data.extend_from_slice(&1i16.to_le_bytes());
+    data.push(20); // vertex
+    data.extend_from_slice(&[0, 0, 0, 0]);
+    data.push(133); // bulge in units of 1/65536
+    data.extend_from_slice(&32768i32.to_le_bytes());
+    data.push(134); // width
+    data.extend_from_slice(&2i16.to_le_bytes());
+    data.extend_from_slice(&4i16.to_le_bytes());
+    data.push(20); // vertex
+    data.extend_from_slice(&[8, 0, 0, 0]);
+    data.push(17); // seqend
+    data.push(0);
+
+    let drawing = unwrap_drawing(Drawing::load(&mut data.as_slice()));
+    let entities = drawing.entities().collect::<Vec<_>>();
+    assert_eq!(2, entities.len());
+    assert_eq!(Some(3), entities[0].common.color.index());
+    match entities[0].specific {
+        EntityType::Arc(ref arc) => {
+            assert_eq!(Point::new(1.0, 2.0, 0.0), arc.center);
+            assert_eq!(5.0, arc.radius);
+            assert_eq!(45.0, arc.start_angle);
+            assert_eq!(90.5, arc.end_angle);
+        }
+        _ => panic!("expected an arc"),
+    }
+    match entities[1].specific {
+        EntityType::Polyline(ref poly) => {
+            assert!(poly.is_closed());
+            let vertices = poly.vertices().collect::<Vec<_>>();
+            assert_eq!(0.5, vertices[0].bulge);
+            assert_eq!(1.0, vertices[0].starting_width);
+            assert_eq!(2.0, vertices[0].ending_width);
+            assert_eq!(Point::new(4.0, 0.0, 0.0), vertices[1].location);
+        }
+        _ => panic!("expected a polyline"),
+    }
+}
+
+#[test]
+fn write_dxb_line_and_trace_extensions() {
+    let mut drawing = Drawing::new();
+    let lines = [
+        ((0.0, 0.0, 0.0), (1.0, 0.0, 0.0)),
+        ((1.0, 0.0, 0.0), (1.0, 1.0, 0.0)),
+        ((1.0, 1.0, 0.0), (1.0, 1.0, 1.0)),
+        ((5.0, 5.0, 5.0), (6.0, 6.0, 6.0)),
+    ];
+    for ((x1, y1, z1), (x2, y2, z2)) in lines {
+        let line = Line::new(Point::new(x1, y1, z1), Point::new(x2, y2, z2));
+        drawing.add_entity(Entity::new(EntityType::Line(line)));
+    }
+    let corners = [
+        (0.0, 0.0),
+        (0.0, 1.0),
+        (2.0, 0.0),
+        (2.0, 1.0),
+        (4.0, 0.0),
+        (4.0, 1.0),
+    ];
+    for i in [0, 2] {
+        let point = |j: usize| Point::new(corners[i + j].0, corners[i + j].1, 0.0);
+        let trace = Trace::new(point(0), point(1), point(2), point(3));
+        drawing.add_entity(Entity::new(EntityType::Trace(trace)));
+    }
+    let mut buf = vec![];
+    drawing.save_dxb(&mut buf).unwrap();
+
+    // skip the sentinel and number mode
+    let items = &buf[19 + 3..];
+    let mut item_types = vec![];
+    let mut offset = 0;
+    while items[offset] != 0 {
+        let item_type = items[offset];
+        item_types.push(item_type);
+        offset += 1 + 8 * match item_type {
+            1 | 21 => 6,
+            130 => 2,
+            137 => 3,
+            9 => 8,
+            131 => 4,
+            _ => panic!("unexpected item type {item_type}"),
+        };
+    }
+    assert_eq!(vec![1, 130, 137, 21, 9, 131], item_types);
+
+    let entities = dxb_round_trip(&drawing, &DxbOptions::default())
+        .entities()
+        .cloned()
+        .collect::<Vec<_>>();
+    let original = drawing.entities().collect::<Vec<_>>();
+    assert_eq!(original.len(), entities.len());
+    for (expected, actual) in original.iter().zip(entities.iter()) {
+        assert_eq!(expected.specific, actual.specific);
+    }
+}
+
+#[test]
+fn dxb_round_trip_with_layers_colors_and_polylines() {
+    let mut drawing = Drawing::new();
+    let mut circle = Entity::new(EntityType::Circle(Circle::new(
+        Point::new(1.25, 2.5, 0.0),
+        3.75,
+    )));
+    circle.common.layer = String::from("circles");
+    circle.common.color = Color::from_index(5);
+    drawing.add_entity(circle);
+    let mut point = Entity::new(EntityType::ModelPoint(ModelPoint::new(Point::new(
+        7.0, 8.0, 0.0,
+    ))));
+    point.common.layer = String::from("points");
+    drawing.add_entity(point);
+    drawing.add_entity(Entity::new(EntityType::Arc(Arc::new(
+        Point::new(1.0, 1.0, 0.0),
+        2.0,
+        30.0,
+        300.0,
+    ))));
+    drawing.add_entity(Entity::new(EntityType::Solid(Solid::new(
+        Point::new(0.0, 0.0, 0.0),
+        Point::new(1.0, 0.0, 0.0),
+        Point::new(0.0, 1.0, 0.0),
+        Point::new(1.0, 1.0, 0.0),
+    ))));
+    drawing.add_entity(Entity::new(EntityType::Face3D(Face3D::new(
+        Point::new(0.0, 0.0, 1.0),
+        Point::new(1.0, 0.0, 2.0),
+        Point::new(1.0, 1.0, 3.0),
+        Point::new(0.0, 1.0, 4.0),
+    ))));
+    let mut poly = LwPolyline::default();
+    poly.set_is_closed(true);
+    poly.vertices.push(LwPolylineVertex {
+        x: 0.0,
+        y: 0.0,
+        bulge: 0.25,
+        starting_width: 0.5,
+        ending_width: 1.5,
+        ..Default::default()
+    });
+    poly.vertices.push(LwPolylineVertex {
+        x: 10.0,
+        y: 0.0,
+        ..Default::default()
+    });
+    drawing.add_entity(Entity::new(EntityType::LwPolyline(poly)));
+
+    for options in [
+        DxbOptions::default(),
+        DxbOptions {
+            use_integers: true,
+            scale_factor: 0.25,
+        },
+    ] {
+        let read = dxb_round_trip(&drawing, &options);
+        let entities = read.entities().collect::<Vec<_>>();
+        assert_eq!(6, entities.len());
+        assert_eq!("circles", entities[0].common.layer);
+        assert_eq!(Some(5), entities[0].common.color.index());
+        assert_eq!("points", entities[1].common.layer);
+        assert!(entities[1].common.color.is_by_layer());
+        assert_eq!("0", entities[2].common.layer);
+        for (expected, actual) in drawing.entities().zip(&entities).take(5) {
+            assert_eq!(expected.specific, actual.specific);
+        }
+        match entities[5].specific {
+            EntityType::Polyline(ref poly) => {
+                assert!(poly.is_closed());
+                let vertices = poly.vertices().collect::<Vec<_>>();
+                assert_eq!(2, vertices.len());
+                assert_eq!(0.25, vertices[0].bulge);
+                assert_eq!(0.5, vertices[0].starting_width);
+                assert_eq!(1.5, vertices[0].ending_width);
+                assert_eq!(Point::new(10.0, 0.0, 0.0), vertices[1].location);
+                assert_eq!(0.0, vertices[1].bulge);
+            }
+            _ => panic!("expected a polyline"),
+        }
+    }
+}
+
+#[test]
+fn write_dxb_integers_are_rounded_to_the_scale_factor() {
+    let mut drawing = Drawing::new();
+    let line = Line::new(Point::new(0.3, 0.6, 0.0), Point::new(100.0, 0.0, 0.0));
+    drawing.add_entity(Entity::new(EntityType::Line(line)));
+    let options = DxbOptions {
+        use_integers: true,
+        scale_factor: 0.5,
+    };
+    let mut buf = vec![];
+    drawing.save_dxb_with_options(&mut buf, &options).unwrap();
+    // sentinel, number mode, scale factor, line and terminator
+    assert_eq!(19 + 3 + 9 + 13 + 1, buf.len());
+    let read = dxb_round_trip(&drawing, &options);
+    let entity = read.entities().next().unwrap();
+    match entity.specific {
+        EntityType::Line(ref line) => {
+            assert_eq!(Point::new(0.5, 0.5, 0.0), line.p1);
+            assert_eq!(Point::new(100.0, 0.0, 0.0), line.p2);
+        }
+        _ => panic!("expected a line"),
+    }
+}
+
+#[test]
+fn dxb_round_trip_block() {
+    let mut drawing = Drawing::new();
+    let mut block = Block {
+        name: String::from("b"),
+        base_point: Point::new(1.0, 2.0, 0.0),
+        ..Default::default()
+    };
+    block.entities.push(Entity::new(EntityType::Line(Line::new(
+        Point::new(1.0, 2.0, 0.0),
+        Point::new(3.0, 4.0, 0.0),
+    ))));
+    drawing.add_block(block);
+    let mut other = Block {
+        name: String::from("other"),
+        base_point: Point::new(10.0, 10.0, 0.0),
+        ..Default::default()
+    };
+    other
+        .entities
+        .push(Entity::new(EntityType::ModelPoint(ModelPoint::new(
+            Point::new(11.0, 10.0, 0.0),
+        ))));
+    drawing.add_block(other);
+
+    let read = dxb_round_trip(&drawing, &DxbOptions::default());
+    assert_eq!(0, read.entities().count());
+    let blocks = read.blocks().collect::<Vec<_>>();
+    assert_eq!(1, blocks.len());
+    assert_eq!(Point::new(1.0, 2.0, 0.0), blocks[0].base_point);
+    assert_eq!(2, blocks[0].entities.len());
+    match blocks[0].entities[1].specific {
+        // every block is moved to the same base point
+        EntityType::ModelPoint(ref point) => {
+            assert_eq!(Point::new(2.0, 2.0, 0.0), point.location)
+        }
+        _ => panic!("expected a point"),
+    }
+}
+
+#[test]
+fn write_dxb_block_references_are_expanded() {
+    let mut drawing = Drawing::new();
+    let mut block = Block {
+        name: String::from("b"),
+        ..Default::default()
+    };
+    let mut circle = Entity::new(EntityType::Circle(Circle::new(
+        Point::new(1.0, 0.0, 0.0),
+        1.0,
+    )));
+    circle.common.color = Color::by_block();
+    block.entities.push(circle);
+    let mut poly = Polyline::default();
+    poly.add_vertex(&mut drawing, Vertex::new(Point::new(0.0, 0.0, 0.0)));
+    poly.add_vertex(&mut drawing, Vertex::new(Point::new(1.0, 0.0, 0.0)));
+    poly.default_starting_width = 0.5;
+    poly.default_ending_width = 0.5;
+    block.entities.push(Entity::new(EntityType::Polyline(poly)));
+    drawing.add_block(block);
+    let mut insert = Entity::new(EntityType::Insert(Insert {
+        name: String::from("b"),
+        location: Point::new(10.0, 0.0, 0.0),
+        x_scale_factor: 2.0,
+        y_scale_factor: 2.0,
+        rotation: 90.0,
+        ..Default::default()
+    }));
+    insert.common.layer = String::from("inserted");
+    insert.common.color = Color::from_index(2);
+    drawing.add_entity(insert);
+
+    let read = dxb_round_trip(&drawing, &DxbOptions::default());
+    let entities = read.entities().collect::<Vec<_>>();
+    assert_eq!(2, entities.len());
+    assert_eq!("inserted", entities[0].common.layer);
+    assert_eq!(Some(2), entities[0].common.color.index());
+    match entities[0].specific {
+        EntityType::Circle(ref circle) => {
+            assert!((circle.center.x - 10.0).abs() < 1e-9);
+            assert!((circle.center.y - 2.0).abs() < 1e-9);
+            assert_eq!(2.0, circle.radius);
+        }
+        _ => panic!("expected a circle"),
+    }
+    assert!(entities[1].common.color.is_by_layer());
+    match entities[1].specific {
+        EntityType::Polyline(ref poly) => {
+            assert_eq!(1.0, poly.default_starting_width);
+            let vertices = poly.vertices().collect::<Vec<_>>();
+            assert!((vertices[1].location.x - 10.0).abs() < 1e-9);
+            assert!((vertices[1].location.y - 2.0).abs() < 1e-9);
+        }
+        _ => panic!("expected a polyline"),
+    }
+}
+
+#[test]
+fn write_dxb_curves_without_equivalents_as_lines() {
+    let mut drawing = Drawing::new();
+    drawing.add_entity(Entity::new(EntityType::Ellipse(Ellipse {
+        major_axis: Vector::new(2.0, 0.0, 0.0),
+        minor_axis_ratio: 0.5,
+        end_parameter: std::f64::consts::PI * 2.0,
+        ..Default::default()
+    })));
+    let read = dxb_round_trip(&drawing, &DxbOptions::default());
+    let entities = read.entities().collect::<Vec<_>>();
+    assert!(entities.len() > 4);
+    assert!(entities
+        .iter()
+        .all(|e| matches!(e.specific, EntityType::Line(_))));
+}
+
 #[test]
 fn dont_write_utf8_bom() {
     let drawing = Drawing::new();
@@ -434,13 +939,106 @@ fn dont_write_utf8_bom() {
 
 #[test]
 fn write_unicode_as_ascii() {
+    // characters outside of the drawing's code page are escaped
     let mut drawing = Drawing::new();
     drawing.header.version = AcadVersion::R2004;
-    drawing.header.project_name = String::from("è");
+    drawing.header.project_name = String::from("\u{0416}");
     assert_contains(
         &drawing,
-        ["  9", "$PROJECTNAME", "  1", "\\U+00E8"].join("\r\n"),
+        ["  9", "$PROJECTNAME", "  1", "\\U+0416"].join("\r\n"),
     );
+
+    // code pages aren't written before R13 so everything is escaped
+    let mut drawing = Drawing::new();
+    drawing.header.version = AcadVersion::R12;
+    drawing.header.file_name = String::from("è");
+    assert_contains(&drawing, ["  9", "$MENU", "  1", "\\U+00E8"].join("\r\n"));
+}
+
+fn drawing_bytes_with_code_page(code_page: &str, project_name: &[u8]) -> Vec<u8> {
+    let mut bytes = [
+        "  0",
+        "SECTION",
+        "  2",
+        "HEADER",
+        "  9",
+        "$ACADVER",
+        "  1",
+        "AC1018",
+        "  9",
+        "$DWGCODEPAGE",
+        "  3",
+        code_page,
+        "  9",
+        "$PROJECTNAME",
+        "  1",
+    ]
+    .join("\r\n")
+    .into_bytes();
+    bytes.extend_from_slice(b"\r\n");
+    bytes.extend_from_slice(project_name);
+    bytes.extend_from_slice(b"\r\n  0\r\nENDSEC\r\n  0\r\nEOF\r\n");
+    bytes
+}
+
+#[test]
+fn read_with_encoding_from_code_page() {
+    // `Жи` in windows-1251
+    let bytes = drawing_bytes_with_code_page("ANSI_1251", &[0xC6, 0xE8]);
+    let drawing = unwrap_drawing(Drawing::load(&mut bytes.as_slice()));
+    assert_eq!("Жи", drawing.header.project_name);
+
+    // `あ` in shift-jis
+    let bytes = drawing_bytes_with_code_page("ANSI_932", &[0x82, 0xA0]);
+    let drawing = unwrap_drawing(Drawing::load(&mut bytes.as_slice()));
+    assert_eq!("あ", drawing.header.project_name);
+
+    // unicode escapes still work
+    let bytes = drawing_bytes_with_code_page("ANSI_932", b"\\U+00E8");
+    let drawing = unwrap_drawing(Drawing::load(&mut bytes.as_slice()));
+    assert_eq!("è", drawing.header.project_name);
+}
+
+#[test]
+fn read_with_explicit_encoding_ignores_code_page() {
+    let bytes = drawing_bytes_with_code_page("ANSI_1251", &[0xC6, 0xE8]);
+    let drawing = unwrap_drawing(Drawing::load_with_encoding(
+        &mut bytes.as_slice(),
+        encoding_rs::WINDOWS_1252,
+    ));
+    assert_eq!("Æè", drawing.header.project_name);
+}
+
+#[test]
+fn write_with_encoding_from_code_page() {
+    let mut drawing = Drawing::new();
+    drawing.header.version = AcadVersion::R2004;
+    drawing.header.drawing_code_page = String::from("ANSI_1251");
+    drawing.header.project_name = String::from("Жè");
+    let mut buf = vec![];
+    drawing.save(&mut buf).unwrap();
+
+    // `Ж` is in the code page, `è` isn't
+    let expected = b"$PROJECTNAME\r\n  1\r\n\xC6\\U+00E8\r\n";
+    assert!(buf.windows(expected.len()).any(|w| w == expected));
+
+    let drawing = unwrap_drawing(Drawing::load(&mut buf.as_slice()));
+    assert_eq!("ANSI_1251", drawing.header.drawing_code_page);
+    assert_eq!("Жè", drawing.header.project_name);
+}
+
+#[test]
+fn binary_round_trip_with_code_page() {
+    for version in [AcadVersion::R12, AcadVersion::R2004, AcadVersion::R2007] {
+        let mut drawing = Drawing::new();
+        drawing.header.version = version;
+        drawing.header.drawing_code_page = String::from("ANSI_932");
+        drawing.header.file_name = String::from("あè");
+        let mut buf = vec![];
+        drawing.save_binary(&mut buf).unwrap();
+        let drawing = unwrap_drawing(Drawing::load(&mut buf.as_slice()));
+        assert_eq!("あè", drawing.header.file_name);
+    }
 }
 
 #[test]
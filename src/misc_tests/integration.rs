@@ -38,7 +38,7 @@ impl Oda {
         let mut oda_convert = Command::new(&self.oda_path)
             .arg(&self.input_path)
             .arg(&self.output_path)
-            .arg(&Oda::version_string(version))
+            .arg(Oda::version_string(version))
             .arg("DXF")
             .arg("0") // recurse
             .arg("1") // audit
@@ -0,0 +1,713 @@
+// parses and builds the inline formatting codes used by `MText` content
+
+use crate::text_codes::decode_unicode_escape;
+use crate::Color;
+
+/// The maximum number of characters stored in a single `MText` text chunk.
+pub(crate) const MTEXT_CHUNK_LENGTH: usize = 250;
+
+/// A piece of parsed `MText` content.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub enum MTextNode {
+    /// Literal text.
+    Text(String),
+    /// A `{...}` group; formatting changes inside a group don't apply after it.
+    Group(Vec<MTextNode>),
+    /// A formatting change that applies to the rest of the enclosing group.
+    Format(MTextFormat),
+    /// Text stacked as a fraction or tolerance (`\S`).
+    Stack {
+        top: String,
+        bottom: String,
+        kind: MTextStackKind,
+    },
+    /// Ends the current paragraph (`\P`).
+    ParagraphBreak,
+    /// Moves to the next column (`\N`).
+    ColumnBreak,
+    /// A tab character (`^I`).
+    Tab,
+    /// A non-breaking space (`\~`).
+    NonBreakingSpace,
+    /// An escape sequence that isn't otherwise understood, stored verbatim.
+    Unknown(String),
+}
+
+/// The way stacked text is drawn.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub enum MTextStackKind {
+    /// A horizontal fraction bar (`/`).
+    Fraction,
+    /// A diagonal fraction bar (`#`).
+    Diagonal,
+    /// A tolerance stack with no bar (`^`).
+    Tolerance,
+}
+
+/// The vertical alignment of text within a line (`\A`).
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub enum MTextVerticalAlignment {
+    Bottom,
+    Center,
+    Top,
+}
+
+/// A font selected with `\f`.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub struct MTextFont {
+    pub name: String,
+    pub is_bold: bool,
+    pub is_italic: bool,
+    pub code_page: i32,
+    pub pitch: i32,
+}
+
+impl MTextFont {
+    pub fn new(name: &str) -> Self {
+        MTextFont {
+            name: String::from(name),
+            is_bold: false,
+            is_italic: false,
+            code_page: 0,
+            pitch: 0,
+        }
+    }
+}
+
+/// Paragraph indents and tab stops set with `\p`.  Bulleted lists use a negative first line indent
+/// with a tab stop at the left indent.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub struct MTextParagraphProperties {
+    pub first_line_indent: Option<f64>,
+    pub left_indent: Option<f64>,
+    pub right_indent: Option<f64>,
+    pub tab_stops: Vec<f64>,
+    /// Any other properties, stored verbatim.
+    pub other: Vec<String>,
+}
+
+/// An inline formatting change.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub enum MTextFormat {
+    /// `\f`
+    Font(MTextFont),
+    /// `\F`
+    FontFile(String),
+    /// `\H`
+    Height(f64),
+    /// `\H` with an `x` suffix.
+    RelativeHeight(f64),
+    /// `\W`
+    WidthFactor(f64),
+    /// `\Q`, in degrees.
+    ObliqueAngle(f64),
+    /// `\T`
+    Tracking(f64),
+    /// `\A`
+    VerticalAlignment(MTextVerticalAlignment),
+    /// `\C`
+    Color(Color),
+    /// `\c`
+    TrueColor(i32),
+    /// `\L` and `\l`
+    Underline(bool),
+    /// `\O` and `\o`
+    Overline(bool),
+    /// `\K` and `\k`
+    StrikeThrough(bool),
+    /// `\p`
+    ParagraphProperties(MTextParagraphProperties),
+}
+
+impl MTextNode {
+    /// Parses `MText` content into a tree of nodes.
+    pub fn parse(text: &str) -> Vec<MTextNode> {
+        let mut chars = text.chars();
+        parse_nodes(&mut chars, false)
+    }
+    /// Formats the nodes as escaped `MText` content.
+    pub fn format(nodes: &[MTextNode]) -> String {
+        let mut result = String::new();
+        for node in nodes {
+            node.write(&mut result);
+        }
+        result
+    }
+    /// Returns the content of the nodes with all formatting removed.  Paragraph and column breaks
+    /// become newlines and stacked text is written as `top/bottom`.
+    pub fn plain_text(nodes: &[MTextNode]) -> String {
+        let mut result = String::new();
+        for node in nodes {
+            match node {
+                MTextNode::Text(text) => result.push_str(text),
+                MTextNode::Group(children) => result.push_str(&MTextNode::plain_text(children)),
+                MTextNode::Stack { top, bottom, .. } => {
+                    result.push_str(top);
+                    result.push('/');
+                    result.push_str(bottom);
+                }
+                MTextNode::ParagraphBreak | MTextNode::ColumnBreak => result.push('\n'),
+                MTextNode::Tab => result.push('\t'),
+                MTextNode::NonBreakingSpace => result.push(' '),
+                MTextNode::Format(_) | MTextNode::Unknown(_) => (),
+            }
+        }
+        result
+    }
+    fn write(&self, result: &mut String) {
+        match self {
+            MTextNode::Text(text) => {
+                for c in text.chars() {
+                    match c {
+                        '\\' => result.push_str("\\\\"),
+                        '{' => result.push_str("\\{"),
+                        '}' => result.push_str("\\}"),
+                        '^' => result.push_str("^ "),
+                        '\n' => result.push_str("\\P"),
+                        '\t' => result.push_str("^I"),
+                        _ => result.push(c),
+                    }
+                }
+            }
+            MTextNode::Group(children) => {
+                result.push('{');
+                for child in children {
+                    child.write(result);
+                }
+                result.push('}');
+            }
+            MTextNode::Format(format) => format.write(result),
+            MTextNode::Stack { top, bottom, kind } => {
+                let separator = match kind {
+                    MTextStackKind::Fraction => '/',
+                    MTextStackKind::Diagonal => '#',
+                    MTextStackKind::Tolerance => '^',
+                };
+                result.push_str("\\S");
+                result.push_str(&escape_stack_text(top));
+                result.push(separator);
+                result.push_str(&escape_stack_text(bottom));
+                result.push(';');
+            }
+            MTextNode::ParagraphBreak => result.push_str("\\P"),
+            MTextNode::ColumnBreak => result.push_str("\\N"),
+            MTextNode::Tab => result.push_str("^I"),
+            MTextNode::NonBreakingSpace => result.push_str("\\~"),
+            MTextNode::Unknown(raw) => result.push_str(raw),
+        }
+    }
+}
+
+impl MTextFormat {
+    fn write(&self, result: &mut String) {
+        let value = match self {
+            MTextFormat::Font(font) => format!(
+                "\\f{}|b{}|i{}|c{}|p{};",
+                font.name,
+                i32::from(font.is_bold),
+                i32::from(font.is_italic),
+                font.code_page,
+                font.pitch
+            ),
+            MTextFormat::FontFile(name) => format!("\\F{name};"),
+            MTextFormat::Height(height) => format!("\\H{height};"),
+            MTextFormat::RelativeHeight(factor) => format!("\\H{factor}x;"),
+            MTextFormat::WidthFactor(factor) => format!("\\W{factor};"),
+            MTextFormat::ObliqueAngle(angle) => format!("\\Q{angle};"),
+            MTextFormat::Tracking(factor) => format!("\\T{factor};"),
+            MTextFormat::VerticalAlignment(alignment) => format!(
+                "\\A{};",
+                match alignment {
+                    MTextVerticalAlignment::Bottom => 0,
+                    MTextVerticalAlignment::Center => 1,
+                    MTextVerticalAlignment::Top => 2,
+                }
+            ),
+            MTextFormat::Color(color) => format!("\\C{};", color.raw_value()),
+            MTextFormat::TrueColor(color) => format!("\\c{color};"),
+            MTextFormat::Underline(true) => String::from("\\L"),
+            MTextFormat::Underline(false) => String::from("\\l"),
+            MTextFormat::Overline(true) => String::from("\\O"),
+            MTextFormat::Overline(false) => String::from("\\o"),
+            MTextFormat::StrikeThrough(true) => String::from("\\K"),
+            MTextFormat::StrikeThrough(false) => String::from("\\k"),
+            MTextFormat::ParagraphProperties(props) => {
+                let mut parts = vec![];
+                if let Some(indent) = props.first_line_indent {
+                    parts.push(format!("i{indent}"));
+                }
+                if let Some(indent) = props.left_indent {
+                    parts.push(format!("l{indent}"));
+                }
+                if let Some(indent) = props.right_indent {
+                    parts.push(format!("r{indent}"));
+                }
+                for (i, stop) in props.tab_stops.iter().enumerate() {
+                    if i == 0 {
+                        parts.push(format!("t{stop}"));
+                    } else {
+                        parts.push(format!("{stop}"));
+                    }
+                }
+                parts.extend(props.other.iter().cloned());
+                format!("\\px{};", parts.join(","))
+            }
+        };
+        result.push_str(&value);
+    }
+}
+
+/// Builds escaped `MText` content.
+///
+/// ```
+/// # use dxf::*;
+/// let text = MTextBuilder::new()
+///     .text("Notes:")
+///     .paragraph_break()
+///     .bullet("\u{2022}", "first")
+///     .bullet("\u{2022}", "second")
+///     .build();
+/// assert_eq!("Notes:\\P{\\pxi-3,l3,t3;\u{2022}^Ifirst\\P}{\\pxi-3,l3,t3;\u{2022}^Isecond\\P}", text);
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct MTextBuilder {
+    nodes: Vec<MTextNode>,
+}
+
+impl MTextBuilder {
+    pub fn new() -> Self {
+        Default::default()
+    }
+    /// Appends literal text.  Newlines and tabs are converted to paragraph breaks and tabs.
+    pub fn text(mut self, text: &str) -> Self {
+        self.nodes.push(MTextNode::Text(String::from(text)));
+        self
+    }
+    /// Appends a formatting change.
+    pub fn format(mut self, format: MTextFormat) -> Self {
+        self.nodes.push(MTextNode::Format(format));
+        self
+    }
+    /// Appends a group whose formatting doesn't affect the following content.
+    pub fn group(mut self, build: impl FnOnce(MTextBuilder) -> MTextBuilder) -> Self {
+        self.nodes
+            .push(MTextNode::Group(build(MTextBuilder::new()).nodes));
+        self
+    }
+    /// Appends stacked text.
+    pub fn stack(mut self, top: &str, bottom: &str, kind: MTextStackKind) -> Self {
+        self.nodes.push(MTextNode::Stack {
+            top: String::from(top),
+            bottom: String::from(bottom),
+            kind,
+        });
+        self
+    }
+    pub fn paragraph_break(mut self) -> Self {
+        self.nodes.push(MTextNode::ParagraphBreak);
+        self
+    }
+    pub fn column_break(mut self) -> Self {
+        self.nodes.push(MTextNode::ColumnBreak);
+        self
+    }
+    pub fn tab(mut self) -> Self {
+        self.nodes.push(MTextNode::Tab);
+        self
+    }
+    pub fn non_breaking_space(mut self) -> Self {
+        self.nodes.push(MTextNode::NonBreakingSpace);
+        self
+    }
+    /// Appends a complete bulleted paragraph with a hanging indent.
+    pub fn bullet(self, bullet: &str, text: &str) -> Self {
+        self.group(|b| {
+            b.format(MTextFormat::ParagraphProperties(MTextParagraphProperties {
+                first_line_indent: Some(-3.0),
+                left_indent: Some(3.0),
+                tab_stops: vec![3.0],
+                ..Default::default()
+            }))
+            .text(bullet)
+            .tab()
+            .text(text)
+            .paragraph_break()
+        })
+    }
+    /// Returns the nodes built so far.
+    pub fn nodes(self) -> Vec<MTextNode> {
+        self.nodes
+    }
+    /// Returns the escaped `MText` content.
+    pub fn build(self) -> String {
+        MTextNode::format(&self.nodes)
+    }
+}
+
+/// Splits `MText` content into chunks of at most 250 characters, never breaking an escape
+/// sequence.  All but the last chunk belong in `extended_text`.
+pub(crate) fn split_mtext_chunks(text: &str) -> Vec<String> {
+    let chars = text.chars().collect::<Vec<_>>();
+    let mut chunks = vec![];
+    let mut chunk_start = 0;
+    let mut last_boundary = 0;
+    let mut i = 0;
+    while i < chars.len() {
+        let token_length = escape_length(&chars[i..]);
+        if i + token_length - chunk_start > MTEXT_CHUNK_LENGTH {
+            // an escape sequence longer than a whole chunk has to be broken
+            let split = if last_boundary > chunk_start {
+                last_boundary
+            } else {
+                chunk_start + MTEXT_CHUNK_LENGTH
+            };
+            chunks.push(chars[chunk_start..split].iter().collect::<String>());
+            chunk_start = split;
+            last_boundary = split;
+            i = i.max(split);
+            continue;
+        }
+        i += token_length;
+        last_boundary = i;
+    }
+    chunks.push(chars[chunk_start..].iter().collect::<String>());
+    chunks
+}
+
+/// Returns the number of characters in the escape sequence at the start of `chars`, or 1 for any
+/// other character.
+fn escape_length(chars: &[char]) -> usize {
+    match chars {
+        ['\\', 'U', '+', ..] => 7.min(chars.len()),
+        ['\\', 'M', '+', ..] => 8.min(chars.len()),
+        ['\\', c, ..] if "fFHWQTACcpS".contains(*c) => chars
+            .iter()
+            .position(|&c| c == ';')
+            .map_or(chars.len(), |p| p + 1),
+        ['\\', _, ..] | ['^', _, ..] => 2,
+        _ => 1,
+    }
+}
+
+fn escape_stack_text(text: &str) -> String {
+    let mut result = String::new();
+    for c in text.chars() {
+        match c {
+            '/' | '#' | '^' | ';' | '\\' => {
+                result.push('\\');
+                result.push(c);
+            }
+            _ => result.push(c),
+        }
+    }
+    result
+}
+
+/// The remaining characters are looked ahead at through `Chars::as_str()` without copying them.
+type CharIter<'a> = std::str::Chars<'a>;
+
+fn parse_nodes(chars: &mut CharIter<'_>, in_group: bool) -> Vec<MTextNode> {
+    let mut nodes = vec![];
+    let mut text = String::new();
+
+    macro_rules! push_node {
+        ($node:expr) => {{
+            if !text.is_empty() {
+                nodes.push(MTextNode::Text(std::mem::take(&mut text)));
+            }
+            nodes.push($node);
+        }};
+    }
+
+    while let Some(c) = chars.next() {
+        match c {
+            '{' => push_node!(MTextNode::Group(parse_nodes(chars, true))),
+            '}' if in_group => break,
+            '}' => (), // unbalanced
+            '^' => match chars.next() {
+                Some('I') => push_node!(MTextNode::Tab),
+                Some('J') => push_node!(MTextNode::ParagraphBreak),
+                Some(' ') => text.push('^'),
+                Some(c) => {
+                    text.push('^');
+                    text.push(c);
+                }
+                None => text.push('^'),
+            },
+            '\\' => match chars.next() {
+                Some('P') => push_node!(MTextNode::ParagraphBreak),
+                Some('N') => push_node!(MTextNode::ColumnBreak),
+                Some('~') => push_node!(MTextNode::NonBreakingSpace),
+                Some(c @ '\\') | Some(c @ '{') | Some(c @ '}') => text.push(c),
+                Some('L') => push_node!(MTextNode::Format(MTextFormat::Underline(true))),
+                Some('l') => push_node!(MTextNode::Format(MTextFormat::Underline(false))),
+                Some('O') => push_node!(MTextNode::Format(MTextFormat::Overline(true))),
+                Some('o') => push_node!(MTextNode::Format(MTextFormat::Overline(false))),
+                Some('K') => push_node!(MTextNode::Format(MTextFormat::StrikeThrough(true))),
+                Some('k') => push_node!(MTextNode::Format(MTextFormat::StrikeThrough(false))),
+                Some(c @ 'U') | Some(c @ 'M') if chars.as_str().starts_with('+') => {
+                    let mut sequence = vec!['\\', c];
+                    sequence.extend(chars.as_str().chars().take(6));
+                    match decode_unicode_escape(&sequence) {
+                        Some((decoded, consumed)) => {
+                            chars.nth(consumed - 3);
+                            text.push(decoded);
+                        }
+                        None => push_node!(MTextNode::Unknown(format!("\\{c}"))),
+                    }
+                }
+                Some('S') => {
+                    let (top, bottom, kind) = parse_stack(&read_argument(chars));
+                    push_node!(MTextNode::Stack { top, bottom, kind });
+                }
+                Some(code) if "fFHWQTACcp".contains(code) => {
+                    let arg = read_argument(chars);
+                    match parse_format(code, &arg) {
+                        Some(format) => push_node!(MTextNode::Format(format)),
+                        None => push_node!(MTextNode::Unknown(format!("\\{code}{arg};"))),
+                    }
+                }
+                Some(c) => push_node!(MTextNode::Unknown(format!("\\{c}"))),
+                None => text.push('\\'),
+            },
+            _ => text.push(c),
+        }
+    }
+
+    if !text.is_empty() {
+        nodes.push(MTextNode::Text(text));
+    }
+    nodes
+}
+
+fn read_argument(chars: &mut CharIter<'_>) -> String {
+    let mut arg = String::new();
+    while let Some(c) = chars.next() {
+        match c {
+            ';' => break,
+            '\\' if chars.as_str().starts_with(';') => {
+                arg.push(c);
+                arg.push(chars.next().unwrap_or(';'));
+            }
+            _ => arg.push(c),
+        }
+    }
+    arg
+}
+
+fn parse_stack(arg: &str) -> (String, String, MTextStackKind) {
+    let mut top = String::new();
+    let mut bottom = String::new();
+    let mut kind = None;
+    let mut chars = arg.chars();
+    while let Some(c) = chars.next() {
+        let target = if kind.is_some() {
+            &mut bottom
+        } else {
+            &mut top
+        };
+        match c {
+            '\\' => {
+                if let Some(c) = chars.next() {
+                    target.push(c);
+                }
+            }
+            '/' if kind.is_none() => kind = Some(MTextStackKind::Fraction),
+            '#' if kind.is_none() => kind = Some(MTextStackKind::Diagonal),
+            '^' if kind.is_none() => kind = Some(MTextStackKind::Tolerance),
+            _ => target.push(c),
+        }
+    }
+    (top, bottom, kind.unwrap_or(MTextStackKind::Fraction))
+}
+
+fn parse_format(code: char, arg: &str) -> Option<MTextFormat> {
+    let number = || arg.trim_end_matches(['x', 'X']).trim().parse::<f64>().ok();
+    match code {
+        'f' => {
+            let mut parts = arg.split('|');
+            let mut font = MTextFont::new(parts.next().unwrap_or(""));
+            for part in parts {
+                let value = part
+                    .get(1..)
+                    .and_then(|v| v.parse::<i32>().ok())
+                    .unwrap_or(0);
+                match part.chars().next() {
+                    Some('b') => font.is_bold = value != 0,
+                    Some('i') => font.is_italic = value != 0,
+                    Some('c') => font.code_page = value,
+                    Some('p') => font.pitch = value,
+                    _ => return None,
+                }
+            }
+            Some(MTextFormat::Font(font))
+        }
+        'F' => Some(MTextFormat::FontFile(String::from(arg))),
+        'H' if arg.ends_with(['x', 'X']) => number().map(MTextFormat::RelativeHeight),
+        'H' => number().map(MTextFormat::Height),
+        'W' => number().map(MTextFormat::WidthFactor),
+        'Q' => number().map(MTextFormat::ObliqueAngle),
+        'T' => number().map(MTextFormat::Tracking),
+        'A' => match arg.trim() {
+            "0" => Some(MTextFormat::VerticalAlignment(
+                MTextVerticalAlignment::Bottom,
+            )),
+            "1" => Some(MTextFormat::VerticalAlignment(
+                MTextVerticalAlignment::Center,
+            )),
+            "2" => Some(MTextFormat::VerticalAlignment(MTextVerticalAlignment::Top)),
+            _ => None,
+        },
+        'C' => arg
+            .trim()
+            .parse::<i16>()
+            .ok()
+            .map(|c| MTextFormat::Color(Color::from_raw_value(c))),
+        'c' => arg.trim().parse::<i32>().ok().map(MTextFormat::TrueColor),
+        'p' => parse_paragraph_properties(arg).map(MTextFormat::ParagraphProperties),
+        _ => None,
+    }
+}
+
+fn parse_paragraph_properties(arg: &str) -> Option<MTextParagraphProperties> {
+    let arg = arg.strip_prefix('x').unwrap_or(arg);
+    let mut props = MTextParagraphProperties::default();
+    let mut reading_tabs = false;
+    for part in arg.split(',').filter(|p| !p.is_empty()) {
+        let value = || part[1..].parse::<f64>().ok();
+        match part.chars().next() {
+            Some('i') => props.first_line_indent = Some(value()?),
+            Some('l') => props.left_indent = Some(value()?),
+            Some('r') => props.right_indent = Some(value()?),
+            Some('t') => {
+                props.tab_stops.push(value()?);
+                reading_tabs = true;
+                continue;
+            }
+            Some(_) if reading_tabs && part.parse::<f64>().is_ok() => {
+                props.tab_stops.push(part.parse::<f64>().ok()?);
+                continue;
+            }
+            _ => props.other.push(String::from(part)),
+        }
+        reading_tabs = false;
+    }
+    Some(props)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::mtext_format::*;
+    use crate::*;
+
+    #[test]
+    fn parse_formatting_tree() {
+        let nodes = MTextNode::parse("a{\\fArial|b1|i0|c0|p34;\\H2.5x;b}\\Pc\\S1/2;^Id");
+        assert_eq!(
+            vec![
+                MTextNode::Text(String::from("a")),
+                MTextNode::Group(vec![
+                    MTextNode::Format(MTextFormat::Font(MTextFont {
+                        name: String::from("Arial"),
+                        is_bold: true,
+                        is_italic: false,
+                        code_page: 0,
+                        pitch: 34,
+                    })),
+                    MTextNode::Format(MTextFormat::RelativeHeight(2.5)),
+                    MTextNode::Text(String::from("b")),
+                ]),
+                MTextNode::ParagraphBreak,
+                MTextNode::Text(String::from("c")),
+                MTextNode::Stack {
+                    top: String::from("1"),
+                    bottom: String::from("2"),
+                    kind: MTextStackKind::Fraction,
+                },
+                MTextNode::Tab,
+                MTextNode::Text(String::from("d")),
+            ],
+            nodes
+        );
+    }
+
+    #[test]
+    fn parse_escapes_and_colors() {
+        let nodes = MTextNode::parse("\\\\\\{\\}\\U+00E9\\M+182A0\\C1;\\c255;x\\~y\\Nz^ ");
+        assert_eq!(
+            vec![
+                MTextNode::Text(String::from("\\{}\u{00E9}\u{3042}")),
+                MTextNode::Format(MTextFormat::Color(Color::from_index(1))),
+                MTextNode::Format(MTextFormat::TrueColor(255)),
+                MTextNode::Text(String::from("x")),
+                MTextNode::NonBreakingSpace,
+                MTextNode::Text(String::from("y")),
+                MTextNode::ColumnBreak,
+                MTextNode::Text(String::from("z^")),
+            ],
+            nodes
+        );
+    }
+
+    #[test]
+    fn parse_paragraph_properties_for_bullets() {
+        let nodes = MTextNode::parse("\\pxi-3,l3,t3,6,qc;");
+        assert_eq!(
+            vec![MTextNode::Format(MTextFormat::ParagraphProperties(
+                MTextParagraphProperties {
+                    first_line_indent: Some(-3.0),
+                    left_indent: Some(3.0),
+                    right_indent: None,
+                    tab_stops: vec![3.0, 6.0],
+                    other: vec![String::from("qc")],
+                }
+            ))],
+            nodes
+        );
+    }
+
+    #[test]
+    fn format_round_trip() {
+        let text = "a{\\fArial|b1|i0|c0|p34;\\H2.5x;b}\\Pc\\S1^2;^Id\\\\\\{\\}\\L\\lz^ \\X";
+        assert_eq!(text, MTextNode::format(&MTextNode::parse(text)));
+    }
+
+    #[test]
+    fn plain_text() {
+        let nodes = MTextNode::parse("{\\fArial;abc}\\~def\\P\\S1#2; ^Ix");
+        assert_eq!("abc def\n1/2 \tx", MTextNode::plain_text(&nodes));
+    }
+
+    #[test]
+    fn builder_escapes_text() {
+        let text = MTextBuilder::new()
+            .group(|b| {
+                b.format(MTextFormat::Color(Color::from_index(1)))
+                    .text("{red}")
+            })
+            .text("a\\b\nc")
+            .stack("1", "2", MTextStackKind::Tolerance)
+            .build();
+        assert_eq!("{\\C1;\\{red\\}}a\\\\b\\Pc\\S1^2;", text);
+    }
+
+    #[test]
+    fn split_chunks_at_escape_boundaries() {
+        let text = "a".repeat(248) + "\\U+00E9" + &"b".repeat(300);
+        let chunks = split_mtext_chunks(&text);
+        assert_eq!(3, chunks.len());
+        assert_eq!(248, chunks[0].chars().count());
+        assert!(chunks[1].starts_with("\\U+00E9"));
+        assert!(chunks.iter().all(|c| c.chars().count() <= 250));
+        assert_eq!(text, chunks.concat());
+
+        assert_eq!(vec![String::from("short")], split_mtext_chunks("short"));
+        assert_eq!(vec![String::new()], split_mtext_chunks(""));
+    }
+}
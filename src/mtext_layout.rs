@@ -0,0 +1,561 @@
+// lays out `MText` content as a series of positioned `Text` entities
+
+use crate::entities::*;
+use crate::enums::*;
+use crate::geometry::*;
+use crate::mtext_format::*;
+use crate::{Color, Point, Vector};
+
+/// The ratio of line pitch to text height used by single line spacing.
+const LINE_SPACING_RATIO: f64 = 5.0 / 3.0;
+
+/// The height of each half of stacked text relative to the surrounding text.
+const STACK_HEIGHT_RATIO: f64 = 0.7;
+
+/// Measures the advance width of text.  Implement this to provide real font metrics to the `MText`
+/// layout.
+pub trait TextMetrics {
+    /// Returns the width of `text` rendered in the specified font at the specified height and
+    /// width factor.
+    fn text_width(&self, text: &str, font_name: &str, height: f64, width_factor: f64) -> f64;
+}
+
+/// Approximates text widths using fixed per-character proportions typical of a sans-serif font.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ApproximateTextMetrics;
+
+impl TextMetrics for ApproximateTextMetrics {
+    fn text_width(&self, text: &str, _font_name: &str, height: f64, width_factor: f64) -> f64 {
+        let width: f64 = text
+            .chars()
+            .map(|c| match c {
+                'i' | 'j' | 'l' | '!' | '|' | '.' | ',' | ':' | ';' | '\'' => 0.25,
+                ' ' | 'f' | 't' | 'r' | 'I' | '(' | ')' | '[' | ']' | '-' => 0.35,
+                'm' | 'w' | 'M' | 'W' | '@' => 0.85,
+                c if c.is_ascii_uppercase() => 0.7,
+                c if c.is_ascii() => 0.55,
+                // most non-latin glyphs are full-width
+                _ => 1.0,
+            })
+            .sum();
+        width * height * width_factor
+    }
+}
+
+/// A single piece of uniformly formatted text produced by laying out an `MText`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MTextRun {
+    /// The positioned text.  Underlined and overlined runs are prefixed with `%%u` and `%%o`.
+    pub text: Text,
+    /// The font requested by an inline `\f` or `\F` code, if any.
+    pub font_name: Option<String>,
+    /// The color requested by an inline `\C` code, if any.
+    pub color: Option<Color>,
+    /// The 24-bit color requested by an inline `\c` code, if any.
+    pub color_24_bit: Option<i32>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct RunStyle {
+    pub font_name: Option<String>,
+    pub height: f64,
+    pub width_factor: f64,
+    pub oblique_angle: f64,
+    pub tracking: f64,
+    pub color: Option<Color>,
+    pub color_24_bit: Option<i32>,
+    pub is_underlined: bool,
+    pub is_overlined: bool,
+    /// 0 = bottom, 1 = center, 2 = top
+    pub alignment: i32,
+}
+
+impl RunStyle {
+    pub(crate) fn new(mtext: &MText) -> Self {
+        RunStyle {
+            font_name: None,
+            height: mtext.initial_text_height,
+            width_factor: 1.0,
+            oblique_angle: 0.0,
+            tracking: 1.0,
+            color: None,
+            color_24_bit: None,
+            is_underlined: false,
+            is_overlined: false,
+            alignment: 0,
+        }
+    }
+    fn measure(&self, text: &str, metrics: &dyn TextMetrics, height: f64) -> f64 {
+        let font_name = self.font_name.as_deref().unwrap_or("");
+        metrics.text_width(text, font_name, height, self.width_factor) * self.tracking
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum Token {
+    Word(String, RunStyle),
+    Space(RunStyle),
+    /// Tolerance-style stacked text drawn one part over the other.
+    Stack(String, String, RunStyle),
+}
+
+impl Token {
+    fn style(&self) -> &RunStyle {
+        match self {
+            Token::Word(_, style) | Token::Space(style) | Token::Stack(_, _, style) => style,
+        }
+    }
+    fn width(&self, metrics: &dyn TextMetrics) -> f64 {
+        match self {
+            Token::Word(text, style) => style.measure(text, metrics, style.height),
+            Token::Space(style) => style.measure(" ", metrics, style.height),
+            Token::Stack(top, bottom, style) => {
+                let height = style.height * STACK_HEIGHT_RATIO;
+                style
+                    .measure(top, metrics, height)
+                    .max(style.measure(bottom, metrics, height))
+            }
+        }
+    }
+}
+
+/// Parses `MText` content into paragraphs of formatted tokens.
+pub(crate) fn parse_paragraphs(text: &str, initial_style: RunStyle) -> Vec<Vec<Token>> {
+    let mut tokenizer = Tokenizer {
+        paragraphs: vec![],
+        tokens: vec![],
+        word: String::new(),
+        style: initial_style,
+    };
+    tokenizer.visit(&MTextNode::parse(text));
+    tokenizer.flush_word();
+    tokenizer.paragraphs.push(tokenizer.tokens);
+    tokenizer.paragraphs
+}
+
+struct Tokenizer {
+    paragraphs: Vec<Vec<Token>>,
+    tokens: Vec<Token>,
+    word: String,
+    style: RunStyle,
+}
+
+impl Tokenizer {
+    fn flush_word(&mut self) {
+        if !self.word.is_empty() {
+            let word = std::mem::take(&mut self.word);
+            self.tokens.push(Token::Word(word, self.style.clone()));
+        }
+    }
+    fn end_paragraph(&mut self) {
+        self.flush_word();
+        self.paragraphs.push(std::mem::take(&mut self.tokens));
+    }
+    fn push_token(&mut self, token: Token) {
+        self.flush_word();
+        self.tokens.push(token);
+    }
+    fn visit(&mut self, nodes: &[MTextNode]) {
+        for node in nodes {
+            match node {
+                MTextNode::Text(text) => {
+                    for c in text.chars() {
+                        match c {
+                            ' ' | '\t' => self.push_token(Token::Space(self.style.clone())),
+                            '\n' => self.end_paragraph(),
+                            _ => self.word.push(c),
+                        }
+                    }
+                }
+                MTextNode::Group(children) => {
+                    self.flush_word();
+                    let saved = self.style.clone();
+                    self.visit(children);
+                    self.flush_word();
+                    self.style = saved;
+                }
+                MTextNode::Format(format) => {
+                    self.flush_word();
+                    self.apply(format);
+                }
+                MTextNode::Stack {
+                    top,
+                    bottom,
+                    kind: MTextStackKind::Tolerance,
+                } => self.push_token(Token::Stack(
+                    top.trim().to_string(),
+                    bottom.trim().to_string(),
+                    self.style.clone(),
+                )),
+                MTextNode::Stack { top, bottom, .. } => {
+                    // fractions are written inline
+                    let fraction = format!("{top}/{bottom}");
+                    self.push_token(Token::Word(fraction, self.style.clone()));
+                }
+                MTextNode::ParagraphBreak | MTextNode::ColumnBreak => self.end_paragraph(),
+                MTextNode::Unknown(raw) if raw == "\\X" => self.end_paragraph(),
+                MTextNode::Tab => self.push_token(Token::Space(self.style.clone())),
+                MTextNode::NonBreakingSpace => self.word.push(' '),
+                MTextNode::Unknown(raw) => self.word.push_str(raw),
+            }
+        }
+    }
+    fn apply(&mut self, format: &MTextFormat) {
+        let style = &mut self.style;
+        match format {
+            MTextFormat::Font(font) => style.font_name = Some(font.name.clone()),
+            MTextFormat::FontFile(name) => style.font_name = Some(name.clone()),
+            MTextFormat::Height(height) => style.height = *height,
+            MTextFormat::RelativeHeight(factor) => style.height *= factor,
+            MTextFormat::WidthFactor(factor) => style.width_factor = *factor,
+            MTextFormat::ObliqueAngle(angle) => style.oblique_angle = *angle,
+            MTextFormat::Tracking(factor) => style.tracking = *factor,
+            MTextFormat::VerticalAlignment(alignment) => {
+                style.alignment = match alignment {
+                    MTextVerticalAlignment::Bottom => 0,
+                    MTextVerticalAlignment::Center => 1,
+                    MTextVerticalAlignment::Top => 2,
+                }
+            }
+            MTextFormat::Color(color) => style.color = Some(color.clone()),
+            MTextFormat::TrueColor(color) => style.color_24_bit = Some(*color),
+            MTextFormat::Underline(value) => style.is_underlined = *value,
+            MTextFormat::Overline(value) => style.is_overlined = *value,
+            // strike-through and paragraph properties aren't supported by `Text`
+            MTextFormat::StrikeThrough(_) | MTextFormat::ParagraphProperties(_) => (),
+        }
+    }
+}
+
+/// Breaks a paragraph into lines no wider than `max_width`; a `max_width` of zero disables wrapping.
+fn wrap_paragraph(
+    tokens: Vec<Token>,
+    max_width: f64,
+    metrics: &dyn TextMetrics,
+) -> Vec<Vec<(Token, f64)>> {
+    let mut lines = vec![];
+    let mut line: Vec<(Token, f64)> = vec![];
+    let mut line_width = 0.0;
+    for token in tokens {
+        let width = token.width(metrics);
+        let is_space = matches!(token, Token::Space(_));
+        if !is_space && max_width > 0.0 && line_width + width > max_width {
+            trim_trailing_spaces(&mut line);
+            if !line.is_empty() {
+                lines.push(std::mem::take(&mut line));
+            }
+            line_width = 0.0;
+        }
+        if is_space && line.is_empty() && !lines.is_empty() {
+            // spaces at a wrap point are dropped
+            continue;
+        }
+        line_width += width;
+        line.push((token, width));
+    }
+
+    trim_trailing_spaces(&mut line);
+    lines.push(line);
+    lines
+}
+
+fn trim_trailing_spaces(line: &mut Vec<(Token, f64)>) {
+    while let Some((Token::Space(_), _)) = line.last() {
+        line.pop();
+    }
+}
+
+/// A run of text relative to the top-left of the `MText` box, in the text's own coordinates.
+struct PlacedRun {
+    text: String,
+    style: RunStyle,
+    height: f64,
+    x: f64,
+    y: f64,
+}
+
+/// Lays out the `MText` content, returning the positioned text runs.
+pub(crate) fn layout(mtext: &MText, metrics: &dyn TextMetrics) -> Vec<MTextRun> {
+    let lines = parse_paragraphs(&mtext.full_text(), RunStyle::new(mtext))
+        .into_iter()
+        .flat_map(|tokens| wrap_paragraph(tokens, mtext.reference_rectangle_width, metrics))
+        .collect::<Vec<_>>();
+
+    // vertical metrics of each line
+    let line_heights = lines
+        .iter()
+        .map(|line| {
+            line.iter()
+                .map(|(token, _)| token.style().height)
+                .fold(0.0, f64::max)
+        })
+        .map(|h| {
+            if h > 0.0 {
+                h
+            } else {
+                mtext.initial_text_height
+            }
+        })
+        .collect::<Vec<_>>();
+    let mut baselines = vec![];
+    let mut baseline = 0.0;
+    for (i, height) in line_heights.iter().enumerate() {
+        if i == 0 {
+            baseline = -height;
+        } else {
+            let pitch_height = match mtext.line_spacing_style {
+                MTextLineSpacingStyle::Exact => mtext.initial_text_height,
+                MTextLineSpacingStyle::AtLeast => height.max(mtext.initial_text_height),
+            };
+            baseline -= pitch_height * LINE_SPACING_RATIO * mtext.line_spacing_factor;
+        }
+        baselines.push(baseline);
+    }
+    let total_height = -baseline;
+
+    let (horizontal_factor, vertical_offset) = match mtext.attachment_point {
+        AttachmentPoint::TopLeft => (0.0, 0.0),
+        AttachmentPoint::TopCenter => (0.5, 0.0),
+        AttachmentPoint::TopRight => (1.0, 0.0),
+        AttachmentPoint::MiddleLeft => (0.0, total_height / 2.0),
+        AttachmentPoint::MiddleCenter => (0.5, total_height / 2.0),
+        AttachmentPoint::MiddleRight => (1.0, total_height / 2.0),
+        AttachmentPoint::BottomLeft => (0.0, total_height),
+        AttachmentPoint::BottomCenter => (0.5, total_height),
+        AttachmentPoint::BottomRight => (1.0, total_height),
+    };
+
+    let mut placed = vec![];
+    for (line_index, line) in lines.into_iter().enumerate() {
+        let line_width: f64 = line.iter().map(|(_, width)| width).sum();
+        let line_height = line_heights[line_index];
+        let baseline = baselines[line_index] + vertical_offset;
+        let mut x = -line_width * horizontal_factor;
+        for (token, width) in line {
+            let style = token.style().clone();
+            let y = baseline + (line_height - style.height) * f64::from(style.alignment) / 2.0;
+            match token {
+                Token::Word(text, _) => {
+                    append_run(&mut placed, text, style.clone(), style.height, x, y)
+                }
+                Token::Space(_) => append_run(
+                    &mut placed,
+                    String::from(" "),
+                    style.clone(),
+                    style.height,
+                    x,
+                    y,
+                ),
+                Token::Stack(top, bottom, _) => {
+                    let height = style.height * STACK_HEIGHT_RATIO;
+                    placed.push(PlacedRun {
+                        text: top,
+                        style: style.clone(),
+                        height,
+                        x,
+                        y: y + style.height * 0.45,
+                    });
+                    let y = y - style.height * 0.25;
+                    placed.push(PlacedRun {
+                        text: bottom,
+                        style,
+                        height,
+                        x,
+                        y,
+                    });
+                }
+            }
+            x += width;
+        }
+    }
+
+    // map the local coordinates into the drawing
+    let x_direction = if vector_length(&mtext.x_axis_direction) > EPSILON {
+        normalize(&mtext.x_axis_direction)
+    } else {
+        let (sin, cos) = mtext.rotation_angle.sin_cos();
+        let (ocs_x, ocs_y) = ocs_axes(&mtext.extrusion_direction);
+        Vector::new(
+            ocs_x.x * cos + ocs_y.x * sin,
+            ocs_x.y * cos + ocs_y.y * sin,
+            ocs_x.z * cos + ocs_y.z * sin,
+        )
+    };
+    let y_direction = normalize(&cross(&mtext.extrusion_direction, &x_direction));
+    let (ocs_x, ocs_y) = ocs_axes(&mtext.extrusion_direction);
+    let rotation = dot(&x_direction, &ocs_y)
+        .atan2(dot(&x_direction, &ocs_x))
+        .to_degrees();
+    placed
+        .into_iter()
+        .filter(|run| !run.text.trim().is_empty())
+        .map(|run| {
+            let world = Point::new(
+                mtext.insertion_point.x + x_direction.x * run.x + y_direction.x * run.y,
+                mtext.insertion_point.y + x_direction.y * run.x + y_direction.y * run.y,
+                mtext.insertion_point.z + x_direction.z * run.x + y_direction.z * run.y,
+            );
+            let mut value = String::new();
+            if run.style.is_underlined {
+                value.push_str("%%u");
+            }
+            if run.style.is_overlined {
+                value.push_str("%%o");
+            }
+            value.push_str(&run.text);
+            MTextRun {
+                text: Text {
+                    location: wcs_to_ocs(&world, &mtext.extrusion_direction),
+                    text_height: run.height,
+                    value,
+                    rotation,
+                    relative_x_scale_factor: run.style.width_factor,
+                    oblique_angle: run.style.oblique_angle,
+                    text_style_name: mtext.text_style_name.clone(),
+                    normal: mtext.extrusion_direction.clone(),
+                    ..Default::default()
+                },
+                font_name: run.style.font_name,
+                color: run.style.color,
+                color_24_bit: run.style.color_24_bit,
+            }
+        })
+        .collect()
+}
+
+/// Appends text to the previous run when the formatting matches, otherwise starts a new run.
+fn append_run(
+    placed: &mut Vec<PlacedRun>,
+    text: String,
+    style: RunStyle,
+    height: f64,
+    x: f64,
+    y: f64,
+) {
+    if let Some(last) = placed.last_mut() {
+        if last.style == style && last.height == height && last.y == y {
+            last.text.push_str(&text);
+            return;
+        }
+    }
+    placed.push(PlacedRun {
+        text,
+        style,
+        height,
+        x,
+        y,
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::mtext_layout::*;
+    use crate::*;
+    use float_cmp::approx_eq;
+
+    /// Every character is exactly as wide as the text height.
+    struct MonospaceMetrics;
+
+    impl TextMetrics for MonospaceMetrics {
+        fn text_width(&self, text: &str, _font_name: &str, height: f64, width_factor: f64) -> f64 {
+            text.chars().count() as f64 * height * width_factor
+        }
+    }
+
+    fn mtext(text: &str) -> MText {
+        MText {
+            text: String::from(text),
+            initial_text_height: 1.0,
+            reference_rectangle_width: 0.0,
+            ..Default::default()
+        }
+    }
+
+    fn values(runs: &[MTextRun]) -> Vec<&str> {
+        runs.iter().map(|r| r.text.value.as_str()).collect()
+    }
+
+    #[test]
+    fn layout_paragraphs() {
+        let runs = mtext("abc\\Pdef").layout(&MonospaceMetrics);
+        assert_eq!(vec!["abc", "def"], values(&runs));
+        assert_eq!(Point::new(0.0, -1.0, 0.0), runs[0].text.location);
+        assert!(approx_eq!(f64, -1.0 - 5.0 / 3.0, runs[1].text.location.y));
+    }
+
+    #[test]
+    fn layout_wraps_to_reference_width() {
+        let mut m = mtext("aaa bbb ccc");
+        m.reference_rectangle_width = 7.5;
+        let runs = m.layout(&MonospaceMetrics);
+        assert_eq!(vec!["aaa bbb", "ccc"], values(&runs));
+        assert_eq!(0.0, runs[1].text.location.x);
+    }
+
+    #[test]
+    fn layout_formatting_codes() {
+        let runs =
+            mtext("a{\\H2x;\\C1;\\fArial|b1;b}\\Lc\\l\\W0.5;\\Q15;d").layout(&MonospaceMetrics);
+        assert_eq!(vec!["a", "b", "%%uc", "d"], values(&runs));
+        assert_eq!(2.0, runs[1].text.text_height);
+        assert_eq!(Some(Color::from_index(1)), runs[1].color);
+        assert_eq!(Some(String::from("Arial")), runs[1].font_name);
+        assert_eq!(None, runs[2].color);
+        assert_eq!(1.0, runs[2].text.text_height);
+        assert_eq!(3.0, runs[2].text.location.x);
+        assert_eq!(0.5, runs[3].text.relative_x_scale_factor);
+        assert_eq!(15.0, runs[3].text.oblique_angle);
+    }
+
+    #[test]
+    fn layout_stacked_text() {
+        let runs = mtext("1\\S+0.1^-0.2;").layout(&MonospaceMetrics);
+        assert_eq!(vec!["1", "+0.1", "-0.2"], values(&runs));
+        assert!(runs[1].text.location.y > runs[2].text.location.y);
+        assert!(approx_eq!(f64, 0.7, runs[1].text.text_height));
+
+        let runs = mtext("\\S1/2;").layout(&MonospaceMetrics);
+        assert_eq!(vec!["1/2"], values(&runs));
+    }
+
+    #[test]
+    fn layout_attachment_and_rotation() {
+        let mut m = mtext("abcd");
+        m.attachment_point = AttachmentPoint::BottomRight;
+        m.insertion_point = Point::new(10.0, 10.0, 0.0);
+        m.x_axis_direction = Vector::new(0.0, 1.0, 0.0);
+        let runs = m.layout(&MonospaceMetrics);
+        assert_eq!(1, runs.len());
+        assert!(approx_eq!(f64, 90.0, runs[0].text.rotation));
+        // the text runs up from the bottom right corner, so it starts 4 units below the insertion
+        assert!(approx_eq!(
+            f64,
+            10.0,
+            runs[0].text.location.x,
+            epsilon = 1e-12
+        ));
+        assert!(approx_eq!(
+            f64,
+            6.0,
+            runs[0].text.location.y,
+            epsilon = 1e-12
+        ));
+    }
+
+    #[test]
+    fn explode_mtext_entity() {
+        let mut ent = Entity::new(EntityType::MText(mtext("a\\C3;b")));
+        ent.common.layer = String::from("layer");
+        let exploded = ent.explode_mtext().unwrap();
+        assert_eq!(2, exploded.len());
+        assert_eq!("layer", exploded[1].common.layer);
+        assert_eq!(Some(3), exploded[1].common.color.index());
+        match exploded[1].specific {
+            EntityType::Text(ref t) => assert_eq!("b", t.value),
+            _ => panic!("expected text"),
+        }
+        assert!(Entity::new(EntityType::Line(Default::default()))
+            .explode_mtext()
+            .is_none());
+    }
+}
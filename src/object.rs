@@ -7,11 +7,11 @@ use std::ops::Add;
 use chrono::Duration;
 
 use crate::{
-    CodePair, Color, DataTableValue, DxfError, DxfResult, Point, SectionTypeSettings,
-    TableCellStyle, TransformationMatrix,
+    CodePair, CodePairValue, Color, DataTableValue, DxfError, DxfResult, Handle, Point,
+    SectionTypeSettings, TableCellStyle, TransformationMatrix,
 };
 
-use crate::code_pair_put_back::CodePairPutBack;
+use crate::code_pair_put_back::{CodePairPutBack, COMMENT_CODE};
 use crate::enums::*;
 use crate::helper_functions::*;
 use crate::objects::*;
@@ -56,6 +56,96 @@ impl MLineStyleElement {
     }
 }
 
+//------------------------------------------------------------------------------
+//                                                                  XRecordValue
+//------------------------------------------------------------------------------
+/// A typed value stored in the data of an `XRecordObject`.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub enum XRecordValue {
+    /// Written with code 1.
+    Str(String),
+    /// Written with code 90.
+    Integer(i32),
+    /// Written with code 40.
+    Real(f64),
+    /// Written with codes 10, 20 and 30.
+    Point(Point),
+    /// Written with code 340.
+    Handle(Handle),
+    /// Any pair that isn't one of the other values, e.g., binary data or a boolean.
+    Other(CodePair),
+}
+
+impl XRecordValue {
+    /// Returns the code pairs that store the value.
+    pub fn code_pairs(&self) -> Vec<CodePair> {
+        match self {
+            XRecordValue::Str(s) => vec![CodePair::new_string(1, s)],
+            XRecordValue::Integer(i) => vec![CodePair::new_i32(90, *i)],
+            XRecordValue::Real(r) => vec![CodePair::new_f64(40, *r)],
+            XRecordValue::Point(p) => vec![
+                CodePair::new_f64(10, p.x),
+                CodePair::new_f64(20, p.y),
+                CodePair::new_f64(30, p.z),
+            ],
+            XRecordValue::Handle(h) => vec![CodePair::new_string(340, h.as_string())],
+            XRecordValue::Other(pair) => vec![pair.clone()],
+        }
+    }
+}
+
+//------------------------------------------------------------------------------
+//                                                                 XRecordObject
+//------------------------------------------------------------------------------
+impl XRecordObject {
+    /// Returns the data as typed values.  Only pairs with the codes that `XRecordValue` writes
+    /// become typed values, i.e., strings with code 1, integers with code 90, reals with code 40,
+    /// points with codes 10, 20 and 30 and handles with code 340; every other pair is returned as
+    /// `XRecordValue::Other`, so `set_values` writes back exactly the same pairs.
+    pub fn values(&self) -> Vec<XRecordValue> {
+        let mut values = vec![];
+        let mut index = 0;
+        while index < self.data_pairs.len() {
+            let pair = &self.data_pairs[index];
+            let value = match (&pair.value, pair.code) {
+                (CodePairValue::Double(x), 10) => match &self.data_pairs[index + 1..] {
+                    [CodePair {
+                        code: 20,
+                        value: CodePairValue::Double(y),
+                        ..
+                    }, CodePair {
+                        code: 30,
+                        value: CodePairValue::Double(z),
+                        ..
+                    }, ..] => {
+                        index += 2;
+                        XRecordValue::Point(Point::new(*x, *y, *z))
+                    }
+                    _ => XRecordValue::Other(pair.clone()),
+                },
+                (CodePairValue::Double(r), 40) => XRecordValue::Real(*r),
+                (CodePairValue::Integer(i), 90) => XRecordValue::Integer(*i),
+                (CodePairValue::Str(s), 1) => XRecordValue::Str(s.clone()),
+                (CodePairValue::Str(_), 340) => match pair.as_handle() {
+                    Ok(h) if h.as_string() == pair.assert_string().unwrap_or_default() => {
+                        XRecordValue::Handle(h)
+                    }
+                    _ => XRecordValue::Other(pair.clone()),
+                },
+                _ => XRecordValue::Other(pair.clone()),
+            };
+            values.push(value);
+            index += 1;
+        }
+        values
+    }
+    /// Replaces the data with the code pairs of `values`.
+    pub fn set_values(&mut self, values: &[XRecordValue]) {
+        self.data_pairs = values.iter().flat_map(|v| v.code_pairs()).collect();
+    }
+}
+
 //------------------------------------------------------------------------------
 //                                                                     DataTable
 //------------------------------------------------------------------------------
@@ -94,17 +184,24 @@ impl Object {
         // no object-specific values to set
     }
     pub(crate) fn read(iter: &mut CodePairPutBack) -> DxfResult<Option<Object>> {
+        let mut obj = Object::read_without_comments(iter)?;
+        if let Some(ref mut obj) = obj {
+            obj.common.comments = iter.take_comments();
+        }
+        Ok(obj)
+    }
+    fn read_without_comments(iter: &mut CodePairPutBack) -> DxfResult<Option<Object>> {
         loop {
             match iter.next() {
                 // first code pair must be 0/object-type
                 Some(Ok(pair @ CodePair { code: 0, .. })) => {
-                    let type_string = pair.assert_string()?;
+                    let type_string = pair.as_str()?;
                     if type_string == "ENDSEC" || type_string == "ENDBLK" {
                         iter.put_back(Ok(pair));
                         return Ok(None);
                     }
 
-                    match ObjectType::from_type_string(&type_string) {
+                    match ObjectType::from_type_string(type_string) {
                         Some(e) => {
                             let mut obj = Object::new(e);
                             if !obj.apply_custom_reader(iter)? {
@@ -116,7 +213,9 @@ impl Object {
                                             iter.put_back(Ok(pair));
                                             break;
                                         }
-                                        Some(Ok(pair)) => obj.apply_code_pair(&pair, iter)?,
+                                        Some(Ok(mut pair)) => {
+                                            obj.apply_code_pair(&mut pair, iter)?
+                                        }
                                         Some(Err(e)) => return Err(e),
                                         None => return Err(DxfError::UnexpectedEndOfInput),
                                     }
@@ -155,7 +254,11 @@ impl Object {
             }
         }
     }
-    fn apply_code_pair(&mut self, pair: &CodePair, iter: &mut CodePairPutBack) -> DxfResult<()> {
+    fn apply_code_pair(
+        &mut self,
+        pair: &mut CodePair,
+        iter: &mut CodePairPutBack,
+    ) -> DxfResult<()> {
         if !self.specific.try_apply_code_pair(pair)? {
             self.common.apply_individual_pair(pair, iter)?;
         }
@@ -328,7 +431,7 @@ impl Object {
         let mut current_3d_point = Point::origin();
 
         loop {
-            let pair = next_pair!(iter);
+            let mut pair = next_pair!(iter);
             match pair.code {
                 1 => {
                     data.name = pair.assert_string()?;
@@ -414,20 +517,16 @@ impl Object {
                     );
                     current_3d_point = Point::origin();
                 }
-                330 | 331 | 340 | 350 | 360 => {
-                    if read_row_count || read_column_count {
-                        data.set_value(
-                            current_row,
-                            current_column,
-                            DataTableValue::Handle(pair.as_handle()?),
-                        );
-                    } else {
-                        common.apply_individual_pair(&pair, iter)?;
-                    }
+                330 | 331 | 340 | 350 | 360 if read_row_count || read_column_count => {
+                    data.set_value(
+                        current_row,
+                        current_column,
+                        DataTableValue::Handle(pair.as_handle()?),
+                    );
                 }
 
                 _ => {
-                    common.apply_individual_pair(&pair, iter)?;
+                    common.apply_individual_pair(&mut pair, iter)?;
                 }
             }
 
@@ -449,7 +548,7 @@ impl Object {
     ) -> DxfResult<bool> {
         let mut last_entry_name = String::new();
         loop {
-            let pair = next_pair!(iter);
+            let mut pair = next_pair!(iter);
             match pair.code {
                 3 => {
                     last_entry_name = pair.assert_string()?;
@@ -466,11 +565,13 @@ impl Object {
                     );
                 }
                 350 | 360 => {
+                    // only hard owners write their entries with 360
+                    dict.is_hard_owner |= pair.code == 360;
                     let handle = pair.as_handle()?;
                     dict.value_handles.insert(last_entry_name.clone(), handle);
                 }
                 _ => {
-                    common.apply_individual_pair(&pair, iter)?;
+                    common.apply_individual_pair(&mut pair, iter)?;
                 }
             }
         }
@@ -482,7 +583,7 @@ impl Object {
     ) -> DxfResult<bool> {
         let mut last_entry_name = String::new();
         loop {
-            let pair = next_pair!(iter);
+            let mut pair = next_pair!(iter);
             match pair.code {
                 3 => {
                     last_entry_name = pair.assert_string()?;
@@ -503,7 +604,7 @@ impl Object {
                     dict.value_handles.insert(last_entry_name.clone(), handle);
                 }
                 _ => {
-                    common.apply_individual_pair(&pair, iter)?;
+                    common.apply_individual_pair(&mut pair, iter)?;
                 }
             }
         }
@@ -515,12 +616,12 @@ impl Object {
     ) -> DxfResult<bool> {
         let mut is_reading_plot_settings = true;
         loop {
-            let pair = next_pair!(iter);
+            let mut pair = next_pair!(iter);
             if is_reading_plot_settings {
-                if pair.code == 100 && pair.assert_string()? == "AcDbLayout" {
+                if pair.code == 100 && pair.as_str()? == "AcDbLayout" {
                     is_reading_plot_settings = false;
                 } else {
-                    common.apply_individual_pair(&pair, iter)?;
+                    common.apply_individual_pair(&mut pair, iter)?;
                 }
             } else {
                 match pair.code {
@@ -620,7 +721,7 @@ impl Object {
                         layout.__table_record_base_handle = pair.as_handle()?;
                     }
                     _ => {
-                        common.apply_individual_pair(&pair, iter)?;
+                        common.apply_individual_pair(&mut pair, iter)?;
                     }
                 }
             }
@@ -633,7 +734,7 @@ impl Object {
     ) -> DxfResult<bool> {
         let mut read_version_number = false;
         loop {
-            let pair = next_pair!(iter);
+            let mut pair = next_pair!(iter);
             match pair.code {
                 1 => {} // don't worry about the light's name; it'll be read from the light entity directly
                 5 => {
@@ -642,7 +743,7 @@ impl Object {
                         ll.__lights_handle.push(pair.as_handle()?);
                     } else {
                         // might still be the handle
-                        common.apply_individual_pair(&pair, iter)?;
+                        common.apply_individual_pair(&mut pair, iter)?;
                     }
                 }
                 90 => {
@@ -654,7 +755,7 @@ impl Object {
                     }
                 }
                 _ => {
-                    common.apply_individual_pair(&pair, iter)?;
+                    common.apply_individual_pair(&mut pair, iter)?;
                 }
             }
         }
@@ -680,7 +781,7 @@ impl Object {
         let mut read_bump_map_auto_transform_method = false;
         let mut read_use_image_file_for_refraction_map = false;
         loop {
-            let pair = next_pair!(iter);
+            let mut pair = next_pair!(iter);
             match pair.code {
                 1 => {
                     mat.name = pair.assert_string()?;
@@ -1066,7 +1167,7 @@ impl Object {
                     mat.gen_proc_real_value = pair.assert_f64()?;
                 }
                 _ => {
-                    common.apply_individual_pair(&pair, iter)?;
+                    common.apply_individual_pair(&mut pair, iter)?;
                 }
             }
         }
@@ -1078,7 +1179,7 @@ impl Object {
     ) -> DxfResult<bool> {
         let mut read_element_count = false;
         loop {
-            let pair = next_pair!(iter);
+            let mut pair = next_pair!(iter);
             match pair.code {
                 2 => {
                     mline.style_name = pair.assert_string()?;
@@ -1115,7 +1216,7 @@ impl Object {
                     read_element_count = true;
                 }
                 _ => {
-                    common.apply_individual_pair(&pair, iter)?;
+                    common.apply_individual_pair(&mut pair, iter)?;
                 }
             }
         }
@@ -1126,7 +1227,7 @@ impl Object {
         iter: &mut CodePairPutBack,
     ) -> DxfResult<bool> {
         loop {
-            let pair = next_pair!(iter);
+            let mut pair = next_pair!(iter);
             match pair.code {
                 1 => {
                     // value should be "SectionTypeSettings", but it doesn't really matter
@@ -1139,7 +1240,7 @@ impl Object {
                 }
                 91 => (), // generation settings count; we just read as many as we're given
                 _ => {
-                    common.apply_individual_pair(&pair, iter)?;
+                    common.apply_individual_pair(&mut pair, iter)?;
                 }
             }
         }
@@ -1151,7 +1252,7 @@ impl Object {
     ) -> DxfResult<bool> {
         let mut is_ready_for_sort_handles = false;
         loop {
-            let pair = next_pair!(iter);
+            let mut pair = next_pair!(iter);
             match pair.code {
                 5 => {
                     if is_ready_for_sort_handles {
@@ -1173,7 +1274,7 @@ impl Object {
                     is_ready_for_sort_handles = true;
                 }
                 _ => {
-                    common.apply_individual_pair(&pair, iter)?;
+                    common.apply_individual_pair(&mut pair, iter)?;
                 }
             }
         }
@@ -1187,7 +1288,7 @@ impl Object {
         let mut set_inverse_matrix = false;
         let mut matrix_list = vec![];
         loop {
-            let pair = next_pair!(iter);
+            let mut pair = next_pair!(iter);
             match pair.code {
                 10 => {
                     // code 10 always starts a new point
@@ -1267,7 +1368,7 @@ impl Object {
                     sf.clip_boundary_normal.z = pair.assert_f64()?;
                 }
                 _ => {
-                    common.apply_individual_pair(&pair, iter)?;
+                    common.apply_individual_pair(&mut pair, iter)?;
                 }
             }
         }
@@ -1281,7 +1382,7 @@ impl Object {
         let mut reading_hours = false;
         let mut julian_day = None;
         loop {
-            let pair = next_pair!(iter);
+            let mut pair = next_pair!(iter);
             match pair.code {
                 1 => {
                     ss.sun_setup_name = pair.assert_string()?;
@@ -1378,7 +1479,7 @@ impl Object {
                     ss.__text_style_handle = pair.as_handle()?;
                 }
                 _ => {
-                    common.apply_individual_pair(&pair, iter)?;
+                    common.apply_individual_pair(&mut pair, iter)?;
                 }
             }
         }
@@ -1390,7 +1491,7 @@ impl Object {
     ) -> DxfResult<bool> {
         let mut read_version = false;
         loop {
-            let pair = next_pair!(iter);
+            let mut pair = next_pair!(iter);
             match pair.code {
                 3 => {
                     ts.description = pair.assert_string()?;
@@ -1427,7 +1528,7 @@ impl Object {
                     ts.is_column_heading_suppressed = as_bool(pair.assert_i16()?);
                 }
                 _ => {
-                    common.apply_individual_pair(&pair, iter)?;
+                    common.apply_individual_pair(&mut pair, iter)?;
                 }
             }
         }
@@ -1438,10 +1539,12 @@ impl Object {
         iter: &mut CodePairPutBack,
     ) -> DxfResult<bool> {
         let mut reading_data = false;
+        let mut is_after_subclass_marker = false;
         loop {
-            let pair = next_pair!(iter);
-            if reading_data {
+            let mut pair = next_pair!(iter);
+            if reading_data && !(is_after_subclass_marker && pair.code == 280) {
                 xr.data_pairs.push(pair);
+                is_after_subclass_marker = false;
             } else {
                 if pair.code == 280 {
                     xr.duplicate_record_handling = enum_from_number!(
@@ -1451,16 +1554,19 @@ impl Object {
                         pair.assert_i16()?
                     );
                     reading_data = true;
+                    is_after_subclass_marker = false;
                     continue;
                 }
 
-                if common.apply_individual_pair(&pair, iter)? {
+                if common.apply_individual_pair(&mut pair, iter)? {
                     continue;
                 }
 
                 match pair.code {
                     100 => {
+                        // the 280 flag is written after the subclass marker
                         reading_data = true;
+                        is_after_subclass_marker = true;
                         continue;
                     } // value should be "AcDbXrecord", some dxf files still need to keep the object read
                     5 | 105 => (), // these codes aren't allowed here
@@ -1474,6 +1580,9 @@ impl Object {
     }
     pub(crate) fn add_code_pairs(&self, pairs: &mut Vec<CodePair>, version: AcadVersion) {
         if self.specific.is_supported_on_version(version) {
+            for comment in &self.common.comments {
+                pairs.push(CodePair::new_string(COMMENT_CODE, comment));
+            }
             pairs.push(CodePair::new_str(0, self.specific.to_type_string()));
             self.common.add_code_pairs(pairs, version);
             if !self.add_custom_code_pairs(pairs, version) {
@@ -1532,7 +1641,7 @@ impl Object {
                                     pairs.push(CodePair::new_f64(31, val.z));
                                 }
                                 Some(DataTableValue::Handle(val)) => {
-                                    pairs.push(CodePair::new_string(331, &val.as_string()));
+                                    pairs.push(CodePair::new_string(331, val.as_string()));
                                 }
                                 None => (),
                             }
@@ -1555,7 +1664,7 @@ impl Object {
                 for key in dict.value_handles.keys().sorted_by(Ord::cmp) {
                     if let Some(value) = dict.value_handles.get(key) {
                         pairs.push(CodePair::new_string(3, key));
-                        pairs.push(CodePair::new_string(code, &value.as_string()));
+                        pairs.push(CodePair::new_string(code, value.as_string()));
                     }
                 }
             }
@@ -1567,11 +1676,11 @@ impl Object {
                         dict.duplicate_record_handling as i16,
                     ));
                 }
-                pairs.push(CodePair::new_string(340, &dict.default_handle.as_string()));
+                pairs.push(CodePair::new_string(340, dict.default_handle.as_string()));
                 for key in dict.value_handles.keys().sorted_by(Ord::cmp) {
                     if let Some(value) = dict.value_handles.get(key) {
                         pairs.push(CodePair::new_string(3, key));
-                        pairs.push(CodePair::new_string(350, &value.as_string()));
+                        pairs.push(CodePair::new_string(350, value.as_string()));
                     }
                 }
             }
@@ -1580,7 +1689,7 @@ impl Object {
                 pairs.push(CodePair::new_i32(90, ll.version));
                 pairs.push(CodePair::new_i32(90, ll.__lights_handle.len() as i32));
                 for light in &ll.__lights_handle {
-                    pairs.push(CodePair::new_string(5, &light.as_string()));
+                    pairs.push(CodePair::new_string(5, light.as_string()));
                     // TODO: write the light's real name
                     pairs.push(CodePair::new_string(1, ""));
                 }
@@ -1594,7 +1703,7 @@ impl Object {
                 }
             }
             ObjectType::SunStudy(ref ss) => {
-                pairs.push(CodePair::new_string(100, &String::from("AcDbSunStudy")));
+                pairs.push(CodePair::new_string(100, "AcDbSunStudy"));
                 pairs.push(CodePair::new_i32(90, ss.version));
                 pairs.push(CodePair::new_string(1, &ss.sun_setup_name));
                 pairs.push(CodePair::new_string(2, &ss.description));
@@ -1617,12 +1726,12 @@ impl Object {
                 }
                 pairs.push(CodePair::new_string(
                     340,
-                    &ss.__page_setup_wizard_handle.as_string(),
+                    ss.__page_setup_wizard_handle.as_string(),
                 ));
-                pairs.push(CodePair::new_string(341, &ss.__view_handle.as_string()));
+                pairs.push(CodePair::new_string(341, ss.__view_handle.as_string()));
                 pairs.push(CodePair::new_string(
                     342,
-                    &ss.__visual_style_handle.as_string(),
+                    ss.__visual_style_handle.as_string(),
                 ));
                 pairs.push(CodePair::new_i16(74, ss.shade_plot_type));
                 pairs.push(CodePair::new_i16(75, ss.viewports_per_page as i16));
@@ -1639,7 +1748,7 @@ impl Object {
                 pairs.push(CodePair::new_bool(294, ss.label_viewports));
                 pairs.push(CodePair::new_string(
                     343,
-                    &ss.__text_style_handle.as_string(),
+                    ss.__text_style_handle.as_string(),
                 ));
             }
             ObjectType::XRecordObject(ref xr) => {
@@ -2206,6 +2315,112 @@ mod tests {
         }
     }
 
+    #[test]
+    fn read_xrecord_flag_after_subclass_marker() {
+        let obj = read_object(
+            "XRECORD",
+            vec![
+                CodePair::new_str(100, "AcDbXrecord"),
+                CodePair::new_i16(280, 1),
+                CodePair::new_i16(280, 7),
+            ],
+        );
+        match obj.specific {
+            ObjectType::XRecordObject(ref xr) => {
+                assert_eq!(
+                    DictionaryDuplicateRecordHandling::KeepExisting,
+                    xr.duplicate_record_handling
+                );
+                assert_eq!(vec![CodePair::new_i16(280, 7)], xr.data_pairs);
+            }
+            _ => panic!("expected a xrecord object"),
+        }
+    }
+
+    #[test]
+    fn read_xrecord_typed_values() {
+        let xr = XRecordObject {
+            data_pairs: vec![
+                CodePair::new_str(1, "pump"),
+                CodePair::new_i16(70, 3),
+                CodePair::new_i32(90, 1_000_000),
+                CodePair::new_f64(40, 2.5),
+                CodePair::new_f64(10, 1.0),
+                CodePair::new_f64(20, 2.0),
+                CodePair::new_f64(30, 3.0),
+                CodePair::new_f64(11, 4.0),
+                CodePair::new_f64(21, 5.0),
+                CodePair::new_str(340, "2F"),
+                CodePair::new_bool(290, true),
+            ],
+            ..Default::default()
+        };
+        assert_eq!(
+            vec![
+                XRecordValue::Str(String::from("pump")),
+                XRecordValue::Other(CodePair::new_i16(70, 3)),
+                XRecordValue::Integer(1_000_000),
+                XRecordValue::Real(2.5),
+                XRecordValue::Point(Point::new(1.0, 2.0, 3.0)),
+                XRecordValue::Other(CodePair::new_f64(11, 4.0)),
+                XRecordValue::Other(CodePair::new_f64(21, 5.0)),
+                XRecordValue::Handle(Handle(0x2F)),
+                XRecordValue::Other(CodePair::new_bool(290, true)),
+            ],
+            xr.values()
+        );
+    }
+
+    #[test]
+    fn xrecord_values_round_trip_original_codes() {
+        let data_pairs = vec![
+            CodePair::new_i16(70, 3),
+            CodePair::new_str(3, "name"),
+            CodePair::new_str(1, "text"),
+            CodePair::new_f64(41, 1.5),
+            CodePair::new_f64(10, 1.0),
+            CodePair::new_f64(20, 2.0), // no z
+            CodePair::new_str(330, "1A"),
+            CodePair::new_str(340, "00b"),
+            CodePair::new_i32(90, 7),
+        ];
+        let mut xr = XRecordObject {
+            data_pairs: data_pairs.clone(),
+            ..Default::default()
+        };
+        let values = xr.values();
+        assert_eq!(XRecordValue::Str(String::from("text")), values[2]);
+        assert_eq!(XRecordValue::Integer(7), values[8]);
+        xr.set_values(&values);
+        assert_eq!(data_pairs, xr.data_pairs);
+    }
+
+    #[test]
+    fn write_xrecord_typed_values() {
+        let mut xr = XRecordObject::default();
+        let values = vec![
+            XRecordValue::Str(String::from("pump")),
+            XRecordValue::Integer(42),
+            XRecordValue::Real(2.5),
+            XRecordValue::Point(Point::new(1.0, 2.0, 3.0)),
+            XRecordValue::Handle(Handle(0x2F)),
+        ];
+        xr.set_values(&values);
+        assert_eq!(
+            vec![
+                CodePair::new_str(1, "pump"),
+                CodePair::new_i32(90, 42),
+                CodePair::new_f64(40, 2.5),
+                CodePair::new_f64(10, 1.0),
+                CodePair::new_f64(20, 2.0),
+                CodePair::new_f64(30, 3.0),
+                CodePair::new_str(340, "2F"),
+            ],
+            xr.data_pairs
+        );
+        assert_eq!(values, xr.values());
+    }
+
     #[test]
     fn read_all_types() {
         for (type_string, expected_type, _) in all_types::all_object_types() {
@@ -1,11 +1,11 @@
 use crate::code_pair_put_back::CodePairPutBack;
 use crate::objects::Object;
 
-pub(crate) struct ObjectIter<'a> {
-    pub iter: &'a mut CodePairPutBack,
+pub(crate) struct ObjectIter<'a, 'b> {
+    pub iter: &'a mut CodePairPutBack<'b>,
 }
 
-impl Iterator for ObjectIter<'_> {
+impl Iterator for ObjectIter<'_, '_> {
     type Item = Object;
 
     fn next(&mut self) -> Option<Object> {
@@ -0,0 +1,302 @@
+use std::thread;
+
+use crate::code_pair_iter::{CodePairIter, RawPosition, RawSection};
+use crate::code_pair_put_back::CodePairPutBack;
+use crate::{CodePair, CodePairValue, DxfResult};
+
+// splitting a section only pays off when every thread has a reasonable amount of work
+const MIN_PAIRS_PER_CHUNK: usize = 4096;
+
+/// Reads the items of the current section on up to `thread_count` threads.
+///
+/// The undecoded pairs up to the section's `0/ENDSEC` (or `0/ENDBLK`) are found without reading
+/// them, split into chunks that each start with an item and every chunk is decoded and read with
+/// `read_item` on its own thread.  The items of each chunk are returned in file order.  If the
+/// section is too small to split or any chunk can't be read to its end, nothing is consumed and
+/// `None` is returned so the caller can read the section sequentially instead; this guarantees the
+/// same result, errors included, as sequential reading.
+pub(crate) fn read_items_in_parallel<T, F>(
+    iter: &mut CodePairPutBack,
+    thread_count: usize,
+    read_item: F,
+) -> Option<Vec<Vec<T>>>
+where
+    T: Send,
+    F: Fn(&mut CodePairPutBack) -> DxfResult<Option<T>> + Sync,
+{
+    if thread_count < 2 {
+        return None;
+    }
+
+    let section = iter.scan_section()?;
+    let chunk_count = thread_count.min(section.pair_count / MIN_PAIRS_PER_CHUNK);
+    if chunk_count < 2 {
+        return None;
+    }
+
+    let bounds = chunk_bounds(&section, chunk_count);
+    let results = thread::scope(|scope| {
+        let workers = bounds
+            .windows(2)
+            .map(|range| {
+                let (start, end) = (range[0], range[1]);
+                let section = &section;
+                let read_item = &read_item;
+                scope.spawn(move || read_chunk(section.decode(start, end), read_item))
+            })
+            .collect::<Vec<_>>();
+        workers
+            .into_iter()
+            .map(|worker| worker.join().ok().flatten())
+            .collect::<Vec<_>>()
+    });
+
+    let end = section.end;
+    if results.iter().all(Option::is_some) {
+        iter.skip_to(end);
+        Some(results.into_iter().flatten().collect())
+    } else {
+        None
+    }
+}
+
+/// Returns the start of each chunk followed by the end of the last one.  Chunks only start where
+/// an item does so that every item is read entirely by one thread.
+fn chunk_bounds(section: &RawSection, chunk_count: usize) -> Vec<RawPosition> {
+    let target_size = section.end.position / chunk_count;
+    let mut bounds = vec![section.start];
+    let mut next_bound = target_size;
+    for &start in &section.item_starts {
+        if start.position >= next_bound {
+            bounds.push(start);
+            next_bound = start.position + target_size;
+        }
+    }
+
+    bounds.push(section.end);
+    bounds
+}
+
+fn read_chunk<T, F>(pairs: Box<dyn CodePairIter + '_>, read_item: &F) -> Option<Vec<T>>
+where
+    F: Fn(&mut CodePairPutBack) -> DxfResult<Option<T>>,
+{
+    let chunk = ChunkPairs {
+        pairs,
+        section_end: Some(CodePair::new_str(0, "ENDSEC")),
+    };
+    let mut iter = CodePairPutBack::from_code_pair_iter(Box::new(chunk));
+    let mut items = vec![];
+    loop {
+        match read_item(&mut iter) {
+            Ok(Some(item)) => items.push(item),
+            Ok(None) => break,
+            Err(_) => return None,
+        }
+    }
+
+    // the chunk only counts as read if nothing but the stand-in is left
+    match (iter.next(), iter.next()) {
+        (Some(Ok(ref pair)), None) if pair.code == 0 && is_section_end(pair) => Some(items),
+        _ => None,
+    }
+}
+
+fn is_section_end(pair: &CodePair) -> bool {
+    match pair.value {
+        CodePairValue::Str(ref s) => s == "ENDSEC" || s == "ENDBLK",
+        _ => false,
+    }
+}
+
+/// The pairs of a chunk followed by a stand-in for the end of the section so the last item stops
+/// where it would have.
+struct ChunkPairs<'a> {
+    pairs: Box<dyn CodePairIter + 'a>,
+    section_end: Option<CodePair>,
+}
+
+impl CodePairIter for ChunkPairs<'_> {
+    fn read_as_utf8(&mut self) {
+        self.pairs.read_as_utf8()
+    }
+    fn read_with_code_page(&mut self, code_page: &str) {
+        self.pairs.read_with_code_page(code_page)
+    }
+}
+
+impl Iterator for ChunkPairs<'_> {
+    type Item = DxfResult<CodePair>;
+
+    fn next(&mut self) -> Option<DxfResult<CodePair>> {
+        match self.pairs.next() {
+            None => self.section_end.take().map(Ok),
+            pair => pair,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::read_items_in_parallel;
+    use crate::code_pair_iter::new_code_pair_iter_from_reader;
+    use crate::code_pair_put_back::CodePairPutBack;
+    use crate::entities::*;
+    use crate::entity_iter::collect_entities;
+    use crate::enums::*;
+    use crate::objects::*;
+    use crate::*;
+
+    use std::io::Cursor;
+
+    fn large_drawing() -> Drawing {
+        let mut drawing = Drawing::new();
+        drawing.header.version = AcadVersion::R2000;
+        for i in 0..3_000 {
+            let x = i as f64;
+            let mut entity = match i % 3 {
+                0 => Entity::new(EntityType::Line(Line::new(
+                    Point::new(x, 0.0, 0.0),
+                    Point::new(x, 1.0, 0.0),
+                ))),
+                1 => {
+                    let mut poly = Polyline::default();
+                    for j in 0..3 {
+                        poly.add_vertex(&mut drawing, Vertex::new(Point::new(x, j as f64, 0.0)));
+                    }
+                    Entity::new(EntityType::Polyline(poly))
+                }
+                _ => {
+                    let mut ins = Insert {
+                        __has_attributes: true,
+                        ..Default::default()
+                    };
+                    ins.add_attribute(
+                        &mut drawing,
+                        Attribute {
+                            value: format!("attribute {}", i),
+                            ..Default::default()
+                        },
+                    );
+                    Entity::new(EntityType::Insert(ins))
+                }
+            };
+            entity.common.layer = format!("layer {}", i % 7);
+            entity.common.comments = vec![format!("entity {}", i)];
+            drawing.add_entity(entity);
+        }
+        for i in 0..3_000 {
+            drawing.add_object(Object::new(ObjectType::XRecordObject(XRecordObject {
+                data_pairs: vec![CodePair::new_string(1, format!("record {}", i))],
+                ..Default::default()
+            })));
+        }
+
+        drawing.section_comments.insert(
+            String::from("ENTITIES"),
+            vec![String::from("end of entities")],
+        );
+        drawing
+    }
+
+    fn save_to_string(drawing: &Drawing) -> String {
+        let mut buf = Cursor::new(vec![]);
+        drawing.save(&mut buf).ok().unwrap();
+        String::from_utf8(buf.into_inner()).unwrap()
+    }
+
+    fn load_parallel(bytes: &[u8]) -> DxfResult<Drawing> {
+        Drawing::load_parallel_with_thread_count(&mut Cursor::new(bytes), 4)
+    }
+
+    #[test]
+    fn parallel_load_matches_sequential_load() {
+        let text = save_to_string(&large_drawing());
+        let sequential = Drawing::load(&mut text.as_bytes()).ok().unwrap();
+        let parallel = load_parallel(text.as_bytes()).ok().unwrap();
+        assert_eq!(save_to_string(&sequential), save_to_string(&parallel));
+
+        assert_eq!(sequential.entities().count(), parallel.entities().count());
+        assert_eq!(sequential.objects().count(), parallel.objects().count());
+        let entities = parallel.entities().collect::<Vec<_>>();
+        assert_eq!(vec![String::from("entity 1")], entities[1].common.comments);
+        match entities[1].specific {
+            EntityType::Polyline(ref poly) => assert_eq!(3, poly.vertices().count()),
+            _ => panic!("expected a polyline"),
+        }
+        match entities[2].specific {
+            EntityType::Insert(ref ins) => assert_eq!(1, ins.attributes().count()),
+            _ => panic!("expected an insert"),
+        }
+    }
+
+    #[test]
+    fn entities_are_read_on_several_threads() {
+        let text = save_to_string(&large_drawing());
+        let section_start = text.find("  0\r\nSECTION\r\n  2\r\nENTITIES").unwrap();
+        let (first_line, rest) = text[section_start..].split_once("\r\n").unwrap();
+        let iter = new_code_pair_iter_from_reader(
+            rest.as_bytes(),
+            encoding_rs::WINDOWS_1252,
+            false,
+            String::from(first_line),
+        )
+        .ok()
+        .unwrap();
+        let mut iter = CodePairPutBack::from_code_pair_iter(iter);
+        iter.next(); // 0/SECTION
+        iter.next(); // 2/ENTITIES
+
+        let chunks =
+            read_items_in_parallel(&mut iter, 4, Entity::read).expect("expected a split section");
+        assert_eq!(4, chunks.len());
+        let mut entities = vec![];
+        collect_entities(&mut chunks.into_iter().flatten(), &mut entities)
+            .ok()
+            .unwrap();
+        assert_eq!(vec![String::from("entity 0")], entities[0].common.comments);
+        let loaded = Drawing::load(&mut text.as_bytes()).ok().unwrap();
+        assert_eq!(loaded.entities().count(), entities.len());
+        let end = iter.next().unwrap().ok().unwrap();
+        assert_eq!(CodePair::new_str(0, "ENDSEC"), end);
+        assert_eq!(vec![String::from("end of entities")], iter.take_comments());
+    }
+
+    #[test]
+    fn parallel_load_matches_sequential_load_binary() {
+        let mut buf = Cursor::new(vec![]);
+        large_drawing().save_binary(&mut buf).ok().unwrap();
+        let bytes = buf.into_inner();
+        let sequential = Drawing::load(&mut bytes.as_slice()).ok().unwrap();
+        let parallel = load_parallel(&bytes).ok().unwrap();
+        assert_eq!(save_to_string(&sequential), save_to_string(&parallel));
+    }
+
+    #[test]
+    fn parallel_load_of_small_drawing() {
+        let mut drawing = Drawing::new();
+        drawing.add_entity(Entity::new(EntityType::Line(Line::default())));
+        let text = save_to_string(&drawing);
+        let sequential = Drawing::load(&mut text.as_bytes()).ok().unwrap();
+        let parallel = load_parallel(text.as_bytes()).ok().unwrap();
+        assert_eq!(save_to_string(&sequential), save_to_string(&parallel));
+    }
+
+    #[test]
+    fn parallel_load_reports_the_same_error_as_sequential_load() {
+        let text = save_to_string(&large_drawing());
+
+        // an unparsable value in the middle of the entities
+        let malformed = text.replacen(" 10\r\n1500.0\r\n", " 10\r\nnot a number\r\n", 1);
+        assert_ne!(text, malformed);
+        let sequential = Drawing::load(&mut malformed.as_bytes()).err().unwrap();
+        let parallel = load_parallel(malformed.as_bytes()).err().unwrap();
+        assert_eq!(sequential.to_string(), parallel.to_string());
+
+        // the file ends in the middle of the entities
+        let truncated = &text[..text.find("entity 1500").unwrap()];
+        let sequential = Drawing::load(&mut truncated.as_bytes()).err().unwrap();
+        let parallel = load_parallel(truncated.as_bytes()).err().unwrap();
+        assert_eq!(sequential.to_string(), parallel.to_string());
+    }
+}
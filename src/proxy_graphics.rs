@@ -0,0 +1,1664 @@
+// decodes the graphics stored with proxy entities and converts them into native entities
+
+use std::f64::consts::PI;
+
+use crate::down_conversion::polyline_from_points;
+use crate::drawing::AUTO_REPLACE_HANDLE;
+use crate::entities::*;
+use crate::geometry::*;
+use crate::render::Placement;
+use crate::{Color, Drawing, DxfError, DxfResult, Point, TransformationMatrix, Vector};
+
+const EXTENTS: u32 = 1;
+const CIRCLE: u32 = 2;
+const CIRCLE_THREE_POINTS: u32 = 3;
+const ARC: u32 = 4;
+const ARC_THREE_POINTS: u32 = 5;
+const POLYLINE: u32 = 6;
+const POLYGON: u32 = 7;
+const MESH: u32 = 8;
+const SHELL: u32 = 9;
+const TEXT: u32 = 10;
+const TEXT2: u32 = 11;
+const XLINE: u32 = 12;
+const RAY: u32 = 13;
+const SUBENTITY_COLOR: u32 = 14;
+const SUBENTITY_LAYER: u32 = 16;
+const SUBENTITY_LINE_TYPE: u32 = 18;
+const SUBENTITY_MARKER: u32 = 19;
+const SUBENTITY_FILL: u32 = 20;
+const SUBENTITY_TRUE_COLOR: u32 = 22;
+const SUBENTITY_LINE_WEIGHT: u32 = 23;
+const SUBENTITY_LINE_TYPE_SCALE: u32 = 24;
+const SUBENTITY_THICKNESS: u32 = 25;
+const PUSH_MATRIX: u32 = 29;
+const PUSH_MATRIX2: u32 = 30;
+const POP_MATRIX: u32 = 31;
+const POLYLINE_WITH_NORMALS: u32 = 32;
+const UNICODE_TEXT: u32 = 36;
+const UNICODE_TEXT2: u32 = 38;
+
+/// The line type index that means the line type of the entity's layer.
+const LINE_TYPE_BY_LAYER: u32 = 0x7FFF;
+
+/// The line type index that means the line type of the containing block reference.
+const LINE_TYPE_BY_BLOCK: u32 = 0x7FFE;
+
+/// The size of the record header: the record size followed by its type.
+const RECORD_HEADER_SIZE: usize = 8;
+
+/// How the ends of an arc in proxy graphics are connected.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub enum ProxyArcType {
+    /// Only the arc itself is drawn.
+    Simple,
+    /// The ends are connected to the center, like a slice of pie.
+    Sector,
+    /// The ends are connected to each other.
+    Chord,
+}
+
+/// A face of a shell in proxy graphics.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub struct ProxyShellFace {
+    /// Zero-based indices into the vertices of the shell.
+    pub vertex_indices: Vec<usize>,
+    /// Whether the face is a hole in the face before it.
+    pub is_hole: bool,
+}
+
+/// A line type selected in proxy graphics.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub enum ProxyLineType {
+    ByLayer,
+    ByBlock,
+    /// An index into the drawing's line type table.
+    Index(usize),
+}
+
+/// Text in proxy graphics.  All positions and directions are in world coordinates.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub struct ProxyText {
+    pub location: Point,
+    pub normal: Vector,
+    /// The direction of the baseline.
+    pub direction: Vector,
+    pub height: f64,
+    pub width_factor: f64,
+    /// The oblique angle in radians.
+    pub oblique_angle: f64,
+    pub value: String,
+    pub tracking_percentage: f64,
+    pub is_backwards: bool,
+    pub is_upside_down: bool,
+    pub is_vertical: bool,
+    pub is_underlined: bool,
+    pub is_overlined: bool,
+    /// The font file, or an empty string if the stream doesn't specify one.
+    pub font_file_name: String,
+    /// The big font file, or an empty string if the stream doesn't specify one.
+    pub big_font_file_name: String,
+}
+
+impl Default for ProxyText {
+    fn default() -> Self {
+        ProxyText {
+            location: Point::origin(),
+            normal: Vector::z_axis(),
+            direction: Vector::x_axis(),
+            height: 1.0,
+            width_factor: 1.0,
+            oblique_angle: 0.0,
+            value: String::new(),
+            tracking_percentage: 1.0,
+            is_backwards: false,
+            is_upside_down: false,
+            is_vertical: false,
+            is_underlined: false,
+            is_overlined: false,
+            font_file_name: String::new(),
+            big_font_file_name: String::new(),
+        }
+    }
+}
+
+/// A single record of the graphics stored with a `ProxyEntity`.  Geometry is in world
+/// coordinates before the current matrix is applied, and the subentity traits (color, layer, etc.)
+/// apply to all geometry that follows them.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub enum ProxyGraphicsPrimitive {
+    /// The bounding box of the graphics.
+    Extents {
+        min: Point,
+        max: Point,
+    },
+    Circle {
+        center: Point,
+        radius: f64,
+        normal: Vector,
+    },
+    /// A circle through three points.
+    CircleThroughPoints {
+        first: Point,
+        second: Point,
+        third: Point,
+    },
+    /// An arc that sweeps counterclockwise about `normal` from `start_vector` by `sweep_angle`
+    /// radians.
+    Arc {
+        center: Point,
+        radius: f64,
+        normal: Vector,
+        start_vector: Vector,
+        sweep_angle: f64,
+        arc_type: ProxyArcType,
+    },
+    /// An arc from `start` through `middle` to `end`.
+    ArcThroughPoints {
+        start: Point,
+        middle: Point,
+        end: Point,
+        arc_type: ProxyArcType,
+    },
+    Polyline {
+        vertices: Vec<Point>,
+        normal: Option<Vector>,
+    },
+    /// A closed polyline.
+    Polygon {
+        vertices: Vec<Point>,
+    },
+    /// A grid of vertices stored row by row.
+    Mesh {
+        row_count: usize,
+        column_count: usize,
+        vertices: Vec<Point>,
+    },
+    Shell {
+        vertices: Vec<Point>,
+        faces: Vec<ProxyShellFace>,
+    },
+    Text(ProxyText),
+    /// An infinite line through two points.
+    XLine {
+        first: Point,
+        second: Point,
+    },
+    /// A line that starts at `start` and passes through `through`.
+    Ray {
+        start: Point,
+        through: Point,
+    },
+    /// Sets the color of the geometry that follows.
+    Color(Color),
+    /// Sets the 24-bit color of the geometry that follows.
+    TrueColor(i32),
+    /// Sets the layer of the geometry that follows to the one at the given index of the drawing's
+    /// layer table.
+    Layer(usize),
+    /// Sets the line type of the geometry that follows.
+    LineType(ProxyLineType),
+    /// Sets the selection marker of the geometry that follows.
+    Marker(i32),
+    /// Turns filling of the geometry that follows on or off.
+    Fill(bool),
+    /// Sets the line weight of the geometry that follows.
+    LineWeight(i16),
+    /// Sets the line type scale of the geometry that follows.
+    LineTypeScale(f64),
+    /// Sets the thickness of the geometry that follows.
+    Thickness(f64),
+    /// Applies a matrix to the geometry that follows until the matching `PopMatrix`.
+    PushMatrix(TransformationMatrix),
+    PopMatrix,
+    /// A record that isn't decoded, e.g., clipping boundaries and materials.
+    Unsupported {
+        record_type: u32,
+        data: Vec<u8>,
+    },
+}
+
+/// Decodes a proxy graphics stream.
+pub(crate) fn decode_proxy_graphics(data: &[u8]) -> DxfResult<Vec<ProxyGraphicsPrimitive>> {
+    let mut reader = ProxyGraphicsReader::new(data);
+    let total_size = reader.read_u32()? as usize;
+    let _record_count = reader.read_u32()?;
+    if total_size > data.len() {
+        return Err(DxfError::UnexpectedEndOfInput);
+    }
+
+    let mut primitives = vec![];
+    while reader.position < total_size {
+        let offset = reader.position;
+        let size = reader.read_u32()? as usize;
+        let record_type = reader.read_u32()?;
+        if size < RECORD_HEADER_SIZE {
+            return Err(DxfError::ParseError(offset));
+        }
+
+        let record = reader.read_bytes(size - RECORD_HEADER_SIZE)?;
+        primitives.push(read_primitive(record_type, record)?);
+    }
+
+    Ok(primitives)
+}
+
+fn read_primitive(record_type: u32, data: &[u8]) -> DxfResult<ProxyGraphicsPrimitive> {
+    let mut reader = ProxyGraphicsReader::new(data);
+    let r = &mut reader;
+    let primitive = match record_type {
+        EXTENTS => ProxyGraphicsPrimitive::Extents {
+            min: r.read_point()?,
+            max: r.read_point()?,
+        },
+        CIRCLE => ProxyGraphicsPrimitive::Circle {
+            center: r.read_point()?,
+            radius: r.read_f64()?,
+            normal: r.read_vector()?,
+        },
+        CIRCLE_THREE_POINTS => ProxyGraphicsPrimitive::CircleThroughPoints {
+            first: r.read_point()?,
+            second: r.read_point()?,
+            third: r.read_point()?,
+        },
+        ARC => ProxyGraphicsPrimitive::Arc {
+            center: r.read_point()?,
+            radius: r.read_f64()?,
+            normal: r.read_vector()?,
+            start_vector: r.read_vector()?,
+            sweep_angle: r.read_f64()?,
+            arc_type: r.read_arc_type()?,
+        },
+        ARC_THREE_POINTS => ProxyGraphicsPrimitive::ArcThroughPoints {
+            start: r.read_point()?,
+            middle: r.read_point()?,
+            end: r.read_point()?,
+            arc_type: r.read_arc_type()?,
+        },
+        POLYLINE => ProxyGraphicsPrimitive::Polyline {
+            vertices: r.read_points()?,
+            normal: None,
+        },
+        POLYLINE_WITH_NORMALS => ProxyGraphicsPrimitive::Polyline {
+            vertices: r.read_points()?,
+            normal: Some(r.read_vector()?),
+        },
+        POLYGON => ProxyGraphicsPrimitive::Polygon {
+            vertices: r.read_points()?,
+        },
+        MESH => {
+            let row_count = r.read_u32()? as usize;
+            let column_count = r.read_u32()? as usize;
+            let count = row_count
+                .checked_mul(column_count)
+                .ok_or(DxfError::UnexpectedEndOfInput)?;
+            // the edge and face traits that may follow aren't needed to draw the mesh
+            ProxyGraphicsPrimitive::Mesh {
+                row_count,
+                column_count,
+                vertices: r.read_point_list(count)?,
+            }
+        }
+        SHELL => read_shell(r)?,
+        TEXT | UNICODE_TEXT => {
+            let location = r.read_point()?;
+            let normal = r.read_vector()?;
+            let direction = r.read_vector()?;
+            let height = r.read_f64()?;
+            let width_factor = r.read_f64()?;
+            let oblique_angle = r.read_f64()?;
+            let value = r.read_padded_string(record_type == UNICODE_TEXT)?;
+            ProxyGraphicsPrimitive::Text(ProxyText {
+                location,
+                normal,
+                direction,
+                height,
+                width_factor,
+                oblique_angle,
+                value,
+                ..Default::default()
+            })
+        }
+        TEXT2 | UNICODE_TEXT2 => read_text2(r, record_type == UNICODE_TEXT2)?,
+        XLINE => ProxyGraphicsPrimitive::XLine {
+            first: r.read_point()?,
+            second: r.read_point()?,
+        },
+        RAY => ProxyGraphicsPrimitive::Ray {
+            start: r.read_point()?,
+            through: r.read_point()?,
+        },
+        SUBENTITY_COLOR => {
+            ProxyGraphicsPrimitive::Color(Color::from_raw_value(r.read_i32()? as i16))
+        }
+        SUBENTITY_TRUE_COLOR => {
+            let value = r.read_u32()?;
+            let rgb = value & 0x00FF_FFFF;
+            match value >> 24 {
+                0xC0 => ProxyGraphicsPrimitive::Color(Color::by_layer()),
+                0xC1 => ProxyGraphicsPrimitive::Color(Color::by_block()),
+                0xC2 => ProxyGraphicsPrimitive::TrueColor(rgb as i32),
+                0xC3 => ProxyGraphicsPrimitive::Color(Color::from_raw_value((rgb & 0xFF) as i16)),
+                _ => unsupported(record_type, data),
+            }
+        }
+        SUBENTITY_LAYER => ProxyGraphicsPrimitive::Layer(r.read_u32()? as usize),
+        SUBENTITY_LINE_TYPE => ProxyGraphicsPrimitive::LineType(match r.read_u32()? {
+            LINE_TYPE_BY_LAYER => ProxyLineType::ByLayer,
+            LINE_TYPE_BY_BLOCK => ProxyLineType::ByBlock,
+            index => ProxyLineType::Index(index as usize),
+        }),
+        SUBENTITY_MARKER => ProxyGraphicsPrimitive::Marker(r.read_i32()?),
+        SUBENTITY_FILL => ProxyGraphicsPrimitive::Fill(r.read_u32()? != 0),
+        SUBENTITY_LINE_WEIGHT => ProxyGraphicsPrimitive::LineWeight(r.read_i32()? as i16),
+        SUBENTITY_LINE_TYPE_SCALE => ProxyGraphicsPrimitive::LineTypeScale(r.read_f64()?),
+        SUBENTITY_THICKNESS => ProxyGraphicsPrimitive::Thickness(r.read_f64()?),
+        PUSH_MATRIX | PUSH_MATRIX2 => {
+            let mut values = Vec::with_capacity(16);
+            for _ in 0..16 {
+                values.push(r.read_f64()?);
+            }
+            ProxyGraphicsPrimitive::PushMatrix(TransformationMatrix::from_vec(&values))
+        }
+        POP_MATRIX => ProxyGraphicsPrimitive::PopMatrix,
+        _ => unsupported(record_type, data),
+    };
+    Ok(primitive)
+}
+
+fn unsupported(record_type: u32, data: &[u8]) -> ProxyGraphicsPrimitive {
+    ProxyGraphicsPrimitive::Unsupported {
+        record_type,
+        data: data.to_vec(),
+    }
+}
+
+fn read_shell(r: &mut ProxyGraphicsReader) -> DxfResult<ProxyGraphicsPrimitive> {
+    let vertices = r.read_points()?;
+    let entry_count = r.read_u32()? as usize;
+
+    // every face is its vertex count followed by that many indices; holes have a negative count
+    let mut faces = vec![];
+    let mut read_count = 0;
+    while read_count < entry_count {
+        let count = r.read_i32()?;
+        let mut vertex_indices = vec![];
+        for _ in 0..count.unsigned_abs() {
+            vertex_indices.push(r.read_u32()? as usize);
+        }
+        read_count += 1 + vertex_indices.len();
+        faces.push(ProxyShellFace {
+            vertex_indices,
+            is_hole: count < 0,
+        });
+    }
+
+    // the edge, face and vertex traits that may follow aren't needed to draw the shell
+    Ok(ProxyGraphicsPrimitive::Shell { vertices, faces })
+}
+
+fn read_text2(r: &mut ProxyGraphicsReader, is_unicode: bool) -> DxfResult<ProxyGraphicsPrimitive> {
+    let location = r.read_point()?;
+    let normal = r.read_vector()?;
+    let direction = r.read_vector()?;
+    let value = r.read_padded_string(is_unicode)?;
+    let _length = r.read_i32()?;
+    let _is_raw = r.read_i32()?;
+    let height = r.read_f64()?;
+    let width_factor = r.read_f64()?;
+    let oblique_angle = r.read_f64()?;
+    let tracking_percentage = r.read_f64()?;
+    let is_backwards = r.read_u32()? != 0;
+    let is_upside_down = r.read_u32()? != 0;
+    let is_vertical = r.read_u32()? != 0;
+    let is_underlined = r.read_u32()? != 0;
+    let is_overlined = r.read_u32()? != 0;
+    let font_file_name = r.read_padded_string(is_unicode)?;
+    let big_font_file_name = r.read_padded_string(is_unicode)?;
+    Ok(ProxyGraphicsPrimitive::Text(ProxyText {
+        location,
+        normal,
+        direction,
+        height,
+        width_factor,
+        oblique_angle,
+        value,
+        tracking_percentage,
+        is_backwards,
+        is_upside_down,
+        is_vertical,
+        is_underlined,
+        is_overlined,
+        font_file_name,
+        big_font_file_name,
+    }))
+}
+
+/// Reads the little endian values of a proxy graphics stream.
+struct ProxyGraphicsReader<'a> {
+    data: &'a [u8],
+    position: usize,
+}
+
+impl<'a> ProxyGraphicsReader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        ProxyGraphicsReader { data, position: 0 }
+    }
+    fn read_bytes(&mut self, count: usize) -> DxfResult<&'a [u8]> {
+        let end = self
+            .position
+            .checked_add(count)
+            .filter(|end| *end <= self.data.len())
+            .ok_or(DxfError::UnexpectedEndOfInput)?;
+        let bytes = &self.data[self.position..end];
+        self.position = end;
+        Ok(bytes)
+    }
+    fn read_u32(&mut self) -> DxfResult<u32> {
+        let mut bytes = [0; 4];
+        bytes.copy_from_slice(self.read_bytes(4)?);
+        Ok(u32::from_le_bytes(bytes))
+    }
+    fn read_i32(&mut self) -> DxfResult<i32> {
+        Ok(self.read_u32()? as i32)
+    }
+    fn read_f64(&mut self) -> DxfResult<f64> {
+        let mut bytes = [0; 8];
+        bytes.copy_from_slice(self.read_bytes(8)?);
+        Ok(f64::from_le_bytes(bytes))
+    }
+    fn read_point(&mut self) -> DxfResult<Point> {
+        Ok(Point::new(
+            self.read_f64()?,
+            self.read_f64()?,
+            self.read_f64()?,
+        ))
+    }
+    fn read_vector(&mut self) -> DxfResult<Vector> {
+        Ok(Vector::new(
+            self.read_f64()?,
+            self.read_f64()?,
+            self.read_f64()?,
+        ))
+    }
+    /// Reads a vertex count followed by that many points.
+    fn read_points(&mut self) -> DxfResult<Vec<Point>> {
+        let count = self.read_u32()? as usize;
+        self.read_point_list(count)
+    }
+    fn read_point_list(&mut self, count: usize) -> DxfResult<Vec<Point>> {
+        // don't trust the count to size the allocation
+        if count > (self.data.len() - self.position) / 24 {
+            return Err(DxfError::UnexpectedEndOfInput);
+        }
+        (0..count).map(|_| self.read_point()).collect()
+    }
+    fn read_arc_type(&mut self) -> DxfResult<ProxyArcType> {
+        Ok(match self.read_u32()? {
+            1 => ProxyArcType::Sector,
+            2 => ProxyArcType::Chord,
+            _ => ProxyArcType::Simple,
+        })
+    }
+    /// Reads a NUL terminated string that's padded to a multiple of 4 bytes.  Unicode strings are
+    /// UTF-16; others are UTF-8, or Windows-1252 if they aren't valid UTF-8.
+    fn read_padded_string(&mut self, is_unicode: bool) -> DxfResult<String> {
+        let remaining = &self.data[self.position..];
+        let (value, length) = if is_unicode {
+            let units = remaining
+                .chunks_exact(2)
+                .map(|c| u16::from_le_bytes([c[0], c[1]]))
+                .take_while(|u| *u != 0)
+                .collect::<Vec<_>>();
+            (String::from_utf16_lossy(&units), units.len() * 2 + 2)
+        } else {
+            let bytes = remaining
+                .iter()
+                .position(|b| *b == 0)
+                .map(|end| &remaining[..end])
+                .ok_or(DxfError::UnexpectedEndOfInput)?;
+            let value = match std::str::from_utf8(bytes) {
+                Ok(s) => String::from(s),
+                Err(_) => encoding_rs::WINDOWS_1252.decode(bytes).0.into_owned(),
+            };
+            (value, bytes.len() + 1)
+        };
+
+        // the padding may be missing at the end of a record
+        let end = self.position + length;
+        let padded_end = (end.div_ceil(4) * 4).min(self.data.len()).max(end);
+        self.read_bytes(padded_end - self.position)?;
+        Ok(value)
+    }
+}
+
+/// Returns the values of `common` that describe how an entity is drawn.  The handle, owner,
+/// reactors, extension dictionary and XDATA stay with the original entity.
+fn display_properties(common: &EntityCommon) -> EntityCommon {
+    EntityCommon {
+        is_in_paper_space: common.is_in_paper_space,
+        layer: common.layer.clone(),
+        line_type_name: common.line_type_name.clone(),
+        elevation: common.elevation,
+        __material_handle: common.__material_handle,
+        color: common.color.clone(),
+        lineweight_enum_value: common.lineweight_enum_value,
+        line_type_scale: common.line_type_scale,
+        is_visible: common.is_visible,
+        color_24_bit: common.color_24_bit,
+        color_name: common.color_name.clone(),
+        transparency: common.transparency,
+        __plot_style_handle: common.__plot_style_handle,
+        shadow_mode: common.shadow_mode,
+        ..Default::default()
+    }
+}
+
+/// Converts the graphics of a proxy entity into native entities that are drawn with its layer,
+/// color and line type.  Returns `None` if the graphics can't be decoded.
+pub(crate) fn explode_proxy(
+    entity: &Entity,
+    proxy: &ProxyEntity,
+    drawing: &Drawing,
+) -> Option<Vec<Entity>> {
+    let primitives = proxy.graphics().ok()?;
+    let mut state = ExplodeState {
+        common: display_properties(&entity.common),
+        thickness: 0.0,
+        matrices: vec![],
+    };
+    let mut entities = vec![];
+    for primitive in primitives {
+        match primitive {
+            ProxyGraphicsPrimitive::Color(color) => {
+                state.common.color = color;
+                state.common.color_24_bit = 0;
+            }
+            ProxyGraphicsPrimitive::TrueColor(rgb) => {
+                state.common.color =
+                    Color::from_rgb_nearest((rgb >> 16) as u8, (rgb >> 8) as u8, rgb as u8);
+                state.common.color_24_bit = rgb;
+            }
+            ProxyGraphicsPrimitive::Layer(index) => {
+                if let Some(layer) = drawing.layers().nth(index) {
+                    state.common.layer = layer.name.clone();
+                }
+            }
+            ProxyGraphicsPrimitive::LineType(line_type) => match line_type {
+                ProxyLineType::ByLayer => state.common.line_type_name = String::from("BYLAYER"),
+                ProxyLineType::ByBlock => state.common.line_type_name = String::from("BYBLOCK"),
+                ProxyLineType::Index(index) => {
+                    if let Some(line_type) = drawing.line_types().nth(index) {
+                        state.common.line_type_name = line_type.name.clone();
+                    }
+                }
+            },
+            ProxyGraphicsPrimitive::LineWeight(weight) => {
+                state.common.lineweight_enum_value = weight
+            }
+            ProxyGraphicsPrimitive::LineTypeScale(scale) => state.common.line_type_scale = scale,
+            ProxyGraphicsPrimitive::Thickness(thickness) => state.thickness = thickness,
+            ProxyGraphicsPrimitive::PushMatrix(matrix) => {
+                let matrix = Placement::from_matrix(&matrix);
+                let matrix = match state.matrices.last() {
+                    Some(current) => matrix.then(current),
+                    None => matrix,
+                };
+                state.matrices.push(matrix);
+            }
+            ProxyGraphicsPrimitive::PopMatrix => {
+                state.matrices.pop();
+            }
+            primitive => {
+                let primitive = match state.matrices.last() {
+                    Some(matrix) => transform_primitive(primitive, matrix),
+                    None => primitive,
+                };
+                for specific in primitive_entities(&primitive, state.thickness) {
+                    entities.push(Entity {
+                        common: state.common.clone(),
+                        specific,
+                    });
+                }
+            }
+        }
+    }
+
+    Some(entities)
+}
+
+/// The subentity traits in effect while exploding proxy graphics.
+struct ExplodeState {
+    common: EntityCommon,
+    thickness: f64,
+    matrices: Vec<Placement>,
+}
+
+/// Returns the native entities that draw a geometry primitive.
+fn primitive_entities(primitive: &ProxyGraphicsPrimitive, thickness: f64) -> Vec<EntityType> {
+    match primitive {
+        ProxyGraphicsPrimitive::Circle {
+            center,
+            radius,
+            normal,
+        } => vec![EntityType::Circle(Circle {
+            center: wcs_to_ocs(center, normal),
+            radius: *radius,
+            normal: normal.clone(),
+            thickness,
+        })],
+        ProxyGraphicsPrimitive::CircleThroughPoints {
+            first,
+            second,
+            third,
+        } => match circle_through_points(first, second, third) {
+            Some((center, radius, normal)) => vec![EntityType::Circle(Circle {
+                center: wcs_to_ocs(&center, &normal),
+                radius,
+                normal,
+                thickness,
+            })],
+            None => vec![],
+        },
+        ProxyGraphicsPrimitive::Arc {
+            center,
+            radius,
+            normal,
+            start_vector,
+            sweep_angle,
+            arc_type,
+        } => {
+            let (x_axis, y_axis) = ocs_axes(normal);
+            let start = dot(start_vector, &y_axis).atan2(dot(start_vector, &x_axis));
+            arc_entities(
+                center,
+                *radius,
+                normal,
+                start,
+                start + sweep_angle,
+                *arc_type,
+                thickness,
+            )
+        }
+        ProxyGraphicsPrimitive::ArcThroughPoints {
+            start,
+            middle,
+            end,
+            arc_type,
+        } => match circle_through_points(start, middle, end) {
+            Some((center, radius, normal)) => {
+                // the normal makes the points counterclockwise, so the arc runs from start to end
+                let (x_axis, y_axis) = ocs_axes(&normal);
+                let angle = |p: &Point| {
+                    let v = vector_between(&center, p);
+                    dot(&v, &y_axis).atan2(dot(&v, &x_axis))
+                };
+                let start_angle = angle(start);
+                let mut end_angle = angle(end);
+                if end_angle < start_angle {
+                    end_angle += 2.0 * PI;
+                }
+                arc_entities(
+                    &center,
+                    radius,
+                    &normal,
+                    start_angle,
+                    end_angle,
+                    *arc_type,
+                    thickness,
+                )
+            }
+            None => vec![],
+        },
+        ProxyGraphicsPrimitive::Polyline { vertices, .. } => {
+            polyline_entities(vertices, false, thickness)
+        }
+        ProxyGraphicsPrimitive::Polygon { vertices } => {
+            polyline_entities(vertices, true, thickness)
+        }
+        ProxyGraphicsPrimitive::Mesh {
+            row_count,
+            column_count,
+            vertices,
+        } => {
+            let mut poly = Polyline {
+                polygon_mesh_m_vertex_count: *row_count as i32,
+                polygon_mesh_n_vertex_count: *column_count as i32,
+                ..Default::default()
+            };
+            poly.set_is_3d_polygon_mesh(true);
+            for location in vertices {
+                let mut vertex = Vertex::new(location.clone());
+                vertex.set_is_3d_polygon_mesh(true);
+                poly.__vertices_and_handles
+                    .push((vertex, AUTO_REPLACE_HANDLE));
+            }
+            vec![EntityType::Polyline(poly)]
+        }
+        ProxyGraphicsPrimitive::Shell { vertices, faces } => {
+            vec![EntityType::Polyline(shell_to_polyface(vertices, faces))]
+        }
+        ProxyGraphicsPrimitive::Text(text) => vec![EntityType::Text(proxy_text_to_text(text))],
+        ProxyGraphicsPrimitive::XLine { first, second } => vec![EntityType::XLine(XLine {
+            first_point: first.clone(),
+            unit_direction_vector: normalize(&vector_between(first, second)),
+        })],
+        ProxyGraphicsPrimitive::Ray { start, through } => vec![EntityType::Ray(Ray {
+            start_point: start.clone(),
+            unit_direction_vector: normalize(&vector_between(start, through)),
+        })],
+        _ => vec![],
+    }
+}
+
+/// Returns an arc between two angles in radians along with the lines that close it.
+fn arc_entities(
+    center: &Point,
+    radius: f64,
+    normal: &Vector,
+    start: f64,
+    end: f64,
+    arc_type: ProxyArcType,
+    thickness: f64,
+) -> Vec<EntityType> {
+    let (start, end) = if end < start {
+        (end, start)
+    } else {
+        (start, end)
+    };
+    let ocs_center = wcs_to_ocs(center, normal);
+    if end - start >= 2.0 * PI - EPSILON {
+        // a full turn has no end points to join
+        return vec![EntityType::Circle(Circle {
+            center: ocs_center,
+            radius,
+            normal: normal.clone(),
+            thickness,
+        })];
+    }
+
+    let mut entities = vec![EntityType::Arc(Arc {
+        center: ocs_center.clone(),
+        radius,
+        start_angle: normalize_degrees(start.to_degrees()),
+        end_angle: normalize_degrees(end.to_degrees()),
+        normal: normal.clone(),
+        thickness,
+    })];
+
+    let point_at = |angle: f64| {
+        ocs_to_wcs(
+            &Point::new(
+                ocs_center.x + radius * angle.cos(),
+                ocs_center.y + radius * angle.sin(),
+                ocs_center.z,
+            ),
+            normal,
+        )
+    };
+    let line = |p1: Point, p2: Point| EntityType::Line(Line::new(p1, p2));
+    match arc_type {
+        ProxyArcType::Simple => (),
+        ProxyArcType::Sector => {
+            entities.push(line(point_at(end), center.clone()));
+            entities.push(line(center.clone(), point_at(start)));
+        }
+        ProxyArcType::Chord => entities.push(line(point_at(end), point_at(start))),
+    }
+    entities
+}
+
+fn polyline_entities(vertices: &[Point], is_closed: bool, thickness: f64) -> Vec<EntityType> {
+    if vertices.len() < 2 {
+        return vec![];
+    }
+
+    let mut poly = polyline_from_points(vertices, &Vector::z_axis(), is_closed);
+    poly.thickness = thickness;
+    vec![EntityType::Polyline(poly)]
+}
+
+/// Builds a polyface mesh from the faces of a shell.  Faces with more than four vertices are split
+/// into triangles with invisible inner edges, and holes are left out.
+fn shell_to_polyface(vertices: &[Point], faces: &[ProxyShellFace]) -> Polyline {
+    let mut poly = Polyline::default();
+    poly.set_is_polyface_mesh(true);
+    for location in vertices {
+        let mut vertex = Vertex::new(location.clone());
+        vertex.set_is_3d_polygon_mesh(true);
+        vertex.set_is_polyface_mesh_vertex(true);
+        poly.__vertices_and_handles
+            .push((vertex, AUTO_REPLACE_HANDLE));
+    }
+
+    let mut face_records = vec![];
+    for face in faces.iter().filter(|f| !f.is_hole) {
+        // polyface indices are one-based and a negative index hides the edge that starts there
+        let indices = face
+            .vertex_indices
+            .iter()
+            .filter(|i| **i < vertices.len())
+            .map(|i| *i as i32 + 1)
+            .collect::<Vec<_>>();
+        match indices.len() {
+            0..=2 => (),
+            3 | 4 => face_records.push(indices),
+            count => {
+                for i in 1..count - 1 {
+                    let first = if i == 1 { indices[0] } else { -indices[0] };
+                    let last = if i == count - 2 {
+                        indices[i + 1]
+                    } else {
+                        -indices[i + 1]
+                    };
+                    face_records.push(vec![first, indices[i], last]);
+                }
+            }
+        }
+    }
+
+    for indices in &face_records {
+        let mut vertex = Vertex::default();
+        vertex.set_is_polyface_mesh_vertex(true);
+        vertex.polyface_mesh_vertex_index1 = indices[0];
+        vertex.polyface_mesh_vertex_index2 = indices[1];
+        vertex.polyface_mesh_vertex_index3 = indices[2];
+        vertex.polyface_mesh_vertex_index4 = indices.get(3).cloned().unwrap_or(0);
+        poly.__vertices_and_handles
+            .push((vertex, AUTO_REPLACE_HANDLE));
+    }
+
+    poly.polygon_mesh_m_vertex_count = vertices.len() as i32;
+    poly.polygon_mesh_n_vertex_count = face_records.len() as i32;
+    poly
+}
+
+fn proxy_text_to_text(text: &ProxyText) -> Text {
+    let (x_axis, y_axis) = ocs_axes(&text.normal);
+    let rotation = dot(&text.direction, &y_axis)
+        .atan2(dot(&text.direction, &x_axis))
+        .to_degrees();
+    let mut value = text.value.clone();
+    if text.is_overlined {
+        value.insert_str(0, "%%o");
+    }
+    if text.is_underlined {
+        value.insert_str(0, "%%u");
+    }
+
+    let mut result = Text {
+        location: wcs_to_ocs(&text.location, &text.normal),
+        text_height: text.height,
+        value,
+        rotation: normalize_degrees(rotation),
+        relative_x_scale_factor: if text.width_factor == 0.0 {
+            1.0
+        } else {
+            text.width_factor
+        },
+        oblique_angle: text.oblique_angle.to_degrees(),
+        normal: text.normal.clone(),
+        ..Default::default()
+    };
+    result.set_is_text_backwards(text.is_backwards);
+    result.set_is_text_upside_down(text.is_upside_down);
+    result
+}
+
+/// Applies a matrix to the geometry of a primitive.  Circles and arcs are assumed to stay circular.
+fn transform_primitive(
+    primitive: ProxyGraphicsPrimitive,
+    matrix: &Placement,
+) -> ProxyGraphicsPrimitive {
+    let point = |p: &Point| matrix.apply(p);
+    let points = |ps: &[Point]| ps.iter().map(|p| matrix.apply(p)).collect::<Vec<_>>();
+    // the transformed OCS axes give the new normal and scale without flipping the arc direction
+    let plane = |normal: &Vector| {
+        let (x_axis, y_axis) = ocs_axes(normal);
+        let x_axis = matrix.apply_vector(&x_axis);
+        let y_axis = matrix.apply_vector(&y_axis);
+        (normalize(&cross(&x_axis, &y_axis)), vector_length(&x_axis))
+    };
+    match primitive {
+        ProxyGraphicsPrimitive::Circle {
+            center,
+            radius,
+            normal,
+        } => {
+            let (normal, scale) = plane(&normal);
+            ProxyGraphicsPrimitive::Circle {
+                center: point(&center),
+                radius: radius * scale,
+                normal,
+            }
+        }
+        ProxyGraphicsPrimitive::CircleThroughPoints {
+            first,
+            second,
+            third,
+        } => ProxyGraphicsPrimitive::CircleThroughPoints {
+            first: point(&first),
+            second: point(&second),
+            third: point(&third),
+        },
+        ProxyGraphicsPrimitive::Arc {
+            center,
+            radius,
+            normal,
+            start_vector,
+            sweep_angle,
+            arc_type,
+        } => {
+            let (new_normal, scale) = plane(&normal);
+            ProxyGraphicsPrimitive::Arc {
+                center: point(&center),
+                radius: radius * scale,
+                normal: new_normal,
+                start_vector: matrix.apply_vector(&start_vector),
+                sweep_angle,
+                arc_type,
+            }
+        }
+        ProxyGraphicsPrimitive::ArcThroughPoints {
+            start,
+            middle,
+            end,
+            arc_type,
+        } => ProxyGraphicsPrimitive::ArcThroughPoints {
+            start: point(&start),
+            middle: point(&middle),
+            end: point(&end),
+            arc_type,
+        },
+        ProxyGraphicsPrimitive::Polyline { vertices, normal } => ProxyGraphicsPrimitive::Polyline {
+            vertices: points(&vertices),
+            normal: normal.map(|n| plane(&n).0),
+        },
+        ProxyGraphicsPrimitive::Polygon { vertices } => ProxyGraphicsPrimitive::Polygon {
+            vertices: points(&vertices),
+        },
+        ProxyGraphicsPrimitive::Mesh {
+            row_count,
+            column_count,
+            vertices,
+        } => ProxyGraphicsPrimitive::Mesh {
+            row_count,
+            column_count,
+            vertices: points(&vertices),
+        },
+        ProxyGraphicsPrimitive::Shell { vertices, faces } => ProxyGraphicsPrimitive::Shell {
+            vertices: points(&vertices),
+            faces,
+        },
+        ProxyGraphicsPrimitive::Text(text) => {
+            let up = normalize(&cross(&text.normal, &text.direction));
+            let (normal, _) = plane(&text.normal);
+            ProxyGraphicsPrimitive::Text(ProxyText {
+                location: point(&text.location),
+                normal,
+                direction: matrix.apply_vector(&text.direction),
+                height: text.height * vector_length(&matrix.apply_vector(&up)),
+                ..text
+            })
+        }
+        ProxyGraphicsPrimitive::XLine { first, second } => ProxyGraphicsPrimitive::XLine {
+            first: point(&first),
+            second: point(&second),
+        },
+        ProxyGraphicsPrimitive::Ray { start, through } => ProxyGraphicsPrimitive::Ray {
+            start: point(&start),
+            through: point(&through),
+        },
+        primitive => primitive,
+    }
+}
+
+fn vector_between(from: &Point, to: &Point) -> Vector {
+    Vector::new(to.x - from.x, to.y - from.y, to.z - from.z)
+}
+
+/// Returns the center, radius and normal of the circle through three points, or `None` if the
+/// points are on a line.  The normal makes the points run counterclockwise.
+fn circle_through_points(a: &Point, b: &Point, c: &Point) -> Option<(Point, f64, Vector)> {
+    let ab = vector_between(a, b);
+    let ac = vector_between(a, c);
+    let normal = cross(&ab, &ac);
+    let normal_length_squared = dot(&normal, &normal);
+    if normal_length_squared < EPSILON {
+        return None;
+    }
+
+    // the center relative to `a` from the standard circumcenter formula
+    let ab_squared = dot(&ab, &ab);
+    let ac_squared = dot(&ac, &ac);
+    let t1 = cross(&normal, &ab);
+    let t2 = cross(&ac, &normal);
+    let scale = 1.0 / (2.0 * normal_length_squared);
+    let offset = Vector::new(
+        (ac_squared * t1.x + ab_squared * t2.x) * scale,
+        (ac_squared * t1.y + ab_squared * t2.y) * scale,
+        (ac_squared * t1.z + ab_squared * t2.z) * scale,
+    );
+    let center = Point::new(a.x + offset.x, a.y + offset.y, a.z + offset.z);
+    Some((center, vector_length(&offset), normalize(&normal)))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::entities::*;
+    use crate::enums::*;
+    use crate::extension_data::{add_reactor, set_extension_dictionary_handle};
+    use crate::tables::*;
+    use crate::*;
+
+    use super::{
+        ARC, CIRCLE, MESH, POLYGON, POLYLINE, POP_MATRIX, PUSH_MATRIX, RAY, SHELL, SUBENTITY_COLOR,
+        SUBENTITY_LAYER, SUBENTITY_LINE_TYPE, SUBENTITY_LINE_WEIGHT, SUBENTITY_TRUE_COLOR, TEXT,
+        TEXT2, UNICODE_TEXT, XLINE,
+    };
+
+    /// Builds a proxy graphics stream.
+    #[derive(Default)]
+    struct Graphics {
+        records: Vec<u8>,
+        count: u32,
+    }
+
+    impl Graphics {
+        fn record(mut self, record_type: u32, data: Vec<u8>) -> Self {
+            self.records
+                .extend_from_slice(&(data.len() as u32 + 8).to_le_bytes());
+            self.records.extend_from_slice(&record_type.to_le_bytes());
+            self.records.extend(data);
+            self.count += 1;
+            self
+        }
+        fn bytes(&self) -> Vec<u8> {
+            let mut bytes = vec![];
+            bytes.extend_from_slice(&(self.records.len() as u32 + 8).to_le_bytes());
+            bytes.extend_from_slice(&self.count.to_le_bytes());
+            bytes.extend_from_slice(&self.records);
+            bytes
+        }
+        fn proxy(&self) -> Entity {
+            let bytes = self.bytes();
+            Entity::new(EntityType::ProxyEntity(ProxyEntity {
+                graphics_data_size: bytes.len() as i32,
+                graphics_data_string: bytes.chunks(127).map(|c| c.to_vec()).collect(),
+                ..Default::default()
+            }))
+        }
+    }
+
+    fn doubles(values: &[f64]) -> Vec<u8> {
+        values.iter().flat_map(|v| v.to_le_bytes()).collect()
+    }
+
+    fn longs(values: &[u32]) -> Vec<u8> {
+        values.iter().flat_map(|v| v.to_le_bytes()).collect()
+    }
+
+    fn padded(s: &str) -> Vec<u8> {
+        let mut bytes = s.as_bytes().to_vec();
+        bytes.push(0);
+        while !bytes.len().is_multiple_of(4) {
+            bytes.push(0);
+        }
+        bytes
+    }
+
+    fn circle(x: f64, y: f64, radius: f64) -> Vec<u8> {
+        doubles(&[x, y, 0.0, radius, 0.0, 0.0, 1.0])
+    }
+
+    fn decode(graphics: &Graphics) -> Vec<ProxyGraphicsPrimitive> {
+        match graphics.proxy().specific {
+            EntityType::ProxyEntity(ref proxy) => proxy.graphics().unwrap(),
+            _ => unreachable!(),
+        }
+    }
+
+    fn explode(drawing: &Drawing, graphics: &Graphics) -> Vec<Entity> {
+        graphics.proxy().explode_proxy(drawing).unwrap()
+    }
+
+    #[test]
+    fn decode_curves_and_lines() {
+        let mut polyline = longs(&[2]);
+        polyline.extend(doubles(&[0.0, 0.0, 0.0, 1.0, 1.0, 0.0]));
+        let mut arc = doubles(&[1.0, 2.0, 3.0, 4.0, 0.0, 0.0, 1.0, 0.0, 1.0, 0.0, 1.5]);
+        arc.extend(longs(&[2]));
+        let graphics = Graphics::default()
+            .record(CIRCLE, circle(1.0, 2.0, 3.0))
+            .record(ARC, arc)
+            .record(POLYLINE, polyline.clone())
+            .record(POLYGON, polyline)
+            .record(XLINE, doubles(&[0.0, 0.0, 0.0, 2.0, 0.0, 0.0]))
+            .record(RAY, doubles(&[1.0, 1.0, 0.0, 1.0, 2.0, 0.0]));
+        let line = vec![Point::new(0.0, 0.0, 0.0), Point::new(1.0, 1.0, 0.0)];
+        assert_eq!(
+            vec![
+                ProxyGraphicsPrimitive::Circle {
+                    center: Point::new(1.0, 2.0, 0.0),
+                    radius: 3.0,
+                    normal: Vector::z_axis(),
+                },
+                ProxyGraphicsPrimitive::Arc {
+                    center: Point::new(1.0, 2.0, 3.0),
+                    radius: 4.0,
+                    normal: Vector::z_axis(),
+                    start_vector: Vector::y_axis(),
+                    sweep_angle: 1.5,
+                    arc_type: ProxyArcType::Chord,
+                },
+                ProxyGraphicsPrimitive::Polyline {
+                    vertices: line.clone(),
+                    normal: None,
+                },
+                ProxyGraphicsPrimitive::Polygon { vertices: line },
+                ProxyGraphicsPrimitive::XLine {
+                    first: Point::origin(),
+                    second: Point::new(2.0, 0.0, 0.0),
+                },
+                ProxyGraphicsPrimitive::Ray {
+                    start: Point::new(1.0, 1.0, 0.0),
+                    through: Point::new(1.0, 2.0, 0.0),
+                },
+            ],
+            decode(&graphics)
+        );
+    }
+
+    #[test]
+    fn decode_mesh_and_shell() {
+        let mut mesh = longs(&[2, 2]);
+        mesh.extend(doubles(&[
+            0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0, 1.0, 1.0, 0.0,
+        ]));
+        mesh.extend(longs(&[0])); // no edge traits
+        let mut shell = longs(&[3]);
+        shell.extend(doubles(&[0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0]));
+        shell.extend(longs(&[8, 3, 0, 1, 2, (-3i32) as u32, 2, 1, 0]));
+        let primitives = decode(&Graphics::default().record(MESH, mesh).record(SHELL, shell));
+        match primitives[0] {
+            ProxyGraphicsPrimitive::Mesh {
+                row_count,
+                column_count,
+                ref vertices,
+            } => {
+                assert_eq!((2, 2), (row_count, column_count));
+                assert_eq!(Point::new(1.0, 1.0, 0.0), vertices[3]);
+            }
+            _ => panic!("expected a mesh"),
+        }
+        match primitives[1] {
+            ProxyGraphicsPrimitive::Shell {
+                ref vertices,
+                ref faces,
+            } => {
+                assert_eq!(3, vertices.len());
+                assert_eq!(
+                    &vec![
+                        ProxyShellFace {
+                            vertex_indices: vec![0, 1, 2],
+                            is_hole: false,
+                        },
+                        ProxyShellFace {
+                            vertex_indices: vec![2, 1, 0],
+                            is_hole: true,
+                        },
+                    ],
+                    faces
+                );
+            }
+            _ => panic!("expected a shell"),
+        }
+    }
+
+    #[test]
+    fn decode_text() {
+        let mut text = doubles(&[1.0, 2.0, 0.0, 0.0, 0.0, 1.0, 1.0, 0.0, 0.0, 2.5, 0.8, 0.0]);
+        text.extend(padded("hello"));
+        let mut text2 = doubles(&[0.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 1.0, 0.0]);
+        text2.extend(padded("abc"));
+        text2.extend(longs(&[3, 0]));
+        text2.extend(doubles(&[1.5, 1.0, 0.25, 1.0]));
+        text2.extend(longs(&[1, 0, 0, 1, 0]));
+        text2.extend(padded("romans.shx"));
+        text2.extend(padded(""));
+        let mut unicode = doubles(&[0.0, 0.0, 0.0, 0.0, 0.0, 1.0, 1.0, 0.0, 0.0, 1.0, 1.0, 0.0]);
+        unicode.extend(
+            "\u{00E9}t\u{00E9}\0"
+                .encode_utf16()
+                .flat_map(|u| u.to_le_bytes()),
+        );
+        let primitives = decode(
+            &Graphics::default()
+                .record(TEXT, text)
+                .record(TEXT2, text2)
+                .record(UNICODE_TEXT, unicode),
+        );
+        assert_eq!(
+            ProxyGraphicsPrimitive::Text(ProxyText {
+                location: Point::new(1.0, 2.0, 0.0),
+                height: 2.5,
+                width_factor: 0.8,
+                value: String::from("hello"),
+                ..Default::default()
+            }),
+            primitives[0]
+        );
+        assert_eq!(
+            ProxyGraphicsPrimitive::Text(ProxyText {
+                direction: Vector::y_axis(),
+                height: 1.5,
+                oblique_angle: 0.25,
+                value: String::from("abc"),
+                is_backwards: true,
+                is_underlined: true,
+                font_file_name: String::from("romans.shx"),
+                ..Default::default()
+            }),
+            primitives[1]
+        );
+        match primitives[2] {
+            ProxyGraphicsPrimitive::Text(ref text) => assert_eq!("\u{00E9}t\u{00E9}", text.value),
+            _ => panic!("expected text"),
+        }
+    }
+
+    #[test]
+    fn decode_subentity_traits() {
+        let mut matrix = vec![0.0; 16];
+        matrix[0] = 1.0;
+        matrix[5] = 1.0;
+        matrix[10] = 1.0;
+        matrix[15] = 1.0;
+        matrix[3] = 5.0;
+        let primitives = decode(
+            &Graphics::default()
+                .record(SUBENTITY_COLOR, longs(&[1]))
+                .record(SUBENTITY_TRUE_COLOR, longs(&[0xC200_8040]))
+                .record(SUBENTITY_TRUE_COLOR, longs(&[0xC000_0000]))
+                .record(SUBENTITY_LAYER, longs(&[2]))
+                .record(SUBENTITY_LINE_TYPE, longs(&[0x7FFF]))
+                .record(SUBENTITY_LINE_TYPE, longs(&[3]))
+                .record(SUBENTITY_LINE_WEIGHT, longs(&[50]))
+                .record(PUSH_MATRIX, doubles(&matrix))
+                .record(POP_MATRIX, vec![])
+                .record(99, vec![1, 2, 3, 4]),
+        );
+        assert_eq!(
+            vec![
+                ProxyGraphicsPrimitive::Color(Color::from_index(1)),
+                ProxyGraphicsPrimitive::TrueColor(0x8040),
+                ProxyGraphicsPrimitive::Color(Color::by_layer()),
+                ProxyGraphicsPrimitive::Layer(2),
+                ProxyGraphicsPrimitive::LineType(ProxyLineType::ByLayer),
+                ProxyGraphicsPrimitive::LineType(ProxyLineType::Index(3)),
+                ProxyGraphicsPrimitive::LineWeight(50),
+                ProxyGraphicsPrimitive::PushMatrix(TransformationMatrix {
+                    m14: 5.0,
+                    ..TransformationMatrix::identity()
+                }),
+                ProxyGraphicsPrimitive::PopMatrix,
+                ProxyGraphicsPrimitive::Unsupported {
+                    record_type: 99,
+                    data: vec![1, 2, 3, 4],
+                },
+            ],
+            primitives
+        );
+    }
+
+    #[test]
+    fn decode_truncated_graphics() {
+        let bytes = Graphics::default()
+            .record(CIRCLE, circle(0.0, 0.0, 1.0))
+            .bytes();
+        let proxy = ProxyEntity {
+            graphics_data_size: bytes.len() as i32 - 4,
+            graphics_data_string: vec![bytes],
+            ..Default::default()
+        };
+        assert!(matches!(
+            proxy.graphics(),
+            Err(DxfError::UnexpectedEndOfInput)
+        ));
+
+        // a record can't be smaller than its header
+        let mut bytes = longs(&[16, 1, 4, CIRCLE]);
+        bytes.truncate(16);
+        assert!(matches!(
+            decode_graphics(bytes),
+            Err(DxfError::ParseError(8))
+        ));
+    }
+
+    fn decode_graphics(bytes: Vec<u8>) -> DxfResult<Vec<ProxyGraphicsPrimitive>> {
+        ProxyEntity {
+            graphics_data_string: vec![bytes],
+            ..Default::default()
+        }
+        .graphics()
+    }
+
+    #[test]
+    fn graphics_data_ignores_trailing_bytes() {
+        let mut bytes = Graphics::default()
+            .record(CIRCLE, circle(0.0, 0.0, 1.0))
+            .bytes();
+        let size = bytes.len() as i32;
+        bytes.extend_from_slice(&[0xFF; 5]);
+        let proxy = ProxyEntity {
+            graphics_data_size: size,
+            graphics_data_string: vec![bytes[..10].to_vec(), bytes[10..].to_vec()],
+            ..Default::default()
+        };
+        assert_eq!(size as usize, proxy.graphics_data().len());
+        assert_eq!(1, proxy.graphics().unwrap().len());
+    }
+
+    #[test]
+    fn explode_only_proxies() {
+        let drawing = Drawing::new();
+        let line = Entity::new(EntityType::Line(Line::default()));
+        assert!(line.explode_proxy(&drawing).is_none());
+    }
+
+    #[test]
+    fn explode_applies_subentity_traits() {
+        let mut drawing = Drawing::new();
+        drawing.add_layer(Layer {
+            name: String::from("walls"),
+            ..Default::default()
+        });
+        let layer_index = drawing.layers().position(|l| l.name == "walls").unwrap();
+        let mut proxy = Graphics::default()
+            .record(CIRCLE, circle(0.0, 0.0, 1.0))
+            .record(SUBENTITY_COLOR, longs(&[3]))
+            .record(SUBENTITY_LAYER, longs(&[layer_index as u32]))
+            .record(SUBENTITY_LINE_TYPE, longs(&[0x7FFE]))
+            .record(SUBENTITY_LINE_WEIGHT, longs(&[35]))
+            .record(XLINE, doubles(&[0.0, 0.0, 0.0, 0.0, 2.0, 0.0]))
+            .record(SUBENTITY_TRUE_COLOR, longs(&[0xC2FF_0000]))
+            .record(RAY, doubles(&[0.0, 0.0, 0.0, 3.0, 0.0, 0.0]))
+            .proxy();
+        proxy.common.layer = String::from("proxies");
+        proxy.common.color = Color::from_index(5);
+        proxy.common.handle = Handle(0x20);
+        add_reactor(&mut proxy.common.extension_data_groups, Handle(0x21));
+        set_extension_dictionary_handle(&mut proxy.common.extension_data_groups, Handle(0x22));
+        proxy.common.x_data.push(XData {
+            application_name: String::from("APP"),
+            items: vec![],
+        });
+        let entities = proxy.explode_proxy(&drawing).unwrap();
+        assert_eq!(3, entities.len());
+
+        // ownership and reactors stay with the proxy
+        for entity in &entities {
+            assert!(entity.common.handle.is_empty());
+            assert!(entity.common.extension_data_groups.is_empty());
+            assert!(entity.common.x_data.is_empty());
+        }
+
+        assert_eq!("proxies", entities[0].common.layer);
+        assert_eq!(Color::from_index(5), entities[0].common.color);
+        match entities[0].specific {
+            EntityType::Circle(ref circle) => assert_eq!(1.0, circle.radius),
+            _ => panic!("expected a circle"),
+        }
+
+        assert_eq!("walls", entities[1].common.layer);
+        assert_eq!(Color::from_index(3), entities[1].common.color);
+        assert_eq!("BYBLOCK", entities[1].common.line_type_name);
+        assert_eq!(35, entities[1].common.lineweight_enum_value);
+        match entities[1].specific {
+            EntityType::XLine(ref xline) => {
+                assert_eq!(Vector::y_axis(), xline.unit_direction_vector)
+            }
+            _ => panic!("expected an xline"),
+        }
+
+        assert_eq!(0xFF_0000, entities[2].common.color_24_bit);
+        match entities[2].specific {
+            EntityType::Ray(ref ray) => assert_eq!(Vector::x_axis(), ray.unit_direction_vector),
+            _ => panic!("expected a ray"),
+        }
+    }
+
+    #[test]
+    fn explode_applies_matrices() {
+        // scale by 2 and move 10 along x
+        let mut matrix = vec![0.0; 16];
+        matrix[0] = 2.0;
+        matrix[5] = 2.0;
+        matrix[10] = 2.0;
+        matrix[15] = 1.0;
+        matrix[3] = 10.0;
+        let entities = explode(
+            &Drawing::new(),
+            &Graphics::default()
+                .record(PUSH_MATRIX, doubles(&matrix))
+                .record(CIRCLE, circle(1.0, 0.0, 1.0))
+                .record(POP_MATRIX, vec![])
+                .record(CIRCLE, circle(1.0, 0.0, 1.0)),
+        );
+        match (&entities[0].specific, &entities[1].specific) {
+            (EntityType::Circle(ref scaled), EntityType::Circle(ref original)) => {
+                assert_eq!(Point::new(12.0, 0.0, 0.0), scaled.center);
+                assert_eq!(2.0, scaled.radius);
+                assert_eq!(Point::new(1.0, 0.0, 0.0), original.center);
+                assert_eq!(1.0, original.radius);
+            }
+            _ => panic!("expected circles"),
+        }
+    }
+
+    #[test]
+    fn explode_arcs() {
+        let mut sector = doubles(&[0.0, 0.0, 0.0, 2.0, 0.0, 0.0, 1.0, 0.0, 1.0, 0.0]);
+        sector.extend(doubles(&[std::f64::consts::FRAC_PI_2]));
+        sector.extend(longs(&[1]));
+        let mut three_points = doubles(&[1.0, 0.0, 0.0, 0.0, 1.0, 0.0, -1.0, 0.0, 0.0]);
+        three_points.extend(longs(&[0]));
+        let entities = explode(
+            &Drawing::new(),
+            &Graphics::default()
+                .record(ARC, sector)
+                .record(5, three_points),
+        );
+        assert_eq!(4, entities.len());
+        match entities[0].specific {
+            EntityType::Arc(ref arc) => {
+                assert!((arc.start_angle - 90.0).abs() < 1e-9);
+                assert!((arc.end_angle - 180.0).abs() < 1e-9);
+                assert_eq!(2.0, arc.radius);
+            }
+            _ => panic!("expected an arc"),
+        }
+        match (&entities[1].specific, &entities[2].specific) {
+            (EntityType::Line(ref first), EntityType::Line(ref second)) => {
+                assert!((first.p1.x + 2.0).abs() < 1e-9 && first.p1.y.abs() < 1e-9);
+                assert_eq!(Point::origin(), first.p2);
+                assert_eq!(Point::origin(), second.p1);
+                assert!(second.p2.x.abs() < 1e-9 && (second.p2.y - 2.0).abs() < 1e-9);
+            }
+            _ => panic!("expected lines"),
+        }
+        match entities[3].specific {
+            EntityType::Arc(ref arc) => {
+                assert!(arc.center.x.abs() < 1e-9 && arc.center.y.abs() < 1e-9);
+                assert!((arc.radius - 1.0).abs() < 1e-9);
+                assert!(arc.start_angle.abs() < 1e-9);
+                assert!((arc.end_angle - 180.0).abs() < 1e-9);
+            }
+            _ => panic!("expected an arc"),
+        }
+    }
+
+    #[test]
+    fn explode_full_turn_arcs_into_circles() {
+        for (sweep, arc_type) in [(2.0 * std::f64::consts::PI, 0), (7.0, 1)] {
+            let mut arc = doubles(&[1.0, 2.0, 0.0, 3.0, 0.0, 0.0, 1.0, 1.0, 0.0, 0.0]);
+            arc.extend(doubles(&[sweep]));
+            arc.extend(longs(&[arc_type]));
+            let entities = explode(&Drawing::new(), &Graphics::default().record(ARC, arc));
+            assert_eq!(1, entities.len());
+            match entities[0].specific {
+                EntityType::Circle(ref circle) => {
+                    assert_eq!(Point::new(1.0, 2.0, 0.0), circle.center);
+                    assert_eq!(3.0, circle.radius);
+                }
+                _ => panic!("expected a circle"),
+            }
+        }
+    }
+
+    #[test]
+    fn explode_polylines_meshes_and_text() {
+        let mut polygon = longs(&[3]);
+        polygon.extend(doubles(&[0.0, 0.0, 1.0, 1.0, 0.0, 1.0, 0.0, 1.0, 1.0]));
+        let mut mesh = longs(&[2, 2]);
+        mesh.extend(doubles(&[
+            0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0, 1.0, 1.0, 0.0,
+        ]));
+        let mut text = doubles(&[1.0, 2.0, 0.0, 0.0, 0.0, 1.0, 0.0, 1.0, 0.0, 2.5, 0.8, 0.0]);
+        text.extend(padded("label"));
+        let entities = explode(
+            &Drawing::new(),
+            &Graphics::default()
+                .record(POLYGON, polygon)
+                .record(MESH, mesh)
+                .record(TEXT, text),
+        );
+        match entities[0].specific {
+            EntityType::Polyline(ref poly) => {
+                assert!(poly.is_closed());
+                assert_eq!(3, poly.vertices().count());
+                assert_eq!(1.0, poly.location.z);
+            }
+            _ => panic!("expected a polyline"),
+        }
+        match entities[1].specific {
+            EntityType::Polyline(ref poly) => {
+                assert!(poly.is_3d_polygon_mesh());
+                assert_eq!(2, poly.polygon_mesh_m_vertex_count);
+                assert_eq!(4, poly.vertices().count());
+            }
+            _ => panic!("expected a polygon mesh"),
+        }
+        match entities[2].specific {
+            EntityType::Text(ref text) => {
+                assert_eq!("label", text.value);
+                assert_eq!(Point::new(1.0, 2.0, 0.0), text.location);
+                assert_eq!(90.0, text.rotation);
+                assert_eq!(2.5, text.text_height);
+                assert_eq!(0.8, text.relative_x_scale_factor);
+            }
+            _ => panic!("expected text"),
+        }
+    }
+
+    #[test]
+    fn explode_shells_into_polyface_meshes() {
+        // a pentagon is split into three triangles
+        let mut shell = longs(&[5]);
+        shell.extend(doubles(&[
+            0.0, 0.0, 0.0, 2.0, 0.0, 0.0, 3.0, 1.0, 0.0, 1.0, 2.0, 0.0, -1.0, 1.0, 0.0,
+        ]));
+        shell.extend(longs(&[6, 5, 0, 1, 2, 3, 4]));
+        let entities = explode(&Drawing::new(), &Graphics::default().record(SHELL, shell));
+        let poly = match entities[0].specific {
+            EntityType::Polyline(ref poly) => poly,
+            _ => panic!("expected a polyface mesh"),
+        };
+        assert!(poly.is_polyface_mesh());
+        let faces = poly
+            .vertices()
+            .filter(|v| !v.is_3d_polygon_mesh())
+            .map(|v| {
+                (
+                    v.polyface_mesh_vertex_index1,
+                    v.polyface_mesh_vertex_index2,
+                    v.polyface_mesh_vertex_index3,
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(vec![(1, 2, -3), (-1, 3, -4), (-1, 4, 5)], faces);
+        assert_eq!(5, poly.polygon_mesh_m_vertex_count);
+        assert_eq!(3, poly.polygon_mesh_n_vertex_count);
+    }
+
+    #[test]
+    fn explode_proxies_in_drawing() {
+        let mut drawing = Drawing::new();
+        drawing.header.version = AcadVersion::R2000;
+        drawing.add_entity(
+            Graphics::default()
+                .record(CIRCLE, circle(0.0, 0.0, 1.0))
+                .record(POLYGON, {
+                    let mut polygon = longs(&[3]);
+                    polygon.extend(doubles(&[0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0]));
+                    polygon
+                })
+                .proxy(),
+        );
+        drawing.add_entity(Entity::new(EntityType::ProxyEntity(ProxyEntity {
+            graphics_data_string: vec![vec![1, 2, 3]],
+            ..Default::default()
+        })));
+        assert_eq!(1, drawing.explode_proxy_entities());
+
+        let entities = drawing.entities().collect::<Vec<_>>();
+        assert_eq!(3, entities.len());
+        assert!(matches!(entities[0].specific, EntityType::Circle(_)));
+        assert!(matches!(entities[1].specific, EntityType::Polyline(_)));
+        assert!(matches!(entities[2].specific, EntityType::ProxyEntity(_)));
+        assert!(!entities[0].common.handle.is_empty());
+        assert_ne!(entities[0].common.handle, entities[1].common.handle);
+
+        // the new entities survive a round trip
+        let mut buf = vec![];
+        drawing.save(&mut buf).unwrap();
+        let reloaded = Drawing::load(&mut buf.as_slice()).unwrap();
+        assert_eq!(3, reloaded.entities().count());
+    }
+
+    #[test]
+    fn render_proxy_graphics() {
+        let mut drawing = Drawing::new();
+        let mut proxy = Graphics::default()
+            .record(SUBENTITY_COLOR, longs(&[1]))
+            .record(POLYLINE, {
+                let mut line = longs(&[2]);
+                line.extend(doubles(&[0.0, 5.0, 0.0, 10.0, 5.0, 0.0]));
+                line
+            })
+            .proxy();
+        proxy.common.color = Color::from_index(5);
+        drawing.add_entity(proxy);
+
+        let options = RasterOptions {
+            view: Some((Point::new(0.0, 0.0, 0.0), Point::new(10.0, 10.0, 0.0))),
+            anti_aliasing: false,
+            width: 10,
+            height: 10,
+            padding: 0,
+            ..Default::default()
+        };
+        let image = drawing.rasterize(&options).unwrap();
+        assert_eq!(&image::Rgba([255, 0, 0, 255]), image.get_pixel(5, 5));
+
+        let mut svg = vec![];
+        drawing.save_svg(&mut svg, &SvgOptions::default()).unwrap();
+        let svg = String::from_utf8(svg).unwrap();
+        assert!(svg.contains("0 -5"), "expected the polyline in:\n{svg}");
+    }
+}
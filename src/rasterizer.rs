@@ -0,0 +1,917 @@
+// renders a drawing to an image
+
+use std::f64::consts::PI;
+
+use image::{Rgba, RgbaImage};
+
+use crate::entities::*;
+use crate::geometry::*;
+use crate::mtext_layout::ApproximateTextMetrics;
+use crate::render::*;
+use crate::{Drawing, DxfResult, Point};
+
+/// The opacity of the boxes drawn in place of text.
+const TEXT_ALPHA: f64 = 0.35;
+
+/// The number of sample rows per pixel used when filling anti-aliased shapes.
+const FILL_SAMPLES: usize = 4;
+
+/// Options used when rasterizing a `Drawing` to an image.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RasterOptions {
+    /// The name of the `Layout` to render, or `None` to render model space.
+    pub layout_name: Option<String>,
+    /// The lower left and upper right corners of the area to render, in drawing units.  When
+    /// `None` the drawn content is fit to the image.  The aspect ratio is always preserved.
+    pub view: Option<(Point, Point)>,
+    /// The width of the image in pixels.
+    pub width: u32,
+    /// The height of the image in pixels.
+    pub height: u32,
+    /// The space left around fitted content, in pixels.
+    pub padding: u32,
+    /// The resolution, in pixels per inch, used to convert line weights to pixels.
+    pub dpi: f64,
+    /// The color the image is cleared to.
+    pub background_color: Rgba<u8>,
+    /// The color used for color index 7.  When `None` either black or white is used, whichever
+    /// contrasts with the background.
+    pub foreground_color: Option<(u8, u8, u8)>,
+    /// Whether edges are smoothed.
+    pub anti_aliasing: bool,
+    /// The line weight, in millimeters, of entities that use the default line weight.
+    pub default_line_weight: f64,
+}
+
+impl Default for RasterOptions {
+    fn default() -> Self {
+        RasterOptions {
+            layout_name: None,
+            view: None,
+            width: 512,
+            height: 384,
+            padding: 8,
+            dpi: 96.0,
+            background_color: Rgba([255, 255, 255, 255]),
+            foreground_color: None,
+            anti_aliasing: true,
+            default_line_weight: 0.25,
+        }
+    }
+}
+
+/// The BYBLOCK properties passed down from a block reference.
+#[derive(Clone, Copy)]
+struct Inherited {
+    color: (u8, u8, u8),
+    line_weight: f64,
+}
+
+/// A flattened shape in world coordinates.
+struct Primitive {
+    points: Vec<(f64, f64)>,
+    is_closed: bool,
+    is_filled: bool,
+    color: (u8, u8, u8),
+    alpha: f64,
+    /// The stroke width in millimeters.
+    line_weight: f64,
+}
+
+pub(crate) struct Rasterizer<'a> {
+    drawing: &'a Drawing,
+    options: &'a RasterOptions,
+    styles: StyleResolver<'a>,
+    primitives: Vec<Primitive>,
+}
+
+impl<'a> Rasterizer<'a> {
+    pub fn new(drawing: &'a Drawing, options: &'a RasterOptions) -> Self {
+        let foreground_color = options.foreground_color.unwrap_or_else(|| {
+            let Rgba([r, g, b, _]) = options.background_color;
+            let luminance = 0.299 * f64::from(r) + 0.587 * f64::from(g) + 0.114 * f64::from(b);
+            if luminance < 128.0 {
+                (255, 255, 255)
+            } else {
+                (0, 0, 0)
+            }
+        });
+        Rasterizer {
+            drawing,
+            options,
+            styles: StyleResolver {
+                drawing,
+                default_line_weight: options.default_line_weight,
+                foreground_color,
+            },
+            primitives: vec![],
+        }
+    }
+    pub fn render(&mut self) -> DxfResult<RgbaImage> {
+        let entities = layout_entities(self.drawing, self.options.layout_name.as_deref())?;
+        let inherited = Inherited {
+            color: self.styles.foreground_color,
+            line_weight: self.options.default_line_weight,
+        };
+        for entity in entities {
+            self.add_entity(entity, &Placement::identity(), inherited, false, 0);
+        }
+
+        let options = self.options;
+        let mut image =
+            RgbaImage::from_pixel(options.width, options.height, options.background_color);
+        let (width, height) = (f64::from(options.width), f64::from(options.height));
+        let (view, padding) = match options.view {
+            Some((ref min, ref max)) => {
+                let mut view = Bounds::empty();
+                view.add_point(min);
+                view.add_point(max);
+                (view, 0.0)
+            }
+            None => {
+                let mut view = Bounds::empty();
+                for (x, y) in self.primitives.iter().flat_map(|p| p.points.iter()) {
+                    view.add(*x, *y);
+                }
+                (view, f64::from(options.padding))
+            }
+        };
+        if view.is_empty() {
+            return Ok(image);
+        }
+
+        // fit the view to the image, keeping its aspect ratio
+        let view_width = view.max_x - view.min_x;
+        let view_height = view.max_y - view.min_y;
+        let available_width = (width - 2.0 * padding).max(1.0);
+        let available_height = (height - 2.0 * padding).max(1.0);
+        let scale = match (view_width > EPSILON, view_height > EPSILON) {
+            (true, true) => (available_width / view_width).min(available_height / view_height),
+            (true, false) => available_width / view_width,
+            (false, true) => available_height / view_height,
+            (false, false) => 1.0,
+        };
+        let center_x = (view.min_x + view.max_x) / 2.0;
+        let center_y = (view.min_y + view.max_y) / 2.0;
+        let to_pixel = |(x, y): &(f64, f64)| {
+            (
+                (x - center_x) * scale + width / 2.0,
+                height / 2.0 - (y - center_y) * scale,
+            )
+        };
+
+        for primitive in &self.primitives {
+            let points = primitive.points.iter().map(to_pixel).collect::<Vec<_>>();
+            let coverage = if primitive.is_filled {
+                fill_coverage(
+                    &points,
+                    options.width,
+                    options.height,
+                    options.anti_aliasing,
+                )
+            } else {
+                let line_width = (primitive.line_weight / 25.4 * options.dpi).max(1.0);
+                stroke_coverage(
+                    &points,
+                    primitive.is_closed,
+                    line_width / 2.0,
+                    options.width,
+                    options.height,
+                    options.anti_aliasing,
+                )
+            };
+            if let Some(coverage) = coverage {
+                coverage.blend(&mut image, primitive.color, primitive.alpha);
+            }
+        }
+
+        Ok(image)
+    }
+    fn add_entity(
+        &mut self,
+        entity: &Entity,
+        transform: &Placement,
+        inherited: Inherited,
+        in_block: bool,
+        depth: usize,
+    ) {
+        if !entity.common.is_visible || !self.styles.is_layer_on(&entity.common.layer) {
+            return;
+        }
+
+        let color = self
+            .styles
+            .color(entity, in_block)
+            .unwrap_or(inherited.color);
+        let line_weight = self
+            .styles
+            .line_weight(entity, in_block)
+            .unwrap_or(inherited.line_weight);
+        let mut add = |points: Vec<Point>, is_closed: bool, is_filled: bool| {
+            self.primitives.push(Primitive {
+                points: points.iter().map(|p| project(transform, p)).collect(),
+                is_closed,
+                is_filled,
+                color,
+                alpha: 1.0,
+                line_weight,
+            });
+        };
+        match entity.specific {
+            EntityType::Line(ref line) => add(vec![line.p1.clone(), line.p2.clone()], false, false),
+            EntityType::Circle(ref circle) => {
+                let points = curve_points(2.0 * PI, |t| {
+                    circle_point(&circle.center, circle.radius, 2.0 * PI * t, &circle.normal)
+                });
+                add(points, true, false);
+            }
+            EntityType::Arc(ref arc) => {
+                let start = arc.start_angle.to_radians();
+                let included = sweep_degrees(arc.start_angle, arc.end_angle).to_radians();
+                let points = curve_points(included, |t| {
+                    circle_point(&arc.center, arc.radius, start + included * t, &arc.normal)
+                });
+                add(points, false, false);
+            }
+            EntityType::Ellipse(ref ellipse) => {
+                let (start, end) = ellipse_parameter_range(ellipse);
+                let points = curve_points(end - start, |t| {
+                    ellipse.point_at_parameter(start + (end - start) * t)
+                });
+                add(points, ellipse.is_closed(), false);
+            }
+            EntityType::LwPolyline(ref poly) => {
+                let vertices = poly
+                    .vertices
+                    .iter()
+                    .map(|v| (v.x, v.y, v.bulge))
+                    .collect::<Vec<_>>();
+                let points = bulged_points(
+                    &vertices,
+                    poly.is_closed(),
+                    entity.common.elevation,
+                    &poly.extrusion_direction,
+                );
+                add(points, poly.is_closed(), false);
+            }
+            EntityType::Polyline(ref poly) => match polyline_straight_paths(poly) {
+                Some(paths) => {
+                    for (points, is_closed) in paths {
+                        add(points, is_closed, false);
+                    }
+                }
+                None => {
+                    let vertices = poly
+                        .vertices()
+                        .map(|v| (v.location.x, v.location.y, v.bulge))
+                        .collect::<Vec<_>>();
+                    let points =
+                        bulged_points(&vertices, poly.is_closed(), poly.location.z, &poly.normal);
+                    add(points, poly.is_closed(), false);
+                }
+            },
+            EntityType::Spline(ref spline) => {
+                add(
+                    spline.tessellate(SPLINE_SEGMENTS_PER_SPAN),
+                    spline.is_closed(),
+                    false,
+                );
+            }
+            EntityType::Solid(ref solid) => {
+                let corners = [
+                    &solid.first_corner,
+                    &solid.second_corner,
+                    &solid.fourth_corner,
+                    &solid.third_corner,
+                ]
+                .iter()
+                .map(|p| ocs_to_wcs(p, &solid.extrusion_direction))
+                .collect();
+                add(corners, true, true);
+            }
+            EntityType::Trace(ref trace) => {
+                let corners = [
+                    &trace.first_corner,
+                    &trace.second_corner,
+                    &trace.fourth_corner,
+                    &trace.third_corner,
+                ]
+                .iter()
+                .map(|p| ocs_to_wcs(p, &trace.extrusion_direction))
+                .collect();
+                add(corners, true, true);
+            }
+            EntityType::Face3D(ref face) => {
+                let corners = dedupe_loop(&[
+                    face.first_corner.clone(),
+                    face.second_corner.clone(),
+                    face.third_corner.clone(),
+                    face.fourth_corner.clone(),
+                ]);
+                add(corners, true, false);
+            }
+            EntityType::ModelPoint(ref point) => add(vec![point.location.clone()], false, false),
+            EntityType::Text(ref text) => {
+                self.add_text(&TextPlacement::from_text(text), transform, color);
+            }
+            EntityType::Attribute(ref att) => self.add_attribute(att, transform, color),
+            EntityType::MText(ref mtext) => {
+                for run in mtext.layout(&ApproximateTextMetrics) {
+                    let run_color = match (run.color_24_bit, run.color) {
+                        (Some(rgb), _) => rgb_components(rgb),
+                        (None, Some(c)) if c.is_index() => self.styles.index_color(&c),
+                        _ => color,
+                    };
+                    self.add_text(&TextPlacement::from_text(&run.text), transform, run_color);
+                }
+            }
+            EntityType::ProxyEntity(_) => {
+                for exploded in entity.explode_proxy(self.drawing).unwrap_or_default() {
+                    self.add_entity(&exploded, transform, inherited, in_block, depth);
+                }
+            }
+            EntityType::Insert(ref insert) => {
+                let inherited = Inherited { color, line_weight };
+                self.add_insert(insert, transform, inherited, depth);
+                for att in insert.attributes() {
+                    self.add_attribute(att, transform, color);
+                }
+            }
+            EntityType::RotatedDimension(ref d) => self.add_block(
+                &d.dimension_base.block_name,
+                transform,
+                color,
+                line_weight,
+                depth,
+            ),
+            EntityType::RadialDimension(ref d) => self.add_block(
+                &d.dimension_base.block_name,
+                transform,
+                color,
+                line_weight,
+                depth,
+            ),
+            EntityType::DiameterDimension(ref d) => self.add_block(
+                &d.dimension_base.block_name,
+                transform,
+                color,
+                line_weight,
+                depth,
+            ),
+            EntityType::AngularThreePointDimension(ref d) => self.add_block(
+                &d.dimension_base.block_name,
+                transform,
+                color,
+                line_weight,
+                depth,
+            ),
+            EntityType::OrdinateDimension(ref d) => self.add_block(
+                &d.dimension_base.block_name,
+                transform,
+                color,
+                line_weight,
+                depth,
+            ),
+            _ => (),
+        }
+    }
+    fn add_insert(
+        &mut self,
+        insert: &Insert,
+        transform: &Placement,
+        inherited: Inherited,
+        depth: usize,
+    ) {
+        let block = match self
+            .drawing
+            .blocks()
+            .find(|b| b.name.eq_ignore_ascii_case(&insert.name))
+        {
+            Some(block) => block,
+            None => return,
+        };
+        if depth >= MAX_BLOCK_DEPTH {
+            return;
+        }
+
+        for row in 0..insert.row_count.max(1) {
+            for column in 0..insert.column_count.max(1) {
+                let block_transform =
+                    Placement::for_insert(insert, &block.base_point, row, column).then(transform);
+                for entity in &block.entities {
+                    self.add_entity(entity, &block_transform, inherited, true, depth + 1);
+                }
+            }
+        }
+    }
+    /// Draws a dimension's block, which contains its geometry in world coordinates.
+    fn add_block(
+        &mut self,
+        name: &str,
+        transform: &Placement,
+        color: (u8, u8, u8),
+        line_weight: f64,
+        depth: usize,
+    ) {
+        let block = match self
+            .drawing
+            .blocks()
+            .find(|b| b.name.eq_ignore_ascii_case(name))
+        {
+            Some(block) => block,
+            None => return,
+        };
+        if depth >= MAX_BLOCK_DEPTH {
+            return;
+        }
+        let inherited = Inherited { color, line_weight };
+        for entity in &block.entities {
+            self.add_entity(entity, transform, inherited, true, depth + 1);
+        }
+    }
+    fn add_attribute(&mut self, att: &Attribute, transform: &Placement, color: (u8, u8, u8)) {
+        if !att.is_invisible() {
+            self.add_text(&TextPlacement::from_attribute(att), transform, color);
+        }
+    }
+    /// Text is drawn as a translucent box covering where the glyphs would be.
+    fn add_text(&mut self, placement: &TextPlacement, transform: &Placement, color: (u8, u8, u8)) {
+        if let Some(frame) = placement.frame() {
+            self.primitives.push(Primitive {
+                points: frame
+                    .corners()
+                    .iter()
+                    .map(|p| project(transform, p))
+                    .collect(),
+                is_closed: true,
+                is_filled: true,
+                color,
+                alpha: TEXT_ALPHA,
+                line_weight: 0.0,
+            });
+        }
+    }
+}
+
+/// Transforms a point into world coordinates and drops its Z coordinate.
+fn project(transform: &Placement, p: &Point) -> (f64, f64) {
+    let p = transform.apply(p);
+    (p.x, p.y)
+}
+
+/// The fraction of each pixel covered by a shape, within the shape's pixel bounds.
+struct Coverage {
+    left: u32,
+    top: u32,
+    width: u32,
+    values: Vec<f64>,
+}
+
+impl Coverage {
+    /// Creates an empty coverage buffer for the pixel area, or `None` if it's off the image.
+    fn new(
+        (min_x, min_y, max_x, max_y): (f64, f64, f64, f64),
+        image_width: u32,
+        image_height: u32,
+    ) -> Option<Self> {
+        let clamp = |value: f64, limit: u32| value.max(0.0).min(f64::from(limit)) as u32;
+        let left = clamp(min_x.floor(), image_width);
+        let top = clamp(min_y.floor(), image_height);
+        let right = clamp(max_x.ceil(), image_width);
+        let bottom = clamp(max_y.ceil(), image_height);
+        if left >= right || top >= bottom {
+            return None;
+        }
+        let width = right - left;
+        Some(Coverage {
+            left,
+            top,
+            width,
+            values: vec![0.0; (width * (bottom - top)) as usize],
+        })
+    }
+    fn height(&self) -> u32 {
+        self.values.len() as u32 / self.width
+    }
+    fn blend(&self, image: &mut RgbaImage, (r, g, b): (u8, u8, u8), alpha: f64) {
+        for (i, coverage) in self.values.iter().enumerate() {
+            let a = (coverage.min(1.0) * alpha) as f32;
+            if a <= 0.0 {
+                continue;
+            }
+            let x = self.left + i as u32 % self.width;
+            let y = self.top + i as u32 / self.width;
+            let Rgba([dr, dg, db, da]) = *image.get_pixel(x, y);
+            let mix = |source: u8, destination: u8| {
+                (f32::from(source) * a + f32::from(destination) * (1.0 - a)).round() as u8
+            };
+            let da = f32::from(da) / 255.0;
+            image.put_pixel(
+                x,
+                y,
+                Rgba([
+                    mix(r, dr),
+                    mix(g, dg),
+                    mix(b, db),
+                    ((a + da * (1.0 - a)) * 255.0).round() as u8,
+                ]),
+            );
+        }
+    }
+}
+
+/// Computes the coverage of a stroke of half width `half_width` along the pixel space points.
+fn stroke_coverage(
+    points: &[(f64, f64)],
+    is_closed: bool,
+    half_width: f64,
+    image_width: u32,
+    image_height: u32,
+    anti_aliasing: bool,
+) -> Option<Coverage> {
+    let reach = half_width + 1.0;
+    let mut coverage = Coverage::new(pixel_bounds(points, reach)?, image_width, image_height)?;
+    let height = coverage.height();
+
+    let mut segments = points.windows(2).map(|w| (w[0], w[1])).collect::<Vec<_>>();
+    match points {
+        [] => return None,
+        [only] => segments.push((*only, *only)),
+        [first, .., last] if is_closed => segments.push((*last, *first)),
+        _ => (),
+    }
+    for ((x1, y1), (x2, y2)) in segments {
+        let (dx, dy) = (x2 - x1, y2 - y1);
+        let length_squared = dx * dx + dy * dy;
+        let column_start = ((x1.min(x2) - reach).floor() - f64::from(coverage.left)).max(0.0);
+        let column_end =
+            ((x1.max(x2) + reach).ceil() - f64::from(coverage.left)).min(f64::from(coverage.width));
+        let row_start = ((y1.min(y2) - reach).floor() - f64::from(coverage.top)).max(0.0);
+        let row_end =
+            ((y1.max(y2) + reach).ceil() - f64::from(coverage.top)).min(f64::from(height));
+        for row in row_start as u32..row_end.max(0.0) as u32 {
+            let py = f64::from(coverage.top + row) + 0.5;
+            for column in column_start as u32..column_end.max(0.0) as u32 {
+                let px = f64::from(coverage.left + column) + 0.5;
+                let t = if length_squared > 0.0 {
+                    (((px - x1) * dx + (py - y1) * dy) / length_squared).clamp(0.0, 1.0)
+                } else {
+                    0.0
+                };
+                let distance = (px - x1 - t * dx).hypot(py - y1 - t * dy);
+                let value = if anti_aliasing {
+                    (half_width + 0.5 - distance).clamp(0.0, 1.0)
+                } else if distance <= half_width.max(0.5) {
+                    1.0
+                } else {
+                    0.0
+                };
+                let index = (row * coverage.width + column) as usize;
+                coverage.values[index] = coverage.values[index].max(value);
+            }
+        }
+    }
+    Some(coverage)
+}
+
+/// Computes the coverage of the polygon through the pixel space points, using the even-odd rule.
+fn fill_coverage(
+    points: &[(f64, f64)],
+    image_width: u32,
+    image_height: u32,
+    anti_aliasing: bool,
+) -> Option<Coverage> {
+    if points.len() < 3 {
+        return None;
+    }
+    let mut coverage = Coverage::new(pixel_bounds(points, 0.0)?, image_width, image_height)?;
+    let samples = if anti_aliasing { FILL_SAMPLES } else { 1 };
+    let mut crossings = vec![];
+    for row in 0..coverage.height() {
+        for sample in 0..samples {
+            let y = f64::from(coverage.top + row) + (sample as f64 + 0.5) / samples as f64;
+            crossings.clear();
+            for (i, &(x1, y1)) in points.iter().enumerate() {
+                let (x2, y2) = points[(i + 1) % points.len()];
+                if (y1 <= y) != (y2 <= y) {
+                    crossings.push(x1 + (y - y1) / (y2 - y1) * (x2 - x1));
+                }
+            }
+            crossings.sort_by(|a, b| a.total_cmp(b));
+            for span in crossings.chunks_exact(2) {
+                let start = span[0] - f64::from(coverage.left);
+                let end = span[1] - f64::from(coverage.left);
+                for column in 0..coverage.width {
+                    let left = f64::from(column);
+                    let covered = if anti_aliasing {
+                        (end.min(left + 1.0) - start.max(left)).max(0.0)
+                    } else if start <= left + 0.5 && left + 0.5 < end {
+                        1.0
+                    } else {
+                        0.0
+                    };
+                    coverage.values[(row * coverage.width + column) as usize] +=
+                        covered / samples as f64;
+                }
+            }
+        }
+    }
+    Some(coverage)
+}
+
+/// Returns the pixel space extents of the points grown by `reach`.
+fn pixel_bounds(points: &[(f64, f64)], reach: f64) -> Option<(f64, f64, f64, f64)> {
+    let mut bounds = Bounds::empty();
+    for (x, y) in points {
+        bounds.add(*x, *y);
+    }
+    if bounds.is_empty() || !(bounds.max_x - bounds.min_x).is_finite() {
+        return None;
+    }
+    Some((
+        bounds.min_x - reach,
+        bounds.min_y - reach,
+        bounds.max_x + reach,
+        bounds.max_y + reach,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::enums::*;
+    use crate::objects::*;
+    use crate::rasterizer::*;
+    use crate::tables::*;
+    use crate::{Block, Color, DxfError, Vector};
+
+    fn options(width: u32, height: u32) -> RasterOptions {
+        RasterOptions {
+            width,
+            height,
+            padding: 0,
+            ..Default::default()
+        }
+    }
+
+    fn line(x1: f64, y1: f64, x2: f64, y2: f64) -> Entity {
+        Entity::new(EntityType::Line(Line::new(
+            Point::new(x1, y1, 0.0),
+            Point::new(x2, y2, 0.0),
+        )))
+    }
+
+    #[test]
+    fn draw_lines_over_the_background() {
+        let mut drawing = Drawing::new();
+        let mut entity = line(0.0, 5.0, 10.0, 5.0);
+        entity.common.color = Color::from_index(1);
+        drawing.add_entity(entity);
+        let options = RasterOptions {
+            view: Some((Point::new(0.0, 0.0, 0.0), Point::new(10.0, 10.0, 0.0))),
+            anti_aliasing: false,
+            ..options(10, 10)
+        };
+        let image = drawing.rasterize(&options).unwrap();
+        assert_eq!((10, 10), image.dimensions());
+        assert_eq!(&Rgba([255, 0, 0, 255]), image.get_pixel(5, 5));
+        assert_eq!(&Rgba([255, 255, 255, 255]), image.get_pixel(5, 1));
+        assert_eq!(&Rgba([255, 255, 255, 255]), image.get_pixel(5, 8));
+    }
+
+    #[test]
+    fn anti_aliasing_blends_edges() {
+        let mut drawing = Drawing::new();
+        // a diagonal line crosses pixels partially
+        drawing.add_entity(line(0.0, 0.0, 10.0, 7.0));
+        let is_partial = |p: &Rgba<u8>| p[0] > 0 && p[0] < 255;
+        let image = drawing.rasterize(&options(20, 20)).unwrap();
+        assert!(image.pixels().any(is_partial));
+
+        let options = RasterOptions {
+            anti_aliasing: false,
+            ..options(20, 20)
+        };
+        let image = drawing.rasterize(&options).unwrap();
+        assert!(!image.pixels().any(is_partial));
+    }
+
+    #[test]
+    fn line_weights_set_the_stroke_width() {
+        let stroke_height = |weight: i16| {
+            let mut drawing = Drawing::new();
+            let mut entity = line(0.0, 5.0, 10.0, 5.0);
+            entity.common.lineweight_enum_value = weight;
+            drawing.add_entity(entity);
+            let options = RasterOptions {
+                view: Some((Point::new(0.0, 0.0, 0.0), Point::new(10.0, 10.0, 0.0))),
+                anti_aliasing: false,
+                dpi: 254.0, // 10 pixels per millimeter
+                ..options(40, 40)
+            };
+            let image = drawing.rasterize(&options).unwrap();
+            (0..40).filter(|y| image.get_pixel(20, *y)[0] == 0).count()
+        };
+        assert_eq!(2, stroke_height(25));
+        assert_eq!(10, stroke_height(100));
+        assert_eq!(2, stroke_height(-3)); // the default weight is 0.25mm
+    }
+
+    #[test]
+    fn layer_colors_and_visibility_are_resolved() {
+        let mut drawing = Drawing::new();
+        drawing.add_layer(Layer {
+            name: String::from("red"),
+            color: Color::from_index(1),
+            ..Default::default()
+        });
+        drawing.add_layer(Layer {
+            name: String::from("hidden"),
+            is_layer_on: false,
+            ..Default::default()
+        });
+        let mut entity = line(0.0, 5.0, 10.0, 5.0);
+        entity.common.layer = String::from("red");
+        drawing.add_entity(entity);
+        let mut entity = line(5.0, 0.0, 5.0, 10.0);
+        entity.common.layer = String::from("hidden");
+        drawing.add_entity(entity);
+        let options = RasterOptions {
+            view: Some((Point::new(0.0, 0.0, 0.0), Point::new(10.0, 10.0, 0.0))),
+            anti_aliasing: false,
+            ..options(10, 10)
+        };
+        let image = drawing.rasterize(&options).unwrap();
+        assert_eq!(&Rgba([255, 0, 0, 255]), image.get_pixel(2, 5));
+        assert_eq!(&Rgba([255, 255, 255, 255]), image.get_pixel(5, 2));
+    }
+
+    #[test]
+    fn inserts_resolve_by_block_colors() {
+        let mut drawing = Drawing::new();
+        let mut line = Entity::new(EntityType::Line(Line::new(
+            Point::new(0.0, 0.0, 0.0),
+            Point::new(4.0, 0.0, 0.0),
+        )));
+        line.common.color = Color::by_block();
+        drawing.add_block(Block {
+            name: String::from("b"),
+            entities: vec![line],
+            ..Default::default()
+        });
+        let mut insert = Insert {
+            name: String::from("b"),
+            location: Point::new(2.0, 5.0, 0.0),
+            ..Default::default()
+        };
+        insert.x_scale_factor = 2.0;
+        let mut insert = Entity::new(EntityType::Insert(insert));
+        insert.common.color = Color::from_index(5);
+        drawing.add_entity(insert);
+
+        let options = RasterOptions {
+            view: Some((Point::new(0.0, 0.0, 0.0), Point::new(20.0, 10.0, 0.0))),
+            anti_aliasing: false,
+            ..options(20, 10)
+        };
+        let image = drawing.rasterize(&options).unwrap();
+        // the scaled line runs from x=2 to x=10
+        assert_eq!(&Rgba([0, 0, 255, 255]), image.get_pixel(9, 5));
+        assert_eq!(&Rgba([255, 255, 255, 255]), image.get_pixel(12, 5));
+    }
+
+    #[test]
+    fn inserts_follow_their_extrusion_direction() {
+        let mut drawing = Drawing::new();
+        drawing.add_block(Block {
+            name: String::from("b"),
+            entities: vec![line(0.0, 0.0, 4.0, 0.0)],
+            ..Default::default()
+        });
+        // looking from below mirrors the insert's X axis
+        drawing.add_entity(Entity::new(EntityType::Insert(Insert {
+            name: String::from("b"),
+            location: Point::new(-6.0, 5.0, 0.0),
+            extrusion_direction: Vector::new(0.0, 0.0, -1.0),
+            ..Default::default()
+        })));
+
+        let options = RasterOptions {
+            view: Some((Point::new(0.0, 0.0, 0.0), Point::new(20.0, 10.0, 0.0))),
+            anti_aliasing: false,
+            ..options(20, 10)
+        };
+        let image = drawing.rasterize(&options).unwrap();
+        // the line runs back from x=6 to x=2
+        assert_eq!(&Rgba([0, 0, 0, 255]), image.get_pixel(3, 5));
+        assert_eq!(&Rgba([255, 255, 255, 255]), image.get_pixel(8, 5));
+    }
+
+    #[test]
+    fn solids_are_filled() {
+        let mut drawing = Drawing::new();
+        drawing.add_entity(Entity::new(EntityType::Solid(Solid::new(
+            Point::new(2.0, 2.0, 0.0),
+            Point::new(8.0, 2.0, 0.0),
+            Point::new(2.0, 8.0, 0.0),
+            Point::new(8.0, 8.0, 0.0),
+        ))));
+        let options = RasterOptions {
+            view: Some((Point::new(0.0, 0.0, 0.0), Point::new(10.0, 10.0, 0.0))),
+            ..options(10, 10)
+        };
+        let image = drawing.rasterize(&options).unwrap();
+        assert_eq!(&Rgba([0, 0, 0, 255]), image.get_pixel(5, 5));
+        assert_eq!(&Rgba([255, 255, 255, 255]), image.get_pixel(0, 0));
+    }
+
+    #[test]
+    fn foreground_contrasts_with_the_background() {
+        let mut drawing = Drawing::new();
+        drawing.add_entity(line(0.0, 5.0, 10.0, 5.0));
+        let options = RasterOptions {
+            view: Some((Point::new(0.0, 0.0, 0.0), Point::new(10.0, 10.0, 0.0))),
+            background_color: Rgba([0, 0, 0, 255]),
+            anti_aliasing: false,
+            ..options(10, 10)
+        };
+        let image = drawing.rasterize(&options).unwrap();
+        assert_eq!(&Rgba([255, 255, 255, 255]), image.get_pixel(5, 5));
+    }
+
+    #[test]
+    fn content_is_fit_to_the_image() {
+        let mut drawing = Drawing::new();
+        drawing.add_entity(line(100.0, 100.0, 100.0, 200.0));
+        let options = RasterOptions {
+            anti_aliasing: false,
+            ..options(20, 20)
+        };
+        let image = drawing.rasterize(&options).unwrap();
+        assert_eq!(&Rgba([0, 0, 0, 255]), image.get_pixel(10, 0));
+        assert_eq!(&Rgba([0, 0, 0, 255]), image.get_pixel(10, 19));
+        assert_eq!(&Rgba([255, 255, 255, 255]), image.get_pixel(2, 10));
+    }
+
+    #[test]
+    fn render_layouts() {
+        let mut drawing = Drawing::new();
+        let mut entity = Entity::new(EntityType::Line(Line::new(
+            Point::new(0.0, 0.0, 0.0),
+            Point::new(1.0, 1.0, 0.0),
+        )));
+        entity.common.is_in_paper_space = true;
+        drawing.add_entity(entity);
+        drawing.add_object(Object::new(ObjectType::Layout(Layout {
+            layout_name: String::from("Layout1"),
+            ..Default::default()
+        })));
+
+        let model = drawing.rasterize(&options(10, 10)).unwrap();
+        assert!(model.pixels().all(|p| p == &Rgba([255, 255, 255, 255])));
+        let options = RasterOptions {
+            layout_name: Some(String::from("Layout1")),
+            ..options(10, 10)
+        };
+        let layout = drawing.rasterize(&options).unwrap();
+        assert!(layout.pixels().any(|p| p != &Rgba([255, 255, 255, 255])));
+
+        let options = RasterOptions {
+            layout_name: Some(String::from("missing")),
+            ..options
+        };
+        match drawing.rasterize(&options) {
+            Err(DxfError::LayoutNotFound(name)) => assert_eq!("missing", name),
+            _ => panic!("expected a missing layout error"),
+        }
+    }
+
+    #[test]
+    fn regenerate_thumbnail_on_save() {
+        let mut drawing = Drawing::new();
+        drawing.header.version = AcadVersion::R2000;
+        drawing.add_entity(line(0.0, 0.0, 10.0, 10.0));
+        drawing.regenerate_thumbnail = Some(options(32, 24));
+
+        let mut buf = vec![];
+        drawing.save(&mut buf).unwrap();
+        let thumbnail = Drawing::load(&mut buf.as_slice())
+            .unwrap()
+            .thumbnail
+            .expect("expected a thumbnail");
+        assert_eq!(32, thumbnail.width());
+        assert_eq!(24, thumbnail.height());
+        let thumbnail = thumbnail.to_rgb8();
+        assert!(thumbnail.pixels().any(|p| p.0 == [0, 0, 0]));
+        assert!(thumbnail.pixels().any(|p| p.0 == [255, 255, 255]));
+    }
+
+    #[test]
+    fn save_png() {
+        let mut drawing = Drawing::new();
+        drawing.add_entity(line(0.0, 0.0, 10.0, 10.0));
+        let mut buf = vec![];
+        drawing.save_png(&mut buf, &options(16, 16)).unwrap();
+        let image = image::load_from_memory_with_format(&buf, image::ImageFormat::Png).unwrap();
+        assert_eq!((16, 16), (image.width(), image.height()));
+    }
+}
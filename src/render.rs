@@ -0,0 +1,886 @@
+// helpers shared by the renderers and exporters
+
+use std::f64::consts::PI;
+
+use crate::entities::*;
+use crate::enums::*;
+use crate::geometry::*;
+use crate::mtext_layout::{ApproximateTextMetrics, TextMetrics};
+use crate::objects::ObjectType;
+use crate::tables::Layer;
+use crate::{
+    Color, DecodedText, Drawing, DxfError, DxfResult, Point, SatDocument, TransformationMatrix,
+    Vector,
+};
+
+/// The number of segments used to approximate a full circle.
+pub(crate) const CIRCLE_SEGMENTS: usize = 128;
+
+/// The number of segments used to approximate each knot span of a spline.
+pub(crate) const SPLINE_SEGMENTS_PER_SPAN: usize = 16;
+
+/// The deepest nesting of block references that's followed; anything deeper is assumed to be a
+/// recursive reference.
+pub(crate) const MAX_BLOCK_DEPTH: usize = 16;
+
+/// Formats a number with at most `decimal_places` decimal places and no trailing zeros.
+pub(crate) fn num(value: f64, decimal_places: i32) -> String {
+    let scale = 10f64.powi(decimal_places);
+    let rounded = (value * scale).round() / scale;
+    if rounded == 0.0 {
+        // avoid writing `-0`
+        String::from("0")
+    } else {
+        format!("{rounded}")
+    }
+}
+
+/// Returns the entities drawn in the named `Layout`, or in model space if no name is given.
+pub(crate) fn layout_entities<'a>(
+    drawing: &'a Drawing,
+    layout_name: Option<&str>,
+) -> DxfResult<Vec<&'a Entity>> {
+    let space = |paper: bool| {
+        drawing
+            .entities()
+            .filter(|e| e.common.is_in_paper_space == paper)
+            .collect::<Vec<_>>()
+    };
+    let layout_name = match layout_name {
+        Some(name) => name,
+        None => return Ok(space(false)),
+    };
+
+    let layout = drawing.objects().find(|o| match o.specific {
+        ObjectType::Layout(ref layout) => layout.layout_name.eq_ignore_ascii_case(layout_name),
+        _ => false,
+    });
+    let layout = match layout {
+        Some(layout) => layout,
+        // drawings without layouts still have model space
+        None if layout_name.eq_ignore_ascii_case("Model") => return Ok(space(false)),
+        None => return Err(DxfError::LayoutNotFound(String::from(layout_name))),
+    };
+
+    let block_record = drawing
+        .block_records()
+        .find(|r| !layout.common.handle.is_empty() && r.__layout_handle == layout.common.handle);
+    match block_record.map(|r| r.name.as_str()) {
+        Some(name) if name.eq_ignore_ascii_case("*Model_Space") => Ok(space(false)),
+        Some(name) if name.eq_ignore_ascii_case("*Paper_Space") => Ok(space(true)),
+        // additional paper space layouts keep their entities in their block
+        Some(name) => Ok(drawing
+            .blocks()
+            .find(|b| b.name.eq_ignore_ascii_case(name))
+            .map(|b| b.entities.iter().collect())
+            .unwrap_or_default()),
+        None => Ok(space(!layout_name.eq_ignore_ascii_case("Model"))),
+    }
+}
+
+/// Returns the number of drawing units per millimeter, derived from `$INSUNITS` unless specified.
+pub(crate) fn units_per_millimeter(drawing: &Drawing, specified: Option<f64>) -> f64 {
+    specified.unwrap_or_else(|| 1.0 / millimeters_per_unit(drawing.header.default_drawing_units))
+}
+
+pub(crate) fn millimeters_per_unit(units: Units) -> f64 {
+    match units {
+        Units::Unitless | Units::Millimeters => 1.0,
+        Units::Inches => 25.4,
+        Units::Feet => 304.8,
+        Units::Miles => 1_609_344.0,
+        Units::Centimeters => 10.0,
+        Units::Meters => 1_000.0,
+        Units::Kilometers => 1_000_000.0,
+        Units::Microinches => 25.4e-6,
+        Units::Mils => 0.0254,
+        Units::Yards => 914.4,
+        Units::Angstroms => 1e-7,
+        Units::Nanometers => 1e-6,
+        Units::Microns => 1e-3,
+        Units::Decimeters => 100.0,
+        Units::Decameters => 10_000.0,
+        Units::Hectometers => 100_000.0,
+        Units::Gigameters => 1e12,
+        Units::AstronomicalUnits => 1.495_978_707e14,
+        Units::LightYears => 9.460_730_472_580_8e18,
+        Units::Parsecs => 3.085_677_581_491_367e19,
+        Units::USSurveyFeet => 1_200_000.0 / 3_937.0,
+        Units::USSurveyInch => 100_000.0 / 3_937.0,
+        Units::USSurveyYard => 3_600_000.0 / 3_937.0,
+        Units::USSurveyMile => 6_336_000_000.0 / 3_937.0,
+    }
+}
+
+pub(crate) fn rgb_components(rgb: i32) -> (u8, u8, u8) {
+    (
+        (rgb >> 16 & 0xFF) as u8,
+        (rgb >> 8 & 0xFF) as u8,
+        (rgb & 0xFF) as u8,
+    )
+}
+
+/// Resolves the BYLAYER and BYBLOCK properties of entities.  Properties that are inherited from
+/// the block reference are returned as `None`.
+pub(crate) struct StyleResolver<'a> {
+    pub drawing: &'a Drawing,
+    /// The line weight, in millimeters, of entities that use the default line weight.
+    pub default_line_weight: f64,
+    /// The color used for color index 7.
+    pub foreground_color: (u8, u8, u8),
+}
+
+impl<'a> StyleResolver<'a> {
+    pub fn layer(&self, name: &str) -> Option<&'a Layer> {
+        self.drawing
+            .layers()
+            .find(|l| l.name.eq_ignore_ascii_case(name))
+    }
+    pub fn is_layer_on(&self, name: &str) -> bool {
+        self.layer(name).is_none_or(|l| l.is_layer_on)
+    }
+    /// Returns `true` if the entity's layer dependent properties are taken from the inserting
+    /// block reference, which happens for entities on layer `0` within a block.
+    fn inherits_layer(entity: &Entity, in_block: bool) -> bool {
+        in_block && entity.common.layer == "0"
+    }
+    pub fn color(&self, entity: &Entity, in_block: bool) -> Option<(u8, u8, u8)> {
+        let common = &entity.common;
+        if common.color_24_bit != 0 {
+            Some(rgb_components(common.color_24_bit))
+        } else if common.color.is_by_block() {
+            None
+        } else if common.color.is_by_layer() {
+            if StyleResolver::inherits_layer(entity, in_block) {
+                return None;
+            }
+            let index = self
+                .layer(&common.layer)
+                .and_then(|l| l.color.index())
+                .unwrap_or(7);
+            Some(self.index_color(&Color::from_index(index)))
+        } else {
+            Some(self.index_color(&common.color))
+        }
+    }
+    pub fn index_color(&self, color: &Color) -> (u8, u8, u8) {
+        match color.index() {
+            Some(7) | None => self.foreground_color,
+            Some(_) => color.to_rgb().unwrap_or(self.foreground_color),
+        }
+    }
+    /// Returns the line weight in millimeters.
+    pub fn line_weight(&self, entity: &Entity, in_block: bool) -> Option<f64> {
+        let millimeters = |hundredths: i16| {
+            if hundredths > 0 {
+                f64::from(hundredths) / 100.0
+            } else {
+                self.default_line_weight
+            }
+        };
+        match entity.common.lineweight_enum_value {
+            -1 if StyleResolver::inherits_layer(entity, in_block) => None,
+            -1 => Some(
+                self.layer(&entity.common.layer)
+                    .map_or(self.default_line_weight, |l| {
+                        millimeters(l.line_weight.raw_value())
+                    }),
+            ),
+            -2 => None,
+            value => Some(millimeters(value)),
+        }
+    }
+    /// Returns the scaled dash, dot and space lengths of the entity's line type; continuous lines
+    /// have an empty pattern.
+    pub fn line_type_pattern(&self, entity: &Entity, in_block: bool) -> Option<Vec<f64>> {
+        let common = &entity.common;
+        let line_type_name = if common.line_type_name.eq_ignore_ascii_case("BYLAYER") {
+            if StyleResolver::inherits_layer(entity, in_block) {
+                return None;
+            }
+            match self.layer(&common.layer) {
+                Some(layer) => layer.line_type_name.as_str(),
+                None => return Some(vec![]),
+            }
+        } else if common.line_type_name.eq_ignore_ascii_case("BYBLOCK") {
+            return None;
+        } else {
+            common.line_type_name.as_str()
+        };
+
+        let scale = self.drawing.header.line_type_scale * common.line_type_scale;
+        Some(
+            self.drawing
+                .line_types()
+                .find(|lt| lt.name.eq_ignore_ascii_case(line_type_name))
+                .map(|lt| {
+                    lt.dash_dot_space_lengths
+                        .iter()
+                        .map(|l| l * scale)
+                        .collect()
+                })
+                .unwrap_or_default(),
+        )
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct Bounds {
+    pub min_x: f64,
+    pub min_y: f64,
+    pub max_x: f64,
+    pub max_y: f64,
+}
+
+impl Bounds {
+    pub fn empty() -> Self {
+        Bounds {
+            min_x: f64::INFINITY,
+            min_y: f64::INFINITY,
+            max_x: f64::NEG_INFINITY,
+            max_y: f64::NEG_INFINITY,
+        }
+    }
+    pub fn is_empty(&self) -> bool {
+        self.min_x > self.max_x
+    }
+    pub fn add(&mut self, x: f64, y: f64) {
+        self.min_x = self.min_x.min(x);
+        self.min_y = self.min_y.min(y);
+        self.max_x = self.max_x.max(x);
+        self.max_y = self.max_y.max(y);
+    }
+    pub fn add_point(&mut self, p: &Point) {
+        self.add(p.x, p.y);
+    }
+    pub fn union(&mut self, other: &Bounds) {
+        if !other.is_empty() {
+            self.add(other.min_x, other.min_y);
+            self.add(other.max_x, other.max_y);
+        }
+    }
+    pub fn corners(&self) -> [(f64, f64); 4] {
+        [
+            (self.min_x, self.min_y),
+            (self.max_x, self.min_y),
+            (self.max_x, self.max_y),
+            (self.min_x, self.max_y),
+        ]
+    }
+}
+
+/// The placement values shared by `Text`, `Attribute` and the runs of laid out `MText`.
+pub(crate) struct TextPlacement<'t> {
+    pub value: &'t str,
+    pub location: &'t Point,
+    pub second_alignment_point: &'t Point,
+    pub height: f64,
+    pub rotation: f64,
+    pub width_factor: f64,
+    pub oblique_angle: f64,
+    pub horizontal: HorizontalTextJustification,
+    pub vertical: VerticalTextJustification,
+    pub normal: &'t Vector,
+    pub is_backwards: bool,
+    pub is_upside_down: bool,
+}
+
+impl<'t> TextPlacement<'t> {
+    pub fn from_text(text: &'t Text) -> Self {
+        TextPlacement {
+            value: &text.value,
+            location: &text.location,
+            second_alignment_point: &text.second_alignment_point,
+            height: text.text_height,
+            rotation: text.rotation,
+            width_factor: text.relative_x_scale_factor,
+            oblique_angle: text.oblique_angle,
+            horizontal: text.horizontal_text_justification,
+            vertical: text.vertical_text_justification,
+            normal: &text.normal,
+            is_backwards: text.is_text_backwards(),
+            is_upside_down: text.is_text_upside_down(),
+        }
+    }
+    pub fn from_attribute(att: &'t Attribute) -> Self {
+        TextPlacement {
+            value: &att.value,
+            location: &att.location,
+            second_alignment_point: &att.second_alignment_point,
+            height: att.text_height,
+            rotation: att.rotation,
+            width_factor: att.relative_x_scale_factor,
+            oblique_angle: att.oblique_angle,
+            horizontal: att.horizontal_text_justification,
+            vertical: att.vertical_text_justification,
+            normal: &att.normal,
+            is_backwards: att.is_text_backwards(),
+            is_upside_down: att.is_text_upside_down(),
+        }
+    }
+    /// Decodes the text and computes where it's drawn, or returns `None` if there's nothing to
+    /// draw.
+    pub fn frame(&self) -> Option<TextFrame> {
+        let text = DecodedText::decode(self.value);
+        if text.text.is_empty() {
+            return None;
+        }
+
+        let location = ocs_to_wcs(self.location, self.normal);
+        let second = ocs_to_wcs(self.second_alignment_point, self.normal);
+        let is_default_alignment = self.horizontal == HorizontalTextJustification::Left
+            && self.vertical == VerticalTextJustification::Baseline;
+        let (anchor_point, rotation, text_length) = match self.horizontal {
+            HorizontalTextJustification::Aligned | HorizontalTextJustification::Fit
+                if !points_are_close(&location, &second) =>
+            {
+                // the text is stretched between the two points
+                let dx = second.x - location.x;
+                let dy = second.y - location.y;
+                (
+                    location,
+                    dy.atan2(dx).to_degrees(),
+                    Some((dx * dx + dy * dy).sqrt()),
+                )
+            }
+            _ if is_default_alignment => (location, self.rotation, None),
+            _ => (second, self.rotation, None),
+        };
+        let anchor_offset = match self.horizontal {
+            HorizontalTextJustification::Center | HorizontalTextJustification::Middle => 0.5,
+            HorizontalTextJustification::Right => 1.0,
+            _ => 0.0,
+        };
+        let vertical = if self.horizontal == HorizontalTextJustification::Middle {
+            VerticalTextJustification::Middle
+        } else {
+            self.vertical
+        };
+        let width = text_length.unwrap_or_else(|| {
+            ApproximateTextMetrics.text_width(&text.text, "", self.height, self.width_factor)
+        });
+        Some(TextFrame {
+            text,
+            anchor_point,
+            rotation,
+            text_length,
+            anchor_offset,
+            vertical,
+            width,
+            height: self.height,
+        })
+    }
+}
+
+/// Where a piece of text is drawn.
+pub(crate) struct TextFrame {
+    pub text: DecodedText,
+    pub anchor_point: Point,
+    /// The rotation in degrees.
+    pub rotation: f64,
+    /// The length the text is stretched to fit, if any.
+    pub text_length: Option<f64>,
+    /// The fraction of the width to the left of the anchor point.
+    pub anchor_offset: f64,
+    pub vertical: VerticalTextJustification,
+    /// The approximate width of the text.
+    pub width: f64,
+    pub height: f64,
+}
+
+impl TextFrame {
+    /// Returns the approximate corners of the box containing the text.
+    pub fn corners(&self) -> [Point; 4] {
+        let bottom = match self.vertical {
+            VerticalTextJustification::Baseline | VerticalTextJustification::Bottom => 0.0,
+            VerticalTextJustification::Middle => -0.5,
+            VerticalTextJustification::Top => -1.0,
+        };
+        let (sin, cos) = self.rotation.to_radians().sin_cos();
+        [(0.0, 0.0), (1.0, 0.0), (1.0, 1.0), (0.0, 1.0)].map(|(x, y)| {
+            let x = (x - self.anchor_offset) * self.width;
+            let y = (y + bottom) * self.height;
+            Point::new(
+                self.anchor_point.x + x * cos - y * sin,
+                self.anchor_point.y + x * sin + y * cos,
+                self.anchor_point.z,
+            )
+        })
+    }
+}
+
+/// Returns the point sequences, and whether each is closed, that draw a polyface mesh, polygon
+/// mesh or 3D polyline, or `None` for 2D polylines whose segments may be bulged.
+pub(crate) fn polyline_straight_paths(poly: &Polyline) -> Option<Vec<(Vec<Point>, bool)>> {
+    let vertices = poly.vertices().collect::<Vec<_>>();
+    if poly.is_polyface_mesh() {
+        let faces = polyface_faces(&vertices)
+            .into_iter()
+            .map(|points| (points, true))
+            .collect();
+        Some(faces)
+    } else if poly.is_3d_polygon_mesh() {
+        let m = poly.polygon_mesh_m_vertex_count.max(0) as usize;
+        let n = poly.polygon_mesh_n_vertex_count.max(0) as usize;
+        if m * n == 0 || m * n > vertices.len() {
+            return Some(vec![]);
+        }
+        let at = |row: usize, column: usize| vertices[row * n + column].location.clone();
+        let rows = (0..m).map(|row| {
+            let points = (0..n).map(|c| at(row, c)).collect();
+            (points, poly.is_polygon_mesh_closed_in_n_direction())
+        });
+        let columns = (0..n).map(|column| {
+            let points = (0..m).map(|r| at(r, column)).collect();
+            (points, poly.is_closed())
+        });
+        Some(rows.chain(columns).collect())
+    } else if poly.is_3d_polyline() {
+        let points = vertices.iter().map(|v| v.location.clone()).collect();
+        Some(vec![(points, poly.is_closed())])
+    } else {
+        None
+    }
+}
+
+/// Returns `true` if the object coordinate system of `normal` has the same XY plane as the world.
+pub(crate) fn is_planar(normal: &Vector) -> bool {
+    normal.x.abs() < EPSILON && normal.y.abs() < EPSILON && normal.z != 0.0
+}
+
+/// Returns the world coordinates of the point at `angle` radians on a circle in the object
+/// coordinate system of `normal`.
+pub(crate) fn circle_point(center: &Point, radius: f64, angle: f64, normal: &Vector) -> Point {
+    let (sin, cos) = angle.sin_cos();
+    ocs_to_wcs(
+        &Point::new(center.x + radius * cos, center.y + radius * sin, center.z),
+        normal,
+    )
+}
+
+/// Samples a curve at `t` from 0 to 1, using enough points for its `included_angle` in radians.
+pub(crate) fn curve_points(included_angle: f64, point_at: impl Fn(f64) -> Point) -> Vec<Point> {
+    let segments = (included_angle.abs() / (2.0 * PI) * CIRCLE_SEGMENTS as f64).ceil();
+    let segments = (segments as usize).max(1);
+    (0..=segments)
+        .map(|i| point_at(i as f64 / segments as f64))
+        .collect()
+}
+
+/// Flattens `(x, y, bulge)` vertices in the object coordinate system of `normal`.
+pub(crate) fn bulged_points(
+    vertices: &[(f64, f64, f64)],
+    is_closed: bool,
+    elevation: f64,
+    normal: &Vector,
+) -> Vec<Point> {
+    let world = |x: f64, y: f64| ocs_to_wcs(&Point::new(x, y, elevation), normal);
+    let mut points = match vertices.first() {
+        Some(&(x, y, _)) => vec![world(x, y)],
+        None => return vec![],
+    };
+    let segment_count = if is_closed {
+        vertices.len()
+    } else {
+        vertices.len() - 1
+    };
+    for i in 0..segment_count {
+        let (x1, y1, bulge) = vertices[i];
+        let (x2, y2, _) = vertices[(i + 1) % vertices.len()];
+        match bulge_to_arc(x1, y1, x2, y2, bulge) {
+            Some(arc) => {
+                let arc_points = curve_points(arc.included_angle, |t| {
+                    let angle = arc.start_angle + arc.included_angle * t;
+                    world(
+                        arc.center_x + arc.radius * angle.cos(),
+                        arc.center_y + arc.radius * angle.sin(),
+                    )
+                });
+                points.extend(arc_points.into_iter().skip(1));
+            }
+            None => points.push(world(x2, y2)),
+        }
+    }
+    points
+}
+
+/// An affine transform of 3D points, stored as the rows of a 3x4 matrix.
+#[derive(Clone, Copy)]
+pub(crate) struct Placement([[f64; 4]; 3]);
+
+impl Placement {
+    pub fn identity() -> Self {
+        Placement::from_columns(
+            &Vector::x_axis(),
+            &Vector::y_axis(),
+            &Vector::z_axis(),
+            &Point::origin(),
+        )
+    }
+    pub fn from_columns(x: &Vector, y: &Vector, z: &Vector, origin: &Point) -> Self {
+        Placement([
+            [x.x, y.x, z.x, origin.x],
+            [x.y, y.y, z.y, origin.y],
+            [x.z, y.z, z.z, origin.z],
+        ])
+    }
+    /// Returns the affine part of a matrix whose fourth column is the translation.
+    pub fn from_matrix(m: &TransformationMatrix) -> Self {
+        Placement([
+            [m.m11, m.m12, m.m13, m.m14],
+            [m.m21, m.m22, m.m23, m.m24],
+            [m.m31, m.m32, m.m33, m.m34],
+        ])
+    }
+    /// Returns the transform that places the contents of a block for one cell of an insert's
+    /// array.
+    pub fn for_insert(insert: &Insert, base_point: &Point, row: i16, column: i16) -> Self {
+        let (sin, cos) = insert.rotation.to_radians().sin_cos();
+        let (sx, sy, sz) = (
+            insert.x_scale_factor,
+            insert.y_scale_factor,
+            insert.z_scale_factor,
+        );
+        let dx = f64::from(column) * insert.column_spacing;
+        let dy = f64::from(row) * insert.row_spacing;
+        // move the base point to the origin, then scale, rotate and move into place in the
+        // insert's object coordinate system
+        let to_base = Placement::from_columns(
+            &Vector::x_axis(),
+            &Vector::y_axis(),
+            &Vector::z_axis(),
+            &Point::new(-base_point.x, -base_point.y, -base_point.z),
+        );
+        let placement = Placement::from_columns(
+            &Vector::new(cos * sx, sin * sx, 0.0),
+            &Vector::new(-sin * sy, cos * sy, 0.0),
+            &Vector::new(0.0, 0.0, sz),
+            &Point::new(
+                insert.location.x + dx * cos - dy * sin,
+                insert.location.y + dx * sin + dy * cos,
+                insert.location.z,
+            ),
+        );
+        let (x_axis, y_axis) = ocs_axes(&insert.extrusion_direction);
+        let ocs = Placement::from_columns(
+            &x_axis,
+            &y_axis,
+            &normalize(&insert.extrusion_direction),
+            &Point::origin(),
+        );
+        to_base.then(&placement).then(&ocs)
+    }
+    pub fn apply(&self, p: &Point) -> Point {
+        let m = &self.0;
+        let row = |r: &[f64; 4]| r[0] * p.x + r[1] * p.y + r[2] * p.z + r[3];
+        Point::new(row(&m[0]), row(&m[1]), row(&m[2]))
+    }
+    /// Transforms a direction, which isn't affected by the translation.
+    pub fn apply_vector(&self, v: &Vector) -> Vector {
+        let m = &self.0;
+        let row = |r: &[f64; 4]| r[0] * v.x + r[1] * v.y + r[2] * v.z;
+        Vector::new(row(&m[0]), row(&m[1]), row(&m[2]))
+    }
+    /// Returns the transform that applies `self` and then `outer`.
+    pub fn then(&self, outer: &Placement) -> Placement {
+        let (a, b) = (&outer.0, &self.0);
+        let mut result = [[0.0; 4]; 3];
+        for (r, row) in result.iter_mut().enumerate() {
+            for (c, value) in row.iter_mut().enumerate() {
+                *value = (0..3).map(|k| a[r][k] * b[k][c]).sum::<f64>();
+                if c == 3 {
+                    *value += a[r][3];
+                }
+            }
+        }
+        Placement(result)
+    }
+    /// Returns the transform as a 4x4 matrix in column-major order.
+    pub fn column_major(&self) -> [f64; 16] {
+        let m = &self.0;
+        [
+            m[0][0], m[1][0], m[2][0], 0.0, m[0][1], m[1][1], m[2][1], 0.0, m[0][2], m[1][2],
+            m[2][2], 0.0, m[0][3], m[1][3], m[2][3], 1.0,
+        ]
+    }
+    /// Returns the factor by which the transform scales volumes, which is negative when it
+    /// mirrors.
+    pub fn determinant(&self) -> f64 {
+        let m = &self.0;
+        m[0][0] * (m[1][1] * m[2][2] - m[1][2] * m[2][1])
+            - m[0][1] * (m[1][0] * m[2][2] - m[1][2] * m[2][0])
+            + m[0][2] * (m[1][0] * m[2][1] - m[1][1] * m[2][0])
+    }
+    /// Returns `true` if the transform turns right-handed faces into left-handed ones.
+    pub fn is_mirroring(&self) -> bool {
+        self.determinant() < 0.0
+    }
+}
+
+/// Returns the planar faces, in the entity's coordinates, that represent an entity's surface.
+pub(crate) fn surface_faces(entity: &Entity) -> Vec<Vec<Point>> {
+    let extrusion = |direction: &Vector, thickness: f64| {
+        let normal = normalize(direction);
+        Vector::new(
+            normal.x * thickness,
+            normal.y * thickness,
+            normal.z * thickness,
+        )
+    };
+    match entity.specific {
+        EntityType::Face3D(ref face) => vec![vec![
+            face.first_corner.clone(),
+            face.second_corner.clone(),
+            face.third_corner.clone(),
+            face.fourth_corner.clone(),
+        ]],
+        EntityType::Polyline(ref poly) => mesh_faces(poly),
+        EntityType::Solid(ref solid) => {
+            // the corners are stored in 'Z' order
+            let corners = [
+                &solid.first_corner,
+                &solid.second_corner,
+                &solid.fourth_corner,
+                &solid.third_corner,
+            ]
+            .iter()
+            .map(|p| ocs_to_wcs(p, &solid.extrusion_direction))
+            .collect::<Vec<_>>();
+            prism(
+                &corners,
+                &extrusion(&solid.extrusion_direction, solid.thickness),
+            )
+        }
+        EntityType::Trace(ref trace) => {
+            let corners = [
+                &trace.first_corner,
+                &trace.second_corner,
+                &trace.fourth_corner,
+                &trace.third_corner,
+            ]
+            .iter()
+            .map(|p| ocs_to_wcs(p, &trace.extrusion_direction))
+            .collect::<Vec<_>>();
+            prism(
+                &corners,
+                &extrusion(&trace.extrusion_direction, trace.thickness),
+            )
+        }
+        EntityType::Line(ref line) if line.thickness != 0.0 => walls(
+            &[line.p1.clone(), line.p2.clone()],
+            &extrusion(&line.extrusion_direction, line.thickness),
+            false,
+        ),
+        EntityType::Circle(ref circle) if circle.thickness != 0.0 => {
+            let ring = dedupe_loop(&curve_points(2.0 * PI, |t| {
+                circle_point(&circle.center, circle.radius, 2.0 * PI * t, &circle.normal)
+            }));
+            prism(&ring, &extrusion(&circle.normal, circle.thickness))
+        }
+        EntityType::Arc(ref arc) if arc.thickness != 0.0 => {
+            let start = arc.start_angle.to_radians();
+            let included = sweep_degrees(arc.start_angle, arc.end_angle).to_radians();
+            let points = curve_points(included, |t| {
+                circle_point(&arc.center, arc.radius, start + included * t, &arc.normal)
+            });
+            walls(&points, &extrusion(&arc.normal, arc.thickness), false)
+        }
+        // only planar faces are exported; curved ones would need a tessellator
+        EntityType::Body(ref body) => sat_planar_faces(body.sat_document()),
+        EntityType::Region(ref region) => sat_planar_faces(region.sat_document()),
+        EntityType::Solid3D(ref solid) => sat_planar_faces(solid.sat_document()),
+        _ => vec![],
+    }
+}
+
+fn sat_planar_faces(document: DxfResult<SatDocument>) -> Vec<Vec<Point>> {
+    document.map(|d| d.planar_faces()).unwrap_or_default()
+}
+
+/// Returns the faces of a polyface or polygon mesh.
+pub(crate) fn mesh_faces(poly: &Polyline) -> Vec<Vec<Point>> {
+    let vertices = poly.vertices().collect::<Vec<_>>();
+    if poly.is_polyface_mesh() {
+        polyface_faces(&vertices)
+    } else if poly.is_3d_polygon_mesh() {
+        let m = poly.polygon_mesh_m_vertex_count.max(0) as usize;
+        let n = poly.polygon_mesh_n_vertex_count.max(0) as usize;
+        if m * n == 0 || m * n > vertices.len() {
+            return vec![];
+        }
+        let at = |row: usize, column: usize| vertices[(row % m) * n + column % n].location.clone();
+        let rows = if poly.is_closed() { m } else { m - 1 };
+        let columns = if poly.is_polygon_mesh_closed_in_n_direction() {
+            n
+        } else {
+            n - 1
+        };
+        (0..rows)
+            .flat_map(|r| {
+                (0..columns)
+                    .map(move |c| vec![at(r, c), at(r, c + 1), at(r + 1, c + 1), at(r + 1, c)])
+            })
+            .collect()
+    } else {
+        vec![]
+    }
+}
+
+/// Returns the faces of a polyface mesh.  Vertex records hold locations and face records index
+/// them, starting at 1; negative indices mark invisible edges and unused indices are 0.
+fn polyface_faces(vertices: &[&Vertex]) -> Vec<Vec<Point>> {
+    let locations = vertices
+        .iter()
+        .filter(|v| v.is_3d_polygon_mesh())
+        .map(|v| v.location.clone())
+        .collect::<Vec<_>>();
+    vertices
+        .iter()
+        .filter(|v| !v.is_3d_polygon_mesh())
+        .map(|face| {
+            [
+                face.polyface_mesh_vertex_index1,
+                face.polyface_mesh_vertex_index2,
+                face.polyface_mesh_vertex_index3,
+                face.polyface_mesh_vertex_index4,
+            ]
+            .iter()
+            .take_while(|i| **i != 0)
+            .filter_map(|i| locations.get(i.unsigned_abs() as usize - 1).cloned())
+            .collect()
+        })
+        .collect()
+}
+
+/// Returns the faces of a planar polygon swept along `offset`, or just the polygon when there's
+/// no offset.  The faces wind counter-clockwise when seen from outside.
+pub(crate) fn prism(base: &[Point], offset: &Vector) -> Vec<Vec<Point>> {
+    if vector_length(offset) < EPSILON {
+        return vec![base.to_vec()];
+    }
+    let mut base = base.to_vec();
+    if dot(&newell_normal(&base), offset) > 0.0 {
+        // the bottom should face away from the top
+        base.reverse();
+    }
+    let top = base.iter().rev().map(|p| translate(p, offset)).collect();
+    let mut faces = walls(&base, offset, true);
+    faces.push(base);
+    faces.push(top);
+    faces
+}
+
+/// Returns the quads swept by each segment of a path moved along `offset`.
+pub(crate) fn walls(points: &[Point], offset: &Vector, is_closed: bool) -> Vec<Vec<Point>> {
+    let segment_count = if is_closed {
+        points.len()
+    } else {
+        points.len().saturating_sub(1)
+    };
+    (0..segment_count)
+        .map(|i| {
+            let a = &points[i];
+            let b = &points[(i + 1) % points.len()];
+            vec![
+                b.clone(),
+                a.clone(),
+                translate(a, offset),
+                translate(b, offset),
+            ]
+        })
+        .collect()
+}
+
+pub(crate) fn translate(p: &Point, offset: &Vector) -> Point {
+    Point::new(p.x + offset.x, p.y + offset.y, p.z + offset.z)
+}
+
+/// Splits a convex face into a fan of triangles with their unit normals, dropping degenerate
+/// ones.
+pub(crate) fn triangles(face: &[Point]) -> Vec<(Vector, [Point; 3])> {
+    (1..face.len().saturating_sub(1))
+        .filter_map(|i| {
+            let corners = [face[0].clone(), face[i].clone(), face[i + 1].clone()];
+            let normal = newell_normal(&corners);
+            if vector_length(&normal) < EPSILON {
+                None
+            } else {
+                Some((normalize(&normal), corners))
+            }
+        })
+        .collect()
+}
+
+/// Returns the point sequences, in world coordinates, and whether each is closed, that trace the
+/// curves of an entity.  Closed sequences don't repeat their first point.  Entities that aren't
+/// curves return nothing.
+pub(crate) fn curve_paths(entity: &Entity, segments_per_span: usize) -> Vec<(Vec<Point>, bool)> {
+    let path = |points: Vec<Point>, is_closed: bool| {
+        if is_closed {
+            (dedupe_loop(&points), true)
+        } else {
+            (points, false)
+        }
+    };
+    let paths = match entity.specific {
+        EntityType::Line(ref line) => vec![(vec![line.p1.clone(), line.p2.clone()], false)],
+        EntityType::Circle(ref circle) => vec![path(
+            curve_points(2.0 * PI, |t| {
+                circle_point(&circle.center, circle.radius, 2.0 * PI * t, &circle.normal)
+            }),
+            true,
+        )],
+        EntityType::Arc(ref arc) => {
+            let start = arc.start_angle.to_radians();
+            let included = sweep_degrees(arc.start_angle, arc.end_angle).to_radians();
+            vec![(
+                curve_points(included, |t| {
+                    circle_point(&arc.center, arc.radius, start + included * t, &arc.normal)
+                }),
+                false,
+            )]
+        }
+        EntityType::Ellipse(ref ellipse) => vec![path(
+            ellipse.tessellate(CIRCLE_SEGMENTS),
+            ellipse.is_closed(),
+        )],
+        EntityType::LwPolyline(ref poly) => {
+            let vertices = poly
+                .vertices
+                .iter()
+                .map(|v| (v.x, v.y, v.bulge))
+                .collect::<Vec<_>>();
+            let points = bulged_points(
+                &vertices,
+                poly.is_closed(),
+                entity.common.elevation,
+                &poly.extrusion_direction,
+            );
+            vec![path(points, poly.is_closed())]
+        }
+        EntityType::Polyline(ref poly) => match polyline_straight_paths(poly) {
+            Some(paths) => paths
+                .into_iter()
+                .map(|(points, is_closed)| path(points, is_closed))
+                .collect(),
+            None => {
+                let vertices = poly
+                    .vertices()
+                    .map(|v| (v.location.x, v.location.y, v.bulge))
+                    .collect::<Vec<_>>();
+                let points =
+                    bulged_points(&vertices, poly.is_closed(), poly.location.z, &poly.normal);
+                vec![path(points, poly.is_closed())]
+            }
+        },
+        EntityType::Spline(ref spline) => vec![path(
+            spline.tessellate(segments_per_span),
+            spline.is_closed(),
+        )],
+        _ => vec![],
+    };
+    paths
+        .into_iter()
+        .filter(|(points, _)| points.len() >= 2)
+        .collect()
+}
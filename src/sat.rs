@@ -0,0 +1,986 @@
+// reads the ACIS SAT text stored with `Body`, `Region` and `Solid3D` entities
+
+use std::collections::HashSet;
+use std::f64::consts::PI;
+
+use crate::geometry::*;
+use crate::render::{curve_points, Placement};
+use crate::{DxfError, DxfResult, Point, Vector};
+
+/// The longest string that's written in a single code pair; longer SAT lines continue in `3` codes.
+const MAX_LINE_LENGTH: usize = 255;
+
+/// The version that added entity ids to every record.
+const ENTITY_ID_VERSION: i32 = 700;
+
+/// Encrypts or decrypts a line of SAT text; the character substitution used by DXF files before
+/// `R2013` is its own inverse.
+pub(crate) fn crypt_sat_line(line: &str) -> String {
+    line.chars()
+        .map(|c| match c {
+            '!'..='~' => char::from(159 - c as u8),
+            _ => c,
+        })
+        .collect()
+}
+
+/// Joins the `1` and `3` code lines of modeler geometry into SAT text, decrypting it if needed.  A
+/// `1` line of the maximum length is continued by the next unused `3` line, and so on.
+pub(crate) fn sat_text_from_lines(lines: &[String], continuations: &[String]) -> String {
+    // plain SAT starts with its version number; encrypted digits aren't digits
+    let is_encrypted = lines
+        .first()
+        .and_then(|l| l.chars().next())
+        .is_some_and(|c| !c.is_ascii_digit());
+    let mut continuations = continuations.iter();
+    let mut text = String::new();
+    for line in lines {
+        let mut full_line = line.clone();
+        let mut last_length = line.chars().count();
+        while last_length == MAX_LINE_LENGTH {
+            match continuations.next() {
+                Some(continuation) => {
+                    full_line.push_str(continuation);
+                    last_length = continuation.chars().count();
+                }
+                None => break,
+            }
+        }
+
+        if is_encrypted {
+            full_line = crypt_sat_line(&full_line);
+        }
+        text.push_str(&full_line);
+        text.push('\n');
+    }
+
+    text
+}
+
+/// Splits SAT text into the `1` and `3` code lines of modeler geometry, encrypting it if requested.
+pub(crate) fn sat_lines_from_text(text: &str, encrypt: bool) -> (Vec<String>, Vec<String>) {
+    let mut lines = vec![];
+    let mut continuations = vec![];
+    for line in text.lines() {
+        let line = if encrypt {
+            crypt_sat_line(line)
+        } else {
+            String::from(line)
+        };
+        let chars = line.chars().collect::<Vec<_>>();
+        let mut chunks = chars.chunks(MAX_LINE_LENGTH).map(|c| c.iter().collect());
+        lines.push(chunks.next().unwrap_or_default());
+        continuations.extend(chunks);
+    }
+
+    (lines, continuations)
+}
+
+/// A value in a SAT record.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub enum SatToken {
+    /// A reference to another record by index, written `$n`; `-1` refers to nothing.
+    Pointer(i64),
+    Number(f64),
+    /// A length-prefixed string, written `@n text`.
+    Text(String),
+    /// Any other word, e.g., `forward`, `reversed` or `I`.
+    Word(String),
+}
+
+/// A single record of a SAT document.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub struct SatRecord {
+    /// The record type, e.g., `body`, `face` or `plane-surface`.
+    pub name: String,
+    /// The values after the record type, not including the terminating `#`.
+    pub tokens: Vec<SatToken>,
+}
+
+/// An ACIS SAT document as stored with `Body`, `Region` and `Solid3D` entities.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub struct SatDocument {
+    /// The SAT format version, e.g., `700` for ACIS 7.0.
+    pub version: i32,
+    /// The product, ACIS version and date strings from the header.
+    pub product: Vec<String>,
+    /// The number of millimeters in a model unit.
+    pub units: f64,
+    pub records: Vec<SatRecord>,
+}
+
+/// A solid, sheet or wire body.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub struct SatBody {
+    pub lumps: Vec<SatLump>,
+}
+
+/// A connected part of a body.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub struct SatLump {
+    /// The faces of all shells of the lump.
+    pub faces: Vec<SatFace>,
+}
+
+/// A bounded part of a surface.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub struct SatFace {
+    pub surface: SatSurface,
+    /// Whether the outside of the face is opposite the surface normal.
+    pub is_reversed: bool,
+    /// The boundaries of the face; the material is to the left when looking down on the face.
+    pub loops: Vec<SatLoop>,
+}
+
+/// A closed boundary of a face.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub struct SatLoop {
+    /// The edges in order around the loop.
+    pub edges: Vec<SatEdge>,
+}
+
+/// A bounded part of a curve, oriented the way its loop runs.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub struct SatEdge {
+    pub start: Point,
+    pub end: Point,
+    pub curve: SatCurve,
+    /// Whether the edge runs opposite the direction of its curve.
+    pub is_reversed: bool,
+}
+
+/// The surface underlying a face.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub enum SatSurface {
+    Plane {
+        root_point: Point,
+        normal: Vector,
+    },
+    /// A cylinder with an elliptical cross section, e.g., a `cone-surface` without a taper.
+    Cylinder {
+        center: Point,
+        axis: Vector,
+        /// The direction and length of the major radius.
+        major_axis: Vector,
+        /// The ratio of the minor radius to the major radius.
+        ratio: f64,
+    },
+    /// Any other surface, by record type.
+    Other(String),
+}
+
+/// The curve underlying an edge.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub enum SatCurve {
+    Line {
+        root_point: Point,
+        direction: Vector,
+    },
+    /// An ellipse that runs counterclockwise about `normal`.
+    Ellipse {
+        center: Point,
+        normal: Vector,
+        /// The direction and length of the major radius.
+        major_axis: Vector,
+        /// The ratio of the minor radius to the major radius.
+        ratio: f64,
+    },
+    /// Any other curve, by record type.
+    Other(String),
+}
+
+impl SatDocument {
+    /// Parses SAT text, e.g., the result of `Solid3D::sat_text()`.
+    pub fn parse(text: &str) -> DxfResult<SatDocument> {
+        let mut lines = text.lines();
+        let version = lines
+            .next()
+            .and_then(|l| l.split_whitespace().next())
+            .and_then(|v| v.parse::<i32>().ok())
+            .ok_or(DxfError::ParseError(0))?;
+
+        let mut rest = lines.collect::<Vec<_>>();
+        let mut product = vec![];
+        if rest
+            .first()
+            .is_some_and(|l| l.trim_start().starts_with('@'))
+        {
+            product = tokenize(rest.remove(0))
+                .into_iter()
+                .filter_map(|t| match t {
+                    SatToken::Text(s) => Some(s),
+                    _ => None,
+                })
+                .collect();
+        }
+        let units = match rest.first().and_then(|l| l.split_whitespace().next()) {
+            Some(units) => {
+                let units = units.parse::<f64>().map_err(|_| DxfError::ParseError(2))?;
+                rest.remove(0);
+                units
+            }
+            None => 1.0,
+        };
+
+        let mut records = vec![];
+        let mut current: Option<SatRecord> = None;
+        for token in tokenize(&rest.join("\n")) {
+            match (current.as_mut(), token) {
+                (None, SatToken::Word(w)) if w.starts_with("End-of-") => break,
+                // records may be prefixed with their negated index
+                (None, SatToken::Number(_)) => (),
+                (None, SatToken::Word(name)) => {
+                    current = Some(SatRecord {
+                        name,
+                        tokens: vec![],
+                    })
+                }
+                (None, _) => return Err(DxfError::ParseError(records.len())),
+                (Some(_), SatToken::Word(w)) if w == "#" => records.extend(current.take()),
+                (Some(record), token) => record.tokens.push(token),
+            }
+        }
+        if current.is_some() {
+            return Err(DxfError::UnexpectedEndOfInput);
+        }
+
+        Ok(SatDocument {
+            version,
+            product,
+            units,
+            records,
+        })
+    }
+    /// Returns the bodies of the document in world coordinates.
+    pub fn bodies(&self) -> Vec<SatBody> {
+        self.records
+            .iter()
+            .filter(|r| r.name == "body")
+            .map(|body| {
+                let transform = self
+                    .first_pointer(body, |n| n == "transform")
+                    .map(|t| self.transform(&self.records[t]))
+                    .unwrap_or_else(Placement::identity);
+                let lumps = self
+                    .chain(self.first_pointer(body, |n| n == "lump"), "lump")
+                    .into_iter()
+                    .map(|lump| SatLump {
+                        faces: self
+                            .chain(
+                                self.first_pointer(&self.records[lump], |n| n == "shell"),
+                                "shell",
+                            )
+                            .into_iter()
+                            .flat_map(|shell| {
+                                self.chain(
+                                    self.first_pointer(&self.records[shell], |n| n == "face"),
+                                    "face",
+                                )
+                            })
+                            .map(|face| self.face(&self.records[face], &transform))
+                            .collect(),
+                    })
+                    .collect();
+                SatBody { lumps }
+            })
+            .collect()
+    }
+    /// Returns the lower left and upper right corners of the box around all edges, or `None` if
+    /// there are no edges.
+    pub fn bounding_box(&self) -> Option<(Point, Point)> {
+        let mut corners: Option<(Point, Point)> = None;
+        let points = self
+            .bodies()
+            .into_iter()
+            .flat_map(|b| b.lumps)
+            .flat_map(|l| l.faces)
+            .flat_map(|f| f.loops)
+            .flat_map(|l| l.edges)
+            .flat_map(|e| e.points());
+        for p in points {
+            corners = Some(match corners {
+                Some((min, max)) => (
+                    Point::new(min.x.min(p.x), min.y.min(p.y), min.z.min(p.z)),
+                    Point::new(max.x.max(p.x), max.y.max(p.y), max.z.max(p.z)),
+                ),
+                None => (p.clone(), p),
+            });
+        }
+        corners
+    }
+    /// Returns the outer boundary of every planar face, counterclockwise when seen from outside.
+    /// Curved edges are approximated with straight segments and holes are ignored.
+    pub fn planar_faces(&self) -> Vec<Vec<Point>> {
+        let mut faces = vec![];
+        for face in self
+            .bodies()
+            .into_iter()
+            .flat_map(|b| b.lumps)
+            .flat_map(|l| l.faces)
+        {
+            let normal = match face.surface {
+                SatSurface::Plane { ref normal, .. } if face.is_reversed => {
+                    Vector::new(-normal.x, -normal.y, -normal.z)
+                }
+                SatSurface::Plane { ref normal, .. } => normal.clone(),
+                _ => continue,
+            };
+
+            // the outer loop encloses the largest area
+            let outer = face
+                .loops
+                .iter()
+                .map(|l| dedupe_loop(&l.points()))
+                .map(|points| (vector_length(&newell_normal(&points)), points))
+                .max_by(|a, b| a.0.total_cmp(&b.0));
+            if let Some((_, mut points)) = outer {
+                if points.len() < 3 {
+                    continue;
+                }
+                if dot(&newell_normal(&points), &normal) < 0.0 {
+                    points.reverse();
+                }
+                faces.push(points);
+            }
+        }
+        faces
+    }
+}
+
+impl SatLoop {
+    /// Returns the points along the loop, approximating curved edges with straight segments.  The
+    /// first point isn't repeated at the end.
+    pub fn points(&self) -> Vec<Point> {
+        let mut points = vec![];
+        for edge in &self.edges {
+            let mut edge_points = edge.points();
+            edge_points.pop();
+            points.extend(edge_points);
+        }
+        points
+    }
+}
+
+impl SatEdge {
+    /// Returns points from the start to the end of the edge, approximating curves with straight
+    /// segments.
+    pub fn points(&self) -> Vec<Point> {
+        match self.curve {
+            SatCurve::Ellipse {
+                ref center,
+                ref normal,
+                ref major_axis,
+                ratio,
+            } => {
+                let minor_axis = cross(&normalize(normal), major_axis);
+                let minor_axis = Vector::new(
+                    minor_axis.x * ratio,
+                    minor_axis.y * ratio,
+                    minor_axis.z * ratio,
+                );
+                let angle = |p: &Point| {
+                    let v = Vector::new(p.x - center.x, p.y - center.y, p.z - center.z);
+                    let x = dot(&v, major_axis) / dot(major_axis, major_axis);
+                    let y = dot(&v, &minor_axis) / dot(&minor_axis, &minor_axis).max(EPSILON);
+                    y.atan2(x)
+                };
+                let start = angle(&self.start);
+                let mut sweep = angle(&self.end) - start;
+                if self.is_reversed {
+                    sweep = -sweep;
+                }
+                if sweep <= EPSILON {
+                    sweep += 2.0 * PI;
+                }
+                if self.is_reversed {
+                    sweep = -sweep;
+                }
+                curve_points(sweep, |t| {
+                    let (sin, cos) = (start + sweep * t).sin_cos();
+                    Point::new(
+                        center.x + major_axis.x * cos + minor_axis.x * sin,
+                        center.y + major_axis.y * cos + minor_axis.y * sin,
+                        center.z + major_axis.z * cos + minor_axis.z * sin,
+                    )
+                })
+            }
+            _ => vec![self.start.clone(), self.end.clone()],
+        }
+    }
+}
+
+// private implementation
+impl SatDocument {
+    fn record_at(&self, pointer: i64) -> Option<usize> {
+        usize::try_from(pointer)
+            .ok()
+            .filter(|index| *index < self.records.len())
+    }
+    /// Returns the records referenced by `record` whose type matches `predicate`, in order.
+    fn pointers(&self, record: &SatRecord, predicate: impl Fn(&str) -> bool) -> Vec<usize> {
+        record
+            .tokens
+            .iter()
+            .filter_map(|t| match t {
+                SatToken::Pointer(p) => self.record_at(*p),
+                _ => None,
+            })
+            .filter(|index| predicate(&self.records[*index].name))
+            .collect()
+    }
+    fn first_pointer(&self, record: &SatRecord, predicate: impl Fn(&str) -> bool) -> Option<usize> {
+        self.pointers(record, predicate).into_iter().next()
+    }
+    /// Follows the `next` pointers of records of type `name`, starting at `first`.
+    fn chain(&self, first: Option<usize>, name: &str) -> Vec<usize> {
+        let mut result = vec![];
+        let mut seen = HashSet::new();
+        let mut current = first;
+        while let Some(index) = current {
+            // the coedges of a loop link back to the first one
+            if !seen.insert(index) {
+                break;
+            }
+            result.push(index);
+            current = self.first_pointer(&self.records[index], |n| n == name);
+        }
+        result
+    }
+    fn face(&self, face: &SatRecord, transform: &Placement) -> SatFace {
+        let surface = match self.first_pointer(face, |n| n.ends_with("-surface")) {
+            Some(surface) => self.surface(&self.records[surface], transform),
+            None => SatSurface::Other(String::new()),
+        };
+        let loops = self
+            .chain(self.first_pointer(face, |n| n == "loop"), "loop")
+            .into_iter()
+            .map(|l| SatLoop {
+                edges: self.loop_edges(&self.records[l], transform),
+            })
+            .collect();
+        SatFace {
+            surface,
+            is_reversed: is_reversed(face),
+            loops,
+        }
+    }
+    fn loop_edges(&self, sat_loop: &SatRecord, transform: &Placement) -> Vec<SatEdge> {
+        let mut edges = vec![];
+        for coedge in self.chain(self.first_pointer(sat_loop, |n| n == "coedge"), "coedge") {
+            let coedge = &self.records[coedge];
+            let edge = match self.first_pointer(coedge, |n| n == "edge") {
+                Some(edge) => &self.records[edge],
+                None => continue,
+            };
+            let vertices = self
+                .pointers(edge, |n| n == "vertex")
+                .into_iter()
+                .map(|v| {
+                    self.first_pointer(&self.records[v], |n| n == "point")
+                        .map(|p| transform.apply(&self.point(&self.records[p])))
+                        .unwrap_or_else(Point::origin)
+                })
+                .collect::<Vec<_>>();
+            let (mut start, mut end) = match vertices.as_slice() {
+                [start, end, ..] => (start.clone(), end.clone()),
+                [vertex] => (vertex.clone(), vertex.clone()),
+                [] => continue,
+            };
+            let curve = match self.first_pointer(edge, |n| n.ends_with("-curve")) {
+                Some(curve) => self.curve(&self.records[curve], transform),
+                None => SatCurve::Other(String::new()),
+            };
+            let is_coedge_reversed = is_reversed(coedge);
+            if is_coedge_reversed {
+                std::mem::swap(&mut start, &mut end);
+            }
+            edges.push(SatEdge {
+                start,
+                end,
+                curve,
+                is_reversed: is_reversed(edge) != is_coedge_reversed,
+            });
+        }
+        edges
+    }
+    fn surface(&self, record: &SatRecord, transform: &Placement) -> SatSurface {
+        let mut values = self.geometry_values(record);
+        match record.name.as_str() {
+            "plane-surface" => {
+                let root_point = values.point();
+                let normal = values.vector();
+                match (root_point, normal) {
+                    (Some(root_point), Some(normal)) => SatSurface::Plane {
+                        root_point: transform.apply(&root_point),
+                        normal: normalize(&transform.apply_vector(&normal)),
+                    },
+                    _ => SatSurface::Other(record.name.clone()),
+                }
+            }
+            "cone-surface" => {
+                let center = values.point();
+                let axis = values.vector();
+                let major_axis = values.vector();
+                let ratio = values.number();
+                values.skip_interval();
+                let sine = values.number();
+                match (center, axis, major_axis, ratio, sine) {
+                    (Some(center), Some(axis), Some(major_axis), Some(ratio), Some(sine))
+                        if sine.abs() < EPSILON =>
+                    {
+                        SatSurface::Cylinder {
+                            center: transform.apply(&center),
+                            axis: normalize(&transform.apply_vector(&axis)),
+                            major_axis: transform.apply_vector(&major_axis),
+                            ratio,
+                        }
+                    }
+                    _ => SatSurface::Other(record.name.clone()),
+                }
+            }
+            _ => SatSurface::Other(record.name.clone()),
+        }
+    }
+    fn curve(&self, record: &SatRecord, transform: &Placement) -> SatCurve {
+        let mut values = self.geometry_values(record);
+        match record.name.as_str() {
+            "straight-curve" => match (values.point(), values.vector()) {
+                (Some(root_point), Some(direction)) => SatCurve::Line {
+                    root_point: transform.apply(&root_point),
+                    direction: transform.apply_vector(&direction),
+                },
+                _ => SatCurve::Other(record.name.clone()),
+            },
+            "ellipse-curve" => {
+                match (
+                    values.point(),
+                    values.vector(),
+                    values.vector(),
+                    values.number(),
+                ) {
+                    (Some(center), Some(normal), Some(major_axis), Some(ratio)) => {
+                        // transforming the axes instead of the normal keeps the direction of
+                        // mirrored ellipses
+                        let major_axis = transform.apply_vector(&major_axis);
+                        let minor_axis =
+                            transform.apply_vector(&cross(&normalize(&normal), &major_axis));
+                        SatCurve::Ellipse {
+                            center: transform.apply(&center),
+                            normal: normalize(&cross(&major_axis, &minor_axis)),
+                            major_axis,
+                            ratio,
+                        }
+                    }
+                    _ => SatCurve::Other(record.name.clone()),
+                }
+            }
+            _ => SatCurve::Other(record.name.clone()),
+        }
+    }
+    fn point(&self, record: &SatRecord) -> Point {
+        self.geometry_values(record)
+            .point()
+            .unwrap_or_else(Point::origin)
+    }
+    /// Returns the matrix of a `transform` record: a row-major 3x3 matrix that's applied to row
+    /// vectors, a translation and a scale.
+    fn transform(&self, record: &SatRecord) -> Placement {
+        let mut values = self.geometry_values(record);
+        let mut m = [0.0; 13];
+        for value in m.iter_mut() {
+            match values.number() {
+                Some(v) => *value = v,
+                None => return Placement::identity(),
+            }
+        }
+        let s = m[12];
+        Placement::from_columns(
+            &Vector::new(s * m[0], s * m[1], s * m[2]),
+            &Vector::new(s * m[3], s * m[4], s * m[5]),
+            &Vector::new(s * m[6], s * m[7], s * m[8]),
+            &Point::new(m[9], m[10], m[11]),
+        )
+    }
+    /// Returns the values of a geometry record after its attribute, id and other pointers.
+    fn geometry_values<'a>(&self, record: &'a SatRecord) -> SatValues<'a> {
+        let mut tokens = record.tokens.as_slice();
+        if let [SatToken::Pointer(_), rest @ ..] = tokens {
+            tokens = rest;
+        }
+        if self.version >= ENTITY_ID_VERSION {
+            if let [SatToken::Number(_), rest @ ..] = tokens {
+                tokens = rest;
+            }
+        }
+        while let [SatToken::Pointer(_), rest @ ..] = tokens {
+            tokens = rest;
+        }
+        SatValues { tokens }
+    }
+}
+
+/// Returns whether a topology record is marked `reversed`.
+fn is_reversed(record: &SatRecord) -> bool {
+    record.tokens.iter().any(|t| match t {
+        SatToken::Word(w) => w == "reversed",
+        _ => false,
+    })
+}
+
+/// Reads the values of a geometry record in order.
+struct SatValues<'a> {
+    tokens: &'a [SatToken],
+}
+
+impl SatValues<'_> {
+    fn number(&mut self) -> Option<f64> {
+        match self.tokens {
+            [SatToken::Number(n), rest @ ..] => {
+                self.tokens = rest;
+                Some(*n)
+            }
+            _ => None,
+        }
+    }
+    fn point(&mut self) -> Option<Point> {
+        Some(Point::new(self.number()?, self.number()?, self.number()?))
+    }
+    fn vector(&mut self) -> Option<Vector> {
+        Some(Vector::new(self.number()?, self.number()?, self.number()?))
+    }
+    /// Skips a parameter range where each end is `I` for infinite or `F` and a value.
+    fn skip_interval(&mut self) {
+        for _ in 0..2 {
+            match self.tokens {
+                [SatToken::Word(w), SatToken::Number(_), rest @ ..] if w == "F" => {
+                    self.tokens = rest
+                }
+                [SatToken::Word(w), rest @ ..] if w == "I" => self.tokens = rest,
+                _ => (),
+            }
+        }
+    }
+}
+
+fn tokenize(text: &str) -> Vec<SatToken> {
+    let mut tokens = vec![];
+    let mut chars = text.chars().peekable();
+    loop {
+        while chars.next_if(|c| c.is_whitespace()).is_some() {}
+        let mut word = String::new();
+        while let Some(c) = chars.next_if(|c| !c.is_whitespace()) {
+            word.push(c);
+        }
+        if word.is_empty() {
+            break;
+        }
+
+        let token = match word.chars().next() {
+            Some('$') => match word[1..].parse::<i64>() {
+                Ok(pointer) => SatToken::Pointer(pointer),
+                Err(_) => SatToken::Word(word),
+            },
+            Some('@') => match word[1..].parse::<usize>() {
+                Ok(length) => {
+                    // the string starts after a single space and may contain spaces itself
+                    chars.next();
+                    SatToken::Text(chars.by_ref().take(length).collect())
+                }
+                Err(_) => SatToken::Word(word),
+            },
+            _ => match word.parse::<f64>() {
+                Ok(number) => SatToken::Number(number),
+                Err(_) => SatToken::Word(word),
+            },
+        };
+        tokens.push(token);
+    }
+    tokens
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::entities::*;
+    use crate::enums::*;
+    use crate::sat::*;
+    use crate::*;
+
+    const HEADER: &str = "700 0 1 0\n@7 Example @8 ACIS 7.0 @24 Thu Jan 01 00:00:00 2020\n1 9.9999999999999995e-07 1e-10\n";
+
+    /// A sheet body with a single 2x1 rectangular face in the XY plane.
+    const RECTANGLE: &str = "body $-1 -1 $-1 $1 $-1 $-1 #
+lump $-1 -1 $-1 $-1 $2 $0 #
+shell $-1 -1 $-1 $-1 $-1 $3 $-1 $1 #
+face $-1 -1 $-1 $-1 $4 $2 $-1 $5 forward double out #
+loop $-1 -1 $-1 $-1 $6 $3 #
+plane-surface $-1 -1 $-1 0 0 0 0 0 1 1 0 0 forward_v I I I I #
+coedge $-1 -1 $-1 $7 $9 $-1 $10 forward $4 $-1 #
+coedge $-1 -1 $-1 $8 $6 $-1 $11 forward $4 $-1 #
+coedge $-1 -1 $-1 $9 $7 $-1 $12 forward $4 $-1 #
+coedge $-1 -1 $-1 $6 $8 $-1 $13 reversed $4 $-1 #
+edge $-1 -1 $-1 $14 0 $15 2 $6 $18 forward @7 unknown #
+edge $-1 -1 $-1 $15 0 $16 1 $7 $19 forward @7 unknown #
+edge $-1 -1 $-1 $16 0 $17 2 $8 $20 forward @7 unknown #
+edge $-1 -1 $-1 $14 0 $17 1 $9 $21 forward @7 unknown #
+vertex $-1 -1 $-1 $10 $22 #
+vertex $-1 -1 $-1 $10 $23 #
+vertex $-1 -1 $-1 $11 $24 #
+vertex $-1 -1 $-1 $12 $25 #
+straight-curve $-1 -1 $-1 0 0 0 1 0 0 I I #
+straight-curve $-1 -1 $-1 2 0 0 0 1 0 I I #
+straight-curve $-1 -1 $-1 2 1 0 -1 0 0 I I #
+straight-curve $-1 -1 $-1 0 0 0 0 1 0 I I #
+point $-1 -1 $-1 0 0 0 #
+point $-1 -1 $-1 2 0 0 #
+point $-1 -1 $-1 2 1 0 #
+point $-1 -1 $-1 0 1 0 #
+End-of-ACIS-data
+";
+
+    /// The side of a cylinder with a radius of 1 from z=0 to z=2.
+    const CYLINDER_SIDE: &str = "body $-1 -1 $-1 $1 $-1 $-1 #
+lump $-1 -1 $-1 $-1 $2 $0 #
+shell $-1 -1 $-1 $-1 $-1 $3 $-1 $1 #
+face $-1 -1 $-1 $-1 $4 $2 $-1 $6 forward single out #
+loop $-1 -1 $-1 $5 $7 $3 #
+loop $-1 -1 $-1 $-1 $8 $3 #
+cone-surface $-1 -1 $-1 0 0 0 0 0 1 1 0 0 1 I I 0 1 1 forward I I I I #
+coedge $-1 -1 $-1 $7 $7 $-1 $9 forward $4 $-1 #
+coedge $-1 -1 $-1 $8 $8 $-1 $10 reversed $5 $-1 #
+edge $-1 -1 $-1 $11 0 $11 6.2831853071795862 $7 $13 forward @7 unknown #
+edge $-1 -1 $-1 $12 0 $12 6.2831853071795862 $8 $14 forward @7 unknown #
+vertex $-1 -1 $-1 $9 $15 #
+vertex $-1 -1 $-1 $10 $16 #
+ellipse-curve $-1 -1 $-1 0 0 0 0 0 1 1 0 0 1 I I #
+ellipse-curve $-1 -1 $-1 0 0 2 0 0 1 1 0 0 1 I I #
+point $-1 -1 $-1 1 0 0 #
+point $-1 -1 $-1 1 0 2 #
+End-of-ACIS-data
+";
+
+    fn rectangle() -> String {
+        format!("{}{}", HEADER, RECTANGLE)
+    }
+
+    fn assert_point_close(expected: (f64, f64, f64), actual: &Point) {
+        assert!(
+            (expected.0 - actual.x).abs() < 1e-9
+                && (expected.1 - actual.y).abs() < 1e-9
+                && (expected.2 - actual.z).abs() < 1e-9,
+            "expected {:?}, got {:?}",
+            expected,
+            actual
+        );
+    }
+
+    #[test]
+    fn crypt_line_is_its_own_inverse() {
+        let line = "700 0 1 0";
+        let encrypted = crypt_sat_line(line);
+        assert_eq!("hoo o n o", encrypted);
+        assert_eq!(line, crypt_sat_line(&encrypted));
+    }
+
+    #[test]
+    fn read_encrypted_text_with_continuations() {
+        let long_line = format!("point $-1 -1 $-1 {} 0 0 #", "1".repeat(300));
+        let text = format!("700 0 1 0\n{}\n", long_line);
+        let (lines, continuations) = sat_lines_from_text(&text, true);
+        assert_eq!(2, lines.len());
+        assert_eq!(255, lines[1].chars().count());
+        assert_eq!(1, continuations.len());
+        assert_eq!(text, sat_text_from_lines(&lines, &continuations));
+
+        let (lines, continuations) = sat_lines_from_text(&text, false);
+        assert_eq!("700 0 1 0", lines[0]);
+        assert_eq!(text, sat_text_from_lines(&lines, &continuations));
+    }
+
+    #[test]
+    fn parse_header_and_records() {
+        let doc = SatDocument::parse(&rectangle()).unwrap();
+        assert_eq!(700, doc.version);
+        assert_eq!(
+            vec!["Example", "ACIS 7.0", "Thu Jan 01 00:00:00 2020"],
+            doc.product
+        );
+        assert_eq!(1.0, doc.units);
+        assert_eq!(26, doc.records.len());
+        assert_eq!("vertex", doc.records[14].name);
+        assert_eq!(
+            vec![
+                SatToken::Pointer(-1),
+                SatToken::Number(-1.0),
+                SatToken::Pointer(-1),
+                SatToken::Pointer(10),
+                SatToken::Pointer(22),
+            ],
+            doc.records[14].tokens
+        );
+    }
+
+    #[test]
+    fn parse_strings_and_record_indices() {
+        let doc = SatDocument::parse(
+            "400 0 1 0\n@4 test\n1\n-0 body @5 a b c $1 #\n-1 lump $-1 #\nEnd-of-ACIS-data\n",
+        )
+        .unwrap();
+        assert_eq!(vec!["test"], doc.product);
+        assert_eq!(2, doc.records.len());
+        assert_eq!(
+            vec![SatToken::Text(String::from("a b c")), SatToken::Pointer(1)],
+            doc.records[0].tokens
+        );
+        assert_eq!("lump", doc.records[1].name);
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert!(SatDocument::parse("not a version\n").is_err());
+        match SatDocument::parse("700 0 1 0\n1\nbody $-1 -1") {
+            Err(DxfError::UnexpectedEndOfInput) => (),
+            _ => panic!("expected unexpected end of input"),
+        }
+    }
+
+    #[test]
+    fn read_planar_topology() {
+        let bodies = SatDocument::parse(&rectangle()).unwrap().bodies();
+        assert_eq!(1, bodies.len());
+        assert_eq!(1, bodies[0].lumps.len());
+        let face = &bodies[0].lumps[0].faces[0];
+        assert_eq!(
+            SatSurface::Plane {
+                root_point: Point::origin(),
+                normal: Vector::z_axis(),
+            },
+            face.surface
+        );
+        let edges = &face.loops[0].edges;
+        assert_eq!(4, edges.len());
+        assert_eq!(Point::new(0.0, 1.0, 0.0), edges[3].start);
+        assert_eq!(Point::origin(), edges[3].end);
+        assert!(edges[3].is_reversed);
+        assert_eq!(
+            SatCurve::Line {
+                root_point: Point::new(2.0, 0.0, 0.0),
+                direction: Vector::y_axis(),
+            },
+            edges[1].curve
+        );
+    }
+
+    #[test]
+    fn planar_faces_and_bounding_box() {
+        let doc = SatDocument::parse(&rectangle()).unwrap();
+        assert_eq!(
+            vec![vec![
+                Point::new(0.0, 0.0, 0.0),
+                Point::new(2.0, 0.0, 0.0),
+                Point::new(2.0, 1.0, 0.0),
+                Point::new(0.0, 1.0, 0.0),
+            ]],
+            doc.planar_faces()
+        );
+        assert_eq!(
+            Some((Point::origin(), Point::new(2.0, 1.0, 0.0))),
+            doc.bounding_box()
+        );
+    }
+
+    #[test]
+    fn body_transform_is_applied() {
+        let text = rectangle()
+            .replacen("body $-1 -1 $-1 $1 $-1 $-1 #", "body $-1 -1 $-1 $1 $-1 $26 #", 1)
+            .replace(
+                "End-of-ACIS-data",
+                "transform $-1 -1 0 1 0 -1 0 0 0 0 1 10 0 0 1 rotate no_reflect no_shear #\nEnd-of-ACIS-data",
+            );
+        let doc = SatDocument::parse(&text).unwrap();
+
+        // rotated a quarter turn about z and moved 10 along x
+        let (min, max) = doc.bounding_box().unwrap();
+        assert_point_close((9.0, 0.0, 0.0), &min);
+        assert_point_close((10.0, 2.0, 0.0), &max);
+    }
+
+    #[test]
+    fn read_cylindrical_face() {
+        let doc = SatDocument::parse(&format!("{}{}", HEADER, CYLINDER_SIDE)).unwrap();
+        let face = &doc.bodies()[0].lumps[0].faces[0];
+        assert_eq!(
+            SatSurface::Cylinder {
+                center: Point::origin(),
+                axis: Vector::z_axis(),
+                major_axis: Vector::x_axis(),
+                ratio: 1.0,
+            },
+            face.surface
+        );
+        assert_eq!(2, face.loops.len());
+        let bottom = face.loops[0].points();
+        assert_eq!(128, bottom.len());
+        assert_point_close((0.0, 1.0, 0.0), &bottom[32]);
+
+        // the top edge runs the other way
+        let top = face.loops[1].points();
+        assert_point_close((0.0, -1.0, 2.0), &top[32]);
+
+        let (min, max) = doc.bounding_box().unwrap();
+        assert_point_close((-1.0, -1.0, 0.0), &min);
+        assert_point_close((1.0, 1.0, 2.0), &max);
+        assert!(doc.planar_faces().is_empty());
+    }
+
+    #[test]
+    fn entity_sat_text_round_trip() {
+        let mut solid = Solid3D::default();
+        solid.set_sat_text(&rectangle(), AcadVersion::R2000);
+        assert_ne!("700 0 1 0", solid.custom_data[0]);
+        assert_eq!(rectangle(), solid.sat_text());
+
+        let mut drawing = Drawing::new();
+        drawing.header.version = AcadVersion::R2000;
+        drawing.add_entity(Entity::new(EntityType::Solid3D(solid)));
+        let mut buf = vec![];
+        drawing.save(&mut buf).unwrap();
+        let drawing = Drawing::load(&mut buf.as_slice()).unwrap();
+        match drawing.entities().next().unwrap().specific {
+            EntityType::Solid3D(ref solid) => {
+                let doc = solid.sat_document().unwrap();
+                assert_eq!(1, doc.planar_faces().len());
+            }
+            _ => panic!("expected a 3D solid"),
+        }
+
+        let mut region = Region::default();
+        region.set_sat_text(&rectangle(), AcadVersion::R2013);
+        assert_eq!("700 0 1 0", region.custom_data[0]);
+        assert_eq!(rectangle(), region.sat_text());
+    }
+
+    #[test]
+    fn export_planar_region_as_stl() {
+        let mut region = Region::default();
+        region.set_sat_text(&rectangle(), AcadVersion::R2000);
+        let mut drawing = Drawing::new();
+        drawing.add_entity(Entity::new(EntityType::Region(region)));
+        let mut buf = vec![];
+        let options = MeshExportOptions {
+            binary_stl: true,
+            ..Default::default()
+        };
+        drawing.save_stl(&mut buf, &options).unwrap();
+        assert_eq!([2, 0, 0, 0], buf[80..84]);
+    }
+}
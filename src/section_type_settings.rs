@@ -100,11 +100,11 @@ impl SectionTypeSettings {
             self.source_object_handles.len() as i32,
         ));
         for handle in &self.source_object_handles {
-            pairs.push(CodePair::new_string(330, &handle.as_string()));
+            pairs.push(CodePair::new_string(330, handle.as_string()));
         }
         pairs.push(CodePair::new_string(
             331,
-            &self.destination_object_handle.as_string(),
+            self.destination_object_handle.as_string(),
         ));
         pairs.push(CodePair::new_string(1, &self.destination_file_name));
         pairs.push(CodePair::new_i32(93, self.geometry_settings.len() as i32));
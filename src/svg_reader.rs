@@ -0,0 +1,1186 @@
+// imports SVG artwork as drawing entities
+
+use std::f64::consts::PI;
+use std::io::Read;
+
+use xmltree::Element;
+
+use crate::entities::*;
+use crate::enums::*;
+use crate::geometry::vector_length;
+use crate::render::Placement;
+use crate::{Drawing, DxfResult, LwPolylineVertex, Point, Vector};
+
+/// The number of CSS pixels per inch, used to convert SVG user units to millimeters.
+const PIXELS_PER_INCH: f64 = 96.0;
+
+/// The relative difference between the axes of an ellipse below which it's treated as a circle.
+const CIRCLE_TOLERANCE: f64 = 1e-9;
+
+/// Options used when importing SVG artwork into a `Drawing`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SvgImportOptions {
+    /// The number of drawing units per SVG user unit.  When `None` the drawing is created in
+    /// millimeters, sized from the root element's `width`, `height` and `viewBox` at 96 pixels
+    /// per inch.
+    pub scale: Option<f64>,
+}
+
+pub(crate) struct SvgReader<'a> {
+    options: &'a SvgImportOptions,
+    entities: Vec<Entity>,
+}
+
+/// The state inherited from ancestor elements.
+#[derive(Clone)]
+struct Context {
+    transform: Placement,
+    layer: String,
+    stroke: Option<String>,
+    fill: Option<String>,
+}
+
+impl<'a> SvgReader<'a> {
+    pub fn new(options: &'a SvgImportOptions) -> Self {
+        SvgReader {
+            options,
+            entities: vec![],
+        }
+    }
+    pub fn load<T>(&mut self, reader: &mut T) -> DxfResult<Drawing>
+    where
+        T: Read + ?Sized,
+    {
+        let root = Element::parse(reader)?;
+        let mut drawing = Drawing::new();
+        if self.options.scale.is_none() {
+            drawing.header.default_drawing_units = Units::Millimeters;
+        }
+
+        let context = Context {
+            transform: root_transform(&root, self.options.scale),
+            layer: String::from("0"),
+            stroke: None,
+            fill: None,
+        };
+        self.read_element(&root, &context);
+        for entity in self.entities.drain(..) {
+            drawing.add_entity(entity);
+        }
+        Ok(drawing)
+    }
+    fn read_element(&mut self, element: &Element, parent: &Context) {
+        if property(element, "display") == Some("none") {
+            return;
+        }
+
+        let mut context = parent.clone();
+        if let Some(transform) = element.attributes.get("transform") {
+            context.transform = parse_transform(transform).then(&parent.transform);
+        }
+        if let Some(stroke) = property(element, "stroke") {
+            context.stroke = Some(String::from(stroke));
+        }
+        if let Some(fill) = property(element, "fill") {
+            context.fill = Some(String::from(fill));
+        }
+
+        let mut path = PathBuilder::new(context.transform);
+        match element.name.as_str() {
+            "svg" | "g" | "a" | "switch" => {
+                if element.name == "g" {
+                    if let Some(layer) = element.attributes.get("id").and_then(|id| layer_name(id))
+                    {
+                        context.layer = layer;
+                    }
+                }
+                for child in &element.children {
+                    self.read_element(child, &context);
+                }
+            }
+            "path" => {
+                if let Some(data) = element.attributes.get("d") {
+                    read_path_data(data, &mut path);
+                }
+            }
+            "line" => {
+                path.move_to((length(element, "x1"), length(element, "y1")));
+                path.line_to((length(element, "x2"), length(element, "y2")));
+            }
+            "polyline" | "polygon" => {
+                let mut data =
+                    PathData::new(element.attributes.get("points").map_or("", |p| p.as_str()));
+                if let Some([x, y]) = data.numbers() {
+                    path.move_to((x, y));
+                    while let Some([x, y]) = data.numbers() {
+                        path.line_to((x, y));
+                    }
+                    if element.name == "polygon" {
+                        path.close();
+                    }
+                }
+            }
+            "rect" => read_rect(element, &mut path),
+            "circle" => {
+                let r = length(element, "r");
+                if r > 0.0 {
+                    path.full_ellipse((length(element, "cx"), length(element, "cy")), r, r);
+                }
+            }
+            "ellipse" => {
+                let rx = length(element, "rx");
+                let ry = length(element, "ry");
+                if rx > 0.0 && ry > 0.0 {
+                    path.full_ellipse((length(element, "cx"), length(element, "cy")), rx, ry);
+                }
+            }
+            // definitions, text and metadata aren't imported
+            _ => (),
+        }
+
+        let color = [&context.stroke, &context.fill]
+            .iter()
+            .find_map(|paint| paint.as_deref().and_then(parse_color));
+        for specific in path.finish() {
+            let mut entity = Entity::new(specific);
+            entity.common.layer = context.layer.clone();
+            if let Some(rgb) = color {
+                entity.common.color_24_bit = rgb;
+            }
+            self.entities.push(entity);
+        }
+    }
+}
+
+/// Returns the transform from the root element's user units to drawing units.  The y axis is
+/// flipped so the bottom left of the view is at the origin.
+fn root_transform(root: &Element, scale: Option<f64>) -> Placement {
+    let view_box = root.attributes.get("viewBox").and_then(|v| {
+        let [x, y, width, height] = PathData::new(v).numbers()?;
+        (width > 0.0 && height > 0.0).then_some((x, y, width, height))
+    });
+    let width = root.attributes.get("width").and_then(|w| parse_length(w));
+    let height = root.attributes.get("height").and_then(|h| parse_length(h));
+    let (min_x, min_y, view_height, pixels_per_unit) = match view_box {
+        Some((x, y, w, h)) => (
+            x,
+            y,
+            Some(h),
+            width.map(|px| px / w).or(height.map(|px| px / h)),
+        ),
+        None => (0.0, 0.0, height, None),
+    };
+    let scale = scale.unwrap_or_else(|| pixels_per_unit.unwrap_or(1.0) * 25.4 / PIXELS_PER_INCH);
+    matrix(
+        scale,
+        0.0,
+        0.0,
+        -scale,
+        -min_x * scale,
+        view_height.map_or(0.0, |h| (min_y + h) * scale),
+    )
+}
+
+fn read_rect(element: &Element, path: &mut PathBuilder) {
+    let x = length(element, "x");
+    let y = length(element, "y");
+    let width = length(element, "width");
+    let height = length(element, "height");
+    if width <= 0.0 || height <= 0.0 {
+        return;
+    }
+
+    // a missing corner radius takes the value of the other one
+    let rx = element.attributes.get("rx").and_then(|r| parse_length(r));
+    let ry = element.attributes.get("ry").and_then(|r| parse_length(r));
+    let (rx, ry) = (rx.or(ry).unwrap_or(0.0), ry.or(rx).unwrap_or(0.0));
+    let rx = rx.clamp(0.0, width / 2.0);
+    let ry = ry.clamp(0.0, height / 2.0);
+    if rx == 0.0 || ry == 0.0 {
+        path.move_to((x, y));
+        path.line_to((x + width, y));
+        path.line_to((x + width, y + height));
+        path.line_to((x, y + height));
+    } else {
+        let (right, bottom) = (x + width, y + height);
+        path.move_to((x + rx, y));
+        path.line_to((right - rx, y));
+        path.arc_to(rx, ry, 0.0, false, true, (right, y + ry));
+        path.line_to((right, bottom - ry));
+        path.arc_to(rx, ry, 0.0, false, true, (right - rx, bottom));
+        path.line_to((x + rx, bottom));
+        path.arc_to(rx, ry, 0.0, false, true, (x, bottom - ry));
+        path.line_to((x, y + ry));
+        path.arc_to(rx, ry, 0.0, false, true, (x + rx, y));
+    }
+    path.close();
+}
+
+/// Reads the commands of a path's `d` attribute.  As in SVG renderers, everything up to the first
+/// error is kept.
+fn read_path_data(d: &str, path: &mut PathBuilder) {
+    let mut data = PathData::new(d);
+    let mut previous = b' ';
+    // the control points reflected by the smooth curve commands
+    let mut cubic_control = None;
+    let mut quadratic_control = None;
+    loop {
+        let command = match data.command() {
+            Some(command) => command,
+            // repeated arguments repeat the command; those after a move are lines
+            None if data.has_number() => match previous {
+                b'M' => b'L',
+                b'm' => b'l',
+                b' ' | b'Z' | b'z' => break,
+                command => command,
+            },
+            None => break,
+        };
+        let (cx, cy) = path.current;
+        let is_relative = command.is_ascii_lowercase();
+        let point = |x: f64, y: f64| {
+            if is_relative {
+                (cx + x, cy + y)
+            } else {
+                (x, y)
+            }
+        };
+        let reflect = |control: Option<(f64, f64)>| match control {
+            Some((x, y)) => (2.0 * cx - x, 2.0 * cy - y),
+            None => (cx, cy),
+        };
+
+        let (mut next_cubic, mut next_quadratic) = (None, None);
+        match command.to_ascii_uppercase() {
+            b'M' => {
+                let Some([x, y]) = data.numbers() else { break };
+                path.move_to(point(x, y));
+            }
+            b'L' => {
+                let Some([x, y]) = data.numbers() else { break };
+                path.line_to(point(x, y));
+            }
+            b'H' => {
+                let Some([x]) = data.numbers() else { break };
+                path.line_to((if is_relative { cx + x } else { x }, cy));
+            }
+            b'V' => {
+                let Some([y]) = data.numbers() else { break };
+                path.line_to((cx, if is_relative { cy + y } else { y }));
+            }
+            b'C' => {
+                let Some([x1, y1, x2, y2, x, y]) = data.numbers() else {
+                    break;
+                };
+                next_cubic = Some(point(x2, y2));
+                path.cubic_to(point(x1, y1), point(x2, y2), point(x, y));
+            }
+            b'S' => {
+                let Some([x2, y2, x, y]) = data.numbers() else {
+                    break;
+                };
+                next_cubic = Some(point(x2, y2));
+                path.cubic_to(reflect(cubic_control), point(x2, y2), point(x, y));
+            }
+            b'Q' => {
+                let Some([x1, y1, x, y]) = data.numbers() else {
+                    break;
+                };
+                next_quadratic = Some(point(x1, y1));
+                path.quadratic_to(point(x1, y1), point(x, y));
+            }
+            b'T' => {
+                let Some([x, y]) = data.numbers() else { break };
+                let control = reflect(quadratic_control);
+                next_quadratic = Some(control);
+                path.quadratic_to(control, point(x, y));
+            }
+            b'A' => {
+                let Some([rx, ry, rotation]) = data.numbers() else {
+                    break;
+                };
+                let (Some(is_large_arc), Some(is_sweep)) = (data.flag(), data.flag()) else {
+                    break;
+                };
+                let Some([x, y]) = data.numbers() else { break };
+                path.arc_to(rx, ry, rotation, is_large_arc, is_sweep, point(x, y));
+            }
+            b'Z' => path.close(),
+            _ => break,
+        }
+        cubic_control = next_cubic;
+        quadratic_control = next_quadratic;
+        previous = command;
+    }
+}
+
+/// The sequence of segments being collected into a single entity.
+enum Run {
+    None,
+    Polyline(Vec<LwPolylineVertex>),
+    /// The degree and control points of joined Bézier segments.
+    Spline(i32, Vec<Point>),
+}
+
+/// Converts path segments in user units to entities in drawing units.  Lines and circular arcs
+/// are joined into polylines, consecutive Bézier segments of the same degree are joined into
+/// splines and elliptical arcs become ellipses.
+struct PathBuilder {
+    transform: Placement,
+    entities: Vec<EntityType>,
+    start: (f64, f64),
+    current: (f64, f64),
+    run: Run,
+    /// Whether the current subpath has already produced an entity.
+    is_split: bool,
+}
+
+impl PathBuilder {
+    fn new(transform: Placement) -> Self {
+        PathBuilder {
+            transform,
+            entities: vec![],
+            start: (0.0, 0.0),
+            current: (0.0, 0.0),
+            run: Run::None,
+            is_split: false,
+        }
+    }
+    fn finish(mut self) -> Vec<EntityType> {
+        self.flush(false);
+        self.entities
+    }
+    fn point(&self, (x, y): (f64, f64)) -> Point {
+        self.transform.apply(&Point::new(x, y, 0.0))
+    }
+    fn move_to(&mut self, p: (f64, f64)) {
+        self.flush(false);
+        self.is_split = false;
+        self.start = p;
+        self.current = p;
+    }
+    fn line_to(&mut self, p: (f64, f64)) {
+        let vertex = self.vertex(p);
+        self.polyline().push(vertex);
+        self.current = p;
+    }
+    fn cubic_to(&mut self, c1: (f64, f64), c2: (f64, f64), p: (f64, f64)) {
+        let points = [self.point(c1), self.point(c2), self.point(p)];
+        self.spline(3).extend(points);
+        self.current = p;
+    }
+    fn quadratic_to(&mut self, c: (f64, f64), p: (f64, f64)) {
+        let points = [self.point(c), self.point(p)];
+        self.spline(2).extend(points);
+        self.current = p;
+    }
+    /// Adds an arc using SVG's endpoint parameterization.
+    fn arc_to(
+        &mut self,
+        rx: f64,
+        ry: f64,
+        rotation: f64,
+        is_large_arc: bool,
+        is_sweep: bool,
+        p: (f64, f64),
+    ) {
+        let (x1, y1) = self.current;
+        let (x2, y2) = p;
+        if x1 == x2 && y1 == y2 {
+            return;
+        }
+        let (mut rx, mut ry) = (rx.abs(), ry.abs());
+        if rx == 0.0 || ry == 0.0 {
+            self.line_to(p);
+            return;
+        }
+
+        // convert to the center parameterization; see the SVG implementation notes
+        let (sin, cos) = rotation.to_radians().sin_cos();
+        let hx = (x1 - x2) / 2.0;
+        let hy = (y1 - y2) / 2.0;
+        let x1p = cos * hx + sin * hy;
+        let y1p = -sin * hx + cos * hy;
+        let lambda = (x1p * x1p) / (rx * rx) + (y1p * y1p) / (ry * ry);
+        if lambda > 1.0 {
+            rx *= lambda.sqrt();
+            ry *= lambda.sqrt();
+        }
+        let numerator = rx * rx * ry * ry - rx * rx * y1p * y1p - ry * ry * x1p * x1p;
+        let denominator = rx * rx * y1p * y1p + ry * ry * x1p * x1p;
+        let sign = if is_large_arc == is_sweep { -1.0 } else { 1.0 };
+        let coefficient = sign * (numerator.max(0.0) / denominator).sqrt();
+        let cxp = coefficient * rx * y1p / ry;
+        let cyp = -coefficient * ry * x1p / rx;
+        let center = (
+            cos * cxp - sin * cyp + (x1 + x2) / 2.0,
+            sin * cxp + cos * cyp + (y1 + y2) / 2.0,
+        );
+        let start_angle = ((y1p - cyp) / ry).atan2((x1p - cxp) / rx);
+        let end_angle = ((-y1p - cyp) / ry).atan2((-x1p - cxp) / rx);
+        let mut sweep = end_angle - start_angle;
+        if is_sweep && sweep < 0.0 {
+            sweep += 2.0 * PI;
+        } else if !is_sweep && sweep > 0.0 {
+            sweep -= 2.0 * PI;
+        }
+
+        // the arc traces `center + axes (cos t, sin t)` in user units
+        let axes = matrix(cos * rx, sin * rx, -sin * ry, cos * ry, 0.0, 0.0);
+        let world_axes = axes.then(&self.transform);
+        let (major_axis, ratio) = principal_axes(&world_axes);
+        if 1.0 - ratio < CIRCLE_TOLERANCE {
+            // arcs are counter-clockwise in the drawing when the sweep agrees with the transform
+            let included = sweep * world_axes.determinant().signum();
+            let end = self.vertex(p);
+            let vertices = self.polyline();
+            if let Some(last) = vertices.last_mut() {
+                last.bulge = (included / 4.0).tan();
+            }
+            vertices.push(end);
+        } else {
+            self.flush(false);
+            let center = self.point(center);
+            let start = self.point((x1, y1));
+            let end = self.point(p);
+            let (start, end) = if sweep * world_axes.determinant() > 0.0 {
+                (start, end)
+            } else {
+                (end, start)
+            };
+            self.entities.push(EntityType::Ellipse(Ellipse {
+                start_parameter: ellipse_parameter(&center, &major_axis, ratio, &start),
+                end_parameter: ellipse_parameter(&center, &major_axis, ratio, &end),
+                center,
+                major_axis,
+                minor_axis_ratio: ratio,
+                ..Default::default()
+            }));
+            self.is_split = true;
+        }
+        self.current = p;
+    }
+    /// Adds a full circle or ellipse centered at `center`.
+    fn full_ellipse(&mut self, center: (f64, f64), rx: f64, ry: f64) {
+        self.flush(false);
+        let axes = matrix(rx, 0.0, 0.0, ry, 0.0, 0.0);
+        let (major_axis, ratio) = principal_axes(&axes.then(&self.transform));
+        let center = self.point(center);
+        let specific = if 1.0 - ratio < CIRCLE_TOLERANCE {
+            EntityType::Circle(Circle::new(center, vector_length(&major_axis)))
+        } else {
+            EntityType::Ellipse(Ellipse {
+                center,
+                major_axis,
+                minor_axis_ratio: ratio,
+                start_parameter: 0.0,
+                end_parameter: 2.0 * PI,
+                ..Default::default()
+            })
+        };
+        self.entities.push(specific);
+    }
+    fn close(&mut self) {
+        match self.run {
+            // a subpath of lines and circular arcs is a single closed polyline
+            Run::Polyline(ref mut vertices) if !self.is_split => {
+                let first = &vertices[0];
+                let last = &vertices[vertices.len() - 1];
+                if vertices.len() > 1 && first.x == last.x && first.y == last.y {
+                    vertices.pop();
+                }
+                self.flush(true);
+            }
+            _ => {
+                if self.current != self.start {
+                    self.line_to(self.start);
+                }
+                self.flush(false);
+            }
+        }
+        self.is_split = false;
+        self.current = self.start;
+    }
+    fn vertex(&self, p: (f64, f64)) -> LwPolylineVertex {
+        let p = self.point(p);
+        LwPolylineVertex {
+            x: p.x,
+            y: p.y,
+            ..Default::default()
+        }
+    }
+    /// Returns the vertices of the current polyline, starting one at the current point if needed.
+    fn polyline(&mut self) -> &mut Vec<LwPolylineVertex> {
+        if !matches!(self.run, Run::Polyline(_)) {
+            self.flush(false);
+            self.run = Run::Polyline(vec![self.vertex(self.current)]);
+        }
+        match self.run {
+            Run::Polyline(ref mut vertices) => vertices,
+            _ => unreachable!(),
+        }
+    }
+    /// Returns the control points of the current spline, starting one at the current point if
+    /// needed.
+    fn spline(&mut self, degree: i32) -> &mut Vec<Point> {
+        if !matches!(self.run, Run::Spline(d, _) if d == degree) {
+            self.flush(false);
+            self.run = Run::Spline(degree, vec![self.point(self.current)]);
+        }
+        match self.run {
+            Run::Spline(_, ref mut points) => points,
+            _ => unreachable!(),
+        }
+    }
+    fn flush(&mut self, is_closed: bool) {
+        let specific = match std::mem::replace(&mut self.run, Run::None) {
+            Run::Polyline(vertices)
+                if vertices.len() == 2 && !is_closed && vertices[0].bulge == 0.0 =>
+            {
+                EntityType::Line(Line::new(
+                    Point::new(vertices[0].x, vertices[0].y, 0.0),
+                    Point::new(vertices[1].x, vertices[1].y, 0.0),
+                ))
+            }
+            Run::Polyline(vertices) if vertices.len() >= 2 => {
+                let mut poly = LwPolyline {
+                    vertices,
+                    ..Default::default()
+                };
+                poly.set_is_closed(is_closed);
+                EntityType::LwPolyline(poly)
+            }
+            Run::Spline(degree, control_points) => {
+                // each Bézier segment is a knot span with full multiplicity interior knots
+                let segments = (control_points.len() - 1) / degree as usize;
+                let degree_knots = degree as usize;
+                let mut knot_values = vec![0.0; degree_knots + 1];
+                for i in 1..segments {
+                    knot_values.extend(std::iter::repeat_n(i as f64, degree_knots));
+                }
+                knot_values.extend(std::iter::repeat_n(segments as f64, degree_knots + 1));
+                let mut spline = Spline {
+                    degree_of_curve: degree,
+                    knot_values,
+                    control_points,
+                    ..Default::default()
+                };
+                spline.set_is_planar(true);
+                EntityType::Spline(spline)
+            }
+            _ => return,
+        };
+        self.entities.push(specific);
+        self.is_split = true;
+    }
+}
+
+/// Returns the major axis and minor to major axis ratio of the ellipse traced by the linear part
+/// of `m` applied to the unit circle.
+fn principal_axes(m: &Placement) -> (Vector, f64) {
+    // the axes are the eigenvectors of m * transpose(m)
+    let u = m.apply_vector(&Vector::x_axis());
+    let v = m.apply_vector(&Vector::y_axis());
+    let a = u.x * u.x + v.x * v.x;
+    let b = u.x * u.y + v.x * v.y;
+    let d = u.y * u.y + v.y * v.y;
+    let half_difference = (a - d) / 2.0;
+    let h = (half_difference * half_difference + b * b).sqrt();
+    let major = ((a + d) / 2.0 + h).sqrt();
+    let minor = ((a + d) / 2.0 - h).max(0.0).sqrt();
+    let angle = (2.0 * b).atan2(a - d) / 2.0;
+    (
+        Vector::new(major * angle.cos(), major * angle.sin(), 0.0),
+        if major > 0.0 { minor / major } else { 1.0 },
+    )
+}
+
+/// Returns the parameter of the point on an ellipse.
+fn ellipse_parameter(center: &Point, major_axis: &Vector, ratio: f64, p: &Point) -> f64 {
+    let dx = p.x - center.x;
+    let dy = p.y - center.y;
+    let length_squared = major_axis.x * major_axis.x + major_axis.y * major_axis.y;
+    let along_major = (dx * major_axis.x + dy * major_axis.y) / length_squared;
+    let along_minor = (-dx * major_axis.y + dy * major_axis.x) / (length_squared * ratio);
+    let parameter = along_minor.atan2(along_major);
+    if parameter < 0.0 {
+        parameter + 2.0 * PI
+    } else {
+        parameter
+    }
+}
+
+/// Returns the transform mapping `(x, y)` to `(a x + c y + e, b x + d y + f)`, as in SVG's
+/// `matrix()`.
+fn matrix(a: f64, b: f64, c: f64, d: f64, e: f64, f: f64) -> Placement {
+    Placement::from_columns(
+        &Vector::new(a, b, 0.0),
+        &Vector::new(c, d, 0.0),
+        &Vector::z_axis(),
+        &Point::new(e, f, 0.0),
+    )
+}
+
+fn translate(x: f64, y: f64) -> Placement {
+    matrix(1.0, 0.0, 0.0, 1.0, x, y)
+}
+
+/// Parses a `transform` attribute such as `translate(10 20) rotate(45)`.
+fn parse_transform(value: &str) -> Placement {
+    let mut result = Placement::identity();
+    let mut rest = value;
+    while let Some(open) = rest.find('(') {
+        let name = rest[..open].trim_matches(|c: char| c.is_whitespace() || c == ',');
+        let close = match rest[open..].find(')') {
+            Some(close) => open + close,
+            None => break,
+        };
+        let mut arguments = PathData::new(&rest[open + 1..close]);
+        let mut values = vec![];
+        while let Some([value]) = arguments.numbers() {
+            values.push(value);
+        }
+        let argument = |i: usize, default: f64| values.get(i).copied().unwrap_or(default);
+        let transform = match (name, values.len()) {
+            ("matrix", 6) => matrix(
+                values[0], values[1], values[2], values[3], values[4], values[5],
+            ),
+            ("translate", 1 | 2) => translate(values[0], argument(1, 0.0)),
+            ("scale", 1 | 2) => matrix(values[0], 0.0, 0.0, argument(1, values[0]), 0.0, 0.0),
+            ("rotate", 1 | 3) => {
+                let (sin, cos) = values[0].to_radians().sin_cos();
+                let (cx, cy) = (argument(1, 0.0), argument(2, 0.0));
+                translate(-cx, -cy)
+                    .then(&matrix(cos, sin, -sin, cos, 0.0, 0.0))
+                    .then(&translate(cx, cy))
+            }
+            ("skewX", 1) => matrix(1.0, 0.0, values[0].to_radians().tan(), 1.0, 0.0, 0.0),
+            ("skewY", 1) => matrix(1.0, values[0].to_radians().tan(), 0.0, 1.0, 0.0, 0.0),
+            // invalid transforms disable the whole attribute
+            _ => return Placement::identity(),
+        };
+        result = transform.then(&result);
+        rest = &rest[close + 1..];
+    }
+    result
+}
+
+/// Reads the numbers, flags and commands of path data and similar attributes.
+struct PathData<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> PathData<'a> {
+    fn new(value: &'a str) -> Self {
+        PathData {
+            bytes: value.as_bytes(),
+            position: 0,
+        }
+    }
+    fn peek(&self) -> Option<u8> {
+        self.bytes.get(self.position).copied()
+    }
+    fn skip_separators(&mut self) {
+        while matches!(self.peek(), Some(b) if b.is_ascii_whitespace() || b == b',') {
+            self.position += 1;
+        }
+    }
+    fn command(&mut self) -> Option<u8> {
+        self.skip_separators();
+        match self.peek() {
+            Some(b) if b.is_ascii_alphabetic() => {
+                self.position += 1;
+                Some(b)
+            }
+            _ => None,
+        }
+    }
+    fn has_number(&mut self) -> bool {
+        self.skip_separators();
+        matches!(self.peek(), Some(b) if b.is_ascii_digit() || b == b'-' || b == b'+' || b == b'.')
+    }
+    fn number(&mut self) -> Option<f64> {
+        self.skip_separators();
+        let start = self.position;
+        if matches!(self.peek(), Some(b'-' | b'+')) {
+            self.position += 1;
+        }
+        let mut has_point = false;
+        while let Some(b) = self.peek() {
+            if b == b'.' && !has_point {
+                has_point = true;
+            } else if !b.is_ascii_digit() {
+                break;
+            }
+            self.position += 1;
+        }
+        if matches!(self.peek(), Some(b'e' | b'E')) {
+            let mantissa_end = self.position;
+            self.position += 1;
+            if matches!(self.peek(), Some(b'-' | b'+')) {
+                self.position += 1;
+            }
+            if matches!(self.peek(), Some(b) if b.is_ascii_digit()) {
+                while matches!(self.peek(), Some(b) if b.is_ascii_digit()) {
+                    self.position += 1;
+                }
+            } else {
+                self.position = mantissa_end;
+            }
+        }
+        let text = std::str::from_utf8(&self.bytes[start..self.position]).ok()?;
+        match text.parse() {
+            Ok(value) => Some(value),
+            Err(_) => {
+                self.position = start;
+                None
+            }
+        }
+    }
+    /// Reads `N` numbers, or returns `None` if there aren't enough.
+    fn numbers<const N: usize>(&mut self) -> Option<[f64; N]> {
+        let mut result = [0.0; N];
+        for value in &mut result {
+            *value = self.number()?;
+        }
+        Some(result)
+    }
+    /// Reads an arc flag, which may be written without a following separator.
+    fn flag(&mut self) -> Option<bool> {
+        self.skip_separators();
+        let flag = match self.peek()? {
+            b'0' => false,
+            b'1' => true,
+            _ => return None,
+        };
+        self.position += 1;
+        Some(flag)
+    }
+}
+
+/// Returns the value of a presentation property, preferring the `style` attribute.
+fn property<'e>(element: &'e Element, name: &str) -> Option<&'e str> {
+    let from_style = element.attributes.get("style").and_then(|style| {
+        style.split(';').rev().find_map(|declaration| {
+            let (key, value) = declaration.split_once(':')?;
+            (key.trim() == name).then(|| value.trim())
+        })
+    });
+    from_style.or_else(|| element.attributes.get(name).map(|v| v.trim()))
+}
+
+/// Returns a length attribute in user units, or 0 if it's missing.
+fn length(element: &Element, name: &str) -> f64 {
+    element
+        .attributes
+        .get(name)
+        .and_then(|value| parse_length(value))
+        .unwrap_or(0.0)
+}
+
+/// Parses a length in user units; percentages and font relative lengths aren't supported.
+fn parse_length(value: &str) -> Option<f64> {
+    let value = value.trim();
+    let unit_start = value
+        .rfind(|c: char| c.is_ascii_digit() || c == '.')
+        .map_or(0, |i| i + 1);
+    let (number, unit) = value.split_at(unit_start);
+    let pixels_per_unit = match unit.trim() {
+        "" | "px" => 1.0,
+        "in" => PIXELS_PER_INCH,
+        "cm" => PIXELS_PER_INCH / 2.54,
+        "mm" => PIXELS_PER_INCH / 25.4,
+        "pt" => PIXELS_PER_INCH / 72.0,
+        "pc" => PIXELS_PER_INCH / 6.0,
+        _ => return None,
+    };
+    number
+        .trim()
+        .parse::<f64>()
+        .ok()
+        .map(|n| n * pixels_per_unit)
+}
+
+/// Parses a paint value into a 24-bit color, or `None` if it isn't a plain color.
+fn parse_color(value: &str) -> Option<i32> {
+    let value = value.trim();
+    if let Some(hex) = value.strip_prefix('#') {
+        let digits = i32::from_str_radix(hex, 16).ok()?;
+        return match hex.len() {
+            3 => {
+                let (r, g, b) = (digits >> 8 & 0xF, digits >> 4 & 0xF, digits & 0xF);
+                Some(((r * 0x11) << 16) | ((g * 0x11) << 8) | (b * 0x11))
+            }
+            6 => Some(digits),
+            _ => None,
+        };
+    }
+    if let Some(arguments) = value.strip_prefix("rgb(").and_then(|v| v.strip_suffix(')')) {
+        let mut components = arguments.split(',').map(|c| {
+            let c = c.trim();
+            let value = match c.strip_suffix('%') {
+                Some(percent) => percent.trim().parse::<f64>().ok()? * 2.55,
+                None => c.parse::<f64>().ok()?,
+            };
+            Some(value.round().clamp(0.0, 255.0) as i32)
+        });
+        let (r, g, b) = (
+            components.next()??,
+            components.next()??,
+            components.next()??,
+        );
+        return Some(r << 16 | g << 8 | b);
+    }
+    let rgb = match value.to_ascii_lowercase().as_str() {
+        "black" => 0x000000,
+        "white" => 0xFFFFFF,
+        "red" => 0xFF0000,
+        "lime" => 0x00FF00,
+        "green" => 0x008000,
+        "blue" => 0x0000FF,
+        "yellow" => 0xFFFF00,
+        "cyan" | "aqua" => 0x00FFFF,
+        "magenta" | "fuchsia" => 0xFF00FF,
+        "gray" | "grey" => 0x808080,
+        "silver" => 0xC0C0C0,
+        "maroon" => 0x800000,
+        "olive" => 0x808000,
+        "navy" => 0x000080,
+        "purple" => 0x800080,
+        "teal" => 0x008080,
+        "orange" => 0xFFA500,
+        _ => return None,
+    };
+    Some(rgb)
+}
+
+/// Converts a group id to a layer name, replacing characters that aren't allowed.
+fn layer_name(id: &str) -> Option<String> {
+    let name = id
+        .trim()
+        .chars()
+        .map(|c| match c {
+            '<' | '>' | '/' | '\\' | '"' | ':' | ';' | '?' | '*' | '|' | '=' | ',' | '`' => '_',
+            c => c,
+        })
+        .collect::<String>();
+    (!name.is_empty()).then_some(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::svg_reader::*;
+    use crate::DxfError;
+    use float_cmp::approx_eq;
+
+    fn load(svg: &str) -> Drawing {
+        let options = SvgImportOptions { scale: Some(1.0) };
+        Drawing::load_svg(&mut svg.as_bytes(), &options).unwrap()
+    }
+
+    fn specifics(drawing: &Drawing) -> Vec<&EntityType> {
+        drawing.entities().map(|e| &e.specific).collect()
+    }
+
+    fn assert_point(expected: (f64, f64), actual: &Point) {
+        assert!(
+            approx_eq!(f64, expected.0, actual.x, epsilon = 1e-9)
+                && approx_eq!(f64, expected.1, actual.y, epsilon = 1e-9),
+            "expected {expected:?}, got ({}, {})",
+            actual.x,
+            actual.y
+        );
+    }
+
+    fn assert_vertices(expected: &[(f64, f64, f64)], poly: &LwPolyline) {
+        assert_eq!(expected.len(), poly.vertices.len());
+        for (e, v) in expected.iter().zip(&poly.vertices) {
+            assert_point((e.0, e.1), &Point::new(v.x, v.y, 0.0));
+            assert!(approx_eq!(f64, e.2, v.bulge, epsilon = 1e-9));
+        }
+    }
+
+    #[test]
+    fn basic_shapes() {
+        let drawing = load(
+            r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 100 100">
+                <line x1="0" y1="0" x2="10" y2="0"/>
+                <polyline points="0,10 10,10 10,20"/>
+                <polygon points="0 30 10 30 10 40"/>
+                <rect x="20" y="20" width="10" height="5"/>
+            </svg>"#,
+        );
+        let entities = specifics(&drawing);
+        assert_eq!(4, entities.len());
+        match entities[0] {
+            EntityType::Line(line) => {
+                // y is flipped so the bottom of the view box is at the origin
+                assert_point((0.0, 100.0), &line.p1);
+                assert_point((10.0, 100.0), &line.p2);
+            }
+            _ => panic!("expected a line"),
+        }
+        match entities[1] {
+            EntityType::LwPolyline(poly) => {
+                assert!(!poly.is_closed());
+                assert_vertices(
+                    &[(0.0, 90.0, 0.0), (10.0, 90.0, 0.0), (10.0, 80.0, 0.0)],
+                    poly,
+                );
+            }
+            _ => panic!("expected a polyline"),
+        }
+        match entities[2] {
+            EntityType::LwPolyline(poly) => {
+                assert!(poly.is_closed());
+                assert_eq!(3, poly.vertices.len());
+            }
+            _ => panic!("expected a polyline"),
+        }
+        match entities[3] {
+            EntityType::LwPolyline(poly) => {
+                assert!(poly.is_closed());
+                assert_vertices(
+                    &[
+                        (20.0, 80.0, 0.0),
+                        (30.0, 80.0, 0.0),
+                        (30.0, 75.0, 0.0),
+                        (20.0, 75.0, 0.0),
+                    ],
+                    poly,
+                );
+            }
+            _ => panic!("expected a polyline"),
+        }
+    }
+
+    #[test]
+    fn rounded_rects_have_bulges() {
+        let drawing = load(r#"<svg><rect width="10" height="10" rx="2"/></svg>"#);
+        match specifics(&drawing)[0] {
+            EntityType::LwPolyline(poly) => {
+                assert!(poly.is_closed());
+                assert_eq!(8, poly.vertices.len());
+                // the corners are clockwise quarter circles
+                let quarter = -(PI / 8.0).tan();
+                let bulges = poly.vertices.iter().map(|v| v.bulge).collect::<Vec<_>>();
+                for (i, bulge) in bulges.iter().enumerate() {
+                    let expected = if i % 2 == 1 { quarter } else { 0.0 };
+                    assert!(approx_eq!(f64, expected, *bulge, epsilon = 1e-9));
+                }
+            }
+            _ => panic!("expected a polyline"),
+        }
+    }
+
+    #[test]
+    fn circles_and_ellipses() {
+        let drawing = load(
+            r#"<svg>
+                <circle cx="5" cy="5" r="2"/>
+                <ellipse cx="5" cy="5" rx="4" ry="2"/>
+                <circle cx="0" cy="0" r="1" transform="scale(2 1)"/>
+            </svg>"#,
+        );
+        let entities = specifics(&drawing);
+        match entities[0] {
+            EntityType::Circle(circle) => {
+                assert_point((5.0, -5.0), &circle.center);
+                assert!(approx_eq!(f64, 2.0, circle.radius));
+            }
+            _ => panic!("expected a circle"),
+        }
+        match entities[1] {
+            EntityType::Ellipse(ellipse) => {
+                assert_point((5.0, -5.0), &ellipse.center);
+                assert!(approx_eq!(
+                    f64,
+                    4.0,
+                    ellipse.major_axis.x.abs(),
+                    epsilon = 1e-9
+                ));
+                assert!(approx_eq!(
+                    f64,
+                    0.5,
+                    ellipse.minor_axis_ratio,
+                    epsilon = 1e-9
+                ));
+                assert!(ellipse.is_closed());
+            }
+            _ => panic!("expected an ellipse"),
+        }
+        match entities[2] {
+            EntityType::Ellipse(ellipse) => {
+                assert!(approx_eq!(
+                    f64,
+                    2.0,
+                    ellipse.major_axis.x.abs(),
+                    epsilon = 1e-9
+                ));
+                assert!(approx_eq!(
+                    f64,
+                    0.5,
+                    ellipse.minor_axis_ratio,
+                    epsilon = 1e-9
+                ));
+            }
+            _ => panic!("expected an ellipse"),
+        }
+    }
+
+    #[test]
+    fn path_lines_and_arcs() {
+        // a half circle closed by its diameter, using relative commands and compact numbers
+        let drawing = load(r#"<svg><path d="M0,0h10a5 5 0 00-10 0z M20 0L30-5.5.5 0"/></svg>"#);
+        let entities = specifics(&drawing);
+        assert_eq!(2, entities.len());
+        match entities[0] {
+            EntityType::LwPolyline(poly) => {
+                assert!(poly.is_closed());
+                // flipping y keeps the on-screen direction of the arc
+                assert_vertices(&[(0.0, 0.0, 0.0), (10.0, 0.0, 1.0)], poly);
+            }
+            _ => panic!("expected a polyline"),
+        }
+        match entities[1] {
+            EntityType::LwPolyline(poly) => {
+                assert_vertices(&[(20.0, 0.0, 0.0), (30.0, 5.5, 0.0), (0.5, 0.0, 0.0)], poly);
+            }
+            _ => panic!("expected a polyline"),
+        }
+    }
+
+    #[test]
+    fn elliptical_arcs_are_ellipses() {
+        let drawing = load(r#"<svg><path d="M0 0 A10 5 0 0 1 20 0"/></svg>"#);
+        match specifics(&drawing)[0] {
+            EntityType::Ellipse(ellipse) => {
+                assert_point((10.0, 0.0), &ellipse.center);
+                assert!(approx_eq!(
+                    f64,
+                    0.5,
+                    ellipse.minor_axis_ratio,
+                    epsilon = 1e-9
+                ));
+                // the arc passes through the top of the drawing's ellipse
+                let middle = ellipse
+                    .point_at_parameter((ellipse.start_parameter + ellipse.end_parameter) / 2.0);
+                assert_point((10.0, 5.0), &middle);
+            }
+            _ => panic!("expected an ellipse"),
+        }
+    }
+
+    #[test]
+    fn beziers_are_splines() {
+        let drawing = load(
+            r#"<svg><path d="M0 0 C0 10 10 10 10 0 S20 -10 20 0 Q25 5 30 0 T40 0 L50 0"/></svg>"#,
+        );
+        let entities = specifics(&drawing);
+        assert_eq!(3, entities.len());
+        match entities[0] {
+            EntityType::Spline(spline) => {
+                assert_eq!(3, spline.degree_of_curve);
+                assert_eq!(
+                    vec![0.0, 0.0, 0.0, 0.0, 1.0, 1.0, 1.0, 2.0, 2.0, 2.0, 2.0],
+                    spline.knot_values
+                );
+                assert_eq!(7, spline.control_points.len());
+                // the smooth segment reflects the previous control point
+                assert_point((10.0, 10.0), &spline.control_points[4]);
+            }
+            _ => panic!("expected a spline"),
+        }
+        match entities[1] {
+            EntityType::Spline(spline) => {
+                assert_eq!(2, spline.degree_of_curve);
+                assert_eq!(5, spline.control_points.len());
+                assert_point((35.0, 5.0), &spline.control_points[3]);
+            }
+            _ => panic!("expected a spline"),
+        }
+        match entities[2] {
+            EntityType::Line(line) => {
+                assert_point((40.0, 0.0), &line.p1);
+                assert_point((50.0, 0.0), &line.p2);
+            }
+            _ => panic!("expected a line"),
+        }
+    }
+
+    #[test]
+    fn groups_are_layers_with_transforms() {
+        let drawing = load(
+            r##"<svg>
+                <line x2="1"/>
+                <g id="cut" transform="translate(10 0)" stroke="#ff0000">
+                    <g transform="rotate(90)">
+                        <line x2="1"/>
+                    </g>
+                    <g id="engrave" style="stroke: blue">
+                        <line x2="1"/>
+                    </g>
+                </g>
+                <defs><line x2="1"/></defs>
+            </svg>"##,
+        );
+        let entities = drawing.entities().collect::<Vec<_>>();
+        assert_eq!(3, entities.len());
+        assert_eq!("0", entities[0].common.layer);
+        assert_eq!("cut", entities[1].common.layer);
+        assert_eq!(0xFF0000, entities[1].common.color_24_bit);
+        match entities[1].specific {
+            EntityType::Line(ref line) => {
+                assert_point((10.0, 0.0), &line.p1);
+                assert_point((10.0, -1.0), &line.p2);
+            }
+            _ => panic!("expected a line"),
+        }
+        assert_eq!("engrave", entities[2].common.layer);
+        assert_eq!(0x0000FF, entities[2].common.color_24_bit);
+        assert!(drawing.layers().any(|l| l.name == "cut"));
+        assert!(drawing.layers().any(|l| l.name == "engrave"));
+    }
+
+    #[test]
+    fn physical_units_become_millimeters() {
+        let svg = r#"<svg width="100mm" height="50mm" viewBox="0 0 400 200">
+            <line x1="0" y1="200" x2="400" y2="200"/>
+        </svg>"#;
+        let drawing = Drawing::load_svg(&mut svg.as_bytes(), &SvgImportOptions::default()).unwrap();
+        assert_eq!(Units::Millimeters, drawing.header.default_drawing_units);
+        match specifics(&drawing)[0] {
+            EntityType::Line(line) => {
+                assert_point((0.0, 0.0), &line.p1);
+                assert_point((100.0, 0.0), &line.p2);
+            }
+            _ => panic!("expected a line"),
+        }
+
+        // user units are 96 pixels per inch
+        let svg = r#"<svg><line x2="96"/></svg>"#;
+        let drawing = Drawing::load_svg(&mut svg.as_bytes(), &SvgImportOptions::default()).unwrap();
+        match specifics(&drawing)[0] {
+            EntityType::Line(line) => assert_point((25.4, 0.0), &line.p2),
+            _ => panic!("expected a line"),
+        }
+    }
+
+    #[test]
+    fn malformed_xml() {
+        let result = Drawing::load_svg(&mut "<svg>".as_bytes(), &SvgImportOptions::default());
+        assert!(matches!(result, Err(DxfError::XmlError(_))));
+    }
+
+    #[test]
+    fn parse_colors() {
+        assert_eq!(Some(0x112233), parse_color("#123"));
+        assert_eq!(Some(0xABCDEF), parse_color("#abcdef"));
+        assert_eq!(Some(0x0A141E), parse_color("rgb(10, 20, 30)"));
+        assert_eq!(Some(0xFF0000), parse_color("rgb(100%,0%,0%)"));
+        assert_eq!(Some(0x0000FF), parse_color("Blue"));
+        assert_eq!(None, parse_color("none"));
+        assert_eq!(None, parse_color("url(#gradient)"));
+    }
+}
@@ -0,0 +1,1212 @@
+// renders a drawing as SVG
+
+use std::collections::HashSet;
+use std::f64::consts::PI;
+use std::io::Write;
+
+use crate::entities::*;
+use crate::enums::*;
+use crate::geometry::*;
+use crate::mtext_layout::ApproximateTextMetrics;
+use crate::render::*;
+use crate::{Drawing, DxfResult, Point, Vector};
+
+/// The number of points sampled along each arc when computing the rendered extents.
+const ARC_BOUNDS_SAMPLES: usize = 16;
+
+/// The number of segments used to approximate curves that don't lie in the XY plane.
+const CURVE_SEGMENTS: usize = 64;
+
+/// The ratio of a font's cap height to its em size; CAD text heights measure the cap height.
+const CAP_HEIGHT_RATIO: f64 = 0.7;
+
+/// The number of decimal places written for coordinates and lengths.
+const DECIMAL_PLACES: i32 = 6;
+
+/// Options used when rendering a `Drawing` as SVG.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SvgOptions {
+    /// The name of the `Layout` to render, or `None` to render model space.
+    pub layout_name: Option<String>,
+    /// The space added around the rendered content, in drawing units.
+    pub margin: f64,
+    /// The number of drawing units per millimeter, used to size line weights.  When `None` this is
+    /// derived from `$INSUNITS`, treating unitless drawings as millimeters.
+    pub units_per_millimeter: Option<f64>,
+    /// The line weight, in millimeters, of entities that use the default line weight.
+    pub default_line_weight: f64,
+    /// The color used for color index 7, which is drawn as either black or white depending on
+    /// the background.
+    pub foreground_color: (u8, u8, u8),
+}
+
+impl Default for SvgOptions {
+    fn default() -> Self {
+        SvgOptions {
+            layout_name: None,
+            margin: 0.0,
+            units_per_millimeter: None,
+            default_line_weight: 0.25,
+            foreground_color: (0, 0, 0),
+        }
+    }
+}
+
+pub(crate) struct SvgWriter<'a> {
+    drawing: &'a Drawing,
+    options: &'a SvgOptions,
+    styles: StyleResolver<'a>,
+    units_per_millimeter: f64,
+    used_ids: HashSet<String>,
+    /// Rendered blocks keyed by their upper-cased name, in the order they were first referenced.
+    symbols: Vec<(String, Symbol)>,
+}
+
+struct Symbol {
+    id: String,
+    name: String,
+    content: String,
+    bounds: Bounds,
+    base_point: Point,
+}
+
+impl<'a> SvgWriter<'a> {
+    pub fn new(drawing: &'a Drawing, options: &'a SvgOptions) -> Self {
+        SvgWriter {
+            drawing,
+            options,
+            styles: StyleResolver {
+                drawing,
+                default_line_weight: options.default_line_weight,
+                foreground_color: options.foreground_color,
+            },
+            units_per_millimeter: units_per_millimeter(drawing, options.units_per_millimeter),
+            used_ids: HashSet::new(),
+            symbols: vec![],
+        }
+    }
+    pub fn write<T>(&mut self, writer: &mut T) -> DxfResult<()>
+    where
+        T: Write + ?Sized,
+    {
+        let entities = layout_entities(self.drawing, self.options.layout_name.as_deref())?;
+
+        // every layer gets a group, followed by any layers that aren't in the table
+        let mut groups = self
+            .drawing
+            .layers()
+            .map(|l| (l.name.clone(), vec![]))
+            .collect::<Vec<(String, Vec<&Entity>)>>();
+        for entity in entities {
+            match groups
+                .iter_mut()
+                .find(|(name, _)| name.eq_ignore_ascii_case(&entity.common.layer))
+            {
+                Some((_, group)) => group.push(entity),
+                None => groups.push((entity.common.layer.clone(), vec![entity])),
+            }
+        }
+
+        let mut body = String::new();
+        let mut bounds = Bounds::empty();
+        for (name, entities) in groups {
+            let mut content = String::new();
+            let mut group_bounds = Bounds::empty();
+            for entity in entities {
+                self.write_entity(&mut content, entity, false, &mut group_bounds);
+            }
+            let is_visible = self.styles.is_layer_on(&name);
+            if is_visible {
+                bounds.union(&group_bounds);
+            }
+            let id = self.unique_id("layer", &name);
+            body.push_str(&format!(
+                "  <g id=\"{}\" data-layer=\"{}\"{}>\n",
+                id,
+                escape(&name),
+                if is_visible { "" } else { " display=\"none\"" }
+            ));
+            body.push_str(&content);
+            body.push_str("  </g>\n");
+        }
+
+        let (min_x, min_y, width, height) = if bounds.is_empty() {
+            (0.0, 0.0, 1.0, 1.0)
+        } else {
+            let margin = self.options.margin;
+            (
+                bounds.min_x - margin,
+                bounds.min_y - margin,
+                bounds.max_x - bounds.min_x + 2.0 * margin,
+                bounds.max_y - bounds.min_y + 2.0 * margin,
+            )
+        };
+
+        writer.write_all(b"<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n")?;
+        writeln!(
+            writer,
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" xmlns:xlink=\"http://www.w3.org/1999/xlink\" viewBox=\"{} {} {} {}\" color=\"{}\" fill=\"none\" stroke=\"currentColor\" stroke-width=\"{}\" stroke-linecap=\"round\" stroke-linejoin=\"round\" font-family=\"sans-serif\">",
+            num(min_x, DECIMAL_PLACES),
+            // the y axis points down in SVG
+            num(-(min_y + height), DECIMAL_PLACES),
+            num(width, DECIMAL_PLACES),
+            num(height, DECIMAL_PLACES),
+            hex_color(self.options.foreground_color),
+            num(self.options.default_line_weight * self.units_per_millimeter, DECIMAL_PLACES),
+        )?;
+        if !self.symbols.is_empty() {
+            writer.write_all(b"  <defs>\n")?;
+            for (_, symbol) in &self.symbols {
+                writeln!(
+                    writer,
+                    "    <symbol id=\"{}\" data-block=\"{}\" overflow=\"visible\">",
+                    symbol.id,
+                    escape(&symbol.name)
+                )?;
+                writer.write_all(symbol.content.as_bytes())?;
+                writer.write_all(b"    </symbol>\n")?;
+            }
+            writer.write_all(b"  </defs>\n")?;
+        }
+        writer.write_all(body.as_bytes())?;
+        writer.write_all(b"</svg>\n")?;
+        Ok(())
+    }
+    fn unique_id(&mut self, prefix: &str, name: &str) -> String {
+        let sanitized = name
+            .chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                    c
+                } else {
+                    '_'
+                }
+            })
+            .collect::<String>();
+        let mut id = format!("{prefix}-{sanitized}");
+        let mut suffix = 1;
+        while self.used_ids.contains(&id) {
+            suffix += 1;
+            id = format!("{prefix}-{sanitized}-{suffix}");
+        }
+        self.used_ids.insert(id.clone());
+        id
+    }
+    /// Returns the rendered block with the specified name, rendering it first if necessary.
+    fn symbol(&mut self, name: &str) -> Option<(String, Bounds, Point)> {
+        let key = name.to_uppercase();
+        if let Some((_, symbol)) = self.symbols.iter().find(|(k, _)| *k == key) {
+            return Some((symbol.id.clone(), symbol.bounds, symbol.base_point.clone()));
+        }
+
+        let drawing = self.drawing;
+        let block = drawing
+            .blocks()
+            .find(|b| b.name.eq_ignore_ascii_case(name))?;
+        let id = self.unique_id("block", &block.name);
+
+        // register the symbol before rendering it so self-referencing blocks terminate
+        self.symbols.push((
+            key.clone(),
+            Symbol {
+                id: id.clone(),
+                name: block.name.clone(),
+                content: String::new(),
+                bounds: Bounds::empty(),
+                base_point: block.base_point.clone(),
+            },
+        ));
+        let mut content = String::new();
+        let mut bounds = Bounds::empty();
+        for entity in &block.entities {
+            self.write_entity(&mut content, entity, true, &mut bounds);
+        }
+        if let Some((_, symbol)) = self.symbols.iter_mut().find(|(k, _)| *k == key) {
+            symbol.content = content;
+            symbol.bounds = bounds;
+        }
+        Some((id, bounds, block.base_point.clone()))
+    }
+    fn write_entity(
+        &mut self,
+        out: &mut String,
+        entity: &Entity,
+        in_block: bool,
+        bounds: &mut Bounds,
+    ) {
+        if !entity.common.is_visible {
+            return;
+        }
+        if in_block && !self.styles.is_layer_on(&entity.common.layer) {
+            return;
+        }
+
+        let stroke = self.stroke_attributes(entity, in_block);
+        match entity.specific {
+            EntityType::Line(ref line) => {
+                bounds.add_point(&line.p1);
+                bounds.add_point(&line.p2);
+                out.push_str(&format!(
+                    "    <line x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\"{}/>\n",
+                    num(line.p1.x, DECIMAL_PLACES),
+                    num(-line.p1.y, DECIMAL_PLACES),
+                    num(line.p2.x, DECIMAL_PLACES),
+                    num(-line.p2.y, DECIMAL_PLACES),
+                    stroke
+                ));
+            }
+            EntityType::Circle(ref circle) => {
+                if is_planar(&circle.normal) {
+                    let center = ocs_to_wcs(&circle.center, &circle.normal);
+                    bounds.add(center.x - circle.radius, center.y - circle.radius);
+                    bounds.add(center.x + circle.radius, center.y + circle.radius);
+                    out.push_str(&format!(
+                        "    <circle cx=\"{}\" cy=\"{}\" r=\"{}\"{}/>\n",
+                        num(center.x, DECIMAL_PLACES),
+                        num(-center.y, DECIMAL_PLACES),
+                        num(circle.radius, DECIMAL_PLACES),
+                        stroke
+                    ));
+                } else {
+                    let points = (0..=CURVE_SEGMENTS)
+                        .map(|i| {
+                            let angle = 2.0 * PI * i as f64 / CURVE_SEGMENTS as f64;
+                            circle_point(&circle.center, circle.radius, angle, &circle.normal)
+                        })
+                        .collect::<Vec<_>>();
+                    write_path(out, &points_path(&points, true, bounds), &stroke);
+                }
+            }
+            EntityType::Arc(ref arc) => {
+                let start = arc.start_angle.to_radians();
+                let included = sweep_degrees(arc.start_angle, arc.end_angle).to_radians();
+                let point_at = |t: f64| {
+                    circle_point(&arc.center, arc.radius, start + included * t, &arc.normal)
+                };
+                let mut path = PathBuilder::default();
+                let first = point_at(0.0);
+                bounds.add_point(&first);
+                path.move_to(&first);
+                if is_planar(&arc.normal) {
+                    let is_counter_clockwise = arc.normal.z > 0.0;
+                    path.arc(
+                        arc.radius,
+                        arc.radius,
+                        0.0,
+                        included,
+                        is_counter_clockwise,
+                        point_at,
+                        bounds,
+                    );
+                } else {
+                    path.sampled(point_at, bounds);
+                }
+                write_path(out, &path.d, &stroke);
+            }
+            EntityType::Ellipse(ref ellipse) => {
+                let (start, end) = ellipse_parameter_range(ellipse);
+                let included = end - start;
+                let point_at = |t: f64| ellipse.point_at_parameter(start + included * t);
+                let mut path = PathBuilder::default();
+                let first = point_at(0.0);
+                bounds.add_point(&first);
+                path.move_to(&first);
+                if is_planar(&ellipse.normal) {
+                    let major = &ellipse.major_axis;
+                    let rx = (major.x * major.x + major.y * major.y).sqrt();
+                    let rotation = major.y.atan2(major.x).to_degrees();
+                    let is_counter_clockwise = ellipse.normal.z > 0.0;
+                    path.arc(
+                        rx,
+                        rx * ellipse.minor_axis_ratio,
+                        rotation,
+                        included,
+                        is_counter_clockwise,
+                        point_at,
+                        bounds,
+                    );
+                } else {
+                    path.sampled(point_at, bounds);
+                }
+                if ellipse.is_closed() {
+                    path.close();
+                }
+                write_path(out, &path.d, &stroke);
+            }
+            EntityType::LwPolyline(ref poly) => {
+                let vertices = poly
+                    .vertices
+                    .iter()
+                    .map(|v| (v.x, v.y, v.bulge))
+                    .collect::<Vec<_>>();
+                let path = bulged_path(
+                    &vertices,
+                    poly.is_closed(),
+                    entity.common.elevation,
+                    &poly.extrusion_direction,
+                    bounds,
+                );
+                write_path(out, &path, &stroke);
+            }
+            EntityType::Polyline(ref poly) => {
+                let path = polyline_path(poly, bounds);
+                write_path(out, &path, &stroke);
+            }
+            EntityType::Spline(ref spline) => {
+                let points = spline.tessellate(SPLINE_SEGMENTS_PER_SPAN);
+                write_path(
+                    out,
+                    &points_path(&points, spline.is_closed(), bounds),
+                    &stroke,
+                );
+            }
+            EntityType::Solid(ref solid) => {
+                let corners = [
+                    &solid.first_corner,
+                    &solid.second_corner,
+                    &solid.fourth_corner,
+                    &solid.third_corner,
+                ]
+                .iter()
+                .map(|p| ocs_to_wcs(p, &solid.extrusion_direction))
+                .collect::<Vec<_>>();
+                let fill = format!(" fill=\"{}\"", self.color(entity, in_block));
+                write_path(out, &points_path(&corners, true, bounds), &(stroke + &fill));
+            }
+            EntityType::Trace(ref trace) => {
+                let corners = [
+                    &trace.first_corner,
+                    &trace.second_corner,
+                    &trace.fourth_corner,
+                    &trace.third_corner,
+                ]
+                .iter()
+                .map(|p| ocs_to_wcs(p, &trace.extrusion_direction))
+                .collect::<Vec<_>>();
+                let fill = format!(" fill=\"{}\"", self.color(entity, in_block));
+                write_path(out, &points_path(&corners, true, bounds), &(stroke + &fill));
+            }
+            EntityType::Face3D(ref face) => {
+                let corners = dedupe_loop(&[
+                    face.first_corner.clone(),
+                    face.second_corner.clone(),
+                    face.third_corner.clone(),
+                    face.fourth_corner.clone(),
+                ]);
+                write_path(out, &points_path(&corners, true, bounds), &stroke);
+            }
+            EntityType::ModelPoint(ref point) => {
+                // a zero-length segment with round caps is drawn as a dot
+                bounds.add_point(&point.location);
+                out.push_str(&format!(
+                    "    <path d=\"M{} {}h0\"{}/>\n",
+                    num(point.location.x, DECIMAL_PLACES),
+                    num(-point.location.y, DECIMAL_PLACES),
+                    stroke
+                ));
+            }
+            EntityType::Text(ref text) => {
+                let placement = TextPlacement::from_text(text);
+                let font = self.style_font(&text.text_style_name);
+                let fill = self.color(entity, in_block);
+                write_text(out, &placement, &fill, font.as_deref(), bounds);
+            }
+            EntityType::Attribute(ref att) => {
+                let fill = self.color(entity, in_block);
+                self.write_attribute(out, att, &fill, bounds);
+            }
+            EntityType::MText(ref mtext) => {
+                let font = self.style_font(&mtext.text_style_name);
+                let entity_fill = self.color(entity, in_block);
+                for run in mtext.layout(&ApproximateTextMetrics) {
+                    let fill = match (run.color_24_bit, run.color) {
+                        (Some(rgb), _) => hex_color(rgb_components(rgb)),
+                        (None, Some(color)) if color.is_index() => {
+                            hex_color(self.styles.index_color(&color))
+                        }
+                        _ => entity_fill.clone(),
+                    };
+                    let placement = TextPlacement::from_text(&run.text);
+                    let font = run.font_name.clone().or_else(|| font.clone());
+                    write_text(out, &placement, &fill, font.as_deref(), bounds);
+                }
+            }
+            EntityType::Insert(ref insert) => {
+                self.write_insert(out, entity, insert, in_block, bounds)
+            }
+            EntityType::ProxyEntity(_) => {
+                for exploded in entity.explode_proxy(self.drawing).unwrap_or_default() {
+                    self.write_entity(out, &exploded, in_block, bounds);
+                }
+            }
+            EntityType::RotatedDimension(ref d) => {
+                self.write_dimension(out, entity, &d.dimension_base, in_block, bounds)
+            }
+            EntityType::RadialDimension(ref d) => {
+                self.write_dimension(out, entity, &d.dimension_base, in_block, bounds)
+            }
+            EntityType::DiameterDimension(ref d) => {
+                self.write_dimension(out, entity, &d.dimension_base, in_block, bounds)
+            }
+            EntityType::AngularThreePointDimension(ref d) => {
+                self.write_dimension(out, entity, &d.dimension_base, in_block, bounds)
+            }
+            EntityType::OrdinateDimension(ref d) => {
+                self.write_dimension(out, entity, &d.dimension_base, in_block, bounds)
+            }
+            _ => (),
+        }
+    }
+    fn write_attribute(&self, out: &mut String, att: &Attribute, fill: &str, bounds: &mut Bounds) {
+        if att.is_invisible() {
+            return;
+        }
+        let placement = TextPlacement::from_attribute(att);
+        let font = self.style_font(&att.text_style_name);
+        write_text(out, &placement, fill, font.as_deref(), bounds);
+    }
+    fn write_insert(
+        &mut self,
+        out: &mut String,
+        entity: &Entity,
+        insert: &Insert,
+        in_block: bool,
+        bounds: &mut Bounds,
+    ) {
+        if let Some((id, block_bounds, base_point)) = self.symbol(&insert.name) {
+            let attributes = self.use_attributes(entity, in_block);
+            for row in 0..insert.row_count.max(1) {
+                for column in 0..insert.column_count.max(1) {
+                    let placement = Placement::for_insert(insert, &base_point, row, column);
+                    if !block_bounds.is_empty() {
+                        for (bx, by) in block_bounds.corners() {
+                            let p = placement.apply(&Point::new(bx, by, 0.0));
+                            bounds.add(p.x, p.y);
+                        }
+                    }
+                    // the placement projected onto the XY plane, in SVG's flipped y coordinates
+                    let x_axis = placement.apply_vector(&Vector::x_axis());
+                    let y_axis = placement.apply_vector(&Vector::y_axis());
+                    let origin = placement.apply(&Point::origin());
+                    out.push_str(&format!(
+                        "    <use xlink:href=\"#{}\" transform=\"matrix({} {} {} {} {} {})\"{}/>\n",
+                        id,
+                        num(x_axis.x, DECIMAL_PLACES),
+                        num(-x_axis.y, DECIMAL_PLACES),
+                        num(-y_axis.x, DECIMAL_PLACES),
+                        num(y_axis.y, DECIMAL_PLACES),
+                        num(origin.x, DECIMAL_PLACES),
+                        num(-origin.y, DECIMAL_PLACES),
+                        attributes
+                    ));
+                }
+            }
+        }
+
+        let fill = self.color(entity, in_block);
+        for att in insert.attributes() {
+            self.write_attribute(out, att, &fill, bounds);
+        }
+    }
+    fn write_dimension(
+        &mut self,
+        out: &mut String,
+        entity: &Entity,
+        dimension: &DimensionBase,
+        in_block: bool,
+        bounds: &mut Bounds,
+    ) {
+        // the dimension's block contains its rendered geometry in world coordinates
+        if let Some((id, block_bounds, _)) = self.symbol(&dimension.block_name) {
+            bounds.union(&block_bounds);
+            out.push_str(&format!(
+                "    <use xlink:href=\"#{}\"{}/>\n",
+                id,
+                self.use_attributes(entity, in_block)
+            ));
+        }
+    }
+    /// Returns the resolved color of the entity, or `currentColor` if it's inherited from the
+    /// block reference.
+    fn color(&self, entity: &Entity, in_block: bool) -> String {
+        match self.styles.color(entity, in_block) {
+            Some(rgb) => hex_color(rgb),
+            None => String::from("currentColor"),
+        }
+    }
+    /// Returns the resolved line weight of the entity in drawing units, or `None` if it's
+    /// inherited from the block reference.
+    fn stroke_width(&self, entity: &Entity, in_block: bool) -> Option<f64> {
+        self.styles
+            .line_weight(entity, in_block)
+            .map(|weight| weight * self.units_per_millimeter)
+    }
+    /// Returns the resolved `stroke-dasharray` of the entity, or `None` if it's inherited from
+    /// the block reference.
+    fn dash_array(&self, entity: &Entity, in_block: bool) -> Option<String> {
+        let dashes = dash_array(&self.styles.line_type_pattern(entity, in_block)?);
+        if dashes.is_empty() {
+            Some(String::from("none"))
+        } else {
+            Some(
+                dashes
+                    .iter()
+                    .map(|d| num(*d, DECIMAL_PLACES))
+                    .collect::<Vec<_>>()
+                    .join(" "),
+            )
+        }
+    }
+    fn stroke_attributes(&self, entity: &Entity, in_block: bool) -> String {
+        let mut result = format!(" stroke=\"{}\"", self.color(entity, in_block));
+        if let Some(width) = self.stroke_width(entity, in_block) {
+            result.push_str(&format!(" stroke-width=\"{}\"", num(width, DECIMAL_PLACES)));
+        }
+        if let Some(dashes) = self.dash_array(entity, in_block) {
+            result.push_str(&format!(" stroke-dasharray=\"{dashes}\""));
+        }
+        result
+    }
+    /// Returns the attributes of a `<use>` element; the referenced symbol inherits these for its
+    /// BYBLOCK content.
+    fn use_attributes(&self, entity: &Entity, in_block: bool) -> String {
+        let mut result = String::new();
+        let color = self.color(entity, in_block);
+        if color != "currentColor" {
+            result.push_str(&format!(" color=\"{color}\""));
+        }
+        if let Some(width) = self.stroke_width(entity, in_block) {
+            result.push_str(&format!(" stroke-width=\"{}\"", num(width, DECIMAL_PLACES)));
+        }
+        if let Some(dashes) = self.dash_array(entity, in_block) {
+            result.push_str(&format!(" stroke-dasharray=\"{dashes}\""));
+        }
+        result
+    }
+    /// Returns the font family of a TrueType text style; shape fonts are drawn with the default
+    /// sans-serif font.
+    fn style_font(&self, style_name: &str) -> Option<String> {
+        let style = self
+            .drawing
+            .styles()
+            .find(|s| s.name.eq_ignore_ascii_case(style_name))?;
+        let file_name = style.primary_font_file_name.to_lowercase();
+        [".ttf", ".otf", ".ttc"].iter().find_map(|extension| {
+            file_name.ends_with(extension).then(|| {
+                style.primary_font_file_name[..file_name.len() - extension.len()].to_string()
+            })
+        })
+    }
+}
+
+/// Converts a line type pattern to an SVG dash array.  Positive lengths are dashes, negative
+/// lengths are spaces and zero lengths are dots.  Returns an empty array for continuous lines.
+fn dash_array(pattern: &[f64]) -> Vec<f64> {
+    // SVG dash arrays alternate between dashes and spaces, starting with a dash
+    let mut result: Vec<f64> = vec![];
+    for length in pattern {
+        let is_dash = *length >= 0.0;
+        let length = length.abs();
+        if result.len() % 2 == usize::from(is_dash) {
+            // same kind as the previous element
+            match result.last_mut() {
+                Some(last) => *last += length,
+                None => result.extend([0.0, length]),
+            }
+        } else {
+            result.push(length);
+        }
+    }
+    if result.len() % 2 == 1 {
+        result.push(0.0);
+    }
+    if result.iter().all(|l| *l == 0.0) || result.iter().skip(1).step_by(2).all(|l| *l == 0.0) {
+        // no visible spaces
+        return vec![];
+    }
+    result
+}
+
+/// Builds the `d` attribute of a `<path>`.  Points are in world coordinates.
+#[derive(Default)]
+struct PathBuilder {
+    d: String,
+}
+
+impl PathBuilder {
+    fn move_to(&mut self, p: &Point) {
+        self.d.push_str(&format!(
+            "M{} {}",
+            num(p.x, DECIMAL_PLACES),
+            num(-p.y, DECIMAL_PLACES)
+        ));
+    }
+    fn line_to(&mut self, p: &Point) {
+        self.d.push_str(&format!(
+            "L{} {}",
+            num(p.x, DECIMAL_PLACES),
+            num(-p.y, DECIMAL_PLACES)
+        ));
+    }
+    fn close(&mut self) {
+        self.d.push('Z');
+    }
+    /// Adds an arc from the current point through the points returned by `point_at` for
+    /// parameters in `[0, 1]`.  Arcs longer than a half turn are split so the large arc flag is
+    /// never needed.
+    #[allow(clippy::too_many_arguments)]
+    fn arc(
+        &mut self,
+        rx: f64,
+        ry: f64,
+        rotation: f64,
+        included_angle: f64,
+        is_counter_clockwise: bool,
+        point_at: impl Fn(f64) -> Point,
+        bounds: &mut Bounds,
+    ) {
+        for i in 1..=ARC_BOUNDS_SAMPLES {
+            bounds.add_point(&point_at(i as f64 / ARC_BOUNDS_SAMPLES as f64));
+        }
+        // counter-clockwise arcs in world coordinates are clockwise once the y axis is flipped
+        let sweep = if is_counter_clockwise { 0 } else { 1 };
+        let mut arc_to = |p: &Point| {
+            self.d.push_str(&format!(
+                "A{} {} {} 0 {} {} {}",
+                num(rx, DECIMAL_PLACES),
+                num(ry, DECIMAL_PLACES),
+                num(-rotation, DECIMAL_PLACES),
+                sweep,
+                num(p.x, DECIMAL_PLACES),
+                num(-p.y, DECIMAL_PLACES)
+            ));
+        };
+        if included_angle.abs() > PI {
+            arc_to(&point_at(0.5));
+        }
+        arc_to(&point_at(1.0));
+    }
+    /// Approximates a curve with straight segments through the points returned by `point_at` for
+    /// parameters in `[0, 1]`.
+    fn sampled(&mut self, point_at: impl Fn(f64) -> Point, bounds: &mut Bounds) {
+        for i in 1..=CURVE_SEGMENTS {
+            let p = point_at(i as f64 / CURVE_SEGMENTS as f64);
+            bounds.add_point(&p);
+            self.line_to(&p);
+        }
+    }
+}
+
+fn write_path(out: &mut String, d: &str, attributes: &str) {
+    if !d.is_empty() {
+        out.push_str(&format!("    <path d=\"{d}\"{attributes}/>\n"));
+    }
+}
+
+fn points_path(points: &[Point], is_closed: bool, bounds: &mut Bounds) -> String {
+    let mut path = PathBuilder::default();
+    for (i, p) in points.iter().enumerate() {
+        bounds.add_point(p);
+        if i == 0 {
+            path.move_to(p);
+        } else {
+            path.line_to(p);
+        }
+    }
+    if is_closed && points.len() > 2 {
+        path.close();
+    }
+    path.d
+}
+
+/// Builds a path through `(x, y, bulge)` vertices in the object coordinate system of `normal`.
+fn bulged_path(
+    vertices: &[(f64, f64, f64)],
+    is_closed: bool,
+    elevation: f64,
+    normal: &Vector,
+    bounds: &mut Bounds,
+) -> String {
+    let mut path = PathBuilder::default();
+    let world = |x: f64, y: f64| ocs_to_wcs(&Point::new(x, y, elevation), normal);
+    let first = match vertices.first() {
+        Some(&(x, y, _)) => world(x, y),
+        None => return path.d,
+    };
+    bounds.add_point(&first);
+    path.move_to(&first);
+
+    let segment_count = if is_closed {
+        vertices.len()
+    } else {
+        vertices.len() - 1
+    };
+    for i in 0..segment_count {
+        let (x1, y1, bulge) = vertices[i];
+        let (x2, y2, _) = vertices[(i + 1) % vertices.len()];
+        match bulge_to_arc(x1, y1, x2, y2, bulge) {
+            Some(arc) => {
+                let point_at = |t: f64| {
+                    let angle = arc.start_angle + arc.included_angle * t;
+                    world(
+                        arc.center_x + arc.radius * angle.cos(),
+                        arc.center_y + arc.radius * angle.sin(),
+                    )
+                };
+                if is_planar(normal) {
+                    let is_counter_clockwise = (arc.included_angle > 0.0) == (normal.z > 0.0);
+                    path.arc(
+                        arc.radius,
+                        arc.radius,
+                        0.0,
+                        arc.included_angle,
+                        is_counter_clockwise,
+                        point_at,
+                        bounds,
+                    );
+                } else {
+                    path.sampled(point_at, bounds);
+                }
+            }
+            None => {
+                let p = world(x2, y2);
+                bounds.add_point(&p);
+                path.line_to(&p);
+            }
+        }
+    }
+    if is_closed {
+        path.close();
+    }
+    path.d
+}
+
+fn polyline_path(poly: &Polyline, bounds: &mut Bounds) -> String {
+    match polyline_straight_paths(poly) {
+        Some(paths) => paths
+            .iter()
+            .map(|(points, is_closed)| points_path(points, *is_closed, bounds))
+            .collect(),
+        None => {
+            let vertices = poly
+                .vertices()
+                .map(|v| (v.location.x, v.location.y, v.bulge))
+                .collect::<Vec<_>>();
+            bulged_path(
+                &vertices,
+                poly.is_closed(),
+                poly.location.z,
+                &poly.normal,
+                bounds,
+            )
+        }
+    }
+}
+
+fn write_text(
+    out: &mut String,
+    placement: &TextPlacement,
+    fill: &str,
+    font: Option<&str>,
+    bounds: &mut Bounds,
+) {
+    let frame = match placement.frame() {
+        Some(frame) => frame,
+        None => return,
+    };
+    for corner in frame.corners() {
+        bounds.add_point(&corner);
+    }
+
+    let anchor = if frame.anchor_offset == 0.0 {
+        "start"
+    } else if frame.anchor_offset == 1.0 {
+        "end"
+    } else {
+        "middle"
+    };
+    let baseline = match frame.vertical {
+        VerticalTextJustification::Baseline => None,
+        VerticalTextJustification::Bottom => Some("text-after-edge"),
+        VerticalTextJustification::Middle => Some("central"),
+        VerticalTextJustification::Top => Some("text-before-edge"),
+    };
+    let (anchor_point, rotation, text_length) =
+        (&frame.anchor_point, frame.rotation, frame.text_length);
+    let decoded = &frame.text;
+
+    let mut transform = format!(
+        "translate({} {}) rotate({})",
+        num(anchor_point.x, DECIMAL_PLACES),
+        num(-anchor_point.y, DECIMAL_PLACES),
+        num(-rotation, DECIMAL_PLACES)
+    );
+    let scale_x = placement.width_factor * if placement.is_backwards { -1.0 } else { 1.0 };
+    let scale_y = if placement.is_upside_down { -1.0 } else { 1.0 };
+    if text_length.is_none() && (scale_x != 1.0 || scale_y != 1.0) {
+        transform.push_str(&format!(
+            " scale({} {})",
+            num(scale_x, DECIMAL_PLACES),
+            num(scale_y, DECIMAL_PLACES)
+        ));
+    }
+    if placement.oblique_angle != 0.0 {
+        transform.push_str(&format!(
+            " skewX({})",
+            num(-placement.oblique_angle, DECIMAL_PLACES)
+        ));
+    }
+
+    out.push_str(&format!(
+        "    <text transform=\"{}\" font-size=\"{}\" fill=\"{}\" stroke=\"none\"",
+        transform,
+        num(frame.height / CAP_HEIGHT_RATIO, DECIMAL_PLACES),
+        fill
+    ));
+    if let Some(font) = font {
+        out.push_str(&format!(" font-family=\"{}\"", escape(font)));
+    }
+    if anchor != "start" {
+        out.push_str(&format!(" text-anchor=\"{anchor}\""));
+    }
+    if let Some(baseline) = baseline {
+        out.push_str(&format!(" dominant-baseline=\"{baseline}\""));
+    }
+    if let Some(length) = text_length {
+        out.push_str(&format!(
+            " textLength=\"{}\" lengthAdjust=\"spacingAndGlyphs\"",
+            num(length, DECIMAL_PLACES)
+        ));
+    }
+    out.push('>');
+
+    // underlined and overlined spans are written as `<tspan>`s
+    let chars = decoded.text.chars().collect::<Vec<_>>();
+    let decoration = |i: usize| {
+        let underline = decoded.underline_spans.iter().any(|s| s.contains(&i));
+        let overline = decoded.overline_spans.iter().any(|s| s.contains(&i));
+        match (underline, overline) {
+            (true, true) => Some("underline overline"),
+            (true, false) => Some("underline"),
+            (false, true) => Some("overline"),
+            (false, false) => None,
+        }
+    };
+    let mut start = 0;
+    while start < chars.len() {
+        let current = decoration(start);
+        let end = (start..chars.len())
+            .find(|i| decoration(*i) != current)
+            .unwrap_or(chars.len());
+        let segment = escape(&chars[start..end].iter().collect::<String>());
+        match current {
+            Some(decoration) => out.push_str(&format!(
+                "<tspan text-decoration=\"{decoration}\">{segment}</tspan>"
+            )),
+            None => out.push_str(&segment),
+        }
+        start = end;
+    }
+    out.push_str("</text>\n");
+}
+
+fn hex_color((r, g, b): (u8, u8, u8)) -> String {
+    format!("#{r:02x}{g:02x}{b:02x}")
+}
+
+fn escape(value: &str) -> String {
+    let mut result = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '&' => result.push_str("&amp;"),
+            '<' => result.push_str("&lt;"),
+            '>' => result.push_str("&gt;"),
+            '"' => result.push_str("&quot;"),
+            '\'' => result.push_str("&apos;"),
+            _ => result.push(c),
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::objects::*;
+    use crate::svg_writer::*;
+    use crate::tables::*;
+    use crate::*;
+
+    fn to_svg(drawing: &Drawing) -> String {
+        to_svg_with_options(drawing, &SvgOptions::default())
+    }
+
+    fn to_svg_with_options(drawing: &Drawing, options: &SvgOptions) -> String {
+        let mut buf = vec![];
+        drawing.save_svg(&mut buf, options).unwrap();
+        String::from_utf8(buf).unwrap()
+    }
+
+    fn assert_contains(svg: &str, expected: &str) {
+        assert!(svg.contains(expected), "expected '{expected}' in:\n{svg}");
+    }
+
+    #[test]
+    fn layers_are_groups() {
+        let mut drawing = Drawing::new();
+        drawing.add_layer(Layer {
+            name: String::from("walls"),
+            color: Color::from_index(1),
+            ..Default::default()
+        });
+        drawing.add_layer(Layer {
+            name: String::from("hidden"),
+            is_layer_on: false,
+            ..Default::default()
+        });
+        let mut line = Entity::new(EntityType::Line(Line::new(
+            Point::new(0.0, 0.0, 0.0),
+            Point::new(10.0, 5.0, 0.0),
+        )));
+        line.common.layer = String::from("walls");
+        drawing.add_entity(line);
+        let mut circle = Entity::new(EntityType::Circle(Circle::new(
+            Point::new(100.0, 100.0, 0.0),
+            1.0,
+        )));
+        circle.common.layer = String::from("hidden");
+        drawing.add_entity(circle);
+
+        let svg = to_svg(&drawing);
+        assert_contains(&svg, "<g id=\"layer-walls\" data-layer=\"walls\">");
+        assert_contains(
+            &svg,
+            "<line x1=\"0\" y1=\"0\" x2=\"10\" y2=\"-5\" stroke=\"#ff0000\"",
+        );
+        assert_contains(&svg, "data-layer=\"hidden\" display=\"none\">");
+        // hidden layers don't contribute to the extents
+        assert_contains(&svg, "viewBox=\"0 -5 10 5\"");
+    }
+
+    #[test]
+    fn arcs_and_bulges_are_svg_arcs() {
+        let mut drawing = Drawing::new();
+        drawing.add_entity(Entity::new(EntityType::Arc(Arc::new(
+            Point::new(0.0, 0.0, 0.0),
+            1.0,
+            0.0,
+            90.0,
+        ))));
+        drawing.add_entity(Entity::new(EntityType::LwPolyline(LwPolyline {
+            vertices: vec![
+                LwPolylineVertex {
+                    x: 0.0,
+                    y: 0.0,
+                    bulge: 1.0,
+                    ..Default::default()
+                },
+                LwPolylineVertex {
+                    x: 2.0,
+                    y: 0.0,
+                    ..Default::default()
+                },
+            ],
+            ..Default::default()
+        })));
+        drawing.add_entity(Entity::new(EntityType::Ellipse(Ellipse {
+            major_axis: Vector::new(0.0, 2.0, 0.0),
+            minor_axis_ratio: 0.5,
+            ..Default::default()
+        })));
+
+        let svg = to_svg(&drawing);
+        // counter-clockwise arcs are clockwise once the y axis is flipped
+        assert_contains(&svg, "<path d=\"M1 0A1 1 0 0 0 0 -1\"");
+        assert_contains(&svg, "<path d=\"M0 0A1 1 0 0 0 2 0\"");
+        // full ellipses are split in two
+        assert_contains(&svg, "<path d=\"M0 -2A2 1 -90 0 0 0 2A2 1 -90 0 0 0 -2Z\"");
+    }
+
+    #[test]
+    fn arcs_with_a_negative_normal_are_mirrored() {
+        let mut drawing = Drawing::new();
+        drawing.add_entity(Entity::new(EntityType::Arc(Arc {
+            normal: Vector::new(0.0, 0.0, -1.0),
+            ..Arc::new(Point::new(0.0, 0.0, 0.0), 1.0, 0.0, 90.0)
+        })));
+        let svg = to_svg(&drawing);
+        assert_contains(&svg, "<path d=\"M-1 0A1 1 0 0 1 0 -1\"");
+    }
+
+    #[test]
+    fn line_type_dash_arrays() {
+        let mut drawing = Drawing::new();
+        drawing.header.line_type_scale = 2.0;
+        drawing.add_line_type(LineType {
+            name: String::from("DASHDOT"),
+            dash_dot_space_lengths: vec![0.5, -0.25, 0.0, -0.25],
+            ..Default::default()
+        });
+        let mut line = Entity::new(EntityType::Line(Default::default()));
+        line.common.line_type_name = String::from("dashdot");
+        drawing.add_entity(line);
+
+        let svg = to_svg(&drawing);
+        assert_contains(&svg, "stroke-dasharray=\"1 0.5 0 0.5\"");
+
+        assert_eq!(vec![0.0, 0.25, 0.5, 0.0], dash_array(&[-0.25, 0.5]));
+        assert_eq!(vec![1.5, 0.5], dash_array(&[1.0, 0.5, -0.5]));
+        assert!(dash_array(&[1.0]).is_empty());
+        assert!(dash_array(&[]).is_empty());
+    }
+
+    #[test]
+    fn line_weights_use_drawing_units() {
+        let mut drawing = Drawing::new();
+        drawing.header.default_drawing_units = Units::Inches;
+        let mut line = Entity::new(EntityType::Line(Default::default()));
+        line.common.lineweight_enum_value = 127;
+        drawing.add_entity(line);
+        let svg = to_svg(&drawing);
+        assert_contains(&svg, "stroke-width=\"0.05\"");
+    }
+
+    #[test]
+    fn inserts_use_block_symbols() {
+        let mut drawing = Drawing::new();
+        let mut block_line = Entity::new(EntityType::Line(Line::new(
+            Point::new(1.0, 0.0, 0.0),
+            Point::new(2.0, 0.0, 0.0),
+        )));
+        block_line.common.color = Color::by_block();
+        drawing.add_block(Block {
+            name: String::from("door"),
+            base_point: Point::new(1.0, 0.0, 0.0),
+            entities: vec![block_line],
+            ..Default::default()
+        });
+        let mut insert = Entity::new(EntityType::Insert(Insert {
+            name: String::from("DOOR"),
+            location: Point::new(5.0, 5.0, 0.0),
+            rotation: 90.0,
+            ..Default::default()
+        }));
+        insert.common.color = Color::from_index(3);
+        drawing.add_entity(insert);
+
+        let svg = to_svg(&drawing);
+        assert_contains(
+            &svg,
+            "<symbol id=\"block-door\" data-block=\"door\" overflow=\"visible\">",
+        );
+        assert_contains(&svg, "stroke=\"currentColor\"");
+        assert_contains(
+            &svg,
+            "<use xlink:href=\"#block-door\" transform=\"matrix(0 -1 1 0 5 -4)\" color=\"#00ff00\"",
+        );
+        // the rotated block extends from (5, 5) to (5, 6)
+        assert_contains(&svg, "viewBox=\"5 -6 0 1\"");
+    }
+
+    #[test]
+    fn inserts_seen_from_below_are_mirrored() {
+        let mut drawing = Drawing::new();
+        drawing.add_block(Block {
+            name: String::from("b"),
+            entities: vec![Entity::new(EntityType::Line(Line::new(
+                Point::new(0.0, 0.0, 0.0),
+                Point::new(2.0, 0.0, 0.0),
+            )))],
+            ..Default::default()
+        });
+        drawing.add_entity(Entity::new(EntityType::Insert(Insert {
+            name: String::from("b"),
+            location: Point::new(-5.0, 5.0, 0.0),
+            extrusion_direction: Vector::new(0.0, 0.0, -1.0),
+            ..Default::default()
+        })));
+
+        let svg = to_svg(&drawing);
+        assert_contains(
+            &svg,
+            "<use xlink:href=\"#block-b\" transform=\"matrix(-1 0 0 1 5 -5)\"",
+        );
+        // the mirrored block extends back from (5, 5) to (3, 5)
+        assert_contains(&svg, "viewBox=\"3 -5 2 0\"");
+    }
+
+    #[test]
+    fn text_is_written_as_text_elements() {
+        let mut drawing = Drawing::new();
+        drawing.add_entity(Entity::new(EntityType::Text(Text {
+            location: Point::new(1.0, 2.0, 0.0),
+            text_height: 0.7,
+            value: String::from("%%c10 %%u<a&b>"),
+            ..Default::default()
+        })));
+        drawing.add_entity(Entity::new(EntityType::Text(Text {
+            second_alignment_point: Point::new(3.0, 4.0, 0.0),
+            horizontal_text_justification: HorizontalTextJustification::Center,
+            vertical_text_justification: VerticalTextJustification::Top,
+            value: String::from("centered"),
+            ..Default::default()
+        })));
+        drawing.add_entity(Entity::new(EntityType::MText(MText {
+            text: String::from("first\\Psecond"),
+            ..Default::default()
+        })));
+
+        let svg = to_svg(&drawing);
+        assert_contains(
+            &svg,
+            "<text transform=\"translate(1 -2) rotate(0)\" font-size=\"1\" fill=\"#000000\" stroke=\"none\">\u{2300}10 <tspan text-decoration=\"underline\">&lt;a&amp;b&gt;</tspan></text>",
+        );
+        assert_contains(&svg, "transform=\"translate(3 -4) rotate(0)\"");
+        assert_contains(
+            &svg,
+            "text-anchor=\"middle\" dominant-baseline=\"text-before-edge\">centered</text>",
+        );
+        assert_contains(&svg, ">first</text>");
+        assert_contains(&svg, ">second</text>");
+    }
+
+    #[test]
+    fn render_layouts() {
+        let mut drawing = Drawing::new();
+        drawing.add_entity(Entity::new(EntityType::Line(Line::new(
+            Point::new(0.0, 0.0, 0.0),
+            Point::new(1.0, 1.0, 0.0),
+        ))));
+        let mut paper = Entity::new(EntityType::Circle(Circle::new(
+            Point::new(0.0, 0.0, 0.0),
+            1.0,
+        )));
+        paper.common.is_in_paper_space = true;
+        drawing.add_entity(paper);
+
+        let model = to_svg(&drawing);
+        assert_contains(&model, "<line ");
+        assert!(!model.contains("<circle "));
+
+        drawing.add_object(Object::new(ObjectType::Layout(Layout {
+            layout_name: String::from("Sheet"),
+            ..Default::default()
+        })));
+        let options = SvgOptions {
+            layout_name: Some(String::from("sheet")),
+            ..Default::default()
+        };
+        let sheet = to_svg_with_options(&drawing, &options);
+        assert_contains(&sheet, "<circle ");
+        assert!(!sheet.contains("<line "));
+
+        let options = SvgOptions {
+            layout_name: Some(String::from("missing")),
+            ..Default::default()
+        };
+        let mut buf = vec![];
+        match drawing.save_svg(&mut buf, &options) {
+            Err(DxfError::LayoutNotFound(name)) => assert_eq!("missing", name),
+            _ => panic!("expected an error"),
+        }
+    }
+}
@@ -0,0 +1,335 @@
+// decodes and encodes the `%%` control codes and unicode escapes used by `Text` values
+
+use std::ops::Range;
+
+use encoding_rs::Encoding;
+
+/// The text of a `Text`, `Attribute` or `AttributeDefinition` value with its control codes decoded.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub struct DecodedText {
+    /// The text with all control codes and escape sequences replaced by unicode characters.
+    pub text: String,
+    /// The character ranges of `text` that are underlined (`%%u`).
+    pub underline_spans: Vec<Range<usize>>,
+    /// The character ranges of `text` that are overlined (`%%o`).
+    pub overline_spans: Vec<Range<usize>>,
+}
+
+impl DecodedText {
+    /// Creates a value with no underlined or overlined text.
+    pub fn new(text: &str) -> Self {
+        DecodedText {
+            text: String::from(text),
+            ..Default::default()
+        }
+    }
+    /// Decodes the `%%d`, `%%c`, `%%p`, `%%%` and `%%nnn` control codes, the `%%u` and `%%o`
+    /// toggles, and the `\U+XXXX` and `\M+nXXXX` escape sequences.  Unrecognized codes are kept
+    /// verbatim.
+    pub fn decode(value: &str) -> Self {
+        let chars = value.chars().collect::<Vec<_>>();
+        let mut result = DecodedText::default();
+        let mut underline_start = None;
+        let mut overline_start = None;
+        let mut length = 0;
+        let mut i = 0;
+        while i < chars.len() {
+            let (decoded, consumed) = match &chars[i..] {
+                ['%', '%', c, ..] => match c.to_ascii_lowercase() {
+                    'd' => (Some('\u{00B0}'), 3),
+                    'c' => (Some('\u{2300}'), 3),
+                    'p' => (Some('\u{00B1}'), 3),
+                    '%' => (Some('%'), 3),
+                    'u' => {
+                        toggle_span(&mut underline_start, &mut result.underline_spans, length);
+                        (None, 3)
+                    }
+                    'o' => {
+                        toggle_span(&mut overline_start, &mut result.overline_spans, length);
+                        (None, 3)
+                    }
+                    _ => match decode_character_number(&chars[i + 2..]) {
+                        Some(c) => (Some(c), 5),
+                        None => (Some('%'), 1),
+                    },
+                },
+                ['\\', 'U', '+', ..] | ['\\', 'M', '+', ..] => {
+                    match decode_unicode_escape(&chars[i..]) {
+                        Some((c, consumed)) => (Some(c), consumed),
+                        None => (Some('\\'), 1),
+                    }
+                }
+                [c, ..] => (Some(*c), 1),
+                [] => unreachable!(),
+            };
+            if let Some(c) = decoded {
+                result.text.push(c);
+                length += 1;
+            }
+            i += consumed;
+        }
+
+        // spans that are never toggled off run to the end of the text
+        toggle_span(&mut underline_start, &mut result.underline_spans, length);
+        toggle_span(&mut overline_start, &mut result.overline_spans, length);
+        result
+    }
+    /// Encodes the text as a `Text` value, writing `%%d`, `%%c` and `%%p` for the degree,
+    /// diameter and plus/minus symbols and `%%u` and `%%o` around the underlined and overlined
+    /// spans.  Other non-ASCII characters are kept as-is and escaped when the drawing is written.
+    pub fn encode(&self) -> String {
+        let mut toggles = vec![];
+        for span in self.underline_spans.iter().filter(|s| !s.is_empty()) {
+            toggles.push((span.start, "%%u"));
+            toggles.push((span.end, "%%u"));
+        }
+        for span in self.overline_spans.iter().filter(|s| !s.is_empty()) {
+            toggles.push((span.start, "%%o"));
+            toggles.push((span.end, "%%o"));
+        }
+        toggles.sort_by_key(|(index, _)| *index);
+
+        let chars = self.text.chars().collect::<Vec<_>>();
+        let mut pieces = vec![];
+        let mut toggles = toggles.into_iter().peekable();
+        for (i, c) in chars.iter().enumerate() {
+            while let Some((_, code)) = toggles.next_if(|(index, _)| *index <= i) {
+                pieces.push(String::from(code));
+            }
+            pieces.push(match c {
+                '\u{00B0}' => String::from("%%d"),
+                '\u{2300}' => String::from("%%c"),
+                '\u{00B1}' => String::from("%%p"),
+                _ => c.to_string(),
+            });
+        }
+
+        // spans that end at the end of the text don't need to be toggled off
+        for (index, code) in toggles {
+            if index < chars.len() {
+                pieces.push(String::from(code));
+            }
+        }
+
+        let mut result = String::new();
+        for (i, piece) in pieces.iter().enumerate() {
+            // a literal '%' next to another '%' could be read as part of a control code
+            let is_next_to_percent = (i > 0 && pieces[i - 1].ends_with('%'))
+                || pieces.get(i + 1).is_some_and(|p| p.starts_with('%'));
+            if piece == "%" && is_next_to_percent {
+                result.push_str("%%%");
+            } else {
+                result.push_str(piece);
+            }
+        }
+        result
+    }
+}
+
+fn toggle_span(start: &mut Option<usize>, spans: &mut Vec<Range<usize>>, index: usize) {
+    match start.take() {
+        Some(start) if start < index => spans.push(start..index),
+        Some(_) => (),
+        None => *start = Some(index),
+    }
+}
+
+/// Decodes the three digit character number of a `%%nnn` code.
+fn decode_character_number(chars: &[char]) -> Option<char> {
+    let digits = chars.get(..3)?;
+    if !digits.iter().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    let code = digits.iter().collect::<String>().parse::<u32>().ok()?;
+    match u8::try_from(code) {
+        Ok(byte) => encoding_rs::WINDOWS_1252
+            .decode_without_bom_handling(&[byte])
+            .0
+            .chars()
+            .next(),
+        Err(_) => char::from_u32(code),
+    }
+}
+
+/// Decodes a `\U+XXXX` or `\M+nXXXX` escape sequence at the start of `chars`, returning the
+/// character and the number of characters consumed.
+pub(crate) fn decode_unicode_escape(chars: &[char]) -> Option<(char, usize)> {
+    let hex = |range: Range<usize>| -> Option<u32> {
+        let digits = chars.get(range)?;
+        if !digits.iter().all(|c| c.is_ascii_hexdigit()) {
+            return None;
+        }
+        u32::from_str_radix(&digits.iter().collect::<String>(), 16).ok()
+    };
+    match chars {
+        ['\\', 'U', '+', ..] => Some((char::from_u32(hex(3..7)?)?, 7)),
+        ['\\', 'M', '+', n, ..] => {
+            let encoding = multi_byte_encoding(*n)?;
+            let code = hex(4..8)?;
+            let bytes = [(code >> 8) as u8, (code & 0xFF) as u8];
+            let (decoded, had_errors) = encoding.decode_without_bom_handling(&bytes);
+            let mut decoded = decoded.chars();
+            match (decoded.next(), decoded.next(), had_errors) {
+                (Some(c), None, false) => Some((c, 8)),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+/// Returns the double-byte encoding used by a `\M+n` escape sequence.  Johab (`n` = 4) isn't
+/// supported.
+fn multi_byte_encoding(n: char) -> Option<&'static Encoding> {
+    match n {
+        '1' => Some(encoding_rs::SHIFT_JIS),
+        '2' => Some(encoding_rs::BIG5),
+        '3' => Some(encoding_rs::EUC_KR),
+        '5' => Some(encoding_rs::GBK),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::entities::*;
+    use crate::enums::*;
+    use crate::*;
+
+    #[test]
+    fn decode_control_codes() {
+        assert_eq!("\u{2300}25", DecodedText::decode("%%c25").text);
+        assert_eq!(
+            "90\u{00B0} \u{00B1}0.1",
+            DecodedText::decode("90%%D %%p0.1").text
+        );
+        assert_eq!("100%", DecodedText::decode("100%%%").text);
+        assert_eq!("5%", DecodedText::decode("5%").text);
+        assert_eq!("\u{00E9}", DecodedText::decode("%%233").text);
+        assert_eq!("%%x", DecodedText::decode("%%x").text);
+    }
+
+    #[test]
+    fn decode_unicode_escapes() {
+        assert_eq!("Rep\u{00E8}re", DecodedText::decode("Rep\\U+00E8re").text);
+        assert_eq!("\u{3042}", DecodedText::decode("\\M+182A0").text);
+        assert_eq!("\u{4F60}", DecodedText::decode("\\M+5C4E3").text);
+        assert_eq!("\\U+zz", DecodedText::decode("\\U+zz").text);
+        assert_eq!("\\M+482A0", DecodedText::decode("\\M+482A0").text);
+    }
+
+    #[test]
+    #[allow(clippy::single_range_in_vec_init)] // the spans are character ranges
+    fn encode_percent_next_to_control_codes() {
+        for (code, decoded) in [
+            (
+                "%%u",
+                DecodedText {
+                    text: String::from("5%x"),
+                    underline_spans: vec![2..3],
+                    ..Default::default()
+                },
+            ),
+            (
+                "%%o",
+                DecodedText {
+                    text: String::from("5%x"),
+                    overline_spans: vec![2..3],
+                    ..Default::default()
+                },
+            ),
+            ("%%d", DecodedText::new("5%\u{00B0}")),
+            ("%%c", DecodedText::new("5%\u{2300}")),
+            ("%%p", DecodedText::new("5%\u{00B1}")),
+        ] {
+            let encoded = decoded.encode();
+            assert!(encoded.starts_with(&format!("5%%%{code}")), "{encoded}");
+            assert_eq!(decoded, DecodedText::decode(&encoded));
+        }
+
+        // and after codes
+        for text in ["\u{00B0}%", "\u{00B0}%%", "%\u{00B1}%"] {
+            let decoded = DecodedText::new(text);
+            assert_eq!(decoded, DecodedText::decode(&decoded.encode()));
+        }
+        let underlined = DecodedText {
+            text: String::from("ab%"),
+            underline_spans: vec![0..2],
+            ..Default::default()
+        };
+        assert_eq!(underlined, DecodedText::decode(&underlined.encode()));
+    }
+
+    #[test]
+    fn decode_underline_and_overline_spans() {
+        let decoded = DecodedText::decode("a%%ubc%%ud%%oef");
+        assert_eq!("abcdef", decoded.text);
+        assert_eq!(vec![1..3], decoded.underline_spans);
+        assert_eq!(vec![4..6], decoded.overline_spans);
+    }
+
+    #[test]
+    fn encode_round_trip() {
+        for value in [
+            "%%c25",
+            "90%%d %%p0.1",
+            "a%%ubc%%ud%%oef",
+            "%%u%%ounder and over",
+            "100%%%%%%",
+            "5%",
+        ] {
+            assert_eq!(value, DecodedText::decode(value).encode());
+        }
+        assert_eq!("%%d\u{00E9}", DecodedText::new("\u{00B0}\u{00E9}").encode());
+    }
+
+    #[test]
+    fn text_entity_values() {
+        let mut text = Text {
+            value: String::from("%%uM8%%u %%c10"),
+            ..Default::default()
+        };
+        let decoded = text.decoded_value();
+        assert_eq!("M8 \u{2300}10", decoded.text);
+        assert_eq!(vec![0..2], decoded.underline_spans);
+
+        text.set_decoded_value(&DecodedText::new("\u{00B1}1"));
+        assert_eq!("%%p1", text.value);
+
+        let attribute = Attribute {
+            value: String::from("%%d"),
+            ..Default::default()
+        };
+        assert_eq!("\u{00B0}", attribute.decoded_value().text);
+        let definition = AttributeDefinition {
+            value: String::from("%%p"),
+            ..Default::default()
+        };
+        assert_eq!("\u{00B1}", definition.decoded_value().text);
+    }
+
+    #[test]
+    fn decode_values_from_any_version() {
+        // older files escape non-ASCII characters which are un-escaped when read; newer files
+        // may still contain the escapes in the value
+        for version in [AcadVersion::R2000, AcadVersion::R2007] {
+            let mut drawing = Drawing::new();
+            drawing.header.version = version;
+            drawing.add_entity(Entity::new(EntityType::Text(Text {
+                value: String::from("%%c\u{00E8}\\U+00E9"),
+                ..Default::default()
+            })));
+            let mut buf = vec![];
+            drawing.save(&mut buf).unwrap();
+            let drawing = Drawing::load(&mut buf.as_slice()).unwrap();
+            let entities = drawing.entities().collect::<Vec<_>>();
+            match entities[0].specific {
+                EntityType::Text(ref text) => {
+                    assert_eq!("\u{2300}\u{00E8}\u{00E9}", text.decoded_value().text)
+                }
+                _ => panic!("expected text"),
+            }
+        }
+    }
+}
@@ -110,7 +110,7 @@ impl XDataItem {
                         Some(Err(e)) => return Err(e),
                         None => return Err(DxfError::UnexpectedEndOfInput),
                     };
-                    if pair.code == XDATA_CONTROLGROUP && pair.assert_string()? == "}" {
+                    if pair.code == XDATA_CONTROLGROUP && pair.as_str()? == "}" {
                         // end of group
                         break;
                     }
@@ -201,7 +201,7 @@ impl XDataItem {
                 pairs.push(CodePair::new_string(XDATA_BINARYDATA, &line));
             }
             XDataItem::Handle(h) => {
-                pairs.push(CodePair::new_string(XDATA_HANDLE, &h.as_string()));
+                pairs.push(CodePair::new_string(XDATA_HANDLE, h.as_string()));
             }
             XDataItem::ThreeReals(x, y, z) => {
                 pairs.push(CodePair::new_f64(XDATA_THREEREALS, *x));